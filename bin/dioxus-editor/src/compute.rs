@@ -9,7 +9,7 @@ use crate::surface::Surface;
 use zosimos::buffer::Descriptor;
 use zosimos::command::{CommandBuffer, Linker, Register};
 use zosimos::pool::{GpuKey, Pool, PoolBridge, PoolKey, SwapChain};
-use zosimos::run::StepLimits;
+use zosimos::run::{StepLimits, StepOutcome};
 
 /// A compute graph.
 pub struct Compute {
@@ -204,15 +204,9 @@ impl Compute {
         Box::new(async move {
             let mut exec = exec;
 
-            while exec.is_running() {
-                let Ok(mut point) = exec.step_to(StepLimits::new().with_steps(32)) else {
-                    break;
-                };
-
-                let Ok(()) = point.block_on() else {
-                    break;
-                };
-            }
+            while let Ok(StepOutcome::Running) =
+                exec.step_async(StepLimits::new().with_steps(32)).await
+            {}
 
             let mut retire = exec.retire_gracefully(&mut pool);
             for (&reg, _) in &bindings {
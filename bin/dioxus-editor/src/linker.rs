@@ -9,5 +9,5 @@ pub async fn from_assets() -> Result<Linker, Box<dyn std::error::Error>> {
     let bytes = response.bytes().await?;
 
     let (core, std) = serde_cbor::from_slice(&bytes)?;
-    Ok(Linker { core, std })
+    Ok(Linker::new(core, std))
 }
@@ -375,17 +375,14 @@ impl Surface {
         // Ensure our cache does not grow infinitely.
         self.pool.clear_cache();
 
-        // FIXME: No. Async. Luckily this is straightforward.
         while running.is_running() {
             let limits = StepLimits::new().with_steps(usize::MAX);
-            let mut step = running
-                .step_to(limits)
-                .expect("Valid binding to start our executable");
             tracing::info!("Step started");
 
-            step.finish(|_| ())
+            running
+                .step_async(limits)
                 .await
-                .expect("Valid binding to block on our execution");
+                .expect("Valid binding to step our execution");
             tracing::info!("Step done");
         }
 
@@ -503,7 +500,7 @@ impl From<CompileError> for NormalizingError {
     fn from(err: CompileError) -> Self {
         let location = core::panic::Location::caller();
         NormalizingError {
-            fail: format!("At {:?}: {:?}", location, err),
+            fail: format!("At {}: {}", location, err),
         }
     }
 }
@@ -513,7 +510,7 @@ impl From<command::CommandError> for NormalizingError {
     fn from(err: command::CommandError) -> Self {
         let location = core::panic::Location::caller();
         NormalizingError {
-            fail: format!("At {:?}: {:?}", location, err),
+            fail: format!("At {}: {}", location, err),
         }
     }
 }
@@ -523,7 +520,7 @@ impl From<LaunchError> for NormalizingError {
     fn from(err: LaunchError) -> Self {
         let location = core::panic::Location::caller();
         NormalizingError {
-            fail: format!("At {:?}: {:?}", location, err),
+            fail: format!("At {}: {}", location, err),
         }
     }
 }
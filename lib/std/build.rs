@@ -29,6 +29,24 @@ fn main() -> Result<(), BuildError> {
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/gaussian.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/histogram.frag",
+            kind: ShaderKind::Fragment,
+            entry: "histogram_rows",
+            name_overwrite: Some("histogram_rows"),
+        },
+        SimpleSource {
+            path: "src/histogram.frag",
+            kind: ShaderKind::Fragment,
+            entry: "histogram_sum",
+            name_overwrite: Some("histogram_sum"),
+        },
         SimpleSource {
             path: "src/inject.frag",
             kind: ShaderKind::Fragment,
@@ -77,36 +95,144 @@ fn main() -> Result<(), BuildError> {
             entry: "encode_r32ui",
             name_overwrite: Some("stage_e32ui"),
         },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "decode_rgba16ui",
+            name_overwrite: Some("stage_d16x4ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "encode_rgba16ui",
+            name_overwrite: Some("stage_e16x4ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "decode_rgba32ui",
+            name_overwrite: Some("stage_d32x4ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "encode_rgba32ui",
+            name_overwrite: Some("stage_e32x4ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "decode_rgb10a2ui",
+            name_overwrite: Some("stage_d10x3a2ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "encode_rgb10a2ui",
+            name_overwrite: Some("stage_e10x3a2ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "decode_rgb565ui",
+            name_overwrite: Some("stage_d565ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "encode_rgb565ui",
+            name_overwrite: Some("stage_e565ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "decode_rgba4ui",
+            name_overwrite: Some("stage_d4x4ui"),
+        },
+        SimpleSource {
+            path: "src/stage.frag",
+            kind: ShaderKind::Fragment,
+            entry: "encode_rgba4ui",
+            name_overwrite: Some("stage_e4x4ui"),
+        },
         SimpleSource {
             path: "src/fill.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/clahe.frag",
+            kind: ShaderKind::Fragment,
+            entry: "clahe_histogram",
+            name_overwrite: Some("clahe_histogram"),
+        },
+        SimpleSource {
+            path: "src/clahe.frag",
+            kind: ShaderKind::Fragment,
+            entry: "clahe_mapping",
+            name_overwrite: Some("clahe_mapping"),
+        },
+        SimpleSource {
+            path: "src/clahe.frag",
+            kind: ShaderKind::Fragment,
+            entry: "clahe_apply",
+            name_overwrite: Some("clahe_apply"),
+        },
         SimpleSource {
             path: "src/distribution_normal2d.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/downsample.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/exposure_fusion.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/fractal_noise.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/pad.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/palette.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/ab_transform.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/bilinear.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/lens_undistort.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/oklab.frag",
             kind: ShaderKind::Fragment,
@@ -131,24 +257,234 @@ fn main() -> Result<(), BuildError> {
             entry: "srlab2_decode",
             name_overwrite: Some("srlab2_decode"),
         },
+        SimpleSource {
+            path: "src/cielab.frag",
+            kind: ShaderKind::Fragment,
+            entry: "cielab_encode",
+            name_overwrite: Some("cielab_encode"),
+        },
+        SimpleSource {
+            path: "src/cielab.frag",
+            kind: ShaderKind::Fragment,
+            entry: "cielab_decode",
+            name_overwrite: Some("cielab_decode"),
+        },
+        SimpleSource {
+            path: "src/cmyk.frag",
+            kind: ShaderKind::Fragment,
+            entry: "cmyk_encode",
+            name_overwrite: Some("cmyk_encode"),
+        },
+        SimpleSource {
+            path: "src/cmyk.frag",
+            kind: ShaderKind::Fragment,
+            entry: "cmyk_decode",
+            name_overwrite: Some("cmyk_decode"),
+        },
+        SimpleSource {
+            path: "src/bradford_nonlinear.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/bright_pass.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/bloom.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/demosaic.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/denoise_nlm.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/sharpen.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/emboss.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/gamut_map.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/gradient_polar.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/box3.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/local_statistics.frag",
+            kind: ShaderKind::Fragment,
+            entry: "ssim",
+            name_overwrite: Some("ssim"),
+        },
         SimpleSource {
             path: "src/mandelbrot.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/perspective.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/prefix_sum.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/pyramid.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/reduce.frag",
+            kind: ShaderKind::Fragment,
+            entry: "reduce_mean",
+            name_overwrite: Some("reduce_mean"),
+        },
+        SimpleSource {
+            path: "src/reduce.frag",
+            kind: ShaderKind::Fragment,
+            entry: "reduce_min",
+            name_overwrite: Some("reduce_min"),
+        },
+        SimpleSource {
+            path: "src/reduce.frag",
+            kind: ShaderKind::Fragment,
+            entry: "reduce_max",
+            name_overwrite: Some("reduce_max"),
+        },
+        SimpleSource {
+            path: "src/resample.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/simplex_noise.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/shadow.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/solid_rgb.frag",
             kind: ShaderKind::Fragment,
             entry: "main",
             name_overwrite: None,
         },
+        SimpleSource {
+            path: "src/spline_ramp.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/squared_difference.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/test_pattern.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/tile.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/tonemap.frag",
+            kind: ShaderKind::Fragment,
+            entry: "tonemap_reinhard",
+            name_overwrite: Some("tonemap_reinhard"),
+        },
+        SimpleSource {
+            path: "src/tonemap.frag",
+            kind: ShaderKind::Fragment,
+            entry: "tonemap_aces",
+            name_overwrite: Some("tonemap_aces"),
+        },
+        SimpleSource {
+            path: "src/worley.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/voronoi.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/white_balance.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/chromatic_aberration.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/chroma_key.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
+        SimpleSource {
+            path: "src/color_ramp.frag",
+            kind: ShaderKind::Fragment,
+            entry: "main",
+            name_overwrite: None,
+        },
         SimpleSource {
             path: "src/crt.frag",
             kind: ShaderKind::Fragment,
@@ -0,0 +1,74 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform AbTransform {
+    // Transforms the linear input color to XYZ.
+    mat3x3 to_xyz;
+    // Transforms XYZ back to the linear input color.
+    mat3x3 from_xyz;
+    // xy: the chroma mapped to the origin, zw: the origin is then mapped here.
+    vec4 mobius;
+    // xy: cosine and sine of the rotation.
+    // z: non-zero if the input is already Oklab and is not converted.
+    vec4 rotation;
+} u_transform;
+
+// The canonical Oklab matrices, but GLSL constructs matrices column-wise.
+const mat3x3 M1 = mat3x3(
+        +0.8189330101, +0.0329845436, +0.0482003018,
+        +0.3618667424, +0.9293118715, +0.2643662691,
+        -0.1288597137, +0.0361456387, +0.6338517070
+    );
+
+const mat3x3 M2 = mat3x3(
+        +0.2104542553, +1.9779984951, +0.0259040371,
+        +0.7936177850, -2.4285922050, +0.7827717662,
+        -0.0040720468, +0.4505937099, -0.8086757660
+    );
+
+vec2 complex_mul(vec2 a, vec2 b) {
+    return vec2(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+vec2 complex_div(vec2 a, vec2 b) {
+    return vec2(a.x * b.x + a.y * b.y, a.y * b.x - a.x * b.y) / dot(b, b);
+}
+
+vec2 conjugate(vec2 a) {
+    return vec2(a.x, -a.y);
+}
+
+// The disk automorphism (z - a) / (1 - adj(a)·z).
+vec2 mobius(vec2 z, vec2 a) {
+    return complex_div(z - a, vec2(1.0, 0.0) - complex_mul(conjugate(a), z));
+}
+
+void main() {
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+    const bool is_oklab = u_transform.rotation.z != 0.0;
+
+    vec3 lab;
+    if (is_oklab) {
+        lab = rgba.xyz;
+    } else {
+        const vec3 lms = M1 * (u_transform.to_xyz * rgba.rgb);
+        lab = M2 * (pow(abs(lms), vec3(1.0 / 3.0)) * sign(lms));
+    }
+
+    vec2 ab = mobius(lab.yz, u_transform.mobius.xy);
+    ab = mobius(ab, -u_transform.mobius.zw);
+    ab = complex_mul(ab, u_transform.rotation.xy);
+    lab = vec3(lab.x, ab);
+
+    if (is_oklab) {
+        f_color = vec4(lab, rgba.a);
+    } else {
+        const vec3 lms_star = inverse(M2) * lab;
+        const vec3 xyz = inverse(M1) * (lms_star * lms_star * lms_star);
+        f_color = vec4(u_transform.from_xyz * xyz, rgba.a);
+    }
+}
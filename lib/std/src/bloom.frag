@@ -0,0 +1,25 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform Bloom {
+    // x: the brightness above which texels glow, unused here.
+    // y: the intensity of the glow.
+    vec4 params;
+    // x: the normalization of the glow, summed over all levels.
+    vec4 glow;
+} u_bloom;
+
+// Add the glow onto the image.
+void main() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const vec4 image = texelFetch(sampler2D(lhs, texture_sampler), pos, 0);
+    const vec3 glow = texelFetch(sampler2D(rhs, texture_sampler), pos, 0).rgb;
+
+    const float strength = u_bloom.params.y * u_bloom.glow.x;
+    f_color = vec4(image.rgb + strength * glow, image.a);
+}
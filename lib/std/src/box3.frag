@@ -7,22 +7,58 @@ layout (set = 1, binding = 1) uniform texture2D in_texture;
 
 layout (set = 2, binding = 0) uniform Box3 {
     mat3x3 weights;
+    // x: the border handling.
+    ivec4 config;
 } box_params;
 
+const int BORDER_CLAMP = 0;
+const int BORDER_MIRROR = 1;
+const int BORDER_WRAP = 2;
+const int BORDER_ZERO = 3;
+
 float weighted_sum(mat3x3 w, mat3x3 c) {
     return dot(matrixCompMult(c, w) * vec3(1.0), vec3(1.0));
 }
 
+// Fetch a tap, remapping coordinates outside the texture according to the border handling.
+vec4 tap(ivec2 pos, ivec2 offset) {
+    const ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    ivec2 coord = pos + offset;
+
+    if (any(lessThan(coord, ivec2(0))) || any(greaterThanEqual(coord, sz))) {
+        switch (box_params.config.x) {
+        case BORDER_CLAMP:
+            coord = clamp(coord, ivec2(0), sz - 1);
+            break;
+        case BORDER_MIRROR:
+            // Reflect about the edge texel, which is not itself repeated.
+            coord = abs(coord);
+            coord = mix(coord, 2 * (sz - 1) - coord, greaterThanEqual(coord, sz));
+            coord = clamp(coord, ivec2(0), sz - 1);
+            break;
+        case BORDER_WRAP:
+            coord = ((coord % sz) + sz) % sz;
+            break;
+        case BORDER_ZERO:
+            return vec4(0.0);
+        }
+    }
+
+    return texelFetch(sampler2D(in_texture, texture_sampler), coord, 0);
+}
+
 void main() {
-    vec4 p00 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(-1, -1));
-    vec4 p01 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(-1, 0));
-    vec4 p02 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(-1, 1));
-    vec4 p10 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(0, -1));
-    vec4 p11 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(0, 0));
-    vec4 p12 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(0, 1));
-    vec4 p20 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(1, -1));
-    vec4 p21 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(1, 0));
-    vec4 p22 = textureOffset(sampler2D(in_texture, texture_sampler), uv, ivec2(1, 1));
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+
+    vec4 p00 = tap(pos, ivec2(-1, -1));
+    vec4 p01 = tap(pos, ivec2(-1, 0));
+    vec4 p02 = tap(pos, ivec2(-1, 1));
+    vec4 p10 = tap(pos, ivec2(0, -1));
+    vec4 p11 = tap(pos, ivec2(0, 0));
+    vec4 p12 = tap(pos, ivec2(0, 1));
+    vec4 p20 = tap(pos, ivec2(1, -1));
+    vec4 p21 = tap(pos, ivec2(1, 0));
+    vec4 p22 = tap(pos, ivec2(1, 1));
 
     // Remember: column-major initializer.
     mat3x3 c0 = mat3x3(
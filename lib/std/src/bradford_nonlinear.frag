@@ -0,0 +1,39 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0, std140) uniform Bradford {
+    // From the linear source color to XYZ.
+    mat3 to_xyz;
+    // The sharpened cone responses of Bradford.
+    mat3 to_lms;
+    // From the cone responses to the linear target color, through XYZ.
+    mat3 from_lms;
+    // xy: the gains of the long and medium cones, z: the gain and w: the exponent of the short
+    // cones with their non-linearity.
+    vec4 adapt;
+} u_bradford;
+
+// The original adaptation of Bradford, with Lam's exponent on the blue response.
+void main() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const vec4 rgba = texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+
+    const vec3 xyz = u_bradford.to_xyz * rgba.rgb;
+    const float y = xyz.y;
+
+    // The responses are those of the color normalized to unit luminance.
+    if (y <= 0.0) {
+        f_color = vec4(vec3(0.0), rgba.a);
+        return;
+    }
+
+    const vec3 lms = u_bradford.to_lms * (xyz / y);
+    const float short_cone = sign(lms.z) * pow(abs(lms.z), u_bradford.adapt.w);
+    const vec3 adapted = vec3(u_bradford.adapt.xy * lms.xy, u_bradford.adapt.z * short_cone);
+
+    f_color = vec4(u_bradford.from_lms * (adapted * y), rgba.a);
+}
@@ -0,0 +1,25 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+// Shared with `bloom.frag`, such that both can be controlled by the same parameters.
+layout (set = 2, binding = 0) uniform Bloom {
+    // x: the brightness above which texels glow.
+    // y: the intensity of the glow, unused here.
+    vec4 params;
+} u_bloom;
+
+// Keep only the part of each texel above the threshold, preserving its hue.
+void main() {
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+    const vec3 color = max(rgba.rgb, vec3(0.0));
+
+    const float brightness = max(color.r, max(color.g, color.b));
+    const float excess = max(brightness - u_bloom.params.x, 0.0);
+    const float scale = brightness > 0.0 ? excess / brightness : 0.0;
+
+    f_color = vec4(color * scale, 1.0);
+}
@@ -0,0 +1,85 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform ChromaKey {
+    // xyz: the key color, in the linear input color.
+    // w: the chroma distance in Oklab up to which texels are fully transparent.
+    vec4 key;
+    // x: the width of the ramp of the matte beyond the tolerance.
+    // y: the strength of removing the key chroma from the remaining texels.
+    // z: non-zero if the input is already Oklab and is not converted.
+    // w: non-zero if the matte is written to all channels instead of the alpha.
+    vec4 params;
+    // Transforms the linear input color to XYZ.
+    mat3x3 to_xyz;
+    // Transforms XYZ back to the linear input color.
+    mat3x3 from_xyz;
+} u_key;
+
+// The canonical Oklab matrices, but GLSL constructs matrices column-wise.
+const mat3x3 M1 = mat3x3(
+        +0.8189330101, +0.0329845436, +0.0482003018,
+        +0.3618667424, +0.9293118715, +0.2643662691,
+        -0.1288597137, +0.0361456387, +0.6338517070
+    );
+
+const mat3x3 M2 = mat3x3(
+        +0.2104542553, +1.9779984951, +0.0259040371,
+        +0.7936177850, -2.4285922050, +0.7827717662,
+        -0.0040720468, +0.4505937099, -0.8086757660
+    );
+
+vec3 oklab(vec3 color) {
+    if (u_key.params.z != 0.0) {
+        return color;
+    }
+
+    const vec3 lms = M1 * (u_key.to_xyz * color);
+    return M2 * (pow(abs(lms), vec3(1.0 / 3.0)) * sign(lms));
+}
+
+vec3 linear(vec3 lab) {
+    if (u_key.params.z != 0.0) {
+        return lab;
+    }
+
+    const vec3 lms_star = inverse(M2) * lab;
+    const vec3 xyz = inverse(M1) * (lms_star * lms_star * lms_star);
+    return u_key.from_xyz * xyz;
+}
+
+void main() {
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+
+    const vec3 lab = oklab(rgba.rgb);
+    const vec2 key = oklab(u_key.key.rgb).yz;
+
+    const float tolerance = u_key.key.w;
+    const float softness = u_key.params.x;
+
+    // The matte is anti-aliased by a ramp over the softness, a hard step without it.
+    const float distance = length(lab.yz - key);
+    const float alpha = softness > 0.0
+        ? smoothstep(tolerance, tolerance + softness, distance)
+        : step(tolerance, distance);
+
+    if (u_key.params.w != 0.0) {
+        f_color = vec4(alpha);
+        return;
+    }
+
+    // Remove the component of the chroma towards the key, which is most visible at the edges.
+    vec2 ab = lab.yz;
+    const float key_chroma = length(key);
+    if (key_chroma > 0.0) {
+        const vec2 direction = key / key_chroma;
+        const float spill = max(dot(ab, direction), 0.0);
+        ab -= u_key.params.y * spill * direction;
+    }
+
+    f_color = vec4(linear(vec3(lab.x, ab)), rgba.a * alpha);
+}
@@ -0,0 +1,27 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Aberration {
+    // rgb: the radial scale at which each channel is sampled.
+    vec4 scales;
+    // xy: the center of the scaling, relative to the image size.
+    vec4 center;
+} u_aberration;
+
+vec4 sample_scaled(float scale) {
+    const vec2 center = u_aberration.center.xy;
+    return texture(sampler2D(in_texture, texture_sampler), center + (uv - center) * scale);
+}
+
+void main() {
+    const vec4 red = sample_scaled(u_aberration.scales.r);
+    const vec4 green = sample_scaled(u_aberration.scales.g);
+    const vec4 blue = sample_scaled(u_aberration.scales.b);
+
+    // The green channel is the reference, its alpha belongs to the least displaced sample.
+    f_color = vec4(red.r, green.g, blue.b, green.a);
+}
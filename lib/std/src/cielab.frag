@@ -0,0 +1,87 @@
+#version 440
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform FragmentColor {
+    vec4 whitepoint_xyz;
+    mat3x3 xyz_transform;
+} u_fragmentColor;
+
+// The CIE constants, with the cube root of the linear segment's limit.
+const float LAB_DELTA = 6.0 / 29.0;
+
+vec3 cielab_non_linearity(vec3);
+vec3 cielab_non_linearity_inv(vec3);
+
+#ifndef CIELAB_ENCODE_AS_MAIN
+#define CIELAB_ENCODE_AS_MAIN cielab_encode
+#endif
+#ifndef CIELAB_DECODE_AS_MAIN
+#define CIELAB_DECODE_AS_MAIN cielab_decode
+#endif
+
+// The lightness and chroma coordinates are stored divided by 100, such that L lies in [0, 1].
+void CIELAB_ENCODE_AS_MAIN() {
+    // Assuming the input is some linear rgb space, the transform adapts it to the whitepoint.
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+    const vec3 xyz = u_fragmentColor.xyz_transform * rgba.rgb;
+
+    const vec3 f = cielab_non_linearity(xyz / u_fragmentColor.whitepoint_xyz.xyz);
+
+    f_color = vec4(
+        vec3(
+            1.16 * f.y - 0.16,
+            5.0 * (f.x - f.y),
+            2.0 * (f.y - f.z)
+        ),
+        rgba.a
+    );
+}
+
+void CIELAB_DECODE_AS_MAIN() {
+    const vec4 lab_a = texture(sampler2D(in_texture, texture_sampler), uv);
+    const vec3 Lab = lab_a.xyz;
+
+    const float fy = (Lab.x + 0.16) / 1.16;
+    const vec3 f = vec3(fy + Lab.y / 5.0, fy, fy - Lab.z / 2.0);
+    const vec3 xyz = cielab_non_linearity_inv(f) * u_fragmentColor.whitepoint_xyz.xyz;
+
+    // Write this as our 'linear color' (preserve alpha).
+    const vec3 rgb = u_fragmentColor.xyz_transform * xyz;
+    f_color = vec4(rgb, lab_a.a);
+}
+
+float cielab_non_linearity_component(float t) {
+    if (t > LAB_DELTA * LAB_DELTA * LAB_DELTA) {
+        return pow(t, 1.0 / 3.0);
+    } else {
+        return t / (3.0 * LAB_DELTA * LAB_DELTA) + 4.0 / 29.0;
+    }
+}
+
+vec3 cielab_non_linearity(vec3 t) {
+    return vec3(
+        cielab_non_linearity_component(t.x),
+        cielab_non_linearity_component(t.y),
+        cielab_non_linearity_component(t.z)
+    );
+}
+
+float cielab_non_linearity_inv_component(float f) {
+    if (f > LAB_DELTA) {
+        return f * f * f;
+    } else {
+        return 3.0 * LAB_DELTA * LAB_DELTA * (f - 4.0 / 29.0);
+    }
+}
+
+vec3 cielab_non_linearity_inv(vec3 f) {
+    return vec3(
+        cielab_non_linearity_inv_component(f.x),
+        cielab_non_linearity_inv_component(f.y),
+        cielab_non_linearity_inv_component(f.z)
+    );
+}
@@ -0,0 +1,158 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+// The source image, or the tile histograms for the computation of mappings.
+layout (set = 1, binding = 1) uniform texture2D lhs;
+// The mappings of all tiles, when the equalization is applied.
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform Clahe {
+    // The weights of the channels whose combination is equalized.
+    vec4 weights;
+    // x: tiles horizontally, y: tiles vertically, z: the number of bins.
+    ivec4 config;
+    // x: the clip limit, relative to the average count of a bin.
+    vec4 params;
+} u_clahe;
+
+#ifndef CLAHE_HISTOGRAM_AS_MAIN
+#define CLAHE_HISTOGRAM_AS_MAIN clahe_histogram
+#endif
+#ifndef CLAHE_MAPPING_AS_MAIN
+#define CLAHE_MAPPING_AS_MAIN clahe_mapping
+#endif
+#ifndef CLAHE_APPLY_AS_MAIN
+#define CLAHE_APPLY_AS_MAIN clahe_apply
+#endif
+
+// Counts are stored as the four little-endian bytes of an unsigned integer, as
+// in the histogram shader.
+vec4 encode_count(uint count) {
+    const uvec4 bytes = (uvec4(count) >> uvec4(0, 8, 16, 24)) & uvec4(0xff);
+    return vec4(bytes) / 255.0;
+}
+
+uint decode_count(vec4 texel) {
+    const uvec4 bytes = uvec4(round(clamp(texel, 0.0, 1.0) * 255.0));
+    return bytes.x | (bytes.y << 8) | (bytes.z << 16) | (bytes.w << 24);
+}
+
+// Tiles partition the image as evenly as possible. Where the tile count does
+// not divide the size, tiles differ in size by one texel.
+int tile_start(int index, int tiles, int size) {
+    return index * size / tiles;
+}
+
+float tile_center(int index, int tiles, int size) {
+    return 0.5 * float(tile_start(index, tiles, size) + tile_start(index + 1, tiles, size));
+}
+
+int bin_of(vec4 texel) {
+    const int bins = u_clahe.config.z;
+    const float value = clamp(dot(texel, u_clahe.weights), 0.0, 1.0);
+    return min(int(value * float(bins)), bins - 1);
+}
+
+// Find the tiles whose centers enclose a coordinate, and the interpolation
+// weight between them. Beyond the outermost centers the mapping is constant.
+void enclosing_tiles(int pos, int tiles, int size, out int lower, out int upper, out float t) {
+    int tile = min(pos * tiles / size, tiles - 1);
+    while (tile + 1 < tiles && tile_start(tile + 1, tiles, size) <= pos) {
+        tile++;
+    }
+    while (tile > 0 && tile_start(tile, tiles, size) > pos) {
+        tile--;
+    }
+
+    const float coord = float(pos) + 0.5;
+    if (coord < tile_center(tile, tiles, size)) {
+        lower = max(tile - 1, 0);
+        upper = tile;
+    } else {
+        lower = tile;
+        upper = min(tile + 1, tiles - 1);
+    }
+
+    const float lo = tile_center(lower, tiles, size);
+    const float hi = tile_center(upper, tiles, size);
+    t = lower == upper ? 0.0 : clamp((coord - lo) / (hi - lo), 0.0, 1.0);
+}
+
+// Count, for the bin and tile of this fragment, the texels of the tile whose
+// value falls into the bin.
+void CLAHE_HISTOGRAM_AS_MAIN() {
+    const ivec2 sz = textureSize(sampler2D(lhs, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const ivec2 tiles = u_clahe.config.xy;
+
+    const ivec2 tile = ivec2(pos.y % tiles.x, pos.y / tiles.x);
+    const ivec2 lo = ivec2(tile_start(tile.x, tiles.x, sz.x), tile_start(tile.y, tiles.y, sz.y));
+    const ivec2 hi = ivec2(tile_start(tile.x + 1, tiles.x, sz.x), tile_start(tile.y + 1, tiles.y, sz.y));
+
+    uint count = 0;
+    for (int y = lo.y; y < hi.y; y++) {
+        for (int x = lo.x; x < hi.x; x++) {
+            const vec4 texel = texelFetch(sampler2D(lhs, texture_sampler), ivec2(x, y), 0);
+            count += uint(bin_of(texel) == pos.x);
+        }
+    }
+
+    f_color = encode_count(count);
+}
+
+// Clip the histogram of a tile, redistribute the excess evenly over all bins,
+// and evaluate the normalized cumulative distribution at this fragment's bin.
+void CLAHE_MAPPING_AS_MAIN() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const int bins = u_clahe.config.z;
+
+    float total = 0.0;
+    for (int i = 0; i < bins; i++) {
+        total += float(decode_count(texelFetch(sampler2D(lhs, texture_sampler), ivec2(i, pos.y), 0)));
+    }
+
+    const float limit = max(1.0, u_clahe.params.x * total / float(bins));
+
+    float excess = 0.0;
+    float cdf = 0.0;
+    for (int i = 0; i < bins; i++) {
+        const float count = float(decode_count(texelFetch(sampler2D(lhs, texture_sampler), ivec2(i, pos.y), 0)));
+        excess += max(count - limit, 0.0);
+        if (i <= pos.x) {
+            cdf += min(count, limit);
+        }
+    }
+
+    cdf += excess * float(pos.x + 1) / float(bins);
+    f_color = vec4(cdf / max(total, 1.0));
+}
+
+// Equalize each texel with the mapping of the surrounding tiles, interpolated
+// bilinearly between their centers.
+void CLAHE_APPLY_AS_MAIN() {
+    const ivec2 sz = textureSize(sampler2D(lhs, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const ivec2 tiles = u_clahe.config.xy;
+
+    const vec4 rgba = texelFetch(sampler2D(lhs, texture_sampler), pos, 0);
+    const int bin = bin_of(rgba);
+
+    int x0, x1, y0, y1;
+    float tx, ty;
+    enclosing_tiles(pos.x, tiles.x, sz.x, x0, x1, tx);
+    enclosing_tiles(pos.y, tiles.y, sz.y, y0, y1, ty);
+
+    const float m00 = texelFetch(sampler2D(rhs, texture_sampler), ivec2(bin, y0 * tiles.x + x0), 0).r;
+    const float m10 = texelFetch(sampler2D(rhs, texture_sampler), ivec2(bin, y0 * tiles.x + x1), 0).r;
+    const float m01 = texelFetch(sampler2D(rhs, texture_sampler), ivec2(bin, y1 * tiles.x + x0), 0).r;
+    const float m11 = texelFetch(sampler2D(rhs, texture_sampler), ivec2(bin, y1 * tiles.x + x1), 0).r;
+    const float mapped = mix(mix(m00, m10, tx), mix(m01, m11, tx), ty);
+
+    // Scale the color, preserving its chromaticity.
+    const float value = dot(rgba, u_clahe.weights);
+    const vec3 rgb = value > 1e-6 ? rgba.rgb * (mapped / value) : vec3(mapped);
+
+    f_color = vec4(rgb, rgba.a);
+}
@@ -0,0 +1,38 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Cmyk {
+    // x: the fraction of the common gray component replaced by black, in [0, 1].
+    vec4 black;
+} u_cmyk;
+
+#ifndef CMYK_ENCODE_AS_MAIN
+#define CMYK_ENCODE_AS_MAIN cmyk_encode
+#endif
+#ifndef CMYK_DECODE_AS_MAIN
+#define CMYK_DECODE_AS_MAIN cmyk_decode
+#endif
+
+// The naive model, the inks subtract their complement from the electrical RGB values.
+void CMYK_ENCODE_AS_MAIN() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const vec3 rgb = clamp(texelFetch(sampler2D(in_texture, texture_sampler), pos, 0).rgb, 0.0, 1.0);
+
+    const float k = u_cmyk.black.x * (1.0 - max(max(rgb.r, rgb.g), rgb.b));
+    // All black, where no ink remains for the colors.
+    const float white = 1.0 - k;
+    const vec3 cmy = white > 0.0 ? (white - rgb) / white : vec3(0.0);
+
+    f_color = vec4(clamp(cmy, 0.0, 1.0), k);
+}
+
+void CMYK_DECODE_AS_MAIN() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const vec4 cmyk = texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+
+    f_color = vec4((1.0 - cmyk.rgb) * (1.0 - cmyk.a), 1.0);
+}
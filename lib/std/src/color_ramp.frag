@@ -0,0 +1,67 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+// Keep in sync with `shaders::color_ramp::MAX_STOPS`.
+#define MAX_STOPS 16
+
+layout (set = 1, binding = 0) uniform ColorRamp {
+    // The colors of all stops, in the space of interpolation.
+    vec4 colors[MAX_STOPS];
+    // The positions of all stops, packed by four to avoid the std140 array stride.
+    vec4 positions[MAX_STOPS / 4];
+    // Transforms XYZ to the linear output color, used when interpolating in Oklab.
+    mat3x3 xyz_transform;
+    // x: the number of stops.
+    // y: non-zero if the ramp runs along the height instead of the width.
+    // z: non-zero if the colors are Oklab and must be decoded.
+    ivec4 config;
+} u_ramp;
+
+// The canonical Oklab matrices, but GLSL constructs matrices column-wise.
+const mat3x3 M1 = mat3x3(
+        +0.8189330101, +0.0329845436, +0.0482003018,
+        +0.3618667424, +0.9293118715, +0.2643662691,
+        -0.1288597137, +0.0361456387, +0.6338517070
+    );
+
+const mat3x3 M2 = mat3x3(
+        +0.2104542553, +1.9779984951, +0.0259040371,
+        +0.7936177850, -2.4285922050, +0.7827717662,
+        -0.0040720468, +0.4505937099, -0.8086757660
+    );
+
+float stop_position(int i) {
+    return u_ramp.positions[i / 4][i % 4];
+}
+
+void main() {
+    const float t = u_ramp.config.y != 0 ? uv.y : uv.x;
+    const int count = u_ramp.config.x;
+
+    vec4 color = u_ramp.colors[0];
+    for (int i = 1; i < count; i++) {
+        const float lower = stop_position(i - 1);
+        const float upper = stop_position(i);
+
+        if (t >= upper) {
+            color = u_ramp.colors[i];
+        } else {
+            if (t > lower) {
+                const float w = (t - lower) / max(upper - lower, 1e-6);
+                color = mix(u_ramp.colors[i - 1], u_ramp.colors[i], w);
+            }
+
+            break;
+        }
+    }
+
+    if (u_ramp.config.z != 0) {
+        const vec3 lms_star = inverse(M2) * color.xyz;
+        const vec3 lms = lms_star * lms_star * lms_star;
+        const vec3 xyz = inverse(M1) * lms;
+        color = vec4(u_ramp.xyz_transform * xyz, color.a);
+    }
+
+    f_color = color;
+}
@@ -0,0 +1,70 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Cfa {
+    // xy: the position of the red site in the 2×2 pattern, zw: the origin in the mosaic.
+    ivec4 pattern;
+    // x: 0 for the bilinear interpolation, 1 for the Malvar-He-Cutler kernels.
+    ivec4 algorithm;
+} u_cfa;
+
+// Reflect at the border, this preserves the parity and thus the color site of each texel.
+float fetch(ivec2 pos, ivec2 size) {
+    const ivec2 last = size - 1;
+    pos = abs(pos);
+    pos = last - abs(last - pos);
+    return texelFetch(sampler2D(in_texture, texture_sampler), pos, 0).r;
+}
+
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const ivec2 site = (pos + u_cfa.pattern.zw) & 1;
+    const ivec2 red = u_cfa.pattern.xy;
+
+    const float c = fetch(pos, size);
+    const float h1 = fetch(pos + ivec2(-1, 0), size) + fetch(pos + ivec2(1, 0), size);
+    const float v1 = fetch(pos + ivec2(0, -1), size) + fetch(pos + ivec2(0, 1), size);
+    const float diag = fetch(pos + ivec2(-1, -1), size) + fetch(pos + ivec2(1, -1), size)
+        + fetch(pos + ivec2(-1, 1), size) + fetch(pos + ivec2(1, 1), size);
+
+    // At a red or blue site: green, and the opposite color of the diagonals.
+    float green;
+    float opposite;
+    // At a green site: the color of the horizontal neighbors, then of the vertical neighbors.
+    float across;
+    float along;
+
+    if (u_cfa.algorithm.x == 0) {
+        green = (h1 + v1) / 4.0;
+        opposite = diag / 4.0;
+        across = h1 / 2.0;
+        along = v1 / 2.0;
+    } else {
+        const float h2 = fetch(pos + ivec2(-2, 0), size) + fetch(pos + ivec2(2, 0), size);
+        const float v2 = fetch(pos + ivec2(0, -2), size) + fetch(pos + ivec2(0, 2), size);
+
+        green = (4.0 * c + 2.0 * (h1 + v1) - (h2 + v2)) / 8.0;
+        opposite = (6.0 * c + 2.0 * diag - 1.5 * (h2 + v2)) / 8.0;
+        across = (5.0 * c + 4.0 * h1 - diag - h2 + 0.5 * v2) / 8.0;
+        along = (5.0 * c + 4.0 * v1 - diag - v2 + 0.5 * h2) / 8.0;
+    }
+
+    vec3 rgb;
+    if (site == red) {
+        rgb = vec3(c, green, opposite);
+    } else if (site == (red ^ 1)) {
+        rgb = vec3(opposite, green, c);
+    } else if (site.y == red.y) {
+        // A green site in a row of red sites.
+        rgb = vec3(across, c, along);
+    } else {
+        rgb = vec3(along, c, across);
+    }
+
+    f_color = vec4(clamp(rgb, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,65 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Nlm {
+    // x: the filter strength h, first such that it can be controlled on its own.
+    vec4 params;
+    // The weights of the channels whose combination is the luma of a texel.
+    vec4 weights;
+    // x: the radius of the search window, y: the radius of the patches.
+    ivec4 radii;
+} u_nlm;
+
+vec4 fetch(ivec2 pos, ivec2 size) {
+    return texelFetch(sampler2D(in_texture, texture_sampler), clamp(pos, ivec2(0), size - 1), 0);
+}
+
+float luma(ivec2 pos, ivec2 size) {
+    return dot(fetch(pos, size), u_nlm.weights);
+}
+
+// The mean squared luma difference of the patches around two texels.
+float patch_distance(ivec2 a, ivec2 b, ivec2 size) {
+    const int radius = u_nlm.radii.y;
+    float sum = 0.0;
+
+    for (int dy = -radius; dy <= radius; dy++) {
+        for (int dx = -radius; dx <= radius; dx++) {
+            const ivec2 d = ivec2(dx, dy);
+            const float diff = luma(a + d, size) - luma(b + d, size);
+            sum += diff * diff;
+        }
+    }
+
+    const float side = float(2 * radius + 1);
+    return sum / (side * side);
+}
+
+// Average the search window, weighting each texel by the similarity of its patch.
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const int radius = u_nlm.radii.x;
+    const float h = u_nlm.params.x;
+    const float inv_h2 = 1.0 / max(h * h, 1e-12);
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+
+    for (int dy = -radius; dy <= radius; dy++) {
+        for (int dx = -radius; dx <= radius; dx++) {
+            const ivec2 other = pos + ivec2(dx, dy);
+            const float weight = exp(-patch_distance(pos, other, size) * inv_h2);
+
+            sum += weight * fetch(other, size);
+            total += weight;
+        }
+    }
+
+    // The texel itself has weight one, the total is never zero.
+    f_color = sum / total;
+}
@@ -0,0 +1,44 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Downsample {
+    // xy: the factor of this pass, zw: the original texels covered by a source texel.
+    uvec4 config;
+    // xy: the size of the original image.
+    uvec4 size;
+} u_downsample;
+
+// The number of original texels covered by a source texel, along one axis.
+float coverage(uint pos, uint scale, uint size) {
+    return float(min((pos + 1u) * scale, size) - pos * scale);
+}
+
+// Average each block of source texels by the original texels they cover, blocks
+// at the right and bottom border of the image may be partial.
+void main() {
+    const ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 factor = ivec2(u_downsample.config.xy);
+    const ivec2 base = factor * ivec2(gl_FragCoord.xy);
+
+    vec4 sum = vec4(0.0);
+    float weight = 0.0;
+
+    for (int dy = 0; dy < factor.y; dy++) {
+        for (int dx = 0; dx < factor.x; dx++) {
+            const ivec2 pos = base + ivec2(dx, dy);
+            if (pos.x < sz.x && pos.y < sz.y) {
+                const float w =
+                    coverage(uint(pos.x), u_downsample.config.z, u_downsample.size.x)
+                    * coverage(uint(pos.y), u_downsample.config.w, u_downsample.size.y);
+                sum += w * texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+                weight += w;
+            }
+        }
+    }
+
+    f_color = sum / max(weight, 1.0);
+}
@@ -0,0 +1,44 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Emboss {
+    // x: the direction of the light in radians, first such that it can be controlled on its own.
+    // y: the depth of the relief.
+    vec4 params;
+    // The weights of the channels whose combination is the luma of a texel.
+    vec4 weights;
+    // x: whether the relief is added onto the source color instead of mid-gray.
+    ivec4 config;
+} u_emboss;
+
+vec4 fetch(ivec2 pos, ivec2 size) {
+    return texelFetch(sampler2D(in_texture, texture_sampler), clamp(pos, ivec2(0), size - 1), 0);
+}
+
+// The directional derivative of luma, with the 3×3 kernel built from the light direction.
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const float angle = u_emboss.params.x;
+    const vec2 light = vec2(cos(angle), sin(angle));
+
+    float relief = 0.0;
+    for (int dy = -1; dy <= 1; dy++) {
+        for (int dx = -1; dx <= 1; dx++) {
+            const vec2 offset = vec2(dx, dy);
+            relief += dot(offset, light) * dot(fetch(pos + ivec2(dx, dy), size), u_emboss.weights);
+        }
+    }
+
+    // The sum of squared weights is 6 for every direction, this normalizes to the slope.
+    relief *= u_emboss.params.y / 6.0;
+
+    const vec4 center = fetch(pos, size);
+    const vec3 base = u_emboss.config.x != 0 ? center.rgb : vec3(0.5);
+
+    f_color = vec4(base + vec3(relief), center.a);
+}
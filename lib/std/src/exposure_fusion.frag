@@ -0,0 +1,68 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+// The accumulated fusion so far, alpha holds the mean weight.
+layout (set = 1, binding = 1) uniform texture2D lhs;
+// The next exposure to fuse.
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform FusionStep {
+    // x: contrast exponent, y: saturation exponent, z: well-exposedness
+    // exponent, w: sigma of well-exposedness.
+    vec4 weights;
+    // x: the number of exposures after this step.
+    // y: non-zero if this is the last step and alpha should be restored.
+    vec4 step;
+} u_fusion;
+
+float luma(vec3 rgb) {
+    return dot(rgb, vec3(0.2126, 0.7152, 0.0722));
+}
+
+// Weights are judged on perceptual, not linear, values.
+vec3 encode(vec3 rgb) {
+    return pow(clamp(rgb, 0.0, 1.0), vec3(1.0 / 2.2));
+}
+
+float weight(vec2 at) {
+    const vec3 rgb = encode(texture(sampler2D(rhs, texture_sampler), at).rgb);
+
+    const float laplace = 4.0 * luma(rgb)
+        - luma(encode(textureOffset(sampler2D(rhs, texture_sampler), at, ivec2(-1, 0)).rgb))
+        - luma(encode(textureOffset(sampler2D(rhs, texture_sampler), at, ivec2(1, 0)).rgb))
+        - luma(encode(textureOffset(sampler2D(rhs, texture_sampler), at, ivec2(0, -1)).rgb))
+        - luma(encode(textureOffset(sampler2D(rhs, texture_sampler), at, ivec2(0, 1)).rgb));
+    const float contrast = abs(laplace);
+
+    const float mean = dot(rgb, vec3(1.0 / 3.0));
+    const vec3 deviation = rgb - vec3(mean);
+    const float saturation = sqrt(dot(deviation, deviation) / 3.0);
+
+    const float sigma = u_fusion.weights.w;
+    const vec3 exposed = exp(-(rgb - 0.5) * (rgb - 0.5) / (2.0 * sigma * sigma));
+    const float exposedness = exposed.r * exposed.g * exposed.b;
+
+    const float w = pow(contrast, u_fusion.weights.x)
+        * pow(saturation, u_fusion.weights.y)
+        * pow(exposedness, u_fusion.weights.z);
+
+    // Avoid a zero total weight where all exposures are equally bad.
+    return clamp(w, 0.0, 1.0) + 1e-6;
+}
+
+void main() {
+    const vec4 acc = texture(sampler2D(lhs, texture_sampler), uv);
+    const vec4 next = texture(sampler2D(rhs, texture_sampler), uv);
+
+    const float count = u_fusion.step.x;
+    const float previous = acc.a * (count - 1.0);
+    const float w = weight(uv);
+    const float total = previous + w;
+
+    const vec3 rgb = (acc.rgb * previous + next.rgb * w) / total;
+    const float alpha = u_fusion.step.y != 0.0 ? next.a : total / count;
+
+    f_color = vec4(rgb, alpha);
+}
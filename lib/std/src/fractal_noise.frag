@@ -13,6 +13,12 @@ layout (set = 1, binding = 0, std430) uniform FractalNoise {
     float damping;
     // The number of iterations to add
     uint num_octaves;
+    // The factor of frequencies between iterations
+    float lacunarity;
+    // Displacement of coordinates by another evaluation, in cells of the first iteration
+    float warp;
+    // Selects one of many uncorrelated noise patterns
+    uint seed;
 } u_fragmentParams;
 
 // From https://jcgt.org/published/0009/03/02/paper.pdf
@@ -27,7 +33,7 @@ uvec4 pcg4d(uvec4 v)
 
 vec4 hash(uvec2 v) {
     const float MAX = float(0xFFFFFFFFu);
-    uvec4 hashed = pcg4d(uvec4(v, 0, 0));
+    uvec4 hashed = pcg4d(uvec4(v, u_fragmentParams.seed, 0));
     return vec4(hashed) / MAX;
 }
 
@@ -49,11 +55,11 @@ vec4 noise(vec3 x) {
 
 vec3 next_point(vec3 x) {
     // Rotate to reduce axial bias
-    const mat3 rot = 2 *
+    const mat3 rot =
         mat3( cos(0.5), sin(0.5), 1,
                 -sin(0.5), cos(0.5), 1,
                 0, 0, 1);
-    return rot * x;
+    return u_fragmentParams.lacunarity * (rot * x);
 }
 
 vec4 fbm(vec2 x) {
@@ -72,5 +78,13 @@ vec4 fbm(vec2 x) {
 }
 
 void main() {
-    f_color = fbm(uv);
+    vec2 pt = uv;
+
+    if (u_fragmentParams.warp != 0.0) {
+        // Two uncorrelated channels of the same noise, centered, displace the coordinates.
+        vec2 offset = 2.0 * fbm(uv).zw - 1.0;
+        pt += u_fragmentParams.warp * offset / u_fragmentParams.initial_scale;
+    }
+
+    f_color = fbm(pt);
 }
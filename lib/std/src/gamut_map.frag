@@ -0,0 +1,98 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0, std140) uniform Gamut {
+    // From the linear destination color to XYZ, relative to D65.
+    mat3 to_xyz;
+    // From XYZ, relative to D65, to the linear destination color.
+    mat3 from_xyz;
+    // x: 0 to clip, 1 to compress the chroma onto the boundary, 2 to compress from a knee.
+    uvec4 method;
+} u_gamut;
+
+// The canonical Oklab matrices, constructed column-wise.
+const mat3 M1 = mat3(
+        +0.8189330101, +0.0329845436, +0.0482003018,
+        +0.3618667424, +0.9293118715, +0.2643662691,
+        -0.1288597137, +0.0361456387, +0.6338517070
+    );
+
+const mat3 M2 = mat3(
+        +0.2104542553, +1.9779984951, +0.0259040371,
+        +0.7936177850, -2.4285922050, +0.7827717662,
+        -0.0040720468, +0.4505937099, -0.8086757660
+    );
+
+// Chroma below this fraction of the boundary is kept by the soft clip.
+const float KNEE = 0.8;
+// Above the chroma of any color in the common RGB gamuts.
+const float MAX_CHROMA = 1.0;
+const int STEPS = 24;
+
+vec3 to_oklab(vec3 rgb) {
+    const vec3 lms = M1 * (u_gamut.to_xyz * rgb);
+    return M2 * (pow(abs(lms), vec3(1.0 / 3.0)) * sign(lms));
+}
+
+vec3 to_rgb(vec3 lab) {
+    const vec3 lms_star = inverse(M2) * lab;
+    return u_gamut.from_xyz * (inverse(M1) * (lms_star * lms_star * lms_star));
+}
+
+bool in_gamut(vec3 rgb) {
+    const float eps = 1e-5;
+    return all(greaterThanEqual(rgb, vec3(-eps))) && all(lessThanEqual(rgb, vec3(1.0 + eps)));
+}
+
+// The largest chroma of a lightness and hue that is inside the gamut. Along a hue the gamut of
+// an RGB space is a single interval of chroma starting at the gray axis.
+float boundary_chroma(float lightness, vec2 hue) {
+    float inside = 0.0;
+    float outside = MAX_CHROMA;
+
+    for (int i = 0; i < STEPS; i++) {
+        const float mid = 0.5 * (inside + outside);
+        if (in_gamut(to_rgb(vec3(lightness, mid * hue)))) {
+            inside = mid;
+        } else {
+            outside = mid;
+        }
+    }
+
+    return inside;
+}
+
+// Reduce the chroma in Oklch, preserving lightness and hue.
+void main() {
+    const vec4 rgba = texelFetch(sampler2D(in_texture, texture_sampler), ivec2(gl_FragCoord.xy), 0);
+    const uint method = u_gamut.method.x;
+
+    const vec3 lab = to_oklab(rgba.rgb);
+    const float lightness = clamp(lab.x, 0.0, 1.0);
+    const float chroma = length(lab.yz);
+
+    // Soft clipping also compresses colors inside the gamut, close to its boundary.
+    if (method == 0 || chroma <= 0.0 || (method == 1 && in_gamut(rgba.rgb))) {
+        f_color = vec4(clamp(rgba.rgb, 0.0, 1.0), rgba.a);
+        return;
+    }
+
+    const vec2 hue = lab.yz / chroma;
+    const float limit = boundary_chroma(lightness, hue);
+
+    float mapped;
+    if (method == 1) {
+        mapped = min(chroma, limit);
+    } else {
+        const float knee = KNEE * limit;
+        const float range = max(limit - knee, 1e-6);
+        mapped = chroma <= knee ? chroma : knee + range * tanh((chroma - knee) / range);
+    }
+
+    const vec3 rgb = to_rgb(vec3(lightness, mapped * hue));
+    f_color = vec4(clamp(rgb, 0.0, 1.0), rgba.a);
+}
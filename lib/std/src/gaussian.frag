@@ -0,0 +1,56 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Gaussian {
+    // x: the axis of the pass, 0 for the width and 1 for the height.
+    // y: the radius of the kernel, in texels, the pipeline is specialized with as `RADIUS`.
+    // z: the shift of the result along the axis, in texels.
+    // w: non-zero to clamp to the edge instead of sampling zero outside.
+    ivec4 config;
+    // x: the standard deviation of the kernel, in texels.
+    vec4 params;
+} u_gaussian;
+
+// The radius of the kernel, in texels. A constant bound such that the loop can be unrolled.
+layout (constant_id = 0) const int RADIUS = 0;
+
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const int axis = u_gaussian.config.x;
+    const float sigma = u_gaussian.params.x;
+
+    const ivec2 step = axis == 0 ? ivec2(1, 0) : ivec2(0, 1);
+    const ivec2 center = ivec2(gl_FragCoord.xy) - step * u_gaussian.config.z;
+
+    vec4 sum = vec4(0.0);
+    float total = 0.0;
+
+    // Walk outwards from the center, visiting the taps on both sides at each distance. The bound
+    // is not negated since that is an operation on the constant, not all consumers support it.
+    for (int i = 0; i <= RADIUS; i++) {
+        const float weight = sigma > 0.0 ? exp(-float(i * i) / (2.0 * sigma * sigma)) : 1.0;
+
+        for (int side = -1; side <= 1; side += 2) {
+            if (i == 0 && side > 0) {
+                break;
+            }
+
+            ivec2 pos = center + side * i * step;
+            total += weight;
+
+            if (u_gaussian.config.w != 0) {
+                pos = clamp(pos, ivec2(0), size - 1);
+            } else if (any(lessThan(pos, ivec2(0))) || any(greaterThanEqual(pos, size))) {
+                continue;
+            }
+
+            sum += weight * texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+        }
+    }
+
+    f_color = sum / total;
+}
@@ -0,0 +1,45 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform GradientPolar {
+    // The derivative along the width, as in `box3.frag`. Its transpose is the one along the height.
+    mat3x3 kernel;
+    // The weights of the channels whose combination is the luma of a texel.
+    vec4 weights;
+} u_gradient;
+
+const float TAU = 6.28318530718;
+
+float luma(ivec2 pos, ivec2 size) {
+    const vec4 texel = texelFetch(sampler2D(in_texture, texture_sampler), clamp(pos, ivec2(0), size - 1), 0);
+    return dot(texel, u_gradient.weights);
+}
+
+float weighted_sum(mat3x3 w, mat3x3 c) {
+    return dot(matrixCompMult(c, w) * vec3(1.0), vec3(1.0));
+}
+
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+
+    // Remember: column-major initializer, with the same layout as `box3.frag`.
+    const mat3x3 c = mat3x3(
+        luma(pos + ivec2(-1, -1), size), luma(pos + ivec2(-1, 0), size), luma(pos + ivec2(-1, 1), size),
+        luma(pos + ivec2(0, -1), size), luma(pos + ivec2(0, 0), size), luma(pos + ivec2(0, 1), size),
+        luma(pos + ivec2(1, -1), size), luma(pos + ivec2(1, 0), size), luma(pos + ivec2(1, 1), size)
+    );
+
+    const float dx = weighted_sum(c, u_gradient.kernel);
+    const float dy = weighted_sum(c, transpose(u_gradient.kernel));
+
+    const float magnitude = length(vec2(dx, dy));
+    // The angle of a vanishing gradient is arbitrary, choose zero instead of relying on atan.
+    const float angle = magnitude > 0.0 ? fract(atan(dy, dx) / TAU) : 0.0;
+
+    f_color = vec4(vec3(magnitude), angle);
+}
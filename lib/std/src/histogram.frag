@@ -0,0 +1,64 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Histogram {
+    // The weights of the channels whose combination is binned.
+    vec4 weights;
+    // x: the number of bins.
+    ivec4 config;
+} u_histogram;
+
+#ifndef HISTOGRAM_ROWS_AS_MAIN
+#define HISTOGRAM_ROWS_AS_MAIN histogram_rows
+#endif
+#ifndef HISTOGRAM_SUM_AS_MAIN
+#define HISTOGRAM_SUM_AS_MAIN histogram_sum
+#endif
+
+// Counts are stored as the four little-endian bytes of an unsigned integer, one
+// per channel of a normalized texel. This representation is exact through all
+// our texture formats and is the byte layout of the final buffer.
+vec4 encode_count(uint count) {
+    const uvec4 bytes = (uvec4(count) >> uvec4(0, 8, 16, 24)) & uvec4(0xff);
+    return vec4(bytes) / 255.0;
+}
+
+uint decode_count(vec4 texel) {
+    const uvec4 bytes = uvec4(round(clamp(texel, 0.0, 1.0) * 255.0));
+    return bytes.x | (bytes.y << 8) | (bytes.z << 16) | (bytes.w << 24);
+}
+
+// Count, for the bin and row of this fragment, the texels in that source row
+// whose value falls into the bin.
+void HISTOGRAM_ROWS_AS_MAIN() {
+    const ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const int bins = u_histogram.config.x;
+
+    uint count = 0;
+    for (int x = 0; x < sz.x; x++) {
+        const vec4 texel = texelFetch(sampler2D(in_texture, texture_sampler), ivec2(x, pos.y), 0);
+        const float value = clamp(dot(texel, u_histogram.weights), 0.0, 1.0);
+        const int bin = min(int(value * float(bins)), bins - 1);
+        count += uint(bin == pos.x);
+    }
+
+    f_color = encode_count(count);
+}
+
+// Sum the per-row counts of the bin of this fragment.
+void HISTOGRAM_SUM_AS_MAIN() {
+    const ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const int bin = int(gl_FragCoord.x);
+
+    uint count = 0;
+    for (int y = 0; y < sz.y; y++) {
+        count += decode_count(texelFetch(sampler2D(in_texture, texture_sampler), ivec2(bin, y), 0));
+    }
+
+    f_color = encode_count(count);
+}
@@ -15,11 +15,44 @@ layout (set = 2, binding = 0) uniform FragmentPushConstants {
     // If we want to support more than one injected channel then we can use a
     // mat4x4 here in the future.
     vec4 color;
+    // The texels of lhs to which rhs is stretched: min x, min y, max x, max y.
+    vec4 placement;
+    // x: the width of rhs, y: its height, z: sampling (0 nearest, 1 bilinear).
+    vec4 params;
 } u_pushConstants;
 
+vec4 fetch_rhs(ivec2 pos) {
+    const ivec2 sz = ivec2(u_pushConstants.params.xy);
+    return texelFetch(sampler2D(rhs, texture_sampler), clamp(pos, ivec2(0), sz - 1), 0);
+}
+
 void main() {
     vec4 bg = texture(sampler2D(lhs, texture_sampler), uv).rgba;
-    vec4 fg = texture(sampler2D(rhs, texture_sampler), uv).rgba;
+
+    const vec2 pos = gl_FragCoord.xy;
+    const vec4 placement = u_pushConstants.placement;
+
+    if (any(lessThan(pos, placement.xy)) || any(greaterThanEqual(pos, placement.zw))) {
+        f_color = bg;
+        return;
+    }
+
+    const vec2 size = u_pushConstants.params.xy;
+    const vec2 source = (pos - placement.xy) / (placement.zw - placement.xy) * size;
+
+    vec4 fg;
+    if (u_pushConstants.params.z == 0.0) {
+        fg = fetch_rhs(ivec2(floor(source)));
+    } else {
+        const vec2 texel = source - 0.5;
+        const ivec2 base = ivec2(floor(texel));
+        const vec2 t = fract(texel);
+
+        const vec4 top = mix(fetch_rhs(base), fetch_rhs(base + ivec2(1, 0)), t.x);
+        const vec4 bot = mix(fetch_rhs(base + ivec2(0, 1)), fetch_rhs(base + ivec2(1, 1)), t.x);
+        fg = mix(top, bot, t.y);
+    }
+
     vec4 inject = vec4(dot(fg, u_pushConstants.color));
     f_color = mix(bg, inject, u_pushConstants.select);
 }
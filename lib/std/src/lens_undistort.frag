@@ -0,0 +1,53 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Distortion {
+    // xyz: the radial coefficients k1, k2, k3.
+    vec4 radial;
+    // xy: the tangential coefficients p1, p2.
+    vec4 tangential;
+    // xy: the focal length in texels, zw: the optical center in texels.
+    vec4 camera;
+    // The color of texels which map outside the source.
+    vec4 fill;
+} u_distortion;
+
+vec4 fetch(ivec2 pos, ivec2 size) {
+    return texelFetch(sampler2D(in_texture, texture_sampler), clamp(pos, ivec2(0), size - 1), 0);
+}
+
+// Map each undistorted output texel to its coordinate in the distorted source, Brown-Conrady.
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const vec2 focal = u_distortion.camera.xy;
+    const vec2 center = u_distortion.camera.zw;
+
+    const vec2 p = (gl_FragCoord.xy - center) / focal;
+    const float r2 = dot(p, p);
+    const vec3 k = u_distortion.radial.xyz;
+    const vec2 t = u_distortion.tangential.xy;
+
+    const float radial = 1.0 + r2 * (k.x + r2 * (k.y + r2 * k.z));
+    const vec2 tangential = vec2(
+        2.0 * t.x * p.x * p.y + t.y * (r2 + 2.0 * p.x * p.x),
+        t.x * (r2 + 2.0 * p.y * p.y) + 2.0 * t.y * p.x * p.y);
+
+    const vec2 pos = (p * radial + tangential) * focal + center;
+
+    if (any(lessThan(pos, vec2(0.0))) || any(greaterThanEqual(pos, vec2(size)))) {
+        f_color = u_distortion.fill;
+        return;
+    }
+
+    const vec2 texel = pos - 0.5;
+    const ivec2 base = ivec2(floor(texel));
+    const vec2 f = fract(texel);
+
+    const vec4 top = mix(fetch(base, size), fetch(base + ivec2(1, 0), size), f.x);
+    const vec4 bot = mix(fetch(base + ivec2(0, 1), size), fetch(base + ivec2(1, 1), size), f.x);
+    f_color = mix(top, bot, f.y);
+}
@@ -3,10 +3,7 @@ use zosimos::shaders::{ShadersCore, ShadersStd, stage::Shaders as ShadersStage};
 
 pub fn from_included() -> &'static Linker {
     static INSTANCE: std::sync::OnceLock<Linker> = std::sync::OnceLock::new();
-    INSTANCE.get_or_init(|| Linker {
-        core: included_shaders_core(),
-        std: included_shaders_std(),
-    })
+    INSTANCE.get_or_init(|| Linker::new(included_shaders_core(), included_shaders_std()))
 }
 
 /// A vertex box shader, rendering a sole quad with given vertex and uv coordinate system.
@@ -20,34 +17,153 @@ pub const FRAG_MIX_RGBA: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv
 pub const FRAG_LINEAR: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/linear.frag.v"));
 
 mod shader {
+    pub const AB_TRANSFORM: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/ab_transform.frag.v"));
+
     pub const BILINEAR: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/bilinear.frag.v"));
 
+    pub const BRADFORD_NONLINEAR: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/bradford_nonlinear.frag.v"));
+
+    pub const BRIGHT_PASS: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/bright_pass.frag.v"));
+
+    pub const BLOOM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/bloom.frag.v"));
+
+    pub const DEMOSAIC: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/demosaic.frag.v"));
+
+    pub const DENOISE_NLM: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/denoise_nlm.frag.v"));
+
+    pub const SHARPEN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/sharpen.frag.v"));
+
+    pub const EMBOSS: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/emboss.frag.v"));
+
+    pub const GAMUT_MAP: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/gamut_map.frag.v"));
+
+    pub const GRADIENT_POLAR: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/gradient_polar.frag.v"));
+
     pub const BOX: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/box3.frag.v"));
 
+    pub const CHROMATIC_ABERRATION: &[u8] = include_bytes!(concat!(
+        env!("OUT_DIR"),
+        "/spirv/chromatic_aberration.frag.v"
+    ));
+
+    pub const CHROMA_KEY: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/chroma_key.frag.v"));
+
+    pub const CIELAB_ENCODE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/cielab_encode.frag.v"));
+    pub const CIELAB_DECODE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/cielab_decode.frag.v"));
+
+    pub const CMYK_ENCODE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/cmyk_encode.frag.v"));
+    pub const CMYK_DECODE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/cmyk_decode.frag.v"));
+
+    pub const COLOR_RAMP: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/color_ramp.frag.v"));
+
+    pub const CLAHE_HISTOGRAM: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/clahe_histogram.frag.v"));
+    pub const CLAHE_MAPPING: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/clahe_mapping.frag.v"));
+    pub const CLAHE_APPLY: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/clahe_apply.frag.v"));
+
     pub const DISTRIBUTION_NORMAL_2D: &[u8] = include_bytes!(concat!(
         env!("OUT_DIR"),
         "/spirv/distribution_normal2d.frag.v"
     ));
 
+    pub const DOWNSAMPLE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/downsample.frag.v"));
+
+    pub const EXPOSURE_FUSION: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/exposure_fusion.frag.v"));
+
     pub const FRACTAL_NOISE: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/fractal_noise.frag.v"));
 
+    pub const GAUSSIAN: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/gaussian.frag.v"));
+
+    pub const HISTOGRAM_ROWS: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/histogram_rows.frag.v"));
+    pub const HISTOGRAM_SUM: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/histogram_sum.frag.v"));
+
     pub const INJECT: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/inject.frag.v"));
 
+    pub const LENS_UNDISTORT: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/lens_undistort.frag.v"));
+
     pub const OKLAB_ENCODE: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/oklab_encode.frag.v"));
     pub const OKLAB_DECODE: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/oklab_decode.frag.v"));
 
+    pub const PAD: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/pad.frag.v"));
+
     pub const PALETTE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/palette.frag.v"));
 
+    pub const PERSPECTIVE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/perspective.frag.v"));
+
+    pub const PREFIX_SUM: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/prefix_sum.frag.v"));
+
+    pub const PYRAMID: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/pyramid.frag.v"));
+
+    pub const REDUCE_MEAN: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/reduce_mean.frag.v"));
+    pub const REDUCE_MIN: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/reduce_min.frag.v"));
+    pub const REDUCE_MAX: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/reduce_max.frag.v"));
+
+    pub const RESAMPLE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/resample.frag.v"));
+
+    pub const SIMPLEX_NOISE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/simplex_noise.frag.v"));
+
+    pub const SHADOW: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/shadow.frag.v"));
+
     pub const SOLID_RGBA: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/solid_rgb.frag.v"));
 
+    pub const SPLINE_RAMP: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/spline_ramp.frag.v"));
+
+    pub const SSIM: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/ssim.frag.v"));
+
+    pub const SQUARED_DIFFERENCE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/squared_difference.frag.v"));
+
     pub const SRLAB2_ENCODE: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/srlab2_encode.frag.v"));
     pub const SRLAB2_DECODE: &[u8] =
         include_bytes!(concat!(env!("OUT_DIR"), "/spirv/srlab2_decode.frag.v"));
+
+    pub const TEST_PATTERN: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/test_pattern.frag.v"));
+
+    pub const TILE: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/tile.frag.v"));
+
+    pub const TONEMAP_REINHARD: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/tonemap_reinhard.frag.v"));
+    pub const TONEMAP_ACES: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/tonemap_aces.frag.v"));
+
+    pub const WORLEY: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/worley.frag.v"));
+
+    pub const VORONOI: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/spirv/voronoi.frag.v"));
+
+    pub const WHITE_BALANCE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/white_balance.frag.v"));
 }
 
 pub fn stage() -> ShadersStage {
@@ -61,6 +177,21 @@ pub fn stage() -> ShadersStage {
         decode_r32ui: std::sync::Arc::from(
             &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d32ui.frag.v"))[..],
         ),
+        decode_rgba16ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d16x4ui.frag.v"))[..],
+        ),
+        decode_rgba32ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d32x4ui.frag.v"))[..],
+        ),
+        decode_rgb10a2ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d10x3a2ui.frag.v"))[..],
+        ),
+        decode_rgb565ui_x2: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d565ui.frag.v"))[..],
+        ),
+        decode_rgba4ui_x2: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_d4x4ui.frag.v"))[..],
+        ),
         encode_r8ui_x4: std::sync::Arc::from(
             &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e8ui.frag.v"))[..],
         ),
@@ -70,6 +201,21 @@ pub fn stage() -> ShadersStage {
         encode_r32ui: std::sync::Arc::from(
             &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e32ui.frag.v"))[..],
         ),
+        encode_rgba16ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e16x4ui.frag.v"))[..],
+        ),
+        encode_rgba32ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e32x4ui.frag.v"))[..],
+        ),
+        encode_rgb10a2ui: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e10x3a2ui.frag.v"))[..],
+        ),
+        encode_rgb565ui_x2: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e565ui.frag.v"))[..],
+        ),
+        encode_rgba4ui_x2: std::sync::Arc::from(
+            &include_bytes!(concat!(env!("OUT_DIR"), "/spirv/stage_e4x4ui.frag.v"))[..],
+        ),
     }
 }
 
@@ -85,17 +231,63 @@ pub fn included_shaders_core() -> ShadersCore {
 
 pub fn included_shaders_std() -> ShadersStd {
     ShadersStd {
+        ab_transform: shader::AB_TRANSFORM.into(),
         bilinear: shader::BILINEAR.into(),
+        bradford_nonlinear: shader::BRADFORD_NONLINEAR.into(),
+        bright_pass: shader::BRIGHT_PASS.into(),
+        bloom: shader::BLOOM.into(),
+        demosaic: shader::DEMOSAIC.into(),
+        denoise_nlm: shader::DENOISE_NLM.into(),
+        sharpen: shader::SHARPEN.into(),
+        emboss: shader::EMBOSS.into(),
+        gamut_map: shader::GAMUT_MAP.into(),
+        gradient_polar: shader::GRADIENT_POLAR.into(),
         box3: shader::BOX.into(),
+        clahe_histogram: shader::CLAHE_HISTOGRAM.into(),
+        clahe_mapping: shader::CLAHE_MAPPING.into(),
+        clahe_apply: shader::CLAHE_APPLY.into(),
+        chromatic_aberration: shader::CHROMATIC_ABERRATION.into(),
+        chroma_key: shader::CHROMA_KEY.into(),
+        cielab_encode: shader::CIELAB_ENCODE.into(),
+        cielab_decode: shader::CIELAB_DECODE.into(),
+        cmyk_encode: shader::CMYK_ENCODE.into(),
+        cmyk_decode: shader::CMYK_DECODE.into(),
+        color_ramp: shader::COLOR_RAMP.into(),
         distribution_normal2d: shader::DISTRIBUTION_NORMAL_2D.into(),
+        downsample: shader::DOWNSAMPLE.into(),
+        exposure_fusion: shader::EXPOSURE_FUSION.into(),
         fractal_noise: shader::FRACTAL_NOISE.into(),
+        gaussian: shader::GAUSSIAN.into(),
+        histogram_rows: shader::HISTOGRAM_ROWS.into(),
+        histogram_sum: shader::HISTOGRAM_SUM.into(),
         inject: shader::INJECT.into(),
         linear_color_transform: FRAG_LINEAR.into(),
+        lens_undistort: shader::LENS_UNDISTORT.into(),
         oklab_encode: shader::OKLAB_ENCODE.into(),
         oklab_decode: shader::OKLAB_DECODE.into(),
+        pad: shader::PAD.into(),
         palette: shader::PALETTE.into(),
+        perspective: shader::PERSPECTIVE.into(),
+        prefix_sum: shader::PREFIX_SUM.into(),
+        pyramid: shader::PYRAMID.into(),
+        reduce_mean: shader::REDUCE_MEAN.into(),
+        reduce_min: shader::REDUCE_MIN.into(),
+        reduce_max: shader::REDUCE_MAX.into(),
+        resample: shader::RESAMPLE.into(),
+        simplex_noise: shader::SIMPLEX_NOISE.into(),
+        shadow: shader::SHADOW.into(),
         solid_rgb: shader::SOLID_RGBA.into(),
+        spline_ramp: shader::SPLINE_RAMP.into(),
+        ssim: shader::SSIM.into(),
+        squared_difference: shader::SQUARED_DIFFERENCE.into(),
         srlab2_encode: shader::SRLAB2_ENCODE.into(),
         srlab2_decode: shader::SRLAB2_DECODE.into(),
+        test_pattern: shader::TEST_PATTERN.into(),
+        tile: shader::TILE.into(),
+        tonemap_reinhard: shader::TONEMAP_REINHARD.into(),
+        tonemap_aces: shader::TONEMAP_ACES.into(),
+        worley: shader::WORLEY.into(),
+        voronoi: shader::VORONOI.into(),
+        white_balance: shader::WHITE_BALANCE.into(),
     }
 }
@@ -7,11 +7,13 @@ layout (set = 1, binding = 1) uniform texture2D in_texture;
 
 layout (set = 2, binding = 0, std140) uniform Matrix {
   mat3 rgb_matrix;
+  // The constant term of an affine transform, zero for a linear one.
+  vec4 offset;
 } color_matrix;
 
 void main() {
     mat3 color_mat = mat3(color_matrix.rgb_matrix);
 	
     vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv).rgba;
-    f_color = vec4(color_mat * rgba.rgb, rgba.a);
+    f_color = vec4(color_mat * rgba.rgb + color_matrix.offset.xyz, rgba.a);
 }
@@ -0,0 +1,80 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform LocalStatistics {
+    // The weights of the channels whose combination is the value of a texel.
+    vec4 weights;
+    // x: the radius of the window, y: the standard deviation of its gaussian.
+    // z, w: the stabilizing constants c1 and c2 of the structural similarity.
+    vec4 params;
+} u_stats;
+
+#ifndef SSIM_AS_MAIN
+#define SSIM_AS_MAIN ssim
+#endif
+
+// Statistics of a pair of values, weighted over a window. Accumulation happens
+// at full precision from the source texels, as the variances are computed as
+// differences of moments. The window is clamped to the edge of the image.
+struct Moments {
+    float weight;
+    vec2 mean;
+    vec2 square;
+    float product;
+};
+
+Moments moments_zero() {
+    return Moments(0.0, vec2(0.0), vec2(0.0), 0.0);
+}
+
+void moments_add(inout Moments m, float weight, vec2 value) {
+    m.weight += weight;
+    m.mean += weight * value;
+    m.square += weight * value * value;
+    m.product += weight * value.x * value.y;
+}
+
+float window_weight(ivec2 offset) {
+    const float sigma = u_stats.params.y;
+    return exp(-float(dot(offset, offset)) / (2.0 * sigma * sigma));
+}
+
+Moments window_moments_pair(ivec2 center) {
+    const ivec2 sz = textureSize(sampler2D(lhs, texture_sampler), 0);
+    const int radius = int(u_stats.params.x);
+
+    Moments m = moments_zero();
+    for (int dy = -radius; dy <= radius; dy++) {
+        for (int dx = -radius; dx <= radius; dx++) {
+            const ivec2 offset = ivec2(dx, dy);
+            const ivec2 pos = clamp(center + offset, ivec2(0), sz - 1);
+            const float x = dot(texelFetch(sampler2D(lhs, texture_sampler), pos, 0), u_stats.weights);
+            const float y = dot(texelFetch(sampler2D(rhs, texture_sampler), pos, 0), u_stats.weights);
+            moments_add(m, window_weight(offset), vec2(x, y));
+        }
+    }
+
+    return m;
+}
+
+// The structural similarity index of the windows around each texel.
+void SSIM_AS_MAIN() {
+    const Moments m = window_moments_pair(ivec2(gl_FragCoord.xy));
+    const float c1 = u_stats.params.z;
+    const float c2 = u_stats.params.w;
+
+    const vec2 mean = m.mean / m.weight;
+    const vec2 variance = max(m.square / m.weight - mean * mean, vec2(0.0));
+    const float covariance = m.product / m.weight - mean.x * mean.y;
+
+    const float luminance = (2.0 * mean.x * mean.y + c1) / (dot(mean, mean) + c1);
+    const float structure = (2.0 * covariance + c2) / (variance.x + variance.y + c2);
+    const float ssim = luminance * structure;
+
+    f_color = vec4(vec3(ssim), 1.0);
+}
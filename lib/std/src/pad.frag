@@ -0,0 +1,52 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Pad {
+    // xy: the position of the source in the target, zw: the size of the source.
+    ivec4 placement;
+    // x: the border mode.
+    ivec4 config;
+    // The color outside the source, for the constant mode.
+    vec4 color;
+} u_pad;
+
+const int BORDER_CONSTANT = 0;
+const int BORDER_CLAMP = 1;
+const int BORDER_MIRROR = 2;
+const int BORDER_WRAP = 3;
+
+ivec2 wrap(ivec2 pos, ivec2 size) {
+    return ((pos % size) + size) % size;
+}
+
+void main() {
+    const ivec2 size = u_pad.placement.zw;
+    ivec2 pos = ivec2(gl_FragCoord.xy) - u_pad.placement.xy;
+
+    const bool inside = all(greaterThanEqual(pos, ivec2(0))) && all(lessThan(pos, size));
+
+    if (!inside) {
+        switch (u_pad.config.x) {
+        case BORDER_CONSTANT:
+            f_color = u_pad.color;
+            return;
+        case BORDER_CLAMP:
+            pos = clamp(pos, ivec2(0), size - 1);
+            break;
+        case BORDER_MIRROR:
+            // Reflected at the edge, such that the edge texel itself is repeated.
+            pos = wrap(pos, 2 * size);
+            pos = mix(pos, 2 * size - 1 - pos, greaterThanEqual(pos, size));
+            break;
+        case BORDER_WRAP:
+            pos = wrap(pos, size);
+            break;
+        }
+    }
+
+    f_color = texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+}
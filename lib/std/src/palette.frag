@@ -12,6 +12,10 @@ layout (set = 2, binding = 0) uniform FragmentPushConstants {
   // FIXME(naga) mat4x2 with std430 gets miscompiled on WebGL.
   // Hence, we use the equivalent layout vec4 [2];
   vec4 channels[2];
+  // The base coordinate in texels of the palette, in `xy`. Paint the border if `z` is set.
+  vec4 base;
+  // The color outside the palette, with border addressing.
+  vec4 border;
 } u_platte;
 
 mat4x2 channel_matrix(vec4 a, vec4 b) {
@@ -26,7 +30,15 @@ void main() {
 
     // FIXME(naga): see above
     mat4x2 mat = channel_matrix(u_platte.channels[0], u_platte.channels[1]);
-    vec2 paletteuv = mat * basis + bias;
+    vec2 base = u_platte.base.xy / vec2(textureSize(sampler2D(lhs, texture_sampler), 0));
+    vec2 paletteuv = mat * basis + base + bias;
+
+    if (u_platte.base.z != 0.0
+      && (any(lessThan(paletteuv, vec2(0.0))) || any(greaterThanEqual(paletteuv, vec2(1.0)))))
+    {
+      f_color = u_platte.border;
+      return;
+    }
 
     f_color = texture(sampler2D(lhs, texture_sampler), paletteuv).rgba;
 }
@@ -0,0 +1,51 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform Perspective {
+    // The rows of the inverse homography, from target to source texel coordinates.
+    vec4 row_x;
+    vec4 row_y;
+    vec4 row_w;
+    // x: the width of the source, y: its height, z: sampling (0 nearest, 1 bilinear).
+    vec4 params;
+} u_perspective;
+
+vec4 fetch_rhs(ivec2 pos) {
+    const ivec2 sz = ivec2(u_perspective.params.xy);
+    return texelFetch(sampler2D(rhs, texture_sampler), clamp(pos, ivec2(0), sz - 1), 0);
+}
+
+// Paint the source on top of the target with a projective transformation, divided per fragment
+// such that the texture coordinates are interpolated projectively across the quad.
+void main() {
+    const vec3 target = vec3(gl_FragCoord.xy, 1.0);
+    const vec3 source = vec3(
+        dot(u_perspective.row_x.xyz, target),
+        dot(u_perspective.row_y.xyz, target),
+        dot(u_perspective.row_w.xyz, target));
+
+    const vec2 size = u_perspective.params.xy;
+    const vec2 pos = source.xy / source.z;
+
+    if (source.z == 0.0 || any(lessThan(pos, vec2(0.0))) || any(greaterThanEqual(pos, size))) {
+        f_color = texelFetch(sampler2D(lhs, texture_sampler), ivec2(gl_FragCoord.xy), 0);
+        return;
+    }
+
+    if (u_perspective.params.z == 0.0) {
+        f_color = fetch_rhs(ivec2(floor(pos)));
+    } else {
+        const vec2 texel = pos - 0.5;
+        const ivec2 base = ivec2(floor(texel));
+        const vec2 t = fract(texel);
+
+        const vec4 top = mix(fetch_rhs(base), fetch_rhs(base + ivec2(1, 0)), t.x);
+        const vec4 bot = mix(fetch_rhs(base + ivec2(0, 1)), fetch_rhs(base + ivec2(1, 1)), t.x);
+        f_color = mix(top, bot, t.y);
+    }
+}
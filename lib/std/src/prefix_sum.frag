@@ -0,0 +1,26 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform PrefixSum {
+    // xy: the offset of the texel which is added, along one axis.
+    ivec4 offset;
+} u_prefix;
+
+// One step of an inclusive scan, in the manner of Hillis and Steele. After the
+// steps with offsets 1, 2, 4, … covering the image size each texel holds the
+// sum of all texels preceding it along the axis, and itself.
+void main() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const ivec2 prior = pos - u_prefix.offset.xy;
+
+    vec4 sum = texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+    if (prior.x >= 0 && prior.y >= 0) {
+        sum += texelFetch(sampler2D(in_texture, texture_sampler), prior, 0);
+    }
+
+    f_color = sum;
+}
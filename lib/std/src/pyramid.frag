@@ -0,0 +1,38 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform Expand {
+    // x: the factor of the expanded coarse level, 1 to add and -1 to subtract.
+    vec4 params;
+} u_expand;
+
+vec4 fetch_coarse(ivec2 pos, ivec2 sz) {
+    return texelFetch(sampler2D(rhs, texture_sampler), clamp(pos, ivec2(0), sz - 1), 0);
+}
+
+// Combine a level of a pyramid with the bi-linear expansion of the next coarser level.
+void main() {
+    const ivec2 fine_size = textureSize(sampler2D(lhs, texture_sampler), 0);
+    const ivec2 coarse_size = textureSize(sampler2D(rhs, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+
+    const vec2 coarse = gl_FragCoord.xy * vec2(coarse_size) / vec2(fine_size) - 0.5;
+    const ivec2 base = ivec2(floor(coarse));
+    const vec2 t = fract(coarse);
+
+    const vec4 top = mix(
+        fetch_coarse(base, coarse_size),
+        fetch_coarse(base + ivec2(1, 0), coarse_size), t.x);
+    const vec4 bot = mix(
+        fetch_coarse(base + ivec2(0, 1), coarse_size),
+        fetch_coarse(base + ivec2(1, 1), coarse_size), t.x);
+    const vec4 expanded = mix(top, bot, t.y);
+
+    f_color = texelFetch(sampler2D(lhs, texture_sampler), pos, 0)
+        + u_expand.params.x * expanded;
+}
@@ -0,0 +1,95 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Reduce {
+    // xy: the size of the original image.
+    // z: the level of the source, each of its texels covers 2^z-by-2^z texels
+    // of the original except at the border.
+    uvec4 config;
+} u_reduce;
+
+#ifndef REDUCE_MEAN_AS_MAIN
+#define REDUCE_MEAN_AS_MAIN reduce_mean
+#endif
+#ifndef REDUCE_MIN_AS_MAIN
+#define REDUCE_MIN_AS_MAIN reduce_min
+#endif
+#ifndef REDUCE_MAX_AS_MAIN
+#define REDUCE_MAX_AS_MAIN reduce_max
+#endif
+
+// One step of a reduction chain. Each target texel reduces the (up to) 2-by-2
+// block of source texels that it covers. Blocks at the border of an image with
+// odd dimensions contain fewer texels.
+
+// The number of original texels covered by a source texel, along one axis.
+float coverage(uint pos, uint size) {
+    const uint scale = 1u << u_reduce.config.z;
+    return float(min((pos + 1u) * scale, size) - pos * scale);
+}
+
+// Source texels are weighted by the original texels they cover, so that the
+// result is the exact mean of the original at every level.
+void REDUCE_MEAN_AS_MAIN() {
+    ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    ivec2 base = 2 * ivec2(gl_FragCoord.xy);
+
+    vec4 sum = vec4(0.0);
+    float weight = 0.0;
+
+    for (int dx = 0; dx < 2; dx++) {
+        for (int dy = 0; dy < 2; dy++) {
+            ivec2 pos = base + ivec2(dx, dy);
+            if (pos.x < sz.x && pos.y < sz.y) {
+                float w = coverage(uint(pos.x), u_reduce.config.x)
+                    * coverage(uint(pos.y), u_reduce.config.y);
+                sum += w * texelFetch(sampler2D(in_texture, texture_sampler), pos, 0);
+                weight += w;
+            }
+        }
+    }
+
+    f_color = sum / max(weight, 1.0);
+}
+
+void REDUCE_MIN_AS_MAIN() {
+    ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    ivec2 base = 2 * ivec2(gl_FragCoord.xy);
+
+    // The block always contains its base texel.
+    vec4 acc = texelFetch(sampler2D(in_texture, texture_sampler), base, 0);
+
+    for (int dx = 0; dx < 2; dx++) {
+        for (int dy = 0; dy < 2; dy++) {
+            ivec2 pos = base + ivec2(dx, dy);
+            if (pos.x < sz.x && pos.y < sz.y) {
+                acc = min(acc, texelFetch(sampler2D(in_texture, texture_sampler), pos, 0));
+            }
+        }
+    }
+
+    f_color = acc;
+}
+
+void REDUCE_MAX_AS_MAIN() {
+    ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    ivec2 base = 2 * ivec2(gl_FragCoord.xy);
+
+    // The block always contains its base texel.
+    vec4 acc = texelFetch(sampler2D(in_texture, texture_sampler), base, 0);
+
+    for (int dx = 0; dx < 2; dx++) {
+        for (int dy = 0; dy < 2; dy++) {
+            ivec2 pos = base + ivec2(dx, dy);
+            if (pos.x < sz.x && pos.y < sz.y) {
+                acc = max(acc, texelFetch(sampler2D(in_texture, texture_sampler), pos, 0));
+            }
+        }
+    }
+
+    f_color = acc;
+}
@@ -0,0 +1,85 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Resample {
+    // x: the reconstruction kernel, y: the axis along which to resample.
+    ivec4 config;
+    // x: the scale of source texels per target texel.
+    // y: the support of the kernel, in source texels.
+    vec4 params;
+} u_resample;
+
+const float PI = 3.14159265358979;
+
+const int KERNEL_TRIANGLE = 0;
+const int KERNEL_CATMULL_ROM = 1;
+const int KERNEL_LANCZOS3 = 2;
+
+float sinc(float x) {
+    if (abs(x) < 1e-6) {
+        return 1.0;
+    }
+
+    const float px = PI * x;
+    return sin(px) / px;
+}
+
+float kernel(float x) {
+    const float t = abs(x);
+
+    switch (u_resample.config.x) {
+    case KERNEL_TRIANGLE:
+        return max(1.0 - t, 0.0);
+    case KERNEL_CATMULL_ROM:
+        if (t < 1.0) {
+            return (1.5 * t - 2.5) * t * t + 1.0;
+        } else if (t < 2.0) {
+            return ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0;
+        }
+        return 0.0;
+    case KERNEL_LANCZOS3:
+        return t < 3.0 ? sinc(t) * sinc(t / 3.0) : 0.0;
+    }
+
+    return 0.0;
+}
+
+// One separable pass of resampling along an axis. When downscaling the kernel
+// is widened by the scale, such that every source texel contributes to the
+// target texels covering it.
+void main() {
+    const ivec2 sz = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const int axis = u_resample.config.y;
+
+    const float scale = u_resample.params.x;
+    const float support = u_resample.params.y;
+    const float widen = max(scale, 1.0);
+
+    // The position of the target texel's center, in source texel coordinates.
+    const float center = (float(pos[axis]) + 0.5) * scale - 0.5;
+    const int first = int(floor(center - support));
+    const int last = int(ceil(center + support));
+
+    vec4 sum = vec4(0.0);
+    float weight = 0.0;
+
+    for (int i = first; i <= last; i++) {
+        const float w = kernel((float(i) - center) / widen);
+        if (w == 0.0) {
+            continue;
+        }
+
+        ivec2 source = pos;
+        source[axis] = clamp(i, 0, sz[axis] - 1);
+
+        sum += w * texelFetch(sampler2D(in_texture, texture_sampler), source, 0);
+        weight += w;
+    }
+
+    f_color = sum / weight;
+}
@@ -0,0 +1,26 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+layout (set = 2, binding = 0) uniform Shadow {
+    // rgb: the color of the shadow, a: its opacity.
+    vec4 color;
+} u_shadow;
+
+// Composite the image over the tinted, blurred silhouette in its alpha channel.
+void main() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+    const vec4 above = texelFetch(sampler2D(lhs, texture_sampler), pos, 0);
+    const float silhouette = texelFetch(sampler2D(rhs, texture_sampler), pos, 0).a;
+
+    // The usual `over` operator, in premultiplied form.
+    const float shadow = u_shadow.color.a * silhouette * (1.0 - above.a);
+    const float alpha = above.a + shadow;
+    const vec3 color = above.rgb * above.a + u_shadow.color.rgb * shadow;
+
+    f_color = vec4(alpha > 0.0 ? color / alpha : vec3(0.0), alpha);
+}
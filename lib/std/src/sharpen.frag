@@ -0,0 +1,38 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Sharpen {
+    // x: the amount of the Laplacian added, first such that it can be controlled on its own.
+    vec4 params;
+    // x: whether the result is clamped to the unit interval.
+    ivec4 config;
+} u_sharpen;
+
+vec4 fetch(ivec2 pos, ivec2 size) {
+    return texelFetch(sampler2D(in_texture, texture_sampler), clamp(pos, ivec2(0), size - 1), 0);
+}
+
+// Add the negative 4-neighbor Laplacian to the color, in a single pass.
+void main() {
+    const ivec2 size = textureSize(sampler2D(in_texture, texture_sampler), 0);
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+
+    const vec4 center = fetch(pos, size);
+    const vec3 neighbors = fetch(pos + ivec2(-1, 0), size).rgb
+        + fetch(pos + ivec2(1, 0), size).rgb
+        + fetch(pos + ivec2(0, -1), size).rgb
+        + fetch(pos + ivec2(0, 1), size).rgb;
+
+    const vec3 laplacian = 4.0 * center.rgb - neighbors;
+    vec3 color = center.rgb + u_sharpen.params.x * laplacian;
+
+    if (u_sharpen.config.x != 0) {
+        color = clamp(color, 0.0, 1.0);
+    }
+
+    f_color = vec4(color, center.a);
+}
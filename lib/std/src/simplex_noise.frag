@@ -0,0 +1,101 @@
+#version 450
+#extension GL_EXT_scalar_block_layout : require
+
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0, std430) uniform SimplexNoise {
+    // Every image is split into a number of cells
+    vec2 initial_scale;
+    // Amplitude used for the first iteration
+    float amplitude;
+    // Damping of the amplitude for further iterations
+    float damping;
+    // The number of iterations to add
+    uint num_octaves;
+    // The factor of frequencies between iterations
+    float lacunarity;
+    // Displacement of coordinates by another evaluation, in cells of the first iteration
+    float warp;
+    // Selects one of many uncorrelated noise patterns
+    uint seed;
+} u_fragmentParams;
+
+const float PI = 3.14159265358979;
+
+// Skewing of the square grid to the simplex grid, and back.
+const float F2 = 0.366025403784439;
+const float G2 = 0.211324865405187;
+
+// From https://jcgt.org/published/0009/03/02/paper.pdf
+uvec4 pcg4d(uvec4 v)
+{
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y*v.w; v.y += v.z*v.x; v.z += v.x*v.y; v.w += v.y*v.z;
+    v = v ^ (v >> 16u);
+    v.x += v.y*v.w; v.y += v.z*v.x; v.z += v.x*v.y; v.w += v.y*v.z;
+    return v;
+}
+
+vec4 hash(uvec2 v) {
+    const float MAX = float(0xFFFFFFFFu);
+    uvec4 hashed = pcg4d(uvec4(v, u_fragmentParams.seed, 0));
+    return vec4(hashed) / MAX;
+}
+
+// The contribution of a corner, one independent gradient for each channel.
+vec4 corner(ivec2 cell, vec2 offset) {
+    float t = max(0.5 - dot(offset, offset), 0.0);
+    t = t * t;
+
+    vec4 angle = 2.0 * PI * hash(uvec2(cell));
+    return t * t * (cos(angle) * offset.x + sin(angle) * offset.y);
+}
+
+// Gradient noise on the simplex grid, in about [-1, 1].
+vec4 noise(vec2 pt) {
+    vec2 skewed = floor(pt + dot(pt, vec2(F2)));
+    ivec2 cell = ivec2(skewed);
+
+    // Offsets to the three corners of the containing simplex.
+    vec2 x0 = pt - skewed + dot(skewed, vec2(G2));
+    ivec2 step = x0.x > x0.y ? ivec2(1, 0) : ivec2(0, 1);
+    vec2 x1 = x0 - vec2(step) + G2;
+    vec2 x2 = x0 - 1.0 + 2.0 * G2;
+
+    vec4 n = corner(cell, x0) + corner(cell + step, x1) + corner(cell + ivec2(1), x2);
+    return 70.0 * n;
+}
+
+vec2 next_point(vec2 x) {
+    // Rotate to reduce axial bias
+    const mat2 rot = mat2(cos(0.5), sin(0.5), -sin(0.5), cos(0.5));
+    return u_fragmentParams.lacunarity * (rot * x);
+}
+
+vec4 fbm(vec2 x) {
+    float a = u_fragmentParams.amplitude;
+    float damping = u_fragmentParams.damping;
+
+    vec2 it = x * u_fragmentParams.initial_scale;
+    vec4 v = vec4(0.0);
+    for (int i = 0; i < u_fragmentParams.num_octaves; ++i) {
+        v += a * (0.5 + 0.5 * noise(it));
+
+        it = next_point(it);
+        a *= damping;
+    }
+    return v;
+}
+
+void main() {
+    vec2 pt = uv;
+
+    if (u_fragmentParams.warp != 0.0) {
+        // Two uncorrelated channels of the same noise, centered, displace the coordinates.
+        vec2 offset = 2.0 * fbm(uv).zw - 1.0;
+        pt += u_fragmentParams.warp * offset / u_fragmentParams.initial_scale;
+    }
+
+    f_color = fbm(pt);
+}
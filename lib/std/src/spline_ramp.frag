@@ -0,0 +1,36 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+// Keep in sync with `shaders::spline_ramp::MAX_POINTS`.
+#define MAX_POINTS 16
+
+layout (set = 1, binding = 0) uniform SplineRamp {
+    // The control points of the spline.
+    vec4 points[MAX_POINTS];
+    // x: the number of control points, at least 4.
+    ivec4 config;
+} u_spline;
+
+void main() {
+    const int count = u_spline.config.x;
+    // The curve passes through all but the first and last point, which only
+    // determine the tangents at its ends.
+    const int segments = count - 3;
+
+    const float position = clamp(uv.x, 0.0, 1.0) * float(segments);
+    const int i = min(int(floor(position)), segments - 1);
+    const float t = position - float(i);
+
+    const vec4 p0 = u_spline.points[i];
+    const vec4 p1 = u_spline.points[i + 1];
+    const vec4 p2 = u_spline.points[i + 2];
+    const vec4 p3 = u_spline.points[i + 3];
+
+    f_color = 0.5 * (
+        2.0 * p1
+        + (p2 - p0) * t
+        + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * t * t
+        + (3.0 * p1 - p0 - 3.0 * p2 + p3) * t * t * t
+    );
+}
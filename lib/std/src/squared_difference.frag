@@ -0,0 +1,15 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+void main() {
+    ivec2 pos = ivec2(gl_FragCoord.xy);
+    vec4 diff = texelFetch(sampler2D(lhs, texture_sampler), pos, 0)
+        - texelFetch(sampler2D(rhs, texture_sampler), pos, 0);
+
+    f_color = diff * diff;
+}
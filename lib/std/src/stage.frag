@@ -8,7 +8,7 @@
  * documentation which doesn't mention this detail at all apart from an internal
  * method.
  */
-#if (defined(DECODE_RGBA32UI_AS_MAIN) || defined(DECODE_RGBA16UI_AS_MAIN) || defined(DECODE_R32UI_AS_MAIN) || defined(DECODE_R16UI_AS_MAIN) || defined(DECODE_R8UI_AS_MAIN))
+#if (defined(DECODE_RGBA32UI_AS_MAIN) || defined(DECODE_RGBA16UI_AS_MAIN) || defined(DECODE_R32UI_AS_MAIN) || defined(DECODE_R16UI_AS_MAIN) || defined(DECODE_R8UI_AS_MAIN) || defined(DECODE_RGB10A2UI_AS_MAIN) || defined(DECODE_RGB565UI_AS_MAIN) || defined(DECODE_RGBA4UI_AS_MAIN))
 #define STAGE_DECODE
 #else
 #define STAGE_ENCODE
@@ -44,6 +44,24 @@
 #ifndef ENCODE_RGBA32UI_AS_MAIN
 #define ENCODE_RGBA32UI_AS_MAIN encode_rgba32ui
 #endif
+#ifndef DECODE_RGB10A2UI_AS_MAIN
+#define DECODE_RGB10A2UI_AS_MAIN decode_rgb10a2ui
+#endif
+#ifndef ENCODE_RGB10A2UI_AS_MAIN
+#define ENCODE_RGB10A2UI_AS_MAIN encode_rgb10a2ui
+#endif
+#ifndef DECODE_RGB565UI_AS_MAIN
+#define DECODE_RGB565UI_AS_MAIN decode_rgb565ui
+#endif
+#ifndef ENCODE_RGB565UI_AS_MAIN
+#define ENCODE_RGB565UI_AS_MAIN encode_rgb565ui
+#endif
+#ifndef DECODE_RGBA4UI_AS_MAIN
+#define DECODE_RGBA4UI_AS_MAIN decode_rgba4ui
+#endif
+#ifndef ENCODE_RGBA4UI_AS_MAIN
+#define ENCODE_RGBA4UI_AS_MAIN encode_rgba4ui
+#endif
 /** This is a special shader to convert to/from color spaces and texture
  * formats that are not natively supported. This works by introducing a staging
  * texture that is in the correct byte representation of the supposed format
@@ -91,6 +109,9 @@ layout (set = 1, binding = 1) uniform utexture2D image_r16ui;
 layout (set = 1, binding = 2) uniform utexture2D image_r32ui;
 layout (set = 1, binding = 3) uniform utexture2D image_rgba16ui;
 layout (set = 1, binding = 4) uniform utexture2D image_rgba32ui;
+layout (set = 1, binding = 5) uniform utexture2D image_rgb10a2ui;
+layout (set = 1, binding = 6) uniform utexture2D image_rgb565ui;
+layout (set = 1, binding = 7) uniform utexture2D image_rgba4ui;
 
 /** For encoding, this is the input frame buffer.
  */
@@ -203,6 +224,12 @@ uint get_horizontal_workload() {
   return max(parameter.space.a, 1) & 0xff;
 }
 
+/** If integer samples are dithered, before being quantized when encoding.
+ */
+bool get_dither() {
+  return (parameter.space.a & 0x100) != 0;
+}
+
 /** The 'position' in the input texel to retrieve one of the actual texels of the input image.
  *
  * Since, on WebGPU, we are only allowed to Load/Store at 32-bit granularity,
@@ -241,6 +268,12 @@ ivec2 encodePixelCoord() {
 vec4 demux_uint(uint, uint kind);
 uint mux_uint(vec4, uint kind);
 
+vec4 demux_uvec4(uvec4, uint kind);
+uvec4 mux_uvec4(vec4, uint kind);
+
+vec4 dither(vec4, ivec2 pixel, uint kind);
+vec4 quantize_nearest(vec4, ivec2 pixel, uint kind);
+
 vec4 parts_normalize(vec4, uint);
 vec4 parts_denormalize(vec4, uint);
 
@@ -273,6 +306,9 @@ float transfer_display_scene_smpte2084(float val);
 float transfer_oe_smpte2084(float val);
 float transfer_oe_inv_smpte2084(float val);
 
+float transfer_oe_bt2100hlg(float val);
+float transfer_oe_inv_bt2100hlg(float val);
+
 vec3 transfer_lab_to_lch(vec3);
 vec3 transfer_lch_to_lab(vec3);
 
@@ -364,7 +400,7 @@ float transfer_eo_bt2020_10b(float val) {
 
 // Used Reference: BT.2100-2, Table 4, Reference PQ EOTF
 #define SMPTE2084_M1 (2610.0/16384.0)
-#define SMPTE2084_M2 (2523.0/4096.0)
+#define SMPTE2084_M2 (2523.0/32.0)
 #define SMPTE2084_C1 (3424.0/4096.0)
 #define SMPTE2084_C2 (2413.0/128.0)
 #define SMPTE2084_C3 (2392.0/128.0)
@@ -407,20 +443,37 @@ float transfer_oe_inv_smpte2084(float val) {
   return transfer_display_scene_smpte2084(transfer_eo_smpte2084(val));
 }
 
-// TODO: https://github.com/colour-science/colour/blob/a196f9536c44e2101cde53446550d64303c0ab46/colour/models/rgb/transfer_functions/arib_std_b67.py#L108
-vec3 transfer_scene_display_bt2100hlg(vec3 rgb) {
-  return vec3(0.0);
+// Used Reference: BT.2100-2, Table 5, Reference HLG OETF
+#define BT2100HLG_A 0.17883277
+#define BT2100HLG_B (1.0 - 4.0 * BT2100HLG_A)
+#define BT2100HLG_C (0.5 - BT2100HLG_A * log(4.0 * BT2100HLG_A))
+
+// Note: the input is relative _scene_ light in [0, 1], not display luminance.
+float transfer_oe_bt2100hlg(float val) {
+  if (val <= 1.0 / 12.0)
+    return sqrt(3.0 * max(val, 0.0));
+  else
+    return BT2100HLG_A * log(12.0 * val - BT2100HLG_B) + BT2100HLG_C;
+}
+
+float transfer_oe_inv_bt2100hlg(float val) {
+  if (val <= 0.5)
+    return val * val / 3.0;
+  else
+    return (exp((val - BT2100HLG_C) / BT2100HLG_A) + BT2100HLG_B) / 12.0;
 }
 
 vec3 transfer_lab_to_lch(vec3 lab) {
   float c = length(lab.yz);
-  // Angle but scaled to [0; 1]
-  float h = (degrees(atan(lab.z, lab.y)) / 360.0) + 0.5;
+  // Angle but scaled to [0; 1), such that it fits unsigned texels. The half turn of `atan`
+  // otherwise maps onto 1.0, and a vanishing chroma has no defined hue at all.
+  float h = c > 0.0 ? fract((degrees(atan(lab.z, lab.y)) / 360.0) + 0.5) : 0.5;
   return vec3(lab.x, c, h);
 }
 
 vec3 transfer_lch_to_lab(vec3 lch) {
-  float angle = radians(360.0 * (lch.z - 0.5));
+  // Wrap around the full turn, hues outside [0; 1) are valid in float texels.
+  float angle = radians(360.0 * (fract(lch.z) - 0.5));
   return vec3(lch.x, lch.y*cos(angle), lch.y*sin(angle));
 }
 
@@ -460,7 +513,8 @@ void ENCODE_R8UI_AS_MAIN() {
     // FIXME: YUV transform and accurate YUV transform.
     vec4 components = parts_denormalize(electrical, get_sample_parts());
 
-    uint texelNum = mux_uint(clamp(components, 0.0, 1.0), get_sample_bits());
+    vec4 quantized = dither(clamp(components, 0.0, 1.0), pixelCoord, get_sample_bits());
+    uint texelNum = mux_uint(quantized, get_sample_bits());
     num |= (texelNum & 0xff) << (8*i);
   }
 
@@ -492,7 +546,8 @@ void ENCODE_R16UI_AS_MAIN() {
     // FIXME: YUV transform and accurate YUV transform.
     vec4 components = parts_denormalize(electrical, get_sample_parts());
 
-    uint texelNum = mux_uint(clamp(components, 0.0, 1.0), get_sample_bits());
+    vec4 quantized = dither(clamp(components, 0.0, 1.0), pixelCoord, get_sample_bits());
+    uint texelNum = mux_uint(quantized, get_sample_bits());
     num |= (texelNum & 0xffff) << (16*i);
   }
 
@@ -519,7 +574,142 @@ void ENCODE_R32UI_AS_MAIN() {
   // FIXME: YUV transform and accurate YUV transform.
   vec4 components = parts_denormalize(electrical, get_sample_parts());
 
-  uint num = mux_uint(clamp(components, 0.0, 1.0), get_sample_bits());
+  vec4 quantized = dither(clamp(components, 0.0, 1.0), ivec2(gl_FragCoord), get_sample_bits());
+  uint num = mux_uint(quantized, get_sample_bits());
+  uvec4 fake_local_noop = uvec4(0);
+  encode_color = uvec4(num);
+}
+
+void DECODE_RGBA16UI_AS_MAIN() {
+  uvec4 num = texelFetch(usampler2D(image_rgba16ui, read_sampler), decodeStageTexelCoord(), 0);
+  vec4 components = demux_uvec4(num, get_sample_bits());
+
+  vec4 electrical = parts_normalize(components, get_sample_parts());
+  vec4 primaries = parts_untransfer(electrical, get_transfer());
+
+  vec4 fake_local_noop = vec4(0);
+  decode_color = primaries;
+}
+
+void ENCODE_RGBA16UI_AS_MAIN() {
+  vec4 primaries = texture(sampler2D(in_texture, texture_sampler), uv).rgba;
+
+  vec4 electrical = parts_transfer(primaries, get_transfer());
+  vec4 components = parts_denormalize(electrical, get_sample_parts());
+
+  // Not clamped, the float samples keep values outside the unit range.
+  vec4 quantized = dither(components, ivec2(gl_FragCoord), get_sample_bits());
+  uvec4 fake_local_noop = uvec4(0);
+  encode_color = mux_uvec4(quantized, get_sample_bits());
+}
+
+void DECODE_RGBA32UI_AS_MAIN() {
+  uvec4 num = texelFetch(usampler2D(image_rgba32ui, read_sampler), decodeStageTexelCoord(), 0);
+  vec4 components = demux_uvec4(num, get_sample_bits());
+
+  vec4 electrical = parts_normalize(components, get_sample_parts());
+  vec4 primaries = parts_untransfer(electrical, get_transfer());
+
+  vec4 fake_local_noop = vec4(0);
+  decode_color = primaries;
+}
+
+void ENCODE_RGBA32UI_AS_MAIN() {
+  vec4 primaries = texture(sampler2D(in_texture, texture_sampler), uv).rgba;
+
+  vec4 electrical = parts_transfer(primaries, get_transfer());
+  vec4 components = parts_denormalize(electrical, get_sample_parts());
+
+  uvec4 fake_local_noop = uvec4(0);
+  encode_color = mux_uvec4(components, get_sample_bits());
+}
+
+/* The packed formats, where channels of different bit widths share one 32-bit
+ * or 16-bit word. The decoding is the same as for the plain integer words but
+ * encoding rounds to the nearest step. With only a handful of levels, such as
+ * the 2-bit alpha of 10-bit color, truncation would be visibly biased.
+ */
+void DECODE_RGB10A2UI_AS_MAIN() {
+  uint num = texelFetch(usampler2D(image_rgb10a2ui, read_sampler), decodeStageTexelCoord(), 0).x;
+  vec4 components = demux_uint(num, get_sample_bits());
+
+  vec4 electrical = parts_normalize(components, get_sample_parts());
+  vec4 primaries = parts_untransfer(electrical, get_transfer());
+
+  vec4 fake_local_noop = vec4(0);
+  decode_color = primaries;
+}
+
+void ENCODE_RGB10A2UI_AS_MAIN() {
+  vec4 primaries = texture(sampler2D(in_texture, texture_sampler), uv).rgba;
+
+  vec4 electrical = parts_transfer(primaries, get_transfer());
+  vec4 components = parts_denormalize(electrical, get_sample_parts());
+
+  vec4 quantized = quantize_nearest(clamp(components, 0.0, 1.0), ivec2(gl_FragCoord), get_sample_bits());
+  uint num = mux_uint(quantized, get_sample_bits());
+  uvec4 fake_local_noop = uvec4(0);
+  encode_color = uvec4(num);
+}
+
+void DECODE_RGB565UI_AS_MAIN() {
+  uint num = texelFetch(usampler2D(image_rgb565ui, read_sampler), decodeStageTexelCoord(), 0).x;
+  uint work = (num >> 16*decodeSubtexelCoord()) & 0xffff;
+  vec4 components = demux_uint(work, get_sample_bits());
+
+  vec4 electrical = parts_normalize(components, get_sample_parts());
+  vec4 primaries = parts_untransfer(electrical, get_transfer());
+
+  vec4 fake_local_noop = vec4(0);
+  decode_color = primaries;
+}
+
+void ENCODE_RGB565UI_AS_MAIN() {
+  ivec2 baseCoord = encodePixelCoord();
+  uint num = 0;
+  for (int i = 0; i < get_horizontal_workload(); i++) {
+    ivec2 pixelCoord = baseCoord + ivec2(i, 0);
+    vec4 primaries = texelFetch(sampler2D(in_texture, texture_sampler), pixelCoord, 0);
+
+    vec4 electrical = parts_transfer(primaries, get_transfer());
+    vec4 components = parts_denormalize(electrical, get_sample_parts());
+
+    vec4 quantized = quantize_nearest(clamp(components, 0.0, 1.0), pixelCoord, get_sample_bits());
+    uint texelNum = mux_uint(quantized, get_sample_bits());
+    num |= (texelNum & 0xffff) << (16*i);
+  }
+
+  uvec4 fake_local_noop = uvec4(0);
+  encode_color = uvec4(num);
+}
+
+void DECODE_RGBA4UI_AS_MAIN() {
+  uint num = texelFetch(usampler2D(image_rgba4ui, read_sampler), decodeStageTexelCoord(), 0).x;
+  uint work = (num >> 16*decodeSubtexelCoord()) & 0xffff;
+  vec4 components = demux_uint(work, get_sample_bits());
+
+  vec4 electrical = parts_normalize(components, get_sample_parts());
+  vec4 primaries = parts_untransfer(electrical, get_transfer());
+
+  vec4 fake_local_noop = vec4(0);
+  decode_color = primaries;
+}
+
+void ENCODE_RGBA4UI_AS_MAIN() {
+  ivec2 baseCoord = encodePixelCoord();
+  uint num = 0;
+  for (int i = 0; i < get_horizontal_workload(); i++) {
+    ivec2 pixelCoord = baseCoord + ivec2(i, 0);
+    vec4 primaries = texelFetch(sampler2D(in_texture, texture_sampler), pixelCoord, 0);
+
+    vec4 electrical = parts_transfer(primaries, get_transfer());
+    vec4 components = parts_denormalize(electrical, get_sample_parts());
+
+    vec4 quantized = quantize_nearest(clamp(components, 0.0, 1.0), pixelCoord, get_sample_bits());
+    uint texelNum = mux_uint(quantized, get_sample_bits());
+    num |= (texelNum & 0xffff) << (16*i);
+  }
+
   uvec4 fake_local_noop = uvec4(0);
   encode_color = uvec4(num);
 }
@@ -640,6 +830,110 @@ uint mux_uint(vec4 c, uint kind) {
 #undef STEALTH_MUX_UINT4
 }
 
+// The bit decoding used by 64bit and 128bit staging.
+// Each component of the texel is in its own channel of the stage texel, in
+// the canonical order of demux_uint for four parts.
+vec4 demux_uvec4(uvec4 num, uint kind) {
+  switch (kind) {
+  case SAMPLE_BITS_Int16x4:
+    return vec4(num & MASK16) / BITS16;
+  case SAMPLE_BITS_Float16x4:
+    return vec4(
+      unpackHalf2x16(num.x).x,
+      unpackHalf2x16(num.y).x,
+      unpackHalf2x16(num.z).x,
+      unpackHalf2x16(num.w).x);
+  case SAMPLE_BITS_Float32x4:
+    return uintBitsToFloat(num);
+  }
+  return BIT_DECODE_FAIL;
+}
+
+// The bit encoding used by 64bit and 128bit staging.
+// Reverses demux_uvec4, only the integer samples are clamped into range.
+uvec4 mux_uvec4(vec4 c, uint kind) {
+  switch (kind) {
+  case SAMPLE_BITS_Int16x4:
+    return uvec4(clamp(c, 0.0, 1.0) * BITS16);
+  case SAMPLE_BITS_Float16x4:
+    return uvec4(
+      packHalf2x16(vec2(c.x, 0.0)),
+      packHalf2x16(vec2(c.y, 0.0)),
+      packHalf2x16(vec2(c.z, 0.0)),
+      packHalf2x16(vec2(c.w, 0.0)));
+  case SAMPLE_BITS_Float32x4:
+    return floatBitsToUint(c);
+  }
+  return uvec4(BIT_ENCODE_FAIL);
+}
+
+// The number of quantization steps of each component, in the canonical order
+// of demux_uint. Zero for float samples which are not quantized.
+vec4 sample_levels(uint kind) {
+  switch (kind) {
+  case SAMPLE_BITS_Int332:
+    return vec4(BITS2, BITS3, BITS3, 1.0);
+  case SAMPLE_BITS_Int233:
+    return vec4(BITS3, BITS3, BITS2, 1.0);
+  case SAMPLE_BITS_Int4x4:
+  case SAMPLE_BITS_Inti444:
+  case SAMPLE_BITS_Int444i:
+    return vec4(BITS4);
+  case SAMPLE_BITS_Int565:
+    return vec4(BITS5, BITS6, BITS5, 1.0);
+  case SAMPLE_BITS_Int16:
+  case SAMPLE_BITS_Int16x2:
+  case SAMPLE_BITS_Int16x3:
+  case SAMPLE_BITS_Int16x4:
+    return vec4(BITS16);
+  case SAMPLE_BITS_Int1010102:
+    return vec4(BITS2, BITS10, BITS10, BITS10);
+  case SAMPLE_BITS_Int2101010:
+    return vec4(BITS10, BITS10, BITS10, BITS2);
+  case SAMPLE_BITS_Int101010i:
+  case SAMPLE_BITS_Inti101010:
+    return vec4(BITS10);
+  case SAMPLE_BITS_Float16x4:
+  case SAMPLE_BITS_Float32x4:
+    return vec4(0.0);
+  }
+  return vec4(BITS8);
+}
+
+// Offset each component by an ordered (Bayer) threshold of less than one step.
+// The quantization truncates, so the expected value of the result matches the
+// unquantized input and a smooth gradient does not band.
+vec4 dither(vec4 c, ivec2 pixel, uint kind) {
+  const float bayer[16] = float[16](
+    0.0, 8.0, 2.0, 10.0,
+    12.0, 4.0, 14.0, 6.0,
+    3.0, 11.0, 1.0, 9.0,
+    15.0, 7.0, 13.0, 5.0);
+
+  if (!get_dither()) {
+    return c;
+  }
+
+  vec4 levels = sample_levels(kind);
+  ivec2 cell = pixel & 3;
+  float threshold = (bayer[cell.y * 4 + cell.x] + 0.5) / 16.0;
+  vec4 lsb = mix(vec4(0.0), 1.0 / max(levels, 1.0), greaterThan(levels, vec4(0.0)));
+  return c + threshold * lsb;
+}
+
+// Offset each component by half a step, such that the truncating quantization
+// rounds to the nearest one. The ordered dither replaces this offset with its
+// own threshold, it already has the expected value of half a step.
+vec4 quantize_nearest(vec4 c, ivec2 pixel, uint kind) {
+  if (get_dither()) {
+    return dither(c, pixel, kind);
+  }
+
+  vec4 levels = sample_levels(kind);
+  vec4 lsb = mix(vec4(0.0), 1.0 / max(levels, 1.0), greaterThan(levels, vec4(0.0)));
+  return c + 0.5 * lsb;
+}
+
 // Swap the parts into the canonical location for the color representation.
 // The order of channels in the inputs depends on the channel count, and only
 // on the input count, as normalized by the used demux_* method.
@@ -667,6 +961,8 @@ vec4 parts_normalize(vec4 components, uint parts) {
     return vec4(vec3(components.x), components.w);
   case SAMPLE_PARTS_Rgb:
   case SAMPLE_PARTS_Rgbx:
+  // Y'CbCr signals are plain scalars in order.
+  case SAMPLE_PARTS_Yuv:
     return vec4(components.xyz, 1.0);
   case SAMPLE_PARTS_Bgr:
   case SAMPLE_PARTS_Bgrx:
@@ -718,6 +1014,7 @@ vec4 parts_denormalize(vec4 c, uint parts) {
     return vec4(vec3(c.x), c.w);
   case SAMPLE_PARTS_Rgb:
   case SAMPLE_PARTS_Rgbx:
+  case SAMPLE_PARTS_Yuv:
     return vec4(c.rgb, 1.0);
   case SAMPLE_PARTS_Bgr:
   case SAMPLE_PARTS_Bgrx:
@@ -765,12 +1062,14 @@ vec4 parts_transfer(vec4 linear, uint fnk) {
   case TRANSFER_Bt2020_10bit:
   case TRANSFER_Bt2020_12bit:
   return TRANSFER_WITH_XYZ(linear, transfer_oe_bt2020_10b);
+  // Display light, where 1.0 is the absolute luminance of 10000 cd/m².
   case TRANSFER_Smpte2084:
-  return TRANSFER_WITH_XYZ(linear, transfer_oe_smpte2084);
+  return TRANSFER_WITH_XYZ(linear, transfer_eo_inv_smpte2084);
+  // Scene light, through the reference OOTF of PQ.
+  case TRANSFER_Bt2100Pq:
   return TRANSFER_WITH_XYZ(linear, transfer_oe_smpte2084);
   case TRANSFER_Bt2100Hlg:
-  // FIXME: unimplemented.
-  return linear;
+  return TRANSFER_WITH_XYZ(linear, transfer_oe_bt2100hlg);
   case TRANSFER_LabLch:
   return vec4(transfer_lab_to_lch(linear.xyz), linear.a);
   }
@@ -798,10 +1097,10 @@ vec4 parts_untransfer(vec4 nonlin, uint fnk) {
   return TRANSFER_WITH_XYZ(nonlin, transfer_eo_bt2020_10b);
   case TRANSFER_Smpte2084:
   return TRANSFER_WITH_XYZ(nonlin, transfer_eo_smpte2084);
-  return TRANSFER_WITH_XYZ(nonlin, transfer_eo_smpte2084);
+  case TRANSFER_Bt2100Pq:
+  return TRANSFER_WITH_XYZ(nonlin, transfer_oe_inv_smpte2084);
   case TRANSFER_Bt2100Hlg:
-  // FIXME: unimplemented.
-  return nonlin;
+  return TRANSFER_WITH_XYZ(nonlin, transfer_oe_inv_bt2100hlg);
   case TRANSFER_LabLch:
   return vec4(transfer_lch_to_lab(nonlin.xyz), nonlin.a);
   }
@@ -0,0 +1,79 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform TestPattern {
+    // x: the pattern, y: the size of a checker cell, zw: the size of the image.
+    ivec4 config;
+    // The two colors of the checker board.
+    vec4 color_a;
+    vec4 color_b;
+    // x: the frequency of a zone plate at the edge.
+    vec4 params;
+} u_pattern;
+
+const int PATTERN_CHECKER = 0;
+const int PATTERN_COLOR_BARS = 1;
+const int PATTERN_ZONE_PLATE = 2;
+
+const float PI = 3.14159265358979;
+
+vec4 checker(ivec2 pos) {
+    const ivec2 cell = pos / u_pattern.config.y;
+    return (cell.x + cell.y) % 2 == 0 ? u_pattern.color_a : u_pattern.color_b;
+}
+
+// Bars in the order of SMPTE color bars: 75% white, yellow, cyan, green,
+// magenta, red and blue. Below are the reversed castellations, and a bottom
+// strip of black, full white and black again.
+vec4 color_bars(ivec2 pos) {
+    const vec2 rel = (vec2(pos) + 0.5) / vec2(u_pattern.config.zw);
+    const int bar = min(int(rel.x * 7.0), 6);
+
+    const vec3 BARS[7] = vec3[](
+        vec3(0.75, 0.75, 0.75),
+        vec3(0.75, 0.75, 0.0),
+        vec3(0.0, 0.75, 0.75),
+        vec3(0.0, 0.75, 0.0),
+        vec3(0.75, 0.0, 0.75),
+        vec3(0.75, 0.0, 0.0),
+        vec3(0.0, 0.0, 0.75)
+    );
+
+    if (rel.y < 0.67) {
+        return vec4(BARS[bar], 1.0);
+    } else if (rel.y < 0.75) {
+        // Every other bar is black, the others are mirrored.
+        return bar % 2 == 1 ? vec4(0.0, 0.0, 0.0, 1.0) : vec4(BARS[6 - bar], 1.0);
+    } else {
+        const int third = min(int(rel.x * 3.0), 2);
+        return third == 1 ? vec4(1.0) : vec4(0.0, 0.0, 0.0, 1.0);
+    }
+}
+
+// A circular zone plate whose frequency grows linearly from the center and
+// reaches the configured frequency at the edge of the image.
+vec4 zone_plate(ivec2 pos) {
+    const vec2 size = vec2(u_pattern.config.zw);
+    const vec2 d = vec2(pos) + 0.5 - size / 2.0;
+    const float radius = max(size.x, size.y) / 2.0;
+
+    const float phase = PI * u_pattern.params.x * dot(d, d) / radius;
+    return vec4(vec3(0.5 + 0.5 * cos(phase)), 1.0);
+}
+
+void main() {
+    const ivec2 pos = ivec2(gl_FragCoord.xy);
+
+    switch (u_pattern.config.x) {
+    case PATTERN_CHECKER:
+        f_color = checker(pos);
+        break;
+    case PATTERN_COLOR_BARS:
+        f_color = color_bars(pos);
+        break;
+    case PATTERN_ZONE_PLATE:
+        f_color = zone_plate(pos);
+        break;
+    }
+}
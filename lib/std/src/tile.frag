@@ -0,0 +1,17 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform Tile {
+    // xy: the number of repetitions of the source along width and height.
+    vec4 repeat;
+} u_tile;
+
+// Repeat the source, the sampler's address mode wraps or mirrors the coordinates. Any fraction of
+// a tile at the right and bottom edge is sampled like every other tile.
+void main() {
+    f_color = texture(sampler2D(in_texture, texture_sampler), uv * u_tile.repeat.xy);
+}
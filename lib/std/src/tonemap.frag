@@ -0,0 +1,38 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D in_texture;
+
+layout (set = 2, binding = 0) uniform ToneMap {
+    // x: the radiance mapped to white, for the extended Reinhard operator.
+    vec4 params;
+} u_tonemap;
+
+#ifndef TONEMAP_REINHARD_AS_MAIN
+#define TONEMAP_REINHARD_AS_MAIN tonemap_reinhard
+#endif
+#ifndef TONEMAP_ACES_AS_MAIN
+#define TONEMAP_ACES_AS_MAIN tonemap_aces
+#endif
+
+void TONEMAP_REINHARD_AS_MAIN() {
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+    const float white = u_tonemap.params.x;
+
+    const vec3 rgb = max(rgba.rgb, vec3(0.0));
+    const vec3 mapped = rgb * (vec3(1.0) + rgb / (white * white)) / (vec3(1.0) + rgb);
+
+    f_color = vec4(clamp(mapped, 0.0, 1.0), rgba.a);
+}
+
+void TONEMAP_ACES_AS_MAIN() {
+    const vec4 rgba = texture(sampler2D(in_texture, texture_sampler), uv);
+
+    // The curve fitted by Krzysztof Narkowicz to the ACES reference transforms.
+    const vec3 x = max(rgba.rgb, vec3(0.0));
+    const vec3 mapped = (x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14);
+
+    f_color = vec4(clamp(mapped, 0.0, 1.0), rgba.a);
+}
@@ -0,0 +1,49 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+// Keep in sync with `shaders::voronoi::MAX_POINTS`.
+#define MAX_POINTS 256
+
+layout (set = 1, binding = 0) uniform Voronoi {
+    // x: the number of seed points.
+    // y: the metric of distances.
+    // zw: the size of the output image, in texels.
+    ivec4 config;
+    // The positions of seed points in xy, relative to the image size.
+    vec4 points[MAX_POINTS];
+    // The color of the cell of each seed point.
+    vec4 colors[MAX_POINTS];
+} u_voronoi;
+
+const int METRIC_EUCLIDEAN = 0;
+const int METRIC_MANHATTAN = 1;
+
+float metric(vec2 delta) {
+    if (u_voronoi.config.y == METRIC_MANHATTAN) {
+        return abs(delta.x) + abs(delta.y);
+    }
+
+    // Only compared, so no need for the square root.
+    return dot(delta, delta);
+}
+
+void main() {
+    // Measure in texels, such that cells are not stretched in non-square images.
+    const vec2 size = vec2(u_voronoi.config.zw);
+    const vec2 position = uv * size;
+    const int count = min(u_voronoi.config.x, MAX_POINTS);
+
+    int nearest = 0;
+    float best = metric(u_voronoi.points[0].xy * size - position);
+
+    for (int i = 1; i < count; i++) {
+        const float distance = metric(u_voronoi.points[i].xy * size - position);
+        if (distance < best) {
+            best = distance;
+            nearest = i;
+        }
+    }
+
+    f_color = u_voronoi.colors[nearest];
+}
@@ -0,0 +1,20 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform sampler texture_sampler;
+layout (set = 1, binding = 1) uniform texture2D lhs;
+// The estimated illuminant, a single texel.
+layout (set = 1, binding = 2) uniform texture2D rhs;
+
+void main() {
+    vec4 rgba = texture(sampler2D(lhs, texture_sampler), uv).rgba;
+    vec3 estimate = texelFetch(sampler2D(rhs, texture_sampler), ivec2(0, 0), 0).rgb;
+
+    // Gray world: scale each channel such that the estimate becomes neutral
+    // while keeping its average intensity.
+    float gray = dot(estimate, vec3(1.0 / 3.0));
+    vec3 gains = vec3(gray) / max(estimate, vec3(1e-6));
+
+    f_color = vec4(rgba.rgb * gains, rgba.a);
+}
@@ -0,0 +1,75 @@
+#version 450
+layout (location = 0) in vec2 uv;
+layout (location = 0) out vec4 f_color;
+
+layout (set = 1, binding = 0) uniform Worley {
+    // The number of cells along width and height.
+    vec2 cells;
+    // How far feature points are displaced from the cell center, relative to the cell.
+    float jitter;
+    // The distance function between points.
+    uint distance_fn;
+    // Selects one of many uncorrelated feature point placements.
+    uint seed;
+} u_worley;
+
+const uint DISTANCE_EUCLIDEAN = 0;
+const uint DISTANCE_MANHATTAN = 1;
+const uint DISTANCE_CHEBYSHEV = 2;
+
+// From https://jcgt.org/published/0009/03/02/paper.pdf
+uvec4 pcg4d(uvec4 v)
+{
+    v = v * 1664525u + 1013904223u;
+    v.x += v.y*v.w; v.y += v.z*v.x; v.z += v.x*v.y; v.w += v.y*v.z;
+    v = v ^ (v >> 16u);
+    v.x += v.y*v.w; v.y += v.z*v.x; v.z += v.x*v.y; v.w += v.y*v.z;
+    return v;
+}
+
+vec4 hash(uvec2 v) {
+    const float MAX = float(0xFFFFFFFFu);
+    uvec4 hashed = pcg4d(uvec4(v, u_worley.seed, 0));
+    return vec4(hashed) / MAX;
+}
+
+float distance_to(vec2 d) {
+    switch (u_worley.distance_fn) {
+    case DISTANCE_MANHATTAN:
+        return abs(d.x) + abs(d.y);
+    case DISTANCE_CHEBYSHEV:
+        return max(abs(d.x), abs(d.y));
+    }
+
+    return length(d);
+}
+
+// Cellular noise: one feature point in each cell, the distances to the nearest
+// two of them in cell units and a random value identifying the nearest cell.
+void main() {
+    const vec2 pt = uv * u_worley.cells;
+    const ivec2 cell = ivec2(floor(pt));
+
+    float f1 = 1e9;
+    float f2 = 1e9;
+    float id = 0.0;
+
+    for (int dy = -1; dy <= 1; dy++) {
+        for (int dx = -1; dx <= 1; dx++) {
+            const ivec2 neighbor = cell + ivec2(dx, dy);
+            const vec4 h = hash(uvec2(neighbor));
+            const vec2 feature = vec2(neighbor) + 0.5 + (h.xy - 0.5) * u_worley.jitter;
+            const float d = distance_to(feature - pt);
+
+            if (d < f1) {
+                f2 = f1;
+                f1 = d;
+                id = h.z;
+            } else if (d < f2) {
+                f2 = d;
+            }
+        }
+    }
+
+    f_color = vec4(f1, f2, f2 - f1, id);
+}
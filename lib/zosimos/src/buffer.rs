@@ -1,6 +1,6 @@
 //! Defines layout and buffer of our images.
 pub use image_canvas::{
-    color::{Color, ColorChannel, Transfer, Whitepoint},
+    color::{Color, ColorChannel, Primaries, Transfer, Whitepoint},
     layout::{Block, CanvasLayout, RowLayoutDescription, SampleBits, SampleParts, Texel},
     Canvas,
 };
@@ -39,6 +39,7 @@ pub struct Descriptor {
 /// This can only make sense with internal knowledge about how we remap color representations into
 /// the texture during the Staging phase of loading a color image.
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[repr(u8)]
 pub(crate) enum ChannelPosition {
     First = 0,
@@ -54,6 +55,19 @@ pub(crate) trait TexelExt {
 
 impl TexelExt for Texel {
     fn channel_texel(&self, ch: ColorChannel) -> Option<Texel> {
+        // The alpha plane on its own is a plain alpha texel, regardless of the color model it was
+        // taken from. It is staged from and into the last component, as in its source.
+        if ch == ColorChannel::Alpha {
+            if !self.parts.color_channels().contains(&Some(ch)) {
+                return None;
+            }
+
+            return Some(Texel {
+                parts: SampleParts::A,
+                ..*self
+            });
+        }
+
         Some(Texel {
             parts: self.parts.with_channel(ch)?,
             ..*self
@@ -114,6 +128,31 @@ impl Descriptor {
         Some(this)
     }
 
+    /// Describe an image of half float texels, in linear light of the sRGB primaries.
+    ///
+    /// This is the recommended intermediate for high dynamic range, such as the radiance passed
+    /// to [`CommandBuffer::tonemap`]. Values above `1.0` are kept and the texels are used on the
+    /// device as they are, without a staging pass.
+    ///
+    /// [`CommandBuffer::tonemap`]: crate::command::CommandBuffer::tonemap
+    pub fn with_linear_half(width: u32, height: u32) -> Option<Self> {
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float16x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let mut color = Color::SRGB;
+        if let Color::Rgb { transfer, .. } = &mut color {
+            *transfer = Transfer::Linear;
+        }
+
+        Some(Descriptor {
+            color,
+            ..Self::with_texel(texel, width, height)?
+        })
+    }
+
     /// Create the highly row-aligned layout with the same row bytes.
     ///
     /// This can overflow as it uses more bytes than the underlying flexible CPU layout. Returns
@@ -181,6 +220,24 @@ impl Descriptor {
     pub fn size(&self) -> (u32, u32) {
         (self.layout.width, self.layout.height)
     }
+
+    /// The same descriptor with a different number of texels in width and height.
+    ///
+    /// Returns `None` if the resulting layout can not be represented.
+    pub(crate) fn with_size(&self, width: u32, height: u32) -> Option<Self> {
+        let this = Descriptor {
+            layout: ByteLayout {
+                width,
+                height,
+                row_stride: u64::from(self.layout.texel_stride) * u64::from(width),
+                ..self.layout
+            },
+            ..self.clone()
+        };
+
+        let _ = this.try_to_canvas()?;
+        Some(this)
+    }
 }
 
 impl ByteLayout {
@@ -296,6 +353,31 @@ impl Descriptor {
                 })?;
                 Some(image::DynamicImage::ImageRgba16(buffer))
             },
+            Texel {
+                block: Block::Pixel,
+                parts: P::RgbA,
+                bits: B::Float16x4,
+            } => |width, height, source| {
+                let data = source
+                    .chunks_exact(2)
+                    .map(|half| f16_to_f32(u16::from_ne_bytes([half[0], half[1]])))
+                    .collect();
+                let buffer = image::ImageBuffer::from_vec(width, height, data)?;
+                Some(image::DynamicImage::ImageRgba32F(buffer))
+            },
+            Texel {
+                block: Block::Pixel,
+                parts: P::RgbA,
+                bits: B::Float32x4,
+            } => |width, height, source| {
+                let source = &source[..(source.len() / 4) * 4];
+                let buffer = image::ImageBuffer::from_vec(width, height, {
+                    let mut data = vec![0f32; source.len() / 4];
+                    bytemuck::cast_slice_mut(&mut data).copy_from_slice(source);
+                    data
+                })?;
+                Some(image::DynamicImage::ImageRgba32F(buffer))
+            },
             _ => return None,
         })
     }
@@ -343,6 +425,16 @@ impl Descriptor {
                 bits: SampleBits::UInt16x4,
                 parts: SampleParts::RgbA,
             },
+            ImageRgb32F(_) => Texel {
+                block: Block::Pixel,
+                bits: SampleBits::Float32x3,
+                parts: SampleParts::Rgb,
+            },
+            ImageRgba32F(_) => Texel {
+                block: Block::Pixel,
+                bits: SampleBits::Float32x4,
+                parts: SampleParts::RgbA,
+            },
             _ => unreachable!("Promise, we match the rest"),
         }
     }
@@ -436,3 +528,59 @@ impl From<&'_ CanvasLayout> for Descriptor {
         }
     }
 }
+
+/// Convert a half float, given by its bits, to single precision. This is exact.
+pub(crate) fn f16_to_f32(half: u16) -> f32 {
+    let sign = u32::from(half & 0x8000) << 16;
+    let exponent = u32::from(half >> 10) & 0x1f;
+    let mantissa = u32::from(half & 0x3ff);
+
+    let magnitude = match exponent {
+        // Zero and subnormals, these are normal numbers in single precision.
+        0 => (mantissa as f32 / 16_777_216.0).to_bits(),
+        // Infinity and NaN.
+        0x1f => 0x7f80_0000 | (mantissa << 13),
+        _ => ((exponent + 112) << 23) | (mantissa << 13),
+    };
+
+    f32::from_bits(sign | magnitude)
+}
+
+/// Convert to the bits of a half float, rounding to nearest even.
+///
+/// Values beyond the range of half floats become infinite, values below half of the smallest
+/// subnormal become zero.
+pub(crate) fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32;
+    let mantissa = bits & 0x7f_ffff;
+
+    if exponent == 0xff {
+        let nan = if mantissa != 0 { 0x200 } else { 0 };
+        return sign | 0x7c00 | nan;
+    }
+
+    let rounded = |value: u32, shift: u32| {
+        let rest = value & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let truncated = value >> shift;
+        let round_up = rest > halfway || (rest == halfway && truncated & 1 == 1);
+        truncated + u32::from(round_up)
+    };
+
+    let half_exponent = exponent - 112;
+    if half_exponent >= 0x1f {
+        sign | 0x7c00
+    } else if half_exponent > 0 {
+        // A carry of the rounding correctly moves into the exponent, up to infinity.
+        let value = ((half_exponent as u32) << 23) | mantissa;
+        sign | rounded(value, 13) as u16
+    } else if half_exponent >= -10 {
+        // Subnormal, with the implicit leading bit of the mantissa.
+        let value = mantissa | 0x80_0000;
+        sign | rounded(value, (14 - half_exponent) as u32) as u16
+    } else {
+        sign
+    }
+}
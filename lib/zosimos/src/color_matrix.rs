@@ -10,6 +10,7 @@ pub(crate) struct ColMatrix(pub(crate) [[f32; 3]; 3]);
 
 /// A row major matrix.
 #[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) struct RowMatrix(pub(crate) [f32; 9]);
 
 #[rustfmt::skip]
@@ -174,6 +175,22 @@ impl RowMatrix {
     pub(crate) fn mul_vec(&self, vec: [f32; 3]) -> [f32; 3] {
         self.multiply_column(vec)
     }
+
+    /// The sharpened cone responses of Bradford, from XYZ.
+    pub(crate) const BRADFORD: RowMatrix = RowMatrix::new([
+         0.8951,  0.2664, -0.1614,
+        -0.7502,  1.7135,  0.0367,
+         0.0389, -0.0685,  1.0296,
+    ]);
+
+    /// The Bradford chromatic adaptation of XYZ, between two whitepoints given in XYZ.
+    pub(crate) fn bradford(source: [f32; 3], target: [f32; 3]) -> Self {
+        let [ls, ms, ss] = Self::BRADFORD.multiply_column(source);
+        let [lt, mt, st] = Self::BRADFORD.multiply_column(target);
+        let scaled = RowMatrix::diag(lt / ls, mt / ms, st / ss).multiply_right(Self::BRADFORD.into());
+
+        Self::BRADFORD.inv().multiply_right(scaled).into()
+    }
 }
 
 impl From<ColMatrix> for RowMatrix {
@@ -194,3 +211,21 @@ fn matrix_ops() {
 
     assert_eq!(mat, mat.transpose().transpose());
 }
+
+#[test]
+fn bradford_maps_whitepoints() {
+    let d65 = [0.95047, 1.0, 1.08883];
+    let d50 = [0.96422, 1.0, 0.82521];
+
+    let adapt = RowMatrix::bradford(d65, d50);
+    let adapted = adapt.multiply_column(d65);
+
+    for (a, b) in adapted.into_iter().zip(d50) {
+        assert!((a - b).abs() < 1e-5, "{adapted:?}");
+    }
+
+    let back = RowMatrix::bradford(d50, d65).multiply_column(adapted);
+    for (a, b) in back.into_iter().zip(d65) {
+        assert!((a - b).abs() < 1e-5, "{back:?}");
+    }
+}
@@ -1,34 +1,45 @@
+mod cvd;
 mod dynamic;
+pub mod knob;
+#[cfg(feature = "serde")]
+mod serialize;
 
+pub use self::cvd::{Cvd, CvdKind};
 pub use self::dynamic::{ShaderCommand, ShaderData, ShaderSource};
+pub use self::knob::{BypassHandle, KnobHandle, KnobLayout, KnobOp, KnobValue};
 
 use crate::buffer::{ByteLayout, CanvasLayout, ChannelPosition, Descriptor, TexelExt};
 use crate::color_matrix::RowMatrix;
 use crate::pool::PoolImage;
 use crate::program::{
-    BufferWrite, ByteBufferAssignment, CallBinding, CompileError, Frame, Function, FunctionLinked,
-    High, ImageBufferAssignment, ImageBufferPlan, ImageDescriptor, Initializer, Knob, KnobUser,
-    ParameterizedFragment, Program, QuadTarget, RegisterAssignment, Target, Texture,
+    BufferInitContent, BufferWrite, ByteBufferAssignment, CallBinding, CompileError, Frame,
+    Function, FunctionLinked, High, ImageBufferAssignment, ImageBufferPlan, ImageDescriptor,
+    Initializer, Knob, KnobUser, ParameterizedFragment, Program, QuadTarget, RegisterAssignment,
+    Target, Texture,
 };
 
 pub use crate::shaders::bilinear::ShaderData as Bilinear;
 pub use crate::shaders::distribution_normal2d::ShaderData as DistributionNormal2d;
 pub use crate::shaders::fractal_noise::ShaderData as FractalNoise;
+pub use crate::shaders::voronoi::Metric as VoronoiMetric;
+pub use crate::shaders::worley::Distance as WorleyDistance;
+pub use crate::shaders::worley::ShaderData as WorleyParams;
 
 use crate::shaders::{
     self, FragmentShaderInvocation, PaintOnTopKind, ShaderInvocation, ShadersCore, ShadersStd,
 };
 
-use image_canvas::color::{Color, ColorChannel, Whitepoint};
-use image_canvas::layout::{SampleParts, Texel};
+use image_canvas::color::{Color, ColorChannel, Primaries, Transfer, Whitepoint};
+use image_canvas::layout::{Block, SampleBits, SampleParts, Texel};
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 /// A reference to one particular value.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Register(pub(crate) usize);
 
 /// The standard library, and others in time..
@@ -38,6 +49,8 @@ pub struct Register(pub(crate) usize);
 pub struct Linker {
     pub core: ShadersCore,
     pub std: ShadersStd,
+    /// Command buffers defining functions by their name, see [`Linker::define`].
+    symbols: HashMap<String, Arc<CommandBuffer>>,
 }
 
 /// One linear sequence of instructions.
@@ -65,33 +78,54 @@ pub struct CommandBuffer {
     tys: Vec<GenericDescriptor>,
     /// Commands that consume a statically initialized buffer, which we can adjust at launch time.
     knobs: HashMap<Register, KnobKind>,
+    /// Commands whose integer result is dithered when it is quantized to its texel.
+    dithered: HashSet<Register>,
+    /// Results that may be painted into the texture of an input, see [`Self::output_in_place`].
+    in_place: HashMap<Register, Register>,
 }
 
 /// Refers to a generic argument declaration.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct GenericVar(pub(crate) usize);
 
 /// Refers to the descriptor introduced by a generic argument or a derived var.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct DescriptorVar(pub(crate) usize);
 
 /// Refers to the function introduced by its signature.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct FunctionVar(pub(crate) usize);
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct CommandSignature {
+    /// The symbol by which the function is resolved, see [`CommandBuffer::function_named`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    name: Option<String>,
     vars: Vec<TyVarBounds>,
     input: Vec<GenericDescriptor>,
     output: Vec<GenericDescriptor>,
+    /// The buffers passed after the images, see [`CommandBuffer::buffer_input`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    buffer_input: Vec<GenericBuffer>,
+    /// The buffers returned after the images, see [`CommandBuffer::buffer_result`].
+    #[cfg_attr(feature = "serde", serde(default))]
+    buffer_output: Vec<GenericBuffer>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct GenericDescriptor {
     size: Generic<(u32, u32)>,
+    #[cfg_attr(feature = "serde", serde(with = "serialize::chroma"))]
     chroma: Generic<(Texel, Color)>,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct GenericBuffer {
     /// The size of this buffer, where statically known.
     ///
@@ -102,12 +136,14 @@ pub struct GenericBuffer {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Generic<T> {
     Concrete(T),
     Generic(GenericVar),
 }
 
 /// Describe the value class of a register, and its precise type.
+#[derive(Clone, Copy, Debug)]
 #[non_exhaustive]
 pub enum RegisterDescription<'cmd> {
     /// This register is not a value operation.
@@ -118,7 +154,46 @@ pub enum RegisterDescription<'cmd> {
     Buffer(&'cmd GenericBuffer),
 }
 
+/// The operation defining a register, see [`CommandBuffer::op_kind`].
+///
+/// This names the operation and the registers it reads, but not its parameters. Operations on
+/// buffers and user-supplied shaders are summarized by the same variants as those on images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum OpKind<'cmd> {
+    /// An input of the command buffer.
+    Input,
+    /// An output of the command buffer.
+    Output { src: Register },
+    /// A render target of the command buffer.
+    Render { src: Register },
+    /// An operation without operands.
+    Construct { name: &'static str },
+    /// An operation with one operand.
+    Unary { name: &'static str, src: Register },
+    /// An operation with two operands.
+    Binary {
+        name: &'static str,
+        lhs: Register,
+        rhs: Register,
+    },
+    /// The invocation of a function, its results are defined by [`OpKind::InvokedResult`].
+    Invoke {
+        function: FunctionVar,
+        arguments: &'cmd [Register],
+    },
+    /// A result of an invocation.
+    InvokedResult { invocation: Register },
+}
+
+/// Where the registers of a command buffer are found after [`CommandBuffer::prune`].
 #[derive(Clone, Debug)]
+pub struct RegisterMap {
+    registers: Vec<Option<Register>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 enum Op {
     /// i := in()
     Input { desc: GenericDescriptor },
@@ -165,6 +240,9 @@ enum Op {
         arguments: Vec<Register>,
         results: Vec<Register>,
         generics: Vec<GenericDescriptor>,
+        /// Apply the function this often, each time to the previous result. See
+        /// [`CommandBuffer::repeat`].
+        repeat: Option<u32>,
     },
     /// The specific return value of a function.
     InvokedResult {
@@ -173,6 +251,15 @@ enum Op {
         /// The result's type monomorphized as called.
         desc: GenericDescriptor,
     },
+    /// The specific buffer returned by a function.
+    InvokedBuffer {
+        /// Where is this register initialized? Must be after its definition.
+        invocation: Register,
+        desc: GenericBuffer,
+    },
+    /// i := in()
+    /// where type(i) = desc, a buffer passed by the caller.
+    BufferInput { desc: GenericBuffer },
     BufferInit {
         op: BufferInitOp,
         desc: GenericBuffer,
@@ -190,31 +277,81 @@ enum Op {
     },
 }
 
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 enum KnobKind {
-    Runtime,
+    Runtime(knob::KnobRecord),
     Buffer {
         buffer: Register,
         range: core::ops::Range<u64>,
     },
 }
 
-#[derive(Clone)]
-struct TyVarBounds {}
+/// The bounds declared on a generic parameter.
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+struct TyVarBounds {
+    bounds: Vec<GenericBound>,
+}
 
 /// Declare a fresh generic declaration parameter.
+#[derive(Default)]
 pub struct GenericDeclaration<'lt> {
+    /// The bounds on the chroma, if it is fresh, or checked against the given chroma otherwise.
     pub bounds: &'lt [GenericBound],
+    /// The size of the declared descriptor.
+    pub size: SizeSpec,
+    /// The texel and color of the declared descriptor.
+    pub chroma: ChromaSpec,
+}
+
+/// The size of a descriptor in a generic declaration.
+#[derive(Clone, Debug, Default)]
+pub enum SizeSpec {
+    /// The size is determined by the new generic parameter.
+    #[default]
+    Fresh,
+    /// The size is the one of another descriptor var, generic or not.
+    Shared(DescriptorVar),
+    /// The size is fixed to a width and height.
+    Concrete(u32, u32),
+}
+
+/// The texel and color of a descriptor in a generic declaration.
+#[derive(Clone, Debug, Default)]
+pub enum ChromaSpec {
+    /// The texel and color are determined by the new generic parameter.
+    #[default]
+    Fresh,
+    /// The texel and color are the ones of another descriptor var, generic or not.
+    Shared(DescriptorVar),
+    /// The texel and color are fixed.
+    Concrete(Texel, Color),
 }
 
 /// Declare a new descriptor type based on a generic bound.
 pub struct DescriptorDerivation {
     pub base: DescriptorVar,
     pub size: Option<(u32, u32)>,
+    /// Replace the texel and color, keeping the size of the base.
+    pub chroma: Option<(Texel, Color)>,
 }
 
-pub enum GenericBound {}
+/// A constraint on the descriptors which can instantiate a generic parameter.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GenericBound {
+    /// The texel has exactly this number of bytes, see [`SampleBits::bytes`].
+    ///
+    /// This proves enough about the texel to transmute between the generic and a concrete texel
+    /// of the same size, see [`CommandBuffer::transmute_generic`].
+    TexelBytes(u8),
+    /// The texel and color map to a format which can be rendered to.
+    Renderable,
+}
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 enum OperandDynKind {
     Construct,
     Unary(Register),
@@ -226,39 +363,64 @@ pub struct InvocationArguments<'lt> {
     pub arguments: &'lt [Register],
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum ConstructOp {
     Bilinear(Bilinear),
     /// A 2d normal distribution.
     DistributionNormal(DistributionNormal2d),
     /// Fractal noise
     DistributionNoise(FractalNoise),
+    /// Fractal noise of simplex noise octaves.
+    DistributionSimplexNoise(FractalNoise),
+    /// Cellular noise.
+    DistributionWorley(WorleyParams),
+    /// Colors of the nearest seed point.
+    Voronoi(shaders::voronoi::ShaderData),
+    /// A synthetic test image.
+    TestPattern(shaders::test_pattern::ShaderData),
     /// A color to repeat on pixels.
     Solid([f32; 4]),
-    /// An existing buffer to use.
-    FromBuffer(Register),
+    /// A piecewise linear interpolation between color stops.
+    ColorRamp(shaders::color_ramp::ShaderData),
+    /// A Catmull-Rom spline through control colors.
+    SplineRamp(shaders::spline_ramp::ShaderData),
+    /// An existing buffer to use, from a byte offset.
+    FromBuffer(Register, u64),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum BufferInitOp {
     FromData {
         placement: core::ops::Range<usize>,
         data: Arc<[u8]>,
     },
+    /// Repeat an aligned pattern over the whole buffer.
+    Splat { pattern: Arc<[u8]> },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum BufferUnaryOp {
     FromImage {},
+    /// The bytes of the source buffer, from an offset for the length of the result.
+    Slice {
+        start: u64,
+    },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum BufferBinaryOp {
     /// Combine two buffers by overlaying one over the contents of the other, at a fixed location.
     Overlay { at: u64 },
+    /// Combine two buffers by appending the contents of one to those of the other.
+    Concat {},
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum UnaryOp {
     /// Op = id
     Crop(Rectangle),
@@ -268,6 +430,9 @@ pub(crate) enum UnaryOp {
     /// Op(T) = T[.color=select(channel, color)]
     #[allow(dead_code)] // "See discussion in its usage. The selection happens in the shader."
     Extract { channel: ChannelPosition },
+    /// Op(T) = T[.texel=Luma]
+    /// The luminance is a linear combination of the linear RGB channels.
+    Luminance { weights: [f32; 3] },
     /// Op(T) = T[.whitepoint=target]
     /// This is a partial method for CIE XYZ-ish color spaces. Note that ICC requires adaptation to
     /// D50 for example as the reference color space.
@@ -278,22 +443,123 @@ pub(crate) enum UnaryOp {
     /// And the byte width of new texel must be consistent with the current byte width.
     Transmute,
     /// Op(T) = T
-    Derivative(Derivative),
+    Derivative(BorderedDerivative),
+    /// Op(T) = T'
+    /// The magnitude and angle of the luma gradient, computed with the weights as channels.
+    GradientPolar {
+        method: DerivativeMethod,
+        weights: [f32; 4],
+    },
+    /// Op(T) = T
+    /// The image plus a multiple of its Laplacian.
+    Sharpen(shaders::sharpen::ShaderData),
+    /// Op(T) = T
+    /// The directional derivative of luma, as a relief.
+    Emboss(shaders::emboss::ShaderData),
+    /// Op(T) = T
+    /// A linear transformation of the linear color channels.
+    LinearColor(RowMatrix),
+    /// Op(T) = T'
+    /// An affine transformation of the color channels, as stored.
+    AffineColor { matrix: RowMatrix, offset: [f32; 3] },
+    /// Op(T) = T'
+    /// Reduce the chroma of linear RGB colors into their gamut, in Oklch.
+    GamutMap {
+        mapping: GamutMapping,
+        to_xyz: RowMatrix,
+    },
+    /// Op(T) = T'
+    /// Naive CMYK from electrical RGB, replacing a fraction of gray with black.
+    CmykEncode { black: f32 },
+    /// Op(T) = T'
+    /// Electrical RGB from naive CMYK.
+    CmykDecode,
+    /// Op(T) = T
+    /// A conformal map of the chroma plane in Oklab.
+    AbTransform(shaders::ab_transform::ShaderData),
+    /// Op(T) = T, or Op(T) = T[.texel=Luma] for the matte alone.
+    /// Transparency by the chroma distance to a key color in Oklab.
+    ChromaKey(shaders::chroma_key::ShaderData),
+    /// Op(T) = T
+    /// Non-local means, weighting texels by the similarity of their surrounding patches.
+    DenoiseNlm(shaders::denoise_nlm::ShaderData),
+    /// Op(T) = T'
+    /// Interpolate the full color of each texel of a mosaic.
+    Demosaic(Cfa),
+    /// Op(T) = T
+    /// Sample each texel at its coordinate under a lens distortion.
+    LensUndistort(Distortion),
+    /// Op(T) = T
+    /// Each color channel sampled at its own radial scale.
+    ChromaticAberration(ChromaticAberration),
+    /// Op(T) = T
+    /// Where T has linear transfer or floating point texels.
+    ToneMap(ToneMap),
+    /// Op(T) = T[.width=ceil(width/2), .height=ceil(height/2)]
+    /// One step in the chain of a reduction.
+    Reduce(shaders::reduce::ShaderData),
+    /// Op(T) = C[.width=bins]
+    /// Where C holds, for each row of T, the count of each bin as a little-endian `u32` texel.
+    HistogramRows(shaders::histogram::ShaderData),
+    /// Op(C) = C[.height=1]
+    /// Sum the counts of all rows.
+    HistogramSum,
+    /// Op(T) = C[.width=bins, .height=tiles]
+    /// Where C holds the count of each bin for each tile, as in `HistogramRows`.
+    ClaheHistogram(shaders::clahe::ShaderData),
+    /// Op(C) = M
+    /// Where M holds the equalizing mapping of each bin for each tile.
+    ClaheMapping(shaders::clahe::ShaderData),
+    /// Op(T) = U
+    /// Where U has floating point texels.
+    /// One step of a prefix sum, adding the texel at an offset.
+    PrefixSum { offset: (u32, u32) },
+    /// Op(T) = T[.width or .height changed]
+    /// One separable pass of resampling along an axis.
+    Resample(shaders::resample::ShaderData),
+    /// Op(T) = T[.width, .height changed]
+    /// Repeat the image, possibly mirroring every other repetition.
+    Tile { repeat: [f32; 2], mirrored: bool },
+    /// Op(T) = T[.width=ceil(width/factor), .height=ceil(height/factor)]
+    /// One pass of averaging blocks of texels.
+    Downsample(shaders::downsample::ShaderData),
+    /// Op(T) = T[.width, .height changed]
+    /// Place the image at an offset, determining all other texels by a border mode.
+    Pad(shaders::pad::ShaderData),
+    /// Op(T) = U
+    /// Where U has floating point texels.
+    /// One separable pass of a gaussian blur along an axis.
+    GaussianBlur(shaders::gaussian::ShaderData),
+    /// Op(T) = U
+    /// Where U has floating point texels.
+    /// The part of each texel above a brightness threshold.
+    BrightPass { threshold: f32, intensity: f32 },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum BinaryOp {
     /// Op = id
     Affine(Affine),
+    /// Op = id
+    Perspective(Homography),
     /// Op[T, U] = T
     /// where T = U
-    Inscribe { placement: Rectangle },
+    Inscribe {
+        placement: Rectangle,
+        /// The part of U which is painted onto the placement.
+        selection: Rectangle,
+    },
     /// Replace a channel T with U itself.
     /// Op[T, U] = T
     /// where select(channel, T.color) = U.color
     Inject {
         channel: ChannelPosition,
+        #[cfg_attr(feature = "serde", serde(with = "serialize::texel"))]
         from_channels: Texel,
+        /// The rectangle of T to which U is stretched, all of T if `None`.
+        placement: Option<Rectangle>,
+        sampling: AffineSample,
     },
     /// Sample from a palette based on the color value of another image.
     /// Op[T, U] = T
@@ -302,6 +568,45 @@ pub(crate) enum BinaryOp {
     ///
     /// Op[T, U] = T
     GainMap(GainMap),
+    /// Fuse one more exposure into an accumulated fusion.
+    ///
+    /// Op[T, U] = T
+    /// where U is the exposure, in the same color as T.
+    FusionStep(shaders::exposure_fusion::ShaderData),
+    /// Balance the colors of an image with an illuminant estimate.
+    ///
+    /// Op[T, U] = T
+    /// where U is a single texel of the same color as T.
+    WhiteBalance(AwbMethod),
+    /// Equalize an image with the mapping functions of its tiles.
+    ///
+    /// Op[T, M] = T
+    ClaheApply(shaders::clahe::ShaderData),
+    /// The squared difference in each channel.
+    ///
+    /// Op[T, T] = U
+    /// where U has floating point texels.
+    SquaredDifference,
+    /// The structural similarity of the luminance around each texel.
+    ///
+    /// Op[T, T] = U
+    /// where U has floating point texels.
+    Ssim(shaders::local_statistics::ShaderData),
+    /// Add, or subtract, the bi-linear expansion of a coarser level of a pyramid.
+    ///
+    /// Op[T, U] = V
+    /// where U is the coarser level and V has the size of T.
+    PyramidExpand { subtract: bool },
+    /// Composite an image over a shadow color, covering the alpha of a silhouette.
+    ///
+    /// Op[T, U] = T
+    /// where only the alpha channel of U is used.
+    DropShadow([f32; 4]),
+    /// Add a glow onto an image.
+    ///
+    /// Op[T, U] = T
+    /// where U is the glow summed over pyramid levels.
+    Bloom(shaders::bloom::ShaderData),
 }
 
 /// A rectangle in `u32` space.
@@ -309,6 +614,7 @@ pub(crate) enum BinaryOp {
 /// rectangle where the order is not correct is interpreted as empty. This has the advantage of
 /// simplifying certain operations that would otherwise need to check for correctness.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Rectangle {
     pub x: u32,
     pub y: u32,
@@ -316,6 +622,17 @@ pub struct Rectangle {
     pub max_y: u32,
 }
 
+/// A rectangle in `i32` space, which may extend beyond the origin.
+/// Interpreted as [`Rectangle`], minimum inclusive and maximum exclusive, and empty when the order
+/// is not correct.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SignedRectangle {
+    pub x: i32,
+    pub y: i32,
+    pub max_x: i32,
+    pub max_y: i32,
+}
+
 #[derive(Clone, Copy)]
 #[non_exhaustive]
 pub enum Blend {
@@ -326,7 +643,8 @@ pub enum Blend {
 ///
 /// Affine transformations are a combination of scaling, translation, rotation. They describe a
 /// transformation of the 2D space of the original image.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Affine {
     /// The affine transformation, as a row-major homogeneous matrix.
     ///
@@ -336,13 +654,31 @@ pub struct Affine {
     pub sampling: AffineSample,
 }
 
+/// Describes a projective transformation of an image.
+///
+/// In contrast to an [`Affine`] transformation this need not preserve parallel lines, the
+/// transformed image is an arbitrary convex quad. This is the transformation for rectifying a
+/// photographed plane or pinning an image to four corners.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Homography {
+    /// The transformation, as a row-major homogeneous matrix.
+    ///
+    /// It maps texel coordinates of the image above to texel coordinates of the image below, the
+    /// resulting point is divided by its third homogeneous coordinate.
+    pub matrix: [f32; 9],
+    /// How pixels are resolved from the underlying texture.
+    pub sampling: AffineSample,
+}
+
 /// The way to perform sampling of an texture that was transformed with an affine transformation.
 ///
 /// You have to be careful that there is NO built-in functionality to avoid attacks that downscale
 /// an image so far that a very particular subset of pixels (or linear interpolation) is shown that
 /// results in an image visually very different from the original. Such an attack works because
 /// scaling down leads to many pixels being ignored.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum AffineSample {
     /// Choose the nearest pixel.
     ///
@@ -356,13 +692,18 @@ pub enum AffineSample {
 }
 
 /// The parameters of color conversion which we will use in the draw call.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) enum ColorConversion {
     Xyz {
         /// The matrix converting source to XYZ.
         to_xyz_matrix: RowMatrix,
-        /// The matrix converting from XYZ to target.
+        /// The matrix converting target to XYZ, inverted for the conversion.
         from_xyz_matrix: RowMatrix,
+        /// The chromatic adaptation between the whitepoints of source and target, in XYZ.
+        adaptation: RowMatrix,
+        /// The ratio of linear values, between transfers of different luminance semantics.
+        scale: f32,
     },
     XyzToOklab {
         /// The matrix converting source to XYZ.
@@ -376,12 +717,49 @@ pub(crate) enum ColorConversion {
         /// The matrix converting source to XYZ.
         to_xyz_matrix: RowMatrix,
         /// The SrLAb2 target whitepoint.
+        #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
         whitepoint: Whitepoint,
     },
     SrLab2ToXyz {
         /// The matrix converting from XYZ to target.
         from_xyz_matrix: RowMatrix,
         /// The SrLAb2 source whitepoint.
+        #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
+        whitepoint: Whitepoint,
+    },
+    XyzToLab {
+        /// The matrix converting source to XYZ, adapted to the whitepoint.
+        to_xyz_matrix: RowMatrix,
+        /// The CIELAB target whitepoint.
+        #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
+        whitepoint: Whitepoint,
+    },
+    LabToXyz {
+        /// The matrix converting target to XYZ, adapted to the whitepoint.
+        from_xyz_matrix: RowMatrix,
+        /// The CIELAB source whitepoint.
+        #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
+        whitepoint: Whitepoint,
+    },
+}
+
+/// The relation of a color to CIE XYZ, through which all color conversions compose.
+///
+/// RGB colors are linear in XYZ after their transfer function. The other colors are non-linear
+/// transforms of XYZ, each of which is implemented by one shader that encodes from, or decodes
+/// into, linear RGB of any primaries.
+#[derive(Clone, Copy, Debug)]
+enum XyzRelation {
+    Rgb {
+        primary: Primaries,
+        whitepoint: Whitepoint,
+        transfer: Transfer,
+    },
+    Oklab,
+    SrLab2 {
+        whitepoint: Whitepoint,
+    },
+    Lab {
         whitepoint: Whitepoint,
     },
 }
@@ -393,6 +771,7 @@ pub(crate) enum ColorConversion {
 /// * protanomaly (red cone cells defective),
 /// * and tritanomaly (blue cone cells defective).
 /// More information here: http://colorspace.r-forge.r-project.org/articles/color_vision_deficiency.html
+/// This is implemented as [`CommandBuffer::simulate_cvd`].
 ///
 /// Matrix for transforming cone response into the opponent color space which is assumed to be a
 /// mostly sufficient input to recreate a particular color impression. In other words, simulate a
@@ -405,21 +784,25 @@ pub(crate) enum ColorConversion {
 /// 0.240    0.105   −0.700
 /// 1.200   −1.600    0.400
 /// ```
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub(crate) struct ChromaticAdaptation {
     /// The matrix converting source to XYZ.
     to_xyz_matrix: RowMatrix,
     /// The target whitepoint of the adaptation.
+    #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
     source: Whitepoint,
     /// The method to use.
     method: ChromaticAdaptationMethod,
     /// The matrix converting from XYZ to target.
     from_xyz_matrix: RowMatrix,
     /// The target whitepoint of the adaptation.
+    #[cfg_attr(feature = "serde", serde(with = "serialize::whitepoint"))]
     target: Whitepoint,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[non_exhaustive]
 pub enum ChromaticAdaptationMethod {
     /// Naive adaptation based on component-wise linear transform in XYZ.
@@ -455,10 +838,137 @@ pub enum ChromaticAdaptationMethod {
     /// \end{bmatrix}
     /// ```
     BradfordVonKries,
-    /// Bradford's originally intended adaptation.
+    /// Bradford's originally intended adaptation, with a non-linear response of the short cones.
+    ///
+    /// The sharpened cone responses of the luminance normalized color are scaled as in
+    /// [`Self::BradfordVonKries`], except for the short cones which are raised to the power of
+    /// `(S_w / S_wr)^0.0834` for the source and target white. This differs noticeably from the
+    /// linear transform only for blue-heavy colors. It can not be composed into a single matrix
+    /// and takes a separate pass when used for a color conversion.
     BradfordNonLinear,
 }
 
+/// The method of estimating the illuminant in an automatic white balance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub enum AwbMethod {
+    /// Assume that the average color of the scene is achromatic.
+    ///
+    /// The illuminant is estimated as the mean over all pixels in linear color. Each channel is
+    /// scaled by a gain such that this mean becomes a gray of equal intensity.
+    GrayWorld,
+}
+
+/// A statistic of each channel over all texels of an image, see [`CommandBuffer::reduce`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub enum Reduction {
+    /// The minimum value of each channel.
+    Min,
+    /// The maximum value of each channel.
+    Max,
+    /// The mean value of each channel.
+    Mean,
+}
+
+/// The reconstruction filter when changing the size of an image, see [`CommandBuffer::resize_with`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResizeFilter {
+    /// Pick the texel at the sampled coordinate, as in [`CommandBuffer::resize`].
+    Nearest,
+    /// Interpolate linearly between neighboring texels, a triangle kernel.
+    Bilinear,
+    /// The Catmull-Rom cubic spline, a kernel with a support of two texels.
+    Bicubic,
+    /// A windowed sinc with a support of three texels.
+    Lanczos3,
+}
+
+/// The number of texels added at each edge of an image, see [`CommandBuffer::pad`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Padding {
+    pub left: u32,
+    pub right: u32,
+    pub top: u32,
+    pub bottom: u32,
+}
+
+/// How the texels beyond the edge of an image are determined.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum BorderMode {
+    /// A constant color, in the linear representation as for [`CommandBuffer::solid_rgba`].
+    Constant([f32; 4]),
+    /// Repeat the texel at the nearest edge.
+    Clamp,
+    /// Reflect the image at its edges, repeating the edge texel itself.
+    Mirror,
+    /// Repeat the image periodically.
+    Wrap,
+}
+
+/// A synthetic image for checking layouts and color pipelines, see [`CommandBuffer::test_pattern`].
+///
+/// Colors are in the linear representation of the descriptor's color, as for
+/// [`CommandBuffer::solid_rgba`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum TestPattern {
+    /// A checker board of square cells.
+    Checker {
+        /// The side length of each cell, in texels.
+        size: u32,
+        /// The color of the cell at the origin, and of its neighbors.
+        colors: [[f32; 4]; 2],
+    },
+    /// Vertical bars in the order of SMPTE color bars at 75% intensity.
+    ColorBars,
+    /// A circular sweep of frequencies, growing linearly from the center.
+    ZonePlate {
+        /// The frequency in cycles per texel at the edge, `0.5` reaches the Nyquist limit.
+        scale: f32,
+    },
+}
+
+/// Seed points of a diagram, see [`CommandBuffer::voronoi`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Voronoi {
+    /// The position of each seed, relative to the image size, and the color of its cell.
+    ///
+    /// Positions in `0.0..=1.0` are within the image. Colors are in the linear representation of
+    /// the descriptor's color, as for [`CommandBuffer::solid_rgba`].
+    pub points: Vec<([f32; 2], [f32; 4])>,
+    /// The metric by which the nearest seed is chosen.
+    pub metric: VoronoiMetric,
+}
+
+/// The parameters of the structural similarity index, see [`CommandBuffer::ssim`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ssim {
+    /// The side length of the gaussian weighted window, an odd number of texels.
+    ///
+    /// The standard deviation of the gaussian scales with the window, it is `1.5` for the
+    /// canonical window of `11` texels.
+    pub window: u32,
+    /// The constant stabilizing the luminance term, relative to the dynamic range.
+    pub k1: f32,
+    /// The constant stabilizing the contrast and structure term, relative to the dynamic range.
+    pub k2: f32,
+}
+
+impl Default for Ssim {
+    fn default() -> Self {
+        Ssim {
+            window: 11,
+            k1: 0.01,
+            k2: 0.03,
+        }
+    }
+}
+
 /// Remove spherical differences in effective irradiation.
 ///
 /// There are a handful of common models for the difference in apparent pixel brightness caused by
@@ -469,7 +979,8 @@ pub enum ChromaticAdaptationMethod {
 /// Note that vignetting is caused by the physical make of the camera and lense system, e.g.
 /// partial shadowing of light paths into a pixel by the shutter and lense body. That is it will
 /// *very* across different focal lengths and lenses.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[non_exhaustive]
 pub enum VignetteRemoval {
     /// The irradiance is by four components with the constant held at `1`.
@@ -501,7 +1012,8 @@ pub enum VignetteRemoval {
 /// Note that some producers may fix the bias values `ksdr` and `khdr` to small values below the
 /// quantization range of the image during the encoding. This avoids mathematical poles in
 /// determining the gain which calculates `log2(sdr - khdr)` and `log2(hdr - khdr)`.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[non_exhaustive]
 pub struct GainMap {
     /// The sdr bias as defined in metadata or broadcast across channels.
@@ -523,7 +1035,7 @@ pub struct GainMap {
 /// A palette lookup operation.
 ///
 /// FIXME description and implementation
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Palette {
     /// Which color channel will provide the texture coordinate along width axis.
     pub width: Option<ColorChannel>,
@@ -533,17 +1045,76 @@ pub struct Palette {
     pub width_base: i32,
     /// The base coordinate for sampling along height.
     pub height_base: i32,
-    // FIXME: wrapping?
+    /// How coordinates outside the palette are sampled.
+    pub address: PaletteAddress,
+    /// Interpolate linearly between the entries of the palette, instead of choosing the nearest.
+    ///
+    /// The interpolation happens in the linear representation of the palette's color.
+    pub interpolate: bool,
+}
+
+/// How a palette is sampled at coordinates outside of it, see [`Palette`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum PaletteAddress {
+    /// Use the closest texel on the edge of the palette.
+    #[default]
+    Clamp,
+    /// Repeat the palette, wrapping the coordinates around.
+    Repeat,
+    /// Repeat the palette, mirrored at each edge.
+    MirrorRepeat,
+    /// A constant color, in the linear representation as for [`CommandBuffer::solid_rgba`].
+    Border([f32; 4]),
 }
 
 /// Calculate a first derivative.
-#[derive(Clone, Debug, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Derivative {
     pub method: DerivativeMethod,
     pub direction: Direction,
 }
 
+/// A first derivative with an explicit handling of the image borders.
+///
+/// Create it with [`Derivative::with_border`]. A plain [`Derivative`] clamps to the edge.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct BorderedDerivative {
+    pub derivative: Derivative,
+    /// How the taps of the kernel outside the image are resolved.
+    pub border: BorderHandling,
+}
+
+/// A relief lit from one direction, see [`CommandBuffer::emboss`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Emboss {
+    /// The direction towards the light, in radians from the width axis towards the height axis.
+    pub direction_rad: f32,
+    /// The scale of the relief, as a multiple of the slope of luma per texel.
+    pub depth: f32,
+    /// Add the relief onto the source color instead of a mid-gray.
+    pub recolor: bool,
+}
+
+/// How a convolution resolves the texels it reads outside of the image.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum BorderHandling {
+    /// Use the nearest texel at the edge.
+    #[default]
+    Clamp,
+    /// Reflect about the edge texel, which is itself not repeated.
+    Mirror,
+    /// Repeat the image periodically.
+    Wrap,
+    /// Treat all texels outside the image as zero.
+    Zero,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub enum Direction {
     /// Along the height of the image.
     Height,
@@ -551,111 +1122,644 @@ pub enum Direction {
     Width,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// The color space in which the stops of a color ramp are interpolated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[non_exhaustive]
-pub enum DerivativeMethod {
-    /// A 2-sized filter with diagonal basis direction.
-    ///     [[1 0] [0 -1]]
-    ///     [[0 1] [-1 0]]
-    Roberts,
-    /// The result of derivative with average smoothing.
-    ///     1/6 [1 1 1]^T [1 0 -1]
-    Prewitt,
-    /// The result of derivative with weighted smoothing.
-    ///     1/8 [1 2 1]^T [1 0 -1]
-    Sobel,
-    /// The Scharr 3×3 operator, floating point precision with the error bound of the paper.
-    ///
-    /// There is a single numerically consistent choice in differentiation but the smoothing for
-    /// rotational symmetry is:
-    ///     [46.84 162.32 46.84]/256
-    ///
-    /// It is optimized to most accurately model the perfect transfer function `pi·i·w` (the first
-    /// derivative operator) while providing the ability to steer the direction. That is, you can
-    /// calculate the derivative into any direction by composing Dx and Dy. The weights for this
-    /// optimization had been `w(k) = ∏i:(1 to D) cos^4(π/2·k_i)` where `D = 2` in the flat image
-    /// case.
-    ///
-    /// It's a bit constricting to use it here because on the GPU integer arithmetic is _NOT_ the
-    /// most efficient form of computation (which is the motivation behind using a quantized
-    /// integer matrix). They nevertheless exist for compatibility reasons.
-    ///
-    /// Reference: Scharr's dissertation, Optimale Operatoren in der Digitalen Bildverarbeitung
-    /// <https://doi.org/10.11588/heidok.00000962>
-    /// <http://archiv.ub.uni-heidelberg.de/volltextserver/962/1/Diss.pdf>
-    Scharr3,
-    /// A 4-tab derivative operator by Scharr.
-    /// * Derivative: `[77.68 139.48 …]/256`
-    /// * Smoothing: `[16.44 111.56 …]/256`
-    Scharr4,
-    /// A 5-tab derivative by Scharr.
-    /// * Derivative: `[21.27 85.46 0 …]/256`
-    /// * Smoothing: `[5.91 61.77 120.64 …]/256`
-    Scharr5,
-    /// The 4-bit approximated Scharr operator.
-    ///     1/32 [3 10 3]^T [1 0 -1]
-    ///
-    /// This is provided for compatibility! For accuracy you may instead prefer to use Scharr3 or
-    /// Schar5Tab.
-    Scharr3To4Bit,
-    /// A non-smoothed 5-tab derivative.
-    ///     `[-0.262 1.525 0 -1.525 0.262]`
-    Scharr5Tab,
-    /// The 8-bit approximated Scharr operator.
+pub enum RampSpace {
+    /// Interpolate the linear color values, as given.
+    Linear,
+    /// Interpolate in Oklab, which is perceptually more uniform.
     ///
-    /// This is provided for compatibility! For accuracy you may instead prefer to use Scharr3.
-    Scharr3To8Bit,
+    /// Requires the descriptor to have an RGB color.
+    Oklab,
 }
 
-/// Methods for removing noise from an image.
+/// The quality measures by which exposures are weighted in a fusion.
 ///
-/// WIP: these are not yet implemented.
+/// Following Mertens et al. "Exposure Fusion", each measure is raised to its exponent and their
+/// product is the weight of a pixel. An exponent of `0.0` disables a measure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FusionWeights {
+    /// The absolute response of a laplacian filter on the luma.
+    pub contrast: f32,
+    /// The standard deviation of the color channels.
+    pub saturation: f32,
+    /// How close each channel is to `0.5`, by a gaussian curve.
+    pub well_exposedness: f32,
+    /// The width of the gaussian curve used by the well-exposedness.
+    pub sigma: f32,
+}
+
+impl Default for FusionWeights {
+    fn default() -> Self {
+        FusionWeights {
+            contrast: 1.0,
+            saturation: 1.0,
+            well_exposedness: 1.0,
+            sigma: 0.2,
+        }
+    }
+}
+
+/// An operator compressing high dynamic range radiance into the displayable range.
 ///
-/// This intuitive understanding applies to single valued, gray scale images. The operator will
-/// also work for any colored images as long as the color space defines a luminance, lightness,
-/// or value channel. We will then choose a pixel by median of that channel.
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+/// All operators work on each linear color channel individually, and clamp the result to
+/// `0.0..=1.0`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[non_exhaustive]
-pub enum SmoothingMethod {
-    /// Also called: average, arithmetic mean.
-    Laplace,
-    /// Weighted average using a gauss kernel.
-    Gaussian,
-    /// Choose the median value from surrounding pixels.
-    ///
-    /// The choice is made through the Luma channel.
-    Median,
-    /// Bilateral filter, weighting pixels by values.
-    ///
-    /// The weighting is made through the Luma channel.
-    Bilteral,
-    /// Chooses a value from the surrounding region with minimal variance.
-    ///
-    /// This implements Kuwahara's initial approach where the representation is chosen to be the
-    /// mean value and the regions are defined as exactly 4 regions overlapping on the axes and the
-    /// pixel itself.
-    ///
-    /// Using hexadecimal bit masks to represent the regions, an example with 3×3 regions:
+pub enum ToneMap {
+    /// The extended Reinhard operator, `x·(1 + x/white²)/(1 + x)`.
     ///
-    /// ```text
-    /// 11322
-    /// 11322
-    /// 55faa
-    /// 44c88
-    /// 44c88
-    /// ```
-    Kuwahara,
+    /// The radiance `white` and above is mapped to `1.0`.
+    Reinhard { white: f32 },
+    /// The curve fitted by Krzysztof Narkowicz to the ACES reference rendering and output
+    /// transforms.
+    AcesFitted,
 }
 
-#[derive(Debug)]
-pub struct CommandError {
-    inner: CommandErrorKind,
+/// A shadow cast by the alpha channel of an image, see [`CommandBuffer::drop_shadow`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shadow {
+    /// The displacement of the shadow relative to the image, in texels.
+    pub offset: (i32, i32),
+    /// The standard deviation of the gaussian blur of the silhouette, in texels.
+    pub blur_sigma: f32,
+    /// The color of the shadow, in the linear representation of the image's color.
+    pub color: [f32; 3],
+    /// The opacity of the shadow under a fully opaque texel, in `0.0..=1.0`.
+    pub opacity: f32,
+    /// Grow the canvas such that the shadow is not cut off at the edges of the image.
+    pub expand: bool,
 }
 
-/// Generic instantiation that is todo by the linker.
+/// A glow around the bright parts of an image, see [`CommandBuffer::bloom`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bloom {
+    /// The brightness, the largest linear color channel, above which texels glow.
+    pub threshold: f32,
+    /// The factor of the glow added onto the image.
+    pub intensity: f32,
+    /// The approximate extent of the glow, in texels.
+    pub radius: f32,
+}
+
+/// A key color to make transparent, see [`CommandBuffer::chroma_key`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ChromaKey {
+    /// The key color, in the linear representation of the source's color.
+    pub color: [f32; 3],
+    /// The chroma distance in Oklab up to which texels become fully transparent.
+    ///
+    /// For reference, the chroma of saturated sRGB primaries is around `0.3`.
+    pub tolerance: f32,
+    /// The width of the anti-aliasing ramp beyond the tolerance, over which texels become opaque.
+    pub softness: f32,
+    /// How much of the chroma towards the key color is removed from the remaining texels.
+    ///
+    /// `0.0` keeps all colors, `1.0` removes the tint of the key reflected onto the subject.
+    pub spill_suppression: f32,
+}
+
+/// The result of a chroma key, see [`CommandBuffer::chroma_key`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum KeyOutput {
+    /// The source with the matte multiplied into its alpha channel.
+    Alpha,
+    /// The matte alone, as a `Luma` image where `1.0` is opaque.
+    Matte,
+}
+
+/// The configuration of a histogram, see [`CommandBuffer::histogram`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistogramConfig {
+    /// The number of bins, evenly partitioning the values `0.0..=1.0`.
+    pub bins: u32,
+    /// The channel whose linear value is counted.
+    ///
+    /// `ColorChannel::Luma` is also available for RGB images, as in [`CommandBuffer::extract`].
+    pub channel: ColorChannel,
+}
+
+/// Contrast limited adaptive histogram equalization, see [`CommandBuffer::clahe`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Clahe {
+    /// The number of tiles horizontally and vertically.
+    ///
+    /// Tile counts need not divide the image size, tiles then differ in size by one texel.
+    pub tiles: (u32, u32),
+    /// The limit of each histogram bin, relative to the average count of a bin in its tile.
+    ///
+    /// Counts above the limit are redistributed evenly over all bins. A limit of `1.0` leaves the
+    /// image unchanged, larger values allow increasingly strong contrast.
+    pub clip_limit: f32,
+}
+
+/// Color channels displaced radially, as by a lens, see [`CommandBuffer::chromatic_aberration`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ChromaticAberration {
+    /// The radial scale at which the red, green and blue channels are sampled.
+    ///
+    /// A scale above one samples further from the center, which moves that channel of the image
+    /// inwards. Choose scales inverse to those of a lens to correct its aberration.
+    pub scales: [f32; 3],
+    /// The center of the scaling, relative to the image size.
+    pub center: [f32; 2],
+}
+
+/// A command that can be switched off at launch time, see [`WithKnob::bypassable`].
+///
+/// Each of these has a configuration in which it reproduces its source, which is written into its
+/// knob to bypass it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Bypassable {
+    /// See [`CommandBuffer::sharpen`], bypassed with an amount of zero.
+    Sharpen(f32),
+    /// See [`CommandBuffer::chromatic_aberration`], bypassed with a scale of one for each channel.
+    ChromaticAberration(ChromaticAberration),
+    /// See [`CommandBuffer::bloom`], bypassed with an intensity of zero.
+    Bloom(Bloom),
+}
+
+/// The color filter array of a mosaic image, see [`CommandBuffer::demosaic`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Cfa {
+    /// The arrangement of colors in each 2×2 block of the sensor.
+    pub pattern: CfaPattern,
+    /// The interpolation of the missing colors.
+    pub algorithm: DemosaicAlgorithm,
+    /// The coordinate of the image origin in the full mosaic.
+    ///
+    /// This is non-zero for crops of the sensor data. Only its parity matters.
+    pub offset: [u32; 2],
+}
+
+/// The colors of a 2×2 block of a Bayer filter, in row-major order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum CfaPattern {
+    Rggb,
+    Bggr,
+    Grbg,
+    Gbrg,
+}
+
+/// The interpolation of the colors which a mosaic does not sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum DemosaicAlgorithm {
+    /// Average the nearest texels of each missing color.
+    Bilinear,
+    /// The 5×5 kernels of Malvar, He and Cutler.
+    ///
+    /// These correct the bilinear estimate with the gradient of the sampled color, which
+    /// noticeably reduces color fringes at edges.
+    Malvar,
+}
+
+/// A Y'CbCr encoding of video, see [`CommandBuffer::ycbcr_encode`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Ycbcr {
+    /// The luma coefficients of the color difference signals.
+    pub matrix: YcbcrMatrix,
+    /// The range of codes which the signals are quantized to.
+    pub range: YcbcrRange,
+}
+
+/// The standard defining the luma coefficients of Y'CbCr.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YcbcrMatrix {
+    /// BT.601, standard definition video.
+    Bt601,
+    /// BT.709, high definition video.
+    Bt709,
+    /// BT.2020, the non-constant luminance variant for ultra high definition video.
+    Bt2020,
+}
+
+/// The quantization range of Y'CbCr signals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum YcbcrRange {
+    /// The studio range, luma in 16–235 and chroma in 16–240 for 8-bit codes.
+    Limited,
+    /// All codes are used, as in JPEG.
+    Full,
+}
+
+/// The options of a color conversion, see [`CommandBuffer::color_convert_with`].
+#[derive(Clone, Debug)]
+pub struct ColorConvertOptions {
+    /// The luminance relating HDR and relative transfer functions.
+    pub luminance: HdrLuminance,
+    /// The chromatic adaptation between RGB colors of different whitepoints.
+    pub adaptation: ChromaticAdaptationMethod,
+    /// The treatment of RGB colors outside the gamut of the target.
+    pub gamut: GamutMapping,
+    /// Dither the result when it is quantized to integer samples of the target texel.
+    ///
+    /// An ordered pattern of less than one step in the depth of each sample is added after the
+    /// transfer function, such that smooth gradients do not show bands. This has no effect on
+    /// floating point texels.
+    pub dither: bool,
+}
+
+/// How a conversion between RGB colors treats colors outside of the target gamut.
+///
+/// All but clipping reduce the chroma of a color in Oklch, preserving its lightness and hue. The
+/// gamut of the target is a cube in its linear RGB, so its boundary is found by a short search
+/// along the chroma at each pixel. See [`CommandBuffer::color_convert_with`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum GamutMapping {
+    /// Clamp each channel on its own, which shifts the hue of saturated colors.
+    #[default]
+    Clip,
+    /// Reduce the chroma of colors outside the gamut onto its boundary.
+    ChromaCompress,
+    /// Compress the chroma above 80% of the boundary smoothly into the gamut.
+    ///
+    /// This keeps gradations between saturated colors distinct, at the cost of slightly
+    /// desaturating colors close to the boundary that were inside the gamut.
+    SoftClip,
+}
+
+/// The luminance relating HDR to relative transfer functions in a color conversion.
+///
+/// See [`CommandBuffer::color_convert_with`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HdrLuminance {
+    /// The luminance of diffuse white in cd/m², which is `1.0` in a relative transfer.
+    ///
+    /// The default is the HDR reference white of 203 cd/m² recommended by BT.2408. With it, the
+    /// peak luminance of PQ at 10000 cd/m² maps to a relative value of about `49.3`.
+    pub white_nits: f32,
+}
+
+/// The amount of black ink in naive CMYK, see [`CommandBuffer::cmyk_encode`].
+///
+/// The gray component of a color is the amount of ink common to cyan, magenta and yellow. Black
+/// ink replaces some or all of it, which does not change the color in the naive model but
+/// substantially changes the inks of a print.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlackGeneration {
+    /// Mix all grays from the colored inks.
+    None,
+    /// Replace the whole gray component with black, at least one colored ink is always unused.
+    Max,
+    /// Replace a percentage of the gray component with black, in `[0, 100]`.
+    Percentage(f32),
+}
+
+/// The Brown-Conrady model of a lens distortion, see [`CommandBuffer::lens_undistort`].
+///
+/// Coordinates are in texels, with the origin at the top left corner of the image. These are the
+/// usual coefficients of camera calibration, with the focal length and optical center of the
+/// camera matrix.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct Distortion {
+    /// The radial coefficient of the second power of the distance.
+    pub k1: f32,
+    /// The radial coefficient of the fourth power of the distance.
+    pub k2: f32,
+    /// The radial coefficient of the sixth power of the distance.
+    pub k3: f32,
+    /// The first tangential coefficient.
+    pub p1: f32,
+    /// The second tangential coefficient.
+    pub p2: f32,
+    /// The optical center.
+    pub center: [f32; 2],
+    /// The focal length, along the width and height.
+    pub focal: [f32; 2],
+    /// The color of texels which map outside the source, in its linear representation.
+    ///
+    /// Use a transparent color to flag these texels in an image with an alpha channel.
+    pub fill: [f32; 4],
+}
+
+/// A Möbius transformation of the chroma plane in Oklab.
+///
+/// Treating the chroma `a*b*` as a complex number `z`, the transformation `(z-a)/(1-z·adj(a))`
+/// maps the unit disk onto itself and moves `a` to the origin. A second such map then moves the
+/// origin to the target. In effect, the `from` chroma becomes the `to` chroma while neutral and
+/// highly saturated colors are distorted as little as possible.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AbMobius {
+    /// The chroma which should be moved, inside the unit disk.
+    pub from: [f32; 2],
+    /// The chroma that `from` is moved to, inside the unit disk.
+    pub to: [f32; 2],
+}
+
+impl Distortion {
+    /// Map an undistorted coordinate to the coordinate where the lens images it.
+    pub fn distort(&self, [x, y]: [f32; 2]) -> [f32; 2] {
+        let [cx, cy] = self.center;
+        let [fx, fy] = self.focal;
+
+        let (x, y) = ((x - cx) / fx, (y - cy) / fy);
+        let r2 = x * x + y * y;
+        let radial = 1.0 + r2 * (self.k1 + r2 * (self.k2 + r2 * self.k3));
+
+        let dx = x * radial + 2.0 * self.p1 * x * y + self.p2 * (r2 + 2.0 * x * x);
+        let dy = y * radial + self.p1 * (r2 + 2.0 * y * y) + 2.0 * self.p2 * x * y;
+
+        [dx * fx + cx, dy * fy + cy]
+    }
+}
+
+impl ChromaticAberration {
+    /// Displace red and blue in opposite directions, relative to the green channel.
+    ///
+    /// A positive strength moves red inwards and blue outwards, a negative one the opposite.
+    pub fn with_strength(strength: f32, center: [f32; 2]) -> Self {
+        ChromaticAberration {
+            scales: [1.0 + strength, 1.0, 1.0 - strength],
+            center,
+        }
+    }
+}
+
+impl HdrLuminance {
+    /// The HDR reference white of BT.2408, in cd/m².
+    pub const REFERENCE_WHITE: f32 = 203.0;
+
+    /// The linear value of reference white in a transfer.
+    pub(crate) fn relative_white(&self, transfer: Transfer) -> Result<f32, CommandError> {
+        /// The peak luminance of PQ, in cd/m².
+        const PQ_PEAK: f32 = 10000.0;
+        /// The scene light of the 75% signal, which BT.2408 assigns to reference white in HLG.
+        const HLG_WHITE: f32 = 0.26496;
+
+        if !(self.white_nits.is_finite() && self.white_nits > 0.0) {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let display = self.white_nits / PQ_PEAK;
+
+        Ok(match transfer {
+            Transfer::Smpte2084 => display,
+            Transfer::Bt2100Pq => {
+                // Invert the reference OOTF of PQ, the BT.709 curve followed by the BT.1886 one.
+                let electrical = (100.0 * display).powf(1.0 / 2.4);
+                let scene = if electrical >= 4.5 * 0.018 {
+                    ((electrical + 0.099) / 1.099).powf(1.0 / 0.45)
+                } else {
+                    electrical / 4.5
+                };
+
+                scene / 59.5208
+            }
+            Transfer::Bt2100Hlg => HLG_WHITE,
+            _ => 1.0,
+        })
+    }
+}
+
+impl Default for ColorConvertOptions {
+    fn default() -> Self {
+        ColorConvertOptions {
+            luminance: HdrLuminance::default(),
+            adaptation: ChromaticAdaptationMethod::BradfordVonKries,
+            gamut: GamutMapping::Clip,
+            dither: false,
+        }
+    }
+}
+
+impl Default for HdrLuminance {
+    fn default() -> Self {
+        HdrLuminance {
+            white_nits: Self::REFERENCE_WHITE,
+        }
+    }
+}
+
+impl Ycbcr {
+    /// The affine map of normalized R'G'B' to normalized Y'CbCr codes of a texel.
+    ///
+    /// The codes of deeper texels are those of 8 bits times a power of two, as in the standards,
+    /// but they are normalized by their own maximum. Float texels use the 8-bit codes.
+    pub(crate) fn encode_affine(&self, bits: SampleBits) -> (RowMatrix, [f32; 3]) {
+        let (kr, kb) = match self.matrix {
+            YcbcrMatrix::Bt601 => (0.299, 0.114),
+            YcbcrMatrix::Bt709 => (0.2126, 0.0722),
+            YcbcrMatrix::Bt2020 => (0.2627, 0.0593),
+        };
+
+        let kg = 1.0 - kr - kb;
+        let (cb, cr) = (2.0 * (1.0 - kb), 2.0 * (1.0 - kr));
+
+        #[rustfmt::skip]
+        let difference = RowMatrix::new([
+            kr, kg, kb,
+            -kr / cb, -kg / cb, 0.5,
+            0.5, -kg / cr, -kb / cr,
+        ]);
+
+        let depth: i32 = match bits {
+            SampleBits::UInt16x3 => 16,
+            _ => 8,
+        };
+
+        // One 8-bit code, in the normalized range of the texel.
+        let code = 2f32.powi(depth - 8) / (2f32.powi(depth) - 1.0);
+
+        let (scale, offset) = match self.range {
+            YcbcrRange::Limited => (
+                RowMatrix::diag(219.0 * code, 224.0 * code, 224.0 * code),
+                [16.0 * code, 128.0 * code, 128.0 * code],
+            ),
+            YcbcrRange::Full => (
+                RowMatrix::diag(1.0, 1.0, 1.0),
+                [0.0, 128.0 * code, 128.0 * code],
+            ),
+        };
+
+        (scale.multiply_right(difference.into()).into(), offset)
+    }
+
+    /// The affine map of normalized Y'CbCr codes to normalized R'G'B', inverting the encoding.
+    pub(crate) fn decode_affine(&self, bits: SampleBits) -> (RowMatrix, [f32; 3]) {
+        let (matrix, offset) = self.encode_affine(bits);
+        let inverse = matrix.inv();
+        let [x, y, z] = inverse.multiply_column(offset);
+        (inverse, [-x, -y, -z])
+    }
+}
+
+impl AbMobius {
+    /// A transformation which makes the chroma `a` neutral.
+    pub fn with_pole(a: [f32; 2]) -> Self {
+        AbMobius {
+            from: a,
+            to: [0.0, 0.0],
+        }
+    }
+
+    /// A transformation which turns one Oklab color into the chroma of another.
+    ///
+    /// Only the chroma is affected, the lightness of each color is ignored.
+    pub fn between(source: [f32; 3], target: [f32; 3]) -> Self {
+        AbMobius {
+            from: [source[1], source[2]],
+            to: [target[1], target[2]],
+        }
+    }
+}
+
+/// A Catmull-Rom spline through a sequence of colors.
+///
+/// The curve passes through all points except the first and the last, which only determine the
+/// tangents at its ends. The interior points are spaced uniformly.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CatmullRom {
+    /// The control colors, in the linear representation of the color.
+    ///
+    /// There must be at least 4 of them and at most
+    /// [`MAX_POINTS`](shaders::spline_ramp::MAX_POINTS).
+    pub points: Vec<[f32; 4]>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub enum DerivativeMethod {
+    /// A 2-sized filter with diagonal basis direction.
+    ///     [[1 0] [0 -1]]
+    ///     [[0 1] [-1 0]]
+    Roberts,
+    /// The result of derivative with average smoothing.
+    ///     1/6 [1 1 1]^T [1 0 -1]
+    Prewitt,
+    /// The result of derivative with weighted smoothing.
+    ///     1/8 [1 2 1]^T [1 0 -1]
+    Sobel,
+    /// The Scharr 3×3 operator, floating point precision with the error bound of the paper.
+    ///
+    /// There is a single numerically consistent choice in differentiation but the smoothing for
+    /// rotational symmetry is:
+    ///     [46.84 162.32 46.84]/256
+    ///
+    /// It is optimized to most accurately model the perfect transfer function `pi·i·w` (the first
+    /// derivative operator) while providing the ability to steer the direction. That is, you can
+    /// calculate the derivative into any direction by composing Dx and Dy. The weights for this
+    /// optimization had been `w(k) = ∏i:(1 to D) cos^4(π/2·k_i)` where `D = 2` in the flat image
+    /// case.
+    ///
+    /// It's a bit constricting to use it here because on the GPU integer arithmetic is _NOT_ the
+    /// most efficient form of computation (which is the motivation behind using a quantized
+    /// integer matrix). They nevertheless exist for compatibility reasons.
+    ///
+    /// Reference: Scharr's dissertation, Optimale Operatoren in der Digitalen Bildverarbeitung
+    /// <https://doi.org/10.11588/heidok.00000962>
+    /// <http://archiv.ub.uni-heidelberg.de/volltextserver/962/1/Diss.pdf>
+    Scharr3,
+    /// A 4-tab derivative operator by Scharr.
+    /// * Derivative: `[77.68 139.48 …]/256`
+    /// * Smoothing: `[16.44 111.56 …]/256`
+    Scharr4,
+    /// A 5-tab derivative by Scharr.
+    /// * Derivative: `[21.27 85.46 0 …]/256`
+    /// * Smoothing: `[5.91 61.77 120.64 …]/256`
+    Scharr5,
+    /// The 4-bit approximated Scharr operator.
+    ///     1/32 [3 10 3]^T [1 0 -1]
+    ///
+    /// This is provided for compatibility! For accuracy you may instead prefer to use Scharr3 or
+    /// Schar5Tab.
+    Scharr3To4Bit,
+    /// A non-smoothed 5-tab derivative.
+    ///     `[-0.262 1.525 0 -1.525 0.262]`
+    Scharr5Tab,
+    /// The 8-bit approximated Scharr operator.
+    ///
+    /// This is provided for compatibility! For accuracy you may instead prefer to use Scharr3.
+    Scharr3To8Bit,
+}
+
+/// Methods for removing noise from an image.
+///
+/// WIP: these are not yet implemented.
+///
+/// This intuitive understanding applies to single valued, gray scale images. The operator will
+/// also work for any colored images as long as the color space defines a luminance, lightness,
+/// or value channel. We will then choose a pixel by median of that channel.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SmoothingMethod {
+    /// Also called: average, arithmetic mean.
+    Laplace,
+    /// Weighted average using a gauss kernel.
+    Gaussian,
+    /// Choose the median value from surrounding pixels.
+    ///
+    /// The choice is made through the Luma channel.
+    Median,
+    /// Bilateral filter, weighting pixels by values.
+    ///
+    /// The weighting is made through the Luma channel.
+    Bilteral,
+    /// Chooses a value from the surrounding region with minimal variance.
+    ///
+    /// This implements Kuwahara's initial approach where the representation is chosen to be the
+    /// mean value and the regions are defined as exactly 4 regions overlapping on the axes and the
+    /// pixel itself.
+    ///
+    /// Using hexadecimal bit masks to represent the regions, an example with 3×3 regions:
+    ///
+    /// ```text
+    /// 11322
+    /// 11322
+    /// 55faa
+    /// 44c88
+    /// 44c88
+    /// ```
+    Kuwahara,
+}
+
+/// Parameters of non-local means denoising, see [`CommandBuffer::denoise_nlm`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Nlm {
+    /// The radius of the window searched for similar patches around each texel.
+    ///
+    /// At most [`MAX_SEARCH_RADIUS`](shaders::denoise_nlm::MAX_SEARCH_RADIUS).
+    pub search_radius: u32,
+    /// The radius of the patches which are compared.
+    ///
+    /// At most [`MAX_PATCH_RADIUS`](shaders::denoise_nlm::MAX_PATCH_RADIUS).
+    pub patch_radius: u32,
+    /// The filter strength, relative to the mean squared luma difference of two patches.
+    ///
+    /// A texel whose patch differs by `h` in each luma value has a weight of `1/e`. Choose it in
+    /// the order of the standard deviation of the noise, in linear color values.
+    pub h: f32,
+}
+
+#[derive(Debug)]
+pub struct CommandError {
+    inner: CommandErrorKind,
+}
+
+/// A violated invariant of a command buffer, see [`CommandBuffer::validate`].
+#[derive(Debug)]
+pub struct ValidationError {
+    register: Option<Register>,
+    error: CommandError,
+}
+
+/// Generic instantiation that is todo by the linker.
 struct CommandMonomorphization<'lt> {
     /// The name of the buffer in the linker.
     link_idx: usize,
+    /// The symbol by which the function was invoked, if it was named.
+    name: Option<String>,
+    /// The function of the program which this instantiation becomes.
+    function: Function,
     command: &'lt CommandBuffer,
     tys: Cow<'lt, [Descriptor]>,
 }
@@ -664,6 +1768,8 @@ struct CommandMonomorphization<'lt> {
 struct LinkedMonomorphicSignature {
     /// The function, by its Linker symbol index.
     link_idx: usize,
+    /// The symbol of the declaration, for diagnostics.
+    name: Option<String>,
     tys: Vec<Descriptor>,
 }
 
@@ -671,19 +1777,31 @@ struct Monomorphizing<'lt> {
     stack: Vec<CommandMonomorphization<'lt>>,
     monomorphic: HashMap<LinkedMonomorphicSignature, Function>,
     commands: Vec<&'lt CommandBuffer>,
-    knobs: HashMap<RegisterKnob, Knob>,
+    knobs: HashMap<RegisterKnob, Vec<(Vec<Descriptor>, Knob)>>,
+    knob_records: HashMap<RegisterKnob, knob::KnobRecord>,
     next_knob: Knob,
     /// The ID of a program being translated.
     current_link_id: usize,
+    /// The generic arguments with which the program is being translated.
+    current_tys: Vec<Descriptor>,
+    /// The function being translated, which is the caller of invocations.
+    current_function: Function,
+    /// The buffer instantiated by each function, by its index.
+    link_ids: Vec<usize>,
+    /// The functions invoked by each function, by its index.
+    callees: Vec<Vec<Function>>,
 }
 
 /// Schedule instructions with host-dependent and modifiable configuration.
+///
+/// Each method returns a [`KnobHandle`] whose layout describes the parameter block of the knob,
+/// see the [`knob`] module.
 pub struct WithKnob<'lt> {
     inner: &'lt mut CommandBuffer,
+    label: Option<String>,
 }
 
 /// Schedule instructions with parameters already in GPU memory.
-#[allow(dead_code)]
 pub struct WithBuffer<'lt> {
     inner: &'lt mut CommandBuffer,
     guaranteed_len: u64,
@@ -698,21 +1816,73 @@ pub struct WithBuffer<'lt> {
 /// set of static parameters in that source. You can discover the identifiers chosen during linking
 /// by passing the index and register of the [`CommandBuffer`] which has created its command with a
 /// corresponding [`WithKnob`] method. Not all commands can have knobs.
+///
+/// A generic function is instantiated once for each distinct set of generic arguments, each with
+/// its own knob. The register then identifies the knob of all those instantiations together while
+/// a specific one is chosen by its arguments, see [`Executable::query_knob_instance`].
+///
+/// [`Executable::query_knob_instance`]: crate::run::Executable::query_knob_instance
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub struct RegisterKnob {
     pub link_idx: usize,
     pub register: Register,
 }
 
+/// The reason a command was rejected, see [`CommandError::kind`].
 #[derive(Debug)]
-// `Debug` is our use. Until we get better errors.
-#[allow(unused)]
-enum CommandErrorKind {
+#[non_exhaustive]
+pub enum CommandErrorKind {
+    /// The register is not defined in this command buffer, or it does not hold a value.
+    BadRegister(Register),
+    /// The register holds a buffer where an image is required.
+    NotATexture(Register),
+    /// The register holds an image where a buffer is required.
+    NotABuffer(Register),
+    /// The descriptor or function variable belongs to a different command buffer.
+    BadVariable,
+    /// A function is invoked with the wrong number of arguments or generics.
+    ArityMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// An argument of an invocation has a different type than its parameter.
+    ArgumentMismatch {
+        argument: Register,
+        expected: GenericDescriptor,
+        actual: GenericDescriptor,
+    },
+    /// A buffer argument of an invocation has a different size than its parameter.
+    BufferMismatch {
+        argument: Register,
+        expected: GenericBuffer,
+        actual: GenericBuffer,
+    },
+    /// Two images are required to have the same size.
+    SizeMismatch {
+        lhs: Register,
+        rhs: Register,
+    },
+    /// There is no conversion from the color of an image to the requested color.
+    UnsupportedConversion {
+        src: Register,
+        from: Color,
+        to: Color,
+    },
+    /// The channel is not present in the texel, nor computed from its color.
+    BadChannel(ColorChannel),
+    /// The transformation is singular or maps part of the image to infinity.
+    DegenerateTransform,
+    /// A parameter of the command is outside of its domain.
+    InvalidArgument,
+    /// A range of buffer bytes does not start and end on a multiple of the copy alignment.
+    MisalignedRange {
+        range: core::ops::Range<u64>,
+        align: u64,
+    },
     BadDescriptor(GenericDescriptor, &'static str),
     ConcreteDescriptorRequired,
     ConflictingTypes(GenericDescriptor, GenericDescriptor),
     GenericTypeError,
-    Other,
     Unimplemented,
 }
 
@@ -721,12 +1891,9 @@ enum CommandErrorKind {
 /// FIXME: missing functions
 /// - a way to represent colors as a function of wavelength, then evaluate with the standard
 /// observers.
-/// - implement simulation of color blindness (reuses matrix multiplication, related to observer)
-/// - generate color ramps
-/// - color interpolation along a cubic spline (how do we represent the parameters?)
-/// - hue shift, transforms on the a*b* circle, such as mobius transform (z-a)/(1-z·adj(a)) i.e or
-///   other holomorphic functions. Ways to construct mobius transfrom from three key points. That
-///   is particular relevant for color correction.
+/// - other holomorphic functions on the a*b* circle, beyond [`CommandBuffer::ab_mobius`]. Ways to
+///   construct mobius transfrom from three key points. That is particular relevant for color
+///   correction.
 ///
 /// For developers aiming to add extensions to the system, see the other impl-block.
 ///
@@ -737,6 +1904,11 @@ enum CommandErrorKind {
 ///   c. … (no operator with more argument atm)
 /// 2. Arguments to the command itself
 impl CommandBuffer {
+    /// The color of images holding CIELAB coordinates, see [`Self::color_convert_cielab`].
+    const CIELAB: Color = Color::Scalars {
+        transfer: Transfer::Linear,
+    };
+
     /// Declare an input.
     ///
     /// Inputs MUST later be bound from the pool during launch.
@@ -755,32 +1927,80 @@ impl CommandBuffer {
     /// See [`Self::generic`].
     pub fn input_generic(&mut self, var: DescriptorVar) -> Result<Register, CommandError> {
         let Some(desc) = self.tys.get(var.0).cloned() else {
-            return Err(CommandError::BAD_REGISTER);
+            return Err(CommandError::BAD_VARIABLE);
         };
 
         Ok(self.push(Op::Input { desc }))
     }
 
-    /// Declare a generic parameter.
+    /// Declare a buffer as input, passed by the caller of this function.
+    ///
+    /// The buffers are the arguments of [`Self::invoke`] which follow the images. Across the call
+    /// the buffer is passed as the bytes of an image, as with [`Self::output_buffer`], so its
+    /// length must be a multiple of 256 bytes. A program can not yet be launched with a buffer
+    /// input, only functions can declare them.
+    pub fn buffer_input(&mut self, len: u64) -> Result<Register, CommandError> {
+        bytes_as_image(len).ok_or(CommandError::INVALID_CALL)?;
+
+        Ok(self.push(Op::BufferInput {
+            desc: GenericBuffer {
+                size: Generic::Concrete(len),
+            },
+        }))
+    }
+
+    /// Declare a generic parameter.
     ///
     /// All generic parameters need to be filled with matching concrete variables when the function
-    /// is instantiated at a later point.
-    pub fn generic(&mut self, generic: GenericDeclaration) -> DescriptorVar {
-        for item in generic.bounds {
-            match *item {}
+    /// is instantiated at a later point. The size and the chroma of the declared descriptor are
+    /// each either determined by that parameter, shared with another descriptor var, or concrete.
+    /// For instance, a descriptor of any size that is always sRGB can be declared with a fresh
+    /// size and a concrete chroma.
+    ///
+    /// The bounds constrain a fresh chroma when the function is instantiated. Otherwise they are
+    /// checked against the given chroma right away, which fails with a type error.
+    pub fn generic(&mut self, generic: GenericDeclaration) -> Result<DescriptorVar, CommandError> {
+        let GenericDeclaration {
+            bounds,
+            size,
+            chroma,
+        } = generic;
+
+        let shared = |var: DescriptorVar| self.tys.get(var.0).ok_or(CommandError::BAD_VARIABLE);
+        let tyvar = GenericVar(self.vars.len());
+
+        let size = match size {
+            SizeSpec::Fresh => Generic::Generic(tyvar),
+            SizeSpec::Shared(var) => shared(var)?.size.clone(),
+            SizeSpec::Concrete(width, height) => Generic::Concrete((width, height)),
+        };
+
+        let (chroma, is_fresh) = match chroma {
+            ChromaSpec::Fresh => (Generic::Generic(tyvar), true),
+            ChromaSpec::Shared(var) => (shared(var)?.chroma.clone(), false),
+            ChromaSpec::Concrete(texel, color) => (Generic::Concrete((texel, color)), false),
+        };
+
+        let desc = GenericDescriptor { size, chroma };
+        let mut bounds = TyVarBounds {
+            bounds: bounds.to_vec(),
+        };
+
+        if !is_fresh {
+            if !bounds.is_satisfied_by(&desc, &self.vars) {
+                return Err(CommandError::TYPE_ERR);
+            }
+
+            // Nothing is left to constrain on instantiation.
+            bounds = TyVarBounds::default();
         }
 
-        let bounds = TyVarBounds {};
-        let tyvar = GenericVar(self.vars.len());
         self.vars.push(bounds);
 
         let descriptor = DescriptorVar(self.tys.len());
-        self.tys.push(GenericDescriptor {
-            size: Generic::Generic(tyvar),
-            chroma: Generic::Generic(tyvar),
-        });
+        self.tys.push(desc);
 
-        descriptor
+        Ok(descriptor)
     }
 
     /// Create a descriptor var by modifying another.
@@ -788,14 +2008,14 @@ impl CommandBuffer {
         &mut self,
         var: DescriptorDerivation,
     ) -> Result<DescriptorVar, CommandError> {
-        let DescriptorDerivation { base, size } = var;
+        let DescriptorDerivation { base, size, chroma } = var;
         let Some(from) = self.tys.get(base.0).cloned() else {
-            return Err(CommandError::BAD_REGISTER);
+            return Err(CommandError::BAD_VARIABLE);
         };
 
         let desc = GenericDescriptor {
             size: size.map_or(from.size, Generic::Concrete),
-            chroma: from.chroma,
+            chroma: chroma.map_or(from.chroma, Generic::Concrete),
         };
 
         let descriptor = DescriptorVar(self.tys.len());
@@ -804,12 +2024,20 @@ impl CommandBuffer {
         Ok(descriptor)
     }
 
+    /// Get the descriptor of a descriptor var.
+    pub fn describe_var(&self, var: DescriptorVar) -> Result<GenericDescriptor, CommandError> {
+        self.tys
+            .get(var.0)
+            .cloned()
+            .ok_or(CommandError::BAD_VARIABLE)
+    }
+
     /// Create a descriptor var, describing a previous register.
     pub fn register_descriptor(
         &mut self,
         register: Register,
     ) -> Result<DescriptorVar, CommandError> {
-        let generic = self.describe_reg(register).as_texture()?;
+        let generic = self.texture_of(register)?;
 
         let descriptor = DescriptorVar(self.tys.len());
         self.tys.push(generic.clone());
@@ -817,39 +2045,479 @@ impl CommandBuffer {
         Ok(descriptor)
     }
 
-    /// Calculate the signature based on generics, inputs, outputs.
-    pub fn computed_signature(&self) -> CommandSignature {
-        CommandSignature {
-            vars: self.vars.clone(),
-            input: self
-                .ops
-                .iter()
-                .filter_map(|op| {
-                    if let Op::Input { desc } = op {
-                        Some(desc.clone())
-                    } else {
-                        None
+    /// Get the descriptor of a register.
+    ///
+    /// This is [`RegisterDescription::None`] for registers which are not values, such as outputs,
+    /// and registers not defined in this command buffer.
+    pub fn describe(&self, register: Register) -> RegisterDescription<'_> {
+        self.describe_reg(register)
+    }
+
+    /// Get a summary of the operation defining a register.
+    pub fn op_kind(&self, Register(reg): Register) -> Option<OpKind<'_>> {
+        Some(match self.ops.get(reg)? {
+            Op::Input { .. } => OpKind::Input,
+            &Op::Output { src } => OpKind::Output { src },
+            &Op::Render { src } => OpKind::Render { src },
+            &Op::Construct {
+                op: ConstructOp::FromBuffer(src, _),
+                ..
+            } => OpKind::Unary {
+                name: "from_buffer",
+                src,
+            },
+            Op::Construct { op, .. } => OpKind::Construct { name: op.name() },
+            &Op::Unary { ref op, src, .. } => OpKind::Unary {
+                name: op.name(),
+                src,
+            },
+            &Op::Binary {
+                ref op, lhs, rhs, ..
+            } => OpKind::Binary {
+                name: op.name(),
+                lhs,
+                rhs,
+            },
+            Op::DynamicImage { call, .. } => match *call {
+                OperandDynKind::Construct => OpKind::Construct { name: "dynamic" },
+                OperandDynKind::Unary(src) => OpKind::Unary {
+                    name: "dynamic",
+                    src,
+                },
+                OperandDynKind::Binary { lhs, rhs } => OpKind::Binary {
+                    name: "dynamic",
+                    lhs,
+                    rhs,
+                },
+            },
+            Op::Invoke {
+                function,
+                arguments,
+                ..
+            } => OpKind::Invoke {
+                function: *function,
+                arguments,
+            },
+            &Op::InvokedResult { invocation, .. } | &Op::InvokedBuffer { invocation, .. } => {
+                OpKind::InvokedResult { invocation }
+            }
+            Op::BufferInput { .. } => OpKind::Input,
+            Op::BufferInit { .. } => OpKind::Construct {
+                name: "buffer_init",
+            },
+            &Op::BufferUnary { src, ref op, .. } => OpKind::Unary {
+                name: op.name(),
+                src,
+            },
+            &Op::BufferBinary {
+                lhs, rhs, ref op, ..
+            } => OpKind::Binary {
+                name: op.name(),
+                lhs,
+                rhs,
+            },
+        })
+    }
+
+    /// Iterate over the inputs, in the order of declaration.
+    pub fn inputs(&self) -> impl Iterator<Item = (Register, RegisterDescription<'_>)> + '_ {
+        self.iter_registers()
+            .filter(|&(reg, _)| matches!(self.op_kind(reg), Some(OpKind::Input)))
+    }
+
+    /// Iterate over the outputs, with the description of the register they output.
+    pub fn outputs(&self) -> impl Iterator<Item = (Register, RegisterDescription<'_>)> + '_ {
+        (0..self.ops.len()).filter_map(|idx| match self.ops[idx] {
+            Op::Output { src } => Some((Register(idx), self.describe_reg(src))),
+            _ => None,
+        })
+    }
+
+    /// Iterate over all registers of this command buffer, in the order of their definition.
+    pub fn iter_registers(&self) -> impl Iterator<Item = (Register, RegisterDescription<'_>)> + '_ {
+        (0..self.ops.len()).map(|idx| (Register(idx), self.describe_reg(Register(idx))))
+    }
+
+    /// Write the operations as text, one line for each register.
+    ///
+    /// This is the same as the `Display` representation. Each register is written as it is defined
+    /// in the manner of an SSA form, with its type, the name of its operation and the registers it
+    /// reads, for example `%3 : 64x64 sRGB/UInt8x4-RgbA = crop %0`. The parameters of operations
+    /// are not written. Function signatures and generics are written before all registers, and
+    /// knobs and dithering are annotated after the operation.
+    pub fn dump(&self) -> String {
+        self.to_string()
+    }
+
+    /// Produce a `dot` describing the dependencies between registers.
+    ///
+    /// Each register is a node labeled with its operation and its type. Inputs, outputs and render
+    /// targets are drawn in distinct shapes, and registers with knobs are drawn bold. The results
+    /// of an invocation are grouped with it in a cluster labeled with the function it calls.
+    pub fn dot(&self) -> String {
+        use core::fmt::Write;
+        let mut nodes = String::new();
+        let mut edges = String::new();
+        let mut invocations = String::new();
+
+        for idx in 0..self.ops.len() {
+            let register = Register(idx);
+            let (label, shape, operands) = match self.op_kind(register) {
+                None => continue,
+                Some(OpKind::Input) => ("input".to_string(), "invhouse", vec![]),
+                Some(OpKind::Output { src }) => ("output".to_string(), "house", vec![src]),
+                Some(OpKind::Render { src }) => ("render".to_string(), "doubleoctagon", vec![src]),
+                Some(OpKind::Construct { name }) => (name.to_string(), "box", vec![]),
+                Some(OpKind::Unary { name, src }) => (name.to_string(), "box", vec![src]),
+                Some(OpKind::Binary { name, lhs, rhs }) => {
+                    (name.to_string(), "box", vec![lhs, rhs])
+                }
+                Some(OpKind::Invoke {
+                    function,
+                    arguments,
+                }) => {
+                    let _ = write!(
+                        &mut invocations,
+                        " subgraph cluster_invoke_{idx} {{ label=\"@{}\"; register_{idx};",
+                        function.0
+                    );
+
+                    if let Op::Invoke { results, .. } = &self.ops[idx] {
+                        for result in results {
+                            let _ = write!(&mut invocations, " register_{};", result.0);
+                        }
                     }
+
+                    let _ = write!(&mut invocations, " }}");
+                    let label = format!("invoke @{}", function.0);
+                    (label, "component", arguments.to_vec())
+                }
+                Some(OpKind::InvokedResult { invocation }) => {
+                    ("result".to_string(), "box", vec![invocation])
+                }
+            };
+
+            let ty = match self.describe_reg(register) {
+                RegisterDescription::None => String::new(),
+                RegisterDescription::Texture(desc) => format!("\\n{}", DumpDescriptor(desc)),
+                RegisterDescription::Buffer(GenericBuffer { size }) => match size {
+                    Generic::Concrete(len) => format!("\\nbuffer[{len}]"),
+                    Generic::Generic(GenericVar(var)) => format!("\\nbuffer[${var}]"),
+                },
+            };
+
+            let style = match self.knobs.get(&register) {
+                None => "",
+                Some(KnobKind::Runtime(_)) => ", style=bold",
+                Some(KnobKind::Buffer { buffer, .. }) => {
+                    let _ = write!(
+                        &mut edges,
+                        " register_{} -> register_{} [style=dashed];",
+                        buffer.0, idx
+                    );
+                    ", style=bold"
+                }
+            };
+
+            let _ = write!(
+                &mut nodes,
+                " register_{idx} [label=\"%{idx} {label}{ty}\", shape={shape}{style}];"
+            );
+
+            for src in operands {
+                let _ = write!(&mut edges, " register_{} -> register_{};", src.0, idx);
+            }
+        }
+
+        format!("digraph commands {{ {nodes}{invocations}{edges} }}")
+    }
+
+    /// Remove the operations which no output or render target depends on.
+    ///
+    /// Linking lowers every operation, so registers which are not used still allocate textures and
+    /// execute their shaders. The pruned buffer only keeps the operations that are transitively
+    /// required by an [`Self::output`] or [`Self::render`]. Inputs are always kept such that the
+    /// signature of the buffer is not changed, and the results of an invocation are kept together.
+    ///
+    /// The remaining registers are renumbered. The returned map translates the registers of this
+    /// buffer to the pruned one, including those identifying a [`RegisterKnob`].
+    pub fn prune(&self) -> (CommandBuffer, RegisterMap) {
+        let mut live = vec![false; self.ops.len()];
+        let mut stack: Vec<Register> = (0..self.ops.len())
+            .filter(|&idx| {
+                matches!(
+                    self.ops[idx],
+                    Op::Input { .. }
+                        | Op::BufferInput { .. }
+                        | Op::Output { .. }
+                        | Op::Render { .. }
+                )
+            })
+            .map(Register)
+            .collect();
+
+        while let Some(register) = stack.pop() {
+            if core::mem::replace(&mut live[register.0], true) {
+                continue;
+            }
+
+            self.ops[register.0]
+                .clone()
+                .map_registers(|reg| stack.push(*reg));
+
+            if let Some(KnobKind::Buffer { buffer, .. }) = self.knobs.get(&register) {
+                stack.push(*buffer);
+            }
+        }
+
+        let mut next = 0;
+        let registers = live
+            .iter()
+            .map(|&live| {
+                live.then(|| {
+                    next += 1;
+                    Register(next - 1)
                 })
+            })
+            .collect();
+
+        let map = RegisterMap { registers };
+        let rename = |reg: &mut Register| {
+            *reg = map.get(*reg).expect("Operands of live registers are live");
+        };
+
+        let ops = self
+            .ops
+            .iter()
+            .zip(&live)
+            .filter(|(_, &live)| live)
+            .map(|(op, _)| {
+                let mut op = op.clone();
+                op.map_registers(rename);
+                op
+            })
+            .collect();
+
+        let knobs = self
+            .knobs
+            .iter()
+            .filter_map(|(&register, knob)| {
+                let register = map.get(register)?;
+                let mut knob = knob.clone();
+
+                if let KnobKind::Buffer { buffer, .. } = &mut knob {
+                    rename(buffer);
+                }
+
+                Some((register, knob))
+            })
+            .collect();
+
+        let dithered = self
+            .dithered
+            .iter()
+            .filter_map(|&register| map.get(register))
+            .collect();
+
+        let in_place = self
+            .in_place
+            .iter()
+            .filter_map(|(&result, &src)| Some((map.get(result)?, map.get(src)?)))
+            .collect();
+
+        let pruned = CommandBuffer {
+            ops,
+            vars: self.vars.clone(),
+            symbols: self.symbols.clone(),
+            tys: self.tys.clone(),
+            knobs,
+            dithered,
+            in_place,
+        };
+
+        (pruned, map)
+    }
+
+    /// Merge operations which compute the same result, then [`Self::prune`] the duplicates.
+    ///
+    /// Two operations are the same if they have equal parameters and read the same registers,
+    /// after those have been merged themselves. Operations with a knob are never merged since
+    /// their parameters change with each launch, nor are user supplied shaders and invocations.
+    /// The returned map translates the registers of this buffer, where each duplicate is mapped
+    /// to the register of its first occurrence.
+    pub fn deduplicate(&self) -> (CommandBuffer, RegisterMap) {
+        let mut canonical: Vec<Register> = (0..self.ops.len()).map(Register).collect();
+        let mut candidates: HashMap<core::mem::Discriminant<Op>, Vec<usize>> = HashMap::new();
+        let mut ops = Vec::with_capacity(self.ops.len());
+
+        for (idx, op) in self.ops.iter().enumerate() {
+            let mut op = op.clone();
+            op.map_registers(|reg| *reg = canonical[reg.0]);
+
+            let mergeable = matches!(
+                op,
+                Op::Construct { .. }
+                    | Op::Unary { .. }
+                    | Op::Binary { .. }
+                    | Op::BufferInit { .. }
+                    | Op::BufferUnary { .. }
+                    | Op::BufferBinary { .. }
+            ) && !self.knobs.contains_key(&Register(idx));
+
+            if mergeable {
+                let dithered = self.dithered.contains(&Register(idx));
+                let same = candidates.entry(core::mem::discriminant(&op)).or_default();
+
+                let first = same.iter().copied().find(|&first| {
+                    ops[first] == op && self.dithered.contains(&Register(first)) == dithered
+                });
+
+                match first {
+                    Some(first) => canonical[idx] = Register(first),
+                    None => same.push(idx),
+                }
+            }
+
+            ops.push(op);
+        }
+
+        let merged = CommandBuffer {
+            ops,
+            vars: self.vars.clone(),
+            symbols: self.symbols.clone(),
+            tys: self.tys.clone(),
+            knobs: self
+                .knobs
+                .iter()
+                .map(|(&register, knob)| (register, knob.clone()))
                 .collect(),
-            output: self
-                .ops
+            dithered: self.dithered.clone(),
+            // A merged result is painted by its first occurrence, which may not be hinted.
+            in_place: self
+                .in_place
                 .iter()
-                .filter_map(|op| {
-                    if let &Op::Output { src } = op {
-                        Some(
-                            // FIXME: non-texture output.
-                            self.describe_reg(src)
-                                .as_texture()
-                                .expect("Validated when creating output")
-                                .clone(),
-                        )
-                    } else {
-                        None
-                    }
-                })
+                .filter(|(&result, _)| canonical[result.0] == result)
+                .map(|(&result, &src)| (result, src))
                 .collect(),
+        };
+
+        let (pruned, pruned_map) = merged.prune();
+        let registers = canonical
+            .into_iter()
+            .map(|register| pruned_map.get(register))
+            .collect();
+
+        (pruned, RegisterMap { registers })
+    }
+
+    /// Check the invariants which the linker relies on, reporting every violation.
+    ///
+    /// The methods of the command buffer maintain these by construction, this is for buffers from
+    /// elsewhere such as a deserialization. Operands must precede their use and hold an image or
+    /// buffer as required, descriptors refer only to declared generics, knobs and dithering refer
+    /// to operations which have them, and the results of an invocation precede the invocation
+    /// which lists them.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = vec![];
+        let mut report = |register, result: Result<(), CommandError>| {
+            if let Err(error) = result {
+                errors.push(ValidationError { register, error });
+            }
+        };
+
+        for signature in &self.symbols {
+            for desc in signature.input.iter().chain(&signature.output) {
+                report(None, validate_descriptor(desc, signature.vars.len()));
+            }
+
+            for desc in signature
+                .buffer_input
+                .iter()
+                .chain(&signature.buffer_output)
+            {
+                report(None, validate_buffer(desc, signature.vars.len()));
+            }
+        }
+
+        for ty in &self.tys {
+            report(None, validate_descriptor(ty, self.vars.len()));
+        }
+
+        for (idx, op) in self.ops.iter().enumerate() {
+            report(Some(Register(idx)), self.validate_op(idx, op));
+
+            // Only now is the invocation following the result known.
+            if let &Op::InvokedResult { invocation, .. } | &Op::InvokedBuffer { invocation, .. } =
+                op
+            {
+                let listed = match self.ops.get(invocation.0) {
+                    Some(Op::Invoke { results, .. }) => results.contains(&Register(idx)),
+                    _ => false,
+                };
+
+                if !listed {
+                    report(
+                        Some(Register(idx)),
+                        Err(CommandError::bad_register(invocation)),
+                    );
+                }
+            }
+        }
+
+        let mut knobs: Vec<_> = self.knobs.iter().collect();
+        knobs.sort_by_key(|(register, _)| register.0);
+
+        for (&register, knob) in knobs {
+            report(Some(register), self.validate_knob(register, knob));
+        }
+
+        let mut dithered: Vec<_> = self.dithered.iter().copied().collect();
+        dithered.sort_by_key(|register| register.0);
+
+        for register in dithered {
+            let result = self.texture_of(register).map(|_| ());
+            report(Some(register), result);
+        }
+
+        let mut in_place: Vec<_> = self.in_place.iter().collect();
+        in_place.sort_by_key(|(result, _)| result.0);
+
+        for (&result, &src) in in_place {
+            report(Some(result), self.validate_in_place(src, result));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Calculate the signature based on generics, inputs, outputs.
+    pub fn computed_signature(&self) -> CommandSignature {
+        let mut signature = CommandSignature {
+            name: None,
+            vars: self.vars.clone(),
+            input: vec![],
+            output: vec![],
+            buffer_input: vec![],
+            buffer_output: vec![],
+        };
+
+        for op in &self.ops {
+            match op {
+                Op::Input { desc } => signature.input.push(desc.clone()),
+                Op::BufferInput { desc } => signature.buffer_input.push(desc.clone()),
+                &Op::Output { src } => match self.describe_reg(src) {
+                    RegisterDescription::Texture(desc) => signature.output.push(desc.clone()),
+                    RegisterDescription::Buffer(desc) => signature.buffer_output.push(desc.clone()),
+                    RegisterDescription::None => unreachable!("Validated when creating output"),
+                },
+                _ => {}
+            }
         }
+
+        signature
     }
 
     /// Declare a function that is later linked in.
@@ -859,6 +2527,18 @@ impl CommandBuffer {
         Ok(symbol)
     }
 
+    /// Declare a function by a name, such as `std/blur/gaussian`.
+    ///
+    /// The linker resolves the name to one of its definitions, see [`Linker::link_by_name`].
+    pub fn function_named(
+        &mut self,
+        name: &str,
+        mut signature: CommandSignature,
+    ) -> Result<FunctionVar, CommandError> {
+        signature.name = Some(name.to_owned());
+        self.function(signature)
+    }
+
     /// Declare an invocation of a separate, possibly generic, function.
     pub fn invoke(
         &mut self,
@@ -868,14 +2548,25 @@ impl CommandBuffer {
         let signature = self
             .symbols
             .get(function.0)
-            .ok_or(CommandError::BAD_REGISTER)?;
+            .ok_or(CommandError::BAD_VARIABLE)?;
 
-        if signature.input.len() != invoke.arguments.len() {
-            return Err(CommandError::INVALID_CALL);
+        let arity = signature.input.len() + signature.buffer_input.len();
+        if arity != invoke.arguments.len() {
+            return Err(CommandError {
+                inner: CommandErrorKind::ArityMismatch {
+                    expected: arity,
+                    actual: invoke.arguments.len(),
+                },
+            });
         }
 
         if signature.vars.len() != invoke.generics.len() {
-            return Err(CommandError::INVALID_CALL);
+            return Err(CommandError {
+                inner: CommandErrorKind::ArityMismatch {
+                    expected: signature.vars.len(),
+                    actual: invoke.generics.len(),
+                },
+            });
         }
 
         let generics: Vec<_> = invoke
@@ -883,25 +2574,56 @@ impl CommandBuffer {
             .iter()
             .map(|&DescriptorVar(idx)| self.tys.get(idx).cloned())
             .collect::<Option<_>>()
-            .ok_or(CommandError::BAD_REGISTER)?;
+            .ok_or(CommandError::BAD_VARIABLE)?;
 
-        for (tyvar, tyarg) in signature.vars.iter().zip(invoke.generics) {
-            // FIXME: fully validate generics. We need to know specifics about a type variable, in
-            // particular its locally known bounds, and a system to query these bounds. We just
-            // incidentally do not yet have any such bounds.
-            let _ = (tyvar, tyarg);
+        for (tyvar, tyarg) in signature.vars.iter().zip(&generics) {
+            if !tyvar.is_satisfied_by(tyarg, &self.vars) {
+                return Err(CommandError::TYPE_ERR);
+            }
         }
 
-        for (param, arg) in signature.input.iter().zip(invoke.arguments) {
+        let (images, buffers) = invoke.arguments.split_at(signature.input.len());
+
+        for (param, arg) in signature.input.iter().zip(images) {
             let expected = param.rewrite(&generics);
-            let arg_ty = self.describe_reg(*arg).as_texture()?;
+            let arg_ty = self.texture_of(*arg)?;
 
             if expected != *arg_ty {
-                return Err(CommandError::INVALID_CALL);
+                return Err(CommandError {
+                    inner: CommandErrorKind::ArgumentMismatch {
+                        argument: *arg,
+                        actual: arg_ty.clone(),
+                        expected,
+                    },
+                });
+            }
+        }
+
+        for (param, arg) in signature.buffer_input.iter().zip(buffers) {
+            let arg_ty = self.buffer_of(*arg)?;
+
+            if *param != *arg_ty {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BufferMismatch {
+                        argument: *arg,
+                        actual: arg_ty.clone(),
+                        expected: param.clone(),
+                    },
+                });
             }
         }
 
-        let invocation = Register(self.ops.len() + signature.output.len());
+        let buffer_results = signature
+            .buffer_output
+            .iter()
+            .map(|output| output.rewrite(&generics))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(CommandError {
+                inner: CommandErrorKind::ConcreteDescriptorRequired,
+            })?;
+
+        let invocation =
+            Register(self.ops.len() + signature.output.len() + signature.buffer_output.len());
 
         let mut results = vec![];
         for (_result_idx, output) in signature.output.iter().enumerate() {
@@ -911,16 +2633,113 @@ impl CommandBuffer {
             self.ops.push(Op::InvokedResult { invocation, desc });
         }
 
+        for desc in buffer_results {
+            results.push(Register(self.ops.len()));
+            self.ops.push(Op::InvokedBuffer { invocation, desc });
+        }
+
         self.ops.push(Op::Invoke {
             function,
             arguments: invoke.arguments.to_vec(),
             results: results.clone(),
             generics,
+            repeat: None,
         });
 
         Ok(results)
     }
 
+    /// Apply a function a fixed number of times, each time to the result of the previous one.
+    ///
+    /// The function must have exactly one image parameter and one image result, with the same
+    /// descriptor. A generic function may have a single generic parameter if it determines that
+    /// descriptor, it is instantiated with the descriptor of `seed`. With a `count` of zero the
+    /// seed itself is returned.
+    ///
+    /// The applications are not unrolled into separate registers. Instead, the linker alternates
+    /// between the texture of the result and one other texture, regardless of the count.
+    pub fn repeat(
+        &mut self,
+        count: u32,
+        body: FunctionVar,
+        seed: Register,
+    ) -> Result<Register, CommandError> {
+        let signature = self.symbols.get(body.0).ok_or(CommandError::BAD_VARIABLE)?;
+        let desc = self.texture_of(seed)?.clone();
+        Self::validate_repeat(signature)?;
+
+        let generics = match signature.vars.len() {
+            0 => vec![],
+            1 if signature.input[0].single_var() == Some(GenericVar(0)) => {
+                vec![desc.clone()]
+            }
+            actual => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::ArityMismatch {
+                        expected: 0,
+                        actual,
+                    },
+                })
+            }
+        };
+
+        for (tyvar, tyarg) in signature.vars.iter().zip(&generics) {
+            if !tyvar.is_satisfied_by(tyarg, &self.vars) {
+                return Err(CommandError::TYPE_ERR);
+            }
+        }
+
+        let expected = signature.input[0].rewrite(&generics);
+
+        if expected != desc {
+            return Err(CommandError {
+                inner: CommandErrorKind::ArgumentMismatch {
+                    argument: seed,
+                    actual: desc,
+                    expected,
+                },
+            });
+        }
+
+        if count == 0 {
+            return Ok(seed);
+        }
+
+        let result = Register(self.ops.len());
+        let invocation = Register(self.ops.len() + 1);
+
+        self.ops.push(Op::InvokedResult { invocation, desc });
+        self.ops.push(Op::Invoke {
+            function: body,
+            arguments: vec![seed],
+            results: vec![result],
+            generics,
+            repeat: Some(count),
+        });
+
+        Ok(result)
+    }
+
+    /// Check that a function maps one image to another of the same descriptor.
+    fn validate_repeat(signature: &CommandSignature) -> Result<(), CommandError> {
+        for (expected, actual) in [
+            (1, signature.input.len() + signature.buffer_input.len()),
+            (1, signature.output.len() + signature.buffer_output.len()),
+        ] {
+            if expected != actual {
+                return Err(CommandError {
+                    inner: CommandErrorKind::ArityMismatch { expected, actual },
+                });
+            }
+        }
+
+        if signature.input != signature.output {
+            return Err(CommandError::TYPE_ERR);
+        }
+
+        Ok(())
+    }
+
     /// Declare an image as input.
     ///
     /// Returns its register if the image has a valid descriptor, otherwise panics.
@@ -939,9 +2758,22 @@ impl CommandBuffer {
         buffer_reg: Register,
         descriptor: Descriptor,
     ) -> Result<Register, CommandError> {
-        let RegisterDescription::Buffer(buffer) = self.describe_reg(buffer_reg) else {
-            return Err(CommandError::TYPE_ERR);
-        };
+        self.from_buffer_at(buffer_reg, 0, descriptor)
+    }
+
+    /// Construct an image from the bytes of a buffer, starting at an offset.
+    ///
+    /// See [`Self::from_buffer`] for the layout of the bytes. The offset must be a multiple of
+    /// the copy alignment of four bytes, and the image must end within the buffer. Use
+    /// [`WithKnob::from_buffer_at`] to choose the offset when the program is launched, for
+    /// instance to select one of multiple frames in the same buffer.
+    pub fn from_buffer_at(
+        &mut self,
+        buffer_reg: Register,
+        offset: u64,
+        descriptor: Descriptor,
+    ) -> Result<Register, CommandError> {
+        let buffer = self.buffer_of(buffer_reg)?;
 
         let gpu_layout = descriptor
             .to_aligned()
@@ -951,8 +2783,16 @@ impl CommandBuffer {
             .checked_mul(gpu_layout.row_stride)
             .ok_or_else(|| CommandError::TYPE_ERR)?;
 
+        let end = offset
+            .checked_add(required_size)
+            .ok_or_else(|| CommandError::INVALID_CALL)?;
+
+        if offset % wgpu::COPY_BUFFER_ALIGNMENT != 0 {
+            return Err(CommandError::misaligned_range(offset..end));
+        }
+
         match buffer.size {
-            Generic::Concrete(sz) if sz >= required_size => {}
+            Generic::Concrete(sz) if sz >= end => {}
             Generic::Concrete(_) => {
                 return Err(CommandError::INVALID_CALL);
             }
@@ -963,13 +2803,13 @@ impl CommandBuffer {
 
         Ok(self.push(Op::Construct {
             desc: descriptor.into(),
-            op: ConstructOp::FromBuffer(buffer_reg),
+            op: ConstructOp::FromBuffer(buffer_reg, offset),
         }))
     }
 
     /// Select a rectangular part of an image.
     pub fn crop(&mut self, src: Register, rect: Rectangle) -> Result<Register, CommandError> {
-        let desc = self.describe_reg(src).as_texture()?.clone();
+        let desc = self.texture_of(src)?.clone();
         Ok(self.push(Op::Unary {
             src,
             op: UnaryOp::Crop(rect),
@@ -979,9 +2819,33 @@ impl CommandBuffer {
 
     /// Create an image with different color encoding.
     ///
-    /// This goes through linear RGB, not ICC, and requires the two models to have same whitepoint.
+    /// This goes through linear RGB, not ICC. Conversions between RGB and the other color models
+    /// require the RGB color to have the whitepoint which the model assumes, if any.
     ///
-    /// Note that this is not a generic operation. It selects the conversion based on the input
+    /// CIELAB has no color of its own, see [`Self::color_convert_cielab`].
+    ///
+    /// The Lab-like colors, Oklab, SrLab2 and CIELAB, can also be stored in polar form with an
+    /// `Lch` or `LchA` texel, such as Oklch. The chroma is the length of the `ab` vector and the
+    /// hue its angle in turns, normalized to `[0, 1)` such that it fits unsigned texels. A hue of
+    /// `0.5` is the direction of positive `a`, that is `h = h°/360 + 1/2` modulo one for the
+    /// angle `h°` in degrees. Note that this is half a turn off the hue of CSS `oklch()` and
+    /// `lch()`, where positive `a` is at 0°. Hues outside that range wrap around when read from
+    /// float texels. All commands operate on the cartesian form, the polar form is a matter of
+    /// storage.
+    ///
+    /// The HDR transfer functions have their own luminance semantics. Linear values of PQ as in
+    /// [`Transfer::Smpte2084`] are display light, where `1.0` is the absolute luminance of
+    /// 10000 cd/m². Those of [`Transfer::Bt2100Pq`] are scene light through the reference OOTF,
+    /// where `1.0` is displayed at that same peak. HLG of [`Transfer::Bt2100Hlg`] is relative
+    /// scene light. Converting between these and other transfers maps the reference white of each
+    /// onto the other, see [`HdrLuminance`] and [`Self::color_convert_with`] to choose it for PQ.
+    /// Other conversions treat linear values as relative to white.
+    ///
+    /// RGB colors of different whitepoints are converted with a chromatic adaptation in XYZ, by
+    /// default the Bradford transform. This is the same as [`Self::chromatic_adaptation`] to the
+    /// target whitepoint followed by the conversion, in a single pass.
+    ///
+    /// Note that this is not a generic operation. It selects the conversion based on the input
     /// type which requires it to have a concrete descriptor.
     pub fn color_convert(
         &mut self,
@@ -989,98 +2853,437 @@ impl CommandBuffer {
         color: Color,
         texel: Texel,
     ) -> Result<Register, CommandError> {
-        let desc_src = self.describe_reg(src).as_texture()?;
-        let conversion;
+        self.color_convert_with(src, color, texel, ColorConvertOptions::default())
+    }
+
+    /// Convert between RGB colors through linear light, and map that into the target gamut.
+    fn color_convert_gamut_mapped(
+        &mut self,
+        src: Register,
+        color: Color,
+        texel: Texel,
+        options: ColorConvertOptions,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+        let (width, height) = desc_src.size();
+
+        let (primary, whitepoint) = match color {
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } => (primary, whitepoint),
+            _ => {
+                return Err(CommandError::unsupported_conversion(
+                    src,
+                    desc_src.color,
+                    color,
+                ))
+            }
+        };
+
+        let mut linear_color = color.clone();
+        if let Color::Rgb { transfer, .. } = &mut linear_color {
+            *transfer = Transfer::Linear;
+        }
+
+        // Floating point texels keep the colors outside the gamut until they are mapped.
+        let float = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let mapping = options.gamut;
+        let dither = options.dither;
+        let options = ColorConvertOptions {
+            gamut: GamutMapping::Clip,
+            dither: false,
+            ..options
+        };
+
+        let linear = self.color_convert_with(src, linear_color, float, options)?;
+
+        // Oklab is relative to D65.
+        let to_xyz = RowMatrix(primary.to_xyz_row_matrix(whitepoint));
+        let adaptation = RowMatrix::bradford(whitepoint.to_xyz(), Whitepoint::D65.to_xyz());
+        let to_xyz = adaptation.multiply_right(to_xyz.into()).into();
+
+        let desc = Descriptor {
+            color,
+            ..Descriptor::with_texel(texel.clone(), width, height)
+                .ok_or(CommandError::INVALID_CALL)?
+        };
+
+        let op = Op::Unary {
+            src: linear,
+            op: UnaryOp::GamutMap { mapping, to_xyz },
+            desc: desc.into(),
+        };
+
+        let register = self.push(op);
+        self.dither_if(register, dither, &texel);
+        Ok(register)
+    }
+
+    /// Convert between two colors that are not RGB, by decoding into linear RGB first.
+    ///
+    /// The intermediate has floating point texels, such that it neither clips nor quantizes the
+    /// colors. Its whitepoint is chosen such that neither pass needs a further adaptation.
+    fn color_convert_through_rgb(
+        &mut self,
+        src: Register,
+        (source, target): (XyzRelation, XyzRelation),
+        color: Color,
+        texel: Texel,
+        options: ColorConvertOptions,
+    ) -> Result<Register, CommandError> {
+        let primary = Primaries::Bt709;
+        let whitepoint = [source.whitepoint(), target.whitepoint()]
+            .into_iter()
+            .find(|&whitepoint| {
+                source.decode_into_rgb(primary, whitepoint).is_some()
+                    && target.encode_from_rgb(primary, whitepoint).is_some()
+            })
+            .ok_or(CommandError::UNIMPLEMENTED)?;
+
+        let float = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let rgb = XyzRelation::Rgb {
+            primary,
+            whitepoint,
+            transfer: Transfer::Linear,
+        };
+
+        let mut linear_color = Color::SRGB;
+        if let Color::Rgb {
+            primary: p,
+            whitepoint: w,
+            transfer,
+            ..
+        } = &mut linear_color
+        {
+            *p = primary;
+            *w = whitepoint;
+            *transfer = Transfer::Linear;
+        }
+
+        let to_linear = ColorConvertOptions {
+            dither: false,
+            ..options.clone()
+        };
+
+        let linear =
+            self.color_convert_relation(src, (source, rgb), linear_color, float, to_linear)?;
+        self.color_convert_relation(linear, (rgb, target), color, texel, options)
+    }
+
+    /// Create an image in CIELAB, relative to the given whitepoint.
+    ///
+    /// CIELAB is not a [`Color`] of the descriptor. The result holds its coordinates as
+    /// [`Color::Scalars`] of a linear transfer in a `LabA` texel, or as LCh in an `LchA` texel,
+    /// and the whitepoint is part of the conversion instead. The source color is adapted to the
+    /// whitepoint with the Bradford transform. Coordinates are stored divided by 100, the
+    /// lightness `L*` in `[0, 1]` and `a*`, `b*` in roughly `[-1, 1]`. Use floating point texels
+    /// for saturated colors, whose chroma exceeds one.
+    ///
+    /// Convert back with [`Self::color_convert_from_cielab`] and the same whitepoint.
+    pub fn color_convert_cielab(
+        &mut self,
+        src: Register,
+        whitepoint: Whitepoint,
+        texel: Texel,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if !matches!(texel.parts, SampleParts::LabA | SampleParts::LchA) {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "CIELAB in a non-Lab texel",
+                ),
+            });
+        }
+
+        let source = XyzRelation::of(&desc_src.color).ok_or_else(|| {
+            CommandError::unsupported_conversion(src, desc_src.color.clone(), Self::CIELAB)
+        })?;
+
+        let target = XyzRelation::Lab { whitepoint };
+        let options = ColorConvertOptions::default();
+        self.color_convert_relation(src, (source, target), Self::CIELAB, texel, options)
+    }
+
+    /// Create an image with a color encoding, from CIELAB relative to the given whitepoint.
+    ///
+    /// The source holds the coordinates as [`Color::Scalars`] of a linear transfer in a `LabA` or
+    /// `LchA` texel, as created by [`Self::color_convert_cielab`]. The whitepoint is not recorded
+    /// in the image and must match the one it was encoded with.
+    pub fn color_convert_from_cielab(
+        &mut self,
+        src: Register,
+        whitepoint: Whitepoint,
+        color: Color,
+        texel: Texel,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if desc_src.color != Self::CIELAB
+            || !matches!(desc_src.texel.parts, SampleParts::LabA | SampleParts::LchA)
+        {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "CIELAB as non-Lab scalars",
+                ),
+            });
+        }
+
+        let target = XyzRelation::of(&color).ok_or_else(|| {
+            CommandError::unsupported_conversion(src, desc_src.color.clone(), color.clone())
+        })?;
+
+        let source = XyzRelation::Lab { whitepoint };
+        let options = ColorConvertOptions::default();
+        self.color_convert_relation(src, (source, target), color, texel, options)
+    }
+
+    /// Mark a register to be dithered when quantized, unless its texel is floating point.
+    fn dither_if(&mut self, register: Register, dither: bool, texel: &Texel) {
+        let is_float = matches!(texel.bits, SampleBits::Float16x4 | SampleBits::Float32x4);
+
+        if dither && !is_float {
+            self.dithered.insert(register);
+        }
+    }
+
+    /// Convert an image into linear light of the given primaries and whitepoint.
+    ///
+    /// This is [`Self::color_convert`] into an RGB color with [`Transfer::Linear`], the values
+    /// are stored as they are computed without re-applying any transfer function. The descriptor
+    /// of the result records the linear transfer, such that later commands do not linearize it a
+    /// second time. Float texels, `Float16x4` and `Float32x4`, keep the precision of dark tones
+    /// and values beyond the unit range as scene light.
+    pub fn color_convert_linear(
+        &mut self,
+        src: Register,
+        primary: Primaries,
+        whitepoint: Whitepoint,
+        texel: Texel,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let mut color = Color::SRGB;
+        if let Color::Rgb {
+            primary: p,
+            whitepoint: w,
+            transfer,
+            luminance,
+        } = &mut color
+        {
+            *p = primary;
+            *w = whitepoint;
+            *transfer = Transfer::Linear;
+
+            if let Color::Rgb {
+                luminance: source, ..
+            } = desc_src.color
+            {
+                *luminance = source;
+            }
+        }
+
+        self.color_convert(src, color, texel)
+    }
+
+    /// Create an image with different color encoding, with explicit options.
+    ///
+    /// See [`Self::color_convert`]. The options choose the reference white of PQ, the method of
+    /// adapting between whitepoints, and the mapping of RGB colors into the target gamut. A
+    /// gamut mapping other than [`GamutMapping::Clip`] converts into linear light of floating
+    /// point texels first and then maps and encodes the colors in a second pass.
+    pub fn color_convert_with(
+        &mut self,
+        src: Register,
+        color: Color,
+        texel: Texel,
+        options: ColorConvertOptions,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
         let desc_src = desc_src.as_concrete().ok_or(CommandError {
             inner: CommandErrorKind::ConcreteDescriptorRequired,
         })?;
 
+        let is_polar = matches!(texel.parts, SampleParts::Lch | SampleParts::LchA);
+        let has_chroma_plane = matches!(color, Color::Oklab | Color::SrLab2 { .. });
+
+        if is_polar && !has_chroma_plane {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "polar texel for a color without a chroma plane",
+                ),
+            });
+        }
+
+        if let (Color::Rgb { .. }, Color::Rgb { .. }) = (&desc_src.color, &color) {
+            if options.gamut != GamutMapping::Clip {
+                return self.color_convert_gamut_mapped(src, color, texel, options);
+            }
+        }
+
+        // The non-linear adaptation is not a matrix, adapt first and then convert.
+        if let (
+            Color::Rgb {
+                whitepoint: wp_src, ..
+            },
+            Color::Rgb {
+                whitepoint: wp_dst, ..
+            },
+        ) = (&desc_src.color, &color)
+        {
+            if wp_src != wp_dst
+                && matches!(
+                    options.adaptation,
+                    ChromaticAdaptationMethod::BradfordNonLinear
+                )
+            {
+                let target = *wp_dst;
+                let adapted = self.chromatic_adaptation(src, options.adaptation.clone(), target)?;
+                return self.color_convert_with(adapted, color, texel, options);
+            }
+        }
+
         // Pretend that all colors with the same whitepoint will be mapped from encoded to
         // linear RGB when loading, and re-encoded in target format when storing them. This is
         // almost correct, but not all GPUs will support all texel kinds. In particular
         // some channel orders or bit-field channels are likely to be unsupported. In these
         // cases, we will later add some temporary conversion.
         //
-        // FIXME: this is growing a bit ugly with non-rgb color spaces. We should find a more
-        // general way to handle these, and in particular also handle non-rgb-to-non-rgb because
-        // that's already happening in the command encoder anyways..
+        // Every color relates to linear RGB, and thus XYZ, by one pass in either direction. Two
+        // RGB colors fuse into a single matrix, all other pairs are composed from a decoding into
+        // and an encoding out of linear RGB.
+        let no_conversion =
+            || CommandError::unsupported_conversion(src, desc_src.color.clone(), color.clone());
+
+        let source = XyzRelation::of(&desc_src.color).ok_or_else(no_conversion)?;
+        let target = XyzRelation::of(&color).ok_or_else(no_conversion)?;
+
+        self.color_convert_relation(src, (source, target), color, texel, options)
+    }
+
+    /// Convert between two colors by their relation to XYZ.
+    ///
+    /// The colors are those of the source descriptor and `color` of the result, unless either is
+    /// CIELAB which is stored as scalars and only known by its relation.
+    fn color_convert_relation(
+        &mut self,
+        src: Register,
+        (source, target): (XyzRelation, XyzRelation),
+        color: Color,
+        texel: Texel,
+        options: ColorConvertOptions,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+        let conversion;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        match (&desc_src.color, &color) {
+        let no_conversion =
+            || CommandError::unsupported_conversion(src, desc_src.color.clone(), color.clone());
+
+        match (source, target) {
             (
-                Color::Rgb {
+                XyzRelation::Rgb {
                     primary: primary_src,
                     whitepoint: wp_src,
-                    ..
+                    transfer: transfer_src,
                 },
-                Color::Rgb {
+                XyzRelation::Rgb {
                     primary: primary_dst,
                     whitepoint: wp_dst,
-                    ..
-                },
-            ) if wp_src == wp_dst => {
-                conversion = ColorConversion::Xyz {
-                    from_xyz_matrix: RowMatrix(primary_src.to_xyz_row_matrix(*wp_src)),
-                    to_xyz_matrix: RowMatrix(primary_dst.to_xyz_row_matrix(*wp_dst)),
-                };
-            }
-            (
-                Color::Rgb {
-                    primary,
-                    whitepoint: Whitepoint::D65,
-                    ..
+                    transfer: transfer_dst,
                 },
-                Color::Oklab,
             ) => {
-                conversion = ColorConversion::XyzToOklab {
-                    to_xyz_matrix: RowMatrix(primary.to_xyz_row_matrix(Whitepoint::D65)),
+                let adaptation = if wp_src == wp_dst {
+                    RowMatrix::diag(1.0, 1.0, 1.0)
+                } else {
+                    let adaptation = ChromaticAdaptation {
+                        to_xyz_matrix: RowMatrix::diag(1.0, 1.0, 1.0),
+                        source: wp_src,
+                        method: options.adaptation.clone(),
+                        from_xyz_matrix: RowMatrix::diag(1.0, 1.0, 1.0),
+                        target: wp_dst,
+                    };
+
+                    RowMatrix::new(
+                        adaptation
+                            .to_matrix()
+                            .map_err(|_| CommandError::UNIMPLEMENTED)?,
+                    )
                 };
-            }
-            (
-                Color::Oklab,
-                Color::Rgb {
-                    primary,
-                    whitepoint: Whitepoint::D65,
-                    ..
-                },
-            ) => {
-                conversion = ColorConversion::OklabToXyz {
-                    from_xyz_matrix: RowMatrix(primary.to_xyz_row_matrix(Whitepoint::D65)),
+
+                conversion = ColorConversion::Xyz {
+                    to_xyz_matrix: RowMatrix(primary_src.to_xyz_row_matrix(wp_src)),
+                    from_xyz_matrix: RowMatrix(primary_dst.to_xyz_row_matrix(wp_dst)),
+                    adaptation,
+                    scale: options.luminance.relative_white(transfer_dst)?
+                        / options.luminance.relative_white(transfer_src)?,
                 };
             }
             (
-                Color::Rgb {
+                XyzRelation::Rgb {
                     primary,
-                    whitepoint: rgb_wp,
+                    whitepoint,
                     ..
                 },
-                Color::SrLab2 { whitepoint },
+                target,
             ) => {
-                conversion = ColorConversion::XyzToSrLab2 {
-                    to_xyz_matrix: RowMatrix(primary.to_xyz_row_matrix(*rgb_wp)),
-                    whitepoint: *whitepoint,
-                };
+                conversion = target
+                    .encode_from_rgb(primary, whitepoint)
+                    .ok_or_else(no_conversion)?;
             }
             (
-                Color::SrLab2 { whitepoint },
-                Color::Rgb {
+                source,
+                XyzRelation::Rgb {
                     primary,
-                    whitepoint: rgb_wp,
+                    whitepoint,
                     ..
                 },
             ) => {
-                conversion = ColorConversion::SrLab2ToXyz {
-                    from_xyz_matrix: RowMatrix(primary.to_xyz_row_matrix(*rgb_wp)),
-                    whitepoint: *whitepoint,
-                };
+                conversion = source
+                    .decode_into_rgb(primary, whitepoint)
+                    .ok_or_else(no_conversion)?;
             }
-            _ => {
-                return Err(CommandError {
-                    inner: CommandErrorKind::BadDescriptor(
-                        desc_src.clone().into(),
-                        "No conversion",
-                    ),
-                })
+            (source, target) => {
+                return self.color_convert_through_rgb(
+                    src,
+                    (source, target),
+                    color,
+                    texel,
+                    options,
+                );
             }
         }
 
@@ -1098,24 +3301,32 @@ impl CommandBuffer {
             desc: Descriptor {
                 color,
                 layout,
-                texel,
+                texel: texel.clone(),
             }
             .into(),
         };
 
-        Ok(self.push(op))
+        let register = self.push(op);
+        self.dither_if(register, options.dither, &texel);
+        Ok(register)
     }
 
     /// Perform a whitepoint adaptation.
     ///
     /// The `function` describes the method and target whitepoint of the chromatic adaptation.
+    ///
+    /// Images in `SrLab2` are decoded to XYZ, adapted, and re-encoded relative to the target
+    /// whitepoint, for CIELAB see [`Self::chromatic_adaptation_cielab`]. `Oklab` has no whitepoint of its own, it is always relative to D65, so
+    /// the adapted colors are encoded as they are and white takes on the tint of the target. RGB
+    /// images of only a `Luma` channel hold no chromaticity, their adaptation degenerates to the
+    /// scale between the luminance of the two whitepoints.
     pub fn chromatic_adaptation(
         &mut self,
         src: Register,
         method: ChromaticAdaptationMethod,
         target: Whitepoint,
     ) -> Result<Register, CommandError> {
-        let desc_src = self.describe_reg(src).as_texture()?;
+        let desc_src = self.texture_of(src)?;
         let texel_color;
         let source_wp;
         let (to_xyz_matrix, from_xyz_matrix);
@@ -1125,6 +3336,35 @@ impl CommandBuffer {
         })?;
 
         match desc_src.color {
+            Color::Rgb {
+                whitepoint,
+                primary,
+                transfer,
+                luminance,
+            } if matches!(desc_src.texel.parts, SampleParts::Luma | SampleParts::LumaA) => {
+                let desc = Descriptor {
+                    color: Color::Rgb {
+                        whitepoint: target,
+                        primary,
+                        transfer,
+                        luminance,
+                    },
+                    ..desc_src.clone()
+                };
+
+                // Any of the methods maps the source white onto the target white.
+                let scale = target.to_xyz()[1] / whitepoint.to_xyz()[1];
+                let op = Op::Unary {
+                    src,
+                    op: UnaryOp::AffineColor {
+                        matrix: RowMatrix::diag(scale, scale, scale),
+                        offset: [0.0; 3],
+                    },
+                    desc: desc.into(),
+                };
+
+                return Ok(self.push(op));
+            }
             Color::Rgb {
                 whitepoint,
                 primary,
@@ -1142,6 +3382,22 @@ impl CommandBuffer {
                 from_xyz_matrix = RowMatrix(primary.from_xyz_row_matrix(target));
                 source_wp = whitepoint;
             }
+            Color::SrLab2 { whitepoint } => {
+                let color = Color::SrLab2 { whitepoint: target };
+                let desc_src = desc_src.clone();
+                return self.adapt_through_rgb(src, &desc_src, method, whitepoint, target, color);
+            }
+            Color::Oklab => {
+                let desc_src = desc_src.clone();
+                return self.adapt_through_rgb(
+                    src,
+                    &desc_src,
+                    method,
+                    Whitepoint::D65,
+                    target,
+                    Color::Oklab,
+                );
+            }
             // Forward compatibility.
             _ => {
                 return Err(CommandError {
@@ -1173,2526 +3429,11638 @@ impl CommandBuffer {
         Ok(self.push(op))
     }
 
-    /// Embed this image as part of a larger one.
-    pub fn inscribe(
+    /// Perform a whitepoint adaptation of CIELAB coordinates.
+    ///
+    /// The source holds CIELAB relative to the `source` whitepoint, see
+    /// [`Self::color_convert_cielab`]. It is decoded, adapted in linear RGB of floating point
+    /// texels, and encoded relative to the `target` whitepoint in the texel of the source.
+    pub fn chromatic_adaptation_cielab(
         &mut self,
-        below: Register,
-        rect: Rectangle,
-        above: Register,
+        src: Register,
+        method: ChromaticAdaptationMethod,
+        source: Whitepoint,
+        target: Whitepoint,
     ) -> Result<Register, CommandError> {
-        let desc_below = self.describe_reg(below).as_texture()?;
-        let desc_above = self.describe_reg(above).as_texture()?;
-
-        if desc_above.descriptor_chroma() != desc_below.descriptor_chroma() {
-            return Err(CommandError {
-                inner: CommandErrorKind::ConflictingTypes(desc_below.clone(), desc_above.clone()),
-            });
-        }
+        let desc_src = self.texture_of(src)?;
 
-        let desc_above = desc_above.as_concrete().ok_or(CommandError {
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
             inner: CommandErrorKind::ConcreteDescriptorRequired,
         })?;
 
-        if Rectangle::with_layout(&desc_above.layout) != rect {
-            return Err(CommandError::OTHER);
+        let mut linear_rgb = Color::SRGB;
+        if let Color::Rgb {
+            whitepoint,
+            transfer,
+            ..
+        } = &mut linear_rgb
+        {
+            *whitepoint = source;
+            *transfer = Transfer::Linear;
         }
 
-        // This is pretty much lint status, actually. Nothing intensely bad happens if we paint
-        // outside the image, we could just paint less of it.
-        if let Some(concrete) = desc_below.as_concrete() {
-            if !Rectangle::with_layout(&concrete.layout).contains(rect) {
-                return Err(CommandError::OTHER);
-            }
-        }
+        // Floating point texels keep the colors outside the gamut of the primaries.
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
 
-        let op = Op::Binary {
-            lhs: below,
-            rhs: above,
-            op: BinaryOp::Inscribe {
-                placement: rect.normalize(),
-            },
-            desc: desc_below.clone(),
+        let rgb = self.color_convert_from_cielab(src, source, linear_rgb, texel)?;
+        let adapted = self.chromatic_adaptation(rgb, method, target)?;
+        self.color_convert_cielab(adapted, target, desc_src.texel)
+    }
+
+    /// Adapt a color, which is not RGB, in a linear RGB image of floating point texels.
+    ///
+    /// The result is encoded as `color`, which is relative to the `target` whitepoint or to D65
+    /// for `Oklab`.
+    fn adapt_through_rgb(
+        &mut self,
+        src: Register,
+        desc_src: &Descriptor,
+        method: ChromaticAdaptationMethod,
+        source: Whitepoint,
+        target: Whitepoint,
+        color: Color,
+    ) -> Result<Register, CommandError> {
+        let linear_rgb = |wp: Whitepoint| {
+            let mut rgb = Color::SRGB;
+            if let Color::Rgb {
+                whitepoint,
+                transfer,
+                ..
+            } = &mut rgb
+            {
+                *whitepoint = wp;
+                *transfer = Transfer::Linear;
+            }
+            rgb
+        };
+
+        // Floating point texels keep the colors outside the gamut of the primaries.
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let (width, height) = desc_src.size();
+        let desc = Descriptor::with_texel(texel.clone(), width, height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let rgb = self.color_convert(src, linear_rgb(source), texel)?;
+        let adapted = self.chromatic_adaptation(rgb, method, target)?;
+
+        let encoded_wp = match color {
+            Color::Oklab => Whitepoint::D65,
+            _ => target,
+        };
+
+        let adapted = if encoded_wp == target {
+            adapted
+        } else {
+            self.transmute(
+                adapted,
+                Descriptor {
+                    color: linear_rgb(encoded_wp),
+                    ..desc
+                },
+            )?
+        };
+
+        self.color_convert(adapted, color, desc_src.texel.clone())
+    }
+
+    /// Simulate the perception of an image with a color vision deficiency.
+    ///
+    /// The simulation is performed in linear color. The result has the same descriptor as the
+    /// source.
+    pub fn simulate_cvd(&mut self, src: Register, cvd: Cvd) -> Result<Register, CommandError> {
+        self.linear_srgb_transform(src, cvd.simulation_matrix())
+    }
+
+    /// Remove noise by non-local means.
+    ///
+    /// Each texel becomes the average of the texels in the search window around it, weighted by
+    /// how similar the patches around either one are. Patches are compared by the luma of the
+    /// linear color, with weights derived from the color as in [`Self::extract`]. Borders are
+    /// clamped to the edge of the image. The result has the same descriptor as the source.
+    ///
+    /// This is expensive. Each texel compares `(2·s + 1)²` patches of `(2·p + 1)²` texels, for a
+    /// search radius `s` and a patch radius `p`. Already a search of 7×7 with patches of 3×3 reads
+    /// close to a thousand texels for every texel of the result, at the maximal radii it is more
+    /// than ten thousand.
+    pub fn denoise_nlm(&mut self, src: Register, nlm: Nlm) -> Result<Register, CommandError> {
+        use shaders::denoise_nlm::{MAX_PATCH_RADIUS, MAX_SEARCH_RADIUS};
+
+        let desc_src = self.texture_of(src)?;
+
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Nlm {
+            search_radius,
+            patch_radius,
+            h,
+        } = nlm;
+
+        if search_radius > MAX_SEARCH_RADIUS || patch_radius > MAX_PATCH_RADIUS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        if !h.is_finite() || h <= 0.0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let weights = Self::channel_weights(&desc, ColorChannel::Luma)?;
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::DenoiseNlm(shaders::denoise_nlm::ShaderData {
+                h,
+                weights,
+                search_radius,
+                patch_radius,
+            }),
+            desc: desc.into(),
         };
 
         Ok(self.push(op))
     }
 
-    /// Extract some channels from an image data into a new view.
-    pub fn extract(
+    /// Convert a mosaic of a color filter array into a color image.
+    ///
+    /// The source must be a single channel of `u8` or `u16` luma samples, each the intensity
+    /// behind one filter of the array. The result has the same size with an `Rgb` texel of the
+    /// same sample depth, and the provided color. The samples are interpolated as they are
+    /// decoded with the color of the source, thus raw sensor data should be given a linear
+    /// transfer function.
+    pub fn demosaic(
         &mut self,
         src: Register,
-        channel: ColorChannel,
+        cfa: Cfa,
+        color: Color,
     ) -> Result<Register, CommandError> {
-        let desc_src = self.describe_reg(src).as_texture()?;
+        let desc_src = self.texture_of(src)?;
 
         let desc_src = desc_src.as_concrete().ok_or(CommandError {
             inner: CommandErrorKind::ConcreteDescriptorRequired,
         })?;
 
-        let texel = desc_src
-            .texel
-            .channel_texel(channel)
-            .ok_or(CommandError::OTHER)?;
-
-        let layout = ByteLayout {
-            texel_stride: texel.bits.bytes(),
-            width: desc_src.layout.width,
-            height: desc_src.layout.height,
-            row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+        let bits = match (desc_src.texel.parts, desc_src.texel.bits) {
+            (SampleParts::Luma, SampleBits::UInt8) => SampleBits::UInt8x3,
+            (SampleParts::Luma, SampleBits::UInt16) => SampleBits::UInt16x3,
+            _ => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BadDescriptor(
+                        desc_src.into(),
+                        "demosaic of a texel other than u8 or u16 luma",
+                    ),
+                })
+            }
         };
 
-        let color = desc_src.color.clone();
+        if desc_src.texel.block != Block::Pixel {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "demosaic of a texel block",
+                ),
+            });
+        }
 
-        // Check that we can actually extract that channel.
-        // This could be unimplemented if the position of a particular channel is not yet a stable
-        // detail. Also, we might introduce 'virtual' channels such as `Luminance` on an RGB image
-        // where such channels are computed by linear combination instead of a binary incidence
-        // vector. Then there might be colors where this does not exist.
-        let channel = ChannelPosition::new(channel).ok_or(CommandError::OTHER)?;
+        let texel = Texel {
+            block: Block::Pixel,
+            bits,
+            parts: SampleParts::Rgb,
+        };
+
+        let desc = Descriptor {
+            color,
+            layout: ByteLayout {
+                texel_stride: texel.bits.bytes(),
+                width: desc_src.layout.width,
+                height: desc_src.layout.height,
+                row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+            },
+            texel,
+        };
 
         let op = Op::Unary {
             src,
-            op: UnaryOp::Extract { channel },
-            desc: Descriptor {
-                color,
-                layout,
-                texel,
-            }
-            .into(),
+            op: UnaryOp::Demosaic(cfa),
+            desc: desc.into(),
         };
 
         Ok(self.push(op))
     }
 
-    /// Reinterpret the bytes of an image as another type.
-    ///
-    /// This command requires that the texel type of the register and the descriptor have the same
-    /// size. It will return an error if this is not the case. Additionally, the provided texel
-    /// must be internally consistent.
+    /// Correct the distortion of a lens.
     ///
-    /// One important use of this method is to add or removed the color interpretation of an image.
-    /// This can be necessary when it has been algorithmically created or when one wants to
-    /// intentionally ignore such meaning.
-    pub fn transmute(
+    /// Each texel of the result is sampled bi-linearly where the lens distorts its coordinate
+    /// to, see [`Distortion::distort`]. Texels that map outside the source have the fill color.
+    /// The result has the same descriptor as the source.
+    pub fn lens_undistort(
         &mut self,
         src: Register,
-        target: Descriptor,
+        distortion: Distortion,
     ) -> Result<Register, CommandError> {
-        self.transmute_generic(src, target.into())
+        let desc_src = self.texture_of(src)?;
+
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Distortion {
+            k1,
+            k2,
+            k3,
+            p1,
+            p2,
+            center,
+            focal,
+            fill,
+        } = distortion;
+
+        let finite = [k1, k2, k3, p1, p2]
+            .iter()
+            .chain(&center)
+            .chain(&fill)
+            .all(|v| v.is_finite());
+
+        if !finite || !focal.iter().all(|f| f.is_finite() && *f > 0.0) {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::LensUndistort(distortion),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    /// Reinterpret the bytes of an image as another type.
-    ///
-    /// Like [`Self::transmute`] except the target can be a generic. Note however that it must be
-    /// provable that the texels contain the same number of bytes and align in their storage layout
-    /// (see [`SampleBits::bytes`]). This requires both texel types to be concrete or to be the
-    /// exact same generic.
+    /// Sample the color channels of an image at different radial scales around a center.
     ///
-    /// Other methods for demonstrating this as a bound might be added at a later point but are
-    /// essentially a form of dependent typing, so don't count too much on it.
-    pub fn transmute_generic(
+    /// This imitates the lateral chromatic aberration of a lens, or corrects it with the inverse
+    /// scales. The alpha channel is taken from the green sample. The image must have an RGB color
+    /// and the result has the same descriptor as the source.
+    pub fn chromatic_aberration(
         &mut self,
         src: Register,
-        into: GenericDescriptor,
+        aberration: ChromaticAberration,
     ) -> Result<Register, CommandError> {
-        let source = self.describe_reg(src).as_texture()?;
-        let supposed_type = into;
+        let desc_src = self.texture_of(src)?;
 
-        if source.size() != supposed_type.size() {
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if !matches!(desc.color, Color::Rgb { .. }) {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    supposed_type,
-                    "invalid transmute with mismatched size",
+                    desc.into(),
+                    "chromatic aberration of non-rgb color",
                 ),
             });
         }
 
-        // Predict if monomorphize will only do correct transmutes. A transmute re-interprets the
-        // buffer containing bit data in storage layout.
-        fn can_transmute(source: Generic<(Texel, Color)>, target: Generic<(Texel, Color)>) -> bool {
-            match (source, target) {
-                (Generic::Generic(vsource), Generic::Generic(vtarget)) => vsource == vtarget,
-                (Generic::Concrete((source, _)), Generic::Concrete((target, _))) => {
-                    source.bits.bytes() == target.bits.bytes()
-                }
-                _ => false,
-            }
+        let ChromaticAberration { scales, center } = aberration;
+        let valid = scales.iter().all(|s| s.is_finite() && *s > 0.0)
+            && center.iter().all(|c| c.is_finite());
+
+        if !valid {
+            return Err(CommandError::INVALID_CALL);
         }
 
-        if !can_transmute(
-            source.descriptor_chroma(),
-            supposed_type.descriptor_chroma(),
-        ) {
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::ChromaticAberration(aberration),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Encode an RGB image as Y'CbCr video signals.
+    ///
+    /// The color difference signals are formed from the electrical R'G'B' values as stored, that
+    /// is with the transfer function of the color applied, as the video standards define them.
+    /// The result has `Yuv` texels of the same bits, with the scalar channels in order luma, blue
+    /// difference and red difference. Its color is [`Color::Scalars`] since the signals are
+    /// relative to the primaries and transfer of the source, which the caller must remember to
+    /// decode them again.
+    ///
+    /// The source texel must consist of the three color channels alone, `Rgb` or `Bgr`. Note
+    /// that chroma subsampling is not supported, the result is 4:4:4 with all signals
+    /// interleaved in each texel.
+    pub fn ycbcr_encode(&mut self, src: Register, ycbcr: Ycbcr) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if !matches!(desc_src.color, Color::Rgb { .. }) {
             return Err(CommandError {
-                inner: CommandErrorKind::ConflictingTypes(source.clone(), supposed_type),
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "Y'CbCr of a non-rgb color",
+                ),
             });
         }
 
-        if !supposed_type
-            .as_concrete()
-            .map_or(true, |descriptor| descriptor.is_consistent())
+        if desc_src.texel.block != Block::Pixel
+            || !matches!(desc_src.texel.parts, SampleParts::Rgb | SampleParts::Bgr)
         {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    supposed_type,
-                    "invalid transmute with inconsistent result",
+                    desc_src.into(),
+                    "Y'CbCr requires Rgb or Bgr pixels",
                 ),
             });
         }
 
-        let op = Op::Unary {
+        let (matrix, offset) = ycbcr.encode_affine(desc_src.texel.bits);
+        let scalars = Color::Scalars {
+            transfer: Transfer::Linear,
+        };
+
+        // Keep the source in its electrical form, the staging would otherwise linearize it.
+        let electrical = self.transmute(
             src,
-            op: UnaryOp::Transmute,
-            desc: supposed_type,
+            Descriptor {
+                color: scalars.clone(),
+                ..desc_src.clone()
+            },
+        )?;
+
+        let desc = Descriptor {
+            color: scalars,
+            texel: Texel {
+                parts: SampleParts::Yuv,
+                ..desc_src.texel
+            },
+            ..desc_src
+        };
+
+        let op = Op::Unary {
+            src: electrical,
+            op: UnaryOp::AffineColor { matrix, offset },
+            desc: desc.into(),
         };
 
         Ok(self.push(op))
     }
 
-    /// Overwrite some channels with overlaid data.
-    ///
-    /// This performs an implicit conversion of the overlaid data to the color channels which is
-    /// performed as if by transmutation. However, contrary to the transmutation we will _only_
-    /// allow the sample parts to be changed arbitrarily.
+    /// Decode Y'CbCr video signals into an RGB image.
     ///
-    /// To perform a mix of two images with differing texels or colors, as if by rendering rather
-    /// than as if by transmute, use `mix` [FIXME: not yet implemented].
-    pub fn inject(
+    /// This inverts [`Self::ycbcr_encode`]. The source must have `Yuv` pixels, with samples in
+    /// the order luma, blue difference and red difference, as 4:4:4 without chroma subsampling.
+    /// The resulting R'G'B' values are interpreted in the given color which must be RGB, with
+    /// `Rgb` texels of the same bits.
+    pub fn ycbcr_decode(
         &mut self,
-        below: Register,
-        channel: ColorChannel,
-        above: Register,
+        src: Register,
+        ycbcr: Ycbcr,
+        color: Color,
     ) -> Result<Register, CommandError> {
-        let desc_below = self.describe_reg(below).as_texture()?;
-        let desc_above = self.describe_reg(above).as_texture()?.clone();
+        let desc_src = self.texture_of(src)?;
 
-        let Generic::Concrete((below_texel, below_color)) = desc_below.descriptor_chroma() else {
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if desc_src.texel.block != Block::Pixel || desc_src.texel.parts != SampleParts::Yuv {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    desc_below.clone(),
-                    "inject into non-concrete texel",
+                    desc_src.into(),
+                    "Y'CbCr decoding requires Yuv pixels",
                 ),
             });
-        };
+        }
 
-        let Generic::Concrete((above_texel, above_color)) = desc_above.descriptor_chroma() else {
+        if !matches!(color, Color::Rgb { .. }) {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    desc_above.clone(),
-                    "inject from non-concrete texel",
+                    desc_src.into(),
+                    "Y'CbCr decoding into a non-rgb color",
                 ),
             });
+        }
+
+        let (matrix, offset) = ycbcr.decode_affine(desc_src.texel.bits);
+
+        let electrical = Descriptor {
+            color: Color::Scalars {
+                transfer: Transfer::Linear,
+            },
+            texel: Texel {
+                parts: SampleParts::Rgb,
+                ..desc_src.texel
+            },
+            ..desc_src
         };
 
-        let expected_texel = below_texel
-            .channel_texel(channel)
-            .ok_or(CommandError::OTHER)?;
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::AffineColor { matrix, offset },
+            desc: electrical.clone().into(),
+        };
 
-        if above_texel.parts.num_components() != expected_texel.parts.num_components() {
-            let wanted = GenericDescriptor {
-                chroma: Generic::Concrete((expected_texel, below_color)),
-                ..desc_below.clone()
-            };
+        let decoded = self.push(op);
 
-            return Err(CommandError {
-                inner: CommandErrorKind::ConflictingTypes(wanted, desc_above),
-            });
-        }
+        self.transmute(
+            decoded,
+            Descriptor {
+                color,
+                ..electrical
+            },
+        )
+    }
 
-        let from_channels = above_texel.clone();
-        // Override the sample part interpretation for comparison. We ignore this and compare
-        // everything else. This is because we change specifically the parts by this operation.
-        let mut above_texel = above_texel;
-        above_texel.parts = expected_texel.parts;
+    /// Separate an RGB image into the inks of naive CMYK.
+    ///
+    /// This is the textbook model, where each ink subtracts its complementary primary from the
+    /// electrical RGB values as stored, and the generation of black is chosen by the caller. It
+    /// does not model any printing process and is no replacement for an ICC profile.
+    ///
+    /// The source texel must contain the three color channels, optionally with alpha. The result
+    /// has four channels of the same sample depth, holding cyan, magenta, yellow and black in the
+    /// order of `RgbA`, with [`Color::Scalars`]. There is no room for alpha in it, which is
+    /// dropped.
+    pub fn cmyk_encode(
+        &mut self,
+        src: Register,
+        black: BlackGeneration,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        // FIXME: should we do parsing instead of validation?
-        // Some type like ChannelPosition but for multiple.
-        if from_channels.channel_weight_vec4().is_none() {
-            return Err(CommandError::OTHER);
-        }
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        if (&expected_texel, &below_color) != (&above_texel, &above_color) {
-            let wanted = GenericDescriptor {
-                chroma: Generic::Concrete((expected_texel, below_color)),
-                ..desc_below.clone()
-            };
+        let black = match black {
+            BlackGeneration::None => 0.0,
+            BlackGeneration::Max => 1.0,
+            BlackGeneration::Percentage(percent) if (0.0..=100.0).contains(&percent) => {
+                percent / 100.0
+            }
+            BlackGeneration::Percentage(_) => return Err(CommandError::INVALID_CALL),
+        };
 
+        if !matches!(desc_src.color, Color::Rgb { .. }) {
             return Err(CommandError {
-                inner: CommandErrorKind::ConflictingTypes(wanted, desc_above),
+                inner: CommandErrorKind::BadDescriptor(desc_src.into(), "CMYK of a non-rgb color"),
             });
         }
 
-        // Find where to insert, see `extract` for this step.
-        let channel = ChannelPosition::new(channel).ok_or(CommandError::OTHER)?;
+        let has_rgb = matches!(
+            desc_src.texel.parts,
+            SampleParts::Rgb
+                | SampleParts::Bgr
+                | SampleParts::RgbA
+                | SampleParts::BgrA
+                | SampleParts::ARgb
+                | SampleParts::ABgr
+        );
 
-        let op = Op::Binary {
-            lhs: below,
-            rhs: above,
-            op: BinaryOp::Inject {
-                channel,
-                from_channels,
-            },
-            desc: desc_below.clone(),
+        let bits = match desc_src.texel.bits {
+            SampleBits::UInt8x3 | SampleBits::UInt8x4 => SampleBits::UInt8x4,
+            SampleBits::UInt16x3 | SampleBits::UInt16x4 => SampleBits::UInt16x4,
+            SampleBits::Float16x4 => SampleBits::Float16x4,
+            SampleBits::Float32x3 | SampleBits::Float32x4 => SampleBits::Float32x4,
+            _ => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BadDescriptor(
+                        desc_src.into(),
+                        "CMYK requires a uniform sample depth",
+                    ),
+                })
+            }
         };
 
-        Ok(self.push(op))
-    }
-
-    /// Grab colors from a palette based on an underlying image of indices.
-    pub fn palette(
-        &mut self,
-        palette: Register,
-        config: Palette,
-        indices: Register,
-    ) -> Result<Register, CommandError> {
-        let color_desc = self.describe_reg(palette).as_texture()?;
-        let idx_desc = self.describe_reg(indices).as_texture()?;
+        if desc_src.texel.block != Block::Pixel || !has_rgb {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "CMYK requires pixels of the rgb channels",
+                ),
+            });
+        }
 
-        // FIXME: check that channels are actually in indices' color type.
-        let x_coord = if let Some(coord) = config.width {
-            let pos = ChannelPosition::new(coord).ok_or(CommandError::TYPE_ERR)?;
-            pos.into_vec4()
-        } else {
-            [0.0; 4]
-        };
+        // Keep the source in its electrical form, the staging would otherwise linearize it.
+        let electrical = self.transmute(
+            src,
+            Descriptor {
+                color: Color::Scalars {
+                    transfer: Transfer::Linear,
+                },
+                ..desc_src.clone()
+            },
+        )?;
 
-        let y_coord = if let Some(coord) = config.height {
-            let pos = ChannelPosition::new(coord).ok_or(CommandError::TYPE_ERR)?;
-            pos.into_vec4()
-        } else {
-            [0.0; 4]
+        let texel = Texel {
+            block: Block::Pixel,
+            bits,
+            parts: SampleParts::RgbA,
         };
 
-        // Compute the target layout (and that we can represent it).
-        let target_layout = GenericDescriptor {
-            chroma: color_desc.descriptor_chroma(),
-            ..idx_desc.clone()
-        };
+        // The inks are scalars, without the transfer of the source.
+        let (width, height) = desc_src.size();
+        let desc =
+            Descriptor::with_texel(texel, width, height).ok_or(CommandError::INVALID_CALL)?;
 
-        let op = Op::Binary {
-            lhs: palette,
-            rhs: indices,
-            op: BinaryOp::Palette(shaders::palette::ShaderData {
-                x_coord,
-                y_coord,
-                base_x: config.width_base,
-                base_y: config.height_base,
-            }),
-            desc: target_layout,
+        let op = Op::Unary {
+            src: electrical,
+            op: UnaryOp::CmykEncode { black },
+            desc: desc.into(),
         };
 
         Ok(self.push(op))
     }
 
-    /// Calculate the derivative of an image.
+    /// Combine the inks of naive CMYK into an RGB image.
     ///
-    /// Currently, will only calculate the derivative for color channels. The alpha channel will be
-    /// copied from the source pixel. To also calculate a derivative over the alpha channel you
-    /// should extract it as a value channel, calculate the derivative there and the inject the
-    /// result back to the image.
-    pub fn derivative(
-        &mut self,
-        image: Register,
-        config: Derivative,
-    ) -> Result<Register, CommandError> {
-        let desc = self.describe_reg(image).as_texture()?.clone();
-
-        let op = Op::Unary {
-            src: image,
-            op: UnaryOp::Derivative(config),
-            desc,
-        };
-
-        Ok(self.push(op))
-    }
+    /// This inverts [`Self::cmyk_encode`], which it reproduces exactly for colors of any black
+    /// generation except for quantization. The source must have [`Color::Scalars`] with cyan,
+    /// magenta, yellow and black in the four channels of `RgbA` pixels. The result has the same
+    /// texel, is opaque and the resulting R'G'B' values are interpreted in the given color which
+    /// must be RGB.
+    pub fn cmyk_decode(&mut self, src: Register, color: Color) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-    /// Overlay this image as part of a larger one, performing blending.
-    pub fn blend(
-        &mut self,
-        _below: Register,
-        _rect: Rectangle,
-        _above: Register,
-        _blend: Blend,
-    ) -> Result<Register, CommandError> {
-        // TODO: What blending should we support
-        Err(CommandError::UNIMPLEMENTED)
-    }
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-    /// A solid color image, from a descriptor and a single color.
-    ///
-    /// Repeats the color across all pixels, then transforms into equivalent texels.
-    pub fn solid_rgba(
-        &mut self,
-        describe: Descriptor,
-        color: [f32; 4],
-    ) -> Result<Register, CommandError> {
-        if !describe.is_consistent() {
+        if !matches!(desc_src.color, Color::Scalars { .. })
+            || desc_src.texel.block != Block::Pixel
+            || desc_src.texel.parts != SampleParts::RgbA
+        {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    describe.into(),
-                    "inconsistent constant color image created",
+                    desc_src.into(),
+                    "CMYK decoding requires four scalar channels",
                 ),
             });
         }
 
-        if color.len() != usize::from(describe.layout.texel_stride) {
+        if !matches!(color, Color::Rgb { .. }) {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    describe.into(),
-                    "inconsistent color description",
+                    desc_src.into(),
+                    "CMYK decoding into a non-rgb color",
                 ),
             });
         }
 
-        Ok(self.push(Op::Construct {
-            desc: describe.into(),
-            op: ConstructOp::Solid(color.to_owned()),
-        }))
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::CmykDecode,
+            desc: desc_src.clone().into(),
+        };
+
+        let decoded = self.push(op);
+
+        self.transmute(decoded, Descriptor { color, ..desc_src })
     }
 
-    /// A 2d image with a normal distribution.
+    /// Enhance the contrast of an image for viewers with a color vision deficiency.
     ///
-    /// The parameters are controlled through the `distribution` parameter while the `texel`
-    /// parameter controls the eventual binary encoding of the image. It must be compatible with a
-    /// single gray channel (but you can have electrical transfer functions, choose arbitrary bit
-    /// widths etc.).
-    pub fn distribution_normal2d(
+    /// The information lost in the simulation of the deficiency is redistributed into the color
+    /// channels that remain distinguishable. The result has the same descriptor as the source.
+    pub fn daltonize(&mut self, src: Register, cvd: Cvd) -> Result<Register, CommandError> {
+        self.linear_srgb_transform(src, cvd.daltonize_matrix())
+    }
+
+    /// Apply a matrix defined on linear sRGB to an RGB image, of any primaries.
+    fn linear_srgb_transform(
         &mut self,
-        describe: Descriptor,
-        distribution: DistributionNormal2d,
+        src: Register,
+        matrix: RowMatrix,
     ) -> Result<Register, CommandError> {
-        if !describe.is_consistent() {
-            return Err(CommandError {
-                inner: CommandErrorKind::BadDescriptor(describe.into(), "inconsistent normal2d"),
-            });
-        }
+        let desc_src = self.texture_of(src)?;
 
-        if describe.texel.parts != SampleParts::Luma && describe.texel.parts != SampleParts::LumaA {
-            return Err(CommandError {
-                inner: CommandErrorKind::BadDescriptor(
-                    describe.into(),
-                    "normal2d for non-LumA texel",
-                ),
-            });
-        }
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        Ok(self.push(Op::Construct {
-            desc: describe.into(),
-            op: ConstructOp::DistributionNormal(distribution),
-        }))
+        let (primary, whitepoint) = match desc.color {
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } => (primary, whitepoint),
+            _ => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BadDescriptor(desc.into(), "non-rgb color transform"),
+                })
+            }
+        };
+
+        let to_xyz = RowMatrix(primary.to_xyz_row_matrix(whitepoint));
+        let from_xyz = RowMatrix(Primaries::Bt709.from_xyz_row_matrix(whitepoint));
+        let to_srgb = RowMatrix::from(from_xyz.multiply_right(to_xyz.into()));
+
+        let in_srgb = RowMatrix::from(matrix.multiply_right(to_srgb.into()));
+        let matrix = RowMatrix::from(to_srgb.inv().multiply_right(in_srgb.into()));
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::LinearColor(matrix),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    /// A 2d image with fractal brownian noise.
+    /// Fuse a sequence of exposures of the same scene into one image.
     ///
-    /// The parameters are controlled through the `distribution` parameter. Output contains
-    /// in each of the 4 color channels uncorrelated, 1 dimensional fractal perlin noise.
-    pub fn distribution_fractal_noise(
+    /// Each pixel is the weighted average of the exposures, by the weights of their quality. This
+    /// is a single-scale fusion, without the pyramid blending, so seams may be visible where the
+    /// weights change abruptly. The fusion is computed by accumulating the exposures one by one,
+    /// there must be at least two of them and all must have the same descriptor.
+    pub fn exposure_fusion(
         &mut self,
-        describe: Descriptor,
-        distribution: FractalNoise,
+        exposures: &[Register],
+        weights: FusionWeights,
     ) -> Result<Register, CommandError> {
-        if !describe.is_consistent() {
+        let (&first, rest) = exposures.split_first().ok_or(CommandError::INVALID_CALL)?;
+
+        if rest.is_empty() {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let desc_first = self.texture_of(first)?;
+        let desc = desc_first.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        for &exposure in rest {
+            let desc_exposure = self.texture_of(exposure)?;
+
+            if desc_exposure.as_concrete().as_ref() != Some(&desc) {
+                return Err(CommandError {
+                    inner: CommandErrorKind::ConflictingTypes(desc.into(), desc_exposure.clone()),
+                });
+            }
+        }
+
+        if !matches!(desc.color, Color::Rgb { .. }) {
             return Err(CommandError {
-                inner: CommandErrorKind::BadDescriptor(
-                    describe.into(),
-                    "inconsistent descriptor for fractal noise",
-                ),
+                inner: CommandErrorKind::BadDescriptor(desc.into(), "non-rgb exposure fusion"),
             });
         }
 
-        Ok(self.push(Op::Construct {
-            desc: describe.into(),
-            op: ConstructOp::DistributionNoise(distribution),
-        }))
+        // The accumulator needs an alpha channel for the mean weight, with some precision.
+        let texel = Texel {
+            block: desc.texel.block,
+            bits: SampleBits::UInt16x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let accumulator = Descriptor {
+            layout: ByteLayout {
+                texel_stride: texel.bits.bytes(),
+                row_stride: (texel.bits.bytes() as u64) * u64::from(desc.layout.width),
+                ..desc.layout
+            },
+            texel,
+            color: desc.color.clone(),
+        };
+
+        let weights = [
+            weights.contrast,
+            weights.saturation,
+            weights.well_exposedness,
+            weights.sigma,
+        ];
+
+        let mut fused = first;
+        for (idx, &exposure) in exposures.iter().enumerate() {
+            let last = idx + 1 == exposures.len();
+
+            fused = self.push(Op::Binary {
+                lhs: fused,
+                rhs: exposure,
+                op: BinaryOp::FusionStep(shaders::exposure_fusion::ShaderData {
+                    weights,
+                    count: idx as u32 + 1,
+                    last,
+                }),
+                desc: if last {
+                    desc.clone().into()
+                } else {
+                    accumulator.clone().into()
+                },
+            });
+        }
+
+        Ok(fused)
     }
 
-    /// Evaluate a bilinear function over a 2d image.
+    /// Map high dynamic range radiance into the displayable range.
     ///
-    /// For each color channel, the parameter contains intervals of values that define how its
-    /// value is determined along the width and height axis.
+    /// The source must have a linear transfer function or floating point texels, already encoded
+    /// colors are not valid radiance. The result has the same descriptor as the source, follow up
+    /// with [`Self::color_convert`] to get an encoded image.
     ///
-    /// This can be used similar to `numpy`'s `mgrid`.
-    pub fn bilinear(
-        &mut self,
-        describe: Descriptor,
-        distribution: Bilinear,
-    ) -> Result<Register, CommandError> {
-        if !describe.is_consistent() {
+    /// Half floats in linear light, see [`Descriptor::with_linear_half`], are the recommended
+    /// radiance. They keep the dynamic range at half the size of single precision floats.
+    pub fn tonemap(&mut self, src: Register, tonemap: ToneMap) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let is_linear = matches!(
+            desc.color,
+            Color::Rgb {
+                transfer: Transfer::Linear,
+                ..
+            } | Color::Scalars {
+                transfer: Transfer::Linear,
+            }
+        );
+
+        let is_float = matches!(
+            desc.texel.bits,
+            SampleBits::Float16x4
+                | SampleBits::Float32
+                | SampleBits::Float32x2
+                | SampleBits::Float32x3
+                | SampleBits::Float32x4
+        );
+
+        if !is_linear && !is_float {
             return Err(CommandError {
                 inner: CommandErrorKind::BadDescriptor(
-                    describe.into(),
-                    "inconsistent descriptor for bilinear",
+                    desc.into(),
+                    "tone mapping of non-linear, non-float texels",
                 ),
             });
         }
 
-        Ok(self.push(Op::Construct {
-            desc: describe.into(),
-            op: ConstructOp::Bilinear(distribution),
-        }))
+        if let ToneMap::Reinhard { white } = tonemap {
+            if !white.is_finite() || white <= 0.0 {
+                return Err(CommandError::INVALID_CALL);
+            }
+        }
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::ToneMap(tonemap),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    /// Overlay an affine transformation of the image.
-    pub fn affine(
+    /// Move the chroma `a` of an image to neutral, with a Möbius transformation in Oklab.
+    ///
+    /// See [`AbMobius`] for details.
+    pub fn ab_mobius(&mut self, src: Register, a: [f32; 2]) -> Result<Register, CommandError> {
+        self.ab_mobius_map(src, AbMobius::with_pole(a))
+    }
+
+    /// Apply a Möbius transformation to the chroma of an image in Oklab.
+    pub fn ab_mobius_map(
         &mut self,
-        below: Register,
-        affine: Affine,
-        above: Register,
+        src: Register,
+        map: AbMobius,
     ) -> Result<Register, CommandError> {
-        // TODO: should we check affine here?
-        let lhs = self.describe_reg(below).as_texture()?.clone();
-        let rhs = self.describe_reg(above).as_texture()?.clone();
+        let in_disk = |[a, b]: [f32; 2]| (a * a + b * b) < 1.0;
 
-        if lhs.descriptor_chroma() != rhs.descriptor_chroma() {
-            return Err(CommandError::TYPE_ERR);
+        if !in_disk(map.from) || !in_disk(map.to) {
+            return Err(CommandError::INVALID_CALL);
         }
 
-        match RowMatrix::new(affine.transformation)
-            .det()
-            .abs()
-            .partial_cmp(&f32::EPSILON)
-        {
-            Some(Ordering::Greater | Ordering::Equal) => {}
-            _ => return Err(CommandError::OTHER),
+        self.ab_transform(src, map.from, map.to, 0.0)
+    }
+
+    /// Rotate the hue of an image around the neutral axis in Oklab.
+    pub fn ab_rotate(&mut self, src: Register, radians: f32) -> Result<Register, CommandError> {
+        if !radians.is_finite() {
+            return Err(CommandError::INVALID_CALL);
         }
 
-        match affine.sampling {
-            AffineSample::Nearest => (),
-            AffineSample::BiLinear => {
-                // "Check for a color which we can sample bi-linearly"
-                return Err(CommandError::UNIMPLEMENTED);
-            }
-        }
-
-        Ok(self.push(Op::Binary {
-            lhs: below,
-            rhs: above,
-            op: BinaryOp::Affine(affine),
-            desc: lhs,
-        }))
+        self.ab_transform(src, [0.0; 2], [0.0; 2], radians)
     }
 
-    pub fn resize(&mut self, below: Register, upper: (u32, u32)) -> Result<Register, CommandError> {
-        let (width, height) = upper;
-        let grid_layout = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), width, height)
-            .ok_or(CommandError::OTHER)?;
+    fn ab_transform(
+        &mut self,
+        src: Register,
+        from: [f32; 2],
+        to: [f32; 2],
+        rotation: f32,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        let grid = self.bilinear(
-            grid_layout,
-            shaders::bilinear::ShaderData {
-                u_min: [0.0, 0.0, 0.0, 1.0],
-                v_min: [0.0, 0.0, 0.0, 1.0],
-                uv_min: [0.0, 0.0, 0.0, 1.0],
-                u_max: [1.0, 0.0, 0.0, 1.0],
-                v_max: [0.0, 1.0, 0.0, 1.0],
-                uv_max: [0.0, 0.0, 0.0, 1.0],
-            },
-        )?;
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        self.palette(
-            below,
-            Palette {
-                width: Some(ColorChannel::R),
-                height: Some(ColorChannel::G),
-                width_base: 0,
-                height_base: 0,
-            },
-            grid,
-        )
+        // An image which is already in Oklab is transformed directly, otherwise the shader
+        // encodes to Oklab and decodes back in the same pass.
+        let xyz = match desc.color {
+            Color::Oklab => None,
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } => Some(Self::oklab_xyz_matrices(primary, whitepoint)),
+            _ => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BadDescriptor(
+                        desc.into(),
+                        "chroma transform for non-rgb, non-oklab color",
+                    ),
+                })
+            }
+        };
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::AbTransform(shaders::ab_transform::ShaderData {
+                xyz,
+                from,
+                to,
+                rotation,
+            }),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    /// Declare an output.
+    /// The matrices from linear RGB to XYZ relative to D65, the whitepoint of Oklab, and back.
     ///
-    /// Outputs MUST later be bound from the pool during launch.
-    pub fn output(&mut self, src: Register) -> Result<(Register, GenericDescriptor), CommandError> {
-        let outformat = self.describe_reg(src).as_texture()?.clone();
-        // Ignore this, it doesn't really produce a register.
-        let register = self.push(Op::Output { src });
-        Ok((register, outformat))
+    /// Colors of another whitepoint are adapted with the Bradford transform in both directions.
+    fn oklab_xyz_matrices(primary: Primaries, whitepoint: Whitepoint) -> (RowMatrix, RowMatrix) {
+        let to_xyz = RowMatrix(primary.to_xyz_row_matrix(whitepoint));
+        let from_xyz = RowMatrix(primary.from_xyz_row_matrix(whitepoint));
+
+        let into_d65 = RowMatrix::bradford(whitepoint.to_xyz(), Whitepoint::D65.to_xyz());
+        let from_d65 = RowMatrix::bradford(Whitepoint::D65.to_xyz(), whitepoint.to_xyz());
+
+        (
+            into_d65.multiply_right(to_xyz.into()).into(),
+            from_xyz.multiply_right(from_d65.into()).into(),
+        )
     }
 
-    /// Declare a render target.
+    /// Make the texels close to a key color transparent, as for green-screen footage.
     ///
-    /// Render targets MUST later be bound from the pool during launch, similar to outputs. However, they are not assumed to be readable afterwards and will never be a copy target.
+    /// The distance to the key is measured between chromas in Oklab, so it does not depend on
+    /// the lightness of a texel. The matte is a smooth ramp from fully transparent at the
+    /// tolerance to opaque after the softness, which anti-aliases the edges of the subject.
     ///
-    /// The target register must be renderable, i.e. a color with a native texture representation.
-    pub fn render(&mut self, src: Register) -> Result<(Register, Descriptor), CommandError> {
-        let outformat = self.describe_reg(src).as_texture()?.clone();
+    /// With [`KeyOutput::Alpha`] the source must have an alpha channel, the result has the same
+    /// descriptor. The spill suppression then removes the chroma component towards the key color
+    /// from all texels. With [`KeyOutput::Matte`] the result is a `Luma` image of the matte.
+    pub fn chroma_key(
+        &mut self,
+        src: Register,
+        key: ChromaKey,
+        output: KeyOutput,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        let outformat = outformat.as_concrete().ok_or(CommandError {
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
             inner: CommandErrorKind::ConcreteDescriptorRequired,
         })?;
 
-        // FIXME: this is too conservative! We need to ensure that our internal assumption about
-        // the texture descriptor is compatible with available wgpu formats (and yields the same
-        // result).
-        if ImageDescriptor::new(&outformat).is_err() {
-            return Err(CommandError::TYPE_ERR);
+        let xyz = match desc_src.color {
+            Color::Oklab => None,
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } => Some(Self::oklab_xyz_matrices(primary, whitepoint)),
+            _ => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::BadDescriptor(
+                        desc_src.into(),
+                        "chroma key for non-rgb, non-oklab color",
+                    ),
+                })
+            }
+        };
+
+        let ChromaKey {
+            color,
+            tolerance,
+            softness,
+            spill_suppression,
+        } = key;
+
+        let valid = color.iter().all(|c| c.is_finite())
+            && tolerance >= 0.0
+            && tolerance.is_finite()
+            && softness >= 0.0
+            && softness.is_finite()
+            && (0.0..=1.0).contains(&spill_suppression);
+
+        if !valid {
+            return Err(CommandError::INVALID_CALL);
         }
 
-        // Ignore this, it doesn't really produce a register.
-        let register = self.push(Op::Render { src });
-        Ok((register, outformat))
-    }
+        let desc = match output {
+            KeyOutput::Alpha => {
+                if desc_src
+                    .texel
+                    .parts
+                    .with_channel(ColorChannel::Alpha)
+                    .is_none()
+                {
+                    return Err(CommandError {
+                        inner: CommandErrorKind::BadDescriptor(
+                            desc_src.into(),
+                            "chroma key into a texel without alpha",
+                        ),
+                    });
+                }
 
-    /// Configure a next, parameterized, operation whose parameter structure can be overridden at
-    /// runtime.
-    pub fn with_knob(&mut self) -> WithKnob<'_> {
-        WithKnob { inner: self }
+                desc_src
+            }
+            KeyOutput::Matte => {
+                let texel = Self::luma_texel(&desc_src.texel)?;
+
+                Descriptor {
+                    color: desc_src.color.clone(),
+                    layout: ByteLayout {
+                        texel_stride: texel.bits.bytes(),
+                        width: desc_src.layout.width,
+                        height: desc_src.layout.height,
+                        row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+                    },
+                    texel,
+                }
+            }
+        };
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::ChromaKey(shaders::chroma_key::ShaderData {
+                xyz,
+                color,
+                tolerance,
+                softness,
+                spill_suppression,
+                matte: output == KeyOutput::Matte,
+            }),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    /// Similar to `with_knob` but here we can use a different set of calls.
+    /// Reduce an image to a single texel holding the mean of all its texels.
     ///
-    /// The next parameterized operation is called with its parameter structure copied from the
-    /// given buffer, instead of parameters supplied statically in the command buffer.
-    ///
-    /// Where it would be necessary to do indirect paint calls it'll get more complicated in the
-    /// translation stage (need new `Low` ops) but it should be simple for a few other calls.
-    pub fn with_buffer(&mut self, register: Register) -> Result<WithBuffer<'_>, CommandError> {
-        let buffer = self.describe_reg(register).as_buffer()?;
+    /// The mean is computed in linear color by a chain of passes, each halving the width and
+    /// height of the image. The passes hold single precision floats, only the result is encoded
+    /// in the descriptor of the source except for its size. See [`Self::reduce`] for the
+    /// statistic itself at full precision.
+    pub fn reduce_mean(&mut self, src: Register) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        let len = buffer.as_concrete().ok_or(CommandError {
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
             inner: CommandErrorKind::ConcreteDescriptorRequired,
         })?;
 
-        Ok(WithBuffer {
-            inner: self,
-            guaranteed_len: len,
-            start: 0,
-            register,
-        })
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let desc = Descriptor::with_texel(texel, desc_src.layout.width, desc_src.layout.height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let result = (desc_src.texel, desc_src.color);
+        Ok(self.reduction_chain_into(src, Reduction::Mean, desc, result))
     }
-}
 
-/// Commands that operate on buffers.
-impl CommandBuffer {
-    /// Construct a buffer by initializing it with data from memory.
+    /// Compute a statistic of each channel over all texels of an image.
     ///
-    /// The binary value will be copied into a buffer held by the execution state. If you intend to
-    /// modify that buffer with each execution, see [`Self::with_knob`] and [`WithKnob::buffer_init`].
+    /// The result is a buffer whose first 16 bytes are the statistic of the four channels of the
+    /// linear color representation, each as a little-endian `f32`, in the order `rgba`. The
+    /// remainder of the buffer is unspecified. It can be read back with [`Self::output_buffer`].
     ///
-    /// FIXME: late errors depending on `wgpu` since we copy the buffer and that requires it to be
-    /// a multiple of `4`. This contradicts the notion that the hardware is chosen at a later
-    /// stage.. We should instead compute?
-    pub fn buffer_init(&mut self, init: &[u8]) -> Register {
-        use core::convert::TryInto as _;
-        let size: u64 = init.len().try_into().unwrap();
+    /// The statistic is computed by a chain of passes, each halving the width and height of the
+    /// image. Texels at the border of odd dimensions are weighted by the number of source texels
+    /// they cover, so the mean is exact up to the half precision of intermediate values.
+    pub fn reduce(
+        &mut self,
+        src: Register,
+        reduction: Reduction,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        self.push(Op::BufferInit {
-            desc: GenericBuffer {
-                size: Generic::Concrete(size),
-            },
-            op: BufferInitOp::FromData {
-                placement: 0..init.len(),
-                data: Arc::from(init),
-            },
-        })
-    }
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-    /// Construct a buffer that is fully zeroed from memory.
-    pub fn buffer_zero(&mut self, len: u64) -> Register {
-        self.push(Op::BufferInit {
-            desc: GenericBuffer {
-                size: Generic::Concrete(len),
-            },
-            op: BufferInitOp::FromData {
-                placement: 0..0,
-                data: Arc::default(),
-            },
-        })
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let desc = Descriptor::with_texel(texel, desc_src.layout.width, desc_src.layout.height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let reduced = self.reduction_chain(src, reduction, desc);
+        self.buffer_from_image(reduced)
     }
 
-    /// Construct a buffer representing *encoded* image data.
+    /// Compute the integral image, also called summed-area table, of an image.
     ///
-    /// FIXME: semantics of `Ok` depend on `wgpu`. This contradicts the notion that the hardware is
-    /// chosen at a later stage..
-    pub fn buffer_from_image(&mut self, register: Register) -> Result<Register, CommandError> {
-        let RegisterDescription::Texture(tex) = self.describe_reg(register) else {
-            return Err(CommandError::BAD_REGISTER);
-        };
+    /// Each texel of the result holds the inclusive sum of the rectangle spanning from the origin
+    /// to that texel, in each channel of the linear color representation. The result has `f32`
+    /// texels with `rgba` channels and no color interpretation.
+    ///
+    /// The sums are computed by scans, first along the width and then along the height, each in
+    /// a logarithmic number of steps.
+    ///
+    /// The sums are accumulated in single precision textures. As these are sampled like all other
+    /// textures, the program requires a device with [`wgpu::Features::FLOAT32_FILTERABLE`], see
+    /// [`Program::required_features`]. Lowering it for another device fails.
+    ///
+    /// [`Program::required_features`]: crate::program::Program::required_features
+    pub fn integral_image(&mut self, src: Register) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-        let len = match tex.as_concrete() {
-            Some(descriptor) => descriptor
-                .u64_gpu_len()
-                // Well can this even happen? A concrete image with no layout on the GPU?
-                .ok_or_else(|| CommandError::INVALID_CALL)?,
-            // FIXME: better diagnostic or allow this? We can't guarantee if this will error or not
-            // and we can not give a concrete length for the buffer. Both must be decided in
-            // some way
-            None => return Err(CommandError::BAD_REGISTER),
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let (width, height) = (desc_src.layout.width, desc_src.layout.height);
+
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
         };
 
-        Ok(self.push(Op::BufferUnary {
-            src: register,
-            desc: GenericBuffer {
-                size: Generic::Concrete(len),
-            },
-            op: BufferUnaryOp::FromImage {},
-        }))
+        let desc: GenericDescriptor = Descriptor::with_texel(texel, width, height)
+            .ok_or(CommandError::INVALID_CALL)?
+            .into();
+
+        // There is at least one step along the width, converting to the result descriptor.
+        let mut offsets = vec![];
+        let mut offset = 1;
+        loop {
+            offsets.push((offset, 0));
+            offset *= 2;
+            if offset >= width {
+                break;
+            }
+        }
+
+        let mut offset = 1;
+        while offset < height {
+            offsets.push((0, offset));
+            offset *= 2;
+        }
+
+        let mut sum = src;
+        for offset in offsets {
+            sum = self.push(Op::Unary {
+                src: sum,
+                op: UnaryOp::PrefixSum { offset },
+                desc: desc.clone(),
+            });
+        }
+
+        Ok(sum)
     }
 
-    /// Construct a buffer by overlaying one on top of another.
+    /// Compute the mean squared error between two images, in each channel.
     ///
-    /// The output buffer is sized according to the underlying buffer. Overflowed data will be
-    /// discarded.
-    pub fn buffer_overlay(
-        &mut self,
-        under: Register,
-        at: u64,
-        over: Register,
-    ) -> Result<Register, CommandError> {
-        let RegisterDescription::Buffer(buf) = self.describe_reg(under) else {
-            return Err(CommandError::BAD_REGISTER);
-        };
+    /// The result is a buffer as for [`Reduction::Mean`] in [`Self::reduce`], its first 16 bytes
+    /// are the error of the four channels of the linear color representation as little-endian
+    /// `f32`. The images must agree in size and chroma. See [`psnr`] for converting the error to
+    /// a peak signal-to-noise ratio.
+    pub fn mse(&mut self, lhs: Register, rhs: Register) -> Result<Register, CommandError> {
+        let desc_lhs = self.texture_of(lhs)?;
+        let desc_rhs = self.texture_of(rhs)?;
+
+        if desc_lhs != desc_rhs {
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(desc_lhs.clone(), desc_rhs.clone()),
+            });
+        }
+
+        let desc = desc_lhs.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        let RegisterDescription::Buffer(_) = self.describe_reg(over) else {
-            return Err(CommandError::BAD_REGISTER);
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
         };
 
-        // FIXME: generate warnings if out of bounds? There is no use cloning a buffer that I can
-        // see right now, it's all still the exact same content.
-        Ok(self.push(Op::BufferBinary {
-            lhs: under,
-            rhs: over,
-            desc: GenericBuffer {
-                size: buf.size.clone(),
-            },
-            op: BufferBinaryOp::Overlay { at },
-        }))
-    }
-}
+        let desc = Descriptor::with_texel(texel, desc.layout.width, desc.layout.height)
+            .ok_or(CommandError::INVALID_CALL)?;
 
-impl WithKnob<'_> {
-    /// Wrap commands that generate one register instruction, that is parameterized by the buffer.
-    fn regular_with_knob(
-        &mut self,
-        fn_: impl FnOnce(&mut CommandBuffer) -> Result<Register, CommandError>,
-    ) -> Result<Register, CommandError> {
-        let register = fn_(&mut self.inner)?;
-        self.inner.knobs.insert(register, KnobKind::Runtime);
-        Ok(register)
+        let difference = self.push(Op::Binary {
+            lhs,
+            rhs,
+            op: BinaryOp::SquaredDifference,
+            desc: desc.clone().into(),
+        });
+
+        let mean = self.reduction_chain(difference, Reduction::Mean, desc);
+        self.buffer_from_image(mean)
     }
 
-    /// See [`CommandBuffer::chromatic_adaptation`].
+    /// Compute the mean structural similarity index between two images.
     ///
-    /// FIXME: untested, does this make sense? Knob controls the color transformation matrix
-    /// directly, not semantically.
-    pub fn chromatic_adaptation(
+    /// The index is computed from the luminance of color images, or the `Luma` channel, with
+    /// means, variances and the covariance over a gaussian weighted window around each texel. The
+    /// window is clamped to the edge of the image instead of excluding the border, which differs
+    /// from the canonical implementation in a border of half the window. The dynamic range is
+    /// that of linear color values, `1.0`.
+    ///
+    /// The result is a buffer as for [`Reduction::Mean`] in [`Self::reduce`], its first 4 bytes
+    /// are the mean index as a little-endian `f32`. The images must agree in size and chroma.
+    pub fn ssim(
         &mut self,
-        src: Register,
-        method: ChromaticAdaptationMethod,
-        target: Whitepoint,
+        lhs: Register,
+        rhs: Register,
+        ssim: Ssim,
     ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.chromatic_adaptation(src, method, target))
-    }
+        use shaders::local_statistics::MAX_RADIUS;
 
-    /// See [`CommandBuffer::inscribe`].
-    ///
-    /// FIXME: untested, does this make sense?
-    pub fn inscribe(
-        &mut self,
-        below: Register,
-        rect: Rectangle,
-        above: Register,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.inscribe(below, rect, above))
-    }
+        let desc_lhs = self.texture_of(lhs)?;
+        let desc_rhs = self.texture_of(rhs)?;
 
-    /// See [`CommandBuffer::solid_rgba`].
-    pub fn solid_rgba(
-        &mut self,
-        describe: Descriptor,
-        color: [f32; 4],
-    ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.solid_rgba(describe, color))
-    }
+        if desc_lhs != desc_rhs {
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(desc_lhs.clone(), desc_rhs.clone()),
+            });
+        }
 
-    /// See [`CommandBuffer::distribution_normal2d`].
-    pub fn distribution_normal2d(
-        &mut self,
-        describe: Descriptor,
-        distribution: DistributionNormal2d,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.distribution_normal2d(describe, distribution))
+        let desc = desc_lhs.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Ssim { window, k1, k2 } = ssim;
+
+        if window % 2 == 0 || window / 2 > MAX_RADIUS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        if !k1.is_finite() || !k2.is_finite() || k1 <= 0.0 || k2 <= 0.0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let data = shaders::local_statistics::ShaderData {
+            weights: Self::channel_weights(&desc, ColorChannel::Luma)?,
+            radius: window / 2,
+            sigma: 1.5 * window as f32 / 11.0,
+            c1: k1 * k1,
+            c2: k2 * k2,
+        };
+
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let desc = Descriptor::with_texel(texel, desc.layout.width, desc.layout.height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let similarity = self.push(Op::Binary {
+            lhs,
+            rhs,
+            op: BinaryOp::Ssim(data),
+            desc: desc.clone().into(),
+        });
+
+        let mean = self.reduction_chain(similarity, Reduction::Mean, desc);
+        self.buffer_from_image(mean)
     }
 
-    /// See [`CommandBuffer::distribution_fractal_noise`].
-    pub fn distribution_fractal_noise(
+    /// Reduce an image to a single texel, by a chain of passes that halve its width and height.
+    ///
+    /// The passes have the descriptor `desc`, which must have the size of the source, except for
+    /// their size. There is at least one pass, such that the result is always of that descriptor.
+    fn reduction_chain(
         &mut self,
-        describe: Descriptor,
-        distribution: FractalNoise,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.distribution_fractal_noise(describe, distribution))
+        src: Register,
+        reduction: Reduction,
+        desc: Descriptor,
+    ) -> Register {
+        let result = (desc.texel.clone(), desc.color.clone());
+        self.reduction_chain_into(src, reduction, desc, result)
     }
 
-    /// See [`CommandBuffer::bilinear`].
-    pub fn bilinear(
+    /// Reduce an image to a single texel, with the texel and color of `result`.
+    ///
+    /// As [`Self::reduction_chain`], but only the intermediate passes have the descriptor `desc`.
+    /// The last one, the only one if the source is already small, encodes into the result.
+    fn reduction_chain_into(
         &mut self,
-        describe: Descriptor,
-        distribution: Bilinear,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| cmd.bilinear(describe, distribution))
-    }
+        src: Register,
+        reduction: Reduction,
+        mut desc: Descriptor,
+        (texel, color): (Texel, Color),
+    ) -> Register {
+        let size = (desc.layout.width, desc.layout.height);
+
+        let mut reduced = src;
+        let mut level = 0;
+
+        loop {
+            let width = desc.layout.width.div_ceil(2);
+            let height = desc.layout.height.div_ceil(2);
+
+            desc = Descriptor {
+                layout: ByteLayout {
+                    width,
+                    height,
+                    row_stride: u64::from(desc.layout.texel_stride) * u64::from(width),
+                    ..desc.layout
+                },
+                ..desc
+            };
 
-    /// See [`CommandBuffer::buffer_init`].
-    pub fn buffer_init(&mut self, init: &[u8]) -> Result<Register, CommandError> {
-        self.regular_with_knob(move |cmd| Ok(cmd.buffer_init(init)))
-    }
+            let last = width <= 1 && height <= 1;
+
+            if last {
+                desc = Descriptor {
+                    layout: ByteLayout {
+                        texel_stride: texel.bits.bytes(),
+                        row_stride: u64::from(texel.bits.bytes()),
+                        ..desc.layout
+                    },
+                    texel: texel.clone(),
+                    color: color.clone(),
+                };
+            }
 
-    /*Should be knob'able but we currently do not generate the vertex coordinate buffer, i.e. sampled
-     * 2d parameterization, in a manner that permits adding a knob.
+            reduced = self.push(Op::Unary {
+                src: reduced,
+                op: UnaryOp::Reduce(shaders::reduce::ShaderData {
+                    reduction,
+                    size,
+                    level,
+                }),
+                desc: desc.clone().into(),
+            });
 
-        /// See [`CommandBuffer::crop`].
-        pub fn crop(&mut self, src: Register, rect: Rectangle) -> Result<Register, CommandError> {
-            self.regular_with_knob(move |cmd| cmd.crop(src, rect))
-        }
+            level += 1;
 
-        /// See [`CommandBuffer::affine`].
-        pub fn affine(
-            &mut self,
-            below: Register,
-            affine: Affine,
-            above: Register,
-        ) -> Result<Register, CommandError> {
-            self.regular_with_knob(move |cmd| cmd.affine(below, affine, above))
+            if last {
+                return reduced;
+            }
         }
+    }
 
-    */
-}
-
-impl WithBuffer<'_> {
-    /// Wrap commands that generate one register instruction, that is parameterized by the buffer.
-    fn regular_with_buffer(
+    /// Reduce the size of an image by an integer factor, averaging blocks of texels.
+    ///
+    /// The result is smaller by the factor, rounded up. Where the factor does not divide the size
+    /// the blocks at the right and bottom edge are partial, the mean of the texels they contain.
+    /// Large factors are split into several passes which still compute the exact block mean.
+    pub fn downsample(
         &mut self,
-        len: u64,
-        fn_: impl FnOnce(&mut CommandBuffer) -> Result<Register, CommandError>,
+        src: Register,
+        (factor_x, factor_y): (u32, u32),
     ) -> Result<Register, CommandError> {
-        if self.guaranteed_len < len {
+        use shaders::downsample::passes;
+
+        let desc_src = self.texture_of(src)?;
+
+        let mut desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if factor_x == 0 || factor_y == 0 {
             return Err(CommandError::INVALID_CALL);
         }
 
-        let register = fn_(&mut self.inner)?;
+        let size = desc.size();
+        let passes_x = passes(factor_x);
+        let passes_y = passes(factor_y);
+        let count = passes_x.len().max(passes_y.len());
+
+        let mut scale = (1, 1);
+        let mut downsampled = src;
+
+        for idx in 0..count {
+            let factor = (
+                passes_x.get(idx).copied().unwrap_or(1),
+                passes_y.get(idx).copied().unwrap_or(1),
+            );
+
+            let (width, height) = desc.size();
+            desc = desc
+                .with_size(width.div_ceil(factor.0), height.div_ceil(factor.1))
+                .ok_or(CommandError::INVALID_CALL)?;
+
+            downsampled = self.push(Op::Unary {
+                src: downsampled,
+                op: UnaryOp::Downsample(shaders::downsample::ShaderData {
+                    factor,
+                    scale,
+                    size,
+                }),
+                desc: desc.clone().into(),
+            });
 
-        self.inner.knobs.insert(
-            register,
-            KnobKind::Buffer {
-                buffer: self.register,
-                range: 0..len,
-            },
-        );
+            scale = (scale.0 * factor.0, scale.1 * factor.1);
+        }
 
-        Ok(register)
+        Ok(downsampled)
     }
 
-    /// Change the start of the buffer region being passed as dynamic value.
-    pub fn with_start(self, start: u64) -> Result<Self, CommandError> {
-        if start % 4 != 0 {
+    /// Build a pyramid of successively halved images.
+    ///
+    /// The result contains `levels` images, the first being the source itself. Each further level
+    /// averages the 2-by-2 blocks of the previous one. Odd dimensions are rounded up, the blocks
+    /// at the edge then cover fewer texels whose exact mean is used. Every texel of a level is
+    /// thus the mean of the texels of the source it covers.
+    pub fn pyramid(&mut self, src: Register, levels: u32) -> Result<Vec<Register>, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let mut desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if levels == 0 {
             return Err(CommandError::INVALID_CALL);
         }
 
-        Ok(WithBuffer { start: 4, ..self })
+        let size = desc.size();
+        let mut pyramid = vec![src];
+
+        for level in 0..levels - 1 {
+            let (width, height) = desc.size();
+            desc = desc
+                .with_size(width.div_ceil(2), height.div_ceil(2))
+                .ok_or(CommandError::INVALID_CALL)?;
+
+            let previous = *pyramid.last().unwrap();
+            pyramid.push(self.push(Op::Unary {
+                src: previous,
+                op: UnaryOp::Reduce(shaders::reduce::ShaderData {
+                    reduction: Reduction::Mean,
+                    size,
+                    level,
+                }),
+                desc: desc.clone().into(),
+            }));
+        }
+
+        Ok(pyramid)
     }
 
-    /// See [`CommandBuffer::chromatic_adaptation`].
-    pub fn chromatic_adaptation(
+    /// Build a pyramid of band-pass images, see [`Self::collapse`] for its inverse.
+    ///
+    /// Each level is the difference of a level of [`Self::pyramid`] to the bi-linear expansion of
+    /// the next, except for the last level which is the coarsest image itself. The differences
+    /// are signed and have floating point texels.
+    pub fn laplacian_pyramid(
         &mut self,
         src: Register,
-        method: ChromaticAdaptationMethod,
-        target: Whitepoint,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_buffer(core::mem::size_of::<[f32; 12]>() as u64, move |cmd| {
-            cmd.chromatic_adaptation(src, method, target)
-        })
-    }
+        levels: u32,
+    ) -> Result<Vec<Register>, CommandError> {
+        let gaussian = self.pyramid(src, levels)?;
+        let mut bands = vec![];
+
+        for pair in gaussian.windows(2) {
+            let (fine, coarse) = (pair[0], pair[1]);
+            let desc_fine = self.texture_of(fine)?;
+            let (width, height) = desc_fine
+                .as_concrete()
+                .ok_or(CommandError {
+                    inner: CommandErrorKind::ConcreteDescriptorRequired,
+                })?
+                .size();
+
+            let texel = Texel {
+                block: Block::Pixel,
+                bits: SampleBits::Float32x4,
+                parts: SampleParts::RgbA,
+            };
 
-    /// See [`CommandBuffer::solid_rgba`].
-    pub fn solid_rgba(
-        &mut self,
-        describe: Descriptor,
-        color: [f32; 4],
-    ) -> Result<Register, CommandError> {
-        self.regular_with_buffer(core::mem::size_of::<[f32; 4]>() as u64, move |cmd| {
-            cmd.solid_rgba(describe, color)
-        })
-    }
+            let desc =
+                Descriptor::with_texel(texel, width, height).ok_or(CommandError::INVALID_CALL)?;
 
-    /// See [`CommandBuffer::distribution_normal2d`].
-    pub fn distribution_normal2d(
-        &mut self,
-        describe: Descriptor,
-        distribution: DistributionNormal2d,
-    ) -> Result<Register, CommandError> {
-        self.regular_with_buffer(core::mem::size_of::<[f32; 8]>() as u64, move |cmd| {
-            cmd.distribution_normal2d(describe, distribution)
-        })
+            bands.push(self.push(Op::Binary {
+                lhs: fine,
+                rhs: coarse,
+                op: BinaryOp::PyramidExpand { subtract: true },
+                desc: desc.into(),
+            }));
+        }
+
+        bands.extend(gaussian.last());
+        Ok(bands)
     }
 
-    /// See [`CommandBuffer::distribution_fractal_noise`].
-    pub fn distribution_fractal_noise(
-        &mut self,
-        describe: Descriptor,
-        distribution: FractalNoise,
-    ) -> Result<Register, CommandError> {
-        #[repr(C)]
-        #[repr(align(8))]
-        struct _ForSizePurpose {
-            _0: [f32; 2],
-            _1: f32,
-            _2: f32,
-            _3: u32,
+    /// Sum a band-pass pyramid back into an image.
+    ///
+    /// Starting from the coarsest level, each level is expanded bi-linearly and added to the
+    /// next finer one. The result has the size of the first level and the color of the last. This
+    /// inverts [`Self::laplacian_pyramid`], up to the precision of intermediate values.
+    pub fn collapse(&mut self, levels: &[Register]) -> Result<Register, CommandError> {
+        let (&coarsest, bands) = levels.split_last().ok_or(CommandError::INVALID_CALL)?;
+
+        let desc = self.texture_of(coarsest)?;
+        let desc = desc.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let mut collapsed = coarsest;
+        for &band in bands.iter().rev() {
+            let desc_band = self.texture_of(band)?;
+            let (width, height) = desc_band
+                .as_concrete()
+                .ok_or(CommandError {
+                    inner: CommandErrorKind::ConcreteDescriptorRequired,
+                })?
+                .size();
+
+            let desc = desc
+                .with_size(width, height)
+                .ok_or(CommandError::INVALID_CALL)?;
+
+            collapsed = self.push(Op::Binary {
+                lhs: band,
+                rhs: collapsed,
+                op: BinaryOp::PyramidExpand { subtract: false },
+                desc: desc.into(),
+            });
         }
 
-        self.regular_with_buffer(core::mem::size_of::<_ForSizePurpose>() as u64, move |cmd| {
-            cmd.distribution_fractal_noise(describe, distribution)
-        })
+        Ok(collapsed)
     }
 
-    /// See [`CommandBuffer::bilinear`].
-    pub fn bilinear(
+    /// Count the values of a channel into the bins of a histogram.
+    ///
+    /// The result is a buffer whose first `4 * bins` bytes are the counts of the bins, each as a
+    /// little-endian `u32`, in order of increasing value. The remainder of the buffer, padding to
+    /// the row alignment of the device, is unspecified. Values are those of the linear color
+    /// representation and are clamped to `0.0..=1.0` before binning, the maximum belongs to the
+    /// last bin. The buffer can be read back with [`Self::output_buffer`].
+    ///
+    /// Each bin is counted by scanning the rows of the source in a fragment shader, instead of
+    /// scattering into bins with atomics, so that this works on WebGPU without compute support.
+    /// The cost is therefore proportional to the number of bins times the number of texels.
+    pub fn histogram(
         &mut self,
-        describe: Descriptor,
-        distribution: Bilinear,
+        src: Register,
+        config: HistogramConfig,
     ) -> Result<Register, CommandError> {
-        self.regular_with_buffer(core::mem::size_of::<[[f32; 4]; 6]>() as u64, move |cmd| {
-            cmd.bilinear(describe, distribution)
-        })
-    }
-}
+        let HistogramConfig { bins, channel } = config;
 
-/// Turn a command buffer into a `Program`.
-impl Linker {
-    #[cfg(test)]
-    pub fn from_included() -> &'static Self {
-        zosimos_std::from_included()
-    }
+        let desc_src = self.texture_of(src)?;
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-    pub fn compile(&self, program: &CommandBuffer) -> Result<Program, CompileError> {
-        self.link(program, &[], &[], &[])
+        if bins == 0 || bins > shaders::histogram::MAX_BINS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let weights = Self::channel_weights(&desc_src, channel)?;
+
+        let counts = Texel::new_u8(SampleParts::RgbA);
+        let rows = Descriptor::with_texel(counts.clone(), bins, desc_src.layout.height)
+            .ok_or(CommandError::INVALID_CALL)?;
+        let sum = Descriptor::with_texel(counts, bins, 1).ok_or(CommandError::INVALID_CALL)?;
+
+        let rows = self.push(Op::Unary {
+            src,
+            op: UnaryOp::HistogramRows(shaders::histogram::ShaderData { weights, bins }),
+            desc: rows.into(),
+        });
+
+        let sum = self.push(Op::Unary {
+            src: rows,
+            op: UnaryOp::HistogramSum,
+            desc: sum.into(),
+        });
+
+        self.buffer_from_image(sum)
     }
 
-    /// An unergonomic interface for linking a collection of different command buffers to a
-    /// program. The `functions` are all buffers besides `self` that are linked. `links` describes
-    /// the relation between them. For each buffer (`self` at 0 then incremented across the array)
-    /// a list match all function declarations in that buffer to the command supplying the
-    /// definition. The generic signature must match each declaration it is linked to.
+    /// Equalize the luminance of an image with contrast limited adaptive histogram equalization.
     ///
-    /// FIXME: higher level interface here. We should be able to configured links with pairs of a
-    /// `FunctionVar` and a higher-level wrapper around a `CommandBuffer` index. Also it makes not
-    /// much sense to treat the `self` special except as a defaulted entry point and for the
-    /// `compile` helper that does not require any linkage.
-    pub fn link(
-        &self,
-        main: &CommandBuffer,
-        tys: &[Descriptor],
-        functions: &[CommandBuffer],
-        links: &[&[usize]],
-    ) -> Result<Program, CompileError> {
-        // We can default to 'no links', which is fine..
-        if functions.len() + 1 < links.len() {
-            eprintln!("Bad link listings count");
-            // Error: more links than functions..
-            return Err(CompileError::NotYetImplemented);
+    /// The image is partitioned into tiles, each with its own histogram of 256 bins. Histograms are
+    /// clipped to the limit, the excess redistributed, and then accumulated to an equalizing
+    /// mapping function of each tile. Each texel is mapped by interpolating bilinearly between the
+    /// mappings of the four tiles whose centers surround it. The color is scaled such that its
+    /// luminance becomes the mapped value, preserving its chromaticity.
+    ///
+    /// Like [`Self::histogram`], the counting is done by scanning the tiles in a fragment shader.
+    pub fn clahe(&mut self, src: Register, clahe: Clahe) -> Result<Register, CommandError> {
+        use shaders::clahe::{BINS, MAX_TILES};
+
+        let desc_src = self.texture_of(src)?;
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Clahe {
+            tiles: (tiles_x, tiles_y),
+            clip_limit,
+        } = clahe;
+
+        let tiles_fit = |tiles: u32, size: u32| (1..=MAX_TILES.min(size)).contains(&tiles);
+
+        if !tiles_fit(tiles_x, desc_src.layout.width) || !tiles_fit(tiles_y, desc_src.layout.height)
+        {
+            return Err(CommandError::INVALID_CALL);
         }
 
-        let mut high_ops = vec![];
+        if !clip_limit.is_finite() || clip_limit < 1.0 {
+            return Err(CommandError::INVALID_CALL);
+        }
 
-        let mut monomorphic = Monomorphizing {
-            stack: vec![],
-            monomorphic: HashMap::new(),
-            commands: Some(main).into_iter().chain(functions).collect(),
-            knobs: HashMap::new(),
-            next_knob: Knob(0),
-            current_link_id: 0,
+        let data = shaders::clahe::ShaderData {
+            weights: Self::channel_weights(&desc_src, ColorChannel::Luma)?,
+            tiles: (tiles_x, tiles_y),
+            clip_limit,
         };
 
-        monomorphic.push_function(LinkedMonomorphicSignature {
-            link_idx: 0,
-            tys: Cow::Borrowed(tys).into_owned(),
+        let tile_texel = Texel::new_u8(SampleParts::RgbA);
+        let tile_desc = Descriptor::with_texel(tile_texel, BINS, tiles_x * tiles_y)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let histograms = self.push(Op::Unary {
+            src,
+            op: UnaryOp::ClaheHistogram(data.clone()),
+            desc: tile_desc.clone().into(),
         });
 
-        impl Monomorphizing<'_> {
-            /// Assign a program function index to a specific generic instantiation.
-            ///
-            /// Remembers to process the monomorphization later if it was not instantiated yet.
-            pub fn push_function(&mut self, sig: LinkedMonomorphicSignature) -> Function {
-                let idx = self.monomorphic.len();
+        let mappings = self.push(Op::Unary {
+            src: histograms,
+            op: UnaryOp::ClaheMapping(data.clone()),
+            desc: tile_desc.into(),
+        });
 
-                let stack = &mut self.stack;
-                let command = &self.commands[sig.link_idx];
+        Ok(self.push(Op::Binary {
+            lhs: src,
+            rhs: mappings,
+            op: BinaryOp::ClaheApply(data),
+            desc: desc_src.into(),
+        }))
+    }
 
-                *self.monomorphic.entry(sig).or_insert_with_key(|key| {
-                    stack.push(CommandMonomorphization {
-                        link_idx: key.link_idx,
-                        command,
-                        tys: Cow::Owned(key.tys.to_vec()),
-                    });
+    /// The weights of linear color channels whose combination is the value of a channel.
+    ///
+    /// This is the incidence vector of a channel present in the texel. `ColorChannel::Luma` is
+    /// also available for RGB colors, with weights derived from the primaries as in
+    /// [`Self::extract`].
+    fn channel_weights(desc: &Descriptor, channel: ColorChannel) -> Result<[f32; 4], CommandError> {
+        if let Some(texel) = desc.texel.channel_texel(channel) {
+            return texel
+                .channel_weight_vec4()
+                .ok_or(CommandError::bad_channel(channel));
+        }
 
-                    Function(idx)
-                })
+        match &desc.color {
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } if channel == ColorChannel::Luma => {
+                let to_xyz = primary.to_xyz_row_matrix(*whitepoint);
+                Ok([to_xyz[3], to_xyz[4], to_xyz[5], 0.0])
             }
+            _ => Err(CommandError::bad_channel(channel)),
+        }
+    }
 
-            pub fn next_knob(&mut self, register: Register) -> Knob {
-                let knob = self.next_knob;
-                self.next_knob.0 += 1;
-                self.knobs.insert(
-                    RegisterKnob {
-                        link_idx: self.current_link_id,
-                        register,
-                    },
-                    knob,
-                );
-                knob
-            }
+    /// Balance the colors of an image automatically.
+    ///
+    /// The illuminant is estimated from the image itself, according to the method. To inspect
+    /// the estimate call [`Self::white_balance_estimate`] and [`Self::white_balance`] instead.
+    pub fn auto_white_balance(
+        &mut self,
+        src: Register,
+        method: AwbMethod,
+    ) -> Result<Register, CommandError> {
+        let estimate = self.white_balance_estimate(src, method.clone())?;
+        self.white_balance(src, method, estimate)
+    }
+
+    /// Estimate the illuminant of an image, as a single texel image.
+    ///
+    /// For [`AwbMethod::GrayWorld`] this is the mean color (see [`Self::reduce_mean`]). The gains
+    /// applied by the balancing are the average of its color channels divided by each channel.
+    pub fn white_balance_estimate(
+        &mut self,
+        src: Register,
+        method: AwbMethod,
+    ) -> Result<Register, CommandError> {
+        match method {
+            AwbMethod::GrayWorld => self.reduce_mean(src),
         }
+    }
 
-        let mut functions = vec![];
-        while let Some(top) = monomorphic.stack.pop() {
-            let CommandMonomorphization {
-                link_idx,
-                command,
-                tys,
-            } = top;
+    /// Balance the colors of an image relative to an illuminant estimate.
+    ///
+    /// The `estimate` must be a single texel image with the same color as the source, such as
+    /// returned from [`Self::white_balance_estimate`].
+    pub fn white_balance(
+        &mut self,
+        src: Register,
+        method: AwbMethod,
+        estimate: Register,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+        let desc_estimate = self.texture_of(estimate)?;
 
-            monomorphic.current_link_id = link_idx;
-            let links = links.get(link_idx).copied().unwrap_or_default();
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-            let linked = Self::link_in(
-                &self.core,
-                &self.std,
-                command,
-                tys,
-                &mut high_ops,
-                &mut monomorphic,
-                links,
-            )?;
+        let estimate_desc = desc_estimate.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-            // FIXME: expand further requested generic instantiations.
-            functions.push(linked);
+        if !matches!(desc.color, Color::Rgb { .. }) {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(desc.into(), "non-rgb white balance"),
+            });
         }
 
-        Ok(Program {
-            ops: high_ops,
-            functions,
-            entry_index: 0,
-            buffer_by_op: HashMap::default(),
-            texture_by_op: HashMap::default(),
-            knobs: monomorphic.knobs,
-            library: crate::program::Library {
-                std: self.std.clone(),
-                core: self.core.clone(),
-            },
-        })
+        if estimate_desc.size() != (1, 1) || estimate_desc.color != desc.color {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    estimate_desc.into(),
+                    "estimate must be a single texel of the same color",
+                ),
+            });
+        }
+
+        let op = Op::Binary {
+            lhs: src,
+            rhs: estimate,
+            op: BinaryOp::WhiteBalance(method),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
     }
 
-    fn link_in(
-        core: &ShadersCore,
-        std: &ShadersStd,
-        command: &CommandBuffer,
-        tys: Cow<'_, [Descriptor]>,
-        high_ops: &mut Vec<High>,
-        mono: &mut Monomorphizing,
-        functions: &[usize],
-    ) -> Result<FunctionLinked, CompileError> {
-        if functions.len() != command.symbols.len() {
-            eprintln!("Bad linked parameter count");
-            return Err(CompileError::NotYetImplemented);
-        }
+    /// Embed this image as part of a larger one.
+    pub fn inscribe(
+        &mut self,
+        below: Register,
+        rect: Rectangle,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        let desc_below = self.texture_of(below)?;
+        let desc_above = self.texture_of(above)?;
 
-        if tys.len() != command.vars.len() {
-            eprintln!("Bad type generic count");
-            return Err(CompileError::NotYetImplemented);
+        if desc_above.descriptor_chroma() != desc_below.descriptor_chroma() {
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(desc_below.clone(), desc_above.clone()),
+            });
         }
 
-        let ops = &command.ops;
-        let steps = ops.len();
-        let tys = tys.as_ref();
-        let start = high_ops.len();
+        let desc_above = desc_above.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
 
-        let mut last_use = vec![0; steps];
-        let mut first_use = vec![steps; steps];
+        let size_mismatch = CommandError {
+            inner: CommandErrorKind::SizeMismatch {
+                lhs: below,
+                rhs: above,
+            },
+        };
 
-        let image_buffers = core::cell::RefCell::new(ImageBufferPlan::default());
+        if Rectangle::with_layout(&desc_above.layout) != rect {
+            return Err(size_mismatch);
+        }
 
-        // Liveness analysis.
-        for (back_idx, op) in ops.iter().rev().enumerate() {
-            let idx = ops.len() - 1 - back_idx;
-            match op {
-                Op::Input { .. }
-                | Op::Construct { .. }
-                | Op::BufferInit { .. }
-                | Op::DynamicImage {
-                    call: OperandDynKind::Construct,
-                    ..
-                } => {}
-                &Op::Output { src: Register(src) } => {
-                    last_use[src] = last_use[src].max(idx);
-                    first_use[src] = first_use[src].min(idx);
-                }
-                &Op::Render { src: Register(src) } => {
-                    last_use[src] = last_use[src].max(idx);
-                    first_use[src] = first_use[src].min(idx);
-                }
-                &Op::Unary {
-                    src: Register(src), ..
-                }
-                | &Op::DynamicImage {
-                    call: OperandDynKind::Unary(Register(src)),
-                    ..
-                }
-                | &Op::BufferUnary {
-                    src: Register(src), ..
-                } => {
-                    last_use[src] = last_use[src].max(idx);
-                    first_use[src] = first_use[src].min(idx);
-                }
-                &Op::Binary {
-                    lhs: Register(lhs),
-                    rhs: Register(rhs),
-                    ..
-                }
-                | &Op::BufferBinary {
-                    lhs: Register(lhs),
-                    rhs: Register(rhs),
-                    ..
-                }
-                | &Op::DynamicImage {
-                    call:
-                        OperandDynKind::Binary {
-                            lhs: Register(lhs),
-                            rhs: Register(rhs),
-                        },
-                    ..
-                } => {
-                    last_use[rhs] = last_use[rhs].max(idx);
-                    first_use[rhs] = first_use[rhs].min(idx);
-                    last_use[lhs] = last_use[lhs].max(idx);
-                    first_use[lhs] = first_use[lhs].min(idx);
-                }
-                Op::Invoke {
-                    function: _,
-                    arguments: args,
-                    results: _,
-                    generics: _,
-                } => {
-                    for &Register(arg) in args {
-                        last_use[arg] = last_use[arg].max(idx);
-                        first_use[arg] = first_use[arg].min(idx);
-                    }
-                }
-                // Not a use of the return value itself.
-                &Op::InvokedResult {
-                    invocation: Register(invocation),
-                    ..
-                } => {
-                    last_use[invocation] = last_use[invocation].max(idx);
-                    first_use[invocation] = first_use[invocation].min(idx);
-                }
+        // This is pretty much lint status, actually. Nothing intensely bad happens if we paint
+        // outside the image, we could just paint less of it.
+        if let Some(concrete) = desc_below.as_concrete() {
+            if !Rectangle::with_layout(&concrete.layout).contains(rect) {
+                return Err(size_mismatch);
             }
         }
 
-        let mut reg_to_texture: HashMap<Register, Texture> = HashMap::default();
+        let op = Op::Binary {
+            lhs: below,
+            rhs: above,
+            op: BinaryOp::Inscribe {
+                placement: rect.normalize(),
+                selection: Rectangle::with_layout(&desc_above.layout),
+            },
+            desc: desc_below.clone(),
+        };
 
-        let mut signature_in: Vec<Register> = vec![];
-        let mut signature_out: Vec<Register> = vec![];
+        Ok(self.push(op))
+    }
 
-        let realize_texture = |idx, op: &Op| {
-            let liveness = first_use[idx]..last_use[idx];
+    /// Embed this image as part of a larger one, clipped to the bounds of the larger one.
+    ///
+    /// The placement must have the size of `above` but, contrary to [`Self::inscribe`], it may
+    /// extend beyond `below` on any side. Only the part of `above` that overlaps `below` is painted.
+    /// If they do not overlap at all the result is a copy of `below`.
+    pub fn inscribe_clipped(
+        &mut self,
+        below: Register,
+        placement: SignedRectangle,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        let desc_below = self.texture_of(below)?;
+        let desc_above = self.texture_of(above)?;
 
-            // FIXME: not all our High ops actually allocate textures..
-            let descriptor = command
-                .describe_reg(if let Op::Output { src } = op {
-                    *src
-                } else if let Op::Render { src } = op {
-                    *src
-                } else {
-                    Register(idx)
-                })
-                .as_texture()
-                .expect("A texture register");
+        if desc_above.descriptor_chroma() != desc_below.descriptor_chroma() {
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(desc_below.clone(), desc_above.clone()),
+            });
+        }
 
-            let descriptor = descriptor.monomorphize(tys);
+        let concrete_required = || CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        };
 
-            let ImageBufferAssignment { buffer: _, texture } = image_buffers
-                .borrow_mut()
-                .alloc_texture_for(&descriptor, liveness, Register(idx));
+        let desc_above = desc_above.as_concrete().ok_or_else(concrete_required)?;
+        let desc_below = desc_below.as_concrete().ok_or_else(concrete_required)?;
 
-            Ok(texture)
-        };
+        if (placement.width(), placement.height()) != desc_above.size() {
+            return Err(CommandError {
+                inner: CommandErrorKind::SizeMismatch {
+                    lhs: below,
+                    rhs: above,
+                },
+            });
+        }
 
-        let realize_buffer = |idx, op: &Op| {
-            let liveness = first_use[idx]..last_use[idx];
+        let bounds = Rectangle::from(&desc_below);
+        let visible = SignedRectangle::try_from(bounds)
+            .map_err(|_| CommandError::INVALID_CALL)?
+            .meet(placement);
 
-            let descriptor = command
-                .describe_reg(if let Op::Output { src } = op {
-                    *src
-                } else if let Op::Render { src } = op {
-                    *src
-                } else {
-                    Register(idx)
-                })
-                .as_buffer()
-                .expect("A buffer register");
+        let Ok(target) = Rectangle::try_from(visible) else {
+            return self.crop(below, bounds);
+        };
 
-            let len = descriptor.monomorphize(tys);
-            let ByteBufferAssignment { buffer } =
-                image_buffers
-                    .borrow_mut()
-                    .alloc_buffer_for(len, liveness, Register(idx));
+        if target.width() == 0 || target.height() == 0 {
+            return self.crop(below, bounds);
+        }
 
-            Ok(buffer)
+        // The visible part relative to the placement, that is the part of above to paint.
+        let local = |visible: i32, origin: i32| (i64::from(visible) - i64::from(origin)) as u32;
+        let selection = Rectangle {
+            x: local(visible.x, placement.x),
+            y: local(visible.y, placement.y),
+            max_x: local(visible.max_x, placement.x),
+            max_y: local(visible.max_y, placement.y),
         };
 
-        for (idx, op) in ops.iter().enumerate() {
-            high_ops.push(High::StackPush(Frame {
-                name: format!("Command: {:#?}", op),
-            }));
+        let op = Op::Binary {
+            lhs: below,
+            rhs: above,
+            op: BinaryOp::Inscribe {
+                placement: target,
+                selection,
+            },
+            desc: desc_below.into(),
+        };
 
-            let idx_reg = Register(idx);
+        Ok(self.push(op))
+    }
 
-            let knob = match command.knobs.get(&idx_reg) {
-                Some(KnobKind::Runtime) => KnobUser::Runtime(mono.next_knob(idx_reg)),
-                Some(KnobKind::Buffer { buffer, range }) => {
-                    let byte_assignment =
-                        match image_buffers.borrow().get_register_resources(*buffer) {
-                            Ok(RegisterAssignment::Buffer(buffer)) => buffer,
-                            _ => return Err(CompileError::NotYetImplemented),
-                        };
+    /// Extract some channels from an image data into a new view.
+    ///
+    /// Extracting `ColorChannel::Luma` from an RGB image without such a channel computes the
+    /// luminance from the linear color values, with weights derived from the primaries. Extracting
+    /// `ColorChannel::Alpha` results in an image of alpha texels, which can be injected as the
+    /// alpha channel of another image, see [`Self::inject`].
+    pub fn extract(
+        &mut self,
+        src: Register,
+        channel: ColorChannel,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
 
-                    KnobUser::Buffer {
-                        buffer: byte_assignment.buffer,
-                        range: range.clone(),
-                    }
-                }
-                None => KnobUser::None,
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if channel == ColorChannel::Luma && desc_src.texel.parts.with_channel(channel).is_none() {
+            return self.extract_luminance(src, &desc_src);
+        }
+
+        let texel = desc_src
+            .texel
+            .channel_texel(channel)
+            .ok_or(CommandError::bad_channel(channel))?;
+
+        let layout = ByteLayout {
+            texel_stride: texel.bits.bytes(),
+            width: desc_src.layout.width,
+            height: desc_src.layout.height,
+            row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+        };
+
+        let color = desc_src.color.clone();
+
+        // Check that we can actually extract that channel.
+        // This could be unimplemented if the position of a particular channel is not yet a stable
+        // detail. 'Virtual' channels such as `Luma` on an RGB image are computed by linear
+        // combination instead of a binary incidence vector, see `extract_luminance`.
+        let channel = ChannelPosition::new(channel).ok_or(CommandError::bad_channel(channel))?;
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::Extract { channel },
+            desc: Descriptor {
+                color,
+                layout,
+                texel,
+            }
+            .into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Compute the luminance of an RGB image as a new `Luma` view.
+    ///
+    /// The weights are the `Y` row of the matrix converting the primaries to CIE XYZ, so they are
+    /// correct for the color space of the source and not only for Rec.709.
+    fn extract_luminance(
+        &mut self,
+        src: Register,
+        desc_src: &Descriptor,
+    ) -> Result<Register, CommandError> {
+        let to_xyz = match &desc_src.color {
+            Color::Rgb {
+                primary,
+                whitepoint,
+                ..
+            } => primary.to_xyz_row_matrix(*whitepoint),
+            _ => return Err(CommandError::bad_channel(ColorChannel::Luma)),
+        };
+
+        let weights = [to_xyz[3], to_xyz[4], to_xyz[5]];
+        let texel = Self::luma_texel(&desc_src.texel)?;
+
+        let layout = ByteLayout {
+            texel_stride: texel.bits.bytes(),
+            width: desc_src.layout.width,
+            height: desc_src.layout.height,
+            row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+        };
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::Luminance { weights },
+            desc: Descriptor {
+                color: desc_src.color.clone(),
+                layout,
+                texel,
+            }
+            .into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// A single `Luma` channel with the bit depth of the channels of a texel.
+    fn luma_texel(texel: &Texel) -> Result<Texel, CommandError> {
+        let bits = match texel.bits {
+            SampleBits::UInt8 | SampleBits::UInt8x2 | SampleBits::UInt8x3 | SampleBits::UInt8x4 => {
+                SampleBits::UInt8
+            }
+            SampleBits::UInt16
+            | SampleBits::UInt16x2
+            | SampleBits::UInt16x3
+            | SampleBits::UInt16x4 => SampleBits::UInt16,
+            SampleBits::Float32
+            | SampleBits::Float32x2
+            | SampleBits::Float32x3
+            | SampleBits::Float32x4 => SampleBits::Float32,
+            _ => return Err(CommandError::UNIMPLEMENTED),
+        };
+
+        Ok(Texel {
+            block: texel.block,
+            bits,
+            parts: SampleParts::Luma,
+        })
+    }
+
+    /// Reinterpret the bytes of an image as another type.
+    ///
+    /// This command requires that the texel type of the register and the descriptor have the same
+    /// size. It will return an error if this is not the case. Additionally, the provided texel
+    /// must be internally consistent.
+    ///
+    /// One important use of this method is to add or removed the color interpretation of an image.
+    /// This can be necessary when it has been algorithmically created or when one wants to
+    /// intentionally ignore such meaning.
+    pub fn transmute(
+        &mut self,
+        src: Register,
+        target: Descriptor,
+    ) -> Result<Register, CommandError> {
+        self.transmute_generic(src, target.into())
+    }
+
+    /// Reinterpret the bytes of an image as another type.
+    ///
+    /// Like [`Self::transmute`] except the target can be a generic. Note however that it must be
+    /// provable that the texels contain the same number of bytes and align in their storage layout
+    /// (see [`SampleBits::bytes`]). This requires both texel types to be concrete, to be the
+    /// exact same generic, or the generics to be bounded by [`GenericBound::TexelBytes`] of the
+    /// size of the other texel.
+    ///
+    /// Other methods for demonstrating this as a bound might be added at a later point but are
+    /// essentially a form of dependent typing, so don't count too much on it.
+    pub fn transmute_generic(
+        &mut self,
+        src: Register,
+        into: GenericDescriptor,
+    ) -> Result<Register, CommandError> {
+        let source = self.texture_of(src)?;
+        let supposed_type = into;
+
+        if source.size() != supposed_type.size() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    supposed_type,
+                    "invalid transmute with mismatched size",
+                ),
+            });
+        }
+
+        // Predict if monomorphize will only do correct transmutes. A transmute re-interprets the
+        // buffer containing bit data in storage layout.
+        fn can_transmute(
+            source: Generic<(Texel, Color)>,
+            target: Generic<(Texel, Color)>,
+            vars: &[TyVarBounds],
+        ) -> bool {
+            let bytes = |chroma: &Generic<(Texel, Color)>| match chroma {
+                Generic::Concrete((texel, _)) => Some(texel.bits.bytes()),
+                Generic::Generic(var) => vars[var.0].texel_bytes().map(u16::from),
             };
 
-            match op {
-                Op::Input { desc: _ } => {
-                    // This implicitly also persists the descriptor
-                    let texture = realize_texture(idx, op)?;
-                    high_ops.push(High::Input(idx_reg));
-                    reg_to_texture.insert(idx_reg, texture);
-                    signature_in.push(idx_reg);
+            match (&source, &target) {
+                (Generic::Generic(vsource), Generic::Generic(vtarget)) if vsource == vtarget => {
+                    true
                 }
-                &Op::Output { src } => {
-                    let _texture = realize_texture(idx, op)?;
-                    signature_out.push(idx_reg);
+                _ => matches!((bytes(&source), bytes(&target)), (Some(a), Some(b)) if a == b),
+            }
+        }
 
-                    high_ops.push(High::Output {
-                        src,
-                        dst: Register(idx),
-                    });
-                }
-                &Op::Render { src } => {
-                    let _texture = realize_texture(idx, op)?;
+        if !can_transmute(
+            source.descriptor_chroma(),
+            supposed_type.descriptor_chroma(),
+            &self.vars,
+        ) {
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(source.clone(), supposed_type),
+            });
+        }
 
-                    high_ops.push(High::Render {
-                        src,
-                        dst: Register(idx),
-                    });
-                }
-                Op::Construct {
-                    desc: _,
-                    op: construct_op,
-                } => {
-                    let texture = realize_texture(idx, op)?;
+        if !supposed_type
+            .as_concrete()
+            .map_or(true, |descriptor| descriptor.is_consistent())
+        {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    supposed_type,
+                    "invalid transmute with inconsistent result",
+                ),
+            });
+        }
 
-                    match construct_op {
-                        &ConstructOp::DistributionNormal(ref distribution) => {
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::Normal2d(
-                                            shaders::DistributionNormal2d {
-                                                data: distribution.clone(),
-                                                spirv: std.distribution_normal2d.clone(),
-                                            },
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            })
-                        }
-                        &ConstructOp::FromBuffer(src) => {
-                            // Well we realized the texture, now just initialize it.
-                            high_ops.push(High::Copy { src, dst: idx_reg });
-                        }
-                        ConstructOp::DistributionNoise(ref noise_params) => {
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::FractalNoise(
-                                            shaders::FractalNoise {
-                                                data: noise_params.clone(),
-                                                spirv: std.fractal_noise.clone(),
-                                            },
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            })
-                        }
-                        &ConstructOp::Bilinear(bilinear) => high_ops.push(High::DrawInto {
-                            dst: Target::Discard(texture),
-                            fn_: Initializer::PaintFullScreen {
-                                shader: ParameterizedFragment {
-                                    invocation: FragmentShaderInvocation::Bilinear(
-                                        shaders::bilinear::Shader {
-                                            data: bilinear,
-                                            spirv: std.bilinear.clone(),
-                                        },
-                                    ),
-                                    knob,
-                                },
-                            },
-                        }),
-                        &ConstructOp::Solid(color) => high_ops.push(High::DrawInto {
-                            dst: Target::Discard(texture),
-                            fn_: Initializer::PaintFullScreen {
-                                shader: ParameterizedFragment {
-                                    invocation: FragmentShaderInvocation::SolidRgb(
-                                        shaders::solid_rgb::Shader {
-                                            data: color.into(),
-                                            spirv: std.solid_rgb.clone(),
-                                        },
-                                    ),
-                                    knob,
-                                },
-                            },
-                        }),
-                    }
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::Transmute,
+            desc: supposed_type,
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Overwrite some channels with overlaid data.
+    ///
+    /// This performs an implicit conversion of the overlaid data to the color channels which is
+    /// performed as if by transmutation. However, contrary to the transmutation we will _only_
+    /// allow the sample parts to be changed arbitrarily.
+    ///
+    /// To perform a mix of two images with differing texels or colors, as if by rendering rather
+    /// than as if by transmute, use `mix` [FIXME: not yet implemented].
+    pub fn inject(
+        &mut self,
+        below: Register,
+        channel: ColorChannel,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        self.inject_with(below, channel, above, None, AffineSample::Nearest)
+    }
+
+    /// Overwrite a channel within a rectangle with overlaid data, stretched to that rectangle.
+    ///
+    /// The overlay is sampled across the placement, for instance to paint a low resolution mask
+    /// into the alpha channel of a larger image. The channel is left untouched outside the
+    /// placement. Otherwise this validates the overlay as [`Self::inject`] does.
+    pub fn inject_at(
+        &mut self,
+        below: Register,
+        channel: ColorChannel,
+        above: Register,
+        rect: Rectangle,
+        sampling: AffineSample,
+    ) -> Result<Register, CommandError> {
+        let desc_below = self.texture_of(below)?;
+
+        if rect.width() == 0 || rect.height() == 0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        if let Some(concrete) = desc_below.as_concrete() {
+            if !Rectangle::with_layout(&concrete.layout).contains(rect) {
+                return Err(CommandError {
+                    inner: CommandErrorKind::SizeMismatch {
+                        lhs: below,
+                        rhs: above,
+                    },
+                });
+            }
+        }
+
+        self.inject_with(below, channel, above, Some(rect), sampling)
+    }
+
+    fn inject_with(
+        &mut self,
+        below: Register,
+        channel: ColorChannel,
+        above: Register,
+        placement: Option<Rectangle>,
+        sampling: AffineSample,
+    ) -> Result<Register, CommandError> {
+        let desc_below = self.texture_of(below)?;
+        let desc_above = self.texture_of(above)?.clone();
+
+        let Generic::Concrete((below_texel, below_color)) = desc_below.descriptor_chroma() else {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_below.clone(),
+                    "inject into non-concrete texel",
+                ),
+            });
+        };
+
+        let Generic::Concrete((above_texel, above_color)) = desc_above.descriptor_chroma() else {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_above.clone(),
+                    "inject from non-concrete texel",
+                ),
+            });
+        };
+
+        let expected_texel = below_texel
+            .channel_texel(channel)
+            .ok_or(CommandError::bad_channel(channel))?;
+
+        if above_texel.parts.num_components() != expected_texel.parts.num_components() {
+            let wanted = GenericDescriptor {
+                chroma: Generic::Concrete((expected_texel, below_color)),
+                ..desc_below.clone()
+            };
+
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(wanted, desc_above),
+            });
+        }
+
+        let from_channels = above_texel.clone();
+        // Override the sample part interpretation for comparison. We ignore this and compare
+        // everything else. This is because we change specifically the parts by this operation.
+        let mut above_texel = above_texel;
+        above_texel.parts = expected_texel.parts;
+
+        // FIXME: should we do parsing instead of validation?
+        // Some type like ChannelPosition but for multiple.
+        if from_channels.channel_weight_vec4().is_none() {
+            return Err(CommandError::bad_channel(channel));
+        }
+
+        if (&expected_texel, &below_color) != (&above_texel, &above_color) {
+            let wanted = GenericDescriptor {
+                chroma: Generic::Concrete((expected_texel, below_color)),
+                ..desc_below.clone()
+            };
+
+            return Err(CommandError {
+                inner: CommandErrorKind::ConflictingTypes(wanted, desc_above),
+            });
+        }
+
+        // Find where to insert, see `extract` for this step.
+        let channel = ChannelPosition::new(channel).ok_or(CommandError::bad_channel(channel))?;
+
+        let op = Op::Binary {
+            lhs: below,
+            rhs: above,
+            op: BinaryOp::Inject {
+                channel,
+                from_channels,
+                placement,
+                sampling,
+            },
+            desc: desc_below.clone(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Grab colors from a palette based on an underlying image of indices.
+    pub fn palette(
+        &mut self,
+        palette: Register,
+        config: Palette,
+        indices: Register,
+    ) -> Result<Register, CommandError> {
+        let color_desc = self.texture_of(palette)?;
+        let idx_desc = self.texture_of(indices)?;
+
+        // FIXME: check that channels are actually in indices' color type.
+        let x_coord = if let Some(coord) = config.width {
+            let pos = ChannelPosition::new(coord).ok_or(CommandError::TYPE_ERR)?;
+            pos.into_vec4()
+        } else {
+            [0.0; 4]
+        };
+
+        let y_coord = if let Some(coord) = config.height {
+            let pos = ChannelPosition::new(coord).ok_or(CommandError::TYPE_ERR)?;
+            pos.into_vec4()
+        } else {
+            [0.0; 4]
+        };
+
+        // Compute the target layout (and that we can represent it).
+        let target_layout = GenericDescriptor {
+            chroma: color_desc.descriptor_chroma(),
+            ..idx_desc.clone()
+        };
+
+        let op = Op::Binary {
+            lhs: palette,
+            rhs: indices,
+            op: BinaryOp::Palette(shaders::palette::ShaderData {
+                x_coord,
+                y_coord,
+                base_x: config.width_base,
+                base_y: config.height_base,
+                address: config.address,
+                interpolate: config.interpolate,
+            }),
+            desc: target_layout,
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Calculate the derivative of an image.
+    ///
+    /// Currently, will only calculate the derivative for color channels. The alpha channel will be
+    /// copied from the source pixel. To also calculate a derivative over the alpha channel you
+    /// should extract it as a value channel, calculate the derivative there and the inject the
+    /// result back to the image.
+    ///
+    /// Taps outside the image are clamped to the edge, unless another mode is chosen with
+    /// [`Derivative::with_border`].
+    pub fn derivative(
+        &mut self,
+        image: Register,
+        config: impl Into<BorderedDerivative>,
+    ) -> Result<Register, CommandError> {
+        let desc = self.texture_of(image)?.clone();
+
+        let op = Op::Unary {
+            src: image,
+            op: UnaryOp::Derivative(config.into()),
+            desc,
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Calculate the gradient of the luma of an image, in polar form.
+    ///
+    /// Both derivatives are evaluated in a single pass, with the kernel of the method along the
+    /// width and its transpose along the height, as in [`Self::derivative`]. The result has a
+    /// two-channel `LumaA` texel of the same sample depth, with scalar color. Luma holds the
+    /// magnitude of the gradient while alpha holds its angle, measured from the width axis
+    /// towards the height axis and normalized from a full turn to `[0, 1)`. Luma weights are
+    /// derived from the color as in [`Self::extract`].
+    ///
+    /// Only methods with a 3×3 kernel are supported and the error for others is deferred to the
+    /// compilation of the program, as for [`Self::derivative`].
+    pub fn gradient_polar(
+        &mut self,
+        src: Register,
+        method: DerivativeMethod,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let weights = Self::channel_weights(&desc_src, ColorChannel::Luma)?;
+
+        let bits = match Self::luma_texel(&desc_src.texel)?.bits {
+            SampleBits::UInt8 => SampleBits::UInt8x2,
+            SampleBits::UInt16 => SampleBits::UInt16x2,
+            _ => SampleBits::Float32x2,
+        };
+
+        let texel = Texel {
+            block: desc_src.texel.block,
+            bits,
+            parts: SampleParts::LumaA,
+        };
+
+        let desc = Descriptor {
+            color: Color::Scalars {
+                transfer: Transfer::Linear,
+            },
+            layout: ByteLayout {
+                texel_stride: texel.bits.bytes(),
+                width: desc_src.layout.width,
+                height: desc_src.layout.height,
+                row_stride: (texel.bits.bytes() as u64) * u64::from(desc_src.layout.width),
+            },
+            texel,
+        };
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::GradientPolar { method, weights },
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Sharpen an image by adding a multiple of its Laplacian.
+    ///
+    /// This is a cheap alternative to an unsharp mask, a single 3×3 pass which subtracts the
+    /// 4-neighbor Laplacian scaled by `amount` from the color channels. The alpha channel is
+    /// copied from the source pixel, borders are clamped to the edge. A negative amount softens
+    /// the image instead. For integer texels the result is clamped to their range. The result has
+    /// the same descriptor as the source.
+    pub fn sharpen(&mut self, src: Register, amount: f32) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if !amount.is_finite() {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let clamp = !matches!(
+            desc.texel.bits,
+            SampleBits::Float32
+                | SampleBits::Float32x2
+                | SampleBits::Float32x3
+                | SampleBits::Float32x4
+        );
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::Sharpen(shaders::sharpen::ShaderData { amount, clamp }),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Emboss an image, as a relief lit from one direction.
+    ///
+    /// The relief is the derivative of luma towards the light, of a 3×3 kernel built from the
+    /// direction, scaled by the depth. It brightens where luma rises towards the light. Without
+    /// recoloring it is offset by a mid-gray, otherwise it is added to the source color. Luma
+    /// weights are derived from the color as in [`Self::extract`]. The alpha channel is copied
+    /// from the source pixel and the result has the same descriptor as the source.
+    pub fn emboss(&mut self, src: Register, emboss: Emboss) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Emboss {
+            direction_rad,
+            depth,
+            recolor,
+        } = emboss;
+
+        if !direction_rad.is_finite() || !depth.is_finite() {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let weights = Self::channel_weights(&desc, ColorChannel::Luma)?;
+
+        let op = Op::Unary {
+            src,
+            op: UnaryOp::Emboss(shaders::emboss::ShaderData {
+                direction_rad,
+                depth,
+                weights,
+                recolor,
+            }),
+            desc: desc.into(),
+        };
+
+        Ok(self.push(op))
+    }
+
+    /// Overlay this image as part of a larger one, performing blending.
+    pub fn blend(
+        &mut self,
+        _below: Register,
+        _rect: Rectangle,
+        _above: Register,
+        _blend: Blend,
+    ) -> Result<Register, CommandError> {
+        // TODO: What blending should we support
+        Err(CommandError::UNIMPLEMENTED)
+    }
+
+    /// A solid color image, from a descriptor and a single color.
+    ///
+    /// Repeats the color across all pixels, then transforms into equivalent texels. The color is
+    /// given in the linear representation of the texel, that is each channel takes its value from
+    /// its position in the color, see [`Self::extract`]. A `Luma` channel takes the first
+    /// component, alpha the last. Components without a channel in the texel are ignored.
+    pub fn solid_rgba(
+        &mut self,
+        describe: Descriptor,
+        color: [f32; 4],
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent constant color image created",
+                ),
+            });
+        }
+
+        let components = usize::from(describe.texel.parts.num_components());
+        if components == 0 || components > color.len() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent color description",
+                ),
+            });
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::Solid(color.to_owned()),
+        }))
+    }
+
+    /// A 2d image with a normal distribution.
+    ///
+    /// The parameters are controlled through the `distribution` parameter while the `texel`
+    /// parameter controls the eventual binary encoding of the image. It must be compatible with a
+    /// single gray channel (but you can have electrical transfer functions, choose arbitrary bit
+    /// widths etc.).
+    ///
+    /// Elongated and rotated distributions are constructed with
+    /// [`DistributionNormal2d::with_rotation`], the isotropic case with
+    /// [`DistributionNormal2d::with_diagonal`].
+    pub fn distribution_normal2d(
+        &mut self,
+        describe: Descriptor,
+        distribution: DistributionNormal2d,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(describe.into(), "inconsistent normal2d"),
+            });
+        }
+
+        if describe.texel.parts != SampleParts::Luma && describe.texel.parts != SampleParts::LumaA {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "normal2d for non-LumA texel",
+                ),
+            });
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::DistributionNormal(distribution),
+        }))
+    }
+
+    /// A 2d image with fractal brownian noise.
+    ///
+    /// The parameters are controlled through the `distribution` parameter. Output contains
+    /// in each of the 4 color channels uncorrelated, 1 dimensional fractal perlin noise.
+    ///
+    /// Each octave scales frequencies by the `lacunarity` and amplitudes by the damping. A
+    /// non-zero `warp` first displaces the coordinates by another evaluation of the same noise.
+    /// The lacunarity must be positive and the warp finite.
+    ///
+    /// The `seed` selects the pattern, the same seed results in the same image on the same device.
+    pub fn distribution_fractal_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for fractal noise",
+                ),
+            });
+        }
+
+        if !distribution.has_valid_octaves() {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::DistributionNoise(distribution),
+        }))
+    }
+
+    /// A 2d image with fractal simplex noise.
+    ///
+    /// Like [`Self::distribution_fractal_noise`], with the same parameters, but each octave is
+    /// gradient noise on a simplex grid which has fewer directional artifacts. Output contains in
+    /// each of the 4 color channels uncorrelated noise.
+    pub fn distribution_simplex_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for simplex noise",
+                ),
+            });
+        }
+
+        if !distribution.has_valid_octaves() {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::DistributionSimplexNoise(distribution),
+        }))
+    }
+
+    /// A 2d image with cellular noise.
+    ///
+    /// Each cell of a grid contains one randomly displaced feature point. The output channels are
+    /// the distance to the nearest feature point, to the second nearest, their difference, and a
+    /// random value in `0.0..1.0` identifying the nearest cell. Distances are in units of cells.
+    pub fn distribution_worley(
+        &mut self,
+        describe: Descriptor,
+        distribution: WorleyParams,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for worley noise",
+                ),
+            });
+        }
+
+        let [width, height] = distribution.cells;
+        if !(width > 0.0 && height > 0.0 && width.is_finite() && height.is_finite()) {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        if !(0.0..=1.0).contains(&distribution.jitter) {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::DistributionWorley(distribution),
+        }))
+    }
+
+    /// A 2d image with a synthetic test pattern.
+    ///
+    /// The patterns are generated for any size of the descriptor.
+    pub fn test_pattern(
+        &mut self,
+        describe: Descriptor,
+        pattern: TestPattern,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for test pattern",
+                ),
+            });
+        }
+
+        match pattern {
+            TestPattern::Checker { size, .. } if size == 0 || i32::try_from(size).is_err() => {
+                return Err(CommandError::INVALID_CALL);
+            }
+            TestPattern::ZonePlate { scale } if !scale.is_finite() => {
+                return Err(CommandError::INVALID_CALL);
+            }
+            _ => {}
+        }
+
+        let size = describe.size();
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::TestPattern(shaders::test_pattern::ShaderData { pattern, size }),
+        }))
+    }
+
+    /// A diagram, coloring each pixel with the color of its nearest seed point.
+    ///
+    /// Every pixel is compared against all seeds, which is fine for a few hundred of them. There
+    /// can be at most [`MAX_POINTS`](shaders::voronoi::MAX_POINTS) seeds and at least one, other
+    /// counts are an error. Distances are measured in pixels, so cells are not stretched in
+    /// non-square images. Ties are resolved to the seed that comes first.
+    pub fn voronoi(
+        &mut self,
+        describe: Descriptor,
+        voronoi: Voronoi,
+    ) -> Result<Register, CommandError> {
+        use shaders::voronoi::MAX_POINTS;
+
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for voronoi diagram",
+                ),
+            });
+        }
+
+        if voronoi.points.is_empty() || voronoi.points.len() > MAX_POINTS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let finite = voronoi
+            .points
+            .iter()
+            .all(|&([x, y], _)| x.is_finite() && y.is_finite());
+
+        if !finite {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let diagram = shaders::voronoi::ShaderData {
+            points: voronoi.points,
+            metric: voronoi.metric,
+            size: describe.size(),
+        };
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::Voronoi(diagram),
+        }))
+    }
+
+    /// A color ramp, interpolating between color stops along one direction of a 2d image.
+    ///
+    /// Each stop is a position in `0.0..=1.0` and a color, given in the linear representation of
+    /// the descriptor's color. The stops must be sorted by position and there can be at most
+    /// [`MAX_STOPS`](shaders::color_ramp::MAX_STOPS) of them. Before the first and after the last
+    /// stop the color is constant.
+    ///
+    /// This can be used to create gradient maps for [`Self::palette`], when `direction` is
+    /// [`Direction::Width`] and the image is N×1.
+    pub fn color_ramp(
+        &mut self,
+        describe: Descriptor,
+        stops: &[(f32, [f32; 4])],
+        direction: Direction,
+        space: RampSpace,
+    ) -> Result<Register, CommandError> {
+        use shaders::color_ramp::{oklab_from_xyz, MAX_STOPS};
+
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for color ramp",
+                ),
+            });
+        }
+
+        if stops.is_empty() || stops.len() > MAX_STOPS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let in_range = stops.iter().all(|&(pos, _)| (0.0..=1.0).contains(&pos));
+        let sorted = stops.windows(2).all(|w| w[0].0 <= w[1].0);
+
+        if !in_range || !sorted {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let oklab = match space {
+            RampSpace::Linear => None,
+            RampSpace::Oklab => match describe.color {
+                Color::Rgb {
+                    primary,
+                    whitepoint,
+                    ..
+                } => Some(Self::oklab_xyz_matrices(primary, whitepoint)),
+                _ => {
+                    return Err(CommandError {
+                        inner: CommandErrorKind::BadDescriptor(
+                            describe.into(),
+                            "oklab color ramp for non-rgb color",
+                        ),
+                    })
+                }
+            },
+        };
+
+        let mut colors = [[0.0; 4]; MAX_STOPS];
+        let mut positions = [0.0; MAX_STOPS];
+
+        for ((color, position), &(pos, stop)) in colors.iter_mut().zip(&mut positions).zip(stops) {
+            *position = pos;
+            *color = match oklab {
+                None => stop,
+                Some((to_xyz, _)) => {
+                    let xyz = to_xyz.multiply_column([stop[0], stop[1], stop[2]]);
+                    let [l, a, b] = oklab_from_xyz(xyz);
+                    [l, a, b, stop[3]]
+                }
+            };
+        }
+
+        let ramp = shaders::color_ramp::ShaderData {
+            colors,
+            positions,
+            oklab: oklab.map(|(_, from_xyz)| from_xyz),
+            count: stops.len() as u32,
+            along_height: direction == Direction::Height,
+        };
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::ColorRamp(ramp),
+        }))
+    }
+
+    /// A smooth color ramp, following a spline through control colors along the width.
+    ///
+    /// Every row of the image has the same colors, so a N×1 image is sufficient to be used as a
+    /// gradient map in [`Self::palette`].
+    pub fn spline_ramp(
+        &mut self,
+        describe: Descriptor,
+        spline: CatmullRom,
+    ) -> Result<Register, CommandError> {
+        use shaders::spline_ramp::MAX_POINTS;
+
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for spline ramp",
+                ),
+            });
+        }
+
+        if spline.points.len() < 4 || spline.points.len() > MAX_POINTS {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let mut points = [[0.0; 4]; MAX_POINTS];
+        points[..spline.points.len()].copy_from_slice(&spline.points);
+
+        let spline = shaders::spline_ramp::ShaderData {
+            points,
+            count: spline.points.len() as u32,
+        };
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::SplineRamp(spline),
+        }))
+    }
+
+    /// Evaluate a bilinear function over a 2d image.
+    ///
+    /// For each color channel, the parameter contains intervals of values that define how its
+    /// value is determined along the width and height axis.
+    ///
+    /// This can be used similar to `numpy`'s `mgrid`.
+    pub fn bilinear(
+        &mut self,
+        describe: Descriptor,
+        distribution: Bilinear,
+    ) -> Result<Register, CommandError> {
+        if !describe.is_consistent() {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    describe.into(),
+                    "inconsistent descriptor for bilinear",
+                ),
+            });
+        }
+
+        Ok(self.push(Op::Construct {
+            desc: describe.into(),
+            op: ConstructOp::Bilinear(distribution),
+        }))
+    }
+
+    /// Overlay an affine transformation of the image.
+    pub fn affine(
+        &mut self,
+        below: Register,
+        affine: Affine,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        // TODO: should we check affine here?
+        let lhs = self.texture_of(below)?.clone();
+        let rhs = self.texture_of(above)?.clone();
+
+        if lhs.descriptor_chroma() != rhs.descriptor_chroma() {
+            return Err(CommandError::TYPE_ERR);
+        }
+
+        match RowMatrix::new(affine.transformation)
+            .det()
+            .abs()
+            .partial_cmp(&f32::EPSILON)
+        {
+            Some(Ordering::Greater | Ordering::Equal) => {}
+            _ => return Err(CommandError::DEGENERATE),
+        }
+
+        match affine.sampling {
+            AffineSample::Nearest => (),
+            AffineSample::BiLinear => {
+                // "Check for a color which we can sample bi-linearly"
+                return Err(CommandError::UNIMPLEMENTED);
+            }
+        }
+
+        Ok(self.push(Op::Binary {
+            lhs: below,
+            rhs: above,
+            op: BinaryOp::Affine(affine),
+            desc: lhs,
+        }))
+    }
+
+    /// Repeat an image periodically to fill a larger area.
+    ///
+    /// The output has the given size, the source is placed at the origin and repeated to the
+    /// right and to the bottom. A size that is not a multiple of the source results in a partial
+    /// repetition at the edges. Combined with a noise generator this makes seamless textures.
+    pub fn tile(&mut self, src: Register, size: (u32, u32)) -> Result<Register, CommandError> {
+        self.tile_with(src, size, false)
+    }
+
+    /// Repeat an image periodically, mirroring every other repetition.
+    ///
+    /// Like [`Self::tile`] but adjacent repetitions are reflections of each other. The result has
+    /// no discontinuity at the seams even when the source is not itself periodic.
+    pub fn tile_mirrored(
+        &mut self,
+        src: Register,
+        size: (u32, u32),
+    ) -> Result<Register, CommandError> {
+        self.tile_with(src, size, true)
+    }
+
+    fn tile_with(
+        &mut self,
+        src: Register,
+        (width, height): (u32, u32),
+        mirrored: bool,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if width == 0 || height == 0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let repeat = [
+            width as f32 / desc_src.layout.width as f32,
+            height as f32 / desc_src.layout.height as f32,
+        ];
+
+        let desc = desc_src
+            .with_size(width, height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        Ok(self.push(Op::Unary {
+            src,
+            op: UnaryOp::Tile { repeat, mirrored },
+            desc: desc.into(),
+        }))
+    }
+
+    /// Grow an image by texels at its edges.
+    ///
+    /// The image is placed at the offset of the left and top padding into an image that is
+    /// larger by the padding. The added texels are determined by the border mode, which makes the
+    /// edge behavior of a subsequent convolution well-defined.
+    pub fn pad(
+        &mut self,
+        src: Register,
+        padding: Padding,
+        border: BorderMode,
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let Padding {
+            left,
+            right,
+            top,
+            bottom,
+        } = padding;
+
+        let (width, height) = desc_src.size();
+        let offset = (
+            i32::try_from(left).map_err(|_| CommandError::INVALID_CALL)?,
+            i32::try_from(top).map_err(|_| CommandError::INVALID_CALL)?,
+        );
+
+        let padded_width = width
+            .checked_add(left)
+            .and_then(|w| w.checked_add(right))
+            .ok_or(CommandError::INVALID_CALL)?;
+        let padded_height = height
+            .checked_add(top)
+            .and_then(|h| h.checked_add(bottom))
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let desc = desc_src
+            .with_size(padded_width, padded_height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        Ok(self.push(Op::Unary {
+            src,
+            op: UnaryOp::Pad(shaders::pad::ShaderData {
+                offset,
+                size: (width, height),
+                border,
+            }),
+            desc: desc.into(),
+        }))
+    }
+
+    /// Select a rectangular part of an image, filling any part outside of it with a color.
+    ///
+    /// The rectangle may extend beyond the image on any side, the result always has the size of
+    /// the rectangle. Texels not covered by the image have the fill color, in the linear
+    /// representation as for [`Self::solid_rgba`].
+    pub fn crop_or_fill(
+        &mut self,
+        src: Register,
+        rect: SignedRectangle,
+        fill: [f32; 4],
+    ) -> Result<Register, CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let (width, height) = (rect.width(), rect.height());
+        if width == 0 || height == 0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        // The image origin relative to the rectangle.
+        let offset = (
+            rect.x.checked_neg().ok_or(CommandError::INVALID_CALL)?,
+            rect.y.checked_neg().ok_or(CommandError::INVALID_CALL)?,
+        );
+
+        let desc = desc_src
+            .with_size(width, height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        Ok(self.push(Op::Unary {
+            src,
+            op: UnaryOp::Pad(shaders::pad::ShaderData {
+                offset,
+                size: desc_src.size(),
+                border: BorderMode::Constant(fill),
+            }),
+            desc: desc.into(),
+        }))
+    }
+
+    /// Add a glow around the bright parts of an image.
+    ///
+    /// The part of each texel whose brightness, the largest of its color channels, exceeds the
+    /// threshold is blurred and added back onto the image. The blur sums the levels of a
+    /// [`Self::pyramid`] of the bright parts, as many as are needed to reach the radius, which
+    /// is cheap even for wide radii. The result has the descriptor of the source.
+    ///
+    /// The image must have linear RGB color, such that the glow is added in linear light.
+    /// Convert other images with [`Self::color_convert`] first.
+    pub fn bloom(&mut self, src: Register, bloom: Bloom) -> Result<Register, CommandError> {
+        let (_, result) = self.bloom_passes(src, bloom)?;
+        Ok(result)
+    }
+
+    /// Issue the passes of a bloom, returning the bright pass and the result.
+    fn bloom_passes(
+        &mut self,
+        src: Register,
+        bloom: Bloom,
+    ) -> Result<(Register, Register), CommandError> {
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if !matches!(
+            desc_src.color,
+            Color::Rgb {
+                transfer: Transfer::Linear,
+                ..
+            }
+        ) {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "bloom of non-linear color",
+                ),
+            });
+        }
+
+        let Bloom {
+            threshold,
+            intensity,
+            radius,
+        } = bloom;
+
+        let valid = [threshold, intensity, radius]
+            .iter()
+            .all(|v| v.is_finite() && *v >= 0.0);
+
+        if !valid {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let (width, height) = desc_src.size();
+
+        // Each level doubles the extent of the blur, up to the level of a single texel.
+        let wanted = 1 + radius.max(1.0).log2().ceil() as u32;
+        let available = 1 + width.max(height).next_power_of_two().trailing_zeros();
+        let levels = wanted.min(available);
+
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let desc_bright =
+            Descriptor::with_texel(texel, width, height).ok_or(CommandError::INVALID_CALL)?;
+
+        let bright = self.push(Op::Unary {
+            src,
+            op: UnaryOp::BrightPass {
+                threshold,
+                intensity,
+            },
+            desc: desc_bright.clone().into(),
+        });
+
+        let pyramid = self.pyramid(bright, levels)?;
+        let (&coarsest, finer) = pyramid.split_last().ok_or(CommandError::INVALID_CALL)?;
+
+        // Sum all levels, each expanded onto the next finer one.
+        let mut glow = coarsest;
+        for &level in finer.iter().rev() {
+            let desc_level = self.texture_of(level)?;
+            let desc_level = desc_level.as_concrete().ok_or(CommandError {
+                inner: CommandErrorKind::ConcreteDescriptorRequired,
+            })?;
+
+            glow = self.push(Op::Binary {
+                lhs: level,
+                rhs: glow,
+                op: BinaryOp::PyramidExpand { subtract: false },
+                desc: desc_level.into(),
+            });
+        }
+
+        let result = self.push(Op::Binary {
+            lhs: src,
+            rhs: glow,
+            op: BinaryOp::Bloom(shaders::bloom::ShaderData {
+                threshold,
+                intensity,
+                levels,
+            }),
+            desc: desc_src.into(),
+        });
+
+        Ok((bright, result))
+    }
+
+    /// Composite an image over its own shadow.
+    ///
+    /// The shadow is the alpha channel of the image, offset, blurred with a gaussian, and tinted
+    /// with the shadow color. The image is then composited over it with the `over` operator. The
+    /// image must have an alpha channel, images without one are an error as their silhouette
+    /// would cover the whole canvas.
+    ///
+    /// Without [`Shadow::expand`] the result has the size of the image and the shadow is cut off
+    /// at its edges. Otherwise the canvas grows by the offset and three standard deviations of
+    /// the blur, the image keeping its place relative to the new top left corner given by
+    /// `max(0, radius - offset)` on each axis. The blur can be at most a third of the
+    /// [`MAX_RADIUS`](shaders::gaussian::MAX_RADIUS) in standard deviations.
+    pub fn drop_shadow(&mut self, src: Register, shadow: Shadow) -> Result<Register, CommandError> {
+        use shaders::gaussian::{ShaderData as Gaussian, MAX_RADIUS};
+
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if desc_src
+            .texel
+            .parts
+            .with_channel(ColorChannel::Alpha)
+            .is_none()
+        {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    desc_src.into(),
+                    "drop shadow of a texel without alpha",
+                ),
+            });
+        }
+
+        let Shadow {
+            offset: (offset_x, offset_y),
+            blur_sigma,
+            color: [r, g, b],
+            opacity,
+            expand,
+        } = shadow;
+
+        let valid = blur_sigma >= 0.0
+            && blur_sigma.is_finite()
+            && Gaussian::radius(blur_sigma) <= MAX_RADIUS
+            && [r, g, b].iter().all(|c| c.is_finite())
+            && (0.0..=1.0).contains(&opacity);
+
+        if !valid {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let image = if expand {
+            let radius = i64::from(Gaussian::radius(blur_sigma));
+            let grow =
+                |extent: i64| u32::try_from(extent.max(0)).map_err(|_| CommandError::INVALID_CALL);
+
+            let padding = Padding {
+                left: grow(radius - i64::from(offset_x))?,
+                right: grow(radius + i64::from(offset_x))?,
+                top: grow(radius - i64::from(offset_y))?,
+                bottom: grow(radius + i64::from(offset_y))?,
+            };
+
+            self.pad(src, padding, BorderMode::Constant([0.0; 4]))?
+        } else {
+            src
+        };
+
+        let desc = self.texture_of(image)?;
+        let desc = desc.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        let (width, height) = desc.size();
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let desc_blur =
+            Descriptor::with_texel(texel, width, height).ok_or(CommandError::INVALID_CALL)?;
+
+        let mut silhouette = image;
+        for (vertical, shift) in [(false, offset_x), (true, offset_y)] {
+            silhouette = self.push(Op::Unary {
+                src: silhouette,
+                op: UnaryOp::GaussianBlur(Gaussian {
+                    sigma: blur_sigma,
+                    vertical,
+                    shift,
+                    clamp: false,
+                }),
+                desc: desc_blur.clone().into(),
+            });
+        }
+
+        Ok(self.push(Op::Binary {
+            lhs: image,
+            rhs: silhouette,
+            op: BinaryOp::DropShadow([r, g, b, opacity]),
+            desc: desc.into(),
+        }))
+    }
+
+    /// Overlay a projective transformation of the image.
+    ///
+    /// The transformation must map the whole image above into finite points, that is the quad it
+    /// is painted to must not contain any point at infinity.
+    pub fn perspective(
+        &mut self,
+        below: Register,
+        homography: Homography,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        let lhs = self.texture_of(below)?.clone();
+        let rhs = self.texture_of(above)?.clone();
+
+        if lhs.descriptor_chroma() != rhs.descriptor_chroma() {
+            return Err(CommandError::TYPE_ERR);
+        }
+
+        let matrix = RowMatrix::new(homography.matrix);
+        match matrix.det().abs().partial_cmp(&f32::EPSILON) {
+            Some(Ordering::Greater | Ordering::Equal) => {}
+            _ => return Err(CommandError::DEGENERATE),
+        }
+
+        // The points at infinity form a line, which must not cross the image above. It suffices
+        // to check that all corners are strictly on the same side.
+        if let Some(rhs) = rhs.as_concrete() {
+            let (width, height) = (rhs.layout.width as f32, rhs.layout.height as f32);
+
+            let [.., g, h, i] = homography.matrix;
+            let div = [(0.0, 0.0), (width, 0.0), (width, height), (0.0, height)]
+                .map(|(x, y)| g * x + h * y + i);
+
+            if !(div.iter().all(|&w| w > 0.0) || div.iter().all(|&w| w < 0.0)) {
+                return Err(CommandError::DEGENERATE);
+            }
+        }
+
+        Ok(self.push(Op::Binary {
+            lhs: below,
+            rhs: above,
+            op: BinaryOp::Perspective(homography),
+            desc: lhs,
+        }))
+    }
+
+    pub fn resize(&mut self, below: Register, upper: (u32, u32)) -> Result<Register, CommandError> {
+        let (width, height) = upper;
+        let grid_layout = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), width, height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let grid = self.bilinear(
+            grid_layout,
+            shaders::bilinear::ShaderData {
+                u_min: [0.0, 0.0, 0.0, 1.0],
+                v_min: [0.0, 0.0, 0.0, 1.0],
+                uv_min: [0.0, 0.0, 0.0, 1.0],
+                u_max: [1.0, 0.0, 0.0, 1.0],
+                v_max: [0.0, 1.0, 0.0, 1.0],
+                uv_max: [0.0, 0.0, 0.0, 1.0],
+            },
+        )?;
+
+        self.palette(
+            below,
+            Palette {
+                width: Some(ColorChannel::R),
+                height: Some(ColorChannel::G),
+                width_base: 0,
+                height_base: 0,
+                address: PaletteAddress::Clamp,
+                interpolate: false,
+            },
+            grid,
+        )
+    }
+
+    /// Change the size of an image, with a choice of reconstruction filter.
+    ///
+    /// All filters other than [`ResizeFilter::Nearest`] are applied as two separable passes, the
+    /// first along the width and the second along the height. When downscaling, the kernel is
+    /// widened by the scale factor such that every source texel contributes to the texels that
+    /// cover it. The color is filtered as its linear representation. Between the passes it is
+    /// held in floating point texels, such that the negative lobes of the kernels are only
+    /// clamped once, by the texel of the result.
+    pub fn resize_with(
+        &mut self,
+        src: Register,
+        (width, height): (u32, u32),
+        filter: ResizeFilter,
+    ) -> Result<Register, CommandError> {
+        use shaders::resample::Kernel;
+
+        let kernel = match filter {
+            ResizeFilter::Nearest => return self.resize(src, (width, height)),
+            ResizeFilter::Bilinear => Kernel::Triangle,
+            ResizeFilter::Bicubic => Kernel::CatmullRom,
+            ResizeFilter::Lanczos3 => Kernel::Lanczos3,
+        };
+
+        let desc_src = self.texture_of(src)?;
+
+        let desc_src = desc_src.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if width == 0 || height == 0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let src_width = desc_src.layout.width;
+        let src_height = desc_src.layout.height;
+
+        let texel = Texel {
+            block: Block::Pixel,
+            bits: SampleBits::Float32x4,
+            parts: SampleParts::RgbA,
+        };
+
+        let along_width =
+            Descriptor::with_texel(texel, width, src_height).ok_or(CommandError::INVALID_CALL)?;
+        let along_height = desc_src
+            .with_size(width, height)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        let src = self.push(Op::Unary {
+            src,
+            op: UnaryOp::Resample(shaders::resample::ShaderData {
+                kernel,
+                vertical: false,
+                scale: src_width as f32 / width as f32,
+            }),
+            desc: along_width.into(),
+        });
+
+        Ok(self.push(Op::Unary {
+            src,
+            op: UnaryOp::Resample(shaders::resample::ShaderData {
+                kernel,
+                vertical: true,
+                scale: src_height as f32 / height as f32,
+            }),
+            desc: along_height.into(),
+        }))
+    }
+
+    /// Declare an output.
+    ///
+    /// Outputs MUST later be bound from the pool during launch.
+    pub fn output(&mut self, src: Register) -> Result<(Register, GenericDescriptor), CommandError> {
+        let outformat = self.texture_of(src)?.clone();
+        // Ignore this, it doesn't really produce a register.
+        let register = self.push(Op::Output { src });
+        Ok((register, outformat))
+    }
+
+    /// Declare an output, whose result may be painted into the texture of an input.
+    ///
+    /// This is a hint for the allocation of textures. Without it every result is painted into a
+    /// texture of its own, even if an input of the same descriptor is not used any further. With
+    /// it `result` reuses the texture of `src` if `src` is no longer read when `result` is
+    /// painted. Otherwise, and in particular when the command painting `result` reads `src`
+    /// itself, the result is allocated separately as a draw can not sample its own target.
+    ///
+    /// The input must be declared by [`Self::input`] with the same descriptor as the result.
+    pub fn output_in_place(
+        &mut self,
+        src: Register,
+        result: Register,
+    ) -> Result<(Register, GenericDescriptor), CommandError> {
+        self.validate_in_place(src, result)?;
+        let (register, outformat) = self.output(result)?;
+        self.in_place.insert(result, src);
+        Ok((register, outformat))
+    }
+
+    /// Declare a buffer as an output.
+    ///
+    /// The bytes are read back after execution with [`Retire::output_buffer`]. The output is
+    /// declared as an image of `u8x4` texels with 64 texels to a row, which can also be retired
+    /// into the pool. As this is exactly the row alignment of the device the bytes of that image
+    /// are those of the buffer, in order. The buffer length must be a multiple of that alignment
+    /// of 256 bytes.
+    ///
+    /// [`Retire::output_buffer`]: crate::run::Retire::output_buffer
+    pub fn output_buffer(&mut self, src: Register) -> Result<(Register, Descriptor), CommandError> {
+        let descriptor = self.buffer_as_image(src)?;
+        let image = self.from_buffer(src, descriptor.clone())?;
+        let register = self.push(Op::Output { src: image });
+        Ok((register, descriptor))
+    }
+
+    /// Declare a buffer as a result of this function, returned to the caller.
+    ///
+    /// The results of [`Self::invoke`] are the images followed by the buffers. The buffer is
+    /// returned as the bytes of an image, see [`Self::buffer_input`] for the same restrictions.
+    /// Unlike [`Self::output_buffer`] the caller receives the buffer itself.
+    pub fn buffer_result(&mut self, src: Register) -> Result<Register, CommandError> {
+        self.buffer_as_result(src)?;
+        Ok(self.push(Op::Output { src }))
+    }
+
+    /// The image whose bytes are those of a buffer register, see [`bytes_as_image`].
+    fn buffer_as_image(&self, src: Register) -> Result<Descriptor, CommandError> {
+        let buffer = self.buffer_of(src)?;
+
+        let len = match buffer.size {
+            Generic::Concrete(len) => len,
+            Generic::Generic(_) => {
+                return Err(CommandError {
+                    inner: CommandErrorKind::ConcreteDescriptorRequired,
+                })
+            }
+        };
+
+        bytes_as_image(len).ok_or(CommandError::INVALID_CALL)
+    }
+
+    /// Check that a buffer can be returned to the caller as the bytes of an image.
+    ///
+    /// Unlike [`Self::buffer_as_image`] this admits a buffer with the length of the encoded image
+    /// of a generic parameter, whose rows are always aligned to the device.
+    fn buffer_as_result(&self, src: Register) -> Result<(), CommandError> {
+        match self.buffer_of(src)?.size {
+            Generic::Concrete(_) => self.buffer_as_image(src).map(|_| ()),
+            Generic::Generic(_) => Ok(()),
+        }
+    }
+
+    /// Declare a render target.
+    ///
+    /// Render targets MUST later be bound from the pool during launch, similar to outputs. However, they are not assumed to be readable afterwards and will never be a copy target.
+    ///
+    /// The target register must be renderable, i.e. a color with a native texture representation.
+    pub fn render(&mut self, src: Register) -> Result<(Register, Descriptor), CommandError> {
+        let outformat = self.texture_of(src)?.clone();
+
+        let outformat = outformat.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        // FIXME: this is too conservative! We need to ensure that our internal assumption about
+        // the texture descriptor is compatible with available wgpu formats (and yields the same
+        // result).
+        if ImageDescriptor::new(&outformat).is_err() {
+            return Err(CommandError::TYPE_ERR);
+        }
+
+        // Ignore this, it doesn't really produce a register.
+        let register = self.push(Op::Render { src });
+        Ok((register, outformat))
+    }
+
+    /// Configure a next, parameterized, operation whose parameter structure can be overridden at
+    /// runtime.
+    pub fn with_knob(&mut self) -> WithKnob<'_> {
+        WithKnob {
+            inner: self,
+            label: None,
+        }
+    }
+
+    /// Similar to `with_knob` but here we can use a different set of calls.
+    ///
+    /// The next parameterized operation is called with its parameter structure copied from the
+    /// given buffer, instead of parameters supplied statically in the command buffer.
+    ///
+    /// Where it would be necessary to do indirect paint calls it'll get more complicated in the
+    /// translation stage (need new `Low` ops) but it should be simple for a few other calls.
+    pub fn with_buffer(&mut self, register: Register) -> Result<WithBuffer<'_>, CommandError> {
+        let buffer = self.buffer_of(register)?;
+
+        let len = buffer.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        Ok(WithBuffer {
+            inner: self,
+            guaranteed_len: len,
+            start: 0,
+            register,
+        })
+    }
+}
+
+/// Commands that operate on buffers.
+impl CommandBuffer {
+    /// Construct a buffer by initializing it with data from memory.
+    ///
+    /// The binary value will be copied into a buffer held by the execution state. If you intend to
+    /// modify that buffer with each execution, see [`Self::with_knob`] and [`WithKnob::buffer_init`].
+    ///
+    /// FIXME: late errors depending on `wgpu` since we copy the buffer and that requires it to be
+    /// a multiple of `4`. This contradicts the notion that the hardware is chosen at a later
+    /// stage.. We should instead compute?
+    pub fn buffer_init(&mut self, init: &[u8]) -> Register {
+        use core::convert::TryInto as _;
+        let size: u64 = init.len().try_into().unwrap();
+
+        self.push(Op::BufferInit {
+            desc: GenericBuffer {
+                size: Generic::Concrete(size),
+            },
+            op: BufferInitOp::FromData {
+                placement: 0..init.len(),
+                data: Arc::from(init),
+            },
+        })
+    }
+
+    /// Construct a buffer that is fully zeroed from memory.
+    pub fn buffer_zero(&mut self, len: u64) -> Register {
+        self.push(Op::BufferInit {
+            desc: GenericBuffer {
+                size: Generic::Concrete(len),
+            },
+            op: BufferInitOp::FromData {
+                placement: 0..0,
+                data: Arc::default(),
+            },
+        })
+    }
+
+    /// Construct a buffer filled with repetitions of a pattern.
+    ///
+    /// The pattern is written once and then repeated by copies on the device, the host never holds
+    /// the full contents. The length of the buffer, `count` times that of the pattern, must be a
+    /// multiple of the copy alignment of four bytes, otherwise this fails with
+    /// [`CommandErrorKind::MisalignedRange`]. A pattern whose own length is not aligned is
+    /// repeated up to four times on the host until it is.
+    pub fn buffer_splat(&mut self, pattern: &[u8], count: u64) -> Result<Register, CommandError> {
+        if pattern.is_empty() || count == 0 {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let pattern_len = pattern.len() as u64;
+        let len = pattern_len
+            .checked_mul(count)
+            .ok_or_else(|| CommandError::INVALID_CALL)?;
+
+        let align = wgpu::COPY_BUFFER_ALIGNMENT;
+        if len % align != 0 {
+            return Err(CommandError::misaligned_range(0..len));
+        }
+
+        // Terminates at most at `align`, and that many repetitions fit into `count`.
+        let repeat = (1..=align)
+            .find(|repeat| (pattern_len * repeat) % align == 0)
+            .unwrap_or(align);
+
+        Ok(self.push(Op::BufferInit {
+            desc: GenericBuffer {
+                size: Generic::Concrete(len),
+            },
+            op: BufferInitOp::Splat {
+                pattern: Arc::from(pattern.repeat(repeat as usize)),
+            },
+        }))
+    }
+
+    /// Construct a buffer representing *encoded* image data.
+    ///
+    /// In a generic function the image may have the descriptor of a generic parameter, that is
+    /// both its size and chroma are those of the same parameter. The length of the buffer is then
+    /// generic as well and only determined when the function is instantiated. Operations which
+    /// require a concrete length, such as [`Self::with_buffer`], reject such a buffer.
+    ///
+    /// FIXME: semantics of `Ok` depend on `wgpu`. This contradicts the notion that the hardware is
+    /// chosen at a later stage..
+    pub fn buffer_from_image(&mut self, register: Register) -> Result<Register, CommandError> {
+        let tex = self.texture_of(register)?;
+
+        let size = match tex.as_concrete() {
+            Some(descriptor) => Generic::Concrete(
+                descriptor
+                    .u64_gpu_len()
+                    // Well can this even happen? A concrete image with no layout on the GPU?
+                    .ok_or_else(|| CommandError::INVALID_CALL)?,
+            ),
+            // The length of any other mix of generic size and chroma can not be expressed.
+            None => Generic::Generic(tex.single_var().ok_or(CommandError {
+                inner: CommandErrorKind::ConcreteDescriptorRequired,
+            })?),
+        };
+
+        Ok(self.push(Op::BufferUnary {
+            src: register,
+            desc: GenericBuffer { size },
+            op: BufferUnaryOp::FromImage {},
+        }))
+    }
+
+    /// Construct a buffer from a range of the bytes of another.
+    ///
+    /// The source must have a concrete size which contains the range, which must not be empty.
+    /// Both ends of the range must be multiples of the copy alignment of four bytes, otherwise this
+    /// fails with [`CommandErrorKind::MisalignedRange`].
+    pub fn buffer_slice(
+        &mut self,
+        src: Register,
+        range: core::ops::Range<u64>,
+    ) -> Result<Register, CommandError> {
+        let buffer = self.buffer_of(src)?;
+
+        let len = buffer.as_concrete().ok_or(CommandError {
+            inner: CommandErrorKind::ConcreteDescriptorRequired,
+        })?;
+
+        if range.is_empty() || range.end > len {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        let align = wgpu::COPY_BUFFER_ALIGNMENT;
+        if range.start % align != 0 || range.end % align != 0 {
+            return Err(CommandError::misaligned_range(range));
+        }
+
+        Ok(self.push(Op::BufferUnary {
+            src,
+            desc: GenericBuffer {
+                size: Generic::Concrete(range.end - range.start),
+            },
+            op: BufferUnaryOp::Slice { start: range.start },
+        }))
+    }
+
+    /// Construct a buffer by concatenating the bytes of others, in order.
+    ///
+    /// Returns the register along with the range of each part in the result, for instance to
+    /// [`Self::buffer_slice`] a part again or to [`WithBuffer::with_start`] at its parameters.
+    /// All parts must have a concrete size which is a multiple of the copy alignment of four bytes,
+    /// otherwise this fails with [`CommandErrorKind::MisalignedRange`]. A single part is returned
+    /// as is.
+    pub fn buffer_concat(
+        &mut self,
+        parts: &[Register],
+    ) -> Result<(Register, Vec<core::ops::Range<u64>>), CommandError> {
+        let Some((&first, rest)) = parts.split_first() else {
+            return Err(CommandError::INVALID_CALL);
+        };
+
+        let mut ranges = vec![];
+        let mut end = 0u64;
+
+        for &part in parts {
+            let len = self.buffer_of(part)?.as_concrete().ok_or(CommandError {
+                inner: CommandErrorKind::ConcreteDescriptorRequired,
+            })?;
+
+            let start = end;
+            end = start
+                .checked_add(len)
+                .ok_or_else(|| CommandError::INVALID_CALL)?;
+
+            if len % wgpu::COPY_BUFFER_ALIGNMENT != 0 {
+                return Err(CommandError::misaligned_range(start..end));
+            }
+
+            ranges.push(start..end);
+        }
+
+        let mut result = first;
+        for (&part, range) in rest.iter().zip(&ranges[1..]) {
+            result = self.push(Op::BufferBinary {
+                lhs: result,
+                rhs: part,
+                desc: GenericBuffer {
+                    size: Generic::Concrete(range.end),
+                },
+                op: BufferBinaryOp::Concat {},
+            });
+        }
+
+        Ok((result, ranges))
+    }
+
+    /// Construct a buffer by overlaying one on top of another.
+    ///
+    /// The output buffer is sized according to the underlying buffer. Overflowed data will be
+    /// discarded.
+    pub fn buffer_overlay(
+        &mut self,
+        under: Register,
+        at: u64,
+        over: Register,
+    ) -> Result<Register, CommandError> {
+        let buf = self.buffer_of(under)?;
+        self.buffer_of(over)?;
+
+        // FIXME: generate warnings if out of bounds? There is no use cloning a buffer that I can
+        // see right now, it's all still the exact same content.
+        Ok(self.push(Op::BufferBinary {
+            lhs: under,
+            rhs: over,
+            desc: GenericBuffer {
+                size: buf.size.clone(),
+            },
+            op: BufferBinaryOp::Overlay { at },
+        }))
+    }
+}
+
+impl WithKnob<'_> {
+    /// Wrap commands that generate one register instruction, that is parameterized by the buffer.
+    fn regular_with_knob<T: KnobLayout + ?Sized>(
+        &mut self,
+        op: KnobOp,
+        len: Option<u64>,
+        fn_: impl FnOnce(&mut CommandBuffer) -> Result<Register, CommandError>,
+    ) -> Result<KnobHandle<T>, CommandError> {
+        let register = fn_(&mut self.inner)?;
+
+        let record = knob::KnobRecord {
+            op,
+            label: self.label.clone(),
+            len,
+            align: T::ALIGN,
+        };
+
+        self.inner.knobs.insert(register, KnobKind::Runtime(record));
+        Ok(KnobHandle::new(register, len))
+    }
+
+    /// Attach a human-readable label to the knob, see [`Program::knobs`].
+    pub fn labeled(self, label: impl Into<String>) -> Self {
+        WithKnob {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    /// See [`CommandBuffer::emboss`].
+    ///
+    /// The knob begins with the direction in radians and the depth as two `f32`, padded to 16
+    /// bytes, followed by internal values that should be left unchanged.
+    pub fn emboss(
+        &mut self,
+        src: Register,
+        emboss: Emboss,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::Emboss, None, move |cmd| cmd.emboss(src, emboss))
+    }
+
+    /// See [`CommandBuffer::sharpen`].
+    ///
+    /// The knob begins with the amount as an `f32`, padded to 16 bytes, followed by internal
+    /// values that should be left unchanged.
+    pub fn sharpen(
+        &mut self,
+        src: Register,
+        amount: f32,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::Sharpen, None, move |cmd| cmd.sharpen(src, amount))
+    }
+
+    /// See [`CommandBuffer::denoise_nlm`].
+    ///
+    /// The knob begins with the filter strength `h` as an `f32`, padded to 16 bytes, followed by
+    /// internal values that should be left unchanged.
+    pub fn denoise_nlm(
+        &mut self,
+        src: Register,
+        nlm: Nlm,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::DenoiseNlm, None, move |cmd| {
+            cmd.denoise_nlm(src, nlm)
+        })
+    }
+
+    /// See [`CommandBuffer::chromatic_aberration`].
+    ///
+    /// The knob holds the scales of the three channels, padded to 16 bytes, followed by the center
+    /// padded to 16 bytes.
+    pub fn chromatic_aberration(
+        &mut self,
+        src: Register,
+        aberration: ChromaticAberration,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::ChromaticAberration, None, move |cmd| {
+            cmd.chromatic_aberration(src, aberration)
+        })
+    }
+
+    /// See [`CommandBuffer::chromatic_adaptation`].
+    ///
+    /// FIXME: untested, does this make sense? Knob controls the color transformation matrix
+    /// directly, not semantically.
+    pub fn chromatic_adaptation(
+        &mut self,
+        src: Register,
+        method: ChromaticAdaptationMethod,
+        target: Whitepoint,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::ChromaticAdaptation, None, move |cmd| {
+            cmd.chromatic_adaptation(src, method, target)
+        })
+    }
+
+    /// See [`CommandBuffer::bloom`].
+    ///
+    /// The knob holds the threshold and intensity as the first two `f32`, but only the intensity
+    /// is used by the pass the knob controls. Use [`WithBuffer::bloom`] to vary both.
+    pub fn bloom(
+        &mut self,
+        src: Register,
+        bloom: Bloom,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::Bloom, None, move |cmd| cmd.bloom(src, bloom))
+    }
+
+    /// Record a command whose effect can be switched off with each launch.
+    ///
+    /// The knob is that of the respective method, e.g. [`Self::sharpen`]. The returned handle
+    /// encodes both the parameters as recorded, to enable the command, and those with which it
+    /// reproduces its source, to bypass it. A single executable serves both by writing either into
+    /// the knob before a launch. A bypassed command is still executed, it only has no effect.
+    ///
+    /// Only the commands of [`Bypassable`] have such a configuration.
+    pub fn bypassable(
+        &mut self,
+        src: Register,
+        op: Bypassable,
+    ) -> Result<BypassHandle, CommandError> {
+        let knob = match op {
+            Bypassable::Sharpen(amount) => self.sharpen(src, amount)?,
+            Bypassable::ChromaticAberration(aberration) => {
+                self.chromatic_aberration(src, aberration)?
+            }
+            Bypassable::Bloom(bloom) => self.bloom(src, bloom)?,
+        };
+
+        fn block(encode: impl FnOnce(&mut Vec<u8>) -> BufferInitContent) -> Vec<u8> {
+            let mut buffer = vec![];
+            let content = encode(&mut buffer);
+            content.as_slice(&buffer).to_vec()
+        }
+
+        let (enabled, bypassed) = match &self.inner.ops[knob.register().0] {
+            Op::Unary {
+                op: UnaryOp::Sharpen(data),
+                ..
+            } => {
+                let identity = shaders::sharpen::ShaderData {
+                    amount: 0.0,
+                    ..*data
+                };
+
+                (
+                    block(|buf| shaders::sharpen::binary_data(data, buf)),
+                    block(|buf| shaders::sharpen::binary_data(&identity, buf)),
+                )
+            }
+            Op::Unary {
+                op: UnaryOp::ChromaticAberration(data),
+                ..
+            } => {
+                let identity = ChromaticAberration {
+                    scales: [1.0; 3],
+                    ..*data
+                };
+
+                (
+                    block(|buf| shaders::chromatic_aberration::binary_data(data, buf)),
+                    block(|buf| shaders::chromatic_aberration::binary_data(&identity, buf)),
+                )
+            }
+            Op::Binary {
+                op: BinaryOp::Bloom(data),
+                ..
+            } => {
+                let identity = shaders::bloom::ShaderData {
+                    intensity: 0.0,
+                    ..*data
+                };
+
+                (
+                    block(|buf| shaders::bloom::binary_data(data, buf)),
+                    block(|buf| shaders::bloom::binary_data(&identity, buf)),
+                )
+            }
+            _ => return Err(CommandError::INVALID_CALL),
+        };
+
+        Ok(BypassHandle::new(knob, enabled, bypassed))
+    }
+
+    /// See [`CommandBuffer::chroma_key`].
+    ///
+    /// The knob begins with the key color as three `f32` in the linear representation of the
+    /// source's color, followed by the `f32` tolerance. The next 16 bytes hold the softness and
+    /// the spill suppression, followed by internal values that should be left unchanged.
+    pub fn chroma_key(
+        &mut self,
+        src: Register,
+        key: ChromaKey,
+        output: KeyOutput,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::ChromaKey, None, move |cmd| {
+            cmd.chroma_key(src, key, output)
+        })
+    }
+
+    /// See [`CommandBuffer::tonemap`].
+    ///
+    /// The knob holds the `white` radiance of [`ToneMap::Reinhard`] as the first `f32`, padded to
+    /// 16 bytes.
+    pub fn tonemap(
+        &mut self,
+        src: Register,
+        tonemap: ToneMap,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::ToneMap, None, move |cmd| cmd.tonemap(src, tonemap))
+    }
+
+    /// See [`CommandBuffer::inscribe`].
+    ///
+    /// The knob holds the corners of the quad on which `above` is painted, laid out as for
+    /// [`Self::crop`]. The positions are relative to the size of `below`.
+    pub fn inscribe(
+        &mut self,
+        below: Register,
+        rect: Rectangle,
+        above: Register,
+    ) -> Result<KnobHandle<knob::Quad>, CommandError> {
+        self.regular_with_knob(KnobOp::Inscribe, Some(64), move |cmd| {
+            cmd.inscribe(below, rect, above)
+        })
+    }
+
+    /// See [`CommandBuffer::palette`].
+    ///
+    /// The knob holds the weights of the four channels of `indices` in the texture coordinates,
+    /// as one pair of `f32` for the width and height coordinate per channel. It is followed by the
+    /// base coordinates along width and height in texels of the palette, as two `f32` padded to 16
+    /// bytes. The base coordinates are not rounded, fractional ones sample between texels. The
+    /// third `f32` of that block is `1.0` with [`PaletteAddress::Border`] and `0.0` otherwise, the
+    /// last 16 bytes are the border color. Setting the flag does not change the addressing of
+    /// the sampler, which is only clamped to the edge for the border.
+    pub fn palette(
+        &mut self,
+        palette: Register,
+        config: Palette,
+        indices: Register,
+    ) -> Result<KnobHandle<knob::Palette>, CommandError> {
+        self.regular_with_knob(KnobOp::Palette, Some(64), move |cmd| {
+            cmd.palette(palette, config, indices)
+        })
+    }
+
+    /// See [`CommandBuffer::solid_rgba`].
+    pub fn solid_rgba(
+        &mut self,
+        describe: Descriptor,
+        color: [f32; 4],
+    ) -> Result<KnobHandle<knob::SolidRgba>, CommandError> {
+        self.regular_with_knob(KnobOp::SolidRgba, Some(16), move |cmd| {
+            cmd.solid_rgba(describe, color)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_normal2d`].
+    pub fn distribution_normal2d(
+        &mut self,
+        describe: Descriptor,
+        distribution: DistributionNormal2d,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::DistributionNormal2d, None, move |cmd| {
+            cmd.distribution_normal2d(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_fractal_noise`].
+    pub fn distribution_fractal_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::FractalNoise, None, move |cmd| {
+            cmd.distribution_fractal_noise(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_simplex_noise`].
+    pub fn distribution_simplex_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::SimplexNoise, None, move |cmd| {
+            cmd.distribution_simplex_noise(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_worley`].
+    pub fn distribution_worley(
+        &mut self,
+        describe: Descriptor,
+        distribution: WorleyParams,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::Worley, None, move |cmd| {
+            cmd.distribution_worley(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::voronoi`].
+    pub fn voronoi(
+        &mut self,
+        describe: Descriptor,
+        voronoi: Voronoi,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::Voronoi, None, move |cmd| {
+            cmd.voronoi(describe, voronoi)
+        })
+    }
+
+    /// See [`CommandBuffer::test_pattern`].
+    pub fn test_pattern(
+        &mut self,
+        describe: Descriptor,
+        pattern: TestPattern,
+    ) -> Result<KnobHandle<knob::Raw>, CommandError> {
+        self.regular_with_knob(KnobOp::TestPattern, None, move |cmd| {
+            cmd.test_pattern(describe, pattern)
+        })
+    }
+
+    /// See [`CommandBuffer::bilinear`].
+    pub fn bilinear(
+        &mut self,
+        describe: Descriptor,
+        distribution: Bilinear,
+    ) -> Result<KnobHandle<knob::Bilinear>, CommandError> {
+        self.regular_with_knob(KnobOp::Bilinear, Some(96), move |cmd| {
+            cmd.bilinear(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::buffer_init`].
+    pub fn buffer_init(
+        &mut self,
+        init: &[u8],
+    ) -> Result<KnobHandle<knob::BufferInit>, CommandError> {
+        self.regular_with_knob(KnobOp::BufferInit, Some(init.len() as u64), move |cmd| {
+            Ok(cmd.buffer_init(init))
+        })
+    }
+
+    /// See [`CommandBuffer::from_buffer_at`].
+    ///
+    /// The knob holds the offset into the buffer as a `u64`. It is validated when the knob is set,
+    /// an offset which is not aligned or at which the image does not fit into the buffer is
+    /// rejected before the program is launched.
+    pub fn from_buffer_at(
+        &mut self,
+        buffer_reg: Register,
+        offset: u64,
+        descriptor: Descriptor,
+    ) -> Result<KnobHandle<knob::Offset>, CommandError> {
+        self.regular_with_knob(KnobOp::FromBuffer, Some(8), move |cmd| {
+            cmd.from_buffer_at(buffer_reg, offset, descriptor)
+        })
+    }
+
+    /// See [`CommandBuffer::crop`].
+    ///
+    /// The knob holds the corners of the painted quad as eight pairs of `f32`, 64 bytes in total.
+    /// The first four are the texture coordinates of the selection in the source, normalized to
+    /// `[0, 1]` of its size. The last four are the positions in the result, normalized to `[0, 1]`
+    /// of the painted area which has the size of `rect`. Both go around the corners in the order
+    /// `(min, min)`, `(max, min)`, `(max, max)`, `(min, max)`.
+    ///
+    /// Changing the knob moves or scales the selection but the painted area of the result keeps
+    /// the size of the recorded `rect`.
+    pub fn crop(
+        &mut self,
+        src: Register,
+        rect: Rectangle,
+    ) -> Result<KnobHandle<knob::Quad>, CommandError> {
+        self.regular_with_knob(KnobOp::Crop, Some(64), move |cmd| cmd.crop(src, rect))
+    }
+
+    /// See [`CommandBuffer::affine`].
+    ///
+    /// The knob holds the corners of the quad on which `above` is painted, laid out as for
+    /// [`Self::crop`]. The positions are those after the transformation, relative to the size of
+    /// `below`. The copy of `below` itself is not affected.
+    pub fn affine(
+        &mut self,
+        below: Register,
+        affine: Affine,
+        above: Register,
+    ) -> Result<KnobHandle<knob::Quad>, CommandError> {
+        self.regular_with_knob(KnobOp::Affine, Some(64), move |cmd| {
+            cmd.affine(below, affine, above)
+        })
+    }
+}
+
+impl WithBuffer<'_> {
+    /// Wrap commands that generate one register instruction, that is parameterized by the buffer.
+    fn regular_with_buffer(
+        &mut self,
+        len: u64,
+        fn_: impl FnOnce(&mut CommandBuffer) -> Result<Register, CommandError>,
+    ) -> Result<Register, CommandError> {
+        let range = self.parameter_range(len)?;
+        let register = fn_(&mut self.inner)?;
+
+        self.inner.knobs.insert(
+            register,
+            KnobKind::Buffer {
+                buffer: self.register,
+                range,
+            },
+        );
+
+        Ok(register)
+    }
+
+    /// The region of the buffer holding a parameter block of some length, after the start.
+    fn parameter_range(&self, len: u64) -> Result<core::ops::Range<u64>, CommandError> {
+        let end = self
+            .start
+            .checked_add(len)
+            .ok_or(CommandError::INVALID_CALL)?;
+
+        if self.guaranteed_len < end {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        Ok(self.start..end)
+    }
+
+    /// See [`CommandBuffer::chromatic_aberration`].
+    pub fn chromatic_aberration(
+        &mut self,
+        src: Register,
+        aberration: ChromaticAberration,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 8]>() as u64, move |cmd| {
+            cmd.chromatic_aberration(src, aberration)
+        })
+    }
+
+    /// See [`CommandBuffer::emboss`].
+    ///
+    /// The buffer holds the direction of the light in radians as a single `f32`.
+    pub fn emboss(&mut self, src: Register, emboss: Emboss) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<f32>() as u64, move |cmd| {
+            cmd.emboss(src, emboss)
+        })
+    }
+
+    /// See [`CommandBuffer::sharpen`].
+    ///
+    /// The buffer holds the amount as a single `f32`.
+    pub fn sharpen(&mut self, src: Register, amount: f32) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<f32>() as u64, move |cmd| {
+            cmd.sharpen(src, amount)
+        })
+    }
+
+    /// See [`CommandBuffer::denoise_nlm`].
+    ///
+    /// The buffer holds the filter strength `h` as a single `f32`.
+    pub fn denoise_nlm(&mut self, src: Register, nlm: Nlm) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<f32>() as u64, move |cmd| {
+            cmd.denoise_nlm(src, nlm)
+        })
+    }
+
+    /// See [`CommandBuffer::lens_undistort`].
+    ///
+    /// The buffer holds the radial coefficients `k1, k2, k3`, the tangential coefficients `p1,
+    /// p2`, each group padded to 16 bytes, then the focal length followed by the center, and the
+    /// fill color, all as `f32`.
+    pub fn lens_undistort(
+        &mut self,
+        src: Register,
+        distortion: Distortion,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 16]>() as u64, move |cmd| {
+            cmd.lens_undistort(src, distortion)
+        })
+    }
+
+    /// See [`CommandBuffer::bloom`].
+    ///
+    /// The buffer holds the threshold and the intensity as two `f32`, padded to 16 bytes. Both the
+    /// bright pass and the final composite read them, so either can change with each launch.
+    pub fn bloom(&mut self, src: Register, bloom: Bloom) -> Result<Register, CommandError> {
+        let range = self.parameter_range(core::mem::size_of::<[f32; 4]>() as u64)?;
+        let (bright, result) = self.inner.bloom_passes(src, bloom)?;
+
+        for register in [bright, result] {
+            self.inner.knobs.insert(
+                register,
+                KnobKind::Buffer {
+                    buffer: self.register,
+                    range: range.clone(),
+                },
+            );
+        }
+
+        Ok(result)
+    }
+
+    /// Change the start of the buffer region being passed as dynamic value.
+    ///
+    /// The parameters of the next operation are read from the bytes following the start, which
+    /// must be a multiple of `4`. The region must lie within the buffer.
+    pub fn with_start(self, start: u64) -> Result<Self, CommandError> {
+        if start % 4 != 0 || start > self.guaranteed_len {
+            return Err(CommandError::INVALID_CALL);
+        }
+
+        Ok(WithBuffer { start, ..self })
+    }
+
+    /// See [`CommandBuffer::crop`].
+    ///
+    /// The buffer holds the corners of the painted quad, see [`WithKnob::crop`].
+    pub fn crop(&mut self, src: Register, rect: Rectangle) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[[f32; 2]; 8]>() as u64, move |cmd| {
+            cmd.crop(src, rect)
+        })
+    }
+
+    /// See [`CommandBuffer::affine`].
+    ///
+    /// The buffer holds the corners of the quad on which `above` is painted, see
+    /// [`WithKnob::affine`].
+    pub fn affine(
+        &mut self,
+        below: Register,
+        affine: Affine,
+        above: Register,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[[f32; 2]; 8]>() as u64, move |cmd| {
+            cmd.affine(below, affine, above)
+        })
+    }
+
+    /// See [`CommandBuffer::chromatic_adaptation`].
+    pub fn chromatic_adaptation(
+        &mut self,
+        src: Register,
+        method: ChromaticAdaptationMethod,
+        target: Whitepoint,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 12]>() as u64, move |cmd| {
+            cmd.chromatic_adaptation(src, method, target)
+        })
+    }
+
+    /// See [`CommandBuffer::palette`].
+    ///
+    /// The buffer holds the channel weights and the base coordinates, see [`WithKnob::palette`].
+    pub fn palette(
+        &mut self,
+        palette: Register,
+        config: Palette,
+        indices: Register,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 16]>() as u64, move |cmd| {
+            cmd.palette(palette, config, indices)
+        })
+    }
+
+    /// See [`CommandBuffer::solid_rgba`].
+    pub fn solid_rgba(
+        &mut self,
+        describe: Descriptor,
+        color: [f32; 4],
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 4]>() as u64, move |cmd| {
+            cmd.solid_rgba(describe, color)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_normal2d`].
+    pub fn distribution_normal2d(
+        &mut self,
+        describe: Descriptor,
+        distribution: DistributionNormal2d,
+    ) -> Result<Register, CommandError> {
+        let size = shaders::distribution_normal2d::UNIFORM_SIZE as u64;
+        self.regular_with_buffer(size, move |cmd| {
+            cmd.distribution_normal2d(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_fractal_noise`].
+    pub fn distribution_fractal_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<Register, CommandError> {
+        #[repr(C)]
+        #[repr(align(8))]
+        struct _ForSizePurpose {
+            _0: [f32; 2],
+            _1: f32,
+            _2: f32,
+            _3: u32,
+            _4: [f32; 2],
+            _5: u32,
+        }
+
+        self.regular_with_buffer(core::mem::size_of::<_ForSizePurpose>() as u64, move |cmd| {
+            cmd.distribution_fractal_noise(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_simplex_noise`].
+    pub fn distribution_simplex_noise(
+        &mut self,
+        describe: Descriptor,
+        distribution: FractalNoise,
+    ) -> Result<Register, CommandError> {
+        #[repr(C)]
+        #[repr(align(8))]
+        struct _ForSizePurpose {
+            _0: [f32; 2],
+            _1: f32,
+            _2: f32,
+            _3: u32,
+            _4: [f32; 2],
+            _5: u32,
+        }
+
+        self.regular_with_buffer(core::mem::size_of::<_ForSizePurpose>() as u64, move |cmd| {
+            cmd.distribution_simplex_noise(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::distribution_worley`].
+    pub fn distribution_worley(
+        &mut self,
+        describe: Descriptor,
+        distribution: WorleyParams,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 8]>() as u64, move |cmd| {
+            cmd.distribution_worley(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::voronoi`].
+    ///
+    /// The buffer always has room for the maximum number of seeds, so that a launch can write any
+    /// seed count into it.
+    pub fn voronoi(
+        &mut self,
+        describe: Descriptor,
+        voronoi: Voronoi,
+    ) -> Result<Register, CommandError> {
+        let size = shaders::voronoi::UNIFORM_SIZE as u64;
+        self.regular_with_buffer(size, move |cmd| cmd.voronoi(describe, voronoi))
+    }
+
+    /// See [`CommandBuffer::test_pattern`].
+    pub fn test_pattern(
+        &mut self,
+        describe: Descriptor,
+        pattern: TestPattern,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[f32; 16]>() as u64, move |cmd| {
+            cmd.test_pattern(describe, pattern)
+        })
+    }
+
+    /// See [`CommandBuffer::bilinear`].
+    pub fn bilinear(
+        &mut self,
+        describe: Descriptor,
+        distribution: Bilinear,
+    ) -> Result<Register, CommandError> {
+        self.regular_with_buffer(core::mem::size_of::<[[f32; 4]; 6]>() as u64, move |cmd| {
+            cmd.bilinear(describe, distribution)
+        })
+    }
+
+    /// See [`CommandBuffer::construct_dynamic`].
+    ///
+    /// The buffer holds the data of the shader, as the command declares it with
+    /// [`ShaderData::set_data`]. The declared data is only used for its length.
+    pub fn construct_dynamic(
+        &mut self,
+        dynamic: &dyn ShaderCommand,
+    ) -> Result<Register, CommandError> {
+        let (command, desc) = CommandBuffer::dynamic_invocation(dynamic, 0);
+        let len = Self::dynamic_data_len(&command)?;
+
+        self.regular_with_buffer(len, move |cmd| {
+            Ok(cmd.push(Op::DynamicImage {
+                call: OperandDynKind::Construct,
+                command,
+                desc: desc.into(),
+            }))
+        })
+    }
+
+    /// See [`CommandBuffer::unary_dynamic`].
+    ///
+    /// The buffer holds the data of the shader, see [`Self::construct_dynamic`].
+    pub fn unary_dynamic(
+        &mut self,
+        op: Register,
+        dynamic: &dyn ShaderCommand,
+    ) -> Result<Register, CommandError> {
+        let (command, desc) = CommandBuffer::dynamic_invocation(dynamic, 1);
+        let len = Self::dynamic_data_len(&command)?;
+
+        self.regular_with_buffer(len, move |cmd| {
+            let _input_descriptor = cmd.texture_of(op)?;
+
+            Ok(cmd.push(Op::DynamicImage {
+                call: OperandDynKind::Unary(op),
+                command,
+                desc: desc.into(),
+            }))
+        })
+    }
+
+    /// See [`CommandBuffer::binary_dynamic`].
+    ///
+    /// The buffer holds the data of the shader, see [`Self::construct_dynamic`].
+    pub fn binary_dynamic(
+        &mut self,
+        lhs: Register,
+        rhs: Register,
+        dynamic: &dyn ShaderCommand,
+    ) -> Result<Register, CommandError> {
+        let (command, desc) = CommandBuffer::dynamic_invocation(dynamic, 2);
+        let len = Self::dynamic_data_len(&command)?;
+
+        self.regular_with_buffer(len, move |cmd| {
+            let _input_descriptor = cmd.texture_of(lhs)?;
+            let _input_descriptor = cmd.texture_of(rhs)?;
+
+            Ok(cmd.push(Op::DynamicImage {
+                call: OperandDynKind::Binary { lhs, rhs },
+                command,
+                desc: desc.into(),
+            }))
+        })
+    }
+
+    /// The length of the data a dynamic shader declares, which the buffer replaces.
+    ///
+    /// A shader without data has no parameters to be read from the buffer.
+    fn dynamic_data_len(command: &ShaderInvocation) -> Result<u64, CommandError> {
+        match &command.shader_data {
+            Some(data) => Ok(data.len() as u64),
+            None => Err(CommandError::INVALID_CALL),
+        }
+    }
+}
+
+/// Turn a command buffer into a `Program`.
+impl Linker {
+    /// A linker of the shaders, without any defined functions.
+    pub fn new(core: ShadersCore, std: ShadersStd) -> Self {
+        Linker {
+            core,
+            std,
+            symbols: HashMap::new(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn from_included() -> &'static Self {
+        zosimos_std::from_included()
+    }
+
+    /// Define the function of a name, for command buffers linked by name.
+    ///
+    /// Each name can be defined once, a second definition is rejected.
+    pub fn define(&mut self, name: &str, commands: CommandBuffer) -> Result<(), CompileError> {
+        if self.symbols.contains_key(name) {
+            return Err(CompileError::DuplicateSymbol(name.to_owned()));
+        }
+
+        self.symbols.insert(name.to_owned(), Arc::new(commands));
+        Ok(())
+    }
+
+    /// The definition of a name, see [`Self::define`].
+    pub fn symbol(&self, name: &str) -> Option<&CommandBuffer> {
+        self.symbols.get(name).map(|commands| &**commands)
+    }
+
+    pub fn compile(&self, program: &CommandBuffer) -> Result<Program, CompileError> {
+        self.link(program, &[], &[], &[])
+    }
+
+    /// An unergonomic interface for linking a collection of different command buffers to a
+    /// program. The `functions` are all buffers besides `self` that are linked. `links` describes
+    /// the relation between them. For each buffer (`self` at 0 then incremented across the array)
+    /// a list match all function declarations in that buffer to the command supplying the
+    /// definition. The generic signature must match each declaration it is linked to.
+    ///
+    /// FIXME: higher level interface here. We should be able to configured links with pairs of a
+    /// `FunctionVar` and a higher-level wrapper around a `CommandBuffer` index. Also it makes not
+    /// much sense to treat the `self` special except as a defaulted entry point and for the
+    /// `compile` helper that does not require any linkage.
+    pub fn link(
+        &self,
+        main: &CommandBuffer,
+        tys: &[Descriptor],
+        functions: &[CommandBuffer],
+        links: &[&[usize]],
+    ) -> Result<Program, CompileError> {
+        let buffers = Some(main).into_iter().chain(functions).collect();
+        self.link_buffers(buffers, tys, links)
+    }
+
+    /// Link a command buffer, resolving its functions by their names, see [`Self::define`].
+    ///
+    /// The functions declared by the definitions are resolved in turn. Each declaration must be
+    /// named, with [`CommandBuffer::function_named`], and its name defined in this linker.
+    pub fn link_by_name(
+        &self,
+        main: &CommandBuffer,
+        tys: &[Descriptor],
+    ) -> Result<Program, CompileError> {
+        let mut buffers = vec![main];
+        let mut link_ids: HashMap<&str, usize> = HashMap::new();
+        let mut links: Vec<Vec<usize>> = vec![];
+
+        while let Some(&commands) = buffers.get(links.len()) {
+            let link_idx = links.len();
+            let mut link = vec![];
+
+            for signature in &commands.symbols {
+                let Some(name) = signature.name.as_deref() else {
+                    return Err(CompileError::UnresolvedSymbol {
+                        link_idx,
+                        name: None,
+                    });
+                };
+
+                let target = match link_ids.get(name) {
+                    Some(&target) => target,
+                    None => {
+                        let Some(definition) = self.symbols.get(name) else {
+                            return Err(CompileError::UnresolvedSymbol {
+                                link_idx,
+                                name: Some(name.to_owned()),
+                            });
+                        };
+
+                        buffers.push(definition);
+                        link_ids.insert(name, buffers.len() - 1);
+                        buffers.len() - 1
+                    }
+                };
+
+                link.push(target);
+            }
+
+            links.push(link);
+        }
+
+        let links: Vec<&[usize]> = links.iter().map(Vec::as_slice).collect();
+        self.link_buffers(buffers, tys, &links)
+    }
+
+    /// Link buffers, the first being the entry point, with the functions of each by index.
+    fn link_buffers(
+        &self,
+        buffers: Vec<&CommandBuffer>,
+        tys: &[Descriptor],
+        links: &[&[usize]],
+    ) -> Result<Program, CompileError> {
+        for (link_idx, commands) in buffers.iter().enumerate() {
+            commands.validate().map_err(|errors| {
+                #[cfg(feature = "tracing")]
+                tracing::error!(link_idx, ?errors, "invalid commands");
+                CompileError::InvalidCommands { link_idx, errors }
+            })?;
+        }
+
+        // We can default to 'no links', which is fine..
+        if buffers.len() < links.len() {
+            // Error: more links than functions..
+            return Err(CompileError::LinkMismatch {
+                what: "link listings",
+                expected: buffers.len(),
+                actual: links.len(),
+            });
+        }
+
+        let mut high_ops = vec![];
+
+        let mut monomorphic = Monomorphizing {
+            stack: vec![],
+            monomorphic: HashMap::new(),
+            commands: buffers,
+            knobs: HashMap::new(),
+            knob_records: HashMap::new(),
+            next_knob: Knob(0),
+            current_link_id: 0,
+            current_tys: vec![],
+            current_function: Function(0),
+            link_ids: vec![],
+            callees: vec![],
+        };
+
+        monomorphic.push_function(LinkedMonomorphicSignature {
+            link_idx: 0,
+            name: None,
+            tys: Cow::Borrowed(tys).into_owned(),
+        });
+
+        impl Monomorphizing<'_> {
+            /// Assign a program function index to a specific generic instantiation.
+            ///
+            /// Remembers to process the monomorphization later if it was not instantiated yet.
+            pub fn push_function(&mut self, sig: LinkedMonomorphicSignature) -> Function {
+                let idx = self.monomorphic.len();
+
+                let stack = &mut self.stack;
+                let link_ids = &mut self.link_ids;
+                let callees = &mut self.callees;
+                let command = &self.commands[sig.link_idx];
+
+                *self.monomorphic.entry(sig).or_insert_with_key(|key| {
+                    stack.push(CommandMonomorphization {
+                        link_idx: key.link_idx,
+                        name: key.name.clone(),
+                        function: Function(idx),
+                        command,
+                        tys: Cow::Owned(key.tys.to_vec()),
+                    });
+
+                    link_ids.push(key.link_idx);
+                    callees.push(vec![]);
+
+                    Function(idx)
+                })
+            }
+
+            /// Instantiate a function invoked by the one being translated.
+            ///
+            /// There is no control flow, a function can not return if it re-enters its own
+            /// instantiation. All calls are recorded such that the call closing a cycle is the
+            /// one rejected, regardless of the order in which the functions are translated.
+            pub fn call_function(
+                &mut self,
+                sig: LinkedMonomorphicSignature,
+            ) -> Result<Function, CompileError> {
+                let caller = self.current_function;
+                let callee = self.push_function(sig);
+
+                let mut path = vec![callee];
+                if self.reaches(&mut path, &mut HashSet::new(), caller) {
+                    let cycle = Some(caller)
+                        .into_iter()
+                        .chain(path)
+                        .map(|Function(idx)| self.link_ids[idx])
+                        .collect();
+
+                    return Err(CompileError::RecursiveLink { cycle });
+                }
+
+                self.callees[caller.0].push(callee);
+                Ok(callee)
+            }
+
+            /// Extend a path of calls until it arrives at the function `to`, if it can.
+            fn reaches(
+                &self,
+                path: &mut Vec<Function>,
+                visited: &mut HashSet<Function>,
+                to: Function,
+            ) -> bool {
+                let Some(&last) = path.last() else {
+                    return false;
+                };
+
+                if last == to {
+                    return true;
+                }
+
+                if !visited.insert(last) {
+                    return false;
+                }
+
+                for &next in &self.callees[last.0] {
+                    path.push(next);
+
+                    if self.reaches(path, visited, to) {
+                        return true;
+                    }
+
+                    path.pop();
+                }
+
+                false
+            }
+
+            pub fn next_knob(&mut self, register: Register, record: &knob::KnobRecord) -> Knob {
+                let knob = self.next_knob;
+                self.next_knob.0 += 1;
+
+                let register = RegisterKnob {
+                    link_idx: self.current_link_id,
+                    register,
+                };
+
+                self.knobs
+                    .entry(register)
+                    .or_default()
+                    .push((self.current_tys.clone(), knob));
+                self.knob_records.insert(register, record.clone());
+                knob
+            }
+        }
+
+        let mut functions = vec![];
+        while let Some(top) = monomorphic.stack.pop() {
+            let CommandMonomorphization {
+                link_idx,
+                name,
+                function,
+                command,
+                tys,
+            } = top;
+
+            monomorphic.current_link_id = link_idx;
+            monomorphic.current_tys = tys.to_vec();
+            monomorphic.current_function = function;
+            let links = links.get(link_idx).copied().unwrap_or_default();
+            let instance = name.map(|name| (name, tys.to_vec()));
+
+            #[cfg(feature = "tracing")]
+            let _monomorphization = tracing::debug_span!(
+                "monomorphization",
+                link_idx,
+                function = function.0,
+                tys = ?tys,
+            )
+            .entered();
+
+            let linked = Self::link_in(
+                &self.core,
+                &self.std,
+                command,
+                tys,
+                &mut high_ops,
+                &mut monomorphic,
+                links,
+            )
+            .map_err(|error| {
+                #[cfg(feature = "tracing")]
+                tracing::error!(link_idx, %error, "linking failed");
+
+                match instance {
+                    Some((name, tys)) => CompileError::Instantiating {
+                        name,
+                        tys,
+                        error: Box::new(error),
+                    },
+                    None => error,
+                }
+            })?;
+
+            // FIXME: expand further requested generic instantiations.
+            functions.push(linked);
+        }
+
+        Ok(Program {
+            ops: high_ops,
+            functions,
+            entry_index: 0,
+            buffer_by_op: HashMap::default(),
+            texture_by_op: HashMap::default(),
+            knobs: monomorphic.knobs,
+            knob_records: monomorphic.knob_records,
+            library: crate::program::Library {
+                std: self.std.clone(),
+                core: self.core.clone(),
+            },
+        })
+    }
+
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            level = "debug",
+            skip_all,
+            fields(link_idx = mono.current_link_id, ops = command.ops.len()),
+        )
+    )]
+    fn link_in(
+        core: &ShadersCore,
+        std: &ShadersStd,
+        command: &CommandBuffer,
+        tys: Cow<'_, [Descriptor]>,
+        high_ops: &mut Vec<High>,
+        mono: &mut Monomorphizing,
+        functions: &[usize],
+    ) -> Result<FunctionLinked, CompileError> {
+        if functions.len() != command.symbols.len() {
+            return Err(CompileError::LinkMismatch {
+                what: "functions",
+                expected: command.symbols.len(),
+                actual: functions.len(),
+            });
+        }
+
+        if tys.len() != command.vars.len() {
+            return Err(CompileError::LinkMismatch {
+                what: "generic types",
+                expected: command.vars.len(),
+                actual: tys.len(),
+            });
+        }
+
+        for (idx, (bounds, ty)) in command.vars.iter().zip(tys.iter()).enumerate() {
+            if !bounds.is_satisfied_by(&GenericDescriptor::from(ty.clone()), &[]) {
+                return Err(CompileError::UnsatisfiedBounds(DescriptorVar(idx)));
+            }
+        }
+
+        let ops = &command.ops;
+        let steps = ops.len();
+        let tys = tys.as_ref();
+        let start = high_ops.len();
+
+        let mut last_use = vec![0; steps];
+        let mut first_use = vec![steps; steps];
+
+        let image_buffers = core::cell::RefCell::new(ImageBufferPlan::default());
+
+        // Liveness analysis.
+        for (back_idx, op) in ops.iter().rev().enumerate() {
+            let idx = ops.len() - 1 - back_idx;
+            match op {
+                Op::Input { .. }
+                | Op::BufferInput { .. }
+                | Op::Construct { .. }
+                | Op::BufferInit { .. }
+                | Op::DynamicImage {
+                    call: OperandDynKind::Construct,
+                    ..
+                } => {}
+                &Op::Output { src: Register(src) } => {
+                    last_use[src] = last_use[src].max(idx);
+                    first_use[src] = first_use[src].min(idx);
+                }
+                &Op::Render { src: Register(src) } => {
+                    last_use[src] = last_use[src].max(idx);
+                    first_use[src] = first_use[src].min(idx);
+                }
+                &Op::Unary {
+                    src: Register(src), ..
+                }
+                | &Op::DynamicImage {
+                    call: OperandDynKind::Unary(Register(src)),
+                    ..
+                }
+                | &Op::BufferUnary {
+                    src: Register(src), ..
+                } => {
+                    last_use[src] = last_use[src].max(idx);
+                    first_use[src] = first_use[src].min(idx);
+                }
+                &Op::Binary {
+                    lhs: Register(lhs),
+                    rhs: Register(rhs),
+                    ..
+                }
+                | &Op::BufferBinary {
+                    lhs: Register(lhs),
+                    rhs: Register(rhs),
+                    ..
+                }
+                | &Op::DynamicImage {
+                    call:
+                        OperandDynKind::Binary {
+                            lhs: Register(lhs),
+                            rhs: Register(rhs),
+                        },
+                    ..
+                } => {
+                    last_use[rhs] = last_use[rhs].max(idx);
+                    first_use[rhs] = first_use[rhs].min(idx);
+                    last_use[lhs] = last_use[lhs].max(idx);
+                    first_use[lhs] = first_use[lhs].min(idx);
+                }
+                Op::Invoke {
+                    function: _,
+                    arguments: args,
+                    results: _,
+                    generics: _,
+                    repeat: _,
+                } => {
+                    for &Register(arg) in args {
+                        last_use[arg] = last_use[arg].max(idx);
+                        first_use[arg] = first_use[arg].min(idx);
+                    }
+                }
+                // Not a use of the return value itself.
+                &Op::InvokedResult {
+                    invocation: Register(invocation),
+                    ..
+                }
+                | &Op::InvokedBuffer {
+                    invocation: Register(invocation),
+                    ..
+                } => {
+                    last_use[invocation] = last_use[invocation].max(idx);
+                    first_use[invocation] = first_use[invocation].min(idx);
+                }
+            }
+        }
+
+        // A color conversion whose only use is a copy is painted by that copy instead, which saves
+        // a pass and the intermediate texture. The source of the conversion lives until the copy.
+        let mut fused: HashMap<Register, (Register, &ColorConversion)> = HashMap::default();
+
+        for (idx, op) in ops.iter().enumerate() {
+            let Op::Unary {
+                src,
+                op: UnaryOp::ColorConvert(conversion),
+                ..
+            } = op
+            else {
+                continue;
+            };
+
+            let user = last_use[idx];
+
+            if first_use[idx] != user
+                || command.knobs.contains_key(&Register(idx))
+                || command.knobs.contains_key(&Register(user))
+                || command.dithered.contains(&Register(idx))
+            {
+                continue;
+            }
+
+            let is_copy = match &ops[user] {
+                Op::Unary {
+                    op: UnaryOp::Crop(_),
+                    ..
+                } => true,
+                // Both operands are painted by separate draws.
+                Op::Binary {
+                    lhs,
+                    rhs,
+                    op: BinaryOp::Inscribe { .. },
+                    ..
+                } => lhs != rhs,
+                _ => false,
+            };
+
+            if is_copy {
+                fused.insert(Register(idx), (*src, conversion));
+                last_use[src.0] = last_use[src.0].max(user);
+            }
+        }
+
+        // A crop whose only use is to be inscribed is painted by the inscribe instead, from the
+        // selection of its source. This is only exact if the crop paints all of its texture, as
+        // its remainder would otherwise hold the clear color. The region of a crop may extend
+        // beyond its source, the selection samples the source as the crop would.
+        let mut placed: HashMap<Register, (Register, Rectangle)> = HashMap::default();
+
+        for (idx, op) in ops.iter().enumerate() {
+            let &Op::Unary {
+                src,
+                op: UnaryOp::Crop(region),
+                ref desc,
+            } = op
+            else {
+                continue;
+            };
+
+            let user = last_use[idx];
+
+            if first_use[idx] != user
+                || command.knobs.contains_key(&Register(idx))
+                || command.dithered.contains(&Register(idx))
+            {
+                continue;
+            }
+
+            let Op::Binary {
+                lhs,
+                rhs,
+                op: BinaryOp::Inscribe { .. },
+                ..
+            } = &ops[user]
+            else {
+                continue;
+            };
+
+            let (width, height) = desc.monomorphize(tys).size();
+
+            if *rhs != Register(idx)
+                || lhs == rhs
+                || region.width() < width
+                || region.height() < height
+            {
+                continue;
+            }
+
+            let selection = Rectangle {
+                x: region.x,
+                y: region.y,
+                max_x: region.x.saturating_add(width),
+                max_y: region.y.saturating_add(height),
+            };
+
+            placed.insert(Register(idx), (src, selection));
+            last_use[src.0] = last_use[src.0].max(user);
+
+            // The source may itself be painted by the copy, from its own source.
+            if let Some((fused_src, _)) = fused.get(&src) {
+                last_use[fused_src.0] = last_use[fused_src.0].max(user);
+            }
+        }
+
+        // Consecutive linear color transforms are painted as one affine transform, by the last of
+        // them. The intermediate results must hold linear light without quantization, that is
+        // linear RGB in a floating point texel, and have no other use nor a knob.
+        let mut folded: HashMap<Register, (Register, RowMatrix, [f32; 3])> = HashMap::default();
+        let mut folded_into_use: HashSet<Register> = HashSet::default();
+
+        for (idx, op) in ops.iter().enumerate() {
+            let Op::Unary { src, op, .. } = op else {
+                continue;
+            };
+
+            let Some((matrix, offset)) = op.linear_color_transform() else {
+                continue;
+            };
+
+            if fused.contains_key(&Register(idx)) || command.knobs.contains_key(&Register(idx)) {
+                continue;
+            }
+
+            let Op::Unary {
+                src: inner_src,
+                op: inner_op,
+                desc: inner_desc,
+            } = &ops[src.0]
+            else {
+                continue;
+            };
+
+            let inner = inner_desc.monomorphize(tys);
+            let is_linear_float = matches!(
+                inner.texel.bits,
+                SampleBits::Float16x4 | SampleBits::Float32x4
+            ) && matches!(
+                inner.color,
+                Color::Rgb {
+                    transfer: Transfer::Linear,
+                    ..
+                }
+            );
+
+            if first_use[src.0] != idx
+                || last_use[src.0] != idx
+                || !is_linear_float
+                || command.knobs.contains_key(src)
+                || command.dithered.contains(src)
+                || fused.contains_key(src)
+            {
+                continue;
+            }
+
+            let (root, inner_matrix, inner_offset) = match folded.remove(src) {
+                Some(chain) => chain,
+                None => match inner_op.linear_color_transform() {
+                    Some((matrix, offset)) => (*inner_src, matrix, offset),
+                    None => continue,
+                },
+            };
+
+            let composed = RowMatrix::from(matrix.multiply_right(inner_matrix.into()));
+            let [x, y, z] = matrix.mul_vec(inner_offset);
+            let offset = [x + offset[0], y + offset[1], z + offset[2]];
+
+            folded.insert(Register(idx), (root, composed, offset));
+            folded_into_use.insert(*src);
+            last_use[root.0] = last_use[root.0].max(idx);
+        }
+
+        // The texture and shader with which a copy paints a register.
+        let paint_copy_of =
+            |reg_to_texture: &HashMap<Register, Texture>, register: &Register| match fused
+                .get(register)
+            {
+                Some((src, conversion)) => (reg_to_texture[src], conversion.to_shader(std)),
+                None => (
+                    reg_to_texture[register],
+                    FragmentShaderInvocation::PaintOnTop(core.paint_copy()),
+                ),
+            };
+
+        let mut reg_to_texture: HashMap<Register, Texture> = HashMap::default();
+
+        let mut signature_in: Vec<Register> = vec![];
+        let mut signature_out: Vec<Register> = vec![];
+        let mut signature_buffer_in: Vec<Register> = vec![];
+        let mut signature_buffer_out: Vec<Register> = vec![];
+
+        let realize_texture = |idx, op: &Op| {
+            let liveness = first_use[idx]..last_use[idx];
+
+            // FIXME: not all our High ops actually allocate textures..
+            let descriptor = command
+                .describe_reg(if let Op::Output { src } = op {
+                    *src
+                } else if let Op::Render { src } = op {
+                    *src
+                } else {
+                    Register(idx)
+                })
+                .as_texture()
+                .expect("A texture register");
+
+            let descriptor = descriptor.monomorphize(tys);
+
+            // The texture of an input may be reused once it is no longer read. The paint must not
+            // sample its own target and the staging of a dithered texture differs.
+            let in_place = command.in_place.get(&Register(idx)).filter(|src| {
+                matches!(ops[src.0], Op::Input { .. })
+                    && last_use[src.0] < idx
+                    && !matches!(op, Op::Output { .. } | Op::Render { .. })
+                    && !command.dithered.contains(&Register(idx))
+            });
+
+            let aliased = in_place.and_then(|&src| {
+                image_buffers
+                    .borrow_mut()
+                    .alias_texture_for(&descriptor, src, Register(idx))
+            });
+
+            let ImageBufferAssignment { buffer: _, texture } = match aliased {
+                Some(assigned) => assigned,
+                None => image_buffers.borrow_mut().alloc_texture_for(
+                    &descriptor,
+                    liveness,
+                    Register(idx),
+                ),
+            };
+
+            Ok(texture)
+        };
+
+        let realize_buffer = |idx, op: &Op| {
+            let liveness = first_use[idx]..last_use[idx];
+
+            let descriptor = command
+                .describe_reg(if let Op::Output { src } = op {
+                    *src
+                } else if let Op::Render { src } = op {
+                    *src
+                } else {
+                    Register(idx)
+                })
+                .as_buffer()
+                .expect("A buffer register");
+
+            let len = descriptor.monomorphize(tys);
+            let ByteBufferAssignment { buffer } =
+                image_buffers
+                    .borrow_mut()
+                    .alloc_buffer_for(len, liveness, Register(idx));
+
+            Ok(buffer)
+        };
+
+        for (idx, op) in ops.iter().enumerate() {
+            high_ops.push(High::StackPush(Frame {
+                name: format!("Command: {:#?}", op),
+            }));
+
+            let idx_reg = Register(idx);
+
+            let knob = match command.knobs.get(&idx_reg) {
+                Some(KnobKind::Runtime(record)) => {
+                    KnobUser::Runtime(mono.next_knob(idx_reg, record))
+                }
+                Some(KnobKind::Buffer { buffer, range }) => {
+                    let byte_assignment =
+                        match image_buffers.borrow().get_register_resources(*buffer) {
+                            Ok(RegisterAssignment::Buffer(buffer)) => buffer,
+                            _ => return Err(CompileError::BadOperand(*buffer)),
+                        };
+
+                    KnobUser::Buffer {
+                        buffer: byte_assignment.buffer,
+                        range: range.clone(),
+                    }
+                }
+                None => KnobUser::None,
+            };
+
+            match op {
+                Op::Input { desc: _ } => {
+                    // This implicitly also persists the descriptor
+                    let texture = realize_texture(idx, op)?;
+                    high_ops.push(High::Input(idx_reg));
+                    reg_to_texture.insert(idx_reg, texture);
+                    signature_in.push(idx_reg);
+                }
+                &Op::Output { src } => {
+                    if let Ok(buffer) = command.describe_reg(src).as_buffer() {
+                        // The caller receives the buffer as the bytes of an image, which is the
+                        // texture of this register.
+                        let len = buffer.monomorphize(tys);
+                        let descriptor =
+                            bytes_as_image(len).ok_or(CompileError::BadOperand(src))?;
+                        let liveness = first_use[idx]..last_use[idx];
+
+                        image_buffers.borrow_mut().alloc_texture_for(
+                            &descriptor,
+                            liveness,
+                            idx_reg,
+                        );
+                        signature_buffer_out.push(idx_reg);
+                    } else {
+                        let _texture = realize_texture(idx, op)?;
+                        signature_out.push(idx_reg);
+                    }
+
+                    high_ops.push(High::Output {
+                        src,
+                        dst: Register(idx),
+                    });
+                }
+                Op::BufferInput { desc } => {
+                    let _buffer = realize_buffer(idx, op)?;
+                    let descriptor = bytes_as_image(desc.monomorphize(tys))
+                        .ok_or(CompileError::BadOperand(idx_reg))?;
+
+                    // The caller passes the bytes of an image which are copied to the buffer.
+                    image_buffers
+                        .borrow_mut()
+                        .alloc_io_slot(&descriptor, idx_reg);
+                    high_ops.push(High::Input(idx_reg));
+                    signature_buffer_in.push(idx_reg);
+                }
+                &Op::Render { src } => {
+                    let _texture = realize_texture(idx, op)?;
+
+                    high_ops.push(High::Render {
+                        src,
+                        dst: Register(idx),
+                    });
+                }
+                Op::Construct {
+                    desc: _,
+                    op: construct_op,
+                } => {
+                    let texture = realize_texture(idx, op)?;
+
+                    match construct_op {
+                        &ConstructOp::DistributionNormal(ref distribution) => {
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Normal2d(
+                                            shaders::DistributionNormal2d {
+                                                data: distribution.clone(),
+                                                spirv: std.distribution_normal2d.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &ConstructOp::FromBuffer(src, 0) if knob == KnobUser::None => {
+                            // Well we realized the texture, now just initialize it.
+                            high_ops.push(High::Copy { src, dst: idx_reg });
+                        }
+                        &ConstructOp::FromBuffer(src, offset) => {
+                            high_ops.push(High::CopyAt {
+                                src,
+                                dst: idx_reg,
+                                offset,
+                                knob,
+                            });
+                        }
+                        ConstructOp::DistributionNoise(ref noise_params) => {
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::FractalNoise(
+                                            shaders::FractalNoise {
+                                                data: noise_params.clone(),
+                                                spirv: std.fractal_noise.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        ConstructOp::DistributionSimplexNoise(ref noise_params) => {
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::SimplexNoise(
+                                            shaders::simplex_noise::Shader {
+                                                data: noise_params.clone(),
+                                                spirv: std.simplex_noise.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        ConstructOp::DistributionWorley(ref worley) => {
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Worley(
+                                            shaders::worley::Shader {
+                                                data: worley.clone(),
+                                                spirv: std.worley.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        ConstructOp::Voronoi(ref diagram) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::Voronoi(
+                                        shaders::voronoi::Shader {
+                                            data: diagram.clone(),
+                                            spirv: std.voronoi.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                        &ConstructOp::TestPattern(pattern) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::TestPattern(
+                                        shaders::test_pattern::Shader {
+                                            data: pattern,
+                                            spirv: std.test_pattern.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                        &ConstructOp::Bilinear(bilinear) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::Bilinear(
+                                        shaders::bilinear::Shader {
+                                            data: bilinear,
+                                            spirv: std.bilinear.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                        ConstructOp::ColorRamp(ramp) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::ColorRamp(
+                                        shaders::color_ramp::Shader {
+                                            data: ramp.clone(),
+                                            spirv: std.color_ramp.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                        ConstructOp::SplineRamp(spline) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::SplineRamp(
+                                        shaders::spline_ramp::Shader {
+                                            data: spline.clone(),
+                                            spirv: std.spline_ramp.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                        &ConstructOp::Solid(color) => high_ops.push(High::DrawInto {
+                            dst: Target::Discard(texture),
+                            fn_: Initializer::PaintFullScreen {
+                                shader: ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::SolidRgb(
+                                        shaders::solid_rgb::Shader {
+                                            data: color.into(),
+                                            spirv: std.solid_rgb.clone(),
+                                        },
+                                    ),
+                                    knob,
+                                },
+                            },
+                        }),
+                    }
+
+                    reg_to_texture.insert(idx_reg, texture);
+                }
+                Op::BufferInit {
+                    op: buf_op,
+                    desc: _,
+                } => {
+                    let buffer = realize_buffer(idx, op)?;
+
+                    match buf_op {
+                        BufferInitOp::Splat { pattern } => {
+                            high_ops.push(High::WriteInto {
+                                dst: buffer,
+                                fn_: BufferWrite::Splat {
+                                    pattern: pattern.clone(),
+                                },
+                            });
+                        }
+                        BufferInitOp::FromData { placement, data } => {
+                            high_ops.push(High::WriteInto {
+                                dst: buffer,
+                                fn_: BufferWrite::Zero,
+                            });
+
+                            high_ops.push(High::WriteInto {
+                                dst: buffer,
+                                fn_: BufferWrite::Put {
+                                    placement: placement.clone(),
+                                    data: data.clone(),
+                                    knob: match knob {
+                                        KnobUser::None => None,
+                                        KnobUser::Runtime(idx) => Some(idx),
+                                        _ => unreachable!(
+                                            "Buffer init from buffer does not make sense"
+                                        ),
+                                    },
+                                },
+                            });
+                        }
+                    }
+                }
+                Op::BufferUnary {
+                    src,
+                    op: buf_op,
+                    desc: _,
+                } => {
+                    let _buffer = realize_buffer(idx, op)?;
+
+                    match buf_op {
+                        BufferUnaryOp::FromImage {} => {
+                            // The encoded image buffer is copied as-is.
+                            high_ops.push(High::Copy {
+                                src: *src,
+                                dst: idx_reg,
+                            });
+                        }
+                        &BufferUnaryOp::Slice { start } => {
+                            high_ops.push(High::CopyAt {
+                                src: *src,
+                                dst: idx_reg,
+                                offset: start,
+                                knob: KnobUser::None,
+                            });
+                        }
+                    }
+                }
+                &Op::BufferBinary {
+                    lhs,
+                    rhs,
+                    op: BufferBinaryOp::Concat {},
+                    desc: _,
+                } => {
+                    let _buffer = realize_buffer(idx, op)?;
+                    let at = command
+                        .buffer_of(lhs)
+                        .map_err(|_| CompileError::BadOperand(lhs))?
+                        .monomorphize(tys);
+
+                    high_ops.push(High::CopyInto {
+                        src: lhs,
+                        dst: idx_reg,
+                        target: 0,
+                    });
+
+                    high_ops.push(High::CopyInto {
+                        src: rhs,
+                        dst: idx_reg,
+                        target: at,
+                    });
+                }
+                Op::Unary { .. } if fused.contains_key(&idx_reg) => {
+                    // Painted by its only use, see above.
+                }
+                Op::Unary { .. } if placed.contains_key(&idx_reg) => {
+                    // Painted by the inscribe of its only use, see above.
+                }
+                Op::Unary { .. } if folded_into_use.contains(&idx_reg) => {
+                    // Part of the transform of its only use, see above.
+                }
+                Op::Unary { .. } if folded.contains_key(&idx_reg) => {
+                    let (src, matrix, offset) = folded[&idx_reg];
+                    let texture = realize_texture(idx, op)?;
+
+                    if command.dithered.contains(&idx_reg) {
+                        image_buffers.borrow_mut().dither(texture);
+                    }
+
+                    high_ops.push(High::PushOperand(reg_to_texture[&src]));
+                    high_ops.push(High::DrawInto {
+                        dst: Target::Discard(texture),
+                        fn_: Initializer::PaintFullScreen {
+                            shader: ParameterizedFragment {
+                                invocation: FragmentShaderInvocation::LinearColorMatrix(
+                                    shaders::LinearColorTransform {
+                                        matrix,
+                                        offset,
+                                        spirv: std.linear_color_transform.clone(),
+                                    },
+                                ),
+                                knob,
+                            },
+                        },
+                    });
+
+                    reg_to_texture.insert(idx_reg, texture);
+                }
+                Op::Unary {
+                    desc: _,
+                    src,
+                    op: unary_op,
+                } => {
+                    let texture = realize_texture(idx, op)?;
+
+                    if command.dithered.contains(&idx_reg) {
+                        image_buffers.borrow_mut().dither(texture);
+                    }
+
+                    match unary_op {
+                        &UnaryOp::Crop(region) => {
+                            let target =
+                                Rectangle::with_width_height(region.width(), region.height());
+                            let (operand, invocation) = paint_copy_of(&reg_to_texture, src);
+                            high_ops.push(High::PushOperand(operand));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintToSelection {
+                                    texture: operand,
+                                    selection: region,
+                                    target: target.into(),
+                                    viewport: target,
+                                    shader: ParameterizedFragment { invocation, knob },
+                                },
+                            });
+                        }
+                        UnaryOp::ChromaticAdaptation(adaptation) => {
+                            let invocation = adaptation.to_shader(std)?;
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment { invocation, knob },
+                                },
+                            });
+                        }
+                        UnaryOp::Vignette(vignette) => {
+                            todo!()
+                        }
+                        UnaryOp::ColorConvert(color) => {
+                            // The inherent OptoToLinear transformation gets us to a linear light
+                            // representation. We want to convert this into a compatible (that is,
+                            // using the same observer definition) other linear light
+                            // representation that we then transfer back to an electrical form.
+                            // Note that these two steps happen, conveniently, automatically.
+                            // Usually it is ensured that only two images with the same linear
+                            // light representation are used in a single paint call but this
+                            // violates it on purpose.
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            // FIXME: using a copy here but this means we do this in unnecessarily
+                            // many steps. We first decode to linear color, then draw, then code
+                            // back to the non-linear electrical space.
+                            // We could do this directly from one matrix to another or try using an
+                            // ephemeral intermediate attachment?
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: color.to_shader(std),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        UnaryOp::Extract { channel: _ } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    // This will grab the right channel, that is all of them.
+                                    // The actual conversion is done in de-staging of the result.
+                                    // TODO: evaluate if this is the right way to do it. We could
+                                    // also perform a LinearColorMatrix shader here with close to
+                                    // the same amount of shader code but a precise result.
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::PaintOnTop(
+                                            core.paint_copy(),
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Luminance { weights } => {
+                            let [r, g, b] = *weights;
+                            // Every output channel holds the luminance, the staging of the result
+                            // then only keeps the first of them.
+                            let matrix = RowMatrix::new([r, g, b, r, g, b, r, g, b]);
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::LinearColorMatrix(
+                                            shaders::LinearColorTransform {
+                                                matrix: matrix.into(),
+                                                offset: [0.0; 3],
+                                                spirv: std.linear_color_transform.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::LinearColor(matrix) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::LinearColorMatrix(
+                                            shaders::LinearColorTransform {
+                                                matrix: *matrix,
+                                                offset: [0.0; 3],
+                                                spirv: std.linear_color_transform.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::AffineColor { matrix, offset } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::LinearColorMatrix(
+                                            shaders::LinearColorTransform {
+                                                matrix: *matrix,
+                                                offset: *offset,
+                                                spirv: std.linear_color_transform.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::CmykEncode { black } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Cmyk(
+                                            shaders::cmyk::Shader {
+                                                black: *black,
+                                                direction: shaders::cmyk::Coding::Encode {
+                                                    spirv: std.cmyk_encode.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::GamutMap { mapping, to_xyz } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::GamutMap(
+                                            shaders::gamut::Shader {
+                                                to_xyz: *to_xyz,
+                                                mapping: *mapping,
+                                                spirv: std.gamut_map.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::CmykDecode => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Cmyk(
+                                            shaders::cmyk::Shader {
+                                                black: 0.0,
+                                                direction: shaders::cmyk::Coding::Decode {
+                                                    spirv: std.cmyk_decode.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::AbTransform(transform) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::AbTransform(
+                                            shaders::ab_transform::Shader {
+                                                data: transform.clone(),
+                                                spirv: std.ab_transform.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::ChromaKey(key) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::ChromaKey(
+                                            shaders::chroma_key::Shader {
+                                                data: key.clone(),
+                                                spirv: std.chroma_key.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::DenoiseNlm(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::DenoiseNlm(
+                                            shaders::denoise_nlm::Shader {
+                                                data,
+                                                spirv: std.denoise_nlm.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::Demosaic(cfa) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Demosaic(
+                                            shaders::demosaic::Shader {
+                                                cfa,
+                                                spirv: std.demosaic.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::LensUndistort(distortion) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::LensUndistort(
+                                            shaders::lens_undistort::Shader {
+                                                data: distortion,
+                                                spirv: std.lens_undistort.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::ChromaticAberration(aberration) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::ChromaticAberration(
+                                            shaders::chromatic_aberration::Shader {
+                                                data: aberration,
+                                                spirv: std.chromatic_aberration.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::ToneMap(tonemap) => {
+                            use shaders::tonemap::Operator;
+
+                            let (white, operator) = match *tonemap {
+                                ToneMap::Reinhard { white } => (
+                                    white,
+                                    Operator::Reinhard {
+                                        spirv: std.tonemap_reinhard.clone(),
+                                    },
+                                ),
+                                ToneMap::AcesFitted => (
+                                    1.0,
+                                    Operator::AcesFitted {
+                                        spirv: std.tonemap_aces.clone(),
+                                    },
+                                ),
+                            };
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::ToneMap(
+                                            shaders::tonemap::Shader { white, operator },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Reduce(data) => {
+                            let spirv = match data.reduction {
+                                Reduction::Min => std.reduce_min.clone(),
+                                Reduction::Max => std.reduce_max.clone(),
+                                Reduction::Mean => std.reduce_mean.clone(),
+                            };
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Reduce(
+                                            shaders::reduce::Shader { data: *data, spirv },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Resample(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Resample(
+                                            shaders::resample::Shader {
+                                                data: *data,
+                                                spirv: std.resample.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::Tile { repeat, mirrored } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Tile(
+                                            shaders::tile::Shader {
+                                                repeat,
+                                                mirrored,
+                                                spirv: std.tile.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Downsample(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Downsample(
+                                            shaders::downsample::Shader {
+                                                data: *data,
+                                                spirv: std.downsample.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::BrightPass {
+                            threshold,
+                            intensity,
+                        } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::BrightPass(
+                                            shaders::bright_pass::Shader {
+                                                threshold,
+                                                intensity,
+                                                spirv: std.bright_pass.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::GaussianBlur(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Gaussian(
+                                            shaders::gaussian::Shader {
+                                                data: data.clone(),
+                                                spirv: std.gaussian.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Pad(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Pad(
+                                            shaders::pad::Shader {
+                                                data: *data,
+                                                spirv: std.pad.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::PrefixSum { offset } => {
+                            image_buffers.borrow_mut().accumulate(texture);
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::PrefixSum(
+                                            shaders::prefix_sum::Shader {
+                                                offset,
+                                                spirv: std.prefix_sum.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::HistogramRows(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Histogram(
+                                            shaders::histogram::Shader {
+                                                stage: shaders::histogram::Stage::Rows {
+                                                    data: data.clone(),
+                                                    spirv: std.histogram_rows.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::ClaheHistogram(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Clahe(
+                                            shaders::clahe::Shader {
+                                                data: data.clone(),
+                                                stage: shaders::clahe::Stage::Histogram {
+                                                    spirv: std.clahe_histogram.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        UnaryOp::ClaheMapping(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Clahe(
+                                            shaders::clahe::Shader {
+                                                data: data.clone(),
+                                                stage: shaders::clahe::Stage::Mapping {
+                                                    spirv: std.clahe_mapping.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        UnaryOp::HistogramSum => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Histogram(
+                                            shaders::histogram::Shader {
+                                                stage: shaders::histogram::Stage::Sum {
+                                                    spirv: std.histogram_sum.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::GradientPolar { method, weights } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::GradientPolar(
+                                            shaders::gradient_polar::Shader {
+                                                kernel: method.kernel_3x3()?,
+                                                weights: *weights,
+                                                spirv: std.gradient_polar.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Derivative(derivative) => {
+                            let BorderedDerivative { derivative, border } = derivative;
+                            let invocation =
+                                derivative
+                                    .method
+                                    .to_shader(derivative.direction, *border, std)?;
+
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment { invocation, knob },
+                                },
+                            })
+                        }
+                        &UnaryOp::Emboss(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Emboss(
+                                            shaders::emboss::Shader {
+                                                data,
+                                                spirv: std.emboss.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        &UnaryOp::Sharpen(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Sharpen(
+                                            shaders::sharpen::Shader {
+                                                data,
+                                                spirv: std.sharpen.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        UnaryOp::Transmute => high_ops.push(High::Copy {
+                            src: *src,
+                            dst: Register(idx),
+                        }),
+                    }
+
+                    reg_to_texture.insert(Register(idx), texture);
+                }
+                Op::Binary {
+                    desc: _,
+                    lhs,
+                    rhs,
+                    op: binary_op,
+                } => {
+                    let texture = realize_texture(idx, op)?;
+
+                    let lhs_descriptor = command
+                        .describe_reg(*lhs)
+                        .as_texture()
+                        .unwrap()
+                        .monomorphize(tys);
+
+                    let rhs_descriptor = command
+                        .describe_reg(*rhs)
+                        .as_texture()
+                        .unwrap()
+                        .monomorphize(tys);
+
+                    let lower_region = Rectangle::from(&lhs_descriptor);
+                    let upper_region = Rectangle::from(&rhs_descriptor);
+
+                    match binary_op {
+                        BinaryOp::Affine(affine) => {
+                            let affine_matrix = RowMatrix::new(affine.transformation);
+
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintToSelection {
+                                    texture: reg_to_texture[lhs],
+                                    selection: lower_region,
+                                    target: lower_region.into(),
+                                    viewport: lower_region,
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::PaintOnTop(
+                                            core.paint_copy(),
+                                        ),
+                                        // The knob places the image above.
+                                        knob: KnobUser::None,
+                                    },
+                                },
+                            });
+
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Load(texture),
+                                fn_: Initializer::PaintToSelection {
+                                    texture: reg_to_texture[rhs],
+                                    selection: upper_region,
+                                    target: QuadTarget::from(upper_region).affine(&affine_matrix),
+                                    viewport: lower_region,
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::PaintOnTop(
+                                            affine.sampling.as_paint_on_top(core)?,
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        BinaryOp::Perspective(homography) => {
+                            let inverse = RowMatrix::new(homography.matrix).inv().into_inner();
+
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Perspective(
+                                            shaders::perspective::Shader {
+                                                data: shaders::perspective::ShaderData {
+                                                    inverse,
+                                                    size: (
+                                                        upper_region.width(),
+                                                        upper_region.height(),
+                                                    ),
+                                                    bilinear: matches!(
+                                                        homography.sampling,
+                                                        AffineSample::BiLinear
+                                                    ),
+                                                },
+                                                spirv: std.perspective.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::Inject {
+                            channel,
+                            from_channels,
+                            placement,
+                            sampling,
+                        } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Inject(
+                                            shaders::inject::Shader {
+                                                data: shaders::inject::ShaderData {
+                                                    mix: channel.into_vec4(),
+                                                    color: from_channels
+                                                        .channel_weight_vec4()
+                                                        .unwrap(),
+                                                    placement: placement.unwrap_or(lower_region),
+                                                    size: (
+                                                        upper_region.width(),
+                                                        upper_region.height(),
+                                                    ),
+                                                    bilinear: matches!(
+                                                        sampling,
+                                                        AffineSample::BiLinear
+                                                    ),
+                                                },
+                                                spirv: std.inject.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            })
+                        }
+                        BinaryOp::Inscribe {
+                            placement,
+                            selection,
+                        } => {
+                            let (operand, invocation) = paint_copy_of(&reg_to_texture, lhs);
+                            high_ops.push(High::PushOperand(operand));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintToSelection {
+                                    texture: operand,
+                                    selection: lower_region,
+                                    target: lower_region.into(),
+                                    viewport: lower_region,
+                                    shader: ParameterizedFragment {
+                                        invocation,
+                                        // The knob places the image above.
+                                        knob: KnobUser::None,
+                                    },
+                                },
+                            });
+
+                            // The selection of a fused crop is relative to its source.
+                            let (above, selection) = match placed.get(rhs) {
+                                Some((src, region)) => (
+                                    src,
+                                    Rectangle {
+                                        x: region.x + selection.x,
+                                        y: region.y + selection.y,
+                                        max_x: region.x + selection.max_x,
+                                        max_y: region.y + selection.max_y,
+                                    },
+                                ),
+                                None => (rhs, *selection),
+                            };
+
+                            let (operand, invocation) = paint_copy_of(&reg_to_texture, above);
+                            high_ops.push(High::PushOperand(operand));
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Load(texture),
+                                fn_: Initializer::PaintToSelection {
+                                    texture: operand,
+                                    selection,
+                                    target: (*placement).into(),
+                                    viewport: lower_region,
+                                    shader: ParameterizedFragment { invocation, knob },
+                                },
+                            });
+                        }
+                        BinaryOp::Palette(shader) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Load(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Palette(
+                                            shaders::palette::Shader {
+                                                data: shader.clone(),
+                                                spirv: std.palette.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::GainMap(_) => {
+                            todo!()
+                        }
+                        BinaryOp::FusionStep(step) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::ExposureFusion(
+                                            shaders::exposure_fusion::Shader {
+                                                data: step.clone(),
+                                                spirv: std.exposure_fusion.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::ClaheApply(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Clahe(
+                                            shaders::clahe::Shader {
+                                                data: data.clone(),
+                                                stage: shaders::clahe::Stage::Apply {
+                                                    spirv: std.clahe_apply.clone(),
+                                                },
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        &BinaryOp::Bloom(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Bloom(
+                                            shaders::bloom::Shader {
+                                                data,
+                                                spirv: std.bloom.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        &BinaryOp::DropShadow(color) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::DropShadow(
+                                            shaders::shadow::Shader {
+                                                color,
+                                                spirv: std.shadow.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        &BinaryOp::PyramidExpand { subtract } => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::PyramidExpand(
+                                            shaders::pyramid::Shader {
+                                                subtract,
+                                                spirv: std.pyramid.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::SquaredDifference => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::SquaredDifference(
+                                            shaders::squared_difference::Shader {
+                                                spirv: std.squared_difference.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::Ssim(data) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::Ssim(
+                                            shaders::local_statistics::Shader {
+                                                data: *data,
+                                                spirv: std.ssim.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                        BinaryOp::WhiteBalance(AwbMethod::GrayWorld) => {
+                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
+                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+
+                            high_ops.push(High::DrawInto {
+                                dst: Target::Discard(texture),
+                                fn_: Initializer::PaintFullScreen {
+                                    shader: ParameterizedFragment {
+                                        invocation: FragmentShaderInvocation::WhiteBalance(
+                                            shaders::white_balance::Shader {
+                                                spirv: std.white_balance.clone(),
+                                            },
+                                        ),
+                                        knob,
+                                    },
+                                },
+                            });
+                        }
+                    }
+
+                    reg_to_texture.insert(Register(idx), texture);
+                }
+                Op::DynamicImage { call, command, .. } => {
+                    let texture = realize_texture(idx, op)?;
+                    let (op_unary, op_binary, arguments);
+
+                    match call {
+                        OperandDynKind::Construct => {
+                            arguments = &[][..];
+                            reg_to_texture.insert(Register(idx), texture);
+                        }
+                        OperandDynKind::Unary(reg) => {
+                            op_unary = [reg_to_texture[reg]];
+                            arguments = &op_unary[..];
+                            reg_to_texture.insert(Register(idx), texture);
+                        }
+                        OperandDynKind::Binary { lhs, rhs } => {
+                            op_binary = [reg_to_texture[lhs], reg_to_texture[rhs]];
+                            arguments = &op_binary[..];
+                            reg_to_texture.insert(Register(idx), texture);
+                        }
+                    }
+
+                    if command.num_args != arguments.len() as u32 {
+                        // FIXME: should just error with information. We need to pin-point  the
+                        // source of the num args to either the library (an internal bug) or the
+                        // user for dynamically constructed shaders. Also consider if the number of
+                        // arguments can be recovered from the SPIR-V earlier.
+                        return Err(CompileError::LinkMismatch {
+                            what: "shader arguments",
+                            expected: command.num_args as usize,
+                            actual: arguments.len(),
+                        });
+                    }
+
+                    for &operand in arguments {
+                        high_ops.push(High::PushOperand(operand));
+                    }
+
+                    // This always 'constructs' an output texture. The image we render to is new,
+                    // no matter how many arguments are being inserted.
+                    high_ops.push(High::DrawInto {
+                        dst: Target::Discard(texture),
+                        fn_: Initializer::PaintFullScreen {
+                            shader: ParameterizedFragment {
+                                invocation: FragmentShaderInvocation::Runtime(command.clone()),
+                                knob,
+                            },
+                        },
+                    })
+                }
+                Op::InvokedResult { .. } => {
+                    let texture = realize_texture(idx, op)?;
+
+                    high_ops.push(High::Uninit {
+                        dst: Target::Discard(texture),
+                    });
+
+                    reg_to_texture.insert(Register(idx), texture);
+                }
+                Op::InvokedBuffer { .. } => {
+                    // Filled by the invocation, like the buffer of an image result.
+                    let _buffer = realize_buffer(idx, op)?;
+                }
+                Op::Invoke {
+                    function,
+                    arguments,
+                    results,
+                    generics,
+                    repeat,
+                } => {
+                    let monomorphic_tys: Vec<_> = generics
+                        .iter()
+                        .map(|gen| gen.monomorphize(tys))
+                        .collect::<_>();
+
+                    let &FunctionVar(function_idx) = function;
+                    let Some(&link_idx) = functions.get(function_idx) else {
+                        return Err(CompileError::LinkMismatch {
+                            what: "functions",
+                            expected: function_idx + 1,
+                            actual: functions.len(),
+                        });
+                    };
+
+                    let function = mono.call_function(LinkedMonomorphicSignature {
+                        link_idx,
+                        name: command.symbols[function_idx].name.clone(),
+                        tys: monomorphic_tys,
+                    })?;
+
+                    if let &Some(count) = repeat {
+                        let (&[seed], &[result]) = (&arguments[..], &results[..]) else {
+                            return Err(CompileError::BadOperand(idx_reg));
+                        };
+
+                        let laid_out_texture = |register: Register| {
+                            image_buffers
+                                .borrow()
+                                .get_register_texture(register)
+                                .map_err(|_| CompileError::BadOperand(register))
+                        };
+
+                        let seed_texture = laid_out_texture(seed)?;
+                        let result_texture = laid_out_texture(result)?;
+
+                        // The applications alternate between the result and a scratch texture,
+                        // held by the invocation itself, such that the last one paints the result.
+                        let scratch_texture = if count > 1 {
+                            let descriptor =
+                                image_buffers.borrow().texture[result_texture.0].clone();
+                            let liveness = first_use[idx]..last_use[idx];
+
+                            image_buffers
+                                .borrow_mut()
+                                .alloc_texture_for(&descriptor, liveness, idx_reg)
+                                .texture
+                        } else {
+                            result_texture
+                        };
+
+                        let mut source = (seed, seed_texture);
+
+                        for remaining in (0..count).rev() {
+                            let target = if remaining % 2 == 0 {
+                                (result, result_texture)
+                            } else {
+                                (idx_reg, scratch_texture)
+                            };
+
+                            high_ops.push(High::Call {
+                                function,
+                                image_io_buffers: Arc::from([
+                                    CallBinding::InTexture {
+                                        register: source.0,
+                                        texture: source.1,
+                                    },
+                                    CallBinding::OutTexture {
+                                        register: target.0,
+                                        texture: target.1,
+                                    },
+                                ]),
+                            });
+
+                            source = target;
+                        }
+                    } else {
+                        let mut image_io = vec![];
+
+                        // Buffers are bound by the buffer already laid out for their register.
+                        let laid_out_buffer = |register: Register| match image_buffers
+                            .borrow()
+                            .get_register_resources(register)
+                        {
+                            Ok(RegisterAssignment::Buffer(assigned)) => Ok(assigned.buffer),
+                            _ => Err(CompileError::BadOperand(register)),
+                        };
+
+                        for &register in arguments {
+                            // Arguments must precede the function and already be laid out.
+                            if register.0 >= idx {
+                                return Err(CompileError::BadOperand(register));
+                            }
+
+                            if let RegisterDescription::Buffer(_) = command.describe_reg(register) {
+                                let buffer = laid_out_buffer(register)?;
+                                image_io.push(CallBinding::InBuffer { register, buffer });
+                                continue;
+                            }
+
+                            let texture = realize_texture(register.0, &ops[register.0])?;
+                            image_io.push(CallBinding::InTexture { register, texture });
+                        }
+
+                        for &register in results {
+                            // Results must precede the function and already be laid out. They are not
+                            // initialized but initialized on return.
+                            if register.0 >= idx {
+                                return Err(CompileError::BadOperand(register));
+                            }
+
+                            if let RegisterDescription::Buffer(_) = command.describe_reg(register) {
+                                let buffer = laid_out_buffer(register)?;
+                                image_io.push(CallBinding::OutBuffer { register, buffer });
+                                continue;
+                            }
+
+                            let texture = realize_texture(register.0, &ops[register.0])?;
+                            image_io.push(CallBinding::OutTexture { register, texture });
+                        }
+
+                        high_ops.push(High::Call {
+                            function,
+                            image_io_buffers: Arc::from(image_io),
+                        });
+                    }
+                }
+                // In case we add a new case.
+                #[allow(unreachable_patterns)]
+                _ => return Err(CompileError::NotYetImplemented("this operation")),
+            }
+
+            high_ops.push(High::Done(Register(idx)));
+            high_ops.push(High::StackPop);
+        }
+
+        let end = high_ops.len();
+
+        // The registers which callers must fill. This must match the order that CallBinding is
+        // passed at call sites, i.e. be consistent with the signature: the images, then the
+        // buffers, of the arguments and then of the results.
+        let signature_registers = signature_in
+            .into_iter()
+            .chain(signature_buffer_in)
+            .chain(signature_out)
+            .chain(signature_buffer_out)
+            .collect();
+
+        Ok(FunctionLinked {
+            ops: start..end,
+            image_buffers: image_buffers.into_inner(),
+            signature_registers,
+        })
+    }
+}
+
+/// Impls on `CommandBuffer` that allow defining custom SPIR-V extensions.
+///
+/// Generally, the steps on the dynamic shader are:
+///
+/// 1. Check the kind, get SPIR-v code.
+/// 2. Determine the dynamic typing of the result.
+/// 3. Have the shader create binary representation of its data.
+/// 3. Create a new entry on the command buffer.
+/// 4. Not yet performed: (Validate the SPIR-V module inputs against the data definition)
+impl CommandBuffer {
+    /// Get the descriptor for a register.
+    fn describe_reg(&self, Register(reg): Register) -> RegisterDescription<'_> {
+        match self.ops.get(reg) {
+            None | Some(Op::Output { .. }) | Some(Op::Render { .. }) => RegisterDescription::None,
+            Some(Op::Invoke { .. }) => {
+                // This does not describe results directly.
+                RegisterDescription::None
+            }
+            Some(Op::InvokedResult { desc, .. })
+            | Some(Op::Input { desc })
+            | Some(Op::Construct { desc, .. })
+            | Some(Op::Unary { desc, .. })
+            | Some(Op::Binary { desc, .. })
+            | Some(Op::DynamicImage { desc, .. }) => RegisterDescription::Texture(desc),
+            Some(Op::BufferInit { desc, .. })
+            | Some(Op::BufferInput { desc })
+            | Some(Op::InvokedBuffer { desc, .. })
+            | Some(Op::BufferUnary { desc, .. })
+            | Some(Op::BufferBinary { desc, .. }) => RegisterDescription::Buffer(desc),
+        }
+    }
+
+    /// Get the descriptor of a register holding an image.
+    fn texture_of(&self, register: Register) -> Result<&GenericDescriptor, CommandError> {
+        match self.describe_reg(register) {
+            RegisterDescription::Texture(desc) => Ok(desc),
+            RegisterDescription::Buffer(_) => Err(CommandError {
+                inner: CommandErrorKind::NotATexture(register),
+            }),
+            RegisterDescription::None => Err(CommandError::bad_register(register)),
+        }
+    }
+
+    /// Check that a result may be painted into the texture of an input.
+    fn validate_in_place(&self, src: Register, result: Register) -> Result<(), CommandError> {
+        let Some(Op::Input { desc }) = self.ops.get(src.0) else {
+            return Err(CommandError::INVALID_CALL);
+        };
+
+        let outformat = self.texture_of(result)?;
+
+        if outformat != desc {
+            return Err(CommandError {
+                inner: CommandErrorKind::BadDescriptor(
+                    outformat.clone(),
+                    "in place result differs from its input",
+                ),
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Get the descriptor of a register holding a buffer.
+    fn buffer_of(&self, register: Register) -> Result<&GenericBuffer, CommandError> {
+        match self.describe_reg(register) {
+            RegisterDescription::Buffer(desc) => Ok(desc),
+            RegisterDescription::Texture(_) => Err(CommandError {
+                inner: CommandErrorKind::NotABuffer(register),
+            }),
+            RegisterDescription::None => Err(CommandError::bad_register(register)),
+        }
+    }
+
+    /// The image in an operand, which must be defined before the operation at `idx`.
+    fn texture_before(
+        &self,
+        idx: usize,
+        register: Register,
+    ) -> Result<&GenericDescriptor, CommandError> {
+        if register.0 >= idx {
+            return Err(CommandError::bad_register(register));
+        }
+
+        self.texture_of(register)
+    }
+
+    /// The buffer in an operand, which must be defined before the operation at `idx`.
+    fn buffer_before(
+        &self,
+        idx: usize,
+        register: Register,
+    ) -> Result<&GenericBuffer, CommandError> {
+        if register.0 >= idx {
+            return Err(CommandError::bad_register(register));
+        }
+
+        self.buffer_of(register)
+    }
+
+    /// Check an operation against those preceding it, see [`Self::validate`].
+    fn validate_op(&self, idx: usize, op: &Op) -> Result<(), CommandError> {
+        let vars = self.vars.len();
+
+        match op {
+            Op::Input { desc } => validate_descriptor(desc, vars)?,
+            &Op::Output { src } => {
+                if src.0 < idx && self.buffer_of(src).is_ok() {
+                    self.buffer_as_result(src)?;
+                } else {
+                    self.texture_before(idx, src)?;
+                }
+            }
+            &Op::Render { src } => {
+                self.texture_before(idx, src)?;
+            }
+            Op::Construct { desc, op } => {
+                validate_descriptor(desc, vars)?;
+                validate_construct(op)?;
+
+                if let &ConstructOp::FromBuffer(buffer, _) = op {
+                    self.buffer_before(idx, buffer)?;
+                }
+            }
+            &Op::Unary { src, ref desc, .. } => {
+                self.texture_before(idx, src)?;
+                validate_descriptor(desc, vars)?;
+            }
+            &Op::Binary {
+                lhs, rhs, ref desc, ..
+            } => {
+                self.texture_before(idx, lhs)?;
+                self.texture_before(idx, rhs)?;
+                validate_descriptor(desc, vars)?;
+            }
+            Op::DynamicImage { call, desc, .. } => {
+                match *call {
+                    OperandDynKind::Construct => {}
+                    OperandDynKind::Unary(src) => {
+                        self.texture_before(idx, src)?;
+                    }
+                    OperandDynKind::Binary { lhs, rhs } => {
+                        self.texture_before(idx, lhs)?;
+                        self.texture_before(idx, rhs)?;
+                    }
+                }
+
+                validate_descriptor(desc, vars)?;
+            }
+            Op::Invoke {
+                function,
+                arguments,
+                results,
+                generics,
+                repeat,
+            } => {
+                let signature = self
+                    .symbols
+                    .get(function.0)
+                    .ok_or(CommandError::BAD_VARIABLE)?;
+
+                if repeat.is_some() {
+                    Self::validate_repeat(signature)?;
+                }
+
+                for (expected, actual) in [
+                    (
+                        signature.input.len() + signature.buffer_input.len(),
+                        arguments.len(),
+                    ),
+                    (
+                        signature.output.len() + signature.buffer_output.len(),
+                        results.len(),
+                    ),
+                    (signature.vars.len(), generics.len()),
+                ] {
+                    if expected != actual {
+                        return Err(CommandError {
+                            inner: CommandErrorKind::ArityMismatch { expected, actual },
+                        });
+                    }
+                }
+
+                for generic in generics {
+                    validate_descriptor(generic, vars)?;
+                }
+
+                let (images, buffers) = arguments.split_at(signature.input.len());
+
+                for (param, &argument) in signature.buffer_input.iter().zip(buffers) {
+                    let actual = self.buffer_before(idx, argument)?;
+
+                    if param != actual {
+                        return Err(CommandError {
+                            inner: CommandErrorKind::BufferMismatch {
+                                argument,
+                                actual: actual.clone(),
+                                expected: param.clone(),
+                            },
+                        });
+                    }
+                }
+
+                let (image_results, buffer_results) = results.split_at(signature.output.len());
+
+                for (output, &result) in signature.buffer_output.iter().zip(buffer_results) {
+                    let Some(Op::InvokedBuffer { invocation, desc }) = self.ops.get(result.0)
+                    else {
+                        return Err(CommandError::bad_register(result));
+                    };
+
+                    if result.0 >= idx
+                        || invocation.0 != idx
+                        || Some(desc) != output.rewrite(generics).as_ref()
+                    {
+                        return Err(CommandError::bad_register(result));
+                    }
+                }
+
+                for (param, &argument) in signature.input.iter().zip(images) {
+                    let expected = param.rewrite(generics);
+                    let actual = self.texture_before(idx, argument)?;
+
+                    if expected != *actual {
+                        return Err(CommandError {
+                            inner: CommandErrorKind::ArgumentMismatch {
+                                argument,
+                                actual: actual.clone(),
+                                expected,
+                            },
+                        });
+                    }
+                }
+
+                for (output, &result) in signature.output.iter().zip(image_results) {
+                    let Some(Op::InvokedResult { invocation, desc }) = self.ops.get(result.0)
+                    else {
+                        return Err(CommandError::bad_register(result));
+                    };
+
+                    if result.0 >= idx || invocation.0 != idx || *desc != output.rewrite(generics) {
+                        return Err(CommandError::bad_register(result));
+                    }
+                }
+            }
+            Op::InvokedResult { invocation, desc } => {
+                if invocation.0 <= idx {
+                    return Err(CommandError::bad_register(*invocation));
+                }
+
+                validate_descriptor(desc, vars)?;
+            }
+            Op::InvokedBuffer { invocation, desc } => {
+                if invocation.0 <= idx {
+                    return Err(CommandError::bad_register(*invocation));
+                }
+
+                validate_buffer(desc, vars)?;
+            }
+            Op::BufferInput { desc } => {
+                let Generic::Concrete(len) = desc.size else {
+                    return Err(CommandError {
+                        inner: CommandErrorKind::ConcreteDescriptorRequired,
+                    });
+                };
+
+                bytes_as_image(len).ok_or(CommandError::INVALID_CALL)?;
+            }
+            Op::BufferInit { op, desc } => {
+                match op {
+                    BufferInitOp::FromData { placement, data } => {
+                        if placement.start > placement.end || placement.end > data.len() {
+                            return Err(CommandError::INVALID_CALL);
+                        }
+                    }
+                    BufferInitOp::Splat { pattern } => {
+                        let pattern_len = pattern.len() as u64;
+                        let fits = desc
+                            .as_concrete()
+                            .is_some_and(|len| pattern_len > 0 && len % pattern_len == 0);
+
+                        if !fits || pattern_len % wgpu::COPY_BUFFER_ALIGNMENT != 0 {
+                            return Err(CommandError::INVALID_CALL);
+                        }
+                    }
+                }
+
+                validate_buffer(desc, vars)?;
+            }
+            &Op::BufferUnary {
+                src,
+                ref desc,
+                ref op,
+            } => {
+                match *op {
+                    BufferUnaryOp::FromImage {} => {
+                        self.texture_before(idx, src)?;
+                    }
+                    BufferUnaryOp::Slice { start } => {
+                        let source = self.buffer_before(idx, src)?;
+                        let fits = match (source.as_concrete(), desc.as_concrete()) {
+                            (Some(len), Some(slice)) => {
+                                start.checked_add(slice).is_some_and(|end| end <= len)
+                            }
+                            _ => false,
+                        };
+
+                        if !fits {
+                            return Err(CommandError::INVALID_CALL);
+                        }
+                    }
+                }
+
+                validate_buffer(desc, vars)?;
+            }
+            &Op::BufferBinary {
+                lhs,
+                rhs,
+                ref desc,
+                ref op,
+            } => {
+                let under = self.buffer_before(idx, lhs)?;
+                let over = self.buffer_before(idx, rhs)?;
+
+                if let BufferBinaryOp::Concat {} = op {
+                    let fits = match (under.as_concrete(), over.as_concrete()) {
+                        (Some(lhs), Some(rhs)) => lhs.checked_add(rhs) == desc.as_concrete(),
+                        _ => false,
+                    };
+
+                    if !fits {
+                        return Err(CommandError::INVALID_CALL);
+                    }
+                }
+
+                validate_buffer(desc, vars)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that a knob belongs to an operation with parameters, see [`Self::validate`].
+    fn validate_knob(&self, register: Register, knob: &KnobKind) -> Result<(), CommandError> {
+        let Some(op) = self.ops.get(register.0) else {
+            return Err(CommandError::bad_register(register));
+        };
+
+        let parameterized = match op {
+            // The offset of an image in a buffer is checked on the host, it must be a runtime knob.
+            Op::Construct {
+                op: ConstructOp::FromBuffer(..),
+                ..
+            } => matches!(knob, KnobKind::Runtime(_)),
+            Op::Construct { .. }
+            | Op::Unary { .. }
+            | Op::Binary { .. }
+            | Op::DynamicImage { .. } => true,
+            // The initial data of a buffer is a runtime knob, but can not be copied from another.
+            Op::BufferInit {
+                op: BufferInitOp::FromData { .. },
+                ..
+            } => matches!(knob, KnobKind::Runtime(_)),
+            _ => false,
+        };
+
+        if !parameterized {
+            return Err(CommandError::bad_register(register));
+        }
+
+        if let KnobKind::Buffer { buffer, range } = knob {
+            let size = &self.buffer_before(register.0, *buffer)?.size;
+
+            let fits = match *size {
+                Generic::Concrete(size) => range.start <= range.end && range.end <= size,
+                Generic::Generic(_) => range.start <= range.end,
+            };
+
+            if !fits {
+                return Err(CommandError::INVALID_CALL);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, op: Op) -> Register {
+        let reg = Register(self.ops.len());
+        self.ops.push(op);
+        reg
+    }
+
+    /// Record a _constructor_, with a user-supplied shader.
+    pub fn construct_dynamic(&mut self, dynamic: &dyn ShaderCommand) -> Register {
+        let (command, desc) = Self::dynamic_invocation(dynamic, 0);
+
+        self.push(Op::DynamicImage {
+            call: OperandDynKind::Construct,
+            command,
+            desc: desc.into(),
+        })
+    }
+
+    /// Record a unary operator, with a user-supplied shader.
+    pub fn unary_dynamic(
+        &mut self,
+        op: Register,
+        dynamic: &dyn ShaderCommand,
+    ) -> Result<Register, CommandError> {
+        let _input_descriptor = self.texture_of(op)?;
+        let (command, desc) = Self::dynamic_invocation(dynamic, 1);
+
+        let out_reg = self.push(Op::DynamicImage {
+            call: OperandDynKind::Unary(op),
+            command,
+            desc: desc.into(),
+        });
+
+        Ok(out_reg)
+    }
+
+    /// Record a binary operator, with a user-supplied shader.
+    pub fn binary_dynamic(
+        &mut self,
+        lhs: Register,
+        rhs: Register,
+        dynamic: &dyn ShaderCommand,
+    ) -> Result<Register, CommandError> {
+        let _input_descriptor = self.texture_of(lhs)?;
+        let _input_descriptor = self.texture_of(rhs)?;
+        let (command, desc) = Self::dynamic_invocation(dynamic, 2);
+
+        let out_reg = self.push(Op::DynamicImage {
+            call: OperandDynKind::Binary { lhs, rhs },
+            command,
+            desc: desc.into(),
+        });
+
+        Ok(out_reg)
+    }
+
+    /// Plan the invocation of a user-supplied shader, with its configured data.
+    fn dynamic_invocation(
+        dynamic: &dyn ShaderCommand,
+        num_args: u32,
+    ) -> (ShaderInvocation, Descriptor) {
+        let mut data = vec![];
+        let mut content = None;
+
+        let source = dynamic.source();
+        let desc = dynamic.data(ShaderData {
+            data_buffer: &mut data,
+            content: &mut content,
+        });
+
+        // FIXME: maybe this conversion should be delayed.
+        // In particular, converting source to SPIR-V may take some form of 'compiler' argument
+        // that's only available during `compile` phase.
+        let command = ShaderInvocation {
+            spirv: match source {
+                ShaderSource::SpirV(spirv) => spirv,
+            },
+            shader_data: match content {
+                None => None,
+                Some(c) => Some(c.as_slice(&data).into()),
+            },
+            num_args,
+        };
+
+        (command, desc)
+    }
+}
+
+impl CommandSignature {
+    /// The symbol by which the function is declared, see [`CommandBuffer::function_named`].
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Verify if a signature matches an other command signature.
+    ///
+    /// That is, whether the subtyping relationship of all its bounds and the argument allows using
+    /// one in place of the other declared type. This checks if `self` contains all bounds that
+    /// occur in `actual`.
+    pub fn is_declaration_of(&self, actual: &CommandSignature) -> bool {
+        if self.vars.len() != actual.vars.len() {
+            return false;
+        }
+
+        for (decl, actual) in self.vars.iter().zip(&actual.vars) {
+            if !decl.contains_bounds(actual) {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+impl GenericDescriptor {
+    /// Query if this describes a monomorphic descriptor.
+    ///
+    /// At the moment this means a fully constrained descriptor where both size and chroma are
+    /// defined. It's a bit odd that this would be an overlapping property with having been
+    /// constructed from an actually concrete defined descriptor. If we had a non-deterministic
+    /// layout algorithm (i.e. multiple permissible layouts for one combination of size/chroma)
+    /// then this might inadvertently throw away some of this information. But for now this
+    /// information is compile time only, the actual dependence of operational semantics on layout
+    /// information is evaluated at runtime. (FIXME: I will have regretted writing this).
+    pub fn as_concrete(&self) -> Option<Descriptor> {
+        let Generic::Concrete((w, h)) = self.size else {
+            return None;
+        };
+
+        let Generic::Concrete((texel, color)) = &self.chroma else {
+            return None;
+        };
+
+        Descriptor::with_texel(texel.clone(), w, h).map(|mut desc| {
+            desc.color = color.clone();
+            desc
+        })
+    }
+
+    /// The generic parameter which determines both the size and the chroma, if any.
+    fn single_var(&self) -> Option<GenericVar> {
+        match (&self.size, &self.chroma) {
+            (&Generic::Generic(size), &Generic::Generic(chroma)) if size == chroma => Some(size),
+            _ => None,
+        }
+    }
+
+    /// FIXME: fallible. If we change the texel from something small to something very large we can
+    /// exceed the allocation limits that are necessary to express the layout.
+    pub fn with_chroma(&self, texel: Texel, color: Color) -> Self {
+        GenericDescriptor {
+            chroma: Generic::Concrete((texel, color)),
+            ..self.clone()
+        }
+    }
+
+    pub fn monomorphize(&self, decl: &[Descriptor]) -> Descriptor {
+        let (w, h) = match &self.size {
+            Generic::Concrete(descriptor) => descriptor.clone(),
+            Generic::Generic(idx) => decl[idx.0].size(),
+        };
+
+        let (texel, color) = match &self.chroma {
+            Generic::Concrete(tuple) => tuple.clone(),
+            Generic::Generic(idx) => {
+                let from = &decl[idx.0];
+                (from.texel.clone(), from.color.clone())
+            }
+        };
+
+        Descriptor::with_texel(texel, w, h)
+            .map(|mut desc| {
+                desc.color = color;
+                desc
+            })
+            .expect("changing texel and color to something that does not fit memory")
+    }
+
+    /// Apply an outer variable definition, replacing generics by at least as concrete terms.
+    ///
+    /// Does not verify any bounds of the rewrites! Which we'll need to do if we had associated
+    /// constants and the rewrite was looking into paths and impls. Consider a trait (similar to
+    /// the Rust type system) / type family such as `LinearizedColor` that associates the linear
+    /// optical colorspace to an arbitrary electrical color encoding. Then we might have the
+    /// signature written in pseudo-code:
+    ///
+    /// ```text
+    ///     function <C: LinearizedColor>(arg0: {C; 256×256}, arg1: {C::Linear; 256×256})
+    /// ```
+    ///
+    /// Now if we rewrite with [C = sRGB] then we want the concrete [C::Linear=CIE-RGB-Wp-D70]
+    /// correspondence. But if we tried [C = CYMK] we have nonsense. Here we allow this function to
+    /// panic, a check must happen earlier.
+    pub fn rewrite(&self, decl: &[GenericDescriptor]) -> Self {
+        GenericDescriptor {
+            size: match &self.size {
+                &Generic::Concrete(size) => Generic::Concrete(size),
+                Generic::Generic(idx) => decl[idx.0].size.clone(),
+            },
+            chroma: match &self.chroma {
+                Generic::Concrete(chroma) => Generic::Concrete(chroma.clone()),
+                Generic::Generic(idx) => decl[idx.0].chroma.clone(),
+            },
+        }
+    }
+
+    pub fn size(&self) -> Generic<(u32, u32)> {
+        self.size.clone()
+    }
+
+    pub fn descriptor_chroma(&self) -> Generic<(Texel, Color)> {
+        self.chroma.clone()
+    }
+}
+
+impl GenericBuffer {
+    pub fn as_concrete(&self) -> Option<u64> {
+        match self.size {
+            Generic::Concrete(val) => Some(val),
+            Generic::Generic(_) => None,
+        }
+    }
+
+    pub fn monomorphize(&self, decl: &[Descriptor]) -> u64 {
+        match self.size {
+            Generic::Concrete(val) => val,
+            // As the buffer of the image, see `CommandBuffer::buffer_from_image`.
+            Generic::Generic(var) => decl[var.0]
+                .u64_gpu_len()
+                .expect("descriptor that has no layout on the device"),
+        }
+    }
+
+    /// Apply an outer variable definition, see [`GenericDescriptor::rewrite`].
+    ///
+    /// Returns `None` if the descriptor substituted for the size is neither concrete nor that of
+    /// a single generic parameter, as there is no way to express the length of the buffer.
+    pub fn rewrite(&self, decl: &[GenericDescriptor]) -> Option<Self> {
+        let size = match self.size {
+            Generic::Concrete(len) => Generic::Concrete(len),
+            Generic::Generic(var) => {
+                let desc = &decl[var.0];
+
+                match desc.as_concrete() {
+                    Some(desc) => Generic::Concrete(desc.u64_gpu_len()?),
+                    None => Generic::Generic(desc.single_var()?),
+                }
+            }
+        };
+
+        Some(GenericBuffer { size })
+    }
+}
+
+impl From<Descriptor> for GenericDescriptor {
+    fn from(desc: Descriptor) -> Self {
+        let size = desc.size();
+        let chroma = (desc.texel.clone(), desc.color.clone());
+
+        GenericDescriptor {
+            size: Generic::Concrete(size),
+            chroma: Generic::Concrete(chroma),
+        }
+    }
+}
+
+impl<'lt> RegisterDescription<'lt> {
+    pub fn as_texture(&self) -> Result<&'lt GenericDescriptor, CommandError> {
+        match self {
+            RegisterDescription::Texture(tex) => Ok(tex),
+            _ => Err(CommandError::TYPE_ERR),
+        }
+    }
+
+    pub fn as_buffer(&self) -> Result<&'lt GenericBuffer, CommandError> {
+        match self {
+            RegisterDescription::Buffer(tex) => Ok(tex),
+            _ => Err(CommandError::TYPE_ERR),
+        }
+    }
+}
+
+impl TyVarBounds {
+    /// Check that all bounds assumed by `actual` are also declared by `self`.
+    pub fn contains_bounds(&self, actual: &TyVarBounds) -> bool {
+        actual
+            .bounds
+            .iter()
+            .all(|bound| self.bounds.contains(bound))
+    }
+
+    /// The number of bytes of the texel, if it is bounded.
+    fn texel_bytes(&self) -> Option<u8> {
+        self.bounds.iter().find_map(|bound| match *bound {
+            GenericBound::TexelBytes(bytes) => Some(bytes),
+            _ => None,
+        })
+    }
+
+    /// Check if a descriptor can instantiate a parameter with these bounds.
+    ///
+    /// A generic chroma satisfies the bounds its own variable declares, in `vars`.
+    fn is_satisfied_by(&self, desc: &GenericDescriptor, vars: &[TyVarBounds]) -> bool {
+        let (texel, color) = match &desc.chroma {
+            Generic::Concrete(chroma) => chroma,
+            Generic::Generic(var) => return vars[var.0].contains_bounds(self),
+        };
+
+        self.bounds.iter().all(|bound| match *bound {
+            GenericBound::TexelBytes(bytes) => texel.bits.bytes() == u16::from(bytes),
+            GenericBound::Renderable => Descriptor::with_texel(texel.clone(), 1, 1)
+                .map(|mut desc| {
+                    desc.color = color.clone();
+                    desc
+                })
+                .map_or(false, |desc| ImageDescriptor::new(&desc).is_ok()),
+        })
+    }
+}
+
+impl UnaryOp {
+    /// The affine transform of the color, for operations painted by a linear color matrix.
+    fn linear_color_transform(&self) -> Option<(RowMatrix, [f32; 3])> {
+        match self {
+            UnaryOp::ColorConvert(conversion) => Some((conversion.rgb_matrix()?, [0.0; 3])),
+            UnaryOp::ChromaticAdaptation(adaptation) => {
+                if let ChromaticAdaptationMethod::BradfordNonLinear = adaptation.method {
+                    return None;
+                }
+
+                Some((adaptation.rgb_matrix().ok()?, [0.0; 3]))
+            }
+            UnaryOp::Luminance { weights } => {
+                let [r, g, b] = *weights;
+                Some((RowMatrix::new([r, g, b, r, g, b, r, g, b]), [0.0; 3]))
+            }
+            UnaryOp::LinearColor(matrix) => Some((*matrix, [0.0; 3])),
+            UnaryOp::AffineColor { matrix, offset } => Some((*matrix, *offset)),
+            _ => None,
+        }
+    }
+}
+
+impl ColorConversion {
+    /// The linear map of an RGB to RGB conversion.
+    pub(crate) fn rgb_matrix(&self) -> Option<RowMatrix> {
+        let ColorConversion::Xyz {
+            to_xyz_matrix,
+            from_xyz_matrix,
+            adaptation,
+            scale,
+        } = self
+        else {
+            return None;
+        };
+
+        let adapted = adaptation.multiply_right((*to_xyz_matrix).into());
+        let matrix = from_xyz_matrix.inv().multiply_right(adapted);
+        Some(
+            RowMatrix::diag(*scale, *scale, *scale)
+                .multiply_right(matrix)
+                .into(),
+        )
+    }
+
+    pub(crate) fn to_shader(&self, std: &ShadersStd) -> FragmentShaderInvocation {
+        match self {
+            ColorConversion::Xyz { .. } => {
+                FragmentShaderInvocation::LinearColorMatrix(shaders::LinearColorTransform {
+                    matrix: self.rgb_matrix().expect("an rgb conversion"),
+                    offset: [0.0; 3],
+                    spirv: std.linear_color_transform.clone(),
+                })
+            }
+            ColorConversion::XyzToOklab { to_xyz_matrix } => {
+                FragmentShaderInvocation::Oklab(shaders::oklab::Shader {
+                    xyz_transform: *to_xyz_matrix,
+                    direction: shaders::oklab::Coding::Encode {
+                        spirv: std.oklab_encode.clone(),
+                    },
+                })
+            }
+            ColorConversion::OklabToXyz { from_xyz_matrix } => {
+                let from_xyz_matrix = from_xyz_matrix.inv();
+                FragmentShaderInvocation::Oklab(shaders::oklab::Shader {
+                    xyz_transform: from_xyz_matrix,
+                    direction: shaders::oklab::Coding::Decode {
+                        spirv: std.oklab_decode.clone(),
+                    },
+                })
+            }
+            ColorConversion::XyzToSrLab2 {
+                to_xyz_matrix,
+                whitepoint,
+            } => FragmentShaderInvocation::SrLab2(shaders::srlab2::Shader {
+                matrix: *to_xyz_matrix,
+                whitepoint: *whitepoint,
+                direction: shaders::srlab2::Coding::Encode {
+                    spirv: std.srlab2_encode.clone(),
+                },
+            }),
+            ColorConversion::SrLab2ToXyz {
+                from_xyz_matrix,
+                whitepoint,
+            } => {
+                let from_xyz_matrix = from_xyz_matrix.inv();
+                FragmentShaderInvocation::SrLab2(shaders::srlab2::Shader {
+                    matrix: from_xyz_matrix,
+                    whitepoint: *whitepoint,
+                    direction: shaders::srlab2::Coding::Decode {
+                        spirv: std.srlab2_decode.clone(),
+                    },
+                })
+            }
+            ColorConversion::XyzToLab {
+                to_xyz_matrix,
+                whitepoint,
+            } => FragmentShaderInvocation::CieLab(shaders::cielab::Shader {
+                matrix: *to_xyz_matrix,
+                whitepoint: *whitepoint,
+                direction: shaders::cielab::Coding::Encode {
+                    spirv: std.cielab_encode.clone(),
+                },
+            }),
+            ColorConversion::LabToXyz {
+                from_xyz_matrix,
+                whitepoint,
+            } => {
+                let from_xyz_matrix = from_xyz_matrix.inv();
+                FragmentShaderInvocation::CieLab(shaders::cielab::Shader {
+                    matrix: from_xyz_matrix,
+                    whitepoint: *whitepoint,
+                    direction: shaders::cielab::Coding::Decode {
+                        spirv: std.cielab_decode.clone(),
+                    },
+                })
+            }
+        }
+    }
+}
+
+impl XyzRelation {
+    /// The relation of a descriptor's color. CIELAB is never one, its whitepoint is not recorded.
+    fn of(color: &Color) -> Option<Self> {
+        Some(match *color {
+            Color::Rgb {
+                primary,
+                whitepoint,
+                transfer,
+                ..
+            } => XyzRelation::Rgb {
+                primary,
+                whitepoint,
+                transfer,
+            },
+            Color::Oklab => XyzRelation::Oklab,
+            Color::SrLab2 { whitepoint } => XyzRelation::SrLab2 { whitepoint },
+            _ => return None,
+        })
+    }
+
+    /// The reference white of the color, Oklab is defined relative to D65.
+    fn whitepoint(self) -> Whitepoint {
+        match self {
+            XyzRelation::Rgb { whitepoint, .. }
+            | XyzRelation::SrLab2 { whitepoint }
+            | XyzRelation::Lab { whitepoint } => whitepoint,
+            XyzRelation::Oklab => Whitepoint::D65,
+        }
+    }
+
+    /// The pass encoding linear RGB into this color, if there is one.
+    fn encode_from_rgb(self, primary: Primaries, rgb_wp: Whitepoint) -> Option<ColorConversion> {
+        let to_xyz_matrix = RowMatrix(primary.to_xyz_row_matrix(rgb_wp));
+
+        Some(match self {
+            XyzRelation::Rgb { .. } => return None,
+            XyzRelation::Oklab if rgb_wp == Whitepoint::D65 => {
+                ColorConversion::XyzToOklab { to_xyz_matrix }
+            }
+            XyzRelation::Oklab => return None,
+            XyzRelation::SrLab2 { whitepoint } => ColorConversion::XyzToSrLab2 {
+                to_xyz_matrix,
+                whitepoint,
+            },
+            // CIELAB is relative to its own whitepoint, to which the RGB color is adapted.
+            XyzRelation::Lab { whitepoint } => ColorConversion::XyzToLab {
+                to_xyz_matrix: Self::adapted(to_xyz_matrix, rgb_wp, whitepoint),
+                whitepoint,
+            },
+        })
+    }
+
+    /// The pass decoding this color into linear RGB, if there is one.
+    fn decode_into_rgb(self, primary: Primaries, rgb_wp: Whitepoint) -> Option<ColorConversion> {
+        let from_xyz_matrix = RowMatrix(primary.to_xyz_row_matrix(rgb_wp));
+
+        Some(match self {
+            XyzRelation::Rgb { .. } => return None,
+            XyzRelation::Oklab if rgb_wp == Whitepoint::D65 => {
+                ColorConversion::OklabToXyz { from_xyz_matrix }
+            }
+            XyzRelation::Oklab => return None,
+            XyzRelation::SrLab2 { whitepoint } => ColorConversion::SrLab2ToXyz {
+                from_xyz_matrix,
+                whitepoint,
+            },
+            XyzRelation::Lab { whitepoint } => ColorConversion::LabToXyz {
+                from_xyz_matrix: Self::adapted(from_xyz_matrix, rgb_wp, whitepoint),
+                whitepoint,
+            },
+        })
+    }
+
+    fn adapted(to_xyz: RowMatrix, rgb_wp: Whitepoint, lab_wp: Whitepoint) -> RowMatrix {
+        let adaptation = RowMatrix::bradford(rgb_wp.to_xyz(), lab_wp.to_xyz());
+        adaptation.multiply_right(to_xyz.into()).into()
+    }
+}
+
+impl ChromaticAdaptation {
+    /// The shader adapting the linear RGB color.
+    pub(crate) fn to_shader(
+        &self,
+        std: &ShadersStd,
+    ) -> Result<FragmentShaderInvocation, CompileError> {
+        if let ChromaticAdaptationMethod::BradfordNonLinear = self.method {
+            return Ok(FragmentShaderInvocation::BradfordNonLinear(
+                shaders::bradford::Shader {
+                    to_xyz: self.to_xyz_matrix,
+                    from_xyz: self.from_xyz_matrix,
+                    source: self.source.to_xyz(),
+                    target: self.target.to_xyz(),
+                    spirv: std.bradford_nonlinear.clone(),
+                },
+            ));
+        }
+
+        Ok(FragmentShaderInvocation::LinearColorMatrix(
+            shaders::LinearColorTransform {
+                matrix: self.rgb_matrix()?.into(),
+                offset: [0.0; 3],
+                spirv: std.linear_color_transform.clone(),
+            },
+        ))
+    }
+
+    /// The linear map of the adaptation in the RGB color.
+    pub(crate) fn rgb_matrix(&self) -> Result<RowMatrix, CompileError> {
+        // Determine matrix for converting to xyz, then adapt, then back.
+        let adapt = RowMatrix::new(self.to_matrix()?);
+        let output = adapt.multiply_right(self.to_xyz_matrix.into());
+        Ok(self.from_xyz_matrix.multiply_right(output).into())
+    }
+
+    pub(crate) fn to_matrix(&self) -> Result<[f32; 9], CompileError> {
+        use palette::{
+            chromatic_adaptation::{Method, TransformMatrix},
+            white_point as wp,
+        };
+
+        // FIXME: when you adjust the value-to-type translation, also adjust it within `method`.
+        macro_rules! translate_matrix {
+            ($source:expr, $target:expr, $($lhs:ident => $lhsty:ty)|*) => {
+                $(
+                    translate_matrix!(
+                        @$source, $target, $lhs => $lhsty :
+                        A => wp::A | B => wp::B | C => wp::C
+                        | D50 => wp::D50 | D55 => wp::D55 | D65 => wp::D65
+                        | D75 => wp::D75 | E => wp::E | F2 => wp::F2
+                        | F7 => wp::F7 | F11 => wp::F11
+                    );
+                )*
+            };
+            (@$source:expr, $target:expr, $lhs:ident => $lhsty:ty : $($rhs:ident => $ty:ty)|*) => {
+                $(
+                    if let (Whitepoint::$lhs, Whitepoint::$rhs) = ($source, $target) {
+                        return Ok((|method| {
+                            let lhswp = <$lhsty as wp::WhitePoint<f32>>::get_xyz();
+                            let rhswp = <$ty as wp::WhitePoint<f32>>::get_xyz();
+                            <Method as TransformMatrix<f32>>::generate_transform_matrix(method, lhswp, rhswp)
+                        })
+                                  as fn(&Method) -> [f32;9]);
+                    }
+                )*
+            };
+        }
+
+        // FIXME: when you adjust the value-to-type translation, also adjust it within
+        // `translate_matrix!`
+        let method = (|| {
+            translate_matrix! {
+                self.source, self.target,
+                A => wp::A | B => wp::B | C => wp::C
+                | D50 => wp::D50 | D55 => wp::D55 | D65 => wp::D65
+                | D75 => wp::D75 | E => wp::E | F2 => wp::F2
+                | F7 => wp::F7 | F11 => wp::F11
+            };
+
+            Err(CompileError::NotYetImplemented(
+                "chromatic adaptation between these whitepoints",
+            ))
+        })()?;
+
+        let matrices = method(match self.method {
+            // Bradford's original method has a slight blue non-linearity, it is not a matrix. See
+            // `to_shader` for its own shader.
+            ChromaticAdaptationMethod::BradfordNonLinear => {
+                return Err(CompileError::NotYetImplemented(
+                    "non-linear Bradford adaptation as a matrix",
+                ))
+            }
+            ChromaticAdaptationMethod::BradfordVonKries => &Method::Bradford,
+            ChromaticAdaptationMethod::VonKries => &Method::VonKries,
+            ChromaticAdaptationMethod::Xyz => &Method::XyzScaling,
+        });
+
+        Ok(matrices)
+    }
+}
+
+impl Derivative {
+    /// Resolve the taps of the kernel outside the image by another mode than clamping.
+    pub fn with_border(self, border: BorderHandling) -> BorderedDerivative {
+        BorderedDerivative {
+            derivative: self,
+            border,
+        }
+    }
+}
+
+impl From<Derivative> for BorderedDerivative {
+    fn from(derivative: Derivative) -> Self {
+        derivative.with_border(BorderHandling::default())
+    }
+}
+
+#[rustfmt::skip]
+impl DerivativeMethod {
+    fn to_shader(&self, direction: Direction, border: BorderHandling, std: &ShadersStd) -> Result<FragmentShaderInvocation, CompileError> {
+        let shader = shaders::box3::Shader {
+            matrix: direction.adjust_vertical_box(self.kernel_3x3()?),
+            border,
+            spirv: std.box3.clone(),
+        };
+
+        Ok(shaders::FragmentShaderInvocation::Box3(shader))
+    }
+
+    /// The 3×3 kernel of the derivative along the width.
+    fn kernel_3x3(&self) -> Result<RowMatrix, CompileError> {
+        use DerivativeMethod::*;
+
+        match self {
+            Prewitt => Ok(RowMatrix::with_outer_product(
+                [1./3., 1./3., 1./3.],
+                [0.5, 0.0, -0.5],
+            )),
+            Sobel => Ok(RowMatrix::with_outer_product(
+                [1./4., 1./2., 1./4.],
+                [0.5, 0.0, -0.5],
+            )),
+            Scharr3 => Ok(RowMatrix::with_outer_product(
+                [46.84/256., 162.32/256., 46.84/256.],
+                [0.5, 0.0, -0.5],
+            )),
+            Scharr3To4Bit => Ok(RowMatrix::with_outer_product(
+                [3./16., 10./16., 3./16.],
+                [0.5, 0.0, -0.5],
+            )),
+            Scharr3To8Bit => Ok(RowMatrix::with_outer_product(
+                [47./256., 162./256., 47./256.],
+                [0.5, 0.0, -0.5],
+            )),
+            // FIXME: implement these.
+            // When you do add them to tests/blend.rs
+            | Roberts
+            | Scharr4
+            | Scharr5
+            | Scharr5Tab => Err(CompileError::NotYetImplemented("Roberts and Scharr derivatives"))
+        }
+    }
+}
+
+impl Direction {
+    fn adjust_vertical_box(self, mat: RowMatrix) -> RowMatrix {
+        match self {
+            Direction::Width => mat,
+            Direction::Height => mat.transpose(),
+        }
+    }
+}
+
+#[rustfmt::skip]
+impl Affine {
+    /// Create affine parameters with identity transformation.
+    pub fn new(sampling: AffineSample) -> Self {
+        Affine {
+            transformation: [
+                1.0, 0., 0.,
+                0., 1.0, 0.,
+                0., 0., 1.0,
+            ],
+            sampling,
+        }
+    }
+
+    /// After the transformation, also scale everything.
+    ///
+    /// This corresponds to a left-side multiplication of the transformation matrix.
+    pub fn scale(self, x: f32, y: f32) -> Self {
+        let post = RowMatrix::diag(x, y, 1.0)
+            .multiply_right(RowMatrix::new(self.transformation).into());
+        let transformation = RowMatrix::from(post).into_inner();
+
+        Affine {
+            transformation,
+            ..self
+        }
+    }
+
+    /// After the transformation, rotate everything clockwise.
+    ///
+    /// This corresponds to a left-side multiplication of the transformation matrix.
+    pub fn rotate(self, rad: f32) -> Self {
+        let post = RowMatrix::new([
+            rad.cos(), rad.sin(), 0.,
+            -rad.sin(), rad.cos(), 0.,
+            0., 0., 1.,
+        ]);
+
+        let post = post.multiply_right(RowMatrix::new(self.transformation).into());
+        let transformation = RowMatrix::from(post).into_inner();
+
+        Affine {
+            transformation,
+            ..self
+        }
+    }
+
+    /// After the transformation, shift by an x and y offset.
+    ///
+    /// This corresponds to a left-side multiplication of the transformation matrix.
+    pub fn shift(self, x: f32, y: f32) -> Self {
+        let post = RowMatrix::new([
+            1., 0., x,
+            0., 1., y,
+            0., 0., 1.,
+        ]);
+
+        let post = post.multiply_right(RowMatrix::new(self.transformation).into());
+        let transformation = RowMatrix::from(post).into_inner();
+
+        Affine {
+            transformation,
+            ..self
+        }
+    }
+}
+
+impl Homography {
+    /// Create homography parameters with identity transformation.
+    pub fn new(sampling: AffineSample) -> Self {
+        let Affine { transformation, .. } = Affine::new(sampling);
+
+        Homography {
+            matrix: transformation,
+            sampling,
+        }
+    }
+
+    /// The homography mapping each of four points to its respective target point.
+    ///
+    /// Points are in texel coordinates, typically `from` are the corners of the image above and
+    /// `to` the corners of the quad it is painted to. Returns `None` if no unique transformation
+    /// exists, i.e. if three of the points are collinear.
+    pub fn from_quad(
+        sampling: AffineSample,
+        from: [[f32; 2]; 4],
+        to: [[f32; 2]; 4],
+    ) -> Option<Self> {
+        // The system for the first eight matrix entries, normalizing the last to one. Each pair
+        // of points contributes one equation for each coordinate:
+        //   x' = (a·x + b·y + c) / (g·x + h·y + 1)
+        //   y' = (d·x + e·y + f) / (g·x + h·y + 1)
+        let mut system = [[0.0f64; 9]; 8];
+
+        for (idx, ([x, y], [u, v])) in from.into_iter().zip(to).enumerate() {
+            let [x, y, u, v] = [x, y, u, v].map(f64::from);
+            system[2 * idx] = [x, y, 1.0, 0.0, 0.0, 0.0, -x * u, -y * u, u];
+            system[2 * idx + 1] = [0.0, 0.0, 0.0, x, y, 1.0, -x * v, -y * v, v];
+        }
+
+        // Gaussian elimination with partial pivoting.
+        for col in 0..8 {
+            let pivot =
+                (col..8).max_by(|&a, &b| system[a][col].abs().total_cmp(&system[b][col].abs()))?;
+
+            if system[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+
+            system.swap(col, pivot);
+
+            for row in 0..8 {
+                if row == col {
+                    continue;
+                }
+
+                let factor = system[row][col] / system[col][col];
+                for k in col..9 {
+                    system[row][k] -= factor * system[col][k];
+                }
+            }
+        }
+
+        let mut matrix = [1.0f32; 9];
+        for (idx, entry) in matrix[..8].iter_mut().enumerate() {
+            *entry = (system[idx][8] / system[idx][idx]) as f32;
+        }
+
+        if !matrix.iter().all(|v| v.is_finite()) {
+            return None;
+        }
+
+        // Three collinear target points still solve the system, but into a singular matrix. Its
+        // determinant is compared against the largest possible one for rows of the same length.
+        let bound: f32 = matrix
+            .chunks_exact(3)
+            .map(|row| row.iter().map(|v| v * v).sum::<f32>().sqrt())
+            .product();
+
+        if RowMatrix::new(matrix).det().abs() <= bound * 1e-6 {
+            return None;
+        }
+
+        Some(Homography { matrix, sampling })
+    }
+}
+
+impl ConstructOp {
+    fn name(&self) -> &'static str {
+        match self {
+            ConstructOp::Bilinear(_) => "bilinear",
+            ConstructOp::DistributionNormal(_) => "distribution_normal",
+            ConstructOp::DistributionNoise(_) => "distribution_noise",
+            ConstructOp::DistributionSimplexNoise(_) => "distribution_simplex_noise",
+            ConstructOp::DistributionWorley(_) => "distribution_worley",
+            ConstructOp::Voronoi(_) => "voronoi",
+            ConstructOp::TestPattern(_) => "test_pattern",
+            ConstructOp::Solid(_) => "solid",
+            ConstructOp::ColorRamp(_) => "color_ramp",
+            ConstructOp::SplineRamp(_) => "spline_ramp",
+            ConstructOp::FromBuffer(..) => "from_buffer",
+        }
+    }
+}
+
+impl BufferUnaryOp {
+    fn name(&self) -> &'static str {
+        match self {
+            BufferUnaryOp::FromImage {} => "buffer_from_image",
+            BufferUnaryOp::Slice { .. } => "buffer_slice",
+        }
+    }
+}
+
+impl BufferBinaryOp {
+    fn name(&self) -> &'static str {
+        match self {
+            BufferBinaryOp::Overlay { .. } => "buffer_overlay",
+            BufferBinaryOp::Concat {} => "buffer_concat",
+        }
+    }
+}
+
+impl UnaryOp {
+    fn name(&self) -> &'static str {
+        match self {
+            UnaryOp::Crop(_) => "crop",
+            UnaryOp::ColorConvert(_) => "color_convert",
+            UnaryOp::Extract { .. } => "extract",
+            UnaryOp::Luminance { .. } => "luminance",
+            UnaryOp::ChromaticAdaptation(_) => "chromatic_adaptation",
+            UnaryOp::Vignette(_) => "vignette",
+            UnaryOp::Transmute => "transmute",
+            UnaryOp::Derivative(_) => "derivative",
+            UnaryOp::GradientPolar { .. } => "gradient_polar",
+            UnaryOp::Sharpen(_) => "sharpen",
+            UnaryOp::Emboss(_) => "emboss",
+            UnaryOp::LinearColor(_) => "linear_color",
+            UnaryOp::AffineColor { .. } => "affine_color",
+            UnaryOp::GamutMap { .. } => "gamut_map",
+            UnaryOp::CmykEncode { .. } => "cmyk_encode",
+            UnaryOp::CmykDecode => "cmyk_decode",
+            UnaryOp::AbTransform(_) => "ab_transform",
+            UnaryOp::ChromaKey(_) => "chroma_key",
+            UnaryOp::DenoiseNlm(_) => "denoise_nlm",
+            UnaryOp::Demosaic(_) => "demosaic",
+            UnaryOp::LensUndistort(_) => "lens_undistort",
+            UnaryOp::ChromaticAberration(_) => "chromatic_aberration",
+            UnaryOp::ToneMap(_) => "tone_map",
+            UnaryOp::Reduce(_) => "reduce",
+            UnaryOp::HistogramRows(_) => "histogram_rows",
+            UnaryOp::HistogramSum => "histogram_sum",
+            UnaryOp::ClaheHistogram(_) => "clahe_histogram",
+            UnaryOp::ClaheMapping(_) => "clahe_mapping",
+            UnaryOp::PrefixSum { .. } => "prefix_sum",
+            UnaryOp::Resample(_) => "resample",
+            UnaryOp::Tile { .. } => "tile",
+            UnaryOp::Downsample(_) => "downsample",
+            UnaryOp::Pad(_) => "pad",
+            UnaryOp::GaussianBlur(_) => "gaussian_blur",
+            UnaryOp::BrightPass { .. } => "bright_pass",
+        }
+    }
+}
+
+impl BinaryOp {
+    fn name(&self) -> &'static str {
+        match self {
+            BinaryOp::Affine(_) => "affine",
+            BinaryOp::Perspective(_) => "perspective",
+            BinaryOp::Inscribe { .. } => "inscribe",
+            BinaryOp::Inject { .. } => "inject",
+            BinaryOp::Palette(_) => "palette",
+            BinaryOp::GainMap(_) => "gain_map",
+            BinaryOp::FusionStep(_) => "fusion_step",
+            BinaryOp::WhiteBalance(_) => "white_balance",
+            BinaryOp::ClaheApply(_) => "clahe_apply",
+            BinaryOp::SquaredDifference => "squared_difference",
+            BinaryOp::Ssim(_) => "ssim",
+            BinaryOp::PyramidExpand { .. } => "pyramid_expand",
+            BinaryOp::DropShadow(_) => "drop_shadow",
+            BinaryOp::Bloom(_) => "bloom",
+        }
+    }
+}
+
+impl Op {
+    /// Visit every register that this operation refers to.
+    ///
+    /// The results of an invocation are referred to by the invocation and each result refers to
+    /// the invocation, such that they are always retained together.
+    fn map_registers(&mut self, mut f: impl FnMut(&mut Register)) {
+        match self {
+            Op::Input { .. } | Op::BufferInput { .. } | Op::BufferInit { .. } => {}
+            Op::Output { src } | Op::Render { src } => f(src),
+            Op::Construct { op, .. } => {
+                if let ConstructOp::FromBuffer(src, _) = op {
+                    f(src);
+                }
+            }
+            Op::Unary { src, .. } | Op::BufferUnary { src, .. } => f(src),
+            Op::Binary { lhs, rhs, .. } | Op::BufferBinary { lhs, rhs, .. } => {
+                f(lhs);
+                f(rhs);
+            }
+            Op::DynamicImage { call, .. } => match call {
+                OperandDynKind::Construct => {}
+                OperandDynKind::Unary(src) => f(src),
+                OperandDynKind::Binary { lhs, rhs } => {
+                    f(lhs);
+                    f(rhs);
+                }
+            },
+            Op::Invoke {
+                arguments, results, ..
+            } => {
+                arguments.iter_mut().chain(results).for_each(f);
+            }
+            Op::InvokedResult { invocation, .. } | Op::InvokedBuffer { invocation, .. } => {
+                f(invocation)
+            }
+        }
+    }
+}
+
+impl RegisterMap {
+    /// The register in the pruned command buffer, if it was retained.
+    pub fn get(&self, Register(reg): Register) -> Option<Register> {
+        self.registers.get(reg).copied().flatten()
+    }
+}
+
+impl AffineSample {
+    fn as_paint_on_top(self, core: &ShadersCore) -> Result<PaintOnTopKind, CompileError> {
+        match self {
+            AffineSample::Nearest => Ok(core.paint_copy()),
+            _ => Err(CompileError::NotYetImplemented("bi-linear affine sampling")),
+        }
+    }
+}
+
+impl Rectangle {
+    /// A rectangle at the origin with given width (x) and height (y).
+    pub fn with_width_height(width: u32, height: u32) -> Self {
+        Rectangle {
+            x: 0,
+            y: 0,
+            max_x: width,
+            max_y: height,
+        }
+    }
+
+    /// A rectangle describing a complete buffer.
+    pub fn with_layout(buffer: &ByteLayout) -> Self {
+        Self::with_width_height(buffer.width, buffer.height)
+    }
+
+    /// The apparent width.
+    pub fn width(self) -> u32 {
+        self.max_x.saturating_sub(self.x)
+    }
+
+    /// The apparent height.
+    pub fn height(self) -> u32 {
+        self.max_y.saturating_sub(self.y)
+    }
+
+    /// Return true if this rectangle fully contains `other`.
+    pub fn contains(self, other: Self) -> bool {
+        self.x <= other.x && self.y <= other.y && {
+            // Offsets are surely non-wrapping.
+            let offset_x = other.x - self.x;
+            let offset_y = other.y - self.y;
+            let rel_width = self.width().checked_sub(offset_x);
+            let rel_height = self.height().checked_sub(offset_y);
+            rel_width >= Some(other.width()) && rel_height >= Some(other.height())
+        }
+    }
+
+    /// Bring the rectangle into normalized form where minimum and maximum form a true interval.
+    #[must_use]
+    pub fn normalize(self) -> Rectangle {
+        Rectangle {
+            x: self.x,
+            y: self.y,
+            max_x: self.x + self.width(),
+            max_y: self.y + self.width(),
+        }
+    }
+
+    /// A rectangle that the overlap of the two.
+    #[must_use]
+    pub fn meet(self, other: Self) -> Rectangle {
+        Rectangle {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+
+    /// The meet, relative to the coordinates of this rectangle.
+    #[must_use]
+    pub fn meet_in_local_coordinates(self, other: Self) -> Rectangle {
+        // Normalize to ensure that max_{x,y} is not less than {x,y}
+        let meet = self.normalize().meet(other);
+        Rectangle {
+            x: meet.x - self.x,
+            y: meet.y - self.y,
+            max_x: meet.max_x - self.x,
+            max_y: meet.max_y - self.y,
+        }
+    }
+
+    /// A rectangle that contains both.
+    #[must_use]
+    pub fn join(self, other: Self) -> Rectangle {
+        Rectangle {
+            x: self.x.min(other.x),
+            y: self.y.min(other.y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    /// Remove border from all sides.
+    /// When the image is smaller than `border` in some dimension then the result is empty and
+    /// contained in the original image but otherwise unspecified.
+    #[must_use]
+    pub fn inset(self, border: u32) -> Self {
+        Rectangle {
+            x: self.x.saturating_add(border),
+            y: self.y.saturating_add(border),
+            max_x: self.max_x.saturating_sub(border),
+            max_y: self.max_y.saturating_sub(border),
+        }
+    }
+}
+
+impl SignedRectangle {
+    /// The apparent width.
+    pub fn width(self) -> u32 {
+        (i64::from(self.max_x) - i64::from(self.x)).max(0) as u32
+    }
+
+    /// The apparent height.
+    pub fn height(self) -> u32 {
+        (i64::from(self.max_y) - i64::from(self.y)).max(0) as u32
+    }
+
+    /// A rectangle that the overlap of the two.
+    #[must_use]
+    pub fn meet(self, other: Self) -> SignedRectangle {
+        SignedRectangle {
+            x: self.x.max(other.x),
+            y: self.y.max(other.y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        }
+    }
+}
+
+impl TryFrom<Rectangle> for SignedRectangle {
+    type Error = core::num::TryFromIntError;
+
+    fn try_from(rect: Rectangle) -> Result<Self, Self::Error> {
+        Ok(SignedRectangle {
+            x: rect.x.try_into()?,
+            y: rect.y.try_into()?,
+            max_x: rect.max_x.try_into()?,
+            max_y: rect.max_y.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SignedRectangle> for Rectangle {
+    type Error = core::num::TryFromIntError;
+
+    fn try_from(rect: SignedRectangle) -> Result<Self, Self::Error> {
+        Ok(Rectangle {
+            x: rect.x.try_into()?,
+            y: rect.y.try_into()?,
+            max_x: rect.max_x.try_into()?,
+            max_y: rect.max_y.try_into()?,
+        })
+    }
+}
+
+impl From<&'_ ByteLayout> for Rectangle {
+    fn from(buffer: &ByteLayout) -> Rectangle {
+        Rectangle::with_width_height(buffer.width, buffer.height)
+    }
+}
+
+impl From<&'_ CanvasLayout> for Rectangle {
+    fn from(buffer: &CanvasLayout) -> Rectangle {
+        Rectangle::with_width_height(buffer.width(), buffer.height())
+    }
+}
+
+impl From<&'_ Descriptor> for Rectangle {
+    fn from(buffer: &Descriptor) -> Rectangle {
+        Rectangle::from(&buffer.layout)
+    }
+}
+
+impl CommandError {
+    /// Indicates a very generic type error.
+    const TYPE_ERR: Self = CommandError {
+        inner: CommandErrorKind::GenericTypeError,
+    };
+
+    /// Specifies that a descriptor or function variable was invalid.
+    const BAD_VARIABLE: Self = CommandError {
+        inner: CommandErrorKind::BadVariable,
+    };
+
+    /// Specifies that a transformation was singular.
+    const DEGENERATE: Self = CommandError {
+        inner: CommandErrorKind::DegenerateTransform,
+    };
+
+    /// Specifies that a parameter of the command was invalid.
+    const INVALID_CALL: Self = CommandError {
+        inner: CommandErrorKind::InvalidArgument,
+    };
+
+    /// Specifies that a register reference was invalid.
+    fn bad_register(register: Register) -> Self {
+        CommandError {
+            inner: CommandErrorKind::BadRegister(register),
+        }
+    }
+
+    /// Specifies that a range of a buffer can not be copied.
+    fn misaligned_range(range: core::ops::Range<u64>) -> Self {
+        CommandError {
+            inner: CommandErrorKind::MisalignedRange {
+                range,
+                align: wgpu::COPY_BUFFER_ALIGNMENT,
+            },
+        }
+    }
+
+    fn unsupported_conversion(src: Register, from: Color, to: Color) -> Self {
+        CommandError {
+            inner: CommandErrorKind::UnsupportedConversion { src, from, to },
+        }
+    }
+
+    fn bad_channel(channel: ColorChannel) -> Self {
+        CommandError {
+            inner: CommandErrorKind::BadChannel(channel),
+        }
+    }
+
+    /// This has not yet been implemented, sorry.
+    ///
+    /// Errors of this kind will be removed over the course of bringing the crate to a first stable
+    /// release, this this will be removed. The method, and importantly its signature, are already
+    /// added for the purpose of exposition and documenting the intention.
+    const UNIMPLEMENTED: Self = CommandError {
+        inner: CommandErrorKind::Unimplemented,
+    };
+
+    /// The reason for the error.
+    pub fn kind(&self) -> &CommandErrorKind {
+        &self.inner
+    }
+
+    pub fn is_type_err(&self) -> bool {
+        matches!(
+            self.inner,
+            CommandErrorKind::GenericTypeError
+                | CommandErrorKind::ConflictingTypes(_, _)
+                | CommandErrorKind::BadDescriptor(_, _)
+                | CommandErrorKind::NotATexture(_)
+                | CommandErrorKind::NotABuffer(_)
+                | CommandErrorKind::ArgumentMismatch { .. }
+                | CommandErrorKind::BufferMismatch { .. }
+                | CommandErrorKind::SizeMismatch { .. }
+        )
+    }
+}
+
+impl core::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.inner {
+            CommandErrorKind::BadRegister(Register(reg)) => {
+                write!(f, "Register {reg} does not hold a value")
+            }
+            CommandErrorKind::NotATexture(Register(reg)) => {
+                write!(f, "Register {reg} holds a buffer, not an image")
+            }
+            CommandErrorKind::NotABuffer(Register(reg)) => {
+                write!(f, "Register {reg} holds an image, not a buffer")
+            }
+            CommandErrorKind::BadVariable => {
+                write!(f, "Variable does not belong to this command buffer")
+            }
+            CommandErrorKind::ArityMismatch { expected, actual } => {
+                write!(f, "Expected {expected} arguments, got {actual}")
+            }
+            CommandErrorKind::ArgumentMismatch {
+                argument: Register(reg),
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Argument in register {reg} is {}, expected {}",
+                DescriptorSummary(actual),
+                DescriptorSummary(expected),
+            ),
+            CommandErrorKind::BufferMismatch {
+                argument: Register(reg),
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Argument in register {reg} is {}, expected {}",
+                DumpBuffer(actual),
+                DumpBuffer(expected),
+            ),
+            CommandErrorKind::SizeMismatch {
+                lhs: Register(lhs),
+                rhs: Register(rhs),
+            } => write!(f, "Sizes of the registers {lhs} and {rhs} do not fit"),
+            CommandErrorKind::UnsupportedConversion {
+                src: Register(reg),
+                from,
+                to,
+            } => write!(f, "No conversion of register {reg} from {from:?} to {to:?}"),
+            CommandErrorKind::BadChannel(channel) => {
+                write!(f, "The channel {channel:?} is not available")
+            }
+            CommandErrorKind::DegenerateTransform => write!(f, "The transformation is degenerate"),
+            CommandErrorKind::InvalidArgument => write!(f, "Invalid argument"),
+            CommandErrorKind::MisalignedRange { range, align } => {
+                write!(f, "The range {range:?} is not aligned to {align} bytes")
+            }
+            CommandErrorKind::BadDescriptor(desc, reason) => {
+                write!(f, "{reason}, for {}", DescriptorSummary(desc))
+            }
+            CommandErrorKind::ConcreteDescriptorRequired => {
+                write!(f, "A concrete descriptor is required")
+            }
+            CommandErrorKind::ConflictingTypes(lhs, rhs) => write!(
+                f,
+                "Conflicting types {} and {}",
+                DescriptorSummary(lhs),
+                DescriptorSummary(rhs),
+            ),
+            CommandErrorKind::GenericTypeError => write!(f, "Type error"),
+            CommandErrorKind::Unimplemented => write!(f, "Not yet implemented"),
+        }
+    }
+}
+
+impl core::error::Error for CommandError {}
+
+/// The image of `u8x4` texels, 64 to a row, whose rows are exactly the bytes of a buffer.
+///
+/// Buffers cross the boundaries of a function as such an image. This requires the length to be a
+/// multiple of 256 bytes, the row alignment of the device.
+pub(crate) fn bytes_as_image(len: u64) -> Option<Descriptor> {
+    const ROW: u64 = 256;
+
+    if len == 0 || len % ROW != 0 {
+        return None;
+    }
+
+    let height = u32::try_from(len / ROW).ok()?;
+    let texel = Texel::new_u8(SampleParts::RgbA);
+    Descriptor::with_texel(texel, (ROW / 4) as u32, height)
+}
+
+fn validate_var(var: GenericVar, vars: usize) -> Result<(), CommandError> {
+    if var.0 < vars {
+        Ok(())
+    } else {
+        Err(CommandError::BAD_VARIABLE)
+    }
+}
+
+/// Check that a descriptor refers to declared generics, and describes an image if concrete.
+fn validate_descriptor(desc: &GenericDescriptor, vars: usize) -> Result<(), CommandError> {
+    if let Generic::Generic(var) = desc.size {
+        validate_var(var, vars)?;
+    }
+
+    if let Generic::Generic(var) = desc.chroma {
+        validate_var(var, vars)?;
+    }
+
+    let concrete = matches!(
+        (&desc.size, &desc.chroma),
+        (Generic::Concrete(_), Generic::Concrete(_))
+    );
+
+    if concrete && desc.as_concrete().is_none() {
+        return Err(CommandError {
+            inner: CommandErrorKind::BadDescriptor(desc.clone(), "not a valid descriptor"),
+        });
+    }
+
+    Ok(())
+}
+
+fn validate_buffer(desc: &GenericBuffer, vars: usize) -> Result<(), CommandError> {
+    match desc.size {
+        Generic::Concrete(_) => Ok(()),
+        Generic::Generic(var) => validate_var(var, vars),
+    }
+}
+
+/// Check the parameters whose bounds the shaders rely on.
+fn validate_construct(op: &ConstructOp) -> Result<(), CommandError> {
+    let in_bounds = match op {
+        ConstructOp::Voronoi(data) => data.points.len() <= shaders::voronoi::MAX_POINTS,
+        ConstructOp::ColorRamp(data) => data.count as usize <= shaders::color_ramp::MAX_STOPS,
+        ConstructOp::SplineRamp(data) => data.count as usize <= shaders::spline_ramp::MAX_POINTS,
+        _ => true,
+    };
+
+    if in_bounds {
+        Ok(())
+    } else {
+        Err(CommandError::INVALID_CALL)
+    }
+}
+
+impl ValidationError {
+    /// The register whose operation violates an invariant, if not one of the declarations.
+    pub fn register(&self) -> Option<Register> {
+        self.register
+    }
+
+    /// The violation, as the operation would have been rejected when recorded.
+    pub fn error(&self) -> &CommandError {
+        &self.error
+    }
+}
+
+impl core::fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.register {
+            Some(Register(reg)) => write!(f, "In register {reg}: {}", self.error),
+            None => write!(f, "In the declarations: {}", self.error),
+        }
+    }
+}
+
+impl core::error::Error for ValidationError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Writes the size and chroma of a descriptor, or the generics that stand in for them.
+struct DescriptorSummary<'lt>(&'lt GenericDescriptor);
+
+impl core::fmt::Display for DescriptorSummary<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0.size {
+            Generic::Concrete((width, height)) => write!(f, "{width}×{height}")?,
+            Generic::Generic(GenericVar(var)) => write!(f, "generic {var}")?,
+        }
+
+        match &self.0.chroma {
+            Generic::Concrete((texel, color)) => {
+                write!(f, " {:?} {:?} in {color:?}", texel.bits, texel.parts)
+            }
+            Generic::Generic(GenericVar(var)) => write!(f, " of generic {var}"),
+        }
+    }
+}
+
+/// Writes a descriptor in the dump of a command buffer, with generics as `$N`.
+pub(crate) struct DumpDescriptor<'lt>(pub(crate) &'lt GenericDescriptor);
+
+impl core::fmt::Display for DumpDescriptor<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.0.size {
+            Generic::Concrete((width, height)) => write!(f, "{width}x{height}")?,
+            Generic::Generic(GenericVar(var)) => write!(f, "${var}")?,
+        }
+
+        match &self.0.chroma {
+            Generic::Concrete((texel, color)) => {
+                if *color == Color::SRGB {
+                    write!(f, " sRGB")?;
+                } else if *color == Color::Oklab {
+                    write!(f, " Oklab")?;
+                } else {
+                    write!(f, " {color:?}")?;
+                }
+
+                write!(f, "/{:?}-{}", texel.bits, DumpParts(texel.parts))
+            }
+            Generic::Generic(GenericVar(var)) => write!(f, " ${var}"),
+        }
+    }
+}
+
+/// Writes sample parts in the dump of a command buffer, by their name or else their channels.
+struct DumpParts(SampleParts);
+
+impl core::fmt::Display for DumpParts {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        const NAMED: &[(SampleParts, &str)] = &[
+            (SampleParts::Rgb, "Rgb"),
+            (SampleParts::RgbA, "RgbA"),
+            (SampleParts::Bgr, "Bgr"),
+            (SampleParts::BgrA, "BgrA"),
+            (SampleParts::ARgb, "ARgb"),
+            (SampleParts::ABgr, "ABgr"),
+            (SampleParts::Luma, "Luma"),
+            (SampleParts::LumaA, "LumaA"),
+            (SampleParts::LabA, "LabA"),
+            (SampleParts::Lch, "Lch"),
+            (SampleParts::LchA, "LchA"),
+            (SampleParts::Yuv, "Yuv"),
+        ];
+
+        if let Some((_, name)) = NAMED.iter().find(|(parts, _)| *parts == self.0) {
+            return f.write_str(name);
+        }
+
+        for channel in self.0.color_channels().into_iter().flatten() {
+            write!(f, "{channel:?}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Writes a buffer in the dump of a command buffer, with generics as `$N`.
+struct DumpBuffer<'lt>(&'lt GenericBuffer);
+
+impl core::fmt::Display for DumpBuffer<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self.0.size {
+            Generic::Concrete(len) => write!(f, "buffer[{len}]"),
+            Generic::Generic(GenericVar(var)) => write!(f, "buffer[${var}]"),
+        }
+    }
+}
+
+/// Writes the generic declarations of a signature, or nothing if it has none.
+struct DumpGenerics<'lt>(&'lt [TyVarBounds]);
+
+impl core::fmt::Display for DumpGenerics<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+
+        write!(f, "<")?;
+        for (idx, var) in self.0.iter().enumerate() {
+            if idx > 0 {
+                write!(f, ", ")?;
+            }
+
+            write!(f, "${idx}")?;
+            for (bound_idx, bound) in var.bounds.iter().enumerate() {
+                let sep = if bound_idx == 0 { ": " } else { " + " };
+                write!(f, "{sep}{bound:?}")?;
+            }
+        }
+        write!(f, ">")
+    }
+}
+
+impl core::fmt::Display for CommandBuffer {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        fn list<T>(
+            f: &mut core::fmt::Formatter,
+            items: impl IntoIterator<Item = T>,
+            mut each: impl FnMut(&mut core::fmt::Formatter, T) -> core::fmt::Result,
+        ) -> core::fmt::Result {
+            for (idx, item) in items.into_iter().enumerate() {
+                if idx > 0 {
+                    write!(f, ", ")?;
+                }
+
+                each(f, item)?;
+            }
+
+            Ok(())
+        }
+
+        if !self.vars.is_empty() {
+            writeln!(f, "generics{}", DumpGenerics(&self.vars))?;
+        }
+
+        for (idx, signature) in self.symbols.iter().enumerate() {
+            write!(f, "fn @{idx}")?;
+            if let Some(name) = &signature.name {
+                write!(f, " {name}")?;
+            }
+            write!(f, "{}(", DumpGenerics(&signature.vars))?;
+            let images = signature
+                .input
+                .iter()
+                .map(|desc| DumpDescriptor(desc).to_string());
+            let buffers = signature.buffer_input.iter();
+            list(
+                f,
+                images.chain(buffers.map(|desc| DumpBuffer(desc).to_string())),
+                |f, desc| write!(f, "{desc}"),
+            )?;
+            write!(f, ") -> (")?;
+            let images = signature
+                .output
+                .iter()
+                .map(|desc| DumpDescriptor(desc).to_string());
+            let buffers = signature.buffer_output.iter();
+            list(
+                f,
+                images.chain(buffers.map(|desc| DumpBuffer(desc).to_string())),
+                |f, desc| write!(f, "{desc}"),
+            )?;
+            writeln!(f, ")")?;
+        }
+
+        for (idx, op) in self.ops.iter().enumerate() {
+            write!(f, "%{idx}")?;
+
+            match self.describe_reg(Register(idx)) {
+                RegisterDescription::None => {}
+                RegisterDescription::Texture(desc) => write!(f, " : {}", DumpDescriptor(desc))?,
+                RegisterDescription::Buffer(desc) => write!(f, " : {}", DumpBuffer(desc))?,
+            }
+
+            write!(f, " = ")?;
+            match self.op_kind(Register(idx)) {
+                None => {}
+                Some(OpKind::Input) => write!(f, "input")?,
+                Some(OpKind::Output { src }) => write!(f, "output %{}", src.0)?,
+                Some(OpKind::Render { src }) => write!(f, "render %{}", src.0)?,
+                Some(OpKind::Construct { name }) => write!(f, "{name}")?,
+                Some(OpKind::Unary { name, src }) => write!(f, "{name} %{}", src.0)?,
+                Some(OpKind::Binary { name, lhs, rhs }) => {
+                    write!(f, "{name} %{}, %{}", lhs.0, rhs.0)?
+                }
+                Some(OpKind::Invoke {
+                    function,
+                    arguments,
+                }) => {
+                    write!(f, "invoke @{}", function.0)?;
+
+                    if let Op::Invoke { generics, .. } = op {
+                        if !generics.is_empty() {
+                            write!(f, "<")?;
+                            list(f, generics, |f, desc| write!(f, "{}", DumpDescriptor(desc)))?;
+                            write!(f, ">")?;
+                        }
+                    }
+
+                    write!(f, "(")?;
+                    list(f, arguments, |f, reg| write!(f, "%{}", reg.0))?;
+                    write!(f, ")")?;
+
+                    if let Op::Invoke {
+                        repeat: Some(count),
+                        ..
+                    } = op
+                    {
+                        write!(f, " repeat {count}")?;
+                    }
+                }
+                Some(OpKind::InvokedResult { invocation }) => {
+                    write!(f, "result %{}", invocation.0)?
+                }
+            }
+
+            match self.knobs.get(&Register(idx)) {
+                None => {}
+                Some(KnobKind::Runtime(knob::KnobRecord {
+                    label: Some(label), ..
+                })) => write!(f, " [knob {label:?}]")?,
+                Some(KnobKind::Runtime(_)) => write!(f, " [knob]")?,
+                Some(KnobKind::Buffer { buffer, range }) => {
+                    write!(f, " [knob %{}[{}..{}]]", buffer.0, range.start, range.end)?
+                }
+            }
+
+            if self.dithered.contains(&Register(idx)) {
+                write!(f, " [dithered]")?;
+            }
+
+            if let Some(src) = self.in_place.get(&Register(idx)) {
+                write!(f, " [in place of %{}]", src.0)?;
+            }
+
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The peak signal-to-noise ratio in decibels, for a mean squared error.
+///
+/// The `peak` is the largest possible value of the signal, `1.0` for the linear color values of
+/// [`CommandBuffer::mse`]. Identical images have an infinite ratio.
+pub fn psnr(mse: f32, peak: f32) -> f32 {
+    10.0 * (peak * peak / mse).log10()
+}
+
+#[test]
+fn rectangles() {
+    let small = Rectangle::with_width_height(2, 2);
+    let large = Rectangle::with_width_height(4, 4);
+
+    assert_eq!(large, large.join(small));
+    assert_eq!(small, large.meet(small));
+    assert!(large.contains(small));
+    assert!(!small.contains(large));
+}
+
+#[test]
+fn simple_program() {
+    use crate::pool::Pool;
+
+    const BACKGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/background.png");
+    const FOREGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/foreground.png");
+
+    let mut pool = Pool::new();
+    let mut commands = CommandBuffer::default();
+
+    let background = image::open(BACKGROUND).expect("Background image opened");
+    let foreground = image::open(FOREGROUND).expect("Background image opened");
+    let expected = ByteLayout::from(&background);
+
+    let placement = Rectangle {
+        x: 0,
+        y: 0,
+        max_x: foreground.width(),
+        max_y: foreground.height(),
+    };
+
+    let background = pool.insert_srgb(&background);
+    let background = commands.input_from(background.into());
+
+    let foreground = pool.insert_srgb(&foreground);
+    let foreground = commands.input_from(foreground.into());
+
+    let result = commands
+        .inscribe(background, placement, foreground)
+        .expect("Valid to inscribe");
+    let (_, outformat) = commands.output(result).expect("Valid for output");
+
+    let linker = Linker::from_included();
+
+    let _ = linker
+        .compile(&commands)
+        .expect("Could build command buffer");
+    assert_eq!(outformat.as_concrete().map(|x| x.layout), Some(expected));
+}
+
+#[test]
+fn generic_size_and_chroma() {
+    let rgba = Texel::new_u8(SampleParts::RgbA);
+    let srgb = |w, h| Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(rgba.clone(), w, h).unwrap()
+    };
+    let luma = |w, h| Descriptor::with_texel(Texel::new_u8(SampleParts::Luma), w, h).unwrap();
+
+    let identity = |declaration: GenericDeclaration| {
+        let mut commands = CommandBuffer::default();
+        let var = commands.generic(declaration).unwrap();
+        let input = commands.input_generic(var).unwrap();
+        commands.output(input).unwrap();
+        commands.computed_signature()
+    };
+
+    let accepts = |signature: CommandSignature, desc: Descriptor| {
+        let mut commands = CommandBuffer::default();
+        let callee = commands.function(signature).unwrap();
+        let input = commands.input(desc).unwrap();
+        let ty = commands.register_descriptor(input).unwrap();
+
+        commands
+            .invoke(
+                callee,
+                InvocationArguments {
+                    generics: &[ty],
+                    arguments: &[input],
+                },
+            )
+            .is_ok()
+    };
+
+    // Any size, but always sRGB.
+    let any_size = || {
+        identity(GenericDeclaration {
+            chroma: ChromaSpec::Concrete(rgba.clone(), Color::SRGB),
+            ..GenericDeclaration::default()
+        })
+    };
+
+    assert!(accepts(any_size(), srgb(16, 16)));
+    assert!(accepts(any_size(), srgb(32, 8)));
+    assert!(!accepts(any_size(), luma(16, 16)));
+
+    // Exactly 256×256, any color.
+    let fixed_size = || {
+        identity(GenericDeclaration {
+            size: SizeSpec::Concrete(256, 256),
+            ..GenericDeclaration::default()
+        })
+    };
+
+    assert!(accepts(fixed_size(), srgb(256, 256)));
+    assert!(accepts(fixed_size(), luma(256, 256)));
+    assert!(!accepts(fixed_size(), luma(128, 128)));
+
+    let mut commands = CommandBuffer::default();
+    let base = commands.generic(GenericDeclaration::default()).unwrap();
+    let shared = commands
+        .generic(GenericDeclaration {
+            size: SizeSpec::Shared(base),
+            ..GenericDeclaration::default()
+        })
+        .unwrap();
+
+    let base = commands.describe_var(base).unwrap();
+    let shared = commands.describe_var(shared).unwrap();
+    assert_eq!(base.size(), shared.size());
+    assert_ne!(base.descriptor_chroma(), shared.descriptor_chroma());
+
+    // The bounds of a concrete chroma are checked when declared.
+    let mismatched = commands.generic(GenericDeclaration {
+        bounds: &[GenericBound::TexelBytes(1)],
+        chroma: ChromaSpec::Concrete(rgba.clone(), Color::SRGB),
+        ..GenericDeclaration::default()
+    });
+    assert!(mismatched.is_err());
+}
+
+#[test]
+fn bounded_generic_transmutes() {
+    let rgba = Texel::new_u8(SampleParts::RgbA);
+
+    let transmuting = |bounds: &[GenericBound]| {
+        let mut commands = CommandBuffer::default();
+        let var = commands
+            .generic(GenericDeclaration {
+                bounds,
+                ..GenericDeclaration::default()
+            })
+            .unwrap();
+        let input = commands.input_generic(var).unwrap();
+        let target = commands
+            .derive_descriptor(DescriptorDerivation {
+                base: var,
+                size: None,
+                chroma: Some((rgba.clone(), Color::SRGB)),
+            })
+            .unwrap();
+
+        let target = commands.describe_var(target).unwrap();
+        let transmuted = commands.transmute_generic(input, target)?;
+        commands.output(transmuted)?;
+        Ok::<_, CommandError>(commands)
+    };
+
+    // Without a bound the size of the texel is unknown.
+    assert!(transmuting(&[]).is_err());
+    let function = transmuting(&[GenericBound::TexelBytes(4)]).unwrap();
+
+    let instantiate = |texel: Texel| {
+        let mut commands = CommandBuffer::default();
+        let callee = commands.function(function.computed_signature()).unwrap();
+        let desc = Descriptor::with_texel(texel, 8, 8).unwrap();
+        let input = commands.input(desc).unwrap();
+        let ty = commands.register_descriptor(input).unwrap();
+
+        commands
+            .invoke(
+                callee,
+                InvocationArguments {
+                    generics: &[ty],
+                    arguments: &[input],
+                },
+            )
+            .map(|_| ())
+    };
+
+    assert!(instantiate(Texel::new_u8(SampleParts::BgrA)).is_ok());
+    assert!(instantiate(Texel::new_u8(SampleParts::Luma)).is_err());
+
+    let linker = Linker::from_included();
+    let valid = Descriptor::with_texel(Texel::new_u8(SampleParts::BgrA), 8, 8).unwrap();
+    let invalid = Descriptor::with_texel(Texel::new_u8(SampleParts::Luma), 8, 8).unwrap();
+    assert!(linker.link(&function, &[valid], &[], &[]).is_ok());
+    assert!(linker.link(&function, &[invalid], &[], &[]).is_err());
+
+    let declared = TyVarBounds {
+        bounds: vec![GenericBound::TexelBytes(4), GenericBound::Renderable],
+    };
+    let assumed = TyVarBounds {
+        bounds: vec![GenericBound::TexelBytes(4)],
+    };
+    assert!(declared.contains_bounds(&assumed));
+    assert!(!assumed.contains_bounds(&declared));
+}
+
+#[test]
+fn mse_of_mismatched_images() {
+    let mut commands = CommandBuffer::default();
+
+    let small = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 2, 2).unwrap();
+    let large = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+
+    let lhs = commands.input(small.clone()).unwrap();
+    let rhs = commands.input(large).unwrap();
+    let same = commands.input(small).unwrap();
+
+    assert!(commands.mse(lhs, rhs).unwrap_err().is_type_err());
+    assert!(commands.mse(lhs, same).is_ok());
+
+    assert_eq!(psnr(0.0, 1.0), f32::INFINITY);
+    assert!((psnr(1e-2, 1.0) - 20.0).abs() < 1e-4);
+}
+
+#[test]
+fn homography_from_quad() {
+    let from = [[0.0, 0.0], [4.0, 0.0], [4.0, 4.0], [0.0, 4.0]];
+    let to = [[1.0, 2.0], [9.0, 1.0], [7.0, 8.0], [2.0, 6.0]];
+
+    let homography = Homography::from_quad(AffineSample::Nearest, from, to).unwrap();
+    let matrix = RowMatrix::new(homography.matrix);
+
+    for (point, expected) in from.into_iter().zip(to) {
+        let [x, y] = matrix.multiply_point(point);
+        assert!((x - expected[0]).abs() < 1e-4, "{x} vs {expected:?}");
+        assert!((y - expected[1]).abs() < 1e-4, "{y} vs {expected:?}");
+    }
+
+    // Collinear points have no unique transformation.
+    let line = [[0.0, 0.0], [1.0, 1.0], [2.0, 2.0], [0.0, 4.0]];
+    assert!(Homography::from_quad(AffineSample::Nearest, from, line).is_none());
+
+    // A quad containing points at infinity can not be painted.
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let below = commands.input(desc.clone()).unwrap();
+    let above = commands.input(desc).unwrap();
+
+    let horizon = Homography {
+        matrix: [1.0, 0.0, 0.0, 0.0, 1.0, 0.0, 0.0, -0.5, 1.0],
+        ..Homography::new(AffineSample::Nearest)
+    };
+
+    assert!(commands.perspective(below, horizon, above).is_err());
+    let identity = Homography::new(AffineSample::BiLinear);
+    assert!(commands.perspective(below, identity, above).is_ok());
+}
+
+#[test]
+fn padding_grows_descriptor() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 3).unwrap();
+    let src = commands.input(desc).unwrap();
+
+    let padding = Padding {
+        left: 1,
+        right: 2,
+        top: 3,
+        bottom: 4,
+    };
+
+    let padded = commands.pad(src, padding, BorderMode::Mirror).unwrap();
+    let padded = commands.describe_reg(padded).as_texture().unwrap();
+    assert_eq!(padded.as_concrete().map(|desc| desc.size()), Some((7, 10)));
+
+    let overflow = Padding {
+        left: u32::MAX,
+        ..Padding::default()
+    };
+
+    assert!(commands.pad(src, overflow, BorderMode::Clamp).is_err());
+}
+
+#[test]
+fn crop_or_fill_beyond_bounds() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let src = commands.input(desc).unwrap();
+
+    let rect = SignedRectangle {
+        x: -2,
+        y: 1,
+        max_x: 3,
+        max_y: 9,
+    };
+
+    assert_eq!((rect.width(), rect.height()), (5, 8));
+
+    let cropped = commands.crop_or_fill(src, rect, [0.0; 4]).unwrap();
+    let cropped = commands.describe_reg(cropped).as_texture().unwrap();
+    assert_eq!(cropped.as_concrete().map(|desc| desc.size()), Some((5, 8)));
+
+    let empty = SignedRectangle { max_x: -3, ..rect };
+
+    assert_eq!(empty.width(), 0);
+    assert!(commands.crop_or_fill(src, empty, [0.0; 4]).is_err());
+}
+
+#[test]
+fn inscribe_clipped_off_canvas() {
+    let small = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let large = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 8, 8).unwrap();
+
+    let inscribed = |x: i32, y: i32| {
+        let mut commands = CommandBuffer::default();
+        let above = commands.input(small.clone()).unwrap();
+        let below = commands.input(large.clone()).unwrap();
+
+        let placement = SignedRectangle {
+            x,
+            y,
+            max_x: x + 4,
+            max_y: y + 4,
+        };
+
+        let result = commands.inscribe_clipped(below, placement, above).unwrap();
+        let desc = commands.describe_reg(result).as_texture().unwrap();
+        assert_eq!(desc.as_concrete().unwrap(), large);
+
+        match &commands.ops[result.0] {
+            Op::Binary {
+                op:
+                    BinaryOp::Inscribe {
+                        placement,
+                        selection,
+                    },
+                ..
+            } => Some((*placement, *selection)),
+            _ => None,
+        }
+    };
+
+    let rect = |x, y, max_x, max_y| Rectangle { x, y, max_x, max_y };
+
+    // Off the left, right, top and bottom edge.
+    assert_eq!(inscribed(-1, 2), Some((rect(0, 2, 3, 6), rect(1, 0, 4, 4))));
+    assert_eq!(inscribed(6, 2), Some((rect(6, 2, 8, 6), rect(0, 0, 2, 4))));
+    assert_eq!(inscribed(2, -3), Some((rect(2, 0, 6, 1), rect(0, 3, 4, 4))));
+    assert_eq!(inscribed(2, 5), Some((rect(2, 5, 6, 8), rect(0, 0, 4, 3))));
+
+    // Fully contained, and not overlapping at all.
+    assert_eq!(inscribed(2, 2), Some((rect(2, 2, 6, 6), rect(0, 0, 4, 4))));
+    assert_eq!(inscribed(-4, 2), None);
+    assert_eq!(inscribed(8, 8), None);
+
+    let mut commands = CommandBuffer::default();
+    let above = commands.input(small.clone()).unwrap();
+    let below = commands.input(large.clone()).unwrap();
+    let wrong_size = SignedRectangle {
+        x: -1,
+        y: -1,
+        max_x: 4,
+        max_y: 4,
+    };
+
+    assert!(commands.inscribe_clipped(below, wrong_size, above).is_err());
+}
+
+#[test]
+fn pyramid_levels_round_up() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 5, 8).unwrap();
+    let src = commands.input(desc).unwrap();
+
+    let pyramid = commands.pyramid(src, 4).unwrap();
+    assert_eq!(pyramid.len(), 4);
+    assert_eq!(pyramid[0], src);
+
+    let sizes: Vec<_> = pyramid
+        .iter()
+        .map(|&level| {
+            let desc = commands.describe_reg(level).as_texture().unwrap();
+            desc.as_concrete().unwrap().size()
+        })
+        .collect();
+
+    assert_eq!(sizes, [(5, 8), (3, 4), (2, 2), (1, 1)]);
+    assert!(commands.pyramid(src, 0).is_err());
+
+    let bands = commands.laplacian_pyramid(src, 3).unwrap();
+    let collapsed = commands.collapse(&bands).unwrap();
+    let collapsed = commands.describe_reg(collapsed).as_texture().unwrap();
+    assert_eq!(collapsed.as_concrete().unwrap().size(), (5, 8));
+}
+
+#[test]
+fn downsample_rounds_up() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 100, 7).unwrap();
+    let src = commands.input(desc).unwrap();
+
+    let small = commands.downsample(src, (64, 3)).unwrap();
+    let small = commands.describe_reg(small).as_texture().unwrap();
+    assert_eq!(small.as_concrete().unwrap().size(), (2, 3));
+
+    assert!(commands.downsample(src, (0, 1)).is_err());
+}
+
+#[test]
+fn reduce_mean_in_float_passes() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 100, 7).unwrap()
+    };
+    let src = commands.input(desc.clone()).unwrap();
+
+    let mean = commands.reduce_mean(src).unwrap();
+    let result = commands.describe_reg(mean).as_texture().unwrap();
+    let result = result.as_concrete().unwrap();
+    assert_eq!(result.size(), (1, 1));
+    assert_eq!((&result.texel, &result.color), (&desc.texel, &desc.color));
+
+    // Every pass before the last one keeps the linear sums as single precision scalars.
+    let mut pass = mean;
+    while let Op::Unary {
+        src: previous,
+        op: UnaryOp::Reduce(_),
+        ..
+    } = &commands.ops[pass.0]
+    {
+        pass = *previous;
+        if pass == src {
+            break;
+        }
+
+        let desc = commands.describe_reg(pass).as_texture().unwrap();
+        let desc = desc.as_concrete().unwrap();
+        assert_eq!(desc.texel.bits, SampleBits::Float32x4);
+        assert_eq!(
+            desc.color,
+            Color::Scalars {
+                transfer: Transfer::Linear
+            }
+        );
+    }
+
+    assert_eq!(pass, src);
+}
+
+#[test]
+fn worley_parameters() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 64).unwrap();
+
+    let worley = WorleyParams::with_cells(8.0, 8.0);
+    assert!(commands
+        .distribution_worley(desc.clone(), worley.clone())
+        .is_ok());
+
+    let no_cells = WorleyParams {
+        cells: [0.0, 8.0],
+        ..worley.clone()
+    };
+
+    assert!(commands
+        .distribution_worley(desc.clone(), no_cells)
+        .is_err());
+
+    let too_jittery = WorleyParams {
+        jitter: 1.5,
+        distance_fn: WorleyDistance::Chebyshev,
+        ..worley
+    };
+
+    assert!(commands.distribution_worley(desc, too_jittery).is_err());
+}
+
+#[test]
+fn test_pattern_parameters() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 48).unwrap();
+
+    let checker = TestPattern::Checker {
+        size: 8,
+        colors: [[0.0, 0.0, 0.0, 1.0], [1.0; 4]],
+    };
+
+    assert!(commands.test_pattern(desc.clone(), checker).is_ok());
+    assert!(commands
+        .test_pattern(desc.clone(), TestPattern::ColorBars)
+        .is_ok());
+
+    let empty = TestPattern::Checker {
+        size: 0,
+        colors: [[0.0; 4]; 2],
+    };
+
+    assert!(commands.test_pattern(desc.clone(), empty).is_err());
+
+    let sweep = TestPattern::ZonePlate { scale: f32::NAN };
+    assert!(commands.test_pattern(desc, sweep).is_err());
+}
+
+#[test]
+fn voronoi_seed_counts() {
+    use crate::shaders::voronoi::MAX_POINTS;
+
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 32).unwrap();
+
+    let seed = ([0.5, 0.5], [1.0, 0.0, 0.0, 1.0]);
+    let diagram = Voronoi {
+        points: vec![seed; 3],
+        metric: VoronoiMetric::Euclidean,
+    };
+
+    assert!(commands.voronoi(desc.clone(), diagram.clone()).is_ok());
+
+    let empty = Voronoi {
+        points: vec![],
+        ..diagram.clone()
+    };
+
+    assert!(commands.voronoi(desc.clone(), empty).is_err());
+
+    let too_many = Voronoi {
+        points: vec![seed; MAX_POINTS + 1],
+        metric: VoronoiMetric::Manhattan,
+    };
+
+    assert!(commands.voronoi(desc.clone(), too_many).is_err());
+
+    let not_finite = Voronoi {
+        points: vec![([f32::NAN, 0.0], seed.1)],
+        ..diagram
+    };
+
+    assert!(commands.voronoi(desc, not_finite).is_err());
+}
+
+#[test]
+fn fractal_noise_parameters() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 64).unwrap();
+
+    let warped = FractalNoise {
+        num_octaves: 32,
+        lacunarity: 1.8,
+        warp: 4.0,
+        ..FractalNoise::with_octaves(32)
+    };
+
+    assert!(commands
+        .distribution_fractal_noise(desc.clone(), warped.clone())
+        .is_ok());
+    assert!(commands
+        .distribution_simplex_noise(desc.clone(), warped.clone())
+        .is_ok());
+
+    let flat = FractalNoise {
+        lacunarity: 0.0,
+        ..warped.clone()
+    };
+
+    assert!(commands
+        .distribution_fractal_noise(desc.clone(), flat)
+        .is_err());
+
+    let torn = FractalNoise {
+        warp: f32::INFINITY,
+        ..warped
+    };
+
+    assert!(commands.distribution_simplex_noise(desc, torn).is_err());
+}
+
+#[test]
+fn chroma_key_outputs() {
+    let mut commands = CommandBuffer::default();
+
+    let rgba = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 16).unwrap()
+    };
+
+    let rgb = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 32, 16).unwrap()
+    };
+
+    let green = ChromaKey {
+        color: [0.0, 1.0, 0.0],
+        tolerance: 0.1,
+        softness: 0.05,
+        spill_suppression: 0.5,
+    };
+
+    let with_alpha = commands.input(rgba.clone()).unwrap();
+    let keyed = commands
+        .chroma_key(with_alpha, green, KeyOutput::Alpha)
+        .unwrap();
+    let keyed = commands.describe_reg(keyed).as_texture().unwrap();
+    assert_eq!(keyed.as_concrete().unwrap(), rgba);
+
+    let without_alpha = commands.input(rgb).unwrap();
+    assert!(commands
+        .chroma_key(without_alpha, green, KeyOutput::Alpha)
+        .is_err());
+
+    let matte = commands
+        .chroma_key(without_alpha, green, KeyOutput::Matte)
+        .unwrap();
+    let matte = commands.describe_reg(matte).as_texture().unwrap();
+    let matte = matte.as_concrete().unwrap();
+    assert_eq!(matte.texel.parts, SampleParts::Luma);
+    assert_eq!(matte.size(), (32, 16));
+
+    let negative = ChromaKey {
+        softness: -1.0,
+        ..green
+    };
+
+    assert!(commands
+        .chroma_key(with_alpha, negative, KeyOutput::Matte)
+        .is_err());
+}
+
+#[test]
+fn drop_shadow_expands_canvas() {
+    let mut commands = CommandBuffer::default();
+    let rgba = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 20, 10).unwrap();
+    let rgb = Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 20, 10).unwrap();
+
+    let shadow = Shadow {
+        offset: (4, -2),
+        blur_sigma: 1.0,
+        color: [0.0; 3],
+        opacity: 0.5,
+        expand: true,
+    };
+
+    let size_of = |commands: &CommandBuffer, reg| {
+        let desc = commands.describe_reg(reg).as_texture().unwrap();
+        desc.as_concrete().unwrap().size()
+    };
+
+    let image = commands.input(rgba).unwrap();
+    let shadowed = commands.drop_shadow(image, shadow).unwrap();
+    // A radius of 3 on each side, adjusted by the offset.
+    assert_eq!(size_of(&commands, shadowed), (20 + 0 + 7, 10 + 5 + 1));
+
+    let cut = Shadow {
+        expand: false,
+        ..shadow
+    };
+
+    let shadowed = commands.drop_shadow(image, cut).unwrap();
+    assert_eq!(size_of(&commands, shadowed), (20, 10));
+
+    let opaque = commands.input(rgb).unwrap();
+    assert!(commands.drop_shadow(opaque, shadow).is_err());
+
+    let too_wide = Shadow {
+        blur_sigma: 1000.0,
+        ..shadow
+    };
+
+    assert!(commands.drop_shadow(image, too_wide).is_err());
+}
+
+#[test]
+fn bloom_requires_linear_color() {
+    let mut commands = CommandBuffer::default();
 
-                    reg_to_texture.insert(idx_reg, texture);
-                }
-                Op::BufferInit {
-                    op: buf_op,
-                    desc: _,
-                } => {
-                    let buffer = realize_buffer(idx, op)?;
+    let srgb = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 48).unwrap()
+    };
 
-                    match buf_op {
-                        BufferInitOp::FromData { placement, data } => {
-                            high_ops.push(High::WriteInto {
-                                dst: buffer,
-                                fn_: BufferWrite::Zero,
-                            });
+    let mut linear = srgb.clone();
+    if let Color::Rgb { transfer, .. } = &mut linear.color {
+        *transfer = Transfer::Linear;
+    }
 
-                            high_ops.push(High::WriteInto {
-                                dst: buffer,
-                                fn_: BufferWrite::Put {
-                                    placement: placement.clone(),
-                                    data: data.clone(),
-                                    knob: match knob {
-                                        KnobUser::None => None,
-                                        KnobUser::Runtime(idx) => Some(idx),
-                                        _ => unreachable!(
-                                            "Buffer init from buffer does not make sense"
-                                        ),
-                                    },
-                                },
-                            });
-                        }
-                    }
-                }
-                Op::Unary {
-                    desc: _,
-                    src,
-                    op: unary_op,
-                } => {
-                    let texture = realize_texture(idx, op)?;
+    let glow = Bloom {
+        threshold: 0.8,
+        intensity: 0.5,
+        radius: 16.0,
+    };
 
-                    match unary_op {
-                        &UnaryOp::Crop(region) => {
-                            let target =
-                                Rectangle::with_width_height(region.width(), region.height());
-                            high_ops.push(High::PushOperand(reg_to_texture[src]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintToSelection {
-                                    texture: reg_to_texture[src],
-                                    selection: region,
-                                    target: target.into(),
-                                    viewport: target,
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            core.paint_copy(),
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            });
-                        }
-                        UnaryOp::ChromaticAdaptation(adaptation) => {
-                            // Determine matrix for converting to xyz, then adapt, then back.
-                            let adapt = RowMatrix::new(adaptation.to_matrix()?);
-                            let output = adapt.multiply_right(adaptation.to_xyz_matrix.into());
-                            let matrix = adaptation.from_xyz_matrix.multiply_right(output);
+    let encoded = commands.input(srgb).unwrap();
+    assert!(commands.bloom(encoded, glow).is_err());
 
-                            high_ops.push(High::PushOperand(reg_to_texture[src]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::LinearColorMatrix(
-                                            shaders::LinearColorTransform {
-                                                matrix: matrix.into(),
-                                                spirv: std.linear_color_transform.clone(),
-                                            },
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            });
-                        }
-                        UnaryOp::Vignette(vignette) => {
-                            todo!()
-                        }
-                        UnaryOp::ColorConvert(color) => {
-                            // The inherent OptoToLinear transformation gets us to a linear light
-                            // representation. We want to convert this into a compatible (that is,
-                            // using the same observer definition) other linear light
-                            // representation that we then transfer back to an electrical form.
-                            // Note that these two steps happen, conveniently, automatically.
-                            // Usually it is ensured that only two images with the same linear
-                            // light representation are used in a single paint call but this
-                            // violates it on purpose.
+    let image = commands.input(linear.clone()).unwrap();
+    let result = commands.bloom(image, glow).unwrap();
+    let result = commands.describe_reg(result).as_texture().unwrap();
+    assert_eq!(result.as_concrete().unwrap(), linear);
 
-                            high_ops.push(High::PushOperand(reg_to_texture[src]));
-                            // FIXME: using a copy here but this means we do this in unnecessarily
-                            // many steps. We first decode to linear color, then draw, then code
-                            // back to the non-linear electrical space.
-                            // We could do this directly from one matrix to another or try using an
-                            // ephemeral intermediate attachment?
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: color.to_shader(std),
-                                        knob,
-                                    },
-                                },
-                            });
-                        }
-                        UnaryOp::Extract { channel: _ } => {
-                            high_ops.push(High::PushOperand(reg_to_texture[src]));
+    let negative = Bloom {
+        radius: -1.0,
+        ..glow
+    };
 
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    // This will grab the right channel, that is all of them.
-                                    // The actual conversion is done in de-staging of the result.
-                                    // TODO: evaluate if this is the right way to do it. We could
-                                    // also perform a LinearColorMatrix shader here with close to
-                                    // the same amount of shader code but a precise result.
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            core.paint_copy(),
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            })
-                        }
-                        UnaryOp::Derivative(derivative) => {
-                            let invocation =
-                                derivative.method.to_shader(derivative.direction, std)?;
+    assert!(commands.bloom(image, negative).is_err());
+}
 
-                            high_ops.push(High::PushOperand(reg_to_texture[src]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment { invocation, knob },
-                                },
-                            })
-                        }
-                        UnaryOp::Transmute => high_ops.push(High::Copy {
-                            src: *src,
-                            dst: Register(idx),
-                        }),
-                    }
+#[test]
+fn chromatic_aberration_scales() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-                    reg_to_texture.insert(Register(idx), texture);
-                }
-                Op::Binary {
-                    desc: _,
-                    lhs,
-                    rhs,
-                    op: binary_op,
-                } => {
-                    let texture = realize_texture(idx, op)?;
+    let image = commands.input(desc).unwrap();
 
-                    let lhs_descriptor = command
-                        .describe_reg(*lhs)
-                        .as_texture()
-                        .unwrap()
-                        .monomorphize(tys);
+    let fringe = ChromaticAberration::with_strength(-0.01, [0.5, 0.5]);
+    assert_eq!(fringe.scales, [0.99, 1.0, 1.01]);
+    assert!(commands.chromatic_aberration(image, fringe).is_ok());
 
-                    let rhs_descriptor = command
-                        .describe_reg(*rhs)
-                        .as_texture()
-                        .unwrap()
-                        .monomorphize(tys);
+    let explicit = ChromaticAberration {
+        scales: [1.002, 1.0, 0.0],
+        center: [0.4, 0.5],
+    };
 
-                    let lower_region = Rectangle::from(&lhs_descriptor);
-                    let upper_region = Rectangle::from(&rhs_descriptor);
+    assert!(commands.chromatic_aberration(image, explicit).is_err());
+}
 
-                    match binary_op {
-                        BinaryOp::Affine(affine) => {
-                            let affine_matrix = RowMatrix::new(affine.transformation);
+#[test]
+fn lens_distortion_model() {
+    let barrel = Distortion {
+        k1: -0.2,
+        k2: 0.0,
+        k3: 0.0,
+        p1: 0.0,
+        p2: 0.0,
+        center: [50.0, 40.0],
+        focal: [100.0, 100.0],
+        fill: [0.0; 4],
+    };
 
-                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintToSelection {
-                                    texture: reg_to_texture[lhs],
-                                    selection: lower_region,
-                                    target: lower_region.into(),
-                                    viewport: lower_region,
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            core.paint_copy(),
-                                        ),
-                                        knob: knob.clone(),
-                                    },
-                                },
-                            });
+    // The center is fixed, other points move towards it.
+    assert_eq!(barrel.distort([50.0, 40.0]), [50.0, 40.0]);
+    let [x, y] = barrel.distort([100.0, 40.0]);
+    assert!((x - 97.5).abs() < 1e-4 && y == 40.0);
 
-                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Load(texture),
-                                fn_: Initializer::PaintToSelection {
-                                    texture: reg_to_texture[rhs],
-                                    selection: upper_region,
-                                    target: QuadTarget::from(upper_region).affine(&affine_matrix),
-                                    viewport: lower_region,
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            affine.sampling.as_paint_on_top(core)?,
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            })
-                        }
-                        BinaryOp::Inject {
-                            channel,
-                            from_channels,
-                        } => {
-                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
-                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 100, 80).unwrap();
+    let image = commands.input(desc).unwrap();
+    assert!(commands.lens_undistort(image, barrel).is_ok());
+
+    let unfocused = Distortion {
+        focal: [0.0, 100.0],
+        ..barrel
+    };
+
+    assert!(commands.lens_undistort(image, unfocused).is_err());
+}
+
+#[test]
+fn demosaic_luma_mosaic() {
+    let mut commands = CommandBuffer::default();
+    let cfa = Cfa {
+        pattern: CfaPattern::Grbg,
+        algorithm: DemosaicAlgorithm::Malvar,
+        offset: [1, 0],
+    };
+
+    let luma = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::UInt16,
+        parts: SampleParts::Luma,
+    };
+
+    let rgba = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 48).unwrap();
+    let mosaic = Descriptor::with_texel(luma, 64, 48).unwrap();
+    let mosaic = commands.input(mosaic).unwrap();
+    let color = commands.demosaic(mosaic, cfa, rgba.color.clone()).unwrap();
+
+    let desc = commands.describe_reg(color).as_texture().unwrap();
+    let desc = desc.as_concrete().unwrap();
+    assert_eq!(desc.texel.bits, SampleBits::UInt16x3);
+    assert_eq!(desc.texel.parts, SampleParts::Rgb);
+    assert_eq!((desc.layout.width, desc.layout.height), (64, 48));
+    assert_eq!(desc.color, rgba.color);
+
+    let color = rgba.color.clone();
+    let rgba = commands.input(rgba).unwrap();
+    assert!(commands.demosaic(rgba, cfa, color).is_err());
+}
+
+#[test]
+fn denoise_nlm_radii() {
+    use shaders::denoise_nlm::{MAX_PATCH_RADIUS, MAX_SEARCH_RADIUS};
+
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let image = commands.input(desc).unwrap();
+
+    let nlm = Nlm {
+        search_radius: MAX_SEARCH_RADIUS,
+        patch_radius: MAX_PATCH_RADIUS,
+        h: 0.05,
+    };
+
+    assert!(commands.denoise_nlm(image, nlm).is_ok());
+
+    let wide = Nlm {
+        search_radius: MAX_SEARCH_RADIUS + 1,
+        ..nlm
+    };
+
+    let large = Nlm {
+        patch_radius: MAX_PATCH_RADIUS + 1,
+        ..nlm
+    };
+
+    let weak = Nlm { h: 0.0, ..nlm };
+
+    assert!(commands.denoise_nlm(image, wide).is_err());
+    assert!(commands.denoise_nlm(image, large).is_err());
+    assert!(commands.denoise_nlm(image, weak).is_err());
+}
+
+#[test]
+fn sharpen_clamps_integer_texels() {
+    let mut commands = CommandBuffer::default();
+
+    let float = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::Float32x4,
+        parts: SampleParts::RgbA,
+    };
+
+    let bytes = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap();
+    let float = Descriptor::with_texel(float, 32, 32).unwrap();
+
+    for (desc, clamped) in [(bytes, true), (float, false)] {
+        let image = commands.input(desc).unwrap();
+        let sharp = commands.sharpen(image, 0.5).unwrap();
+
+        assert!(matches!(
+            commands.ops[sharp.0],
+            Op::Unary {
+                op: UnaryOp::Sharpen(shaders::sharpen::ShaderData { clamp, .. }),
+                ..
+            } if clamp == clamped
+        ));
+
+        assert!(commands.sharpen(image, f32::NAN).is_err());
+    }
+}
+
+#[test]
+fn derivative_clamps_unless_bordered() {
+    let mut commands = CommandBuffer::default();
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap();
+    let image = commands.input(desc).unwrap();
+
+    let derivative = Derivative {
+        method: DerivativeMethod::Sobel,
+        direction: Direction::Width,
+    };
 
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::Inject(
-                                            shaders::inject::Shader {
-                                                data: shaders::inject::ShaderData {
-                                                    mix: channel.into_vec4(),
-                                                    color: from_channels
-                                                        .channel_weight_vec4()
-                                                        .unwrap(),
-                                                },
-                                                spirv: std.inject.clone(),
-                                            },
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            })
-                        }
-                        BinaryOp::Inscribe { placement } => {
-                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Discard(texture),
-                                fn_: Initializer::PaintToSelection {
-                                    texture: reg_to_texture[lhs],
-                                    selection: lower_region,
-                                    target: lower_region.into(),
-                                    viewport: lower_region,
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            core.paint_copy(),
-                                        ),
-                                        knob: knob.clone(),
-                                    },
-                                },
-                            });
+    for (config, expected) in [
+        (derivative.clone().into(), BorderHandling::Clamp),
+        (
+            derivative.with_border(BorderHandling::Mirror),
+            BorderHandling::Mirror,
+        ),
+    ] {
+        let result = commands.derivative(image, config).unwrap();
+
+        assert!(matches!(
+            commands.ops[result.0],
+            Op::Unary {
+                op: UnaryOp::Derivative(BorderedDerivative { border, .. }),
+                ..
+            } if border == expected
+        ));
+    }
+}
 
-                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Load(texture),
-                                fn_: Initializer::PaintToSelection {
-                                    texture: reg_to_texture[rhs],
-                                    selection: upper_region,
-                                    target: (*placement).into(),
-                                    viewport: lower_region,
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::PaintOnTop(
-                                            core.paint_copy(),
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            });
-                        }
-                        BinaryOp::Palette(shader) => {
-                            high_ops.push(High::PushOperand(reg_to_texture[lhs]));
-                            high_ops.push(High::PushOperand(reg_to_texture[rhs]));
+#[test]
+fn emboss_keeps_texel() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-                            high_ops.push(High::DrawInto {
-                                dst: Target::Load(texture),
-                                fn_: Initializer::PaintFullScreen {
-                                    shader: ParameterizedFragment {
-                                        invocation: FragmentShaderInvocation::Palette(
-                                            shaders::palette::Shader {
-                                                data: shader.clone(),
-                                                spirv: std.palette.clone(),
-                                            },
-                                        ),
-                                        knob,
-                                    },
-                                },
-                            });
-                        }
-                        BinaryOp::GainMap(_) => {
-                            todo!()
-                        }
-                    }
+    let image = commands.input(desc.clone()).unwrap();
 
-                    reg_to_texture.insert(Register(idx), texture);
-                }
-                Op::DynamicImage { call, command, .. } => {
-                    let texture = realize_texture(idx, op)?;
-                    let (op_unary, op_binary, arguments);
+    let emboss = Emboss {
+        direction_rad: core::f32::consts::FRAC_PI_4,
+        depth: 2.0,
+        recolor: false,
+    };
 
-                    match call {
-                        OperandDynKind::Construct => {
-                            arguments = &[][..];
-                            reg_to_texture.insert(Register(idx), texture);
-                        }
-                        OperandDynKind::Unary(reg) => {
-                            op_unary = [reg_to_texture[reg]];
-                            arguments = &op_unary[..];
-                            reg_to_texture.insert(Register(idx), texture);
-                        }
-                        OperandDynKind::Binary { lhs, rhs } => {
-                            op_binary = [reg_to_texture[lhs], reg_to_texture[rhs]];
-                            arguments = &op_binary[..];
-                            reg_to_texture.insert(Register(idx), texture);
-                        }
-                    }
+    let relief = commands.emboss(image, emboss).unwrap();
+    let relief = commands.describe_reg(relief).as_texture().unwrap();
+    assert_eq!(relief.as_concrete().unwrap(), desc);
 
-                    if command.num_args != arguments.len() as u32 {
-                        // FIXME: should just error with information. We need to pin-point  the
-                        // source of the num args to either the library (an internal bug) or the
-                        // user for dynamically constructed shaders. Also consider if the number of
-                        // arguments can be recovered from the SPIR-V earlier.
-                        return Err(CompileError::NotYetImplemented);
-                    }
+    let recolor = Emboss {
+        recolor: true,
+        ..emboss
+    };
 
-                    for &operand in arguments {
-                        high_ops.push(High::PushOperand(operand));
-                    }
+    assert!(commands.emboss(image, recolor).is_ok());
 
-                    // This always 'constructs' an output texture. The image we render to is new,
-                    // no matter how many arguments are being inserted.
-                    high_ops.push(High::DrawInto {
-                        dst: Target::Discard(texture),
-                        fn_: Initializer::PaintFullScreen {
-                            shader: ParameterizedFragment {
-                                invocation: FragmentShaderInvocation::Runtime(command.clone()),
-                                knob,
-                            },
-                        },
-                    })
-                }
-                Op::InvokedResult { .. } => {
-                    let texture = realize_texture(idx, op)?;
+    let unlit = Emboss {
+        direction_rad: f32::INFINITY,
+        ..emboss
+    };
 
-                    high_ops.push(High::Uninit {
-                        dst: Target::Discard(texture),
-                    });
+    assert!(commands.emboss(image, unlit).is_err());
+}
 
-                    reg_to_texture.insert(Register(idx), texture);
-                }
-                Op::Invoke {
-                    function,
-                    arguments,
-                    results,
-                    generics,
-                } => {
-                    let monomorphic_tys: Vec<_> = generics
-                        .iter()
-                        .map(|gen| gen.monomorphize(tys))
-                        .collect::<_>();
+#[test]
+fn solid_rgba_of_any_texel() {
+    let mut commands = CommandBuffer::default();
 
-                    let &FunctionVar(function_idx) = function;
-                    let link_idx = *functions
-                        .get(function_idx)
-                        .ok_or(CompileError::NotYetImplemented)?;
+    let texels = [
+        Texel::new_u8(SampleParts::Luma),
+        Texel::new_u8(SampleParts::LumaA),
+        Texel::new_u8(SampleParts::Rgb),
+        Texel {
+            bits: SampleBits::UInt16x4,
+            ..Texel::new_u8(SampleParts::RgbA)
+        },
+        Texel {
+            bits: SampleBits::Float32x4,
+            ..Texel::new_u8(SampleParts::RgbA)
+        },
+    ];
+
+    for texel in texels {
+        let desc = Descriptor::with_texel(texel, 4, 4).unwrap();
+        assert!(commands.solid_rgba(desc, [0.5; 4]).is_ok());
+    }
 
-                    let function = mono.push_function(LinkedMonomorphicSignature {
-                        link_idx,
-                        tys: monomorphic_tys,
-                    });
+    // Three channels of one byte each, not to be confused with a stride of four bytes.
+    let rgb = Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 4, 4).unwrap();
+    assert_eq!(rgb.texel.bits, SampleBits::UInt8x3);
+    assert_eq!(rgb.layout.texel_stride, 3);
 
-                    let mut image_io = vec![];
+    let mut inconsistent = Descriptor::with_texel(Texel::new_u8(SampleParts::Luma), 4, 4).unwrap();
+    inconsistent.layout.texel_stride = 4;
+    assert!(commands.solid_rgba(inconsistent, [0.5; 4]).is_err());
+}
 
-                    for &register in arguments {
-                        // Arguments must precede the function and already be laid out.
-                        if register.0 >= idx {
-                            return Err(CompileError::NotYetImplemented);
-                        }
+#[test]
+fn extract_alpha_plane() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-                        let texture = realize_texture(register.0, &ops[register.0])?;
-                        image_io.push(CallBinding::InTexture { register, texture });
-                    }
+    let opaque = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 32, 32).unwrap()
+    };
 
-                    for &register in results {
-                        // Results must precede the function and already be laid out. They are not
-                        // initialized but initialized on return.
-                        if register.0 >= idx {
-                            return Err(CompileError::NotYetImplemented);
-                        }
+    let image = commands.input(desc.clone()).unwrap();
+    let alpha = commands.extract(image, ColorChannel::Alpha).unwrap();
 
-                        let texture = realize_texture(register.0, &ops[register.0])?;
-                        image_io.push(CallBinding::OutTexture { register, texture });
-                    }
+    let plane = commands.describe_reg(alpha).as_texture().unwrap();
+    let plane = plane.as_concrete().unwrap();
+    assert_eq!(plane.texel.parts, SampleParts::A);
+    assert_eq!((plane.layout.width, plane.layout.height), (32, 32));
 
-                    high_ops.push(High::Call {
-                        function,
-                        image_io_buffers: Arc::from(image_io),
-                    });
-                }
-                // In case we add a new case.
-                #[allow(unreachable_patterns)]
-                _ => {
-                    eprintln!("Unimplemented operation");
-                    return Err(CompileError::NotYetImplemented);
-                }
-            }
+    let injected = commands.inject(image, ColorChannel::Alpha, alpha).unwrap();
+    let injected = commands.describe_reg(injected).as_texture().unwrap();
+    assert_eq!(injected.as_concrete().unwrap(), desc);
 
-            high_ops.push(High::Done(Register(idx)));
-            high_ops.push(High::StackPop);
-        }
+    let opaque = commands.input(opaque).unwrap();
+    assert!(commands.extract(opaque, ColorChannel::Alpha).is_err());
+    assert!(commands.inject(opaque, ColorChannel::Alpha, alpha).is_err());
+}
 
-        let end = high_ops.len();
+#[test]
+fn inject_at_placement() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-        // The registers which callers must fill. This must match the order that CallBinding is
-        // passed at call sites, i.e. be consistent with the signature.
-        let signature_registers = signature_in.into_iter().chain(signature_out).collect();
+    let matte = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 8, 8).unwrap()
+    };
 
-        Ok(FunctionLinked {
-            ops: start..end,
-            image_buffers: image_buffers.into_inner(),
-            signature_registers,
-        })
+    let image = commands.input(desc.clone()).unwrap();
+    let matte = commands.input(matte).unwrap();
+    let alpha = commands.extract(matte, ColorChannel::Alpha).unwrap();
+
+    let inside = Rectangle {
+        x: 8,
+        y: 0,
+        max_x: 24,
+        max_y: 16,
+    };
+
+    let injected = commands
+        .inject_at(
+            image,
+            ColorChannel::Alpha,
+            alpha,
+            inside,
+            AffineSample::BiLinear,
+        )
+        .unwrap();
+    let injected = commands.describe_reg(injected).as_texture().unwrap();
+    assert_eq!(injected.as_concrete().unwrap(), desc);
+
+    let outside = Rectangle {
+        max_x: 40,
+        ..inside
+    };
+
+    let empty = Rectangle { max_y: 0, ..inside };
+
+    for rect in [outside, empty] {
+        let sampling = AffineSample::Nearest;
+        assert!(commands
+            .inject_at(image, ColorChannel::Alpha, alpha, rect, sampling)
+            .is_err());
     }
 }
 
-/// Impls on `CommandBuffer` that allow defining custom SPIR-V extensions.
-///
-/// Generally, the steps on the dynamic shader are:
-///
-/// 1. Check the kind, get SPIR-v code.
-/// 2. Determine the dynamic typing of the result.
-/// 3. Have the shader create binary representation of its data.
-/// 3. Create a new entry on the command buffer.
-/// 4. Not yet performed: (Validate the SPIR-V module inputs against the data definition)
-impl CommandBuffer {
-    /// Get the descriptor for a register.
-    fn describe_reg(&self, Register(reg): Register) -> RegisterDescription<'_> {
-        match self.ops.get(reg) {
-            None | Some(Op::Output { .. }) | Some(Op::Render { .. }) => RegisterDescription::None,
-            Some(Op::Invoke { .. }) => {
-                // This does not describe results directly.
-                RegisterDescription::None
+#[test]
+fn gradient_polar_two_channels() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let image = commands.input(desc).unwrap();
+    let gradient = commands
+        .gradient_polar(image, DerivativeMethod::Scharr3)
+        .unwrap();
+
+    let gradient = commands.describe_reg(gradient).as_texture().unwrap();
+    let gradient = gradient.as_concrete().unwrap();
+    assert_eq!(gradient.texel.parts, SampleParts::LumaA);
+    assert_eq!(gradient.texel.bits, SampleBits::UInt8x2);
+    assert_eq!(gradient.layout.texel_stride, 2);
+    assert!(matches!(gradient.color, Color::Scalars { .. }));
+
+    // Luma is not defined for scalars without a luma channel.
+    let scalars = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap();
+    let scalars = commands.input(scalars).unwrap();
+    assert!(commands
+        .gradient_polar(scalars, DerivativeMethod::Sobel)
+        .is_err());
+}
+
+#[test]
+fn ycbcr_codes() {
+    let studio = Ycbcr {
+        matrix: YcbcrMatrix::Bt709,
+        range: YcbcrRange::Limited,
+    };
+
+    let (matrix, offset) = studio.encode_affine(SampleBits::UInt8x3);
+    let encode = |rgb: [f32; 3]| {
+        let [y, cb, cr] = matrix.multiply_column(rgb);
+        [y + offset[0], cb + offset[1], cr + offset[2]].map(|v| v * 255.0)
+    };
+
+    for (rgb, codes) in [
+        ([0.0, 0.0, 0.0], [16.0, 128.0, 128.0]),
+        ([1.0, 1.0, 1.0], [235.0, 128.0, 128.0]),
+        ([0.0, 0.0, 1.0], [32.0, 240.0, 118.0]),
+        ([1.0, 0.0, 0.0], [63.0, 102.0, 240.0]),
+    ] {
+        let encoded = encode(rgb);
+        for (a, b) in encoded.into_iter().zip(codes) {
+            assert!(
+                (a - b).abs() < 0.6,
+                "{rgb:?} is {encoded:?}, expected {codes:?}"
+            );
+        }
+    }
+
+    for matrix in [YcbcrMatrix::Bt601, YcbcrMatrix::Bt709, YcbcrMatrix::Bt2020] {
+        for range in [YcbcrRange::Limited, YcbcrRange::Full] {
+            let ycbcr = Ycbcr { matrix, range };
+            let (encode, offset) = ycbcr.encode_affine(SampleBits::UInt16x3);
+            let (decode, back) = ycbcr.decode_affine(SampleBits::UInt16x3);
+
+            let rgb = [0.25, 0.5, 0.75];
+            let [y, cb, cr] = encode.multiply_column(rgb);
+            let signal = [y + offset[0], cb + offset[1], cr + offset[2]];
+            let [r, g, b] = decode.multiply_column(signal);
+
+            for (a, b) in [r + back[0], g + back[1], b + back[2]].into_iter().zip(rgb) {
+                assert!((a - b).abs() < 1e-5, "{ycbcr:?}");
             }
-            Some(Op::InvokedResult { desc, .. })
-            | Some(Op::Input { desc })
-            | Some(Op::Construct { desc, .. })
-            | Some(Op::Unary { desc, .. })
-            | Some(Op::Binary { desc, .. })
-            | Some(Op::DynamicImage { desc, .. }) => RegisterDescription::Texture(desc),
-            Some(Op::BufferInit { desc, .. })
-            | Some(Op::BufferUnary { desc, .. })
-            | Some(Op::BufferBinary { desc, .. }) => RegisterDescription::Buffer(desc),
         }
     }
+}
+
+#[test]
+fn ycbcr_requires_three_channels() {
+    let mut commands = CommandBuffer::default();
+    let ycbcr = Ycbcr {
+        matrix: YcbcrMatrix::Bt601,
+        range: YcbcrRange::Full,
+    };
+
+    let rgb = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 32, 32).unwrap()
+    };
+
+    let image = commands.input(rgb.clone()).unwrap();
+    let signal = commands.ycbcr_encode(image, ycbcr).unwrap();
+
+    let described = commands.describe_reg(signal).as_texture().unwrap();
+    let described = described.as_concrete().unwrap();
+    assert_eq!(described.texel.parts, SampleParts::Yuv);
+    assert_eq!(described.texel.bits, rgb.texel.bits);
+
+    let decoded = commands.ycbcr_decode(signal, ycbcr, Color::SRGB).unwrap();
+    let decoded = commands.describe_reg(decoded).as_texture().unwrap();
+    assert_eq!(decoded.as_concrete().unwrap(), rgb);
+
+    // Neither alpha nor signals that are already decoded.
+    let rgba = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let rgba = commands.input(rgba).unwrap();
+    assert!(commands.ycbcr_encode(rgba, ycbcr).is_err());
+    assert!(commands.ycbcr_decode(image, ycbcr, Color::SRGB).is_err());
+    assert!(commands.ycbcr_decode(signal, ycbcr, Color::Oklab).is_err());
+}
+
+#[test]
+fn cmyk_four_channels() {
+    let mut commands = CommandBuffer::default();
+    let rgb = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Rgb), 32, 32).unwrap()
+    };
+
+    let image = commands.input(rgb).unwrap();
+    let inks = commands.cmyk_encode(image, BlackGeneration::Max).unwrap();
+
+    let described = commands.describe_reg(inks).as_texture().unwrap();
+    let described = described.as_concrete().unwrap();
+    assert_eq!(described.texel.parts, SampleParts::RgbA);
+    assert_eq!(described.texel.bits, SampleBits::UInt8x4);
+    assert_eq!(described.layout.texel_stride, 4);
+    assert!(matches!(described.color, Color::Scalars { .. }));
+
+    let decoded = commands.cmyk_decode(inks, Color::SRGB).unwrap();
+    let decoded = commands.describe_reg(decoded).as_texture().unwrap();
+    assert_eq!(decoded.as_concrete().unwrap().color, Color::SRGB);
+
+    assert!(commands
+        .cmyk_encode(image, BlackGeneration::Percentage(50.0))
+        .is_ok());
+    assert!(commands
+        .cmyk_encode(image, BlackGeneration::Percentage(150.0))
+        .is_err());
+    assert!(commands.cmyk_decode(image, Color::SRGB).is_err());
+    assert!(commands.cmyk_decode(inks, Color::Oklab).is_err());
+
+    let luma = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Luma), 32, 32).unwrap()
+    };
+
+    let luma = commands.input(luma).unwrap();
+    assert!(commands.cmyk_encode(luma, BlackGeneration::None).is_err());
+}
+
+#[test]
+fn hdr_reference_white() {
+    let luminance = HdrLuminance::default();
+
+    assert_eq!(luminance.relative_white(Transfer::Srgb).unwrap(), 1.0);
+    assert_eq!(luminance.relative_white(Transfer::Linear).unwrap(), 1.0);
 
-    fn push(&mut self, op: Op) -> Register {
-        let reg = Register(self.ops.len());
-        self.ops.push(op);
-        reg
-    }
+    let pq = luminance.relative_white(Transfer::Smpte2084).unwrap();
+    assert!((pq - 0.0203).abs() < 1e-6, "{pq}");
 
-    /// Record a _constructor_, with a user-supplied shader.
-    pub fn construct_dynamic(&mut self, dynamic: &dyn ShaderCommand) -> Register {
-        let mut data = vec![];
-        let mut content = None;
+    // Scene light of the display white through the inverse of the reference OOTF.
+    let scene = luminance.relative_white(Transfer::Bt2100Pq).unwrap();
+    assert!((scene - 0.03073).abs() < 1e-4, "{scene}");
 
-        let source = dynamic.source();
-        let desc = dynamic.data(ShaderData {
-            data_buffer: &mut data,
-            content: &mut content,
-        });
+    let hlg = luminance.relative_white(Transfer::Bt2100Hlg).unwrap();
+    assert!((hlg - 0.26496).abs() < 1e-5, "{hlg}");
 
-        self.push(Op::DynamicImage {
-            call: OperandDynKind::Construct,
-            // FIXME: maybe this conversion should be delayed.
-            // In particular, converting source to SPIR-V may take some form of 'compiler' argument
-            // that's only available during `compile` phase.
-            command: ShaderInvocation {
-                spirv: match source {
-                    ShaderSource::SpirV(spirv) => spirv,
-                },
-                shader_data: match content {
-                    None => None,
-                    Some(c) => Some(c.as_slice(&data).into()),
-                },
-                num_args: 0,
-            },
-            desc: desc.into(),
-        })
+    let dark = HdrLuminance { white_nits: 0.0 };
+    assert!(dark.relative_white(Transfer::Smpte2084).is_err());
+}
+
+#[test]
+fn color_convert_adapts_whitepoints() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let mut target = Color::SRGB;
+    if let Color::Rgb {
+        primary,
+        whitepoint,
+        ..
+    } = &mut target
+    {
+        *primary = Primaries::Bt2020;
+        *whitepoint = Whitepoint::D50;
     }
 
-    /// Record a unary operator, with a user-supplied shader.
-    pub fn unary_dynamic(
-        &mut self,
-        op: Register,
-        dynamic: &dyn ShaderCommand,
-    ) -> Result<Register, CommandError> {
-        let _input_descriptor = match self.describe_reg(op) {
-            RegisterDescription::Texture(desc) => desc,
-            _ => return Err(CommandError::INVALID_CALL),
-        };
+    let image = commands.input(desc.clone()).unwrap();
+    let direct = commands
+        .color_convert(image, target.clone(), desc.texel.clone())
+        .unwrap();
 
-        let mut data = vec![];
-        let mut content = None;
+    let adapted = commands
+        .chromatic_adaptation(
+            image,
+            ChromaticAdaptationMethod::BradfordVonKries,
+            Whitepoint::D50,
+        )
+        .unwrap();
+    let chained = commands
+        .color_convert(adapted, target.clone(), desc.texel.clone())
+        .unwrap();
 
-        let source = dynamic.source();
-        let desc = dynamic.data(ShaderData {
-            data_buffer: &mut data,
-            content: &mut content,
-        });
+    let matrix_of = |reg: Register| match &commands.ops[reg.0] {
+        Op::Unary {
+            op: UnaryOp::ColorConvert(conversion),
+            ..
+        } => conversion.rgb_matrix().unwrap(),
+        Op::Unary {
+            op: UnaryOp::ChromaticAdaptation(adaptation),
+            ..
+        } => adaptation.rgb_matrix().unwrap(),
+        _ => panic!("Not a linear color operation"),
+    };
 
-        let out_reg = self.push(Op::DynamicImage {
-            call: OperandDynKind::Unary(op),
-            // FIXME: maybe this conversion should be delayed.
-            // In particular, converting source to SPIR-V may take some form of 'compiler' argument
-            // that's only available during `compile` phase.
-            command: ShaderInvocation {
-                spirv: match source {
-                    ShaderSource::SpirV(spirv) => spirv,
-                },
-                shader_data: match content {
-                    None => None,
-                    Some(c) => Some(c.as_slice(&data).into()),
-                },
-                num_args: 1,
-            },
-            desc: desc.into(),
-        });
+    let direct = matrix_of(direct);
+    let chained = matrix_of(chained).multiply_right(matrix_of(adapted).into());
+    let chained = RowMatrix::from(chained);
 
-        Ok(out_reg)
+    for (a, b) in direct.into_inner().into_iter().zip(chained.into_inner()) {
+        assert!((a - b).abs() < 1e-5, "{direct:?} differs from {chained:?}");
     }
 
-    /// Record a binary operator, with a user-supplied shader.
-    pub fn binary_dynamic(
-        &mut self,
-        lhs: Register,
-        rhs: Register,
-        dynamic: &dyn ShaderCommand,
-    ) -> Result<Register, CommandError> {
-        let _input_descriptor = match self.describe_reg(lhs) {
-            RegisterDescription::Texture(desc) => desc,
-            _ => return Err(CommandError::INVALID_CALL),
-        };
+    // Adaptation is only defined between RGB colors.
+    let oklab = commands
+        .color_convert(image, Color::Oklab, desc.texel.clone())
+        .unwrap();
+    assert!(commands
+        .color_convert(oklab, target, desc.texel.clone())
+        .is_err());
+}
 
-        let _input_descriptor = match self.describe_reg(rhs) {
-            RegisterDescription::Texture(desc) => desc,
-            _ => return Err(CommandError::INVALID_CALL),
-        };
+#[test]
+fn nonlinear_adaptation_takes_a_pass() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-        let mut data = vec![];
-        let mut content = None;
+    let mut target = Color::SRGB;
+    if let Color::Rgb { whitepoint, .. } = &mut target {
+        *whitepoint = Whitepoint::D50;
+    }
 
-        let source = dynamic.source();
-        let desc = dynamic.data(ShaderData {
-            data_buffer: &mut data,
-            content: &mut content,
-        });
+    let options = ColorConvertOptions {
+        adaptation: ChromaticAdaptationMethod::BradfordNonLinear,
+        ..ColorConvertOptions::default()
+    };
 
-        let out_reg = self.push(Op::DynamicImage {
-            call: OperandDynKind::Binary { lhs, rhs },
-            // FIXME: maybe this conversion should be delayed.
-            // In particular, converting source to SPIR-V may take some form of 'compiler' argument
-            // that's only available during `compile` phase.
-            command: ShaderInvocation {
-                spirv: match source {
-                    ShaderSource::SpirV(spirv) => spirv,
-                },
-                shader_data: match content {
-                    None => None,
-                    Some(c) => Some(c.as_slice(&data).into()),
-                },
-                num_args: 2,
-            },
-            desc: desc.into(),
-        });
+    let image = commands.input(desc.clone()).unwrap();
+    let converted = commands
+        .color_convert_with(image, target, desc.texel.clone(), options)
+        .unwrap();
+
+    let Op::Unary {
+        src,
+        op: UnaryOp::ColorConvert(conversion),
+        ..
+    } = &commands.ops[converted.0]
+    else {
+        panic!("Not a color conversion");
+    };
 
-        Ok(out_reg)
-    }
+    // The conversion itself no longer changes the whitepoint.
+    let matrix = conversion.rgb_matrix().unwrap();
+    let white = matrix.multiply_column([1.0; 3]);
+    assert!(white.iter().all(|v| (v - 1.0).abs() < 1e-5), "{white:?}");
+
+    assert!(matches!(
+        &commands.ops[src.0],
+        Op::Unary {
+            op: UnaryOp::ChromaticAdaptation(ChromaticAdaptation {
+                method: ChromaticAdaptationMethod::BradfordNonLinear,
+                target: Whitepoint::D50,
+                ..
+            }),
+            ..
+        }
+    ));
 }
 
-impl CommandSignature {
-    /// Verify if a signature matches an other command signature.
-    ///
-    /// That is, whether the subtyping relationship of all its bounds and the argument allows using
-    /// one in place of the other declared type. This checks if `self` contains all bounds that
-    /// occur in `actual`.
-    pub fn is_declaration_of(&self, actual: &CommandSignature) -> bool {
-        if self.vars.len() != actual.vars.len() {
-            return false;
+#[test]
+fn chromatic_adaptation_of_lab_and_luma() {
+    let mut commands = CommandBuffer::default();
+    let lab = Descriptor {
+        color: Color::SrLab2 {
+            whitepoint: Whitepoint::D65,
+        },
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::LabA), 32, 32).unwrap()
+    };
+
+    let image = commands.input(lab.clone()).unwrap();
+    let adapted = commands
+        .chromatic_adaptation(
+            image,
+            ChromaticAdaptationMethod::BradfordVonKries,
+            Whitepoint::D50,
+        )
+        .unwrap();
+
+    let desc = commands.describe_reg(adapted).as_texture().unwrap();
+    let desc = desc.as_concrete().unwrap();
+    assert_eq!(desc.texel, lab.texel);
+    assert!(matches!(
+        desc.color,
+        Color::SrLab2 {
+            whitepoint: Whitepoint::D50
         }
+    ));
 
-        for (decl, actual) in self.vars.iter().zip(&actual.vars) {
-            if !decl.contains_bounds(actual) {
-                return false;
-            }
+    let oklab = Descriptor {
+        color: Color::Oklab,
+        ..lab.clone()
+    };
+    let image = commands.input(oklab).unwrap();
+    let adapted = commands
+        .chromatic_adaptation(image, ChromaticAdaptationMethod::VonKries, Whitepoint::D50)
+        .unwrap();
+
+    let desc = commands.describe_reg(adapted).as_texture().unwrap();
+    assert!(matches!(desc.as_concrete().unwrap().color, Color::Oklab));
+
+    let cielab = Descriptor {
+        color: CommandBuffer::CIELAB,
+        ..lab.clone()
+    };
+    let image = commands.input(cielab.clone()).unwrap();
+    let adapted = commands
+        .chromatic_adaptation_cielab(
+            image,
+            ChromaticAdaptationMethod::BradfordVonKries,
+            Whitepoint::D65,
+            Whitepoint::D50,
+        )
+        .unwrap();
+
+    let desc = commands.describe_reg(adapted).as_texture().unwrap();
+    assert_eq!(desc.as_concrete().unwrap(), cielab);
+    assert!(matches!(
+        &commands.ops[adapted.0],
+        Op::Unary {
+            op: UnaryOp::ColorConvert(ColorConversion::XyzToLab {
+                whitepoint: Whitepoint::D50,
+                ..
+            }),
+            ..
         }
+    ));
 
-        true
-    }
+    // The scalars alone do not record the whitepoint to adapt from.
+    assert!(commands
+        .chromatic_adaptation(image, ChromaticAdaptationMethod::Xyz, Whitepoint::D50)
+        .is_err());
+
+    let luma = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::Luma), 32, 32).unwrap()
+    };
+    let image = commands.input(luma).unwrap();
+    let adapted = commands
+        .chromatic_adaptation(
+            image,
+            ChromaticAdaptationMethod::BradfordVonKries,
+            Whitepoint::D50,
+        )
+        .unwrap();
+
+    // The whitepoints are normalized to the same luminance.
+    let Op::Unary {
+        op: UnaryOp::AffineColor { matrix, .. },
+        ..
+    } = &commands.ops[adapted.0]
+    else {
+        panic!("Not a luminance scale");
+    };
+    assert!((matrix.into_inner()[0] - 1.0).abs() < 1e-5, "{matrix:?}");
+
+    let scalars = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap();
+    let image = commands.input(scalars).unwrap();
+    assert!(commands
+        .chromatic_adaptation(image, ChromaticAdaptationMethod::Xyz, Whitepoint::D50)
+        .is_err());
 }
 
-impl GenericDescriptor {
-    /// Query if this describes a monomorphic descriptor.
-    ///
-    /// At the moment this means a fully constrained descriptor where both size and chroma are
-    /// defined. It's a bit odd that this would be an overlapping property with having been
-    /// constructed from an actually concrete defined descriptor. If we had a non-deterministic
-    /// layout algorithm (i.e. multiple permissible layouts for one combination of size/chroma)
-    /// then this might inadvertently throw away some of this information. But for now this
-    /// information is compile time only, the actual dependence of operational semantics on layout
-    /// information is evaluated at runtime. (FIXME: I will have regretted writing this).
-    pub fn as_concrete(&self) -> Option<Descriptor> {
-        let Generic::Concrete((w, h)) = self.size else {
-            return None;
-        };
+#[test]
+fn color_convert_linear_records_transfer() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-        let Generic::Concrete((texel, color)) = &self.chroma else {
-            return None;
+    for bits in [SampleBits::Float16x4, SampleBits::Float32x4] {
+        let texel = Texel {
+            block: Block::Pixel,
+            bits,
+            parts: SampleParts::RgbA,
         };
 
-        Descriptor::with_texel(texel.clone(), w, h).map(|mut desc| {
-            desc.color = color.clone();
-            desc
-        })
+        let image = commands.input(desc.clone()).unwrap();
+        let linear = commands
+            .color_convert_linear(image, Primaries::Bt2020, Whitepoint::D65, texel.clone())
+            .unwrap();
+
+        let result = commands.describe_reg(linear).as_texture().unwrap();
+        let result = result.as_concrete().unwrap();
+        assert_eq!(result.texel, texel);
+        assert!(matches!(
+            result.color,
+            Color::Rgb {
+                primary: Primaries::Bt2020,
+                transfer: Transfer::Linear,
+                ..
+            }
+        ));
     }
+}
 
-    /// FIXME: fallible. If we change the texel from something small to something very large we can
-    /// exceed the allocation limits that are necessary to express the layout.
-    pub fn with_chroma(&self, texel: Texel, color: Color) -> Self {
-        GenericDescriptor {
-            chroma: Generic::Concrete((texel, color)),
-            ..self.clone()
-        }
+#[test]
+fn gamut_mapping_through_linear_light() {
+    let mut commands = CommandBuffer::default();
+    let mut wide = Color::SRGB;
+    if let Color::Rgb { primary, .. } = &mut wide {
+        *primary = Primaries::Bt2020;
     }
 
-    pub fn monomorphize(&self, decl: &[Descriptor]) -> Descriptor {
-        let (w, h) = match &self.size {
-            Generic::Concrete(descriptor) => descriptor.clone(),
-            Generic::Generic(idx) => decl[idx.0].size(),
-        };
+    let desc = Descriptor {
+        color: wide,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
 
-        let (texel, color) = match &self.chroma {
-            Generic::Concrete(tuple) => tuple.clone(),
-            Generic::Generic(idx) => {
-                let from = &decl[idx.0];
-                (from.texel.clone(), from.color.clone())
-            }
-        };
+    let image = commands.input(desc.clone()).unwrap();
+    let clipped = commands
+        .color_convert(image, Color::SRGB, desc.texel.clone())
+        .unwrap();
+    assert!(matches!(
+        &commands.ops[clipped.0],
+        Op::Unary {
+            op: UnaryOp::ColorConvert(_),
+            ..
+        }
+    ));
 
-        Descriptor::with_texel(texel, w, h)
-            .map(|mut desc| {
-                desc.color = color;
-                desc
-            })
-            .expect("changing texel and color to something that does not fit memory")
-    }
+    let options = ColorConvertOptions {
+        gamut: GamutMapping::ChromaCompress,
+        ..ColorConvertOptions::default()
+    };
 
-    /// Apply an outer variable definition, replacing generics by at least as concrete terms.
-    ///
-    /// Does not verify any bounds of the rewrites! Which we'll need to do if we had associated
-    /// constants and the rewrite was looking into paths and impls. Consider a trait (similar to
-    /// the Rust type system) / type family such as `LinearizedColor` that associates the linear
-    /// optical colorspace to an arbitrary electrical color encoding. Then we might have the
-    /// signature written in pseudo-code:
-    ///
-    /// ```text
-    ///     function <C: LinearizedColor>(arg0: {C; 256×256}, arg1: {C::Linear; 256×256})
-    /// ```
-    ///
-    /// Now if we rewrite with [C = sRGB] then we want the concrete [C::Linear=CIE-RGB-Wp-D70]
-    /// correspondence. But if we tried [C = CYMK] we have nonsense. Here we allow this function to
-    /// panic, a check must happen earlier.
-    pub fn rewrite(&self, decl: &[GenericDescriptor]) -> Self {
-        GenericDescriptor {
-            size: match &self.size {
-                &Generic::Concrete(size) => Generic::Concrete(size),
-                Generic::Generic(idx) => decl[idx.0].size.clone(),
-            },
-            chroma: match &self.chroma {
-                Generic::Concrete(chroma) => Generic::Concrete(chroma.clone()),
-                Generic::Generic(idx) => decl[idx.0].chroma.clone(),
-            },
+    let mapped = commands
+        .color_convert_with(image, Color::SRGB, desc.texel.clone(), options)
+        .unwrap();
+
+    let Op::Unary {
+        src,
+        op: UnaryOp::GamutMap { mapping, .. },
+        desc: mapped_desc,
+    } = &commands.ops[mapped.0]
+    else {
+        panic!("Not a gamut mapping");
+    };
+
+    assert_eq!(*mapping, GamutMapping::ChromaCompress);
+    let mapped_desc = mapped_desc.as_concrete().unwrap();
+    assert_eq!(mapped_desc.texel, desc.texel);
+
+    let linear = commands.describe_reg(*src).as_texture().unwrap();
+    let linear = linear.as_concrete().unwrap();
+    assert_eq!(linear.texel.bits, SampleBits::Float32x4);
+    assert!(matches!(
+        linear.color,
+        Color::Rgb {
+            transfer: Transfer::Linear,
+            primary: Primaries::Bt709,
+            ..
+        }
+    ));
+}
+
+#[test]
+fn cielab_is_stored_as_scalars() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+    let image = commands.input(desc.clone()).unwrap();
+
+    let lab_texel = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::Float32x4,
+        parts: SampleParts::LabA,
+    };
+
+    let lab = commands
+        .color_convert_cielab(image, Whitepoint::D50, lab_texel.clone())
+        .unwrap();
+    let lab_desc = commands.describe_reg(lab).as_texture().unwrap();
+    let lab_desc = lab_desc.as_concrete().unwrap();
+    assert_eq!(lab_desc.color, CommandBuffer::CIELAB);
+
+    assert!(matches!(
+        &commands.ops[lab.0],
+        Op::Unary {
+            op: UnaryOp::ColorConvert(ColorConversion::XyzToLab {
+                whitepoint: Whitepoint::D50,
+                ..
+            }),
+            ..
+        }
+    ));
+
+    let rgb = commands
+        .color_convert_from_cielab(lab, Whitepoint::D50, desc.color.clone(), desc.texel.clone())
+        .unwrap();
+    assert!(matches!(
+        &commands.ops[rgb.0],
+        Op::Unary {
+            op: UnaryOp::ColorConvert(ColorConversion::LabToXyz {
+                whitepoint: Whitepoint::D50,
+                ..
+            }),
+            ..
+        }
+    ));
+
+    // Neither are the coordinates stored in an RGB texel, nor is RGB read as CIELAB.
+    assert!(commands
+        .color_convert_cielab(image, Whitepoint::D50, desc.texel.clone())
+        .is_err());
+    assert!(commands
+        .color_convert_from_cielab(image, Whitepoint::D50, desc.color.clone(), desc.texel)
+        .is_err());
+}
+
+#[test]
+fn non_rgb_colors_compose_through_linear_rgb() {
+    let colors = [
+        Color::Oklab,
+        Color::SrLab2 {
+            whitepoint: Whitepoint::D65,
+        },
+        Color::SrLab2 {
+            whitepoint: Whitepoint::D50,
+        },
+    ];
+
+    let texel = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::Float32x4,
+        parts: SampleParts::LabA,
+    };
+
+    for source in &colors {
+        for target in &colors {
+            let mut commands = CommandBuffer::default();
+            let desc = Descriptor {
+                color: source.clone(),
+                ..Descriptor::with_texel(texel.clone(), 32, 32).unwrap()
+            };
+
+            let image = commands.input(desc).unwrap();
+            let converted = commands
+                .color_convert(image, target.clone(), texel.clone())
+                .unwrap_or_else(|err| panic!("{source:?} to {target:?}: {err:?}"));
+
+            assert_decoded_into_linear_rgb(&commands, converted);
         }
     }
 
-    pub fn size(&self) -> Generic<(u32, u32)> {
-        self.size.clone()
+    // CIELAB composes with the others in the same way, with its own commands.
+    for color in &colors {
+        let mut commands = CommandBuffer::default();
+        let desc = Descriptor {
+            color: color.clone(),
+            ..Descriptor::with_texel(texel.clone(), 32, 32).unwrap()
+        };
+
+        let image = commands.input(desc).unwrap();
+        let lab = commands
+            .color_convert_cielab(image, Whitepoint::D50, texel.clone())
+            .unwrap_or_else(|err| panic!("{color:?} to CIELAB: {err:?}"));
+        let back = commands
+            .color_convert_from_cielab(lab, Whitepoint::D50, color.clone(), texel.clone())
+            .unwrap_or_else(|err| panic!("CIELAB to {color:?}: {err:?}"));
+
+        assert_decoded_into_linear_rgb(&commands, lab);
+        assert_decoded_into_linear_rgb(&commands, back);
     }
 
-    pub fn descriptor_chroma(&self) -> Generic<(Texel, Color)> {
-        self.chroma.clone()
+    fn assert_decoded_into_linear_rgb(commands: &CommandBuffer, converted: Register) {
+        let Op::Unary {
+            src,
+            op: UnaryOp::ColorConvert(_),
+            ..
+        } = &commands.ops[converted.0]
+        else {
+            panic!("Not a color conversion");
+        };
+
+        // One decoding pass, into linear RGB, precedes the encoding.
+        let linear = commands.describe_reg(*src).as_texture().unwrap();
+        let linear = linear.as_concrete().unwrap();
+        assert_eq!(linear.texel.bits, SampleBits::Float32x4);
+        assert!(matches!(
+            linear.color,
+            Color::Rgb {
+                transfer: Transfer::Linear,
+                ..
+            }
+        ));
     }
 }
 
-impl GenericBuffer {
-    pub fn as_concrete(&self) -> Option<u64> {
-        match self.size {
-            Generic::Concrete(val) => Some(val),
-            Generic::Generic(_) => None,
-        }
-    }
+#[test]
+fn color_conversion_fuses_into_copy() {
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let region = Rectangle::with_width_height(16, 16);
+
+    let count = |commands: &CommandBuffer| {
+        let program = Linker::from_included().compile(commands).unwrap();
+        let draws = program
+            .ops
+            .iter()
+            .filter(|high| matches!(high, High::DrawInto { .. }))
+            .count();
+        (draws, program.functions[0].image_buffers.texture.len())
+    };
+
+    let mut commands = CommandBuffer::default();
+    let image = commands.input(desc.clone()).unwrap();
+    let converted = commands
+        .color_convert(image, Color::BT709_RGB, desc.texel.clone())
+        .unwrap();
+    let cropped = commands.crop(converted, region).unwrap();
+    commands.output(cropped).unwrap();
+
+    // The crop paints the conversion, there is no texture for the converted image.
+    assert_eq!(count(&commands), (1, 3));
+
+    let mut commands = CommandBuffer::default();
+    let image = commands.input(desc.clone()).unwrap();
+    let converted = commands
+        .color_convert(image, Color::BT709_RGB, desc.texel.clone())
+        .unwrap();
+    let cropped = commands.crop(converted, region).unwrap();
+    commands.output(cropped).unwrap();
+    commands.output(converted).unwrap();
+
+    // With another use the converted image is kept.
+    assert_eq!(count(&commands), (2, 5));
+}
+
+#[test]
+fn dither_only_integer_results() {
+    let mut commands = CommandBuffer::default();
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
+    };
+
+    let options = ColorConvertOptions {
+        dither: true,
+        ..ColorConvertOptions::default()
+    };
 
-    pub fn monomorphize(&self, decl: &[Descriptor]) -> u64 {
-        match self.size {
-            Generic::Concrete(val) => val,
-            Generic::Generic(var) => decl[var.0].to_canvas().u64_len(),
-        }
-    }
+    let float = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::Float32x4,
+        parts: SampleParts::RgbA,
+    };
+
+    let image = commands.input(desc.clone()).unwrap();
+    let plain = commands
+        .color_convert(image, Color::SRGB, desc.texel.clone())
+        .unwrap();
+    let dithered = commands
+        .color_convert_with(image, Color::SRGB, desc.texel.clone(), options.clone())
+        .unwrap();
+    let floating = commands
+        .color_convert_with(image, Color::SRGB, float, options)
+        .unwrap();
+
+    assert!(!commands.dithered.contains(&plain));
+    assert!(commands.dithered.contains(&dithered));
+    assert!(!commands.dithered.contains(&floating));
 }
 
-impl From<Descriptor> for GenericDescriptor {
-    fn from(desc: Descriptor) -> Self {
-        let size = desc.size();
-        let chroma = (desc.texel.clone(), desc.color.clone());
+#[test]
+fn command_error_kinds() {
+    let rgba = Texel::new_u8(SampleParts::RgbA);
+    let small = Descriptor::with_texel(rgba.clone(), 2, 2).unwrap();
+    let large = Descriptor::with_texel(rgba.clone(), 4, 4).unwrap();
 
-        GenericDescriptor {
-            size: Generic::Concrete(size),
-            chroma: Generic::Concrete(chroma),
-        }
-    }
+    let mut commands = CommandBuffer::default();
+    let lhs = commands.input(small.clone()).unwrap();
+    let rhs = commands.input(large.clone()).unwrap();
+    let buffer = commands.buffer_init(&[0; 4]);
+
+    let missing = Register(commands.ops.len() + 1);
+    assert!(matches!(
+        commands.crop(missing, Rectangle::with_width_height(1, 1)).unwrap_err().kind(),
+        CommandErrorKind::BadRegister(reg) if *reg == missing,
+    ));
+
+    assert!(matches!(
+        commands.crop(buffer, Rectangle::with_width_height(1, 1)).unwrap_err().kind(),
+        CommandErrorKind::NotATexture(reg) if *reg == buffer,
+    ));
+
+    let inscribed = commands
+        .inscribe(lhs, Rectangle::with_width_height(2, 2), rhs)
+        .unwrap_err();
+    assert!(inscribed.is_type_err());
+    assert!(matches!(
+        inscribed.kind(),
+        CommandErrorKind::SizeMismatch { lhs: below, rhs: above } if (*below, *above) == (lhs, rhs),
+    ));
+
+    let scalars = Descriptor {
+        color: Color::Scalars {
+            transfer: Transfer::Linear,
+        },
+        ..small.clone()
+    };
+    let scalars = commands.input(scalars).unwrap();
+    assert!(matches!(
+        commands.color_convert(scalars, Color::Oklab, rgba.clone()).unwrap_err().kind(),
+        CommandErrorKind::UnsupportedConversion { src, .. } if *src == scalars,
+    ));
+
+    // Invocations check their arity before the arguments.
+    let signature = {
+        let mut callee = CommandBuffer::default();
+        let input = callee.input(small).unwrap();
+        callee.output(input).unwrap();
+        callee.computed_signature()
+    };
+
+    let callee = commands.function(signature).unwrap();
+    let invoked = commands.invoke(
+        callee,
+        InvocationArguments {
+            generics: &[],
+            arguments: &[lhs, rhs],
+        },
+    );
+    assert!(matches!(
+        invoked.unwrap_err().kind(),
+        CommandErrorKind::ArityMismatch {
+            expected: 1,
+            actual: 2
+        },
+    ));
+
+    let invoked = commands.invoke(
+        callee,
+        InvocationArguments {
+            generics: &[],
+            arguments: &[rhs],
+        },
+    );
+    assert!(matches!(
+        invoked.unwrap_err().kind(),
+        CommandErrorKind::ArgumentMismatch { argument, .. } if *argument == rhs,
+    ));
 }
 
-impl<'lt> RegisterDescription<'lt> {
-    pub fn as_texture(&self) -> Result<&'lt GenericDescriptor, CommandError> {
-        match self {
-            RegisterDescription::Texture(tex) => Ok(tex),
-            _ => Err(CommandError::BAD_REGISTER),
-        }
-    }
+#[test]
+fn error_messages() {
+    let mut commands = CommandBuffer::default();
+    let missing = Register(commands.ops.len());
+    let err = commands.crop(missing, Rectangle::with_width_height(1, 1));
+    assert_eq!(
+        err.unwrap_err().to_string(),
+        format!("Register {} does not hold a value", missing.0)
+    );
+
+    let var = commands.generic(GenericDeclaration::default()).unwrap();
+    let input = commands.input_generic(var).unwrap();
+    commands.output(input).unwrap();
+
+    // Compiling without the generic type fails to link.
+    let Err(err) = Linker::from_included().compile(&commands) else {
+        panic!("Compiled without the generic type");
+    };
 
-    pub fn as_buffer(&self) -> Result<&'lt GenericBuffer, CommandError> {
-        match self {
-            RegisterDescription::Buffer(tex) => Ok(tex),
-            _ => Err(CommandError::BAD_REGISTER),
+    assert!(matches!(
+        err,
+        CompileError::LinkMismatch {
+            expected: 1,
+            actual: 0,
+            ..
         }
-    }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "Expected 1 generic types for linking, got 0"
+    );
 }
 
-impl TyVarBounds {
-    pub fn contains_bounds(&self, actual: &TyVarBounds) -> bool {
-        self.is_empty() && actual.is_empty()
-    }
+#[test]
+fn register_introspection() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
 
-    fn is_empty(&self) -> bool {
-        // FIXME: if we collect the list.
-        true
-    }
+    let mut commands = CommandBuffer::default();
+    let lhs = commands.input(desc.clone()).unwrap();
+    let rhs = commands.input(desc.clone()).unwrap();
+    let cropped = commands
+        .crop(lhs, Rectangle::with_width_height(2, 2))
+        .unwrap();
+    let result = commands
+        .inscribe(rhs, Rectangle::with_width_height(4, 4), cropped)
+        .unwrap();
+    let (output, _) = commands.output(result).unwrap();
+
+    let inputs: Vec<_> = commands.inputs().map(|(reg, _)| reg).collect();
+    assert_eq!(inputs, [lhs, rhs]);
+
+    let outputs: Vec<_> = commands.outputs().collect();
+    assert!(matches!(
+        outputs[..],
+        [(reg, RegisterDescription::Texture(desc))]
+            if reg == output && desc.as_concrete().map(|desc| desc.size()) == Some((4, 4)),
+    ));
+
+    assert_eq!(commands.iter_registers().count(), 5);
+    assert!(matches!(
+        commands.describe(output),
+        RegisterDescription::None
+    ));
+    assert!(matches!(
+        commands.describe(cropped),
+        RegisterDescription::Texture(_)
+    ));
+
+    assert_eq!(
+        commands.op_kind(cropped),
+        Some(OpKind::Unary {
+            name: "crop",
+            src: lhs
+        })
+    );
+    assert_eq!(
+        commands.op_kind(result),
+        Some(OpKind::Binary {
+            name: "inscribe",
+            lhs: rhs,
+            rhs: cropped
+        })
+    );
+    assert_eq!(
+        commands.op_kind(output),
+        Some(OpKind::Output { src: result })
+    );
+    assert_eq!(commands.op_kind(Register(5)), None);
 }
 
-impl ColorConversion {
-    pub(crate) fn to_shader(&self, std: &ShadersStd) -> FragmentShaderInvocation {
-        match self {
-            ColorConversion::Xyz {
-                to_xyz_matrix,
-                from_xyz_matrix,
-            } => {
-                let from = from_xyz_matrix.inv();
-                let matrix = to_xyz_matrix.multiply_right(from.into()).into();
+#[test]
+fn dump_registers() {
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap()
+    };
 
-                FragmentShaderInvocation::LinearColorMatrix(shaders::LinearColorTransform {
-                    matrix,
-                    spirv: std.linear_color_transform.clone(),
-                })
-            }
-            ColorConversion::XyzToOklab { to_xyz_matrix } => {
-                FragmentShaderInvocation::Oklab(shaders::oklab::Shader {
-                    xyz_transform: *to_xyz_matrix,
-                    direction: shaders::oklab::Coding::Encode {
-                        spirv: std.oklab_encode.clone(),
-                    },
-                })
-            }
-            ColorConversion::OklabToXyz { from_xyz_matrix } => {
-                let from_xyz_matrix = from_xyz_matrix.inv();
-                FragmentShaderInvocation::Oklab(shaders::oklab::Shader {
-                    xyz_transform: from_xyz_matrix,
-                    direction: shaders::oklab::Coding::Decode {
-                        spirv: std.oklab_decode.clone(),
-                    },
-                })
-            }
-            ColorConversion::XyzToSrLab2 {
-                to_xyz_matrix,
-                whitepoint,
-            } => FragmentShaderInvocation::SrLab2(shaders::srlab2::Shader {
-                matrix: *to_xyz_matrix,
-                whitepoint: *whitepoint,
-                direction: shaders::srlab2::Coding::Encode {
-                    spirv: std.srlab2_encode.clone(),
-                },
-            }),
-            ColorConversion::SrLab2ToXyz {
-                from_xyz_matrix,
-                whitepoint,
-            } => {
-                let from_xyz_matrix = from_xyz_matrix.inv();
-                FragmentShaderInvocation::SrLab2(shaders::srlab2::Shader {
-                    matrix: from_xyz_matrix,
-                    whitepoint: *whitepoint,
-                    direction: shaders::srlab2::Coding::Decode {
-                        spirv: std.srlab2_decode.clone(),
-                    },
-                })
-            }
-        }
-    }
+    let mut commands = CommandBuffer::default();
+    let lhs = commands.input(desc.clone()).unwrap();
+    let rhs = commands.input(desc.clone()).unwrap();
+    let cropped = commands
+        .with_knob()
+        .crop(lhs, Rectangle::with_width_height(2, 2))
+        .unwrap()
+        .register();
+    let result = commands
+        .inscribe(rhs, Rectangle::with_width_height(4, 4), cropped)
+        .unwrap();
+    commands.output(result).unwrap();
+
+    assert_eq!(
+        commands.dump(),
+        "\
+%0 : 4x4 sRGB/UInt8x4-RgbA = input
+%1 : 4x4 sRGB/UInt8x4-RgbA = input
+%2 : 4x4 sRGB/UInt8x4-RgbA = crop %0 [knob]
+%3 : 4x4 sRGB/UInt8x4-RgbA = inscribe %1, %2
+%4 = output %3
+"
+    );
+    assert_eq!(commands.dump(), commands.to_string());
 }
 
-impl ChromaticAdaptation {
-    pub(crate) fn to_matrix(&self) -> Result<[f32; 9], CompileError> {
-        use palette::{
-            chromatic_adaptation::{Method, TransformMatrix},
-            white_point as wp,
-        };
+#[test]
+fn dot_graph() {
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap()
+    };
 
-        // FIXME: when you adjust the value-to-type translation, also adjust it within `method`.
-        macro_rules! translate_matrix {
-            ($source:expr, $target:expr, $($lhs:ident => $lhsty:ty)|*) => {
-                $(
-                    translate_matrix!(
-                        @$source, $target, $lhs => $lhsty :
-                        A => wp::A | B => wp::B | C => wp::C
-                        | D50 => wp::D50 | D55 => wp::D55 | D65 => wp::D65
-                        | D75 => wp::D75 | E => wp::E | F2 => wp::F2
-                        | F7 => wp::F7 | F11 => wp::F11
-                    );
-                )*
-            };
-            (@$source:expr, $target:expr, $lhs:ident => $lhsty:ty : $($rhs:ident => $ty:ty)|*) => {
-                $(
-                    if let (Whitepoint::$lhs, Whitepoint::$rhs) = ($source, $target) {
-                        return Ok((|method| {
-                            let lhswp = <$lhsty as wp::WhitePoint<f32>>::get_xyz();
-                            let rhswp = <$ty as wp::WhitePoint<f32>>::get_xyz();
-                            <Method as TransformMatrix<f32>>::generate_transform_matrix(method, lhswp, rhswp)
-                        })
-                                  as fn(&Method) -> [f32;9]);
-                    }
-                )*
-            };
-        }
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(desc).unwrap();
+    let cropped = commands
+        .with_knob()
+        .crop(input, Rectangle::with_width_height(2, 2))
+        .unwrap()
+        .register();
+    commands.output(cropped).unwrap();
+
+    let dot = commands.dot();
+    assert!(dot.starts_with("digraph commands {"));
+    assert!(
+        dot.contains("register_0 [label=\"%0 input\\n4x4 sRGB/UInt8x4-RgbA\", shape=invhouse];")
+    );
+    assert!(dot.contains("shape=box, style=bold];"));
+    assert!(dot.contains("register_2 [label=\"%2 output\", shape=house];"));
+    assert!(dot.contains("register_0 -> register_1;"));
+    assert!(dot.contains("register_1 -> register_2;"));
+}
 
-        // FIXME: when you adjust the value-to-type translation, also adjust it within
-        // `translate_matrix!`
-        let method = (|| {
-            translate_matrix! {
-                self.source, self.target,
-                A => wp::A | B => wp::B | C => wp::C
-                | D50 => wp::D50 | D55 => wp::D55 | D65 => wp::D65
-                | D75 => wp::D75 | E => wp::E | F2 => wp::F2
-                | F7 => wp::F7 | F11 => wp::F11
-            };
+#[test]
+fn prune_dangling_branches() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
 
-            Err(CompileError::NotYetImplemented)
-        })()?;
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(desc.clone()).unwrap();
+    let dangling = commands
+        .crop(input, Rectangle::with_width_height(2, 2))
+        .unwrap();
+    let _dangling = commands
+        .inscribe(input, Rectangle::with_width_height(4, 4), dangling)
+        .unwrap();
+    let knobbed = commands
+        .with_knob()
+        .crop(input, Rectangle::with_width_height(2, 2))
+        .unwrap()
+        .register();
+    let (output, _) = commands.output(knobbed).unwrap();
+
+    let (pruned, map) = commands.prune();
+    assert_eq!(pruned.ops.len(), 3);
+    assert_eq!(map.get(input), Some(Register(0)));
+    assert_eq!(map.get(dangling), None);
+    assert_eq!(map.get(knobbed), Some(Register(1)));
+    assert_eq!(map.get(output), Some(Register(2)));
+    assert!(pruned.knobs.contains_key(&Register(1)));
 
-        let matrices = method(match self.method {
-            // Bradford's original method that does slight blue non-linearity is not yet supported.
-            // Please implement the paper if you feel compelled to.
-            ChromaticAdaptationMethod::BradfordNonLinear => {
-                return Err(CompileError::NotYetImplemented)
-            }
-            ChromaticAdaptationMethod::BradfordVonKries => &Method::Bradford,
-            ChromaticAdaptationMethod::VonKries => &Method::VonKries,
-            ChromaticAdaptationMethod::Xyz => &Method::XyzScaling,
-        });
+    let linker = Linker::from_included();
+    let full = linker.compile(&commands).unwrap();
+    let pruned = linker.compile(&pruned).unwrap();
+    assert!(pruned.ops.len() < full.ops.len());
 
-        Ok(matrices)
-    }
+    let registers = |program: &Program| program.functions[0].image_buffers.by_register.len();
+    assert!(registers(&pruned) < registers(&full));
 }
 
-#[rustfmt::skip]
-impl DerivativeMethod {
-    fn to_shader(&self, direction: Direction, std: &ShadersStd) -> Result<FragmentShaderInvocation, CompileError> {
-        use DerivativeMethod::*;
-        use shaders::box3;
-
-        let from_kernel_3x3 = |matrix| {
-            box3::Shader {
-                matrix,
-                spirv: std.box3.clone(),
-            }
-        };
+#[test]
+fn deduplicate_ops() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let rect = Rectangle::with_width_height(2, 2);
 
-        match self {
-            Prewitt => {
-                let matrix = RowMatrix::with_outer_product(
-                    [1./3., 1./3., 1./3.],
-                    [0.5, 0.0, -0.5],
-                );
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(desc.clone()).unwrap();
+    let first = commands.crop(input, rect).unwrap();
+    let second = commands.crop(input, rect).unwrap();
+    let full = Rectangle::with_width_height(4, 4);
+    let lhs = commands.inscribe(input, full, first).unwrap();
+    let rhs = commands.inscribe(input, full, second).unwrap();
+    let knob_first = commands.with_knob().crop(input, rect).unwrap().register();
+    let knob_second = commands.with_knob().crop(input, rect).unwrap().register();
+
+    for register in [lhs, rhs, knob_first, knob_second] {
+        commands.output(register).unwrap();
+    }
 
-                let shader = from_kernel_3x3(direction.adjust_vertical_box(matrix));
-                Ok(shaders::FragmentShaderInvocation::Box3(shader))
-            }
-            Sobel => {
-                let matrix = RowMatrix::with_outer_product(
-                    [1./4., 1./2., 1./4.],
-                    [0.5, 0.0, -0.5],
-                );
+    let (merged, map) = commands.deduplicate();
+    assert_eq!(map.get(first), map.get(second));
+    assert_eq!(map.get(lhs), map.get(rhs));
+    assert_ne!(map.get(knob_first), map.get(knob_second));
+    assert!(map.get(knob_first).is_some() && map.get(knob_second).is_some());
 
-                let shader = from_kernel_3x3(direction.adjust_vertical_box(matrix));
-                Ok(shaders::FragmentShaderInvocation::Box3(shader))
-            }
-            Scharr3 => {
-                let matrix = RowMatrix::with_outer_product(
-                    [46.84/256., 162.32/256., 46.84/256.],
-                    [0.5, 0.0, -0.5],
-                );
+    // The input, a crop, an inscribe, two knobbed crops, and four outputs.
+    assert_eq!(merged.ops.len(), 9);
+    assert_eq!(merged.knobs.len(), 2);
 
-                let shader = from_kernel_3x3(direction.adjust_vertical_box(matrix));
-                Ok(shaders::FragmentShaderInvocation::Box3(shader))
-            }
-            Scharr3To4Bit => {
-                let matrix = RowMatrix::with_outer_product(
-                    [3./16., 10./16., 3./16.],
-                    [0.5, 0.0, -0.5],
-                );
+    let Op::Output { src } = merged.ops[merged.ops.len() - 3] else {
+        panic!("Not an output");
+    };
+    assert_eq!(Some(src), map.get(lhs));
+}
 
-                let shader = from_kernel_3x3(direction.adjust_vertical_box(matrix));
-                Ok(shaders::FragmentShaderInvocation::Box3(shader))
-            }
-            Scharr3To8Bit => {
-                let matrix = RowMatrix::with_outer_product(
-                    [47./256., 162./256., 47./256.],
-                    [0.5, 0.0, -0.5],
-                );
+#[test]
+fn enumerate_program_knobs() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
 
-                let shader = from_kernel_3x3(direction.adjust_vertical_box(matrix));
-                Ok(shaders::FragmentShaderInvocation::Box3(shader))
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(desc.clone()).unwrap();
+    let solid = commands
+        .with_knob()
+        .labeled("bg-color")
+        .solid_rgba(desc.clone(), [0.0, 0.0, 0.0, 1.0])
+        .unwrap();
+    let cropped = commands
+        .with_knob()
+        .crop(input, Rectangle::with_width_height(2, 2))
+        .unwrap();
+    // Buffers are output as rows of 256 bytes.
+    let buffer = commands.with_knob().buffer_init(&[0; 256]).unwrap();
+    let unknobbed = commands.solid_rgba(desc.clone(), [1.0; 4]).unwrap();
+
+    for register in [solid.register(), cropped.register(), unknobbed] {
+        commands.output(register).unwrap();
+    }
+    commands.output_buffer(buffer.register()).unwrap();
+
+    let program = Linker::from_included().compile(&commands).unwrap();
+    let knobs: Vec<_> = program.knobs().collect();
+    assert_eq!(knobs.len(), 3);
+
+    for (info, register, op, len, label) in [
+        (
+            &knobs[0],
+            solid.register(),
+            KnobOp::SolidRgba,
+            16,
+            Some("bg-color"),
+        ),
+        (&knobs[1], cropped.register(), KnobOp::Crop, 64, None),
+        (&knobs[2], buffer.register(), KnobOp::BufferInit, 256, None),
+    ] {
+        assert_eq!(
+            info.register,
+            RegisterKnob {
+                link_idx: 0,
+                register
             }
-            // FIXME: implement these.
-            // When you do add them to tests/blend.rs
-            | Roberts
-            | Scharr4
-            | Scharr5
-            | Scharr5Tab => Err(CompileError::NotYetImplemented)
-        }
+        );
+        assert_eq!(info.knob, program.knobs[&info.register][0].1);
+        assert!(info.generics.is_empty());
+        assert_eq!(info.op, op);
+        assert_eq!(info.len, Some(len));
+        assert_eq!(info.label.as_deref(), label);
     }
 }
 
-impl Direction {
-    fn adjust_vertical_box(self, mat: RowMatrix) -> RowMatrix {
-        match self {
-            Direction::Width => mat,
-            Direction::Height => mat.transpose(),
+#[test]
+fn linear_color_transforms_fold() {
+    let linear = |primary| {
+        let mut color = Color::BT709_RGB;
+        if let Color::Rgb {
+            primary: p,
+            transfer,
+            ..
+        } = &mut color
+        {
+            *p = primary;
+            *transfer = Transfer::Linear;
         }
-    }
-}
+        color
+    };
 
-#[rustfmt::skip]
-impl Affine {
-    /// Create affine parameters with identity transformation.
-    pub fn new(sampling: AffineSample) -> Self {
-        Affine {
-            transformation: [
-                1.0, 0., 0.,
-                0., 1.0, 0.,
-                0., 0., 1.0,
-            ],
-            sampling,
-        }
-    }
+    let float = Texel {
+        block: Block::Pixel,
+        bits: SampleBits::Float32x4,
+        parts: SampleParts::RgbA,
+    };
 
-    /// After the transformation, also scale everything.
-    ///
-    /// This corresponds to a left-side multiplication of the transformation matrix.
-    pub fn scale(self, x: f32, y: f32) -> Self {
-        let post = RowMatrix::diag(x, y, 1.0)
-            .multiply_right(RowMatrix::new(self.transformation).into());
-        let transformation = RowMatrix::from(post).into_inner();
+    let desc = Descriptor {
+        color: linear(Primaries::Bt709),
+        ..Descriptor::with_texel(float.clone(), 32, 32).unwrap()
+    };
 
-        Affine {
-            transformation,
-            ..self
+    let linear_draws = |commands: &CommandBuffer| {
+        let program = Linker::from_included().compile(commands).unwrap();
+        let matrices: Vec<_> = program
+            .ops
+            .iter()
+            .filter_map(|high| match high {
+                High::DrawInto {
+                    fn_:
+                        Initializer::PaintFullScreen {
+                            shader:
+                                ParameterizedFragment {
+                                    invocation: FragmentShaderInvocation::LinearColorMatrix(shader),
+                                    ..
+                                },
+                        },
+                    ..
+                } => Some((shader.matrix, shader.offset)),
+                _ => None,
+            })
+            .collect();
+        (matrices, program.functions[0].image_buffers.texture.len())
+    };
+
+    let chain = |commands: &mut CommandBuffer, knob: bool| {
+        let image = commands.input(desc.clone()).unwrap();
+        let wide = commands
+            .color_convert(image, linear(Primaries::Bt2020), float.clone())
+            .unwrap();
+        let adapted = if knob {
+            commands
+                .with_knob()
+                .chromatic_adaptation(wide, ChromaticAdaptationMethod::VonKries, Whitepoint::D50)
+                .map(|knob| knob.register())
+        } else {
+            commands.chromatic_adaptation(
+                wide,
+                ChromaticAdaptationMethod::VonKries,
+                Whitepoint::D50,
+            )
         }
-    }
+        .unwrap();
+        let back = commands
+            .color_convert(adapted, linear(Primaries::Bt709), float.clone())
+            .unwrap();
+        commands.output(back).unwrap();
+    };
 
-    /// After the transformation, rotate everything clockwise.
-    ///
-    /// This corresponds to a left-side multiplication of the transformation matrix.
-    pub fn rotate(self, rad: f32) -> Self {
-        let post = RowMatrix::new([
-            rad.cos(), rad.sin(), 0.,
-            -rad.sin(), rad.cos(), 0.,
-            0., 0., 1.,
-        ]);
+    let mut commands = CommandBuffer::default();
+    chain(&mut commands, true);
+    let (separate, separate_textures) = linear_draws(&commands);
+    assert_eq!(separate.len(), 3);
+
+    let mut commands = CommandBuffer::default();
+    chain(&mut commands, false);
+    let (folded, folded_textures) = linear_draws(&commands);
+    assert_eq!(folded.len(), 1);
+    assert!(folded_textures < separate_textures);
+
+    // The folded matrix transforms colors as the separate passes do.
+    let color = [0.25, 0.5, 0.75];
+    let expected = separate
+        .iter()
+        .fold(color, |color, (matrix, _)| matrix.mul_vec(color));
+    let actual = folded[0].0.mul_vec(color);
+
+    for (expected, actual) in expected.iter().zip(actual) {
+        assert!((expected - actual).abs() < 1e-5, "{expected} != {actual}");
+    }
+}
 
-        let post = post.multiply_right(RowMatrix::new(self.transformation).into());
-        let transformation = RowMatrix::from(post).into_inner();
+#[test]
+fn crop_fuses_into_inscribe() {
+    let small = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let large = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 8, 8).unwrap();
+    // The placement of an inscribe is the full rectangle of the image above.
+    let placement = Rectangle::with_width_height(4, 4);
+
+    let compile = |commands: &CommandBuffer| {
+        let program = Linker::from_included().compile(commands).unwrap();
+        let selections: Vec<_> = program
+            .ops
+            .iter()
+            .filter_map(|high| match high {
+                High::DrawInto {
+                    fn_: Initializer::PaintToSelection { selection, .. },
+                    ..
+                } => Some(*selection),
+                _ => None,
+            })
+            .collect();
+        (selections, program.functions[0].image_buffers.texture.len())
+    };
 
-        Affine {
-            transformation,
-            ..self
+    let build = |region: Rectangle, reuse: bool| {
+        let mut commands = CommandBuffer::default();
+        let above = commands.input(small.clone()).unwrap();
+        let below = commands.input(large.clone()).unwrap();
+        let cropped = commands.crop(above, region).unwrap();
+        let result = commands.inscribe(below, placement, cropped).unwrap();
+        commands.output(result).unwrap();
+        if reuse {
+            commands.output(cropped).unwrap();
         }
-    }
+        commands
+    };
 
-    /// After the transformation, shift by an x and y offset.
-    ///
-    /// This corresponds to a left-side multiplication of the transformation matrix.
-    pub fn shift(self, x: f32, y: f32) -> Self {
-        let post = RowMatrix::new([
-            1., 0., x,
-            0., 1., y,
-            0., 0., 1.,
-        ]);
+    // The region extends beyond the source, the inscribe selects the same texels from it.
+    let region = Rectangle {
+        x: 2,
+        y: 1,
+        max_x: 6,
+        max_y: 5,
+    };
 
-        let post = post.multiply_right(RowMatrix::new(self.transformation).into());
-        let transformation = RowMatrix::from(post).into_inner();
+    let (fused, fused_textures) = compile(&build(region, false));
+    assert_eq!(fused, [Rectangle::with_width_height(8, 8), region]);
 
-        Affine {
-            transformation,
-            ..self
-        }
-    }
+    // With another use the crop is kept.
+    let (separate, separate_textures) = compile(&build(region, true));
+    assert_eq!(separate.len(), 3);
+    assert!(separate.contains(&region));
+    assert!(fused_textures < separate_textures);
+
+    // A smaller region leaves part of the cropped texture unpainted, which is not fused.
+    let region = Rectangle::with_width_height(2, 2);
+    let (separate, _) = compile(&build(region, false));
+    assert_eq!(separate.len(), 3);
 }
 
-impl AffineSample {
-    fn as_paint_on_top(self, core: &ShadersCore) -> Result<PaintOnTopKind, CompileError> {
-        match self {
-            AffineSample::Nearest => Ok(core.paint_copy()),
-            _ => Err(CompileError::NotYetImplemented),
-        }
-    }
+#[test]
+fn validate_broken_buffers() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+
+    let mut broken = CommandBuffer::default();
+    let input = broken.input(desc.clone()).unwrap();
+    let cropped = broken
+        .crop(input, Rectangle::with_width_height(4, 4))
+        .unwrap();
+    broken.output(cropped).unwrap();
+    assert!(broken.validate().is_ok());
+
+    // An output of a register which is never defined.
+    broken.ops.push(Op::Output { src: Register(10) });
+    // A knob on an input, which has no parameters.
+    broken.knobs.insert(
+        input,
+        KnobKind::Runtime(knob::KnobRecord {
+            op: KnobOp::Crop,
+            label: None,
+            len: None,
+            align: 16,
+        }),
+    );
+    // A descriptor with a generic which the buffer does not declare.
+    broken.ops.push(Op::Input {
+        desc: GenericDescriptor {
+            size: Generic::Generic(GenericVar(0)),
+            chroma: Generic::Generic(GenericVar(0)),
+        },
+    });
+
+    let errors = broken.validate().unwrap_err();
+    let found: Vec<_> = errors.iter().map(|error| error.register()).collect();
+    assert_eq!(found, [Some(Register(3)), Some(Register(4)), Some(input)]);
+
+    assert!(matches!(
+        errors[0].error().kind(),
+        CommandErrorKind::BadRegister(Register(10)),
+    ));
+    assert!(matches!(
+        errors[1].error().kind(),
+        CommandErrorKind::BadVariable
+    ));
+    assert!(matches!(
+        errors[2].error().kind(),
+        CommandErrorKind::BadRegister(reg) if *reg == input,
+    ));
+
+    assert!(matches!(
+        Linker::from_included().compile(&broken),
+        Err(CompileError::InvalidCommands { link_idx: 0, ref errors }) if errors.len() == 3,
+    ));
 }
 
-impl Rectangle {
-    /// A rectangle at the origin with given width (x) and height (y).
-    pub fn with_width_height(width: u32, height: u32) -> Self {
-        Rectangle {
-            x: 0,
-            y: 0,
-            max_x: width,
-            max_y: height,
-        }
-    }
+#[test]
+fn recursive_links_are_rejected() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+
+    let mut leaf = CommandBuffer::default();
+    let input = leaf.input(desc.clone()).unwrap();
+    leaf.output(input).unwrap();
+    let signature = leaf.computed_signature();
+
+    // Each passes its image through a function of the same signature.
+    let calling = || {
+        let mut commands = CommandBuffer::default();
+        let callee = commands.function(signature.clone()).unwrap();
+        let input = commands.input(desc.clone()).unwrap();
+        let [result] = commands
+            .invoke(
+                callee,
+                InvocationArguments {
+                    generics: &[],
+                    arguments: &[input],
+                },
+            )
+            .unwrap()[..]
+        else {
+            panic!("Mismatched function results count");
+        };
+        commands.output(result).unwrap();
+        commands
+    };
 
-    /// A rectangle describing a complete buffer.
-    pub fn with_layout(buffer: &ByteLayout) -> Self {
-        Self::with_width_height(buffer.width, buffer.height)
-    }
+    let (first, second) = (calling(), calling());
+    let linker = Linker::from_included();
 
-    /// The apparent width.
-    pub fn width(self) -> u32 {
-        self.max_x.saturating_sub(self.x)
-    }
+    assert!(linker.link(&first, &[], &[leaf], &[&[1], &[]]).is_ok());
 
-    /// The apparent height.
-    pub fn height(self) -> u32 {
-        self.max_y.saturating_sub(self.y)
-    }
+    assert!(matches!(
+        linker.link(&first, &[], &[second], &[&[1], &[0]]),
+        Err(CompileError::RecursiveLink { ref cycle }) if *cycle == [1, 0, 1],
+    ));
 
-    /// Return true if this rectangle fully contains `other`.
-    pub fn contains(self, other: Self) -> bool {
-        self.x <= other.x && self.y <= other.y && {
-            // Offsets are surely non-wrapping.
-            let offset_x = other.x - self.x;
-            let offset_y = other.y - self.y;
-            let rel_width = self.width().checked_sub(offset_x);
-            let rel_height = self.height().checked_sub(offset_y);
-            rel_width >= Some(other.width()) && rel_height >= Some(other.height())
-        }
-    }
+    assert!(matches!(
+        linker.link(&first, &[], &[], &[&[0]]),
+        Err(CompileError::RecursiveLink { ref cycle }) if *cycle == [0, 0],
+    ));
+}
 
-    /// Bring the rectangle into normalized form where minimum and maximum form a true interval.
-    #[must_use]
-    pub fn normalize(self) -> Rectangle {
-        Rectangle {
-            x: self.x,
-            y: self.y,
-            max_x: self.x + self.width(),
-            max_y: self.y + self.width(),
-        }
-    }
+#[test]
+fn link_functions_by_name() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+
+    let mut leaf = CommandBuffer::default();
+    let input = leaf.input(desc.clone()).unwrap();
+    leaf.output(input).unwrap();
+    let signature = leaf.computed_signature();
+
+    let calling = |name: &str| {
+        let mut commands = CommandBuffer::default();
+        let callee = commands.function_named(name, signature.clone()).unwrap();
+        let input = commands.input(desc.clone()).unwrap();
+        let [result] = commands
+            .invoke(
+                callee,
+                InvocationArguments {
+                    generics: &[],
+                    arguments: &[input],
+                },
+            )
+            .unwrap()[..]
+        else {
+            panic!("Mismatched function results count");
+        };
+        commands.output(result).unwrap();
+        commands
+    };
 
-    /// A rectangle that the overlap of the two.
-    #[must_use]
-    pub fn meet(self, other: Self) -> Rectangle {
-        Rectangle {
-            x: self.x.max(other.x),
-            y: self.y.max(other.y),
-            max_x: self.max_x.min(other.max_x),
-            max_y: self.max_y.min(other.max_y),
-        }
-    }
+    let mut linker = Linker::from_included().clone();
+    linker.define("test/leaf", leaf).unwrap();
+    linker.define("test/call", calling("test/leaf")).unwrap();
+    linker.define("test/loop", calling("test/loop")).unwrap();
+
+    assert!(matches!(
+        linker.define("test/leaf", CommandBuffer::default()),
+        Err(CompileError::DuplicateSymbol(ref name)) if name == "test/leaf",
+    ));
+
+    let main = calling("test/call");
+    assert!(main.dump().contains("fn @0 test/call("));
+    let program = linker.link_by_name(&main, &[]).unwrap();
+    assert_eq!(program.functions.len(), 3);
+
+    assert!(matches!(
+        linker.link_by_name(&calling("test/missing"), &[]),
+        Err(CompileError::UnresolvedSymbol { link_idx: 0, name: Some(ref name) })
+            if name == "test/missing",
+    ));
+
+    // Positional declarations can not be resolved by name.
+    let mut anonymous = CommandBuffer::default();
+    anonymous.function(signature.clone()).unwrap();
+    assert!(matches!(
+        linker.link_by_name(&anonymous, &[]),
+        Err(CompileError::UnresolvedSymbol {
+            link_idx: 0,
+            name: None
+        }),
+    ));
 
-    /// The meet, relative to the coordinates of this rectangle.
-    #[must_use]
-    pub fn meet_in_local_coordinates(self, other: Self) -> Rectangle {
-        // Normalize to ensure that max_{x,y} is not less than {x,y}
-        let meet = self.normalize().meet(other);
-        Rectangle {
-            x: meet.x - self.x,
-            y: meet.y - self.y,
-            max_x: meet.max_x - self.x,
-            max_y: meet.max_y - self.y,
-        }
-    }
+    let Err(error) = linker.link_by_name(&calling("test/loop"), &[]) else {
+        panic!("A recursive function must not link");
+    };
 
-    /// A rectangle that contains both.
-    #[must_use]
-    pub fn join(self, other: Self) -> Rectangle {
-        Rectangle {
-            x: self.x.min(other.x),
-            y: self.y.min(other.y),
-            max_x: self.max_x.max(other.max_x),
-            max_y: self.max_y.max(other.max_y),
-        }
-    }
+    assert!(error
+        .to_string()
+        .starts_with("While instantiating test/loop: "));
+    assert!(matches!(
+        error,
+        CompileError::Instantiating { ref name, ref error, .. }
+            if name == "test/loop" && matches!(**error, CompileError::RecursiveLink { .. }),
+    ));
+}
 
-    /// Remove border from all sides.
-    /// When the image is smaller than `border` in some dimension then the result is empty and
-    /// contained in the original image but otherwise unspecified.
-    #[must_use]
-    pub fn inset(self, border: u32) -> Self {
-        Rectangle {
-            x: self.x.saturating_add(border),
-            y: self.y.saturating_add(border),
-            max_x: self.max_x.saturating_sub(border),
-            max_y: self.max_y.saturating_sub(border),
-        }
-    }
+#[test]
+fn buffer_parameters() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+
+    let mut function = CommandBuffer::default();
+    let image = function.input(desc.clone()).unwrap();
+    let kernel = function.buffer_input(256).unwrap();
+    let own = function.buffer_init(&[0; 256]);
+    let (joined, _) = function.buffer_concat(&[own, kernel]).unwrap();
+    function.output(image).unwrap();
+    function.buffer_result(joined).unwrap();
+
+    assert!(function.buffer_input(100).is_err());
+    let signature = function.computed_signature();
+    assert_eq!(signature.buffer_input.len(), 1);
+    assert_eq!(signature.buffer_output.len(), 1);
+
+    let mut commands = CommandBuffer::default();
+    let callee = commands.function(signature).unwrap();
+    assert!(commands.dump().contains("buffer[256]) -> ("));
+    assert!(commands.dump().contains(", buffer[512])"));
+
+    let input = commands.input(desc.clone()).unwrap();
+    let kernel = commands.buffer_init(&[0xff; 256]);
+    let wrong = commands.buffer_init(&[0xff; 512]);
+
+    let invoke = |commands: &mut CommandBuffer, arguments: &[Register]| {
+        commands.invoke(
+            callee,
+            InvocationArguments {
+                generics: &[],
+                arguments,
+            },
+        )
+    };
+
+    assert!(invoke(&mut commands, &[input, wrong])
+        .unwrap_err()
+        .is_type_err());
+    assert!(invoke(&mut commands, &[input, input]).is_err());
+
+    let [image, buffer] = invoke(&mut commands, &[input, kernel]).unwrap()[..] else {
+        panic!("Mismatched function results count");
+    };
+
+    assert!(matches!(
+        commands.describe_reg(buffer),
+        RegisterDescription::Buffer(_)
+    ));
+
+    commands.output(image).unwrap();
+    commands.output_buffer(buffer).unwrap();
+
+    let program = Linker::from_included()
+        .link(&commands, &[], &[function], &[&[1], &[]])
+        .unwrap();
+
+    let bindings = program
+        .ops
+        .iter()
+        .find_map(|high| match high {
+            High::Call {
+                image_io_buffers, ..
+            } => Some(image_io_buffers.clone()),
+            _ => None,
+        })
+        .expect("a call of the function");
+
+    assert!(bindings
+        .iter()
+        .any(|binding| matches!(binding, CallBinding::InBuffer { .. })));
+    assert!(bindings
+        .iter()
+        .any(|binding| matches!(binding, CallBinding::OutBuffer { .. })));
+    assert!(program
+        .functions
+        .iter()
+        .any(|function| function.signature_registers.len() == 4));
 }
 
-impl From<&'_ ByteLayout> for Rectangle {
-    fn from(buffer: &ByteLayout) -> Rectangle {
-        Rectangle::with_width_height(buffer.width, buffer.height)
-    }
+#[test]
+fn buffer_slice_ranges() {
+    let mut commands = CommandBuffer::default();
+    let buffer = commands.buffer_init(&[0; 1024]);
+
+    let slice = commands.buffer_slice(buffer, 256..768).unwrap();
+    assert!(matches!(
+        commands.describe_reg(slice),
+        RegisterDescription::Buffer(desc) if desc.as_concrete() == Some(512),
+    ));
+    assert!(matches!(
+        commands.op_kind(slice),
+        Some(OpKind::Unary { name: "buffer_slice", src }) if src == buffer,
+    ));
+
+    assert!(matches!(
+        commands.buffer_slice(buffer, 2..256).unwrap_err().kind(),
+        CommandErrorKind::MisalignedRange { range, align: 4 } if *range == (2..256),
+    ));
+    assert!(matches!(
+        commands.buffer_slice(buffer, 0..1022).unwrap_err().kind(),
+        CommandErrorKind::MisalignedRange { .. },
+    ));
+
+    assert!(commands.buffer_slice(buffer, 512..1028).is_err());
+    assert!(commands.buffer_slice(buffer, 512..512).is_err());
+
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let image = commands.input(desc).unwrap();
+    assert!(commands.buffer_slice(image, 0..4).is_err());
+
+    commands.validate().unwrap();
 }
 
-impl From<&'_ CanvasLayout> for Rectangle {
-    fn from(buffer: &CanvasLayout) -> Rectangle {
-        Rectangle::with_width_height(buffer.width(), buffer.height())
-    }
+#[test]
+fn buffer_splat_alignment() {
+    let mut commands = CommandBuffer::default();
+
+    let block = commands.buffer_splat(&[1, 2, 3, 4], 64).unwrap();
+    assert!(matches!(
+        commands.describe_reg(block),
+        RegisterDescription::Buffer(desc) if desc.as_concrete() == Some(256),
+    ));
+
+    // An unaligned pattern is repeated until it is aligned.
+    let triple = commands.buffer_splat(&[1, 2, 3], 8).unwrap();
+    assert!(matches!(
+        &commands.ops[triple.0],
+        Op::BufferInit { op: BufferInitOp::Splat { pattern }, .. } if pattern.len() == 12,
+    ));
+
+    assert!(matches!(
+        commands.buffer_splat(&[1, 2, 3], 5).unwrap_err().kind(),
+        CommandErrorKind::MisalignedRange { range, .. } if *range == (0..15),
+    ));
+    assert!(commands.buffer_splat(&[], 4).is_err());
+    assert!(commands.buffer_splat(&[0; 4], 0).is_err());
+
+    commands.validate().unwrap();
 }
 
-impl From<&'_ Descriptor> for Rectangle {
-    fn from(buffer: &Descriptor) -> Rectangle {
-        Rectangle::from(&buffer.layout)
+#[test]
+fn buffer_concat_ranges() {
+    let mut commands = CommandBuffer::default();
+    let header = commands.buffer_init(&[0; 16]);
+    let body = commands.buffer_init(&[0; 256]);
+    let footer = commands.buffer_init(&[0; 4]);
+
+    let (blob, ranges) = commands.buffer_concat(&[header, body, footer]).unwrap();
+    assert_eq!(ranges, [0..16, 16..272, 272..276]);
+    assert!(matches!(
+        commands.describe_reg(blob),
+        RegisterDescription::Buffer(desc) if desc.as_concrete() == Some(276),
+    ));
+    assert!(matches!(
+        commands.op_kind(blob),
+        Some(OpKind::Binary { name: "buffer_concat", rhs, .. }) if rhs == footer,
+    ));
+
+    // The parts can be retrieved again.
+    commands.buffer_slice(blob, ranges[1].clone()).unwrap();
+
+    let (single, ranges) = commands.buffer_concat(&[body]).unwrap();
+    assert_eq!((single, ranges), (body, vec![0..256]));
+
+    let odd = commands.buffer_init(&[0; 3]);
+    assert!(matches!(
+        commands.buffer_concat(&[header, odd, body]).unwrap_err().kind(),
+        CommandErrorKind::MisalignedRange { range, .. } if *range == (16..19),
+    ));
+    assert!(commands.buffer_concat(&[]).is_err());
+
+    commands.validate().unwrap();
+
+    let mut function = CommandBuffer::default();
+    let var = function.generic(GenericDeclaration::default()).unwrap();
+    let image = function.input_generic(var).unwrap();
+    let generic = function.buffer_from_image(image).unwrap();
+    let header = function.buffer_init(&[0; 16]);
+
+    assert!(matches!(
+        function
+            .buffer_concat(&[header, generic])
+            .unwrap_err()
+            .kind(),
+        CommandErrorKind::ConcreteDescriptorRequired,
+    ));
+}
+
+#[test]
+fn generic_buffer_from_image() {
+    let mut function = CommandBuffer::default();
+    let var = function.generic(GenericDeclaration::default()).unwrap();
+    let image = function.input_generic(var).unwrap();
+    let buffer = function.buffer_from_image(image).unwrap();
+
+    // The length is unknown until instantiation, so the buffer can not hold parameters.
+    assert!(matches!(
+        function.with_buffer(buffer).err().map(|err| err.inner),
+        Some(CommandErrorKind::ConcreteDescriptorRequired),
+    ));
+
+    // Only the descriptor of a single parameter determines the length.
+    let srgb = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 1, 1).unwrap();
+    let sized = function
+        .generic(GenericDeclaration {
+            bounds: &[],
+            size: SizeSpec::Fresh,
+            chroma: ChromaSpec::Concrete(srgb.texel.clone(), srgb.color.clone()),
+        })
+        .unwrap();
+    let sized = function.input_generic(sized).unwrap();
+    assert!(function.buffer_from_image(sized).is_err());
+
+    function.buffer_result(buffer).unwrap();
+    let signature = function.computed_signature();
+    assert!(signature.buffer_output[0].as_concrete().is_none());
+
+    let mut commands = CommandBuffer::default();
+    let callee = commands.function(signature).unwrap();
+
+    let small = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let large = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 8, 300).unwrap();
+
+    for desc in [small, large] {
+        let input = commands.input(desc.clone()).unwrap();
+        let sized = commands.input(srgb.clone()).unwrap();
+        let ty = commands.register_descriptor(input).unwrap();
+        let sized_ty = commands.register_descriptor(sized).unwrap();
+
+        let [buffer] = commands
+            .invoke(
+                callee,
+                InvocationArguments {
+                    generics: &[ty, sized_ty],
+                    arguments: &[input, sized],
+                },
+            )
+            .unwrap()[..]
+        else {
+            panic!("Mismatched function results count");
+        };
+
+        let RegisterDescription::Buffer(result) = commands.describe_reg(buffer) else {
+            panic!("Function result is not a buffer");
+        };
+
+        assert_eq!(result.as_concrete(), desc.u64_gpu_len());
     }
+
+    commands.validate().unwrap();
 }
 
-impl CommandError {
-    /// Indicates a very generic type error.
-    const TYPE_ERR: Self = CommandError {
-        inner: CommandErrorKind::GenericTypeError,
+#[test]
+fn output_in_place_reuses_dead_input() {
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 32, 32).unwrap()
     };
 
-    /// Indicates a very generic other error.
-    /// E.g. the usage of a command requires an extension? Not quite sure yet.
-    const OTHER: Self = CommandError {
-        inner: CommandErrorKind::Other,
+    let region = Rectangle::with_width_height(32, 32);
+
+    let textures = |commands: &CommandBuffer| {
+        let program = Linker::from_included().compile(commands).unwrap();
+        let textures = &program.functions[0].image_buffers.texture;
+        textures.len()
     };
 
-    /// Specifies that a register reference was invalid.
-    const BAD_REGISTER: Self = Self::OTHER;
+    let chain = |in_place: bool| {
+        let mut commands = CommandBuffer::default();
+        let image = commands.input(desc.clone()).unwrap();
+        let first = commands.crop(image, region).unwrap();
+        let second = commands.crop(first, region).unwrap();
 
-    /// Specifies that a register reference was invalid.
-    const INVALID_CALL: Self = Self::OTHER;
+        if in_place {
+            commands.output_in_place(image, second).unwrap();
+        } else {
+            commands.output(second).unwrap();
+        }
 
-    /// This has not yet been implemented, sorry.
-    ///
-    /// Errors of this kind will be removed over the course of bringing the crate to a first stable
-    /// release, this this will be removed. The method, and importantly its signature, are already
-    /// added for the purpose of exposition and documenting the intention.
-    const UNIMPLEMENTED: Self = CommandError {
-        inner: CommandErrorKind::Unimplemented,
+        commands.validate().unwrap();
+        commands
     };
 
-    pub fn is_type_err(&self) -> bool {
-        matches!(
-            self.inner,
-            CommandErrorKind::GenericTypeError
-                | CommandErrorKind::ConflictingTypes(_, _)
-                | CommandErrorKind::BadDescriptor(_, _)
-        )
-    }
-}
+    // The input is dead when the second crop is painted, which takes over its texture.
+    assert_eq!(textures(&chain(false)), 4);
+    assert_eq!(textures(&chain(true)), 3);
 
-#[test]
-fn rectangles() {
-    let small = Rectangle::with_width_height(2, 2);
-    let large = Rectangle::with_width_height(4, 4);
+    // A paint does not sample its own target, the crop reading the input is kept apart.
+    let mut commands = CommandBuffer::default();
+    let image = commands.input(desc.clone()).unwrap();
+    let cropped = commands.crop(image, region).unwrap();
+    commands.output_in_place(image, cropped).unwrap();
+    assert_eq!(textures(&commands), 3);
 
-    assert_eq!(large, large.join(small));
-    assert_eq!(small, large.meet(small));
-    assert!(large.contains(small));
-    assert!(!small.contains(large));
+    // Only inputs of the same descriptor may be taken over.
+    let mut commands = CommandBuffer::default();
+    let image = commands.input(desc.clone()).unwrap();
+    let cropped = commands.crop(image, region).unwrap();
+    let smaller = commands.resize(cropped, (16, 16)).expect("Valid to resize");
+    assert!(commands.output_in_place(cropped, smaller).is_err());
+    assert!(commands.output_in_place(image, smaller).is_err());
 }
 
 #[test]
-fn simple_program() {
-    use crate::pool::Pool;
+fn repeat_alternates_textures() {
+    let desc = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap();
+    let other = Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 8, 8).unwrap();
+    let region = Rectangle::with_width_height(4, 4);
 
-    const BACKGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/background.png");
-    const FOREGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/foreground.png");
+    let mut function = CommandBuffer::default();
+    let image = function.input(desc.clone()).unwrap();
+    let cropped = function.crop(image, region).unwrap();
+    function.output(cropped).unwrap();
 
-    let mut pool = Pool::new();
-    let mut commands = CommandBuffer::default();
+    let mut resizing = CommandBuffer::default();
+    let image = resizing.input(desc.clone()).unwrap();
+    let resized = resizing.resize(image, (8, 8)).unwrap();
+    resizing.output(resized).unwrap();
 
-    let background = image::open(BACKGROUND).expect("Background image opened");
-    let foreground = image::open(FOREGROUND).expect("Background image opened");
-    let expected = ByteLayout::from(&background);
+    let mut commands = CommandBuffer::default();
+    let body = commands.function(function.computed_signature()).unwrap();
+    let resize = commands.function(resizing.computed_signature()).unwrap();
+    let input = commands.input(desc.clone()).unwrap();
+    let wrong = commands.input(other).unwrap();
+
+    assert!(commands.repeat(2, resize, input).is_err());
+    assert!(commands.repeat(2, body, wrong).is_err());
+    assert_eq!(commands.repeat(0, body, input).unwrap(), input);
+
+    let result = commands.repeat(5, body, input).unwrap();
+    commands.output(result).unwrap();
+    commands.validate().unwrap();
+    assert!(commands.dump().contains(" repeat 5"));
+
+    let program = Linker::from_included()
+        .link(&commands, &[], &[function, resizing], &[&[1, 2], &[], &[]])
+        .unwrap();
+
+    let calls: Vec<_> = program
+        .ops
+        .iter()
+        .filter_map(|high| match high {
+            High::Call {
+                image_io_buffers, ..
+            } => match image_io_buffers[..] {
+                [CallBinding::InTexture {
+                    register: source, ..
+                }, CallBinding::OutTexture {
+                    register: target,
+                    texture,
+                }] => Some((source, target, texture)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
 
-    let placement = Rectangle {
-        x: 0,
-        y: 0,
-        max_x: foreground.width(),
-        max_y: foreground.height(),
-    };
+    assert_eq!(calls.len(), 5);
+    assert_eq!(calls[0].0, input);
+    assert_eq!(calls[4].1, result);
 
-    let background = pool.insert_srgb(&background);
-    let background = commands.input_from(background.into());
+    for (call, next) in calls.iter().zip(&calls[1..]) {
+        assert_eq!(call.1, next.0);
+        assert_ne!(call.2, next.2);
+    }
 
-    let foreground = pool.insert_srgb(&foreground);
-    let foreground = commands.input_from(foreground.into());
+    // Only the texture of the result and one other are painted.
+    assert_eq!(calls[0].2, calls[2].2);
+    assert_eq!(calls[2].2, calls[4].2);
+    assert_eq!(calls[1].2, calls[3].2);
+}
 
-    let result = commands
-        .inscribe(background, placement, foreground)
-        .expect("Valid to inscribe");
-    let (_, outformat) = commands.output(result).expect("Valid for output");
+#[test]
+fn bypassable_encodes_identity() {
+    let desc = Descriptor {
+        color: Color::SRGB,
+        ..Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 4, 4).unwrap()
+    };
 
-    let linker = Linker::from_included();
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(desc).unwrap();
+
+    let sharpen = commands
+        .with_knob()
+        .bypassable(input, Bypassable::Sharpen(0.5))
+        .unwrap();
+    assert!(commands.knobs.contains_key(&sharpen.register()));
+
+    let enabled = sharpen.encode(true);
+    let bypassed = sharpen.encode(false);
+    assert_eq!(enabled.len(), 32);
+    assert_eq!(enabled[..4], 0.5f32.to_ne_bytes());
+    assert_eq!(bypassed[..4], 0.0f32.to_ne_bytes());
+    // The internal values, here whether to clamp, are kept as recorded.
+    assert_eq!(enabled[4..], bypassed[4..]);
+
+    let aberration = ChromaticAberration {
+        scales: [1.1, 1.0, 0.9],
+        center: [0.25, 0.5],
+    };
 
-    let _ = linker
-        .compile(&commands)
-        .expect("Could build command buffer");
-    assert_eq!(outformat.as_concrete().map(|x| x.layout), Some(expected));
+    let shifted = commands
+        .with_knob()
+        .bypassable(
+            sharpen.register(),
+            Bypassable::ChromaticAberration(aberration),
+        )
+        .unwrap();
+
+    let bypassed: Vec<f32> = shifted
+        .encode(false)
+        .chunks_exact(4)
+        .map(|bytes| f32::from_ne_bytes(bytes.try_into().unwrap()))
+        .collect();
+    assert_eq!(bypassed, [1.0, 1.0, 1.0, 0.0, 0.25, 0.5, 0.0, 0.0]);
 }
@@ -0,0 +1,373 @@
+//! The layouts of parameter blocks controlled by knobs.
+//!
+//! Each method of [`WithKnob`] returns a [`KnobHandle`], typed with one of the layouts in this
+//! module. The layout knows how to encode a host value into the bytes of the knob, such that
+//! these need not be reconstructed from the shader source.
+//!
+//! [`WithKnob`]: super::WithKnob
+use core::marker::PhantomData;
+
+use super::{
+    Bilinear as BilinearParams, Palette as PaletteConfig, PaletteAddress, Register, RegisterKnob,
+};
+use crate::buffer::ChannelPosition;
+
+use image_canvas::color::ColorChannel;
+
+/// The byte layout of the parameter block of a knob.
+pub trait KnobLayout {
+    /// The host value from which the knob data is encoded.
+    type Value: ?Sized;
+
+    /// The alignment of the parameter block in the program's binary data, in bytes.
+    const ALIGN: u64;
+
+    /// Encode a value into the bytes of the parameter block.
+    fn encode(value: &Self::Value) -> Vec<u8>;
+}
+
+/// A value that can be written into a knob at launch time.
+///
+/// See [`Environment::set_knob`], which validates the length of the encoded value against the
+/// region of the knob.
+///
+/// [`Environment::set_knob`]: crate::run::Environment::set_knob
+pub trait KnobValue {
+    /// The bytes of the value, as they are written into the knob's region.
+    fn knob_bytes(&self) -> Vec<u8>;
+}
+
+/// The knob of a command, as returned by a [`WithKnob`] method.
+///
+/// The handle records the register of the command, and the byte length of its parameter block if
+/// that is determined by the command alone. Use [`Self::encode`] to turn a host value into the
+/// knob data, and [`Self::in_link`] to find the knob in a linked program.
+///
+/// [`WithKnob`]: super::WithKnob
+pub struct KnobHandle<T: ?Sized> {
+    register: Register,
+    len: Option<u64>,
+    layout: PhantomData<fn() -> T>,
+}
+
+/// The knob of a command that can be bypassed, as returned by [`WithKnob::bypassable`].
+///
+/// Besides the knob itself, the handle holds the two parameter blocks between which the command
+/// is switched at launch time. Use [`Self::encode`] to choose one of them.
+///
+/// [`WithKnob::bypassable`]: super::WithKnob::bypassable
+#[derive(Clone, Debug)]
+pub struct BypassHandle {
+    knob: KnobHandle<Raw>,
+    enabled: Vec<u8>,
+    bypassed: Vec<u8>,
+}
+
+/// The command which created a knob, see [`KnobInfo`].
+///
+/// There is one kind for each method of [`WithKnob`].
+///
+/// [`KnobInfo`]: crate::program::KnobInfo
+/// [`WithKnob`]: super::WithKnob
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[non_exhaustive]
+pub enum KnobOp {
+    Affine,
+    Bilinear,
+    Bloom,
+    BufferInit,
+    ChromaKey,
+    ChromaticAberration,
+    ChromaticAdaptation,
+    Crop,
+    DenoiseNlm,
+    DistributionNormal2d,
+    Emboss,
+    FractalNoise,
+    FromBuffer,
+    Inscribe,
+    Palette,
+    Sharpen,
+    SimplexNoise,
+    SolidRgba,
+    TestPattern,
+    ToneMap,
+    Voronoi,
+    Worley,
+}
+
+/// What a command buffer records about a knob of one of its commands.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct KnobRecord {
+    pub(crate) op: KnobOp,
+    pub(crate) label: Option<String>,
+    pub(crate) len: Option<u64>,
+    pub(crate) align: u64,
+}
+
+/// A parameter block whose layout is internal to its shader.
+///
+/// The data is passed as is. It usually contains values that should be left as initialized, see
+/// the documentation of the respective [`WithKnob`] method for the parts that may be changed.
+///
+/// [`WithKnob`]: super::WithKnob
+pub enum Raw {}
+
+/// The color of [`CommandBuffer::solid_rgba`], as four `f32` in 16 bytes.
+///
+/// [`CommandBuffer::solid_rgba`]: super::CommandBuffer::solid_rgba
+pub enum SolidRgba {}
+
+/// The corners of a painted quad, as eight pairs of `f32` in 64 bytes.
+///
+/// See [`WithKnob::crop`] for the order of the corners.
+///
+/// [`WithKnob::crop`]: super::WithKnob::crop
+pub enum Quad {}
+
+/// The byte offset of [`CommandBuffer::from_buffer_at`], as one `u64` in 8 bytes.
+///
+/// [`CommandBuffer::from_buffer_at`]: super::CommandBuffer::from_buffer_at
+pub enum Offset {}
+
+/// The channel weights, base coordinates and border of [`CommandBuffer::palette`], in 64 bytes.
+///
+/// [`CommandBuffer::palette`]: super::CommandBuffer::palette
+pub enum Palette {}
+
+/// The coefficients of [`CommandBuffer::bilinear`], as six vectors of four `f32` in 96 bytes.
+///
+/// [`CommandBuffer::bilinear`]: super::CommandBuffer::bilinear
+pub enum Bilinear {}
+
+/// The contents of a buffer initialized with [`CommandBuffer::buffer_init`].
+///
+/// [`CommandBuffer::buffer_init`]: super::CommandBuffer::buffer_init
+pub enum BufferInit {}
+
+impl<T: ?Sized> KnobHandle<T> {
+    pub(crate) fn new(register: Register, len: Option<u64>) -> Self {
+        KnobHandle {
+            register,
+            len,
+            layout: PhantomData,
+        }
+    }
+
+    /// The register of the command controlled by the knob.
+    pub fn register(&self) -> Register {
+        self.register
+    }
+
+    /// The knob of this command in the command buffer of the given index of a link.
+    ///
+    /// The command buffer passed to [`Linker::compile`] has the index `0`.
+    ///
+    /// [`Linker::compile`]: super::Linker::compile
+    pub fn in_link(&self, link_idx: usize) -> RegisterKnob {
+        RegisterKnob {
+            link_idx,
+            register: self.register,
+        }
+    }
+
+    /// The length of the parameter block in bytes, if it is determined by the command.
+    pub fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
+
+impl<T: KnobLayout + ?Sized> KnobHandle<T> {
+    /// The alignment of the parameter block in bytes.
+    pub fn align(&self) -> u64 {
+        T::ALIGN
+    }
+
+    /// Encode a value into the bytes of this knob.
+    pub fn encode(&self, value: &T::Value) -> Vec<u8> {
+        T::encode(value)
+    }
+}
+
+impl BypassHandle {
+    pub(crate) fn new(knob: KnobHandle<Raw>, enabled: Vec<u8>, bypassed: Vec<u8>) -> Self {
+        BypassHandle {
+            knob,
+            enabled,
+            bypassed,
+        }
+    }
+
+    /// The handle of the underlying knob.
+    pub fn knob(&self) -> KnobHandle<Raw> {
+        self.knob
+    }
+
+    /// The register of the command controlled by the knob.
+    pub fn register(&self) -> Register {
+        self.knob.register()
+    }
+
+    /// The knob of this command in the command buffer of the given index of a link.
+    ///
+    /// See [`KnobHandle::in_link`].
+    pub fn in_link(&self, link_idx: usize) -> RegisterKnob {
+        self.knob.in_link(link_idx)
+    }
+
+    /// The bytes of the knob, with the command either enabled as recorded or bypassed.
+    pub fn encode(&self, enabled: bool) -> Vec<u8> {
+        if enabled {
+            self.enabled.clone()
+        } else {
+            self.bypassed.clone()
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for KnobHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for KnobHandle<T> {}
+
+impl<T: ?Sized> core::fmt::Debug for KnobHandle<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("KnobHandle")
+            .field("register", &self.register)
+            .field("len", &self.len)
+            .finish()
+    }
+}
+
+impl KnobLayout for Raw {
+    type Value = [u8];
+    const ALIGN: u64 = 16;
+
+    fn encode(value: &[u8]) -> Vec<u8> {
+        value.to_vec()
+    }
+}
+
+impl KnobLayout for SolidRgba {
+    type Value = [f32; 4];
+    const ALIGN: u64 = 16;
+
+    fn encode(value: &[f32; 4]) -> Vec<u8> {
+        bytemuck::bytes_of(value).to_vec()
+    }
+}
+
+impl KnobLayout for Quad {
+    type Value = [[f32; 2]; 8];
+    const ALIGN: u64 = 16;
+
+    fn encode(value: &[[f32; 2]; 8]) -> Vec<u8> {
+        bytemuck::bytes_of(value).to_vec()
+    }
+}
+
+impl KnobLayout for Offset {
+    type Value = u64;
+    const ALIGN: u64 = 8;
+
+    fn encode(value: &u64) -> Vec<u8> {
+        value.to_ne_bytes().to_vec()
+    }
+}
+
+impl KnobLayout for Palette {
+    type Value = PaletteConfig;
+    const ALIGN: u64 = 16;
+
+    /// Encode the configuration as [`CommandBuffer::palette`] would.
+    ///
+    /// A channel without a position in the linear color, e.g. `Cb`, is encoded with zero weights.
+    ///
+    /// [`CommandBuffer::palette`]: super::CommandBuffer::palette
+    #[rustfmt::skip]
+    fn encode(value: &PaletteConfig) -> Vec<u8> {
+        let weights = |channel: Option<ColorChannel>| {
+            channel
+                .and_then(ChannelPosition::new)
+                .map_or([0.0; 4], ChannelPosition::into_vec4)
+        };
+
+        let x_coord = weights(value.width);
+        let y_coord = weights(value.height);
+
+        let (flag, border) = match value.address {
+            PaletteAddress::Border(color) => (1.0, color),
+            _ => (0.0, [0.0; 4]),
+        };
+
+        let block: [f32; 16] = [
+            x_coord[0], y_coord[0],
+            x_coord[1], y_coord[1],
+            x_coord[2], y_coord[2],
+            x_coord[3], y_coord[3],
+            value.width_base as f32, value.height_base as f32, flag, 0.0,
+            border[0], border[1], border[2], border[3],
+        ];
+
+        bytemuck::bytes_of(&block).to_vec()
+    }
+}
+
+impl KnobLayout for Bilinear {
+    type Value = BilinearParams;
+    const ALIGN: u64 = 16;
+
+    fn encode(value: &BilinearParams) -> Vec<u8> {
+        value.into_std430()
+    }
+}
+
+impl KnobLayout for BufferInit {
+    type Value = [u8];
+    const ALIGN: u64 = 4;
+
+    fn encode(value: &[u8]) -> Vec<u8> {
+        value.to_vec()
+    }
+}
+
+impl KnobValue for [u8] {
+    fn knob_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl KnobValue for Vec<u8> {
+    fn knob_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+impl KnobValue for f32 {
+    fn knob_bytes(&self) -> Vec<u8> {
+        self.to_ne_bytes().to_vec()
+    }
+}
+
+impl KnobValue for u64 {
+    fn knob_bytes(&self) -> Vec<u8> {
+        self.to_ne_bytes().to_vec()
+    }
+}
+
+impl<const N: usize> KnobValue for [u8; N] {
+    fn knob_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl<const N: usize> KnobValue for [f32; N] {
+    fn knob_bytes(&self) -> Vec<u8> {
+        bytemuck::cast_slice::<f32, u8>(&self[..]).to_vec()
+    }
+}
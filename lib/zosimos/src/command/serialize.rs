@@ -0,0 +1,409 @@
+//! Serialization of command buffers, with the `serde` feature.
+//!
+//! The types of `image-canvas` are written by the names of their values. Only the values which
+//! are named here can be serialized, others fail with an error instead of being written lossily.
+//! Deserialization validates the operations as if they had been recorded by the methods of the
+//! command buffer, such that a program can not refer to registers it does not define.
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{
+    CommandBuffer, CommandSignature, Generic, GenericDescriptor, KnobKind, Op, Register,
+    TyVarBounds,
+};
+use crate::buffer::{Block, Color, ColorChannel, Primaries, SampleBits, SampleParts, Texel};
+use crate::buffer::{Transfer, Whitepoint};
+use image_canvas::color::{Differencing, Luminance};
+
+macro_rules! named {
+    ($(mod $name:ident: $ty:ident { $($value:ident),* $(,)? })*) => {$(
+        pub(super) mod $name {
+            use super::*;
+
+            const VALUES: &[(&str, $ty)] = &[$((stringify!($value), $ty::$value)),*];
+
+            pub fn serialize<S: Serializer>(value: &$ty, serializer: S) -> Result<S::Ok, S::Error> {
+                match VALUES.iter().find(|(_, known)| known == value) {
+                    Some((name, _)) => serializer.serialize_str(name),
+                    None => Err(<S::Error as serde::ser::Error>::custom(format_args!(
+                        "can not serialize {value:?}"
+                    ))),
+                }
+            }
+
+            pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<$ty, D::Error> {
+                let name = String::deserialize(deserializer)?;
+                match VALUES.iter().find(|(known, _)| *known == name) {
+                    Some((_, value)) => Ok(value.clone()),
+                    None => Err(<D::Error as serde::de::Error>::custom(format_args!(
+                        "unknown {} `{name}`",
+                        stringify!($ty),
+                    ))),
+                }
+            }
+        }
+    )*};
+}
+
+named! {
+    mod block: Block { Pixel }
+    mod sample_bits: SampleBits {
+        UInt8, UInt8x2, UInt8x3, UInt8x4, UInt16, UInt16x2, UInt16x3, UInt16x4, Float16x4,
+        Float32, Float32x2, Float32x3, Float32x4,
+    }
+    mod color_channel: ColorChannel {
+        R, G, B, Alpha, Luma, Cb, Cr, L, LABa, LABb, C, LABh, X, Y, Z, Scalar0, Scalar1, Scalar2,
+    }
+    mod primaries: Primaries { Xyz, Bt601_525, Bt601_625, Bt709, Smpte240, Bt2020, Bt2100 }
+    mod transfer: Transfer {
+        Bt709, Bt601, Smpte240, Linear, Srgb, Bt2020_10bit, Smpte2084, Bt2100Pq, Bt2100Hlg,
+    }
+    mod whitepoint: Whitepoint { A, B, C, D50, D55, D65, D75, E, F2, F7, F11 }
+    mod luminance: Luminance { Sdr, Hdr, AdobeRgb, DciP3 }
+    mod differencing: Differencing {
+        Bt407MPal, Bt407MPalPrecise, Bt601, Bt601Quantized, Bt601FullSwing, Bt709, Bt709Quantized,
+        Bt709FullSwing, YDbDr, Bt2020, Bt2100, YCoCg,
+    }
+}
+
+/// A color channel in the parts of a texel.
+#[derive(Deserialize, Serialize)]
+struct Channel(#[serde(with = "color_channel")] ColorChannel);
+
+/// Sample parts are written as their channels, which identify them among the parts we know.
+mod sample_parts {
+    use super::*;
+
+    const KNOWN: &[SampleParts] = &[
+        SampleParts::Rgb,
+        SampleParts::RgbA,
+        SampleParts::Bgr,
+        SampleParts::BgrA,
+        SampleParts::ARgb,
+        SampleParts::ABgr,
+        SampleParts::Luma,
+        SampleParts::LumaA,
+        SampleParts::LabA,
+        SampleParts::Lch,
+        SampleParts::LchA,
+        SampleParts::Yuv,
+    ];
+
+    /// The known parts, and those of each of their single channels.
+    fn candidates() -> impl Iterator<Item = SampleParts> {
+        KNOWN.iter().flat_map(|&parts| {
+            let single = parts
+                .color_channels()
+                .into_iter()
+                .flatten()
+                .filter_map(move |channel| parts.with_channel(channel));
+            core::iter::once(parts).chain(single)
+        })
+    }
+
+    pub fn serialize<S: Serializer>(parts: &SampleParts, serializer: S) -> Result<S::Ok, S::Error> {
+        let channels = parts.color_channels();
+
+        match candidates().find(|known| known.color_channels() == channels) {
+            Some(known) if known == *parts => {
+                channels.map(|ch| ch.map(Channel)).serialize(serializer)
+            }
+            _ => Err(<S::Error as serde::ser::Error>::custom(format_args!(
+                "can not serialize {parts:?}"
+            ))),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<SampleParts, D::Error> {
+        let channels = <[Option<Channel>; 4]>::deserialize(deserializer)?;
+        let channels = channels.map(|ch| ch.map(|Channel(ch)| ch));
+
+        candidates()
+            .find(|known| known.color_channels() == channels)
+            .ok_or_else(|| <D::Error as serde::de::Error>::custom("unknown sample parts"))
+    }
+}
+
+pub(super) mod texel {
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename = "Texel")]
+    struct Repr {
+        #[serde(with = "block")]
+        block: Block,
+        #[serde(with = "sample_bits")]
+        bits: SampleBits,
+        #[serde(with = "sample_parts")]
+        parts: SampleParts,
+    }
+
+    pub fn serialize<S: Serializer>(texel: &Texel, serializer: S) -> Result<S::Ok, S::Error> {
+        let texel = texel.clone();
+
+        Repr {
+            block: texel.block,
+            bits: texel.bits,
+            parts: texel.parts,
+        }
+        .serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Texel, D::Error> {
+        let Repr { block, bits, parts } = Repr::deserialize(deserializer)?;
+        Ok(Texel { block, bits, parts })
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename = "Color")]
+enum ColorRepr {
+    Rgb {
+        #[serde(with = "primaries")]
+        primary: Primaries,
+        #[serde(with = "transfer")]
+        transfer: Transfer,
+        #[serde(with = "whitepoint")]
+        whitepoint: Whitepoint,
+        #[serde(with = "luminance")]
+        luminance: Luminance,
+    },
+    Luma {
+        #[serde(with = "transfer")]
+        transfer: Transfer,
+        #[serde(with = "whitepoint")]
+        whitepoint: Whitepoint,
+        #[serde(with = "luminance")]
+        luminance: Luminance,
+    },
+    Yuv {
+        #[serde(with = "primaries")]
+        primary: Primaries,
+        #[serde(with = "whitepoint")]
+        whitepoint: Whitepoint,
+        #[serde(with = "transfer")]
+        transfer: Transfer,
+        #[serde(with = "luminance")]
+        luminance: Luminance,
+        #[serde(with = "differencing")]
+        differencing: Differencing,
+    },
+    Oklab,
+    SrLab2 {
+        #[serde(with = "whitepoint")]
+        whitepoint: Whitepoint,
+    },
+    Scalars {
+        #[serde(with = "transfer")]
+        transfer: Transfer,
+    },
+}
+
+impl ColorRepr {
+    fn new(color: &Color) -> Option<Self> {
+        Some(match color.clone() {
+            Color::Rgb {
+                primary,
+                transfer,
+                whitepoint,
+                luminance,
+            } => ColorRepr::Rgb {
+                primary,
+                transfer,
+                whitepoint,
+                luminance,
+            },
+            Color::Luma {
+                transfer,
+                whitepoint,
+                luminance,
+            } => ColorRepr::Luma {
+                transfer,
+                whitepoint,
+                luminance,
+            },
+            Color::Yuv {
+                primary,
+                whitepoint,
+                transfer,
+                luminance,
+                differencing,
+            } => ColorRepr::Yuv {
+                primary,
+                whitepoint,
+                transfer,
+                luminance,
+                differencing,
+            },
+            Color::Oklab => ColorRepr::Oklab,
+            Color::SrLab2 { whitepoint } => ColorRepr::SrLab2 { whitepoint },
+            Color::Scalars { transfer } => ColorRepr::Scalars { transfer },
+            // In case the color model of `image-canvas` grows.
+            _ => return None,
+        })
+    }
+
+    fn into_color(self) -> Color {
+        match self {
+            ColorRepr::Rgb {
+                primary,
+                transfer,
+                whitepoint,
+                luminance,
+            } => Color::Rgb {
+                primary,
+                transfer,
+                whitepoint,
+                luminance,
+            },
+            ColorRepr::Luma {
+                transfer,
+                whitepoint,
+                luminance,
+            } => Color::Luma {
+                transfer,
+                whitepoint,
+                luminance,
+            },
+            ColorRepr::Yuv {
+                primary,
+                whitepoint,
+                transfer,
+                luminance,
+                differencing,
+            } => Color::Yuv {
+                primary,
+                whitepoint,
+                transfer,
+                luminance,
+                differencing,
+            },
+            ColorRepr::Oklab => Color::Oklab,
+            ColorRepr::SrLab2 { whitepoint } => Color::SrLab2 { whitepoint },
+            ColorRepr::Scalars { transfer } => Color::Scalars { transfer },
+        }
+    }
+}
+
+pub(super) mod chroma {
+    use super::*;
+
+    #[derive(Deserialize, Serialize)]
+    #[serde(rename = "Chroma")]
+    struct Repr(#[serde(with = "texel")] Texel, ColorRepr);
+
+    pub fn serialize<S: Serializer>(
+        chroma: &Generic<(Texel, Color)>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let chroma = match chroma {
+            Generic::Concrete((texel, color)) => {
+                let Some(color) = ColorRepr::new(color) else {
+                    return Err(<S::Error as serde::ser::Error>::custom(format_args!(
+                        "can not serialize {color:?}"
+                    )));
+                };
+
+                Generic::Concrete(Repr(texel.clone(), color))
+            }
+            &Generic::Generic(var) => Generic::Generic(var),
+        };
+
+        chroma.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Generic<(Texel, Color)>, D::Error> {
+        Ok(match Generic::<Repr>::deserialize(deserializer)? {
+            Generic::Concrete(Repr(texel, color)) => Generic::Concrete((texel, color.into_color())),
+            Generic::Generic(var) => Generic::Generic(var),
+        })
+    }
+}
+
+/// The command buffer as written, with sets in the order of their registers.
+#[derive(Serialize)]
+#[serde(rename = "CommandBuffer")]
+struct CommandBufferRef<'lt> {
+    ops: &'lt [Op],
+    vars: &'lt [TyVarBounds],
+    symbols: &'lt [CommandSignature],
+    tys: &'lt [GenericDescriptor],
+    knobs: Vec<(Register, &'lt KnobKind)>,
+    dithered: Vec<Register>,
+    in_place: Vec<(Register, Register)>,
+}
+
+/// The command buffer as read, before its validation.
+#[derive(Deserialize)]
+#[serde(rename = "CommandBuffer")]
+struct CommandBufferData {
+    ops: Vec<Op>,
+    vars: Vec<TyVarBounds>,
+    symbols: Vec<CommandSignature>,
+    tys: Vec<GenericDescriptor>,
+    knobs: Vec<(Register, KnobKind)>,
+    dithered: Vec<Register>,
+    #[serde(default)]
+    in_place: Vec<(Register, Register)>,
+}
+
+impl Serialize for CommandBuffer {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut knobs: Vec<_> = self.knobs.iter().map(|(&reg, knob)| (reg, knob)).collect();
+        knobs.sort_by_key(|&(Register(reg), _)| reg);
+
+        let mut dithered: Vec<_> = self.dithered.iter().copied().collect();
+        dithered.sort_by_key(|&Register(reg)| reg);
+
+        let mut in_place: Vec<_> = self
+            .in_place
+            .iter()
+            .map(|(&res, &src)| (res, src))
+            .collect();
+        in_place.sort_by_key(|&(Register(reg), _)| reg);
+
+        CommandBufferRef {
+            ops: &self.ops,
+            vars: &self.vars,
+            symbols: &self.symbols,
+            tys: &self.tys,
+            knobs,
+            dithered,
+            in_place,
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for CommandBuffer {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let CommandBufferData {
+            ops,
+            vars,
+            symbols,
+            tys,
+            knobs,
+            dithered,
+            in_place,
+        } = CommandBufferData::deserialize(deserializer)?;
+
+        let commands = CommandBuffer {
+            ops,
+            vars,
+            symbols,
+            tys,
+            knobs: knobs.into_iter().collect(),
+            dithered: dithered.into_iter().collect(),
+            in_place: in_place.into_iter().collect(),
+        };
+
+        // The operations must be as if they had been recorded by the methods of the buffer.
+        match commands.validate() {
+            Ok(()) => Ok(commands),
+            Err(errors) => Err(<D::Error as serde::de::Error>::custom(&errors[0])),
+        }
+    }
+}
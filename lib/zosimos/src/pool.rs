@@ -1,11 +1,12 @@
 use core::{fmt, mem};
 use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use slotmap::{DefaultKey, SlotMap};
 use wgpu::{Buffer, Texture};
 
-use crate::buffer::{CanvasLayout, Color, Descriptor, ImageBuffer};
+use crate::buffer::{f32_to_f16, ByteLayout, CanvasLayout, Color, Descriptor, ImageBuffer};
 use crate::program::{
     BufferDescriptor, BufferUsage, Capabilities, ImageDescriptor, RenderPipelineKey,
     ShaderDescriptorKey, TextureDescriptor,
@@ -20,8 +21,10 @@ pub struct Pool {
     items: SlotMap<DefaultKey, Image>,
     buffers: SlotMap<DefaultKey, (BufferDescriptor, GpuKey, wgpu::Buffer)>,
     textures: SlotMap<DefaultKey, (TextureDescriptor, GpuKey, wgpu::Texture)>,
-    shaders: SlotMap<DefaultKey, (ShaderDescriptorKey, GpuKey, wgpu::ShaderModule)>,
-    pipelines: SlotMap<DefaultKey, (RenderPipelineKey, GpuKey, wgpu::RenderPipeline)>,
+    /// Compiled modules, shared by all executions on the same device.
+    shaders: SlotMap<DefaultKey, (ShaderDescriptorKey, GpuKey, Arc<wgpu::ShaderModule>)>,
+    /// Compiled pipelines, shared by all executions on the same device.
+    pipelines: SlotMap<DefaultKey, (RenderPipelineKey, GpuKey, Arc<wgpu::RenderPipeline>)>,
     devices: SlotMap<DefaultKey, Gpu>,
 }
 
@@ -38,6 +41,19 @@ pub struct PoolBridge {
 #[derive(Clone)]
 pub struct Gpu {
     inner: Arc<(wgpu::Device, wgpu::Queue)>,
+    /// Set by the device when it is lost, see [`Gpu::is_lost`].
+    lost: Arc<AtomicBool>,
+    /// The cache passed to all pipelines created on the device.
+    pipeline_cache: Option<Arc<wgpu::PipelineCache>>,
+}
+
+/// Whether a device uses a pipeline cache, see [`Pool::set_pipeline_cache`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PipelineCacheStatus {
+    /// The device was not created with `Features::PIPELINE_CACHE`, the cache was discarded.
+    Unsupported,
+    /// Pipelines created on the device use the cache.
+    Attached,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -93,8 +109,6 @@ pub struct IterMut<'pool> {
 pub struct Cache<'pool> {
     texture_sets: HashMap<TextureDescriptor, Vec<PoolKey>>,
     buffer_sets: HashMap<BufferDescriptor, Vec<BufferKey>>,
-    // FIXME: really, we should only ever need one instance of each shader!
-    // But since it isn't `Clone` we rely on the encoder for this invariant.
     shader_sets: HashMap<ShaderDescriptorKey, Vec<ShaderKey>>,
     pipeline_sets: HashMap<RenderPipelineKey, Vec<PipelineKey>>,
     pool: &'pool mut Pool,
@@ -173,6 +187,9 @@ pub enum ImageUploadError {
     /// Impossible to generate a GPU descriptor for the image. Only if the memory for a GPU texture
     /// is too large to accommodate a properly aligned row representation of the texel matrix.
     BadDescriptor,
+    /// The byte layout does not describe the supplied data. Either the rows are shorter than the
+    /// texels they hold, the total size overflows, or the data is too short.
+    BadLayout,
     /// The target GPU currently in-use.
     InactiveGpu,
 }
@@ -212,6 +229,42 @@ impl Pool {
         self.devices.iter().map(|gpu| gpu.1.device())
     }
 
+    /// Use a pipeline cache for all pipelines created on a device.
+    ///
+    /// Creating the cache is `unsafe` in `wgpu`, which is why it is left to the caller. The data
+    /// must have been returned by [`Self::pipeline_cache_data`] for an adapter with the same
+    /// [`wgpu::util::pipeline_cache_key`]. Create the cache with `fallback: true` to tolerate data
+    /// of another driver version, or with `fallback: false` in an error scope to find out if the
+    /// data was rejected.
+    ///
+    /// Backends and devices without support for a cache silently ignore it. Returns `None` if
+    /// there is no device with the key.
+    pub fn set_pipeline_cache(
+        &mut self,
+        key: GpuKey,
+        cache: wgpu::PipelineCache,
+    ) -> Option<PipelineCacheStatus> {
+        let gpu = self.devices.get_mut(key.0)?;
+
+        if !gpu
+            .device()
+            .features()
+            .contains(wgpu::Features::PIPELINE_CACHE)
+        {
+            return Some(PipelineCacheStatus::Unsupported);
+        }
+
+        gpu.pipeline_cache = Some(Arc::new(cache));
+        Some(PipelineCacheStatus::Attached)
+    }
+
+    /// The data of the pipeline cache of a device, for persisting it between runs.
+    ///
+    /// This is `None` if no cache is used or the backend does not support retrieving its data.
+    pub fn pipeline_cache_data(&self, key: GpuKey) -> Option<Vec<u8>> {
+        self.devices.get(key.0)?.pipeline_cache.as_ref()?.get_data()
+    }
+
     pub(crate) fn reinsert_device(&mut self, key: GpuKey, gpu: Gpu) {
         if let Some(device) = self.devices.get_mut(key.0) {
             *device = gpu;
@@ -256,6 +309,23 @@ impl Pool {
         self.insert(buffer, descriptor)
     }
 
+    /// Insert a floating point image as half floats in linear light.
+    ///
+    /// The image is described with [`Descriptor::with_linear_half`]. The samples are rounded to
+    /// the nearest half float, values beyond its range become infinite.
+    pub fn insert_linear_half(&mut self, image: &image::Rgba32FImage) -> PoolImageMut<'_> {
+        let descriptor = Descriptor::with_linear_half(image.width(), image.height())
+            .expect("Valid layout for an in-memory image");
+
+        let mut buffer = ImageBuffer::with_descriptor(&descriptor);
+        let target = buffer.as_bytes_mut().chunks_exact_mut(2);
+        for (half, &sample) in target.zip(image.as_raw()) {
+            half.copy_from_slice(&f32_to_f16(sample).to_ne_bytes());
+        }
+
+        self.insert(buffer, descriptor)
+    }
+
     /// Create the image based on an entry.
     ///
     /// This allocates a host-accessible buffer with the same layout and metadata as the image. If
@@ -387,21 +457,41 @@ impl Pool {
         BufferKey(key)
     }
 
+    /// Insert a compiled module, unless it was shared from this pool in the first place.
     pub(crate) fn insert_cacheable_shader(
         &mut self,
         desc: &ShaderDescriptorKey,
-        data: wgpu::ShaderModule,
+        data: Arc<wgpu::ShaderModule>,
     ) -> ShaderKey {
+        let shared = self
+            .shaders
+            .iter()
+            .find(|(_, (_, _, shader))| Arc::ptr_eq(shader, &data));
+
+        if let Some((key, _)) = shared {
+            return ShaderKey(key);
+        }
+
         let gpu = GpuKey(slotmap::KeyData::from_ffi(0).into());
         let key = self.shaders.insert((desc.clone(), gpu, data));
         ShaderKey(key)
     }
 
+    /// Insert a compiled pipeline, unless it was shared from this pool in the first place.
     pub(crate) fn insert_cacheable_pipeline(
         &mut self,
         desc: &RenderPipelineKey,
-        data: wgpu::RenderPipeline,
+        data: Arc<wgpu::RenderPipeline>,
     ) -> PipelineKey {
+        let shared = self
+            .pipelines
+            .iter()
+            .find(|(_, (_, _, pipeline))| Arc::ptr_eq(pipeline, &data));
+
+        if let Some((key, _)) = shared {
+            return PipelineKey(key);
+        }
+
         let gpu = GpuKey(slotmap::KeyData::from_ffi(0).into());
         let key = self.pipelines.insert((desc.clone(), gpu, data));
         PipelineKey(key)
@@ -446,15 +536,36 @@ impl Pool {
         }
     }
 
-    /// Discard all cached textures, buffers, etc.
+    /// Discard all cached textures and buffers.
+    ///
+    /// Compiled shader modules and pipelines are kept, these are shared between all executions
+    /// on the same device instead of being moved into one of them. See [`Self::clear_pipelines`].
     pub fn clear_cache(&mut self) {
         self.buffers.clear();
         self.textures.clear();
+    }
+
+    /// Discard all compiled shader modules and pipelines.
+    pub fn clear_pipelines(&mut self) {
         self.shaders.clear();
         self.pipelines.clear();
     }
 
+    /// Discard all cached resources of a device that was lost, these can never be used again.
+    fn purge_lost_device(&mut self, gpu: GpuKey) {
+        if !self.devices.get(gpu.0).is_some_and(Gpu::is_lost) {
+            return;
+        }
+
+        self.buffers.retain(|_, (_, key, _)| key.0 != gpu.0);
+        self.textures.retain(|_, (_, key, _)| key.0 != gpu.0);
+        self.shaders.retain(|_, (_, key, _)| key.0 != gpu.0);
+        self.pipelines.retain(|_, (_, key, _)| key.0 != gpu.0);
+    }
+
     pub(crate) fn as_cache(&mut self, gpu: GpuKey) -> Cache<'_> {
+        self.purge_lost_device(gpu);
+
         let mut buffer_sets = HashMap::<_, Vec<_>>::new();
         let mut texture_sets = HashMap::<_, Vec<_>>::new();
         let mut shader_sets = HashMap::<_, Vec<_>>::new();
@@ -741,6 +852,58 @@ impl PoolImageMut<'_> {
         self.image.data = ImageData::Host(buffer);
     }
 
+    /// Replace this image with host allocated data of an explicit byte layout.
+    ///
+    /// The texel and color of the current descriptor are kept, only the layout is changed. The
+    /// `row_stride` of the layout may exceed the bytes of the texels in a row, such as for frames
+    /// whose rows are padded for alignment. The data is copied as is, the padding is skipped when
+    /// the image is uploaded. An input declared with [`CommandBuffer::input_from`] decodes the
+    /// image accordingly.
+    ///
+    /// [`CommandBuffer::input_from`]: crate::command::CommandBuffer::input_from
+    pub fn set_bytes_with_stride(
+        &mut self,
+        data: &[u8],
+        layout: ByteLayout,
+    ) -> Result<(), ImageUploadError> {
+        let mut descriptor = self.image.descriptor.clone();
+
+        if layout.texel_stride != descriptor.texel.bits.bytes() {
+            return Err(ImageUploadError::BadLayout);
+        }
+
+        let min_stride = u64::from(layout.texel_stride)
+            .checked_mul(u64::from(layout.width))
+            .ok_or(ImageUploadError::BadLayout)?;
+
+        if layout.row_stride < min_stride {
+            return Err(ImageUploadError::BadLayout);
+        }
+
+        layout
+            .row_stride
+            .checked_mul(u64::from(layout.height))
+            .and_then(|len| usize::try_from(len).ok())
+            .ok_or(ImageUploadError::BadLayout)?;
+
+        descriptor.layout = layout;
+        let canvas = descriptor
+            .try_to_canvas()
+            .ok_or(ImageUploadError::BadLayout)?;
+
+        let mut buffer = ImageBuffer::with_layout(&canvas);
+        let target = buffer.as_bytes_mut();
+        let source = data
+            .get(..target.len())
+            .ok_or(ImageUploadError::BadLayout)?;
+        target.copy_from_slice(source);
+
+        self.image.descriptor = descriptor;
+        self.image.data = ImageData::Host(buffer);
+
+        Ok(())
+    }
+
     /// Create a texture suitable for the image descriptor.
     pub fn set_texture(
         &mut self,
@@ -994,9 +1157,37 @@ impl PoolBridge {
 }
 
 impl Gpu {
+    /// Wrap a device and its queue.
+    ///
+    /// This registers the device lost callback of the device, see [`Self::is_lost`].
     pub fn new(device: wgpu::Device, queue: wgpu::Queue) -> Self {
+        let lost = Arc::new(AtomicBool::new(false));
+
+        let flag = lost.clone();
+        device.set_device_lost_callback(move |reason, message| {
+            log::warn!("Device lost ({reason:?}): {message}");
+            flag.store(true, Ordering::Release);
+        });
+
         let inner = Arc::new((device, queue));
-        Gpu { inner }
+
+        Gpu {
+            inner,
+            lost,
+            pipeline_cache: None,
+        }
+    }
+
+    /// Whether the device was lost.
+    ///
+    /// Resources created on a lost device, including the shader modules and pipelines shared in a
+    /// pool, are no longer valid. The pool discards them instead of sharing them.
+    pub fn is_lost(&self) -> bool {
+        self.lost.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn pipeline_cache(&self) -> Option<Arc<wgpu::PipelineCache>> {
+        self.pipeline_cache.clone()
     }
 
     pub fn device(&self) -> &wgpu::Device {
@@ -1023,22 +1214,24 @@ impl Cache<'_> {
         Some(buffer)
     }
 
-    pub(crate) fn extract_shader(
+    /// Share a compiled module, it stays in the pool.
+    pub(crate) fn share_shader(
         &mut self,
         desc: &ShaderDescriptorKey,
-    ) -> Option<wgpu::ShaderModule> {
-        let ShaderKey(key) = self.shader_sets.get_mut(desc)?.pop()?;
-        let (_, _, shader) = self.pool.shaders.remove(key)?;
-        Some(shader)
+    ) -> Option<Arc<wgpu::ShaderModule>> {
+        let &ShaderKey(key) = self.shader_sets.get(desc)?.last()?;
+        let (_, _, shader) = self.pool.shaders.get(key)?;
+        Some(shader.clone())
     }
 
-    pub(crate) fn extract_pipeline(
+    /// Share a compiled pipeline, it stays in the pool.
+    pub(crate) fn share_pipeline(
         &mut self,
         desc: &RenderPipelineKey,
-    ) -> Option<wgpu::RenderPipeline> {
-        let PipelineKey(key) = self.pipeline_sets.get_mut(desc)?.pop()?;
-        let (_, _, pipeline) = self.pool.pipelines.remove(key)?;
-        Some(pipeline)
+    ) -> Option<Arc<wgpu::RenderPipeline>> {
+        let &PipelineKey(key) = self.pipeline_sets.get(desc)?.last()?;
+        let (_, _, pipeline) = self.pool.pipelines.get(key)?;
+        Some(pipeline.clone())
     }
 }
 
@@ -3,14 +3,18 @@ mod encoder;
 use core::{num::NonZeroU32, ops::Range};
 
 use std::borrow::Cow;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
 use crate::buffer::{
     Block, ByteLayout, Color, Descriptor, SampleBits, SampleParts, Texel, Transfer,
 };
 use crate::color_matrix::RowMatrix;
-use crate::command::{Rectangle, Register, RegisterKnob};
+use crate::command::knob::{KnobOp, KnobRecord};
+use crate::command::{
+    DescriptorVar, DumpDescriptor, GenericDescriptor, Rectangle, Register, RegisterKnob,
+    ValidationError,
+};
 use crate::pool::{Pool, PoolKey};
 use crate::{run, shaders};
 
@@ -32,8 +36,11 @@ pub struct Program {
     pub(crate) texture_by_op: HashMap<usize, TextureDescriptor>,
     /// Annotates which function allocates a cacheable buffer.
     pub(crate) buffer_by_op: HashMap<usize, BufferDescriptor>,
-    /// The maps of registers to persistent global knobs indices.
-    pub(crate) knobs: HashMap<RegisterKnob, Knob>,
+    /// The maps of registers to persistent global knobs indices, for each instantiation by its
+    /// generic arguments.
+    pub(crate) knobs: HashMap<RegisterKnob, Vec<(Vec<Descriptor>, Knob)>>,
+    /// The command buffer's records of each knob, shared by all instantiations.
+    pub(crate) knob_records: HashMap<RegisterKnob, KnobRecord>,
     /// The map to shader programs (SPIR-V; for now).
     pub(crate) library: Library,
 }
@@ -127,6 +134,21 @@ pub(crate) enum High {
         src: Register,
         dst: Register,
     },
+    /// Copy all binary data of a buffer into another buffer, starting at an offset of the target.
+    CopyInto {
+        src: Register,
+        dst: Register,
+        target: u64,
+    },
+    /// Copy binary data from a buffer, starting at an offset, into an image or buffer.
+    ///
+    /// The length of the copy is that of the target. With a runtime knob the offset is chosen at launch time, within the bounds of the buffer.
+    CopyAt {
+        src: Register,
+        dst: Register,
+        offset: u64,
+        knob: KnobUser,
+    },
     /// Push one high-level function marker.
     StackPush(Frame),
     /// Pop a high-level function marker.
@@ -202,6 +224,8 @@ pub(crate) enum Initializer {
         /// The target coordinates are relative to this and the fragment shader given by
         /// paint_on_top is only executed within that rectangle.
         viewport: Rectangle,
+        /// The shader painting the quad. Its knob controls the quad coordinates, that is the
+        /// texture coordinates of the selection followed by the target positions, as `[f32; 2]`.
         shader: ParameterizedFragment,
     },
     /// Execute a shader on full textures.
@@ -249,6 +273,11 @@ pub(crate) enum BufferWrite {
         data: Arc<[u8]>,
         knob: Option<Knob>,
     },
+
+    /// Fill the buffer with repetitions of a pattern, whose length is aligned for copies.
+    Splat {
+        pattern: Arc<[u8]>,
+    },
 }
 
 /// Describes a method of calculating the screen space coordinates of the painted quad.
@@ -264,6 +293,13 @@ pub struct ImageBufferPlan {
     pub(crate) buffer: Vec<BufferLayout>,
     pub(crate) by_register: HashMap<Register, RegisterAssignment>,
     pub(crate) by_layout: HashMap<ByteLayout, Texture>,
+    /// Textures whose integer samples are dithered when encoded.
+    pub(crate) dithered: HashSet<Texture>,
+    /// Textures of sums, which are kept in single precision where the device allows it.
+    pub(crate) accumulated: HashSet<Texture>,
+    /// The texture of the IO slot through which a buffer register is passed, see
+    /// [`ImageBufferPlan::alloc_io_slot`].
+    pub(crate) io_slot: HashMap<Register, Texture>,
 }
 
 /// The *definitional* size of a buffer. On the device each can be described as a pure linear u64
@@ -323,6 +359,17 @@ pub(crate) struct Frame {
 pub(crate) struct KnobDescriptor {
     /// The range of the initial data in the binary.
     pub range: Range<usize>,
+    /// The values which the data may take, checked when it is set.
+    pub bound: KnobBound,
+}
+
+/// A constraint on the data of a knob, beyond its length.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum KnobBound {
+    /// Any bytes are valid.
+    Any,
+    /// A `u64` offset which is a multiple of `align` and at most `max`.
+    Offset { max: u64, align: u64 },
 }
 
 /// A gpu buffer associated with an image buffer.
@@ -362,6 +409,32 @@ pub struct Event(pub(crate) usize);
 #[derive(Clone, Copy, Debug, Hash, PartialEq, Eq)]
 pub struct Knob(pub(crate) usize);
 
+/// The description of a knob in a linked program, see [`Program::knobs`].
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct KnobInfo {
+    /// The command which created the knob, by the register in its command buffer.
+    pub register: RegisterKnob,
+    /// The knob of this instantiation of the command.
+    pub knob: Knob,
+    /// The generic arguments of the instantiation, empty outside generic functions.
+    pub generics: Vec<Descriptor>,
+    /// The byte length of the knob's region, if it is determined by the command.
+    ///
+    /// The length of all knobs is known after lowering, see [`Executable::knob_len`].
+    ///
+    /// [`Executable::knob_len`]: crate::run::Executable::knob_len
+    pub len: Option<u64>,
+    /// The alignment of the knob's region in bytes.
+    pub align: u64,
+    /// The kind of command which created the knob.
+    pub op: KnobOp,
+    /// The label given with [`WithKnob::labeled`], if any.
+    ///
+    /// [`WithKnob::labeled`]: crate::command::WithKnob::labeled
+    pub label: Option<String>,
+}
+
 /// A map of features which we may use during encoding.
 #[derive(Clone, Debug)]
 pub struct Capabilities {
@@ -370,14 +443,25 @@ pub struct Capabilities {
 }
 
 #[derive(Debug)]
-#[allow(unused)]
 pub struct LaunchError {
     kind: LaunchErrorKind,
 }
 
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum LaunchErrorKind {
+    /// An internal inconsistency, detected at a line of the library.
     FromLine(u32),
+    /// The pool has no image under the key.
+    MissingImage(PoolKey),
+    /// The register is not an image input of the program.
+    NotAnInput(Register),
+    /// No image was bound to the input register.
+    UnboundInput(Register),
+    /// The adapter did not provide a device for the program.
+    RequestDevice(wgpu::RequestDeviceError),
+    /// The device lacks features required by the program, see [`Program::required_features`].
+    MissingFeatures(wgpu::Features),
 }
 
 /// Low level instruction.
@@ -507,6 +591,16 @@ pub(crate) enum Low {
         size: u64,
     },
 
+    /// Copy between buffers, with a source offset read from the binary data or its knob.
+    CopyBufferToBufferAt {
+        source_buffer: DeviceBuffer,
+        /// The range of the `u64` offset in the binary data.
+        source: Range<usize>,
+        target_buffer: DeviceBuffer,
+        target: u64,
+        size: u64,
+    },
+
     ZeroBuffer {
         start: u64,
         size: u64,
@@ -579,6 +673,10 @@ pub(crate) enum CallBinding {
         texture: Texture,
         register: Register,
     },
+    /// Buffer that is initialized on entry, passed as the bytes of an image.
+    InBuffer { buffer: Buffer, register: Register },
+    /// Buffer that gets initialized by this call, returned as the bytes of an image.
+    OutBuffer { buffer: Buffer, register: Register },
 }
 
 /// FIXME: name... is it appropriate to use the same component `Call` for High and Low since this
@@ -643,6 +741,8 @@ pub(crate) struct RenderPipelineKey {
     pub vertex_entry: &'static str,
     pub fragment_module: ShaderDescriptorKey,
     pub fragment_entry: &'static str,
+    /// The format, blend state and write mask of each color target.
+    pub targets: Vec<wgpu::ColorTargetState>,
     pub primitive: PrimitiveState,
 }
 
@@ -668,6 +768,8 @@ pub(crate) struct FragmentState {
     pub fragment_module: usize,
     pub entry_point: &'static str,
     pub targets: Vec<wgpu::ColorTargetState>,
+    /// The values of specialization constants, named by their decimal id.
+    pub constants: Vec<(String, f64)>,
 }
 
 #[derive(Debug)]
@@ -780,6 +882,17 @@ pub(crate) struct StagingDescriptor {
 
 impl ImageDescriptor {
     pub(crate) fn new(descriptor: &Descriptor) -> Result<Self, LaunchError> {
+        Self::with_features(descriptor, wgpu::Features::empty())
+    }
+
+    /// Describe the texture of an image on a device with some optional features.
+    ///
+    /// This is only used for the textures of sums, other images are described the same on all
+    /// devices such that [`Program::required_features`] determines where they can run.
+    pub(crate) fn with_features(
+        descriptor: &Descriptor,
+        features: wgpu::Features,
+    ) -> Result<Self, LaunchError> {
         fn validate_size(layout: &ByteLayout) -> Option<(NonZeroU32, NonZeroU32)> {
             Some((
                 NonZeroU32::new(layout.width)?,
@@ -836,26 +949,62 @@ impl ImageDescriptor {
                     ..
                 },
             ) => wgpu::TextureFormat::Bgra8Unorm,
+            // The linear format of staging, no conversion needed.
             (
                 Texel {
                     block: Block::Pixel,
-                    bits,
-                    parts,
+                    bits: SampleBits::Float16x4,
+                    parts: SampleParts::RgbA,
                 },
-                Color::Rgb { transfer, .. },
-            )
-            | (
+                Color::Rgb {
+                    transfer: Transfer::Linear,
+                    ..
+                }
+                | Color::Scalars {
+                    transfer: Transfer::Linear,
+                    ..
+                },
+            ) => wgpu::TextureFormat::Rgba16Float,
+            // Single precision is only sampled like the other formats if it is filterable.
+            (
+                Texel {
+                    block: Block::Pixel,
+                    bits: SampleBits::Float32x4,
+                    parts: SampleParts::RgbA,
+                },
+                Color::Rgb {
+                    transfer: Transfer::Linear,
+                    ..
+                }
+                | Color::Scalars {
+                    transfer: Transfer::Linear,
+                    ..
+                },
+            ) if features.contains(wgpu::Features::FLOAT32_FILTERABLE) => {
+                wgpu::TextureFormat::Rgba32Float
+            }
+            // FIXME: very, very duplicate code.
+            // Scalars in Lab texels are the coordinates of CIELAB, which has no color of its own.
+            (
                 Texel {
                     block: Block::Pixel,
                     bits,
-                    parts,
+                    parts: parts @ (SampleParts::LchA | SampleParts::LabA),
+                },
+                Color::SrLab2 { .. }
+                | Color::Scalars {
+                    transfer: Transfer::Linear,
                 },
-                Color::Scalars { transfer, .. },
             ) => {
                 let parameter = shaders::stage::XyzParameter {
-                    transfer: shaders::stage::Transfer::Rgb(*transfer),
+                    transfer: match *parts {
+                        SampleParts::LchA => shaders::stage::Transfer::LabLch,
+                        SampleParts::LabA => shaders::stage::Transfer::Rgb(Transfer::Linear),
+                        _ => return Err(LaunchError::InternalCommandError(line!())),
+                    },
+                    parts: SampleParts::LchA,
                     bits: *bits,
-                    parts: *parts,
+                    dither: false,
                 };
 
                 let result = parameter.linear_format();
@@ -875,21 +1024,25 @@ impl ImageDescriptor {
                 Texel {
                     block: Block::Pixel,
                     bits,
-                    parts: parts @ (SampleParts::LchA | SampleParts::LabA),
+                    parts,
                 },
-                Color::Oklab,
+                Color::Rgb { transfer, .. },
+            )
+            | (
+                Texel {
+                    block: Block::Pixel,
+                    bits,
+                    parts,
+                },
+                Color::Scalars { transfer, .. },
             ) => {
                 let parameter = shaders::stage::XyzParameter {
-                    transfer: match *parts {
-                        SampleParts::LchA => shaders::stage::Transfer::LabLch,
-                        SampleParts::LabA => shaders::stage::Transfer::Rgb(Transfer::Linear),
-                        _ => return Err(LaunchError::InternalCommandError(line!())),
-                    },
-                    parts: SampleParts::LchA,
+                    transfer: shaders::stage::Transfer::Rgb(*transfer),
                     bits: *bits,
+                    parts: *parts,
+                    dither: false,
                 };
 
-                // FIXME: duplicate code.
                 let result = parameter.linear_format();
                 let stage_kind = parameter
                     .stage_kind()
@@ -903,14 +1056,13 @@ impl ImageDescriptor {
 
                 result
             }
-            // FIXME: very, very duplicate code.
             (
                 Texel {
                     block: Block::Pixel,
                     bits,
                     parts: parts @ (SampleParts::LchA | SampleParts::LabA),
                 },
-                Color::SrLab2 { .. },
+                Color::Oklab,
             ) => {
                 let parameter = shaders::stage::XyzParameter {
                     transfer: match *parts {
@@ -920,8 +1072,10 @@ impl ImageDescriptor {
                     },
                     parts: SampleParts::LchA,
                     bits: *bits,
+                    dither: false,
                 };
 
+                // FIXME: duplicate code.
                 let result = parameter.linear_format();
                 let stage_kind = parameter
                     .stage_kind()
@@ -1004,10 +1158,133 @@ pub(crate) struct SamplerDescriptor {
 
 /// The commands could not be made into a program.
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum CompileError {
     // FIXME: turn this warning on to find things to implement.
     // #[deprecated = "We should strive to remove these"]
-    NotYetImplemented,
+    /// The lowering of the named operation is not yet implemented.
+    NotYetImplemented(&'static str),
+    /// The linked functions or types do not match the declarations of a command buffer.
+    LinkMismatch {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    /// A type supplied for a generic of a function does not satisfy its bounds.
+    UnsatisfiedBounds(DescriptorVar),
+    /// The register is not defined where an operation uses it.
+    BadOperand(Register),
+    /// A command buffer to link does not hold its invariants, see [`CommandBuffer::validate`].
+    ///
+    /// [`CommandBuffer::validate`]: crate::command::CommandBuffer::validate
+    InvalidCommands {
+        /// The index of the buffer, the main buffer first and then the functions.
+        link_idx: usize,
+        errors: Vec<ValidationError>,
+    },
+    /// A function invokes itself through the functions it is linked to, with the same types.
+    ///
+    /// Without control flow such an invocation could never return.
+    RecursiveLink {
+        /// The buffers along the cycle by their index, the first of which is repeated last.
+        cycle: Vec<usize>,
+    },
+    /// A declared function has no definition of its name in the linker.
+    ///
+    /// The name is missing if the function was declared without one.
+    UnresolvedSymbol {
+        /// The buffer declaring the function, by its index.
+        link_idx: usize,
+        name: Option<String>,
+    },
+    /// The linker already has a definition of the name.
+    DuplicateSymbol(String),
+    /// The instantiation of a named function failed.
+    Instantiating {
+        name: String,
+        tys: Vec<Descriptor>,
+        error: Box<CompileError>,
+    },
+}
+
+impl core::fmt::Display for CompileError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            CompileError::NotYetImplemented(operation) => {
+                write!(f, "Lowering {operation} is not yet implemented")
+            }
+            CompileError::LinkMismatch {
+                what,
+                expected,
+                actual,
+            } => write!(f, "Expected {expected} {what} for linking, got {actual}"),
+            CompileError::UnsatisfiedBounds(DescriptorVar(var)) => {
+                write!(f, "The type of generic {var} does not satisfy its bounds")
+            }
+            CompileError::BadOperand(Register(reg)) => {
+                write!(f, "Register {reg} is not defined where it is used")
+            }
+            CompileError::InvalidCommands { link_idx, errors } => {
+                write!(f, "Command buffer {link_idx} is invalid")?;
+
+                for error in errors {
+                    write!(f, "\n{error}")?;
+                }
+
+                Ok(())
+            }
+            CompileError::RecursiveLink { cycle } => {
+                write!(f, "Linking recurses through the command buffers")?;
+
+                for link_idx in cycle {
+                    write!(f, " {link_idx}")?;
+                }
+
+                Ok(())
+            }
+            CompileError::UnresolvedSymbol {
+                link_idx,
+                name: Some(name),
+            } => {
+                write!(
+                    f,
+                    "Function {name} of command buffer {link_idx} is not defined"
+                )
+            }
+            CompileError::UnresolvedSymbol {
+                link_idx,
+                name: None,
+            } => {
+                write!(
+                    f,
+                    "A function of command buffer {link_idx} has no name to resolve"
+                )
+            }
+            CompileError::DuplicateSymbol(name) => {
+                write!(f, "Function {name} is already defined")
+            }
+            CompileError::Instantiating { name, tys, error } => {
+                write!(f, "While instantiating {name}")?;
+
+                for (idx, ty) in tys.iter().enumerate() {
+                    let sep = if idx == 0 { " with" } else { "," };
+                    let ty = GenericDescriptor::from(ty.clone());
+                    write!(f, "{sep} {}", DumpDescriptor(&ty))?;
+                }
+
+                write!(f, ": {error}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            CompileError::Instantiating { error, .. } => Some(&**error),
+            _ => None,
+        }
+    }
 }
 
 /// Something won't work with this program and pool combination, no matter the amount of
@@ -1053,12 +1330,65 @@ impl ImageBufferPlan {
         let buffer = Buffer(self.buffer.len());
         self.buffer.push(BufferLayout::Texture(desc.layout.clone()));
         self.by_layout.insert(desc.layout.clone(), texture);
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(
+            texture = texture.0,
+            width = desc.layout.width,
+            height = desc.layout.height,
+            bytes = self.buffer[buffer.0].u64_len(),
+            "allocated texture"
+        );
+
         let assigned = ImageBufferAssignment { buffer, texture };
         self.by_register
             .insert(register, RegisterAssignment::Image(assigned));
         assigned
     }
 
+    /// Assign a register the texture and buffer of another, which is no longer live.
+    ///
+    /// Returns `None` if the other register does not hold an image of the same descriptor.
+    pub(crate) fn alias_texture_for(
+        &mut self,
+        desc: &Descriptor,
+        src: Register,
+        register: Register,
+    ) -> Option<ImageBufferAssignment> {
+        let Some(&RegisterAssignment::Image(assigned)) = self.by_register.get(&src) else {
+            return None;
+        };
+
+        if self.texture[assigned.texture.0] != *desc {
+            return None;
+        }
+
+        self.by_register
+            .insert(register, RegisterAssignment::Image(assigned));
+        Some(assigned)
+    }
+
+    /// Allocate the texture of an IO slot for a buffer parameter.
+    ///
+    /// IO is indexed by the textures of a function, but the register of a buffer parameter has a
+    /// buffer. The slot holds the image of the bytes instead and is never used on the device.
+    pub(crate) fn alloc_io_slot(&mut self, desc: &Descriptor, register: Register) -> Texture {
+        let texture = Texture(self.texture.len());
+        self.texture.push(desc.clone());
+        self.io_slot.insert(register, texture);
+        texture
+    }
+
+    /// Dither the texture when it is quantized to its texel.
+    pub(crate) fn dither(&mut self, texture: Texture) {
+        self.dithered.insert(texture);
+    }
+
+    /// Accumulate sums in the texture, see [`ImageDescriptor::with_features`].
+    pub(crate) fn accumulate(&mut self, texture: Texture) {
+        self.accumulated.insert(texture);
+    }
+
     pub(crate) fn alloc_buffer_for(
         &mut self,
         len: u64,
@@ -1067,6 +1397,10 @@ impl ImageBufferPlan {
     ) -> ByteBufferAssignment {
         let buffer = Buffer(self.buffer.len());
         self.buffer.push(BufferLayout::Linear(len));
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(buffer = buffer.0, bytes = len, "allocated buffer");
+
         let assigned = ByteBufferAssignment { buffer };
         self.by_register
             .insert(register, RegisterAssignment::Buffer(assigned));
@@ -1142,6 +1476,33 @@ impl BufferLayout {
 }
 
 impl Program {
+    /// Enumerate the knobs of the program, ordered by their assigned index.
+    ///
+    /// A generic function has one knob for each of its instantiations. These are all listed, each
+    /// with the generic arguments of its instantiation.
+    pub fn knobs(&self) -> impl Iterator<Item = KnobInfo> + '_ {
+        let mut knobs: Vec<_> = self
+            .knobs
+            .iter()
+            .flat_map(|(&register, instances)| {
+                let record = &self.knob_records[&register];
+
+                instances.iter().map(move |(generics, knob)| KnobInfo {
+                    register,
+                    knob: *knob,
+                    generics: generics.clone(),
+                    len: record.len,
+                    align: record.align,
+                    op: record.op,
+                    label: record.label.clone(),
+                })
+            })
+            .collect();
+
+        knobs.sort_by_key(|info| info.knob.0);
+        knobs.into_iter()
+    }
+
     pub fn describe_register(&self, reg: Register) -> Option<&'_ Descriptor> {
         let main = &self.functions[self.entry_index];
         let texture = main.image_buffers.get_info(reg).ok()?;
@@ -1180,7 +1541,8 @@ impl Program {
     ) -> Result<wgpu::Adapter, MismatchError> {
         // FIXME: no. We could derive 'trait bounds' on the system that are necessary for executing
         // the operations. If we can make sure these are purely additive.
-        Program::minimum_adapter(from)
+        let required = self.required_features();
+        Program::minimum_adapter(from.filter(|adapter| adapter.features().contains(required)))
     }
 
     /// Select an adapter that fulfills the minimum requirements for running programs.
@@ -1254,9 +1616,33 @@ impl Program {
 
     /// Return a descriptor for a device that's capable of executing the program.
     pub fn device_descriptor(&self) -> wgpu::DeviceDescriptor<'static> {
-        // FIXME: no. We could derive 'trait bounds' on the system that are necessary for executing
-        // the operations. If we can make sure these are purely additive.
-        Self::minimal_device_descriptor()
+        let mut descriptor = Self::minimal_device_descriptor();
+        descriptor.required_features |= self.required_features();
+        descriptor
+    }
+
+    /// The features which a device must have to execute the program.
+    ///
+    /// A summed-area table, see [`CommandBuffer::integral_image`], accumulates into single
+    /// precision textures. These are sampled like all other textures and need to be filterable.
+    ///
+    /// [`CommandBuffer::integral_image`]: crate::command::CommandBuffer::integral_image
+    pub fn required_features(&self) -> wgpu::Features {
+        let accumulates = self.ops.iter().any(|high| {
+            matches!(
+                high,
+                High::DrawInto {
+                    fn_: Initializer::PaintFullScreen { shader },
+                    ..
+                } if matches!(shader.invocation, shaders::FragmentShaderInvocation::PrefixSum(_))
+            )
+        });
+
+        if accumulates {
+            wgpu::Features::FLOAT32_FILTERABLE
+        } else {
+            wgpu::Features::empty()
+        }
     }
 
     pub fn minimal_device_descriptor() -> wgpu::DeviceDescriptor<'static> {
@@ -1301,6 +1687,10 @@ impl Program {
         }
     }
 
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(level = "debug", skip_all, fields(functions = self.functions.len()))
+    )]
     pub fn lower_to(&self, capabilities: Capabilities) -> Result<run::Executable, LaunchError> {
         let main = &self.functions[self.entry_index];
 
@@ -1428,6 +1818,13 @@ impl Program {
         function: &FunctionLinked,
         pool_plan: Option<&ImagePoolPlan>,
     ) -> Result<Encoder, LaunchError> {
+        let missing = self.required_features().difference(capabilities.features);
+        if !missing.is_empty() {
+            return Err(LaunchError {
+                kind: LaunchErrorKind::MissingFeatures(missing),
+            });
+        }
+
         let mut encoder = Encoder::new(self.library.clone());
         encoder.enable_capabilities(capabilities);
 
@@ -1455,19 +1852,35 @@ impl Program {
                     // format to avoid regressions.
                 }
                 &High::Input(dst) => {
-                    // Identify how we ingest this image.
-                    // If it is a texture format that we support then we will allocate and upload
-                    // it directly. If it is not then we will allocate a generic version capable of
-                    // holding a lossless convert variant of it and add instructions to convert
-                    // into that buffer.
-                    encoder.copy_input_to_buffer(dst)?;
-                    encoder.copy_buffer_to_staging(dst)?;
+                    let is_buffer =
+                        matches!(encoder.allocate_register(dst)?, RegisterMap::Buffer { .. });
+
+                    if is_buffer {
+                        // A buffer parameter, passed as the bytes of an image.
+                        encoder.copy_input_to_byte_buffer(dst)?;
+                    } else {
+                        // Identify how we ingest this image.
+                        // If it is a texture format that we support then we will allocate and
+                        // upload it directly. If it is not then we will allocate a generic version
+                        // capable of holding a lossless convert variant of it and add instructions
+                        // to convert into that buffer.
+                        encoder.copy_input_to_buffer(dst)?;
+                        encoder.copy_buffer_to_staging(dst)?;
+                    }
                 }
                 &High::Output { src, dst } => {
-                    // Identify if we need to transform the texture from the internal format to the
-                    // one actually chosen for this texture.
-                    encoder.copy_staging_to_buffer(src)?;
-                    encoder.copy_buffer_to_output(src, dst)?;
+                    let is_buffer =
+                        matches!(encoder.allocate_register(src)?, RegisterMap::Buffer { .. });
+
+                    if is_buffer {
+                        // A buffer result, returned as the bytes of an image.
+                        encoder.copy_byte_buffer_to_output(src, dst)?;
+                    } else {
+                        // Identify if we need to transform the texture from the internal format to
+                        // the one actually chosen for this texture.
+                        encoder.copy_staging_to_buffer(src)?;
+                        encoder.copy_buffer_to_output(src, dst)?;
+                    }
                 }
                 &High::Render { src, dst } => {
                     encoder.render_staging_to_output(src, dst)?;
@@ -1574,6 +1987,108 @@ impl Program {
                         encoder.copy_buffer_to_staging(*dst)?;
                     }
                 }
+                High::CopyInto { src, dst, target } => {
+                    let (source_buffer, size) = match encoder.allocate_register(*src)? {
+                        &RegisterMap::Buffer {
+                            buffer,
+                            ref buffer_layout,
+                            ..
+                        } => (buffer, buffer_layout.u64_len()),
+                        RegisterMap::Image { .. } => {
+                            return Err(LaunchError::InternalCommandError(line!()))
+                        }
+                    };
+
+                    let target_buffer = match encoder.allocate_register(*dst)? {
+                        RegisterMap::Buffer { buffer, .. } => *buffer,
+                        RegisterMap::Image { .. } => {
+                            return Err(LaunchError::InternalCommandError(line!()))
+                        }
+                    };
+
+                    encoder.push(Low::BeginCommands)?;
+                    encoder.push(Low::CopyBufferToBuffer {
+                        source_buffer,
+                        source: 0,
+                        target_buffer,
+                        target: *target,
+                        size,
+                    })?;
+                    encoder.push(Low::EndCommands)?;
+                    encoder.push(Low::RunTopCommand)?;
+                }
+                High::CopyAt {
+                    src,
+                    dst,
+                    offset,
+                    knob,
+                } => {
+                    let (source_buffer, source_len) = match encoder.allocate_register(*src)? {
+                        &RegisterMap::Buffer {
+                            buffer,
+                            ref buffer_layout,
+                            ..
+                        } => (buffer, buffer_layout.u64_len()),
+                        RegisterMap::Image { .. } => {
+                            return Err(LaunchError::InternalCommandError(line!()))
+                        }
+                    };
+
+                    let (target_buffer, size) = match encoder.allocate_register(*dst)? {
+                        &RegisterMap::Image {
+                            buffer,
+                            ref buffer_layout,
+                            ..
+                        } => (buffer, buffer_layout.u64_len()),
+                        &RegisterMap::Buffer {
+                            buffer,
+                            ref buffer_layout,
+                            ..
+                        } => (buffer, buffer_layout.u64_len()),
+                    };
+
+                    let max = source_len
+                        .checked_sub(size)
+                        .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+
+                    encoder.push(Low::BeginCommands)?;
+                    match knob {
+                        KnobUser::None => {
+                            encoder.push(Low::CopyBufferToBuffer {
+                                source_buffer,
+                                source: *offset,
+                                target_buffer,
+                                target: 0,
+                                size,
+                            })?;
+                        }
+                        KnobUser::Runtime(_) => {
+                            let bound = KnobBound::Offset {
+                                max,
+                                align: wgpu::COPY_BUFFER_ALIGNMENT,
+                            };
+
+                            let source = encoder.ingest_copy_offset(knob, *offset, bound)?;
+                            encoder.push(Low::CopyBufferToBufferAt {
+                                source_buffer,
+                                source,
+                                target_buffer,
+                                target: 0,
+                                size,
+                            })?;
+                        }
+                        // Rejected when the knob is recorded, the offset is checked on the host.
+                        KnobUser::Buffer { .. } => {
+                            return Err(LaunchError::InternalCommandError(line!()))
+                        }
+                    }
+                    encoder.push(Low::EndCommands)?;
+                    encoder.push(Low::RunTopCommand)?;
+
+                    if let RegisterMap::Image { .. } = encoder.allocate_register(*dst)? {
+                        encoder.copy_buffer_to_staging(*dst)?;
+                    }
+                }
                 High::WriteInto { dst, fn_ } => {
                     encoder.prepare_buffer_write(fn_, *dst)?;
                 }
@@ -1625,6 +2140,25 @@ impl Program {
                                     in_io,
                                 });
                             }
+                            &CallBinding::InBuffer { buffer, register }
+                            | &CallBinding::OutBuffer { buffer, register } => {
+                                let RegisterMap::Buffer { buffer: device, .. } =
+                                    encoder.allocate_register(register)?.clone()
+                                else {
+                                    return Err(LaunchError::InternalCommandError(line!()));
+                                };
+
+                                // The callee has the slot of the same image of the bytes.
+                                let len = function.image_buffers.buffer[buffer.0].u64_len();
+                                let descriptor = crate::command::bytes_as_image(len)
+                                    .ok_or(LaunchError::InternalCommandError(line!()))?;
+
+                                io_buffers.push(CallImageArgument {
+                                    buffer: device,
+                                    descriptor,
+                                    in_io,
+                                });
+                            }
                         }
                     }
 
@@ -1696,6 +2230,7 @@ impl Program {
             | Low::CopyBufferToTexture { .. }
             | Low::CopyTextureToBuffer { .. }
             | Low::CopyBufferToBuffer { .. }
+            | Low::CopyBufferToBufferAt { .. }
             | Low::ZeroBuffer { .. }
             | Low::ReadBuffer { .. }
             | Low::StackFrame(_)
@@ -1715,7 +2250,9 @@ impl Launcher<'_> {
     /// the key in the pool, or when the image in the pool does not match the declared format.
     pub fn bind(mut self, reg: Register, img: PoolKey) -> Result<Self, LaunchError> {
         if self.pool.entry(img).is_none() {
-            return Err(LaunchError::InternalCommandError(line!()));
+            return Err(LaunchError {
+                kind: LaunchErrorKind::MissingImage(img),
+            });
         }
 
         let RegisterAssignment::Image(ImageBufferAssignment {
@@ -1723,7 +2260,9 @@ impl Launcher<'_> {
             ..
         }) = self.main.image_buffers.get_register_resources(reg)?
         else {
-            return Err(LaunchError::InternalCommandError(line!()));
+            return Err(LaunchError {
+                kind: LaunchErrorKind::NotAnInput(reg),
+            });
         };
 
         self.pool_plan.plan.insert(reg, img);
@@ -1762,7 +2301,9 @@ impl Launcher<'_> {
         for high in &self.program.ops {
             if let High::Input(register) = *high {
                 if self.pool_plan.get_texture(register).is_none() {
-                    return Err(LaunchError::InternalCommandError(line!()));
+                    return Err(LaunchError {
+                        kind: LaunchErrorKind::UnboundInput(register),
+                    });
                 }
             }
         }
@@ -1773,7 +2314,11 @@ impl Launcher<'_> {
         let request = Box::pin(request);
         let (device, queue) = match run::block_on(request, None) {
             Ok(tuple) => tuple,
-            Err(_) => return Err(LaunchError::InternalCommandError(line!())),
+            Err(err) => {
+                return Err(LaunchError {
+                    kind: LaunchErrorKind::RequestDevice(err),
+                })
+            }
         };
 
         let capabilities = Capabilities::from(&device);
@@ -1992,9 +2537,59 @@ impl KnobDescriptor {
             ..self
         }
     }
+
+    /// Check that data, of the length of the range, is a valid value for the knob.
+    pub fn admits(&self, data: &[u8]) -> bool {
+        match self.bound {
+            KnobBound::Any => true,
+            KnobBound::Offset { max, align } => {
+                let Ok(bytes) = <[u8; 8]>::try_from(data) else {
+                    return false;
+                };
+
+                let offset = u64::from_ne_bytes(bytes);
+                offset <= max && offset % align == 0
+            }
+        }
+    }
+}
+
+impl core::fmt::Display for LaunchError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match &self.kind {
+            LaunchErrorKind::FromLine(line) => {
+                write!(f, "Internal error while launching, at line {line}")
+            }
+            LaunchErrorKind::MissingImage(key) => write!(f, "No image in the pool under {key:?}"),
+            LaunchErrorKind::NotAnInput(Register(reg)) => {
+                write!(f, "Register {reg} is not an image input")
+            }
+            LaunchErrorKind::UnboundInput(Register(reg)) => {
+                write!(f, "No image bound to the input register {reg}")
+            }
+            LaunchErrorKind::RequestDevice(_) => write!(f, "Failed to request a device"),
+            LaunchErrorKind::MissingFeatures(features) => {
+                write!(f, "The device lacks required features {features:?}")
+            }
+        }
+    }
+}
+
+impl core::error::Error for LaunchError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match &self.kind {
+            LaunchErrorKind::RequestDevice(err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 impl LaunchError {
+    /// The reason for the error.
+    pub fn kind(&self) -> &LaunchErrorKind {
+        &self.kind
+    }
+
     #[allow(non_snake_case)]
     // FIXME: find a better error representation but it's okay for now.
     // #[deprecated = "This should be cleaned up"]
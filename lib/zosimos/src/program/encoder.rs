@@ -11,10 +11,11 @@ use crate::program::{
     BufferDescriptorInit, BufferInitContent, BufferLayout, BufferUsage, ByteBufferAssignment,
     Capabilities, ColorAttachmentDescriptor, DeviceBuffer, DeviceTexture, Event, FragmentState,
     ImageBufferAssignment, ImageBufferPlan, ImageDescriptor, ImagePoolPlan, Initializer,
-    Instruction, Knob, KnobDescriptor, LaunchError, Library, Low, PipelineLayoutDescriptor,
-    PipelineLayoutKey, PrimitiveState, RegisterAssignment, RenderPassDescriptor,
-    RenderPipelineDescriptor, RenderPipelineKey, SamplerDescriptor, ShaderDescriptor,
-    ShaderDescriptorKey, Texture, TextureDescriptor, TextureViewDescriptor, VertexState,
+    Instruction, Knob, KnobBound, KnobDescriptor, LaunchError, Library, Low,
+    PipelineLayoutDescriptor, PipelineLayoutKey, PrimitiveState, RegisterAssignment,
+    RenderPassDescriptor, RenderPipelineDescriptor, RenderPipelineKey, SamplerDescriptor,
+    ShaderDescriptor, ShaderDescriptorKey, Texture, TextureDescriptor, TextureViewDescriptor,
+    VertexState,
 };
 use crate::util::ExtendOne;
 use crate::{run, shaders};
@@ -56,6 +57,8 @@ pub(crate) struct Encoder<Instructions: ExtendOne<Low> = Vec<Low>> {
     /// the pool to the state exactly before, even when the program is stopped. In those cases we
     /// might still hold images in textures. This instructs us to trace where.
     trace_pool_plan: bool,
+    /// The optional features of the device, which may allow textures of sums without staging.
+    features: wgpu::Features,
     /// How we mapped registers to images in the pool.
     pool_plan: ImagePoolPlan,
     /// Declare where we put our input registers.
@@ -81,6 +84,8 @@ pub(crate) struct Encoder<Instructions: ExtendOne<Low> = Vec<Low>> {
     stage_group_layout: HashMap<u32, usize>,
     known_samplers: HashMap<SamplerDescriptor, usize>,
     fragment_shaders: HashMap<shaders::FragmentShaderKey, usize>,
+    /// The specialization constants of fragment shader modules, by their index.
+    fragment_constants: HashMap<usize, Vec<(u32, u32)>>,
     vertex_shaders: HashMap<shaders::VertexShader, usize>,
     simple_quad_buffer: Option<DeviceBuffer>,
     /// The render pipeline state for staging a texture.
@@ -229,8 +234,14 @@ struct SimpleRenderPipelineDescriptor<'data> {
     pipeline_target: PipelineTarget,
     /// Bind data for (set 0, binding 0).
     vertex_bind_data: BufferBind<'data>,
+    /// How the vertex bind buffer is being filled.
+    vertex_knob: KnobUsage,
     /// Texture for (set 1, binding 0)
     fragment_texture: TextureBind,
+    /// How the sampler bound with the textures addresses coordinates outside of them.
+    fragment_sampler: wgpu::AddressMode,
+    /// How the sampler bound with the textures filters between their texels.
+    fragment_filter: wgpu::FilterMode,
     /// Texture for (set 2, binding 0)
     fragment_bind_data: BufferBind<'data>,
     /// How the fragment bind buffer is being filled.
@@ -306,6 +317,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             is_in_render_pass: Default::default(),
             buffer_plan: Default::default(),
             trace_pool_plan: Default::default(),
+            features: wgpu::Features::empty(),
             pool_plan: Default::default(),
             input_map: Default::default(),
             output_map: Default::default(),
@@ -317,6 +329,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             stage_group_layout: Default::default(),
             known_samplers: Default::default(),
             fragment_shaders: Default::default(),
+            fragment_constants: Default::default(),
             vertex_shaders: Default::default(),
             simple_quad_buffer: Default::default(),
             staged_to_pipelines: Default::default(),
@@ -335,10 +348,9 @@ impl<I: ExtendOne<Low>> Encoder<I> {
     /// Some features require GPU support. At this point we decide if our request has succeeded and
     /// we might poly-fill it with a compute shader or something similar.
     pub(crate) fn enable_capabilities(&mut self, caps: &Capabilities) {
-        // FIXME: currently nothing uses features or limits.
-        // Which is wrong, we can use features to skip some staging. We might also have some
-        // slightly different shader features such as using push constants in some cases?
-        let _ = caps;
+        // Features are used to skip some staging, limits are not used yet. We might also have
+        // some slightly different shader features such as using push constants in some cases?
+        self.features = caps.features;
     }
 
     pub(crate) fn set_buffer_plan(&mut self, plan: &ImageBufferPlan) {
@@ -464,6 +476,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             | Low::CopyBufferToTexture { .. }
             | Low::CopyTextureToBuffer { .. }
             | Low::CopyBufferToBuffer { .. }
+            | Low::CopyBufferToBufferAt { .. }
             | Low::ZeroBuffer { .. } => {
                 if !self.is_in_command_encoder {
                     return Err(LaunchError::InternalCommandError(line!()));
@@ -512,7 +525,19 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         texture: Texture,
     ) -> Result<ImageDescriptor, LaunchError> {
         let descriptor = &self.buffer_plan.texture[texture.0];
-        ImageDescriptor::new(descriptor)
+        let mut descriptor = if self.buffer_plan.accumulated.contains(&texture) {
+            ImageDescriptor::with_features(descriptor, self.features)?
+        } else {
+            ImageDescriptor::new(descriptor)?
+        };
+
+        if self.buffer_plan.dithered.contains(&texture) {
+            if let Some(staging) = &mut descriptor.staging {
+                staging.parameter.dither = true;
+            }
+        }
+
+        Ok(descriptor)
     }
 
     pub(crate) fn push_operand(&mut self, texture: Texture) -> Result<(), LaunchError> {
@@ -824,6 +849,42 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         Ok(())
     }
 
+    /// Copy the bytes of an input image to a buffer register, for a buffer parameter.
+    pub(crate) fn copy_input_to_byte_buffer(&mut self, idx: Register) -> Result<(), LaunchError> {
+        let RegisterMap::Buffer { buffer, .. } = self.allocate_register(idx)?.clone() else {
+            return Err(LaunchError::InternalCommandError(line!()));
+        };
+
+        let source_image = *self
+            .buffer_plan
+            .io_slot
+            .get(&idx)
+            .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+        let write_event = self.make_event();
+        self.input_map.insert(idx, source_image);
+
+        let descriptor = &self.buffer_plan.texture[source_image.0];
+        let size = descriptor.size();
+        let target_layout = descriptor
+            .to_aligned()
+            .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+
+        // The bytes of the image are exactly those of the buffer, there is no other layout.
+        let inst = self.push(Low::WriteImageToBuffer {
+            copy_dst_buffer: buffer,
+            source_image,
+            size,
+            offset: (0, 0),
+            target_buffer: buffer,
+            target_layout,
+            write_event,
+        })?;
+
+        self.info.skip_by_op.insert(inst, write_event);
+
+        Ok(())
+    }
+
     /// Copy from memory visible buffer to the texture.
     pub(crate) fn copy_buffer_to_staging(&mut self, idx: Register) -> Result<(), LaunchError> {
         let RegisterMap::Image {
@@ -993,6 +1054,40 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         Ok(())
     }
 
+    /// Copy a buffer register to the bytes of an output image, for a buffer result.
+    ///
+    /// The output is a buffer of the caller, a host image would require the buffer to be mapped.
+    pub(crate) fn copy_byte_buffer_to_output(
+        &mut self,
+        idx: Register,
+        dst: Register,
+    ) -> Result<(), LaunchError> {
+        let RegisterMap::Buffer { buffer, .. } = self.allocate_register(idx)?.clone() else {
+            return Err(LaunchError::InternalCommandError(line!()));
+        };
+
+        let target_image = self.ingest_image_data(dst)?;
+        self.output_map.insert(dst, target_image);
+
+        let descriptor = &self.buffer_plan.texture[target_image.0];
+        let size = descriptor.size();
+        let source_layout = descriptor
+            .to_aligned()
+            .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+
+        self.plan_gpu_effects_visible()?;
+        self.push(Low::ReadBuffer {
+            copy_src_buffer: buffer,
+            source_buffer: buffer,
+            source_layout,
+            size,
+            offset: (0, 0),
+            target_image,
+        })?;
+
+        Ok(())
+    }
+
     /// Copy the memory buffer to the output.
     pub(crate) fn copy_buffer_to_output(
         &mut self,
@@ -1065,9 +1160,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         // FIXME: have the caller provide this directly?
         let dst_format = {
             let reg_texture = self.buffer_plan.get_register_texture(dst)?;
-            let descriptor = &self.buffer_plan.texture[reg_texture.0];
-            let descriptor = ImageDescriptor::new(descriptor)?;
-            descriptor.format
+            self.make_texture_descriptor(reg_texture)?.format
         };
 
         self.copy_staging_to_texture(reg_texture)?;
@@ -1087,7 +1180,11 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             let key = shader.key();
             let spirv = shader.spirv_source();
 
-            let fragment = self.fragment_shader(key, shader_include_to_spirv(&*spirv))?;
+            let fragment = self.fragment_shader(
+                key,
+                shader_include_to_spirv(&*spirv),
+                shader.specialization(),
+            )?;
             let fragment_bind_data = shader
                 .binary_data(&mut self.binary_data)
                 .map(|data| self.ingest_buffer_init(data))
@@ -1102,7 +1199,10 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                 vertex_bind_data: BufferBind::Set {
                     data: bytemuck::cast_slice(&Self::FULL_VERTEX_BUFFER[..]),
                 },
+                vertex_knob: KnobUsage::Noop,
                 fragment_texture: TextureBind::Textures(arguments as usize),
+                fragment_sampler: shader.address_mode(),
+                fragment_filter: shader.filter_mode(),
                 fragment_bind_data,
                 fragment_knob: KnobUsage::Noop,
                 vertex: ShaderBind::ShaderMain(vertex),
@@ -1372,20 +1472,33 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         Ok(idx)
     }
 
+    /// Create a fragment shader module, to be specialized with constants in its pipelines.
+    ///
+    /// The constants are part of the key such that differently specialized pipelines of the same
+    /// module are not confused with each other.
     fn fragment_shader(
         &mut self,
         kind: Option<shaders::FragmentShaderKey>,
         source: Cow<'static, [u32]>,
+        constants: Vec<(u32, u32)>,
     ) -> Result<usize, LaunchError> {
+        let kind = kind.map(|key| key.specialize(&constants));
+
         if let Some(&shader) = kind.as_ref().and_then(|k| self.fragment_shaders.get(&k)) {
             return Ok(shader);
         }
 
-        self.shader(ShaderDescriptor {
+        let shader = self.shader(ShaderDescriptor {
             name: "",
             source_spirv: source,
             key: kind.map(Into::into),
-        })
+        })?;
+
+        if !constants.is_empty() {
+            self.fragment_constants.insert(shader, constants);
+        }
+
+        Ok(shader)
     }
 
     fn vertex_shader(
@@ -1459,6 +1572,13 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             ShaderBind::Shader { id, entry_point } => (id, entry_point),
         };
 
+        // Careful of `RenderPipelineKey` if changed.
+        let targets = vec![wgpu::ColorTargetState {
+            blend: None,
+            write_mask: wgpu::ColorWrites::ALL,
+            format,
+        }];
+
         let desc_vertex = self.info.shader_by_idx.get(&vertex);
         let desc_fragment = self.info.shader_by_idx.get(&fragment);
         match (desc_vertex, desc_fragment) {
@@ -1469,6 +1589,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     vertex_entry: vertex_entry_point,
                     fragment_module: f.clone(),
                     fragment_entry: fragment_entry_point,
+                    targets: targets.clone(),
                     primitive: PrimitiveState::TriangleStrip,
                 };
 
@@ -1488,12 +1609,18 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             fragment: FragmentState {
                 entry_point: fragment_entry_point,
                 fragment_module: fragment,
-                // Careful of `RenderPipelineKey` if changed.
-                targets: vec![wgpu::ColorTargetState {
-                    blend: None,
-                    write_mask: wgpu::ColorWrites::ALL,
-                    format,
-                }],
+                // Part of `RenderPipelineKey` through the key of the fragment module.
+                constants: self.fragment_constants.get(&fragment).map_or_else(
+                    Vec::new,
+                    |constants| {
+                        constants
+                            .iter()
+                            .map(|&(id, value)| (id.to_string(), f64::from(value)))
+                            .collect()
+                    },
+                ),
+                // Part of `RenderPipelineKey`.
+                targets,
             },
             // Careful of `RenderPipelineKey` if changed.
             primitive: PrimitiveState::TriangleStrip,
@@ -1524,16 +1651,21 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             })
     }
 
-    fn make_bind_group_sampled_texture(&mut self, count: usize) -> Result<usize, LaunchError> {
+    fn make_bind_group_sampled_texture(
+        &mut self,
+        count: usize,
+        address_mode: wgpu::AddressMode,
+        resize_filter: wgpu::FilterMode,
+    ) -> Result<usize, LaunchError> {
         let start_of_operands = match self.operands.len().checked_sub(count) {
             None => return Err(LaunchError::InternalCommandError(line!())),
             Some(i) => i,
         };
 
         let sampler = self.make_sampler(SamplerDescriptor {
-            address_mode: wgpu::AddressMode::default(),
+            address_mode,
             border_color: None,
-            resize_filter: wgpu::FilterMode::Nearest,
+            resize_filter,
         });
 
         let mut entries = vec![BindingResource::Sampler(sampler)];
@@ -1706,7 +1838,11 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         let group = match &descriptor.fragment_texture {
             TextureBind::Textures(0) => None,
             &TextureBind::Textures(count) => {
-                let group = self.make_bind_group_sampled_texture(count)?;
+                let group = self.make_bind_group_sampled_texture(
+                    count,
+                    descriptor.fragment_sampler,
+                    descriptor.fragment_filter,
+                )?;
                 Some(group)
             }
             &TextureBind::PreComputedGroup { group, .. } => Some(group),
@@ -1715,7 +1851,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         let vertex_layout = self.make_quad_bind_group();
         let vertex_bind = self.make_bound_buffer(
             descriptor.vertex_bind_data,
-            /* we do not permit knob for the vertex data */ KnobUsage::Noop,
+            descriptor.vertex_knob,
             vertex_layout,
         )?;
 
@@ -1815,20 +1951,21 @@ impl<I: ExtendOne<Low>> Encoder<I> {
             Initializer::PaintToSelection { texture, selection, target: target_coords, viewport, shader } => {
                 let (tex_width, tex_height) = self.texture_map[texture].format.size;
 
-                // FIXME: choose this shader depending on whether target_coords are knob'd or not.
-                // Since the screen space transform depends on sizes, the user in commands.rs can
-                // not and importantly shouldn't need to care.
                 let vertex = self.vertex_shader(
                     Some(shaders::VertexShader::Noop),
                     shader_include_to_spirv(&*self.library.core.vert_noop))?;
 
-                let super::ParameterizedFragment { invocation, knob: _ } = shader;
+                // The knob of a paint to a selection controls its quad coordinates.
+                let super::ParameterizedFragment { invocation, knob } = shader;
 
                 let shader = invocation.shader();
                 let key = shader.key();
                 let spirv = shader.spirv_source();
 
-                let fragment = self.fragment_shader(key, shader_include_to_spirv(&*spirv))?;
+                let fragment = self.fragment_shader(
+                    key,
+                    shader_include_to_spirv(&*spirv),
+                    shader.specialization())?;
 
                 let buffer: [[f32; 2]; 8];
                 let min_u = (selection.x as f32) / (tex_width.get() as f32);
@@ -1851,14 +1988,17 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     coords[3],
                 ];
 
+                let data = self.ingest_data(&buffer[..]);
+                let vertex_knob = self.plan_knob_data_range(knob, data.clone())?;
+
                 self.prepare_simple_pipeline(SimpleRenderPipelineDescriptor{
                     pipeline_target: PipelineTarget::Texture(target),
-                    vertex_bind_data: BufferBind::Set {
-                        data: bytemuck::cast_slice(&buffer[..]),
-                    },
+                    vertex_bind_data: BufferBind::Planned { data },
+                    vertex_knob,
                     fragment_texture: TextureBind::Textures(1),
+                    fragment_sampler: shader.address_mode(),
+                    fragment_filter: shader.filter_mode(),
                     fragment_bind_data: BufferBind::None,
-                    // FIXME: see knob'able data.
                     fragment_knob: KnobUsage::Noop,
                     vertex: ShaderBind::ShaderMain(vertex),
                     fragment: ShaderBind::ShaderMain(fragment),
@@ -1875,7 +2015,10 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                 let key = shader.key();
                 let spirv = shader.spirv_source();
 
-                let fragment = self.fragment_shader(key, shader_include_to_spirv(&*spirv))?;
+                let fragment = self.fragment_shader(
+                    key,
+                    shader_include_to_spirv(&*spirv),
+                    shader.specialization())?;
                 let fragment_bind_data = shader.binary_data(&mut self.binary_data)
                     .map(|data| self.ingest_buffer_init(data))
                     .map(|data| BufferBind::Planned { data })
@@ -1889,7 +2032,10 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     vertex_bind_data: BufferBind::Set {
                         data: bytemuck::cast_slice(&Self::FULL_VERTEX_BUFFER[..]),
                     },
+                    vertex_knob: KnobUsage::Noop,
                     fragment_texture: TextureBind::Textures(arguments as usize),
+                    fragment_sampler: shader.address_mode(),
+                    fragment_filter: shader.filter_mode(),
                     fragment_bind_data,
                     fragment_knob,
                     vertex: ShaderBind::ShaderMain(vertex),
@@ -1905,7 +2051,8 @@ impl<I: ExtendOne<Low>> Encoder<I> {
 
                 let fragment = self.fragment_shader(
                     Some(shaders::FragmentShaderKey::Convert(shaders::Direction::Decode, *stage_kind)),
-                    shader_include_to_spirv(&*shader))?;
+                    shader_include_to_spirv(&*shader),
+                    vec![])?;
 
                 let buffer = parameter.serialize_std140();
                 // FIXME: see below, shaderc requires renamed entry points to "main".
@@ -1926,10 +2073,13 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     vertex_bind_data: BufferBind::Set {
                         data: bytemuck::cast_slice(&Self::FULL_VERTEX_BUFFER[..]),
                     },
+                    vertex_knob: KnobUsage::Noop,
                     fragment_texture: TextureBind::PreComputedGroup {
                         group,
                         layout,
                     },
+                    fragment_sampler: wgpu::AddressMode::default(),
+                    fragment_filter: wgpu::FilterMode::Nearest,
                     fragment_bind_data: BufferBind::Set {
                         data: bytemuck::cast_slice(&buffer[..]),
                     },
@@ -1953,7 +2103,8 @@ impl<I: ExtendOne<Low>> Encoder<I> {
 
                 let fragment = self.fragment_shader(
                     Some(shaders::FragmentShaderKey::Convert(shaders::Direction::Encode, *stage_kind)),
-                    shader_include_to_spirv(&*shader))?;
+                    shader_include_to_spirv(&*shader),
+                    vec![])?;
 
                 let buffer = parameter.serialize_std140();
                 // FIXME: see below, shaderc requires renamed entry points to "main".
@@ -1978,10 +2129,13 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     vertex_bind_data: BufferBind::Set {
                         data: bytemuck::cast_slice(&Self::FULL_VERTEX_BUFFER[..]),
                     },
+                    vertex_knob: KnobUsage::Noop,
                     fragment_texture: TextureBind::PreComputedGroup {
                         group,
                         layout,
                     },
+                    fragment_sampler: wgpu::AddressMode::default(),
+                    fragment_filter: wgpu::FilterMode::Nearest,
                     fragment_bind_data: BufferBind::Set {
                         data: bytemuck::cast_slice(&buffer[..]),
                     },
@@ -2083,6 +2237,79 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                 self.push(Low::EndCommands)?;
                 self.push(Low::RunTopCommand)?;
 
+                Ok(())
+            }
+            super::BufferWrite::Splat { pattern } => {
+                let buf_layout = self
+                    .buffer_plan
+                    .buffer
+                    .get(dst.0)
+                    .cloned()
+                    .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+
+                let map = self
+                    .buffer_map
+                    .get(&dst)
+                    .map(Clone::clone)
+                    .ok_or_else(|| LaunchError::InternalCommandError(line!()))?;
+
+                let size = buf_layout.u64_len();
+                let data_range = self.ingest_data(pattern);
+                let seed = (data_range.len() as u64).min(size);
+
+                let stage_buffer = DeviceBuffer(self.buffers);
+                self.push(Low::BufferInit(BufferDescriptorInit {
+                    content: data_range,
+                    usage: BufferUsage::DataBuffer,
+                }))?;
+
+                // The filled prefix doubles with each step. A buffer can not be copied into
+                // itself, so each step goes through a scratch buffer for the largest copy.
+                let mut copies = vec![];
+                let mut filled = seed;
+                while filled < size {
+                    let len = filled.min(size - filled);
+                    copies.push((filled, len));
+                    filled += len;
+                }
+
+                let scratch = DeviceBuffer(self.buffers);
+                if let Some(scratch_size) = copies.iter().map(|&(_, len)| len).max() {
+                    self.push(Low::Buffer(BufferDescriptor {
+                        size: scratch_size,
+                        usage: BufferUsage::DataBuffer,
+                    }))?;
+                }
+
+                self.push(Low::BeginCommands)?;
+                self.push(Low::CopyBufferToBuffer {
+                    source_buffer: stage_buffer,
+                    source: 0,
+                    target_buffer: map.device,
+                    target: 0,
+                    size: seed,
+                })?;
+
+                for (target, len) in copies {
+                    self.push(Low::CopyBufferToBuffer {
+                        source_buffer: map.device,
+                        source: 0,
+                        target_buffer: scratch,
+                        target: 0,
+                        size: len,
+                    })?;
+                    self.push(Low::CopyBufferToBuffer {
+                        source_buffer: scratch,
+                        source: 0,
+                        target_buffer: map.device,
+                        target,
+                        size: len,
+                    })?;
+                }
+
+                self.push(Low::EndCommands)?;
+                self.push(Low::RunTopCommand)?;
+
                 Ok(())
             }
         }
@@ -2110,6 +2337,15 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         &mut self,
         knob: &KnobUser,
         data: Range<usize>,
+    ) -> Result<KnobUsage, LaunchError> {
+        self.plan_knob_bounded(knob, data, KnobBound::Any)
+    }
+
+    fn plan_knob_bounded(
+        &mut self,
+        knob: &KnobUser,
+        data: Range<usize>,
+        bound: KnobBound,
     ) -> Result<KnobUsage, LaunchError> {
         match knob {
             KnobUser::None => return Ok(KnobUsage::Noop),
@@ -2118,6 +2354,7 @@ impl<I: ExtendOne<Low>> Encoder<I> {
                     knob,
                     KnobDescriptor {
                         range: data.clone(),
+                        bound,
                     },
                 );
 
@@ -2134,6 +2371,23 @@ impl<I: ExtendOne<Low>> Encoder<I> {
         }
     }
 
+    /// Ingest the source offset of a buffer copy, whose knob may change it within the bound.
+    pub(crate) fn ingest_copy_offset(
+        &mut self,
+        knob: &KnobUser,
+        offset: u64,
+        bound: KnobBound,
+    ) -> Result<Range<usize>, LaunchError> {
+        let data_range = self.ingest_data(&[offset]);
+
+        match self.plan_knob_bounded(knob, data_range.clone(), bound)? {
+            KnobUsage::Noop => {}
+            KnobUsage::CopyFrom { .. } => return Err(LaunchError::InternalCommandError(line!())),
+        }
+
+        Ok(data_range)
+    }
+
     /// Ingest the data into the encoder's active buffer data.
     fn ingest_data(&mut self, data: &[impl bytemuck::Pod]) -> Range<usize> {
         Self::append_range(&mut self.binary_data, data)
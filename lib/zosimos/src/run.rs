@@ -1,3 +1,4 @@
+mod poll;
 mod timing;
 
 use core::{
@@ -15,7 +16,7 @@ use std::sync::{
 };
 
 use crate::buffer::{ByteLayout, CanvasLayout, Descriptor};
-use crate::command::{Register, RegisterKnob};
+use crate::command::{KnobValue, Register, RegisterKnob};
 use crate::pool::{
     BufferKey, Gpu, GpuKey, ImageData, PipelineKey, Pool, PoolImage, PoolKey, ShaderKey, TextureKey,
 };
@@ -70,11 +71,39 @@ pub(crate) struct ProgramInfo {
     /// TODO: some instruction results supply multiple events.
     pub(crate) skip_by_op: HashMap<program::Instruction, program::Event>,
     pub(crate) functions: HashMap<program::Function, program::FunctionFrame>,
-    pub(crate) knobs: HashMap<RegisterKnob, program::Knob>,
+    pub(crate) knobs: HashMap<RegisterKnob, Vec<(Vec<Descriptor>, program::Knob)>>,
     pub(crate) knob_descriptors: HashMap<program::Knob, program::KnobDescriptor>,
     pub(crate) knob_starts: BTreeMap<usize, program::Knob>,
 }
 
+impl ProgramInfo {
+    /// Check that data fits the region of a knob.
+    fn check_knob(&self, knob: Knob, data: &[u8]) -> Result<(), StartError> {
+        let desc = self
+            .knob_descriptors
+            .get(&knob)
+            .ok_or_else(|| StartError::InternalCommandError(line!()))?;
+
+        if data.len() != desc.range.len() {
+            return Err(StartError {
+                kind: LaunchErrorKind::MismatchedKnob {
+                    knob,
+                    expected: desc.range.len(),
+                    supplied: data.len(),
+                },
+            });
+        }
+
+        if !desc.admits(data) {
+            return Err(StartError {
+                kind: LaunchErrorKind::OutOfRangeKnob(knob),
+            });
+        }
+
+        Ok(())
+    }
+}
+
 /// Configures devices and input/output buffers for an executable.
 ///
 /// This is created via the [`Executable::from_pool`].
@@ -124,6 +153,8 @@ pub(crate) struct Host {
 
     knob_data: Arc<[u8]>,
     knobs: HashMap<Knob, Range<usize>>,
+    /// The knobs whose data has been read into a buffer by the execution.
+    consumed_knobs: HashSet<Knob>,
 
     /// Variable information during execution.
     pub(crate) command_encoder: Option<wgpu::CommandEncoder>,
@@ -204,8 +235,8 @@ enum TextureInitState {
 pub(crate) struct Cache {
     preallocated_textures: HashMap<usize, wgpu::Texture>,
     preallocated_buffers: HashMap<usize, wgpu::Buffer>,
-    precompiled_shader: HashMap<usize, wgpu::ShaderModule>,
-    precompiled_pipelines: HashMap<usize, wgpu::RenderPipeline>,
+    precompiled_shader: HashMap<usize, Arc<wgpu::ShaderModule>>,
+    precompiled_pipelines: HashMap<usize, Arc<wgpu::RenderPipeline>>,
 }
 
 #[derive(Debug, Default)]
@@ -218,12 +249,22 @@ pub struct ResourcesUsed {
     shaders_reused: u64,
     pipelines_compiled: u64,
     pipelines_reused: u64,
+    render_passes: u64,
 }
 
 pub struct StepLimits {
     instructions: usize,
 }
 
+/// The state of an execution after a step, see [`Execution::step_async`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// Instructions remain to be stepped.
+    Running,
+    /// The program has ended, the execution can be retired.
+    Finished,
+}
+
 #[derive(Clone, Debug)]
 pub(crate) struct Frame {
     // Used through Debug
@@ -271,9 +312,9 @@ pub(crate) struct Descriptors {
     bind_group_layouts: Vec<wgpu::BindGroupLayout>,
     buffers: Vec<Arc<wgpu::Buffer>>,
     command_buffers: Vec<wgpu::CommandBuffer>,
-    shaders: Vec<wgpu::ShaderModule>,
+    shaders: Vec<Arc<wgpu::ShaderModule>>,
     pipeline_layouts: Vec<wgpu::PipelineLayout>,
-    render_pipelines: Vec<wgpu::RenderPipeline>,
+    render_pipelines: Vec<Arc<wgpu::RenderPipeline>>,
     sampler: Vec<wgpu::Sampler>,
     textures: Vec<wgpu::Texture>,
     texture_views: Vec<wgpu::TextureView>,
@@ -319,6 +360,52 @@ pub struct RetiredBufferStats {
     pipeline_keys: Vec<PipelineKey>,
 }
 
+impl ResourcesUsed {
+    /// The number of shader modules created by the execution.
+    pub fn shaders_compiled(&self) -> u64 {
+        self.shaders_compiled
+    }
+
+    /// The number of shader modules shared from the pool.
+    pub fn shaders_reused(&self) -> u64 {
+        self.shaders_reused
+    }
+
+    /// The number of render pipelines created by the execution.
+    pub fn pipelines_compiled(&self) -> u64 {
+        self.pipelines_compiled
+    }
+
+    /// The number of render pipelines shared from the pool.
+    pub fn pipelines_reused(&self) -> u64 {
+        self.pipelines_reused
+    }
+
+    /// The number of render passes recorded by the execution.
+    pub fn render_passes(&self) -> u64 {
+        self.render_passes
+    }
+}
+
+impl RecoveredBufferStats {
+    /// The bytes of all textures and buffers taken from the pool.
+    pub fn mem(&self) -> u64 {
+        self.mem
+    }
+}
+
+impl RetiredBufferStats {
+    /// The bytes of all textures and buffers put into the pool.
+    pub fn mem(&self) -> u64 {
+        self.mem
+    }
+
+    /// The number of textures put into the pool.
+    pub fn texture_count(&self) -> usize {
+        self.texture_keys.len()
+    }
+}
+
 trait WithGpu {
     fn with_gpu<T>(&self, once: impl FnOnce(&Gpu) -> T) -> T;
 }
@@ -389,6 +476,18 @@ pub enum LaunchErrorKind {
         register: Register,
         descriptor: Descriptor,
     },
+    /// The register does not have a knob in the program.
+    MissingKnob(RegisterKnob),
+    /// The data written into a knob does not have the length of its region.
+    MismatchedKnob {
+        knob: Knob,
+        expected: usize,
+        supplied: usize,
+    },
+    /// The knob was already read by the running execution.
+    ConsumedKnob(Knob),
+    /// The data written into a knob is not a valid value, such as an offset beyond its buffer.
+    OutOfRangeKnob(Knob),
 }
 
 #[derive(Debug)]
@@ -417,6 +516,9 @@ struct Submissions {
     /// Did we submit to the device, i.e. if we want to sync can we `on_submitted_work_done` or
     /// not? If multi-device then this should become a set or map from gpu keys.
     submit: bool,
+    /// The number of render passes encoded by the instruction.
+    #[cfg_attr(not(feature = "tracing"), allow(dead_code))]
+    passes: usize,
 }
 
 impl core::fmt::Display for BadInstruction {
@@ -445,6 +547,8 @@ pub enum RetireErrorKind {
     NoSuchInput,
     NoSuchOutput,
     BadInstruction,
+    /// The output is not available as bytes on the host, e.g. as it was bound to a device image.
+    NoHostData,
 }
 
 impl Image {
@@ -892,10 +996,13 @@ impl Executable {
                 }
                 Low::CopyBufferToBuffer {
                     source_buffer,
-                    source: _,
                     target_buffer,
-                    target: _,
-                    size: _,
+                    ..
+                }
+                | Low::CopyBufferToBufferAt {
+                    source_buffer,
+                    target_buffer,
+                    ..
                 } => {
                     let idx = queue;
                     let _ = write!(&mut cons, " queue_{};", idx);
@@ -1009,8 +1116,31 @@ impl Executable {
         )
     }
 
+    /// Find the knob of a register, if its function was instantiated exactly once.
+    ///
+    /// Each instantiation of a generic function has its own knob. Those are found with
+    /// [`Self::query_knob_instance`] instead, or all set together through
+    /// [`Environment::knob_by_register`].
     pub fn query_knob(&self, knob: RegisterKnob) -> Option<Knob> {
-        self.info.knobs.get(&knob).copied()
+        match self.info.knobs.get(&knob)?[..] {
+            [(_, knob)] => Some(knob),
+            _ => None,
+        }
+    }
+
+    /// Find the knob of a register in the instantiation of its function with some generics.
+    pub fn query_knob_instance(&self, knob: RegisterKnob, tys: &[Descriptor]) -> Option<Knob> {
+        self.info
+            .knobs
+            .get(&knob)?
+            .iter()
+            .find_map(|(instance, knob)| (instance[..] == *tys).then_some(*knob))
+    }
+
+    /// The byte length of the region of a knob, as lowered for the device.
+    pub fn knob_len(&self, knob: Knob) -> Option<usize> {
+        let desc = self.info.knob_descriptors.get(&knob)?;
+        Some(desc.range.len())
     }
 
     pub fn launch(&self, mut env: Environment) -> Result<Execution, StartError> {
@@ -1036,6 +1166,7 @@ impl Executable {
                 io_map: self.io_map.clone(),
                 knob_data: env.knob_data.into(),
                 knobs: env.knobs.into(),
+                consumed_knobs: HashSet::new(),
                 info: self.info.clone(),
                 call_stack: vec![],
                 debug_stack: vec![],
@@ -1067,6 +1198,7 @@ impl Executable {
                 io_map: self.io_map.clone(),
                 knob_data: env.knob_data.into(),
                 knobs: env.knobs.into(),
+                consumed_knobs: HashSet::new(),
                 info: self.info.clone(),
                 call_stack: vec![],
                 debug_stack: vec![],
@@ -1279,24 +1411,60 @@ impl Environment<'_> {
     }
 
     /// Define the knob data for this run, by register.
+    ///
+    /// This sets the data of the knob in all instantiations of the function, see
+    /// [`Executable::query_knob_instance`] to choose one of them.
     #[track_caller]
     pub fn knob_by_register(&mut self, knob: &RegisterKnob, data: &[u8]) -> Result<(), StartError> {
-        let knob = self
-            .info
+        let info = self.info.clone();
+        let instances = info
             .knobs
             .get(knob)
             .expect("Knob does not exist in this program");
-        self.knob(*knob, data)
+
+        for &(_, knob) in instances {
+            self.knob(knob, data)?;
+        }
+
+        Ok(())
     }
 
-    pub fn knob(&mut self, knob: Knob, data: &[u8]) -> Result<(), StartError> {
-        let desc = &self.info.knob_descriptors[&knob];
+    /// Define the knob data of a register for this run, from a value.
+    ///
+    /// Like [`Self::knob_by_register`] but the value is validated against the region of each knob
+    /// before any of them is changed. A value of the wrong length is an error instead of
+    /// overwriting adjacent parameter data, as is a register without a knob in this program.
+    ///
+    /// The bytes of a [`KnobHandle`] are encoded with [`KnobHandle::encode`].
+    ///
+    /// [`KnobHandle`]: crate::command::KnobHandle
+    /// [`KnobHandle::encode`]: crate::command::KnobHandle::encode
+    pub fn set_knob(
+        &mut self,
+        knob: &RegisterKnob,
+        value: &(impl KnobValue + ?Sized),
+    ) -> Result<(), StartError> {
+        let info = self.info.clone();
+        let instances = info.knobs.get(knob).ok_or(StartError {
+            kind: LaunchErrorKind::MissingKnob(*knob),
+        })?;
+
+        let data = value.knob_bytes();
+
+        for &(_, knob) in instances {
+            self.check_knob(knob, &data)?;
+        }
 
-        if data.len() != desc.range.len() {
-            // FIXME: better error!
-            return Err(StartError::InternalCommandError(line!()));
+        for &(_, knob) in instances {
+            self.knob(knob, &data)?;
         }
 
+        Ok(())
+    }
+
+    pub fn knob(&mut self, knob: Knob, data: &[u8]) -> Result<(), StartError> {
+        self.check_knob(knob, data)?;
+
         let start = self.knob_data.len();
         self.knob_data.extend_from_slice(data);
         let end = self.knob_data.len();
@@ -1305,10 +1473,15 @@ impl Environment<'_> {
         Ok(())
     }
 
+    fn check_knob(&self, knob: Knob, data: &[u8]) -> Result<(), StartError> {
+        self.info.check_knob(knob, data)
+    }
+
     /// Retrieve matching temporary buffers from the pool.
     ///
     /// This reuses of allocations of buffers, textures, etc. from previous iterations of this
-    /// program run where possible.
+    /// program run where possible. Compiled shader modules and pipelines are shared with all
+    /// other executions on the same device, including those of other programs.
     pub fn recover_buffers(&mut self) -> RecoveredBufferStats {
         let mut stats = RecoveredBufferStats::default();
 
@@ -1332,13 +1505,13 @@ impl Environment<'_> {
         }
 
         for (&inst, desc) in &self.info.shader_by_op {
-            if let Some(buffer) = pool_cache.extract_shader(desc) {
-                self.cache.precompiled_shader.insert(inst, buffer);
+            if let Some(shader) = pool_cache.share_shader(desc) {
+                self.cache.precompiled_shader.insert(inst, shader);
             }
         }
 
         for (&inst, desc) in &self.info.pipeline_by_op {
-            if let Some(pipeline) = pool_cache.extract_pipeline(desc) {
+            if let Some(pipeline) = pool_cache.share_pipeline(desc) {
                 self.cache.precompiled_pipelines.insert(inst, pipeline);
             }
         }
@@ -1367,6 +1540,7 @@ impl Execution {
                 io_map: init.io_map,
                 knob_data: Arc::default(),
                 knobs: Default::default(),
+                consumed_knobs: HashSet::new(),
                 info: init.info,
                 call_stack: vec![],
                 debug_stack: vec![],
@@ -1378,6 +1552,56 @@ impl Execution {
         }
     }
 
+    /// Change the data of a knob while the execution is running.
+    ///
+    /// The data is used by all steps which follow, if they are the first to read the knob. It is
+    /// an error to change a knob whose data was already read into a buffer, since the operations
+    /// using it may have been submitted already. This includes a function which reads the knob in
+    /// each of multiple calls, after the first one. The execution is unchanged by an error.
+    ///
+    /// The data are the encoded bytes of the value, such as from [`KnobHandle::encode`], unlike
+    /// the value taken by [`Environment::set_knob`] before the launch.
+    ///
+    /// [`KnobHandle::encode`]: crate::command::KnobHandle::encode
+    pub fn set_knob_bytes(&mut self, knob: Knob, data: &[u8]) -> Result<(), StartError> {
+        let host = &mut self.host;
+        host.info.check_knob(knob, data)?;
+
+        if host.consumed_knobs.contains(&knob) {
+            return Err(StartError {
+                kind: LaunchErrorKind::ConsumedKnob(knob),
+            });
+        }
+
+        // Data of the same length replaces the previous value of the knob where it is.
+        if let Some(range) = host
+            .knobs
+            .get(&knob)
+            .filter(|range| range.len() == data.len())
+        {
+            match Arc::get_mut(&mut host.knob_data) {
+                Some(knob_data) => knob_data[range.clone()].copy_from_slice(data),
+                None => {
+                    let mut knob_data = host.knob_data.to_vec();
+                    knob_data[range.clone()].copy_from_slice(data);
+                    host.knob_data = knob_data.into();
+                }
+            }
+
+            return Ok(());
+        }
+
+        let mut knob_data = host.knob_data.to_vec();
+        let start = knob_data.len();
+        knob_data.extend_from_slice(data);
+        let end = knob_data.len();
+
+        host.knob_data = knob_data.into();
+        host.knobs.insert(knob, start..end);
+
+        Ok(())
+    }
+
     /// Check if the machine is still running.
     pub fn is_running(&self) -> bool {
         self.host.machine.is_running()
@@ -1420,6 +1644,8 @@ impl Execution {
 
         let async_step = async move {
             let mut limits = limits;
+            #[cfg(feature = "tracing")]
+            let mut passes = 0;
 
             loop {
                 match host.step_inner(cache, gpu, &mut limits).await {
@@ -1433,6 +1659,16 @@ impl Execution {
                         return Err(error);
                     }
                     Ok(submission) => {
+                        #[cfg(feature = "tracing")]
+                        {
+                            passes += submission.passes;
+
+                            if submission.submit {
+                                tracing::debug!(passes, "submitted");
+                                passes = 0;
+                            }
+                        }
+
                         if submission.submit {
                             submit_flag.fetch_add(1, Ordering::Release);
                         }
@@ -1451,6 +1687,12 @@ impl Execution {
             Ok(())
         };
 
+        #[cfg(feature = "tracing")]
+        let async_step = tracing::Instrument::instrument(
+            async_step,
+            tracing::debug_span!("step_to", instruction_pointer),
+        );
+
         Ok(SyncPoint {
             future: Some(DevicePolled {
                 future: Box::pin(async_step),
@@ -1463,6 +1705,41 @@ impl Execution {
         })
     }
 
+    /// Do a number of limited steps, awaiting the device instead of blocking on it.
+    ///
+    /// The submitted work is awaited through the callbacks of the queue, see
+    /// [`SyncPoint::finish`]. On the web the device makes progress by itself. On native targets
+    /// it is polled from a separate thread for the duration of the step, such that the awaiting
+    /// task is never blocked. Use [`SyncPoint::block_on`] to step synchronously instead.
+    pub async fn step_async(&mut self, limits: StepLimits) -> Result<StepOutcome, StepError> {
+        let mut step = self.step_to(limits)?;
+        step.finish(poll::DevicePoller::new).await?;
+        drop(step);
+
+        Ok(if self.is_running() {
+            StepOutcome::Running
+        } else {
+            StepOutcome::Finished
+        })
+    }
+
+    /// Abandon the remaining instructions of the program.
+    ///
+    /// No further work is submitted and the execution is no longer running. As for a finished
+    /// program, further steps succeed without doing anything and [`Self::step_async`] reports
+    /// [`StepOutcome::Finished`]. Work that was already submitted is waited for, such that all
+    /// resources can afterwards be recovered with [`Self::retire_gracefully`] exactly as for a
+    /// completed run. The outputs are not written to. Cancelling a finished or cancelled
+    /// execution does nothing.
+    pub fn cancel(&mut self) {
+        if !self.host.machine.is_running() {
+            return;
+        }
+
+        self.host.machine.cancel();
+        let _ = self.gpu.device().poll(wgpu::PollType::Wait);
+    }
+
     /// Stop the execution.
     ///
     /// Discards all resources that are still held like buffers, the device, etc.
@@ -1592,6 +1869,7 @@ impl Host {
                 {
                     let kdesc = &self.info.knob_descriptors[knob];
                     assert_eq!(&kdesc.range, &desc.content, "Unhandled encoding error");
+                    self.consumed_knobs.insert(*knob);
                     self.knobs.get(knob)
                 } else {
                     None
@@ -1633,7 +1911,7 @@ impl Host {
                         shader
                     } else {
                         self.usage.shaders_compiled += 1;
-                        gpu.with_gpu(|gpu| gpu.device().create_shader_module(wgpu_desc))
+                        Arc::new(gpu.with_gpu(|gpu| gpu.device().create_shader_module(wgpu_desc)))
                     };
                 } else {
                     let wgpu_desc = wgpu::ShaderModuleDescriptor {
@@ -1646,7 +1924,7 @@ impl Host {
                         shader
                     } else {
                         self.usage.shaders_compiled += 1;
-                        gpu.with_gpu(|gpu| gpu.device().create_shader_module(wgpu_desc))
+                        Arc::new(gpu.with_gpu(|gpu| gpu.device().create_shader_module(wgpu_desc)))
                     };
                 };
 
@@ -1804,11 +2082,18 @@ impl Host {
 
                     let mut vertex_buffers = vec![];
                     let mut fragments = vec![];
-
-                    let pipeline =
-                        self.descriptors
-                            .pipeline(desc, &mut vertex_buffers, &mut fragments)?;
-                    gpu.with_gpu(|gpu| gpu.device().create_render_pipeline(&pipeline))
+                    let mut constants = vec![];
+                    let pipeline_cache = gpu.with_gpu(Gpu::pipeline_cache);
+
+                    let mut pipeline = self.descriptors.pipeline(
+                        desc,
+                        &mut vertex_buffers,
+                        &mut fragments,
+                        &mut constants,
+                    )?;
+
+                    pipeline.cache = pipeline_cache.as_deref();
+                    Arc::new(gpu.with_gpu(|gpu| gpu.device().create_render_pipeline(&pipeline)))
                 };
 
                 let pipeline_idx = self.descriptors.render_pipelines.len();
@@ -1836,6 +2121,7 @@ impl Host {
                 Ok(Submissions::default())
             }
             Low::BeginRenderPass(descriptor) => {
+                self.usage.render_passes += 1;
                 let mut attachment_buf = vec![];
                 let descriptor = self.descriptors.prepare_attachments_for_render_pass(
                     descriptor,
@@ -1851,7 +2137,10 @@ impl Host {
                 drop(attachment_buf);
                 self.machine.render_pass(&self.descriptors, pass)?;
 
-                Ok(Submissions::default())
+                Ok(Submissions {
+                    passes: 1,
+                    ..Submissions::default()
+                })
             }
             Low::EndCommands => match self.command_encoder.take() {
                 None => Err(StepError::InvalidInstruction(line!())),
@@ -1867,7 +2156,10 @@ impl Host {
                     .pop()
                     .ok_or_else(|| StepError::InvalidInstruction(line!()))?;
                 gpu.with_gpu(|gpu| gpu.queue().submit(once(command)));
-                Ok(Submissions { submit: true })
+                Ok(Submissions {
+                    submit: true,
+                    ..Submissions::default()
+                })
             }
             &Low::RunTopCommand => {
                 let many = 1 + self.delayed_submits;
@@ -1879,7 +2171,10 @@ impl Host {
                     return Err(StepError::InvalidInstruction(line!()));
                 }
 
-                Ok(Submissions { submit: true })
+                Ok(Submissions {
+                    submit: true,
+                    ..Submissions::default()
+                })
             }
             &Low::RunBotToTop(many) => {
                 let many = many + self.delayed_submits;
@@ -1891,7 +2186,10 @@ impl Host {
                     return Err(StepError::InvalidInstruction(line!()));
                 }
 
-                Ok(Submissions { submit: true })
+                Ok(Submissions {
+                    submit: true,
+                    ..Submissions::default()
+                })
             }
             &Low::WriteImageToBuffer {
                 source_image,
@@ -2138,6 +2436,64 @@ impl Host {
 
                 Ok(Submissions::default())
             }
+            Low::CopyBufferToBufferAt {
+                source_buffer,
+                source,
+                target_buffer,
+                target,
+                size,
+            } => {
+                let knob_range = if let Some(knob) = self.info.knob_starts.get(&source.start) {
+                    self.consumed_knobs.insert(*knob);
+                    self.knobs.get(knob)
+                } else {
+                    None
+                };
+
+                let offset = if let Some(knob_range) = knob_range {
+                    &self.knob_data[knob_range.clone()]
+                } else {
+                    self.binary_data
+                        .get(source.clone())
+                        .ok_or_else(|| StepError::InvalidInstruction(line!()))?
+                };
+
+                let offset = <[u8; 8]>::try_from(offset)
+                    .map(u64::from_ne_bytes)
+                    .map_err(|_| StepError::InvalidInstruction(line!()))?;
+
+                let encoder = match &mut self.command_encoder {
+                    Some(encoder) => encoder,
+                    None => return Err(StepError::InvalidInstruction(line!())),
+                };
+
+                let source_buf = match self.descriptors.buffers.get(source_buffer.0) {
+                    Some(source) => source,
+                    None => return Err(StepError::InvalidInstruction(line!())),
+                };
+
+                let target_buf = match self.descriptors.buffers.get(target_buffer.0) {
+                    Some(target) => target,
+                    None => return Err(StepError::InvalidInstruction(line!())),
+                };
+
+                // The knob was validated when set, this must never reach the device.
+                if offset
+                    .checked_add(*size)
+                    .map_or(true, |end| end > source_buf.size())
+                {
+                    return Err(StepError::InvalidInstruction(line!()));
+                }
+
+                self.debug
+                    .buffer_use(*source_buffer, TextureInitState::UseRead);
+                self.debug
+                    .buffer_use(*target_buffer, TextureInitState::WriteTo);
+
+                encoder.copy_buffer_to_buffer(source_buf, offset, target_buf, *target, *size);
+
+                Ok(Submissions::default())
+            }
             Low::ZeroBuffer {
                 start,
                 size,
@@ -2217,7 +2573,10 @@ impl Host {
                     let command = encoder.finish();
                     gpu.with_gpu(|gpu| gpu.queue().submit(once(command)));
 
-                    return Ok(Submissions { submit: true });
+                    return Ok(Submissions {
+                        submit: true,
+                        ..Submissions::default()
+                    });
                 } else if let ImageData::GpuBuffer {
                     buffer,
                     layout,
@@ -2239,7 +2598,10 @@ impl Host {
                     let command = encoder.finish();
                     gpu.with_gpu(|gpu| gpu.queue().submit(once(command)));
 
-                    return Ok(Submissions { submit: true });
+                    return Ok(Submissions {
+                        submit: true,
+                        ..Submissions::default()
+                    });
                 }
 
                 if image.as_bytes().is_none() {
@@ -2490,9 +2852,10 @@ impl Descriptors {
 
     fn pipeline<'set>(
         &'set self,
-        desc: &program::RenderPipelineDescriptor,
+        desc: &'set program::RenderPipelineDescriptor,
         vertex_buffers: &'set mut Vec<wgpu::VertexBufferLayout<'set>>,
         fragments: &'set mut Vec<Option<wgpu::ColorTargetState>>,
+        constants: &'set mut Vec<(&'set str, f64)>,
     ) -> Result<wgpu::RenderPipelineDescriptor<'set>, StepError> {
         Ok(wgpu::RenderPipelineDescriptor {
             label: None,
@@ -2515,7 +2878,7 @@ impl Descriptors {
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
-            fragment: Some(self.fragment_state(&desc.fragment, fragments)?),
+            fragment: Some(self.fragment_state(&desc.fragment, fragments, constants)?),
             // TODO: could be an efficient way to paint multiple times, with _different_ sets of
             // parameters. As opposed to rebinding buffers between paints.
             //
@@ -2577,11 +2940,18 @@ impl Descriptors {
 
     fn fragment_state<'set>(
         &'set self,
-        desc: &program::FragmentState,
+        desc: &'set program::FragmentState,
         buf: &'set mut Vec<Option<wgpu::ColorTargetState>>,
+        constants: &'set mut Vec<(&'set str, f64)>,
     ) -> Result<wgpu::FragmentState<'set>, StepError> {
         buf.clear();
         buf.extend(desc.targets.iter().cloned().map(Some));
+        constants.clear();
+        constants.extend(
+            desc.constants
+                .iter()
+                .map(|(id, value)| (id.as_str(), *value)),
+        );
         Ok(wgpu::FragmentState {
             module: self
                 .shaders
@@ -2589,7 +2959,10 @@ impl Descriptors {
                 .ok_or_else(|| StepError::InvalidInstruction(line!()))?,
             entry_point: Some(desc.entry_point),
             targets: buf,
-            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            compilation_options: wgpu::PipelineCompilationOptions {
+                constants,
+                ..Default::default()
+            },
         })
     }
 
@@ -2646,6 +3019,10 @@ impl Machine {
         !self.instruction_pointer.is_empty()
     }
 
+    fn cancel(&mut self) {
+        self.instruction_pointer.clear();
+    }
+
     fn next_instruction(&mut self) -> Result<(program::Instruction, &Low), StepError> {
         let instruction = loop {
             let ip = self
@@ -2820,6 +3197,33 @@ impl Retire<'_> {
         self.retire_image(index)
     }
 
+    /// Read the bytes of a buffer output corresponding to `reg`.
+    ///
+    /// The register is one returned from [`CommandBuffer::output_buffer`]. The output stays in
+    /// the execution and is not moved into the pool, the bytes are copied out in their order
+    /// within the device buffer.
+    ///
+    /// [`CommandBuffer::output_buffer`]: crate::command::CommandBuffer::output_buffer
+    pub fn output_buffer(&mut self, reg: Register) -> Result<Vec<u8>, RetireError> {
+        let index = self
+            .execution
+            .host
+            .io_map
+            .outputs
+            .get(&reg)
+            .copied()
+            .ok_or(RetireError {
+                inner: RetireErrorKind::NoSuchOutput,
+            })?;
+
+        let image = &self.execution.host.descriptors.image_io_buffers[index];
+        let bytes = image.data.as_bytes().ok_or(RetireError {
+            inner: RetireErrorKind::NoHostData,
+        })?;
+
+        Ok(bytes.to_vec())
+    }
+
     /// Move the render target corresponding to `reg` into the pool.
     ///
     /// Return the image as viewed inside the pool.
@@ -3043,6 +3447,7 @@ impl SyncPoint<'_> {
         // A method that will ensure the GPU queue to be polled while its guard is live.
         queue_poll: impl FnOnce(Gpu) -> Guard,
     ) -> Result<(), StepError> {
+        #[cfg(feature = "tracing")]
         tracing::info!("Finishing with guard");
         let Some(polled) = &mut self.future else {
             return Ok(());
@@ -3145,6 +3550,7 @@ impl SyncPoint<'_> {
             queue: submit_gpu.queue(),
         };
 
+        #[cfg(feature = "tracing")]
         tracing::info!("Waiting on step future");
 
         // We're building our own select here, for two futures where only one can finish.
@@ -3160,6 +3566,7 @@ impl SyncPoint<'_> {
         })
         .await;
 
+        #[cfg(feature = "tracing")]
         tracing::info!("Step future done");
 
         // Avoid polling the future (on Drop) after this point, it's done.
@@ -0,0 +1,70 @@
+use crate::pool::Gpu;
+
+/// Keeps a device polled while it is alive, see [`SyncPoint::finish`].
+///
+/// On the web the device makes progress by itself and there is nothing to do.
+///
+/// [`SyncPoint::finish`]: super::SyncPoint::finish
+#[cfg(target_arch = "wasm32")]
+pub struct DevicePoller {
+    _inner: (),
+}
+
+/// Keeps a device polled while it is alive, see [`SyncPoint::finish`].
+///
+/// The device is polled from a separate thread, which waits for the submitted work instead of
+/// spinning. The callbacks of the queue then wake the task awaiting the step.
+///
+/// [`SyncPoint::finish`]: super::SyncPoint::finish
+#[cfg(not(target_arch = "wasm32"))]
+pub struct DevicePoller {
+    stop: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl DevicePoller {
+    pub fn new(_: Gpu) -> Self {
+        DevicePoller { _inner: () }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl DevicePoller {
+    pub fn new(gpu: Gpu) -> Self {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+
+        let thread = std::thread::spawn(move || {
+            while !stopped.load(Ordering::Acquire) {
+                let status = gpu.device().poll(wgpu::PollType::Wait);
+
+                // Nothing was submitted yet, wait for the step to submit or to finish.
+                if matches!(status, Ok(wgpu::PollStatus::QueueEmpty) | Err(_)) {
+                    std::thread::park_timeout(Duration::from_millis(1));
+                }
+            }
+        });
+
+        DevicePoller {
+            stop,
+            thread: Some(thread),
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl Drop for DevicePoller {
+    fn drop(&mut self) {
+        self.stop.store(true, std::sync::atomic::Ordering::Release);
+
+        if let Some(thread) = self.thread.take() {
+            thread.thread().unpark();
+            let _ = thread.join();
+        }
+    }
+}
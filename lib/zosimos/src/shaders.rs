@@ -1,23 +1,63 @@
 use crate::color_matrix::RowMatrix;
 use crate::program::BufferInitContent;
 
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
+pub mod ab_transform;
 pub mod bilinear;
+pub mod bloom;
 pub mod box3;
+pub mod bradford;
+pub mod bright_pass;
+pub mod chroma_key;
+pub mod chromatic_aberration;
+pub mod cielab;
+pub mod clahe;
+pub mod cmyk;
+pub mod color_ramp;
+pub mod demosaic;
+pub mod denoise_nlm;
 pub mod distribution_normal2d;
+pub mod downsample;
+pub mod emboss;
+pub mod exposure_fusion;
 pub mod fractal_noise;
+pub mod gamut;
+pub mod gaussian;
+pub mod gradient_polar;
+pub mod histogram;
 pub mod inject;
+pub mod lens_undistort;
+pub mod local_statistics;
 pub mod oklab;
+pub mod pad;
 pub mod palette;
+pub mod perspective;
+pub mod prefix_sum;
+pub mod pyramid;
+pub mod reduce;
+pub mod resample;
+pub mod shadow;
+pub mod sharpen;
+pub mod simplex_noise;
 pub mod solid_rgb;
+pub mod spline_ramp;
+pub mod squared_difference;
 pub mod srlab2;
 pub mod stage;
+pub mod test_pattern;
+pub mod tile;
+pub mod tonemap;
+pub mod voronoi;
+pub mod white_balance;
+pub mod worley;
 
 /// All the programs we need for the core language, i.e. everything that is not functions but just
 /// managing the buffers, moving between bytes and textures type system.
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ShadersCore {
     pub vert_noop: Arc<[u8]>,
     pub frag_copy: Arc<[u8]>,
@@ -26,20 +66,67 @@ pub struct ShadersCore {
     pub stage: stage::Shaders,
 }
 
-#[derive(Clone, Deserialize, Serialize)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ShadersStd {
+    pub ab_transform: Arc<[u8]>,
     pub bilinear: Arc<[u8]>,
+    pub bradford_nonlinear: Arc<[u8]>,
+    pub bright_pass: Arc<[u8]>,
+    pub bloom: Arc<[u8]>,
+    pub demosaic: Arc<[u8]>,
+    pub denoise_nlm: Arc<[u8]>,
+    pub sharpen: Arc<[u8]>,
+    pub emboss: Arc<[u8]>,
+    pub gamut_map: Arc<[u8]>,
+    pub gradient_polar: Arc<[u8]>,
     pub box3: Arc<[u8]>,
+    pub clahe_histogram: Arc<[u8]>,
+    pub clahe_mapping: Arc<[u8]>,
+    pub clahe_apply: Arc<[u8]>,
+    pub chromatic_aberration: Arc<[u8]>,
+    pub chroma_key: Arc<[u8]>,
+    pub cielab_encode: Arc<[u8]>,
+    pub cielab_decode: Arc<[u8]>,
+    pub cmyk_encode: Arc<[u8]>,
+    pub cmyk_decode: Arc<[u8]>,
+    pub color_ramp: Arc<[u8]>,
     pub distribution_normal2d: Arc<[u8]>,
+    pub downsample: Arc<[u8]>,
+    pub exposure_fusion: Arc<[u8]>,
     pub fractal_noise: Arc<[u8]>,
+    pub gaussian: Arc<[u8]>,
+    pub histogram_rows: Arc<[u8]>,
+    pub histogram_sum: Arc<[u8]>,
     pub inject: Arc<[u8]>,
     pub linear_color_transform: Arc<[u8]>,
+    pub lens_undistort: Arc<[u8]>,
     pub oklab_encode: Arc<[u8]>,
     pub oklab_decode: Arc<[u8]>,
+    pub pad: Arc<[u8]>,
     pub palette: Arc<[u8]>,
+    pub perspective: Arc<[u8]>,
+    pub prefix_sum: Arc<[u8]>,
+    pub pyramid: Arc<[u8]>,
+    pub reduce_mean: Arc<[u8]>,
+    pub reduce_min: Arc<[u8]>,
+    pub reduce_max: Arc<[u8]>,
+    pub resample: Arc<[u8]>,
+    pub simplex_noise: Arc<[u8]>,
+    pub shadow: Arc<[u8]>,
     pub solid_rgb: Arc<[u8]>,
+    pub spline_ramp: Arc<[u8]>,
+    pub squared_difference: Arc<[u8]>,
+    pub ssim: Arc<[u8]>,
     pub srlab2_encode: Arc<[u8]>,
     pub srlab2_decode: Arc<[u8]>,
+    pub test_pattern: Arc<[u8]>,
+    pub tile: Arc<[u8]>,
+    pub tonemap_reinhard: Arc<[u8]>,
+    pub tonemap_aces: Arc<[u8]>,
+    pub worley: Arc<[u8]>,
+    pub voronoi: Arc<[u8]>,
+    pub white_balance: Arc<[u8]>,
 }
 
 /// A simple shader invocation.
@@ -49,6 +136,7 @@ pub struct ShadersStd {
 ///
 /// FIXME: deriving PartialEq may be inferior to an actual implementation.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct ShaderInvocation {
     /// The shader source, shared between all instances of this similar invocation.
     pub(crate) spirv: Arc<[u8]>,
@@ -82,6 +170,24 @@ pub(crate) trait FragmentShaderData: core::fmt::Debug {
     fn num_args(&self) -> u32 {
         1
     }
+
+    /// How the sampler addresses texture coordinates outside of the argument images.
+    fn address_mode(&self) -> wgpu::AddressMode {
+        wgpu::AddressMode::default()
+    }
+
+    /// How the sampler filters between the texels of the argument images.
+    fn filter_mode(&self) -> wgpu::FilterMode {
+        wgpu::FilterMode::Nearest
+    }
+
+    /// The values of SPIR-V specialization constants, as pairs of their id and value.
+    ///
+    /// The value is converted to the type of the constant, an integer or a boolean which is true
+    /// for any non-zero value. Each distinct set of values creates its own pipeline.
+    fn specialization(&self) -> Vec<(u32, u32)> {
+        vec![]
+    }
 }
 
 impl FragmentShaderData for ShaderInvocation {
@@ -135,11 +241,101 @@ pub(crate) enum FragmentShaderKey {
     OklabTransform(Direction),
     /// A shader transforming between XYZ and SrLab2 color space.
     Srlab2Transform(Direction),
+    /// A shader transforming between XYZ and CIELAB color space.
+    CielabTransform(Direction),
+    /// A shader transforming between RGB and naive CMYK.
+    CmykTransform(Direction),
     /// A convolution with a 3-by-3 box function.
     Box3,
+    /// A piecewise linear interpolation between color stops.
+    ColorRamp,
+    /// A conformal transformation of the Oklab chroma plane.
+    AbTransform,
+    /// A Catmull-Rom spline through control colors.
+    SplineRamp,
+    /// One step of halving an image by reducing its texels.
+    Reduce(crate::command::Reduction),
+    /// Scaling color channels by gains derived from an illuminant estimate.
+    WhiteBalance,
+    /// One step of fusing exposures by their quality.
+    ExposureFusion,
+    /// A tone mapping operator for high dynamic range radiance.
+    ToneMap(tonemap::ToneMapKind),
+    /// A pass of counting texels into the bins of a histogram.
+    Histogram(histogram::HistogramStage),
+    /// A pass of contrast limited adaptive histogram equalization.
+    Clahe(clahe::ClaheStage),
+    /// The squared difference of two images.
+    SquaredDifference,
+    /// The structural similarity of two images, from local statistics.
+    Ssim,
+    /// One step of a prefix sum along an axis.
+    PrefixSum,
+    /// One separable pass of resampling with a reconstruction kernel.
+    Resample,
+    /// Painting an image on top of another with a projective transformation.
+    Perspective,
+    /// Repeating an image periodically, with a wrapping sampler.
+    Tile,
+    /// Placing an image into a larger or smaller one, with a border mode.
+    Pad,
+    /// Combining a level of a pyramid with the expansion of a coarser level.
+    PyramidExpand,
+    /// One pass of averaging blocks of texels by an integer factor.
+    Downsample,
+    /// Fractal noise from octaves of simplex noise.
+    SimplexNoise,
+    /// Cellular noise from jittered feature points.
+    Worley,
+    /// A family of synthetic test images.
+    TestPattern,
+    /// Coloring by the nearest of a set of seed points.
+    Voronoi,
+    /// Transparency by the chroma distance to a key color.
+    ChromaKey,
+    /// A separable pass of a gaussian blur.
+    Gaussian,
+    /// Compositing over a tinted silhouette.
+    DropShadow,
+    /// Keeping the brightness above a threshold.
+    BrightPass,
+    /// Adding a glow onto an image.
+    Bloom,
+    /// Sampling color channels at different radial scales.
+    ChromaticAberration,
+    /// Inverse mapping of a Brown-Conrady lens distortion.
+    LensUndistort,
+    /// The demosaicing of a color filter array.
+    Demosaic,
+    /// The non-local means denoising filter.
+    DenoiseNlm,
+    /// The fused Laplacian sharpening.
+    Sharpen,
+    /// The directional relief of luma.
+    Emboss,
+    /// The polar gradient of luma.
+    GradientPolar,
+    /// The non-linear chromatic adaptation of Bradford.
+    BradfordNonLinear,
+    /// The chroma reduction into the gamut of an RGB color.
+    GamutMap,
     /// The key is the address of some dynamic object, unique for the duration of the pipeline.
     /// One shouldn't rely on uniqueness of soundness.
     Dynamic(usize),
+    /// Another shader, with values for its specialization constants.
+    Specialized(Box<FragmentShaderKey>, Vec<(u32, u32)>),
+}
+
+impl FragmentShaderKey {
+    /// The key of this shader specialized with some constants, see
+    /// [`FragmentShaderData::specialization`].
+    pub(crate) fn specialize(self, constants: &[(u32, u32)]) -> Self {
+        if constants.is_empty() {
+            self
+        } else {
+            FragmentShaderKey::Specialized(Box::new(self), constants.to_vec())
+        }
+    }
 }
 
 /// Identifies the vertex shading.
@@ -166,8 +362,46 @@ pub(crate) enum FragmentShaderInvocation {
     Inject(self::inject::Shader),
     Oklab(self::oklab::Shader),
     SrLab2(self::srlab2::Shader),
+    CieLab(self::cielab::Shader),
+    Cmyk(self::cmyk::Shader),
     Box3(self::box3::Shader),
     SolidRgb(self::solid_rgb::Shader),
+    ColorRamp(self::color_ramp::Shader),
+    AbTransform(self::ab_transform::Shader),
+    SplineRamp(self::spline_ramp::Shader),
+    Reduce(self::reduce::Shader),
+    WhiteBalance(self::white_balance::Shader),
+    ToneMap(self::tonemap::Shader),
+    ExposureFusion(self::exposure_fusion::Shader),
+    Histogram(self::histogram::Shader),
+    Clahe(self::clahe::Shader),
+    SquaredDifference(self::squared_difference::Shader),
+    Ssim(self::local_statistics::Shader),
+    PrefixSum(self::prefix_sum::Shader),
+    Resample(self::resample::Shader),
+    Perspective(self::perspective::Shader),
+    Tile(self::tile::Shader),
+    Pad(self::pad::Shader),
+    PyramidExpand(self::pyramid::Shader),
+    Downsample(self::downsample::Shader),
+    SimplexNoise(self::simplex_noise::Shader),
+    Worley(self::worley::Shader),
+    TestPattern(self::test_pattern::Shader),
+    Voronoi(self::voronoi::Shader),
+    ChromaKey(self::chroma_key::Shader),
+    Gaussian(self::gaussian::Shader),
+    DropShadow(self::shadow::Shader),
+    BrightPass(self::bright_pass::Shader),
+    Bloom(self::bloom::Shader),
+    ChromaticAberration(self::chromatic_aberration::Shader),
+    LensUndistort(self::lens_undistort::Shader),
+    Demosaic(self::demosaic::Shader),
+    DenoiseNlm(self::denoise_nlm::Shader),
+    Sharpen(self::sharpen::Shader),
+    Emboss(self::emboss::Shader),
+    GradientPolar(self::gradient_polar::Shader),
+    BradfordNonLinear(self::bradford::Shader),
+    GamutMap(self::gamut::Shader),
     Runtime(ShaderInvocation),
 }
 
@@ -183,8 +417,48 @@ impl FragmentShaderInvocation {
             FragmentShaderInvocation::Inject(inject) => inject,
             FragmentShaderInvocation::Oklab(oklab) => oklab,
             FragmentShaderInvocation::SrLab2(srlab2) => srlab2,
+            FragmentShaderInvocation::CieLab(cielab) => cielab,
+            FragmentShaderInvocation::Cmyk(cmyk) => cmyk,
             FragmentShaderInvocation::Box3(box3) => box3,
             FragmentShaderInvocation::SolidRgb(color) => color,
+            FragmentShaderInvocation::ColorRamp(ramp) => ramp,
+            FragmentShaderInvocation::AbTransform(transform) => transform,
+            FragmentShaderInvocation::SplineRamp(spline) => spline,
+            FragmentShaderInvocation::Reduce(reduce) => reduce,
+            FragmentShaderInvocation::WhiteBalance(balance) => balance,
+            FragmentShaderInvocation::ToneMap(tonemap) => tonemap,
+            FragmentShaderInvocation::ExposureFusion(fusion) => fusion,
+            FragmentShaderInvocation::Histogram(histogram) => histogram,
+            FragmentShaderInvocation::Clahe(clahe) => clahe,
+            FragmentShaderInvocation::SquaredDifference(difference) => difference,
+            FragmentShaderInvocation::Ssim(ssim) => ssim,
+            FragmentShaderInvocation::PrefixSum(sum) => sum,
+            FragmentShaderInvocation::Resample(resample) => resample,
+            FragmentShaderInvocation::Perspective(perspective) => perspective,
+            FragmentShaderInvocation::Tile(tile) => tile,
+            FragmentShaderInvocation::Pad(pad) => pad,
+            FragmentShaderInvocation::PyramidExpand(pyramid) => pyramid,
+            FragmentShaderInvocation::Downsample(downsample) => downsample,
+            FragmentShaderInvocation::SimplexNoise(simplex_noise) => simplex_noise,
+            FragmentShaderInvocation::Worley(worley) => worley,
+            FragmentShaderInvocation::TestPattern(test_pattern) => test_pattern,
+            FragmentShaderInvocation::Voronoi(voronoi) => voronoi,
+            FragmentShaderInvocation::ChromaKey(chroma_key) => chroma_key,
+            FragmentShaderInvocation::Gaussian(gaussian) => gaussian,
+            FragmentShaderInvocation::DropShadow(shadow) => shadow,
+            FragmentShaderInvocation::BrightPass(bright_pass) => bright_pass,
+            FragmentShaderInvocation::Bloom(bloom) => bloom,
+            FragmentShaderInvocation::ChromaticAberration(chromatic_aberration) => {
+                chromatic_aberration
+            }
+            FragmentShaderInvocation::LensUndistort(lens_undistort) => lens_undistort,
+            FragmentShaderInvocation::Demosaic(demosaic) => demosaic,
+            FragmentShaderInvocation::DenoiseNlm(denoise_nlm) => denoise_nlm,
+            FragmentShaderInvocation::Sharpen(sharpen) => sharpen,
+            FragmentShaderInvocation::Emboss(emboss) => emboss,
+            FragmentShaderInvocation::GradientPolar(gradient_polar) => gradient_polar,
+            FragmentShaderInvocation::BradfordNonLinear(bradford) => bradford,
+            FragmentShaderInvocation::GamutMap(gamut) => gamut,
             FragmentShaderInvocation::Runtime(dynamic) => dynamic,
         }
     }
@@ -218,6 +492,8 @@ impl FragmentShaderData for PaintOnTopKind {
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct LinearColorTransform {
     pub matrix: RowMatrix,
+    /// Added after the matrix, zero for a linear transform.
+    pub offset: [f32; 3],
     pub spirv: Arc<[u8]>,
 }
 
@@ -232,7 +508,12 @@ impl FragmentShaderData for LinearColorTransform {
 
     fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
         let rgb_matrix: [f32; 12] = self.matrix.into_mat3x3_std140();
-        Some(BufferInitContent::new(buffer, &rgb_matrix))
+        let [x, y, z] = self.offset;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&rgb_matrix);
+        buffer_content.extend_from_pods(&[x, y, z, 0.0]);
+        Some(buffer_content.build())
     }
 }
 
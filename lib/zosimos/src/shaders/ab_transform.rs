@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+
+/// A conformal transformation of the chroma plane in Oklab.
+///
+/// The chroma `z = a + bi` is first mapped by a Möbius transformation which moves `from` to the
+/// origin and the origin to `to`, then rotated.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// Transforms the linear color to XYZ, or `None` if the color is already Oklab.
+    pub(crate) xyz: Option<(RowMatrix, RowMatrix)>,
+    pub(crate) from: [f32; 2],
+    pub(crate) to: [f32; 2],
+    pub(crate) rotation: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::AbTransform)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            xyz,
+            from,
+            to,
+            rotation,
+        } = &self.data;
+
+        let identity = RowMatrix::diag(1.0, 1.0, 1.0);
+        let (to_xyz, from_xyz) = xyz.unwrap_or((identity, identity));
+        let is_oklab = if xyz.is_none() { 1.0 } else { 0.0 };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&to_xyz.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&from_xyz.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&[from[0], from[1], to[0], to[1]]);
+        buffer_content.extend_from_pods(&[rotation.cos(), rotation.sin(), is_oklab, 0.0]);
+
+        Some(buffer_content.build())
+    }
+}
@@ -4,6 +4,7 @@ use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
 
 /// The palette shader, computing texture coordinates from an input color.
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct ShaderData {
     pub u_min: [f32; 4],
     pub u_max: [f32; 4],
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Add the glow of a bright pass, blurred over a number of levels, onto an image.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub threshold: f32,
+    pub intensity: f32,
+    /// The number of pyramid levels summed into the glow.
+    pub levels: u32,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Bloom)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        Some(binary_data(&self.data, buffer))
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
+
+/// Append the parameter block of the shader.
+pub(crate) fn binary_data(data: &ShaderData, buffer: &mut Vec<u8>) -> BufferInitContent {
+    let &ShaderData {
+        threshold,
+        intensity,
+        levels,
+    } = data;
+
+    let normalize = 1.0 / levels.max(1) as f32;
+
+    let mut buffer_content = BufferInitContent::builder(buffer);
+    buffer_content.extend_from_pods(&[threshold, intensity, 0.0, 0.0]);
+    buffer_content.extend_from_pods(&[normalize, 0.0, 0.0, 0.0]);
+    buffer_content.build()
+}
@@ -2,11 +2,14 @@ use std::sync::Arc;
 
 use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
 use crate::color_matrix::RowMatrix;
+use crate::command::BorderHandling;
 
 /// The palette shader, computing texture coordinates from an input color.
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct Shader {
     pub matrix: RowMatrix,
+    /// How taps outside the texture are resolved.
+    pub border: BorderHandling,
     pub spirv: Arc<[u8]>,
 }
 
@@ -23,12 +26,95 @@ impl FragmentShaderData for Shader {
 
     /// Encode the shader's data into the buffer, returning the descriptor to that.
     fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
-        let data = self.matrix.into_mat3x3_std140();
+        let border = match self.border {
+            BorderHandling::Clamp => 0i32,
+            BorderHandling::Mirror => 1,
+            BorderHandling::Wrap => 2,
+            BorderHandling::Zero => 3,
+        };
 
-        Some(BufferInitContent::new(buffer, &data))
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&self.matrix.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&[border, 0, 0, 0]);
+        Some(buffer_content.build())
     }
 
     fn num_args(&self) -> u32 {
         1
     }
 }
+
+/// The tap coordinate remapping of `box3.frag`, `None` for a zero tap.
+#[cfg(test)]
+fn remap(coord: i32, size: i32, border: BorderHandling) -> Option<i32> {
+    if (0..size).contains(&coord) {
+        return Some(coord);
+    }
+
+    Some(match border {
+        BorderHandling::Clamp => coord.clamp(0, size - 1),
+        BorderHandling::Mirror => {
+            let coord = coord.abs();
+            let coord = if coord >= size {
+                2 * (size - 1) - coord
+            } else {
+                coord
+            };
+            coord.clamp(0, size - 1)
+        }
+        BorderHandling::Wrap => coord.rem_euclid(size),
+        BorderHandling::Zero => return None,
+    })
+}
+
+/// Apply a 3×3 kernel to a single channel, as `box3.frag` does.
+#[cfg(test)]
+fn reference(image: &[f32], (width, height): (i32, i32), shader: &Shader) -> Vec<f32> {
+    let weights = shader.matrix.into_inner();
+    let mut result = vec![];
+
+    for y in 0..height {
+        for x in 0..width {
+            let mut sum = 0.0;
+
+            for (idx, weight) in weights.iter().enumerate() {
+                let (dx, dy) = (idx as i32 % 3 - 1, idx as i32 / 3 - 1);
+                let tap_x = remap(x + dx, width, shader.border);
+                let tap_y = remap(y + dy, height, shader.border);
+
+                if let (Some(tap_x), Some(tap_y)) = (tap_x, tap_y) {
+                    sum += weight * image[(tap_y * width + tap_x) as usize];
+                }
+            }
+
+            result.push(sum);
+        }
+    }
+
+    result
+}
+
+#[test]
+fn border_handling_at_edges() {
+    // A central difference along the width.
+    let matrix = RowMatrix::new([0.0, 0.0, 0.0, 0.5, 0.0, -0.5, 0.0, 0.0, 0.0]);
+    let image = [1.0, 2.0, 4.0, 8.0];
+
+    let expected = [
+        (BorderHandling::Clamp, [-0.5, -1.5, -3.0, -2.0]),
+        (BorderHandling::Mirror, [0.0, -1.5, -3.0, 0.0]),
+        (BorderHandling::Wrap, [3.0, -1.5, -3.0, 1.5]),
+        (BorderHandling::Zero, [-1.0, -1.5, -3.0, 2.0]),
+    ];
+
+    for (border, expected) in expected {
+        let shader = Shader {
+            matrix,
+            border,
+            spirv: Arc::from(&[][..]),
+        };
+
+        let result = reference(&image, (4, 1), &shader);
+        assert_eq!(result, expected, "{border:?}");
+    }
+}
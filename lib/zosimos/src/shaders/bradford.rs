@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+
+/// The exponent of the short cone response in the original Bradford transform.
+const BLUE_EXPONENT: f32 = 0.0834;
+
+/// The non-linear Bradford chromatic adaptation, between two linear RGB colors.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// From the linear source color to XYZ.
+    pub to_xyz: RowMatrix,
+    /// From XYZ to the linear target color.
+    pub from_xyz: RowMatrix,
+    /// The source whitepoint in XYZ.
+    pub source: [f32; 3],
+    /// The target whitepoint in XYZ.
+    pub target: [f32; 3],
+    pub spirv: Arc<[u8]>,
+}
+
+/// The gains of the long and medium cones, the gain and the exponent of the short cones.
+fn adaptation(source: [f32; 3], target: [f32; 3]) -> [f32; 4] {
+    let [ls, ms, ss] = RowMatrix::BRADFORD.multiply_column(source);
+    let [lt, mt, st] = RowMatrix::BRADFORD.multiply_column(target);
+    let exponent = (ss / st).powf(BLUE_EXPONENT);
+
+    [lt / ls, mt / ms, st / ss.powf(exponent), exponent]
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::BradfordNonLinear)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let from_lms: RowMatrix = self
+            .from_xyz
+            .multiply_right(RowMatrix::BRADFORD.inv().into())
+            .into();
+
+        let mut content = BufferInitContent::builder(buffer);
+        content.extend_from_pods(&self.to_xyz.into_mat3x3_std140());
+        content.extend_from_pods(&RowMatrix::BRADFORD.into_mat3x3_std140());
+        content.extend_from_pods(&from_lms.into_mat3x3_std140());
+        content.extend_from_pods(&adaptation(self.source, self.target));
+        Some(content.build())
+    }
+}
+
+/// Adapt an XYZ color, as `bradford_nonlinear.frag` does.
+#[cfg(test)]
+fn adapt_reference(xyz: [f32; 3], source: [f32; 3], target: [f32; 3]) -> [f32; 3] {
+    let y = xyz[1];
+
+    if y <= 0.0 {
+        return [0.0; 3];
+    }
+
+    let [gain_l, gain_m, gain_s, exponent] = adaptation(source, target);
+    let [l, m, s] = RowMatrix::BRADFORD.multiply_column(xyz.map(|v| v / y));
+    let s = s.signum() * s.abs().powf(exponent);
+    let lms = [gain_l * l * y, gain_m * m * y, gain_s * s * y];
+
+    RowMatrix::BRADFORD.inv().multiply_column(lms)
+}
+
+#[test]
+fn diverges_from_linear_bradford() {
+    let d65 = [0.95047, 1.0, 1.08883];
+    let d50 = [0.96422, 1.0, 0.82521];
+    // The sRGB primaries, in XYZ relative to D65.
+    let red = [0.4124, 0.2126, 0.0193];
+    let blue = [0.1805, 0.0722, 0.9505];
+
+    let linear = RowMatrix::bradford(d65, d50);
+    let distance = |xyz: [f32; 3]| {
+        let a = adapt_reference(xyz, d65, d50);
+        let b = linear.multiply_column(xyz);
+        a.into_iter()
+            .zip(b)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f32::max)
+    };
+
+    // Both map the whitepoints, and grays, onto each other.
+    assert!(distance(d65) < 1e-5);
+    assert!(distance(d65.map(|v| v * 0.25)) < 1e-5);
+    assert!(distance(red) < 2e-3, "{}", distance(red));
+
+    // The non-linearity only stands out for the short wavelengths.
+    let blue_distance = distance(blue);
+    assert!(
+        blue_distance > 1e-2 && blue_distance < 1e-1,
+        "{blue_distance}"
+    );
+
+    assert_eq!(adapt_reference([0.0; 3], d65, d50), [0.0; 3]);
+}
@@ -0,0 +1,27 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Keep the part of each texel brighter than a threshold.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub threshold: f32,
+    /// Not used by the pass, but the parameters are shared with the bloom composite.
+    pub intensity: f32,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::BrightPass)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let params = [self.threshold, self.intensity, 0.0, 0.0];
+        Some(BufferInitContent::new(buffer, &params))
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+
+/// A matte from the chroma distance to a key color in Oklab.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ShaderData {
+    /// Transforms the linear color to XYZ, or `None` if the color is already Oklab.
+    pub(crate) xyz: Option<(RowMatrix, RowMatrix)>,
+    pub(crate) color: [f32; 3],
+    pub(crate) tolerance: f32,
+    pub(crate) softness: f32,
+    pub(crate) spill_suppression: f32,
+    /// Write the matte into all channels instead of the alpha channel.
+    pub(crate) matte: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::ChromaKey)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            xyz,
+            color: [r, g, b],
+            tolerance,
+            softness,
+            spill_suppression,
+            matte,
+        } = self.data;
+
+        let identity = RowMatrix::diag(1.0, 1.0, 1.0);
+        let (to_xyz, from_xyz) = xyz.unwrap_or((identity, identity));
+        let is_oklab = if xyz.is_none() { 1.0 } else { 0.0 };
+        let matte = if matte { 1.0 } else { 0.0 };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[r, g, b, tolerance]);
+        buffer_content.extend_from_pods(&[softness, spill_suppression, is_oklab, matte]);
+        buffer_content.extend_from_pods(&to_xyz.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&from_xyz.into_mat3x3_std140());
+
+        Some(buffer_content.build())
+    }
+}
@@ -0,0 +1,38 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::ChromaticAberration;
+
+/// Sample each color channel at its own radial scale around a center.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: ChromaticAberration,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::ChromaticAberration)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        Some(binary_data(&self.data, buffer))
+    }
+}
+
+/// Append the parameter block of the shader.
+pub(crate) fn binary_data(data: &ChromaticAberration, buffer: &mut Vec<u8>) -> BufferInitContent {
+    let &ChromaticAberration {
+        scales: [r, g, b],
+        center: [x, y],
+    } = data;
+
+    let mut buffer_content = BufferInitContent::builder(buffer);
+    buffer_content.extend_from_pods(&[r, g, b, 0.0]);
+    buffer_content.extend_from_pods(&[x, y, 0.0, 0.0]);
+    buffer_content.build()
+}
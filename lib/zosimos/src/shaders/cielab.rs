@@ -0,0 +1,139 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, Direction, FragmentShaderData, FragmentShaderKey};
+use crate::buffer::Whitepoint;
+use crate::color_matrix::RowMatrix;
+
+/// The CIELAB shader, between linear RGB and L*a*b* relative to a whitepoint.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The matrix from linear RGB to XYZ adapted to the whitepoint, or its inverse.
+    pub matrix: RowMatrix,
+    pub whitepoint: Whitepoint,
+    pub direction: Coding,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Coding {
+    Encode { spirv: Arc<[u8]> },
+    Decode { spirv: Arc<[u8]> },
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::CielabTransform(match self.direction {
+            Coding::Encode { .. } => Direction::Encode,
+            Coding::Decode { .. } => Direction::Decode,
+        }))
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        match &self.direction {
+            Coding::Encode { spirv } | Coding::Decode { spirv } => spirv.clone(),
+        }
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let data = self.matrix.into_mat3x3_std140();
+        let wp = self.whitepoint.to_xyz();
+
+        let mut content = BufferInitContent::builder(buffer);
+        content.extend_from_pods(&[wp]);
+        content.align_by_exponent(4);
+        content.extend_from_pods(&[data]);
+        Some(content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        1
+    }
+}
+
+/// The L*a*b* coordinates of an XYZ color, as `cielab.frag` computes them but scaled by 100.
+#[cfg(test)]
+fn encode_reference(xyz: [f32; 3], whitepoint: [f32; 3]) -> [f32; 3] {
+    const DELTA: f32 = 6.0 / 29.0;
+
+    let f = |t: f32| {
+        if t > DELTA * DELTA * DELTA {
+            t.cbrt()
+        } else {
+            t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+        }
+    };
+
+    let [x, y, z] = [0, 1, 2].map(|i| f(xyz[i] / whitepoint[i]));
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+/// Invert [`encode_reference`], as `cielab.frag` does.
+#[cfg(test)]
+fn decode_reference([l, a, b]: [f32; 3], whitepoint: [f32; 3]) -> [f32; 3] {
+    const DELTA: f32 = 6.0 / 29.0;
+
+    let f_inv = |f: f32| {
+        if f > DELTA {
+            f * f * f
+        } else {
+            3.0 * DELTA * DELTA * (f - 4.0 / 29.0)
+        }
+    };
+
+    let fy = (l + 16.0) / 116.0;
+    let f = [fy + a / 500.0, fy, fy - b / 200.0];
+    [0, 1, 2].map(|i| f_inv(f[i]) * whitepoint[i])
+}
+
+#[test]
+fn srgb_primaries_in_lab() {
+    use image_canvas::color::Primaries;
+
+    let to_xyz = RowMatrix(Primaries::Bt709.to_xyz_row_matrix(Whitepoint::D65));
+    let d65 = Whitepoint::D65.to_xyz();
+    let d50 = Whitepoint::D50.to_xyz();
+    let adapt = RowMatrix::from(RowMatrix::bradford(d65, d50).multiply_right(to_xyz.into()));
+
+    // Published values for the sRGB primaries, relative to D65 and after Bradford adaptation to
+    // D50 respectively.
+    let expected = [
+        (
+            [1.0, 0.0, 0.0],
+            [53.24, 80.09, 67.20],
+            [54.29, 80.80, 69.89],
+        ),
+        (
+            [0.0, 1.0, 0.0],
+            [87.73, -86.18, 83.18],
+            [87.82, -79.27, 80.99],
+        ),
+        (
+            [0.0, 0.0, 1.0],
+            [32.30, 79.19, -107.86],
+            [29.57, 68.30, -112.03],
+        ),
+    ];
+
+    for (rgb, lab_d65, lab_d50) in expected {
+        for (matrix, whitepoint, expected) in [(to_xyz, d65, lab_d65), (adapt, d50, lab_d50)] {
+            let xyz = matrix.multiply_column(rgb);
+            let lab = encode_reference(xyz, whitepoint);
+
+            for (v, e) in lab.into_iter().zip(expected) {
+                assert!(
+                    (v - e).abs() < 0.1,
+                    "{rgb:?} to {lab:?}, expected {expected:?}"
+                );
+            }
+
+            let back = matrix
+                .inv()
+                .multiply_column(decode_reference(lab, whitepoint));
+            for (v, e) in back.into_iter().zip(rgb) {
+                assert!((v - e).abs() < 1e-3, "{rgb:?} round trip to {back:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The number of bins of each tile histogram.
+pub const BINS: u32 = 256;
+
+/// The largest number of tiles along each axis.
+///
+/// All tiles are laid out along the height of intermediate images, which must stay within the
+/// texture dimension limits of WebGPU.
+pub const MAX_TILES: u32 = 64;
+
+/// One pass of contrast limited adaptive histogram equalization.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub stage: Stage,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The weights of the channels whose combination is equalized.
+    pub weights: [f32; 4],
+    pub tiles: (u32, u32),
+    /// The clip limit, relative to the average count of a bin.
+    pub clip_limit: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Stage {
+    /// Count the histogram of each tile.
+    Histogram { spirv: Arc<[u8]> },
+    /// Clip the histograms and accumulate them to mapping functions.
+    Mapping { spirv: Arc<[u8]> },
+    /// Interpolate the mapping functions of tiles to equalize each texel.
+    Apply { spirv: Arc<[u8]> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum ClaheStage {
+    Histogram,
+    Mapping,
+    Apply,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Clahe(match self.stage {
+            Stage::Histogram { .. } => ClaheStage::Histogram,
+            Stage::Mapping { .. } => ClaheStage::Mapping,
+            Stage::Apply { .. } => ClaheStage::Apply,
+        }))
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        match &self.stage {
+            Stage::Histogram { spirv } | Stage::Mapping { spirv } | Stage::Apply { spirv } => {
+                spirv.clone()
+            }
+        }
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let (tiles_x, tiles_y) = self.data.tiles;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&self.data.weights);
+        buffer_content.extend_from_pods(&[tiles_x, tiles_y, BINS, 0]);
+        buffer_content.extend_from_pods(&[self.data.clip_limit, 0.0, 0.0, 0.0]);
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        match self.stage {
+            Stage::Histogram { .. } | Stage::Mapping { .. } => 1,
+            Stage::Apply { .. } => 2,
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, Direction, FragmentShaderData, FragmentShaderKey};
+
+/// The naive CMYK shader, between electrical RGB values and ink coverages.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The fraction of the gray component replaced by black ink when encoding.
+    pub black: f32,
+    pub direction: Coding,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Coding {
+    Encode { spirv: Arc<[u8]> },
+    Decode { spirv: Arc<[u8]> },
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::CmykTransform(match self.direction {
+            Coding::Encode { .. } => Direction::Encode,
+            Coding::Decode { .. } => Direction::Decode,
+        }))
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        match &self.direction {
+            Coding::Encode { spirv } | Coding::Decode { spirv } => spirv.clone(),
+        }
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let mut content = BufferInitContent::builder(buffer);
+        content.extend_from_pods(&[self.black, 0.0, 0.0, 0.0]);
+        Some(content.build())
+    }
+}
+
+/// The ink coverages of an RGB color, as `cmyk.frag` computes them.
+#[cfg(test)]
+fn encode_reference(rgb: [f32; 3], black: f32) -> [f32; 4] {
+    let rgb = rgb.map(|v| v.clamp(0.0, 1.0));
+    let k = black * (1.0 - rgb[0].max(rgb[1]).max(rgb[2]));
+    let white = 1.0 - k;
+
+    let cmy = if white > 0.0 {
+        rgb.map(|v| ((white - v) / white).clamp(0.0, 1.0))
+    } else {
+        [0.0; 3]
+    };
+
+    [cmy[0], cmy[1], cmy[2], k]
+}
+
+/// Invert [`encode_reference`], as `cmyk.frag` does.
+#[cfg(test)]
+fn decode_reference([c, m, y, k]: [f32; 4]) -> [f32; 3] {
+    [c, m, y].map(|v| (1.0 - v) * (1.0 - k))
+}
+
+#[test]
+fn primaries_in_cmyk() {
+    const MAX: f32 = 1.0;
+
+    let primaries = [
+        ([1.0, 0.0, 0.0], [0.0, 1.0, 1.0, 0.0]),
+        ([0.0, 1.0, 0.0], [1.0, 0.0, 1.0, 0.0]),
+        ([0.0, 0.0, 1.0], [1.0, 1.0, 0.0, 0.0]),
+        ([0.0, 1.0, 1.0], [1.0, 0.0, 0.0, 0.0]),
+        ([1.0, 1.0, 1.0], [0.0, 0.0, 0.0, 0.0]),
+        ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]),
+        ([0.5, 0.5, 0.5], [0.0, 0.0, 0.0, 0.5]),
+    ];
+
+    for (rgb, cmyk) in primaries {
+        assert_eq!(encode_reference(rgb, MAX), cmyk, "{rgb:?}");
+    }
+
+    // Without black generation the gray is mixed from the colored inks.
+    assert_eq!(
+        encode_reference([0.25, 0.25, 0.25], 0.0),
+        [0.75, 0.75, 0.75, 0.0]
+    );
+
+    for black in [0.0, 0.3, 1.0] {
+        for rgb in [
+            [0.2, 0.5, 0.9],
+            [0.0, 0.0, 0.0],
+            [1.0, 0.8, 0.1],
+            [0.4, 0.4, 0.4],
+        ] {
+            let back = decode_reference(encode_reference(rgb, black));
+
+            for (a, b) in back.into_iter().zip(rgb) {
+                assert!((a - b).abs() < 1e-6, "{rgb:?} with {black} is {back:?}");
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+
+/// The maximum number of stops in a single ramp.
+///
+/// Keep in sync with the definition in `color_ramp.frag`.
+pub const MAX_STOPS: usize = 16;
+
+#[rustfmt::skip]
+const OKLAB_M1: RowMatrix = RowMatrix::new([
+    0.8189330101, 0.3618667424, -0.1288597137,
+    0.0329845436, 0.9293118715, 0.0361456387,
+    0.0482003018, 0.2643662691, 0.6338517070,
+]);
+
+#[rustfmt::skip]
+const OKLAB_M2: RowMatrix = RowMatrix::new([
+    0.2104542553, 0.7936177850, -0.0040720468,
+    1.9779984951, -2.4285922050, 0.4505937099,
+    0.0259040371, 0.7827717662, -0.8086757660,
+]);
+
+/// A piecewise linear interpolation between color stops.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub(crate) colors: [[f32; 4]; MAX_STOPS],
+    pub(crate) positions: [f32; MAX_STOPS],
+    /// The transform of XYZ to the output color, if the colors are in Oklab.
+    pub(crate) oklab: Option<RowMatrix>,
+    pub(crate) count: u32,
+    pub(crate) along_height: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+/// Convert a color in CIE XYZ to Oklab.
+pub(crate) fn oklab_from_xyz(xyz: [f32; 3]) -> [f32; 3] {
+    let lms = OKLAB_M1.multiply_column(xyz);
+    let lms_star = lms.map(f32::cbrt);
+    OKLAB_M2.multiply_column(lms_star)
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::ColorRamp)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            colors,
+            positions,
+            oklab,
+            count,
+            along_height,
+        } = &self.data;
+
+        let xyz_transform = oklab.unwrap_or(RowMatrix::diag(1.0, 1.0, 1.0));
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&colors[..]);
+        buffer_content.extend_from_pods(&positions[..]);
+        buffer_content.extend_from_pods(&xyz_transform.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&[
+            *count as i32,
+            i32::from(*along_height),
+            i32::from(oklab.is_some()),
+            0,
+        ]);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
@@ -0,0 +1,50 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::{Cfa, CfaPattern, DemosaicAlgorithm};
+
+/// Interpolate the full color of each texel of a color filter array mosaic.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub cfa: Cfa,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Demosaic)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let Cfa {
+            pattern,
+            algorithm,
+            offset: [x, y],
+        } = self.cfa;
+
+        let [red_x, red_y]: [i32; 2] = match pattern {
+            CfaPattern::Rggb => [0, 0],
+            CfaPattern::Grbg => [1, 0],
+            CfaPattern::Gbrg => [0, 1],
+            CfaPattern::Bggr => [1, 1],
+        };
+
+        let algorithm: i32 = match algorithm {
+            DemosaicAlgorithm::Bilinear => 0,
+            DemosaicAlgorithm::Malvar => 1,
+        };
+
+        // Only the parity of the origin matters.
+        let (x, y) = ((x % 2) as i32, (y % 2) as i32);
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[red_x, red_y, x, y]);
+        buffer_content.extend_from_pods(&[algorithm, 0, 0, 0]);
+
+        Some(buffer_content.build())
+    }
+}
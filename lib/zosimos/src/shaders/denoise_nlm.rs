@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The largest radius of the search window of non-local means.
+pub const MAX_SEARCH_RADIUS: u32 = 7;
+
+/// The largest radius of the patches compared by non-local means.
+pub const MAX_PATCH_RADIUS: u32 = 3;
+
+/// Average texels in a search window, weighted by the similarity of their surrounding patches.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The filter strength.
+    pub h: f32,
+    /// The weights of the channels whose combination is the luma of a texel.
+    pub weights: [f32; 4],
+    pub search_radius: u32,
+    pub patch_radius: u32,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::DenoiseNlm)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            h,
+            weights,
+            search_radius,
+            patch_radius,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[h, 0.0, 0.0, 0.0]);
+        buffer_content.extend_from_pods(&weights);
+        buffer_content.extend_from_pods(&[search_radius as i32, patch_radius as i32, 0, 0]);
+
+        Some(buffer_content.build())
+    }
+}
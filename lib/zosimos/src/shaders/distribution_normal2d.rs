@@ -2,7 +2,11 @@ use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
 use std::f32::consts::PI as PIf32;
 use std::sync::Arc;
 
+/// The size of the uniform buffer, in bytes.
+pub(crate) const UNIFORM_SIZE: usize = core::mem::size_of::<[f32; 8]>();
+
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct ShaderData {
     pub expectation: [f32; 2],
     pub covariance_inverse: Mat2,
@@ -16,6 +20,7 @@ pub struct Shader {
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct Mat2 {
     row_major: [f32; 4],
 }
@@ -38,6 +43,36 @@ impl ShaderData {
         }
     }
 
+    /// Construct a centered normal distribution, elongated along rotated axes.
+    ///
+    /// The standard deviations `sigma_x` and `sigma_y` apply before the rotation, which is
+    /// counter-clockwise by `angle` in radians. With the positive axes towards the bottom and right
+    /// this appears clockwise on screen. A standard deviation of zero degenerates to a 1d
+    /// distribution along the other axis, as for [`Self::with_diagonal`].
+    pub fn with_rotation(sigma_x: f32, sigma_y: f32, angle: f32) -> Self {
+        let (var0, var1) = (sigma_x * sigma_x, sigma_y * sigma_y);
+
+        let d0 = if var0 == 0.0 { 0.0 } else { 1.0 / var0 };
+        let d1 = if var1 == 0.0 { 0.0 } else { 1.0 / var1 };
+
+        let f0 = if var0 == 0.0 { 1.0 } else { 2.0 * PIf32 * var0 };
+        let f1 = if var1 == 0.0 { 1.0 } else { 2.0 * PIf32 * var1 };
+
+        // The (pseudo) inverse of R·diag(var0, var1)·R^T is R·diag(d0, d1)·R^T.
+        let (sin, cos) = angle.sin_cos();
+        let xx = cos * cos * d0 + sin * sin * d1;
+        let xy = cos * sin * (d0 - d1);
+        let yy = sin * sin * d0 + cos * cos * d1;
+
+        ShaderData {
+            expectation: [0.0, 0.0],
+            covariance_inverse: Mat2 {
+                row_major: [xx, xy, xy, yy],
+            },
+            pseudo_determinant: f0 * f1,
+        }
+    }
+
     /// Construct a 1d, centered normal distribution given the direction.
     ///
     /// The length of the vector defines the variance. The positive direction of the coordinate
@@ -129,3 +164,25 @@ impl FragmentShaderData for Shader {
         0
     }
 }
+
+#[test]
+fn rotation_of_diagonal() {
+    let close = |a: f32, b: f32| (a - b).abs() <= 1e-3 * a.abs().max(b.abs());
+
+    let diagonal = ShaderData::with_diagonal(0.04, 0.01);
+    let [dx, _, _, dy] = diagonal.covariance_inverse.row_major;
+
+    let straight = ShaderData::with_rotation(0.2, 0.1, 0.0);
+    let [xx, xy, yx, yy] = straight.covariance_inverse.row_major;
+    assert!(close(xx, dx) && close(yy, dy));
+    assert!(xy == 0.0 && yx == 0.0);
+
+    let quarter = ShaderData::with_rotation(0.1, 0.2, core::f32::consts::FRAC_PI_2);
+    let [xx, xy, yx, yy] = quarter.covariance_inverse.row_major;
+    assert!(close(xx, dx) && close(yy, dy));
+    assert!(xy.abs() < 1e-3 * dx && xy == yx);
+    assert!(close(
+        quarter.pseudo_determinant,
+        diagonal.pseudo_determinant
+    ));
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// One pass of averaging blocks of texels.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+/// The largest factor of a single pass, along each axis.
+pub const MAX_FACTOR: u32 = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The size of the blocks averaged in this pass.
+    pub factor: (u32, u32),
+    /// The number of original texels covered by each source texel, along each axis.
+    pub scale: (u32, u32),
+    /// The size of the original image.
+    pub size: (u32, u32),
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Downsample)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            factor,
+            scale,
+            size,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[factor.0, factor.1, scale.0, scale.1]);
+        buffer_content.extend_from_pods(&[size.0, size.1, 0, 0]);
+        Some(buffer_content.build())
+    }
+}
+
+/// Split a factor into the factors of successive passes.
+///
+/// Each is at most [`MAX_FACTOR`] unless the factor has a larger prime divisor, which can only be
+/// done as one pass of its own. There is always at least one pass.
+pub(crate) fn passes(mut factor: u32) -> Vec<u32> {
+    let mut passes = vec![];
+
+    while factor > 1 {
+        let pass = (2..=MAX_FACTOR.min(factor))
+            .rev()
+            .find(|div| factor % div == 0)
+            .unwrap_or_else(|| {
+                // No small divisor, use the smallest one.
+                (2..=factor).find(|div| factor % div == 0).unwrap()
+            });
+
+        passes.push(pass);
+        factor /= pass;
+    }
+
+    if passes.is_empty() {
+        passes.push(1);
+    }
+
+    passes
+}
+
+#[test]
+fn passes_multiply_to_factor() {
+    assert_eq!(passes(1), [1]);
+    assert_eq!(passes(12), [12]);
+    assert_eq!(passes(64), [16, 4]);
+    assert_eq!(passes(34), [2, 17]);
+    assert_eq!(passes(19), [19]);
+
+    for factor in 1..1000u32 {
+        let passes = passes(factor);
+        assert_eq!(passes.iter().product::<u32>(), factor);
+        assert!(passes
+            .iter()
+            .all(|&pass| pass <= MAX_FACTOR || factor % pass == 0));
+    }
+}
@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// A directional relief of the luma, around mid-gray or the source color.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub direction_rad: f32,
+    pub depth: f32,
+    /// The weights of the channels whose combination is the luma of a texel.
+    pub weights: [f32; 4],
+    pub recolor: bool,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Emboss)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            direction_rad,
+            depth,
+            weights,
+            recolor,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[direction_rad, depth, 0.0, 0.0]);
+        buffer_content.extend_from_pods(&weights);
+        buffer_content.extend_from_pods(&[i32::from(recolor), 0, 0, 0]);
+
+        Some(buffer_content.build())
+    }
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// One step of a pairwise exposure fusion.
+///
+/// The accumulated image holds the weighted mean of all prior exposures in its color channels,
+/// and the mean of their weights in its alpha channel.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// Exponents of contrast, saturation and well-exposedness, then the sigma of the latter.
+    pub(crate) weights: [f32; 4],
+    /// The number of exposures fused after this step.
+    pub(crate) count: u32,
+    /// If this is the last step, the alpha channel is taken from the exposure.
+    pub(crate) last: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::ExposureFusion)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let step = [
+            self.data.count as f32,
+            if self.data.last { 1.0 } else { 0.0 },
+            0.0,
+            0.0,
+        ];
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&self.data.weights);
+        buffer_content.extend_from_pods(&step);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
@@ -3,11 +3,24 @@ use std::sync::Arc;
 use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct ShaderData {
     pub num_octaves: u32,
     pub initial_amplitude: f32,
     pub amplitude_damping: f32,
     pub grid_scale: [f32; 2],
+    /// The factor of frequencies between consecutive octaves.
+    pub lacunarity: f32,
+    /// How far coordinates are displaced by another evaluation of the noise, in units of the
+    /// cells of the first octave.
+    ///
+    /// Zero disables the domain warp, larger values produce marble-like swirls.
+    pub warp: f32,
+    /// Selects one of many uncorrelated patterns.
+    ///
+    /// The same seed results in the same image on the same device, each output channel is
+    /// decorrelated independently by a different seed.
+    pub seed: u32,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -28,6 +41,9 @@ impl ShaderData {
             grid_scale,
             initial_amplitude,
             amplitude_damping,
+            lacunarity: 2.0,
+            warp: 0.0,
+            seed: 0,
         }
     }
 
@@ -47,6 +63,11 @@ impl ShaderData {
         };
         self.amplitude_damping = damping;
     }
+
+    /// Check the parameters of octaves, which the shader can not recover from.
+    pub(crate) fn has_valid_octaves(&self) -> bool {
+        self.lacunarity > 0.0 && self.lacunarity.is_finite() && self.warp.is_finite()
+    }
 }
 
 #[test]
@@ -79,6 +100,9 @@ impl FragmentShaderData for Shader {
             initial_amplitude,
             amplitude_damping,
             grid_scale,
+            lacunarity,
+            warp,
+            seed,
         } = self.data;
 
         let mut buffer_content = BufferInitContent::builder(buffer);
@@ -86,6 +110,8 @@ impl FragmentShaderData for Shader {
         buffer_content.extend_from_pods(&[initial_amplitude]);
         buffer_content.extend_from_pods(&[amplitude_damping]);
         buffer_content.extend_from_pods(&[num_octaves]);
+        buffer_content.extend_from_pods(&[lacunarity, warp]);
+        buffer_content.extend_from_pods(&[seed]);
         buffer_content.align_by_exponent(3);
 
         Some(buffer_content.build())
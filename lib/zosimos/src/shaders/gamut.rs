@@ -0,0 +1,162 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+use crate::command::GamutMapping;
+
+/// Map linear RGB colors into their gamut by reducing chroma in Oklch.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// From the linear color to XYZ, relative to D65.
+    pub to_xyz: RowMatrix,
+    pub mapping: GamutMapping,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::GamutMap)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let method: u32 = match self.mapping {
+            GamutMapping::Clip => 0,
+            GamutMapping::ChromaCompress => 1,
+            GamutMapping::SoftClip => 2,
+        };
+
+        let mut content = BufferInitContent::builder(buffer);
+        content.extend_from_pods(&self.to_xyz.into_mat3x3_std140());
+        content.extend_from_pods(&self.to_xyz.inv().into_mat3x3_std140());
+        content.extend_from_pods(&[method, 0, 0, 0]);
+        Some(content.build())
+    }
+}
+
+#[cfg(test)]
+mod reference {
+    use super::{GamutMapping, RowMatrix};
+
+    #[rustfmt::skip]
+    const M1: RowMatrix = RowMatrix::new([
+        0.8189330101, 0.3618667424, -0.1288597137,
+        0.0329845436, 0.9293118715, 0.0361456387,
+        0.0482003018, 0.2643662691, 0.6338517070,
+    ]);
+
+    #[rustfmt::skip]
+    const M2: RowMatrix = RowMatrix::new([
+        0.2104542553, 0.7936177850, -0.0040720468,
+        1.9779984951, -2.4285922050, 0.4505937099,
+        0.0259040371, 0.7827717662, -0.8086757660,
+    ]);
+
+    pub(super) fn to_oklab(rgb: [f32; 3], to_xyz: RowMatrix) -> [f32; 3] {
+        let lms = M1.multiply_column(to_xyz.multiply_column(rgb));
+        M2.multiply_column(lms.map(f32::cbrt))
+    }
+
+    fn to_rgb(lab: [f32; 3], to_xyz: RowMatrix) -> [f32; 3] {
+        let lms = M2.inv().multiply_column(lab).map(|v| v * v * v);
+        to_xyz.inv().multiply_column(M1.inv().multiply_column(lms))
+    }
+
+    fn in_gamut(rgb: [f32; 3]) -> bool {
+        rgb.iter().all(|v| (-1e-5..=1.0 + 1e-5).contains(v))
+    }
+
+    /// Map a color into the gamut, as `gamut_map.frag` does.
+    pub(super) fn map(rgb: [f32; 3], to_xyz: RowMatrix, mapping: GamutMapping) -> [f32; 3] {
+        let clip = |rgb: [f32; 3]| rgb.map(|v| v.clamp(0.0, 1.0));
+
+        let [lightness, a, b] = to_oklab(rgb, to_xyz);
+        let lightness = lightness.clamp(0.0, 1.0);
+        let chroma = a.hypot(b);
+
+        let compress = match mapping {
+            GamutMapping::Clip => return clip(rgb),
+            _ if chroma <= 0.0 => return clip(rgb),
+            GamutMapping::ChromaCompress if in_gamut(rgb) => return clip(rgb),
+            other => other,
+        };
+
+        let hue = [a / chroma, b / chroma];
+        let at = |c: f32| [lightness, c * hue[0], c * hue[1]];
+
+        let (mut inside, mut outside) = (0.0f32, 1.0f32);
+        for _ in 0..24 {
+            let mid = 0.5 * (inside + outside);
+            if in_gamut(to_rgb(at(mid), to_xyz)) {
+                inside = mid;
+            } else {
+                outside = mid;
+            }
+        }
+
+        let mapped = match compress {
+            GamutMapping::SoftClip => {
+                let knee = 0.8 * inside;
+                let range = (inside - knee).max(1e-6);
+                if chroma <= knee {
+                    chroma
+                } else {
+                    knee + range * ((chroma - knee) / range).tanh()
+                }
+            }
+            _ => chroma.min(inside),
+        };
+
+        clip(to_rgb(at(mapped), to_xyz))
+    }
+}
+
+#[test]
+fn compression_keeps_hue() {
+    use crate::buffer::{Primaries, Whitepoint};
+
+    let srgb = RowMatrix(Primaries::Bt709.to_xyz_row_matrix(Whitepoint::D65));
+    // The red primary of Display P3, in linear sRGB.
+    let p3_red = [1.2249, -0.0421, -0.0196];
+
+    let polar = |rgb: [f32; 3]| {
+        let [lightness, a, b] = reference::to_oklab(rgb, srgb);
+        (lightness, b.atan2(a).to_degrees())
+    };
+
+    let (lightness, hue) = polar(p3_red);
+    let clipped = reference::map(p3_red, srgb, GamutMapping::Clip);
+    let compressed = reference::map(p3_red, srgb, GamutMapping::ChromaCompress);
+    let soft = reference::map(p3_red, srgb, GamutMapping::SoftClip);
+
+    assert_eq!(clipped, [1.0, 0.0, 0.0]);
+    assert!(
+        compressed[1] > 0.01 && compressed[2] > 0.01,
+        "{compressed:?}"
+    );
+    assert!(soft != compressed && soft != clipped, "{soft:?}");
+
+    // Clipping shifts the hue and darkens, compression preserves both.
+    let (clip_lightness, clip_hue) = polar(clipped);
+    assert!((clip_hue - hue).abs() > 0.2, "{clip_hue} against {hue}");
+    assert!((clip_lightness - lightness).abs() > 0.01);
+
+    for mapped in [compressed, soft] {
+        let (mapped_lightness, mapped_hue) = polar(mapped);
+        assert!(
+            (mapped_hue - hue).abs() < 0.05,
+            "{mapped_hue} against {hue}"
+        );
+        assert!((mapped_lightness - lightness).abs() < 1e-3);
+    }
+
+    // Colors inside the gamut are only changed by the soft clip, close to the boundary.
+    let gray = [0.5, 0.5, 0.5];
+    for mapping in [GamutMapping::ChromaCompress, GamutMapping::SoftClip] {
+        let mapped = reference::map(gray, srgb, mapping);
+        assert!(mapped.iter().all(|v| (v - 0.5).abs() < 1e-4), "{mapped:?}");
+    }
+}
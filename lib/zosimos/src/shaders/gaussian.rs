@@ -0,0 +1,96 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The largest radius of a single pass, in texels.
+///
+/// Each texel of the result reads this many texels on either side, wider blurs should first
+/// reduce the image.
+pub const MAX_RADIUS: u32 = 128;
+
+/// One separable pass of a gaussian blur, along one axis.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The standard deviation, in texels.
+    pub sigma: f32,
+    pub vertical: bool,
+    /// Move the result along the axis, in texels.
+    pub shift: i32,
+    /// Clamp reads to the edge, instead of treating the outside as transparent.
+    pub clamp: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl ShaderData {
+    /// The radius covering three standard deviations.
+    pub(crate) fn radius(sigma: f32) -> u32 {
+        (3.0 * sigma).ceil() as u32
+    }
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Gaussian)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            sigma,
+            vertical,
+            shift,
+            clamp,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[
+            i32::from(vertical),
+            ShaderData::radius(sigma) as i32,
+            shift,
+            i32::from(clamp),
+        ]);
+        buffer_content.extend_from_pods(&[sigma, 0.0, 0.0, 0.0]);
+
+        Some(buffer_content.build())
+    }
+
+    /// The radius bounds the loop over the taps of the kernel.
+    fn specialization(&self) -> Vec<(u32, u32)> {
+        vec![(0, ShaderData::radius(self.data.sigma))]
+    }
+}
+
+#[test]
+fn radius_specializes_pipeline() {
+    let shader = |sigma: f32| Shader {
+        data: ShaderData {
+            sigma,
+            vertical: false,
+            shift: 0,
+            clamp: false,
+        },
+        spirv: Arc::from(&[][..]),
+    };
+
+    let key = |shader: &Shader| {
+        let constants = shader.specialization();
+        shader.key().map(|key| key.specialize(&constants))
+    };
+
+    let (narrow, wide) = (shader(1.0), shader(3.0));
+    assert_eq!(narrow.specialization(), [(0, 3)]);
+    assert_eq!(wide.specialization(), [(0, 9)]);
+    assert_ne!(key(&narrow), key(&wide));
+
+    // Only the radius is a constant, the standard deviation stays in the parameter block.
+    assert_eq!(key(&narrow), key(&shader(0.9)));
+}
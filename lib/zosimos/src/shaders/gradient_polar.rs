@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::color_matrix::RowMatrix;
+
+/// The magnitude and orientation of the luma gradient, from a pair of 3×3 derivative kernels.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The derivative along the width, its transpose is taken along the height.
+    pub kernel: RowMatrix,
+    /// The weights of the channels whose combination is the luma of a texel.
+    pub weights: [f32; 4],
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::GradientPolar)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&self.kernel.into_mat3x3_std140());
+        buffer_content.extend_from_pods(&self.weights);
+        Some(buffer_content.build())
+    }
+}
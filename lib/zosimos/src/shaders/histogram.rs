@@ -0,0 +1,66 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The largest number of bins of a histogram.
+///
+/// Bins are laid out along the width of intermediate images, which must stay within the texture
+/// dimension limits of WebGPU.
+pub const MAX_BINS: u32 = 4096;
+
+/// One pass of counting the texels of an image into bins.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub stage: Stage,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The weights of the channels whose combination is binned.
+    pub weights: [f32; 4],
+    pub bins: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Stage {
+    /// Count each bin for each row of the source image.
+    Rows { data: ShaderData, spirv: Arc<[u8]> },
+    /// Sum the per-row counts into a single row.
+    Sum { spirv: Arc<[u8]> },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum HistogramStage {
+    Rows,
+    Sum,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Histogram(match self.stage {
+            Stage::Rows { .. } => HistogramStage::Rows,
+            Stage::Sum { .. } => HistogramStage::Sum,
+        }))
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        match &self.stage {
+            Stage::Rows { spirv, .. } | Stage::Sum { spirv } => spirv.clone(),
+        }
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let Stage::Rows { data, .. } = &self.stage else {
+            return None;
+        };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&data.weights);
+        buffer_content.extend_from_pods(&[data.bins, 0, 0, 0]);
+        Some(buffer_content.build())
+    }
+}
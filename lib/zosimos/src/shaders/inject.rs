@@ -1,16 +1,23 @@
 use std::sync::Arc;
 
 use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::Rectangle;
 
-/// The palette shader, computing texture coordinates from an input color.
-#[derive(Clone, Debug, Default, PartialEq)]
+/// The inject shader, replacing channels of the background with a color of the foreground.
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ShaderData {
     pub mix: [f32; 4],
     /// How to determine the color to mix from the foreground (dot product).
     pub color: [f32; 4],
+    /// The texels of the background to which the foreground is stretched.
+    pub placement: Rectangle,
+    /// The size of the foreground image.
+    pub size: (u32, u32),
+    /// Interpolate bi-linearly instead of choosing the nearest texel.
+    pub bilinear: bool,
 }
 
-#[derive(Clone, Debug, Default, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Shader {
     pub data: ShaderData,
     pub spirv: Arc<[u8]>,
@@ -26,8 +33,27 @@ impl FragmentShaderData for Shader {
     }
 
     fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
-        let data = [self.data.mix, self.data.color];
-        Some(BufferInitContent::new(buffer, &data))
+        let ShaderData {
+            mix,
+            color,
+            placement,
+            size: (width, height),
+            bilinear,
+        } = self.data;
+
+        let sampling = if bilinear { 1.0 } else { 0.0 };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&mix);
+        buffer_content.extend_from_pods(&color);
+        buffer_content.extend_from_pods(&[
+            placement.x as f32,
+            placement.y as f32,
+            placement.max_x as f32,
+            placement.max_y as f32,
+        ]);
+        buffer_content.extend_from_pods(&[width as f32, height as f32, sampling, 0.0]);
+        Some(buffer_content.build())
     }
 
     fn num_args(&self) -> u32 {
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::Distortion;
+
+/// Undo a lens distortion by sampling the distorted coordinate of each texel.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: Distortion,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::LensUndistort)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let Distortion {
+            k1,
+            k2,
+            k3,
+            p1,
+            p2,
+            center: [cx, cy],
+            focal: [fx, fy],
+            fill,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[k1, k2, k3, 0.0]);
+        buffer_content.extend_from_pods(&[p1, p2, 0.0, 0.0]);
+        buffer_content.extend_from_pods(&[fx, fy, cx, cy]);
+        buffer_content.extend_from_pods(&fill);
+
+        Some(buffer_content.build())
+    }
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The largest radius of a window of local statistics.
+pub const MAX_RADIUS: u32 = 15;
+
+/// Statistics over a gaussian weighted window around each texel.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The weights of the channels whose combination is the value of a texel.
+    pub weights: [f32; 4],
+    /// The window spans `2·radius + 1` texels in each direction.
+    pub radius: u32,
+    /// The standard deviation of the gaussian weights.
+    pub sigma: f32,
+    /// The stabilizing constants of the structural similarity.
+    pub c1: f32,
+    pub c2: f32,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Ssim)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            weights,
+            radius,
+            sigma,
+            c1,
+            c2,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&weights);
+        buffer_content.extend_from_pods(&[radius as f32, sigma, c1, c2]);
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
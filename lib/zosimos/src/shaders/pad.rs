@@ -0,0 +1,55 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::BorderMode;
+
+/// Place an image into a target, determining the texels outside of it by a border mode.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    /// The position of the source's origin in the target, may be outside the target.
+    pub offset: (i32, i32),
+    /// The size of the source image.
+    pub size: (u32, u32),
+    pub border: BorderMode,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Pad)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            offset: (x, y),
+            size: (width, height),
+            border,
+        } = self.data;
+
+        let (mode, color) = match border {
+            BorderMode::Constant(color) => (0, color),
+            BorderMode::Clamp => (1, [0.0; 4]),
+            BorderMode::Mirror => (2, [0.0; 4]),
+            BorderMode::Wrap => (3, [0.0; 4]),
+        };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[x, y, width as i32, height as i32]);
+        buffer_content.extend_from_pods(&[mode, 0, 0, 0]);
+        buffer_content.extend_from_pods(&color);
+        Some(buffer_content.build())
+    }
+}
@@ -2,14 +2,18 @@ use std::sync::Arc;
 
 use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
 use crate::buffer::{ChannelPosition, ColorChannel};
+use crate::command::PaletteAddress;
 
 /// The palette shader, computing texture coordinates from an input color.
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 pub struct ShaderData {
     pub(crate) x_coord: [f32; 4],
     pub(crate) y_coord: [f32; 4],
     pub(crate) base_x: i32,
     pub(crate) base_y: i32,
+    pub(crate) address: PaletteAddress,
+    pub(crate) interpolate: bool,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -24,15 +28,18 @@ impl ChannelPosition {
     /// The caller in `command` is responsible for ensuring
     ///
     /// Reminder: we are looking for the position of the color channel in the _linear_
-    /// representation of the color, i.e. within the vec4 loaded from the sampled texture.
+    /// representation of the color, i.e. within the vec4 loaded from the sampled texture. A `Luma`
+    /// channel is staged into all three color components, we pick the first of them. The alpha
+    /// channel is always the last component.
     // For further colors later.
     #[allow(unreachable_patterns)]
     pub(crate) fn new(channel: ColorChannel) -> Option<Self> {
         use ColorChannel as Cc;
         Some(match channel {
-            Cc::R => ChannelPosition::First,
+            Cc::R | Cc::Luma => ChannelPosition::First,
             Cc::G => ChannelPosition::Second,
             Cc::B => ChannelPosition::Third,
+            Cc::Alpha => ChannelPosition::Fourth,
             _ => return None,
         })
     }
@@ -62,7 +69,36 @@ impl FragmentShaderData for Shader {
             self.data.x_coord[3], self.data.y_coord[3],
         ];
 
-        Some(BufferInitContent::new(buffer, &mat4x2))
+        // The shader paints the border color where the flag after the base is set.
+        let (flag, border) = match self.data.address {
+            PaletteAddress::Border(color) => (1.0, color),
+            _ => (0.0, [0.0; 4]),
+        };
+
+        let base = [self.data.base_x as f32, self.data.base_y as f32, flag, 0.0];
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&mat4x2);
+        buffer_content.extend_from_pods(&base);
+        buffer_content.extend_from_pods(&border);
+        Some(buffer_content.build())
+    }
+
+    fn address_mode(&self) -> wgpu::AddressMode {
+        match self.data.address {
+            // The border is painted by the shader, beyond the clamped edge.
+            PaletteAddress::Clamp | PaletteAddress::Border(_) => wgpu::AddressMode::ClampToEdge,
+            PaletteAddress::Repeat => wgpu::AddressMode::Repeat,
+            PaletteAddress::MirrorRepeat => wgpu::AddressMode::MirrorRepeat,
+        }
+    }
+
+    fn filter_mode(&self) -> wgpu::FilterMode {
+        if self.data.interpolate {
+            wgpu::FilterMode::Linear
+        } else {
+            wgpu::FilterMode::Nearest
+        }
     }
 
     fn num_args(&self) -> u32 {
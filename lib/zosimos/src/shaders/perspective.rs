@@ -0,0 +1,54 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Paint an image on top of another with a projective transformation.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) struct ShaderData {
+    /// The inverse of the homography, row-major, mapping target to source texel coordinates.
+    pub inverse: [f32; 9],
+    /// The size of the source image.
+    pub size: (u32, u32),
+    /// Interpolate bi-linearly instead of choosing the nearest texel.
+    pub bilinear: bool,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Perspective)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            inverse: [a, b, c, d, e, f, g, h, i],
+            size: (width, height),
+            bilinear,
+        } = self.data;
+
+        let sampling = if bilinear { 1.0 } else { 0.0 };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[a, b, c, 0.0]);
+        buffer_content.extend_from_pods(&[d, e, f, 0.0]);
+        buffer_content.extend_from_pods(&[g, h, i, 0.0]);
+        buffer_content.extend_from_pods(&[width as f32, height as f32, sampling, 0.0]);
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
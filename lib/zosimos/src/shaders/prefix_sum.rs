@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// One step of a prefix sum along one axis of an image.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The offset of the texel which is added.
+    pub offset: (u32, u32),
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::PrefixSum)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let (x, y) = self.offset;
+        Some(BufferInitContent::new(buffer, &[x, y, 0, 0]))
+    }
+}
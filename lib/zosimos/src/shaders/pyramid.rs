@@ -0,0 +1,33 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Combine a level of a pyramid with the expansion of the next coarser level.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// Subtract the expanded level instead of adding it.
+    pub subtract: bool,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::PyramidExpand)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let factor: f32 = if self.subtract { -1.0 } else { 1.0 };
+        Some(BufferInitContent::new(buffer, &[factor, 0.0, 0.0, 0.0]))
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::Reduction;
+
+/// One step of a reduction, halving the size of the input image.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub reduction: Reduction,
+    /// The size of the original image, at the start of the chain.
+    pub size: (u32, u32),
+    /// The number of steps preceding this one.
+    pub level: u32,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Reduce(self.data.reduction))
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let (width, height) = self.data.size;
+        Some(BufferInitContent::new(
+            buffer,
+            &[width, height, self.data.level, 0],
+        ))
+    }
+}
@@ -0,0 +1,110 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// One separable pass of resampling an image along one axis.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub kernel: Kernel,
+    /// Resample along the height instead of the width.
+    pub vertical: bool,
+    /// The number of source texels per target texel, along the axis.
+    pub scale: f32,
+}
+
+/// A reconstruction kernel, as in `resample.frag`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) enum Kernel {
+    Triangle = 0,
+    CatmullRom = 1,
+    Lanczos3 = 2,
+}
+
+impl Kernel {
+    /// The radius outside of which the kernel is zero.
+    pub fn support(self) -> f32 {
+        match self {
+            Kernel::Triangle => 1.0,
+            Kernel::CatmullRom => 2.0,
+            Kernel::Lanczos3 => 3.0,
+        }
+    }
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Resample)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            kernel,
+            vertical,
+            scale,
+        } = self.data;
+
+        // The kernel is widened when downscaling.
+        let support = kernel.support() * scale.max(1.0);
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[kernel as u32, u32::from(vertical), 0, 0]);
+        buffer_content.extend_from_pods(&[scale, support, 0.0, 0.0]);
+        Some(buffer_content.build())
+    }
+}
+
+/// The kernels of `resample.frag`, for reference.
+#[cfg(test)]
+fn kernel(kernel: Kernel, x: f32) -> f32 {
+    use core::f32::consts::PI;
+    let sinc = |x: f32| {
+        if x.abs() < 1e-6 {
+            1.0
+        } else {
+            (PI * x).sin() / (PI * x)
+        }
+    };
+    let t = x.abs();
+
+    match kernel {
+        Kernel::Triangle => (1.0 - t).max(0.0),
+        Kernel::CatmullRom if t < 1.0 => (1.5 * t - 2.5) * t * t + 1.0,
+        Kernel::CatmullRom if t < 2.0 => ((-0.5 * t + 2.5) * t - 4.0) * t + 2.0,
+        Kernel::CatmullRom => 0.0,
+        Kernel::Lanczos3 if t < 3.0 => sinc(t) * sinc(t / 3.0),
+        Kernel::Lanczos3 => 0.0,
+    }
+}
+
+#[test]
+fn kernels_interpolate() {
+    for filter in [Kernel::Triangle, Kernel::CatmullRom, Kernel::Lanczos3] {
+        assert!((kernel(filter, 0.0) - 1.0).abs() < 1e-6);
+
+        for n in 1..4 {
+            assert!(kernel(filter, n as f32).abs() < 1e-6, "{filter:?} at {n}");
+            assert!(
+                kernel(filter, -(n as f32)).abs() < 1e-6,
+                "{filter:?} at -{n}"
+            );
+        }
+
+        // Continuous at the edge of the support.
+        assert!(kernel(filter, filter.support() - 1e-4).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,29 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Composite an image over the tinted silhouette of a blurred alpha channel.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The color of the shadow, and its opacity in alpha.
+    pub color: [f32; 4],
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::DropShadow)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        Some(BufferInitContent::new(buffer, &self.color))
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Add the Laplacian of an image onto itself, fused into a single 3×3 pass.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub amount: f32,
+    /// Clamp the result to the range representable by integer texels.
+    pub clamp: bool,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Sharpen)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        Some(binary_data(&self.data, buffer))
+    }
+}
+
+/// Append the parameter block of the shader.
+pub(crate) fn binary_data(data: &ShaderData, buffer: &mut Vec<u8>) -> BufferInitContent {
+    let &ShaderData { amount, clamp } = data;
+
+    let mut buffer_content = BufferInitContent::builder(buffer);
+    buffer_content.extend_from_pods(&[amount, 0.0, 0.0, 0.0]);
+    buffer_content.extend_from_pods(&[i32::from(clamp), 0, 0, 0]);
+    buffer_content.build()
+}
@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::FractalNoise;
+
+/// Fractal noise of octaves of simplex noise, with the same parameters as the fractal noise.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: FractalNoise,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::SimplexNoise)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let FractalNoise {
+            num_octaves,
+            initial_amplitude,
+            amplitude_damping,
+            grid_scale,
+            lacunarity,
+            warp,
+            seed,
+        } = self.data;
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[grid_scale[0], grid_scale[1]]);
+        buffer_content.extend_from_pods(&[initial_amplitude]);
+        buffer_content.extend_from_pods(&[amplitude_damping]);
+        buffer_content.extend_from_pods(&[num_octaves]);
+        buffer_content.extend_from_pods(&[lacunarity, warp]);
+        buffer_content.extend_from_pods(&[seed]);
+        buffer_content.align_by_exponent(3);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The maximum number of control points of a spline.
+///
+/// Keep in sync with the definition in `spline_ramp.frag`.
+pub const MAX_POINTS: usize = 16;
+
+/// A Catmull-Rom spline through control points, evaluated along the width.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub(crate) points: [[f32; 4]; MAX_POINTS],
+    pub(crate) count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::SplineRamp)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&self.data.points[..]);
+        buffer_content.extend_from_pods(&[self.data.count as i32, 0, 0, 0]);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
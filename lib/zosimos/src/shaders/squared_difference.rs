@@ -0,0 +1,25 @@
+use std::sync::Arc;
+
+use super::{FragmentShaderData, FragmentShaderKey};
+
+/// The squared difference of two images, in each linear color channel.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::SquaredDifference)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn num_args(&self) -> u32 {
+        2
+    }
+}
@@ -3,6 +3,7 @@ use core::num::NonZeroU32;
 use std::sync::Arc;
 
 use crate::buffer::{SampleBits, SampleParts, Transfer as RgbTransfer};
+#[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
 use wgpu::TextureFormat;
 
@@ -20,16 +21,29 @@ pub(crate) struct XyzParameter {
     pub bits: SampleBits,
     pub parts: SampleParts,
     pub transfer: Transfer,
+    /// Add an ordered dither of one quantization step before encoding integer samples.
+    pub dither: bool,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 pub struct Shaders {
     pub decode_r8ui_x4: Arc<[u8]>,
     pub decode_r16ui_x2: Arc<[u8]>,
     pub decode_r32ui: Arc<[u8]>,
+    pub decode_rgba16ui: Arc<[u8]>,
+    pub decode_rgba32ui: Arc<[u8]>,
+    pub decode_rgb10a2ui: Arc<[u8]>,
+    pub decode_rgb565ui_x2: Arc<[u8]>,
+    pub decode_rgba4ui_x2: Arc<[u8]>,
     pub encode_r8ui_x4: Arc<[u8]>,
     pub encode_r16ui_x2: Arc<[u8]>,
     pub encode_r32ui: Arc<[u8]>,
+    pub encode_rgba16ui: Arc<[u8]>,
+    pub encode_rgba32ui: Arc<[u8]>,
+    pub encode_rgb10a2ui: Arc<[u8]>,
+    pub encode_rgb565ui_x2: Arc<[u8]>,
+    pub encode_rgba4ui_x2: Arc<[u8]>,
 }
 
 /// Defines the bit representation we use for our own coding of texels and pixels.
@@ -47,6 +61,13 @@ pub(crate) enum StageKind {
     /// Each texel is 128 bit and we decode it from 32-bit RGBA.
     /// That's scarily large.
     Rgba32ui = 4,
+    /// Each texel is 32 bit, packed as three 10-bit channels and one 2-bit channel.
+    Rgb10a2ui = 5,
+    /// Each texel is 16 bit, packed as 5-6-5 bits, and we operate on 2 texels at the same time.
+    Rgb565uiX2 = 6,
+    /// Each texel is 16 bit, packed as four 4-bit channels, and we operate on 2 texels at the
+    /// same time.
+    Rgba4uiX2 = 7,
 }
 
 impl XyzParameter {
@@ -56,11 +77,19 @@ impl XyzParameter {
             Self::serialize_parts(self.parts),
             Self::serialize_bits(self.bits),
             // Upper bits are still reserved for texel block size.
-            self.horizontal_subfactor() & 0xff,
+            (self.horizontal_subfactor() & 0xff) | (u32::from(self.dither) << 8),
         ]
     }
 
     pub(crate) fn stage_kind(&self) -> Option<StageKind> {
+        // The packed formats round when encoding, their channels have too few levels to truncate.
+        match self.bits {
+            SampleBits::UInt1010102 | SampleBits::UInt2101010 => return Some(StageKind::Rgb10a2ui),
+            SampleBits::UInt565 => return Some(StageKind::Rgb565uiX2),
+            SampleBits::UInt4x4 => return Some(StageKind::Rgba4uiX2),
+            _ => {}
+        }
+
         Some(match self.bits.bytes() {
             1 => StageKind::R8uiX4,
             2 => StageKind::R16uiX2,
@@ -132,12 +161,15 @@ impl XyzParameter {
 }
 
 impl StageKind {
-    pub const ALL: [Self; 5] = [
+    pub const ALL: [Self; 8] = [
         Self::R8uiX4,
         Self::R16uiX2,
         Self::R32ui,
         Self::Rgba16ui,
         Self::Rgba32ui,
+        Self::Rgb10a2ui,
+        Self::Rgb565uiX2,
+        Self::Rgba4uiX2,
     ];
 
     pub(crate) fn encode_entry_point(self) -> &'static str {
@@ -147,6 +179,9 @@ impl StageKind {
             Self::R32ui => "encode_r32ui",
             Self::Rgba16ui => "encode_rgba16ui",
             Self::Rgba32ui => "encode_rgba32ui",
+            Self::Rgb10a2ui => "encode_rgb10a2ui",
+            Self::Rgb565uiX2 => "encode_rgb565ui",
+            Self::Rgba4uiX2 => "encode_rgba4ui",
         }
     }
 
@@ -157,6 +192,9 @@ impl StageKind {
             Self::R32ui => "decode_r32ui",
             Self::Rgba16ui => "decode_rgba16ui",
             Self::Rgba32ui => "decode_rgba32ui",
+            Self::Rgb10a2ui => "decode_rgb10a2ui",
+            Self::Rgb565uiX2 => "decode_rgb565ui",
+            Self::Rgba4uiX2 => "decode_rgba4ui",
         }
     }
 
@@ -171,6 +209,10 @@ impl StageKind {
             Self::R32ui => TextureFormat::R32Uint,
             Self::Rgba16ui => TextureFormat::Rgba16Uint,
             Self::Rgba32ui => TextureFormat::Rgba32Uint,
+            // `Rgb10a2Uint` only fits one of the two packings, the shader unpacks the word itself.
+            Self::Rgb10a2ui => TextureFormat::R32Uint,
+            Self::Rgb565uiX2 => TextureFormat::R32Uint,
+            Self::Rgba4uiX2 => TextureFormat::R32Uint,
         }
     }
 
@@ -185,7 +227,7 @@ impl StageKind {
     pub(crate) fn horizontal_subfactor(self) -> u32 {
         match self {
             Self::R8uiX4 => 4,
-            Self::R16uiX2 => 2,
+            Self::R16uiX2 | Self::Rgb565uiX2 | Self::Rgba4uiX2 => 2,
             _ => 1,
         }
     }
@@ -203,7 +245,11 @@ impl Shaders {
             StageKind::R8uiX4 => self.decode_r8ui_x4.clone(),
             StageKind::R16uiX2 => self.decode_r16ui_x2.clone(),
             StageKind::R32ui => self.decode_r32ui.clone(),
-            _ => todo!("{:?}", self),
+            StageKind::Rgba16ui => self.decode_rgba16ui.clone(),
+            StageKind::Rgba32ui => self.decode_rgba32ui.clone(),
+            StageKind::Rgb10a2ui => self.decode_rgb10a2ui.clone(),
+            StageKind::Rgb565uiX2 => self.decode_rgb565ui_x2.clone(),
+            StageKind::Rgba4uiX2 => self.decode_rgba4ui_x2.clone(),
         }
     }
 
@@ -212,7 +258,11 @@ impl Shaders {
             StageKind::R8uiX4 => self.encode_r8ui_x4.clone(),
             StageKind::R16uiX2 => self.encode_r16ui_x2.clone(),
             StageKind::R32ui => self.encode_r32ui.clone(),
-            _ => todo!("{:?}", self),
+            StageKind::Rgba16ui => self.encode_rgba16ui.clone(),
+            StageKind::Rgba32ui => self.encode_rgba32ui.clone(),
+            StageKind::Rgb10a2ui => self.encode_rgb10a2ui.clone(),
+            StageKind::Rgb565uiX2 => self.encode_rgb565ui_x2.clone(),
+            StageKind::Rgba4uiX2 => self.encode_rgba4ui_x2.clone(),
         }
     }
 }
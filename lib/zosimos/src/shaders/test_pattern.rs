@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+use crate::command::TestPattern;
+
+/// A family of synthetic test images.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub(crate) struct ShaderData {
+    pub pattern: TestPattern,
+    /// The size of the image, the patterns scale to it.
+    pub size: (u32, u32),
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::TestPattern)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let (width, height) = self.data.size;
+        let (width, height) = (width as i32, height as i32);
+
+        let (kind, cell, colors, frequency) = match self.data.pattern {
+            TestPattern::Checker { size, colors } => (0, size as i32, colors, 0.0),
+            TestPattern::ColorBars => (1, 1, [[0.0; 4]; 2], 0.0),
+            TestPattern::ZonePlate { scale } => (2, 1, [[0.0; 4]; 2], scale),
+        };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[kind, cell, width, height]);
+        buffer_content.extend_from_pods(&colors);
+        buffer_content.extend_from_pods(&[frequency, 0.0, 0.0, 0.0]);
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
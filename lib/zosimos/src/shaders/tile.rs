@@ -0,0 +1,39 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Repeat an image periodically over a larger area.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The number of repetitions along width and height, need not be whole.
+    pub repeat: [f32; 2],
+    /// Mirror every other repetition.
+    pub mirrored: bool,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Tile)
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let [x, y] = self.repeat;
+        Some(BufferInitContent::new(buffer, &[x, y, 0.0, 0.0]))
+    }
+
+    fn address_mode(&self) -> wgpu::AddressMode {
+        if self.mirrored {
+            wgpu::AddressMode::MirrorRepeat
+        } else {
+            wgpu::AddressMode::Repeat
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// Map linear, high dynamic range radiance into the displayable range.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Shader {
+    /// The radiance mapped to white, used by the Reinhard operator.
+    pub white: f32,
+    pub operator: Operator,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Operator {
+    Reinhard { spirv: Arc<[u8]> },
+    AcesFitted { spirv: Arc<[u8]> },
+}
+
+impl FragmentShaderData for Shader {
+    /// The unique key identifying this shader module.
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::ToneMap(match self.operator {
+            Operator::Reinhard { .. } => ToneMapKind::Reinhard,
+            Operator::AcesFitted { .. } => ToneMapKind::AcesFitted,
+        }))
+    }
+
+    /// The SPIR-V shader source code.
+    fn spirv_source(&self) -> Arc<[u8]> {
+        match &self.operator {
+            Operator::Reinhard { spirv } | Operator::AcesFitted { spirv } => spirv.clone(),
+        }
+    }
+
+    /// Encode the shader's data into the buffer, returning the descriptor to that.
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        Some(BufferInitContent::new(buffer, &[self.white, 0.0, 0.0, 0.0]))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum ToneMapKind {
+    Reinhard,
+    AcesFitted,
+}
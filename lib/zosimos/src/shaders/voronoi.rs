@@ -0,0 +1,85 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The maximum number of seed points of a diagram.
+///
+/// Keep in sync with the definition in `voronoi.frag`.
+pub const MAX_POINTS: usize = 256;
+
+/// The size of the uniform buffer, in bytes.
+pub(crate) const UNIFORM_SIZE: usize = 16 + 2 * 16 * MAX_POINTS;
+
+/// The metric by which the nearest seed point is chosen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Metric {
+    /// The usual distance, resulting in convex cells with straight edges.
+    Euclidean,
+    /// The sum of distances along each axis.
+    Manhattan,
+}
+
+/// A diagram coloring each texel by its nearest seed point.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ShaderData {
+    pub(crate) points: Vec<([f32; 2], [f32; 4])>,
+    pub(crate) metric: Metric,
+    pub(crate) size: (u32, u32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Voronoi)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            points,
+            metric,
+            size: (width, height),
+        } = &self.data;
+
+        let metric: i32 = match metric {
+            Metric::Euclidean => 0,
+            Metric::Manhattan => 1,
+        };
+
+        let mut positions = [[0.0f32; 4]; MAX_POINTS];
+        let mut colors = [[0.0f32; 4]; MAX_POINTS];
+
+        for ((position, color), &([x, y], rgba)) in
+            positions.iter_mut().zip(&mut colors).zip(points)
+        {
+            *position = [x, y, 0.0, 0.0];
+            *color = rgba;
+        }
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&[
+            points.len() as i32,
+            metric,
+            *width as i32,
+            *height as i32,
+        ]);
+        buffer_content.extend_from_pods(&positions[..]);
+        buffer_content.extend_from_pods(&colors[..]);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use super::{BufferInitContent, FragmentShaderData, FragmentShaderKey};
+
+/// The parameters of cellular noise.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub struct ShaderData {
+    /// The number of cells along width and height, each containing one feature point.
+    pub cells: [f32; 2],
+    /// How far the feature points are displaced from the center of their cell, in `0.0..=1.0`.
+    ///
+    /// With no jitter the cells form a regular grid, with full jitter the feature point is
+    /// anywhere within its cell.
+    pub jitter: f32,
+    /// The metric of distances to the feature points.
+    pub distance_fn: Distance,
+    /// Selects one of many uncorrelated placements of feature points.
+    pub seed: u32,
+}
+
+/// A metric of the plane, see [`ShaderData::distance_fn`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+pub enum Distance {
+    /// The usual distance, resulting in round cells.
+    Euclidean,
+    /// The sum of distances along each axis, resulting in diagonal edges.
+    Manhattan,
+    /// The maximum of distances along each axis, resulting in axis aligned edges.
+    Chebyshev,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shader {
+    pub data: ShaderData,
+    pub spirv: Arc<[u8]>,
+}
+
+impl ShaderData {
+    /// Cellular noise with a number of cells along each axis, fully jittered.
+    pub fn with_cells(width: f32, height: f32) -> Self {
+        ShaderData {
+            cells: [width, height],
+            jitter: 1.0,
+            distance_fn: Distance::Euclidean,
+            seed: 0,
+        }
+    }
+}
+
+impl FragmentShaderData for Shader {
+    fn key(&self) -> Option<FragmentShaderKey> {
+        Some(FragmentShaderKey::Worley)
+    }
+
+    fn spirv_source(&self) -> Arc<[u8]> {
+        self.spirv.clone()
+    }
+
+    fn binary_data(&self, buffer: &mut Vec<u8>) -> Option<BufferInitContent> {
+        let ShaderData {
+            cells,
+            jitter,
+            distance_fn,
+            seed,
+        } = self.data;
+
+        let distance_fn: u32 = match distance_fn {
+            Distance::Euclidean => 0,
+            Distance::Manhattan => 1,
+            Distance::Chebyshev => 2,
+        };
+
+        let mut buffer_content = BufferInitContent::builder(buffer);
+        buffer_content.extend_from_pods(&cells);
+        buffer_content.extend_from_pods(&[jitter]);
+        buffer_content.extend_from_pods(&[distance_fn]);
+        buffer_content.extend_from_pods(&[seed, 0, 0, 0]);
+
+        Some(buffer_content.build())
+    }
+
+    fn num_args(&self) -> u32 {
+        0
+    }
+}
@@ -5,7 +5,7 @@ use zosimos::buffer::Descriptor;
 use zosimos::command::{self, CommandBuffer, Linker, Register};
 use zosimos::pool::{Gpu, Pool, PoolKey};
 use zosimos::program::{Capabilities, Program};
-use zosimos::run::{Executable, Retire};
+use zosimos::run::{Executable, Retire, StepLimits, StepOutcome};
 
 const BACKGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/background.png");
 const FOREGROUND: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/input/foreground.png");
@@ -80,10 +80,13 @@ async fn run_affine(
 
     let (output_affine, _outformat) = commands.output(result_affine).expect("Valid for output");
 
-    let result = run_once_with_output(
-        commands,
+    let executable = compile(&commands, pool);
+    let binds = [(background, bg_key), (foreground, fg_key)];
+
+    let result = run_executable_with_output(
+        &executable,
         pool,
-        vec![(background, bg_key), (foreground, fg_key)],
+        binds,
         util::retire_with_one_image(output_affine),
     )
     .await;
@@ -91,6 +94,17 @@ async fn run_affine(
     let image_affine = pool.entry(result).unwrap();
     // Using the same as synchronous code in blend on purpose. This is a form of consistency check.
     util::assert_reference(image_affine.into(), "affine.crc.png");
+
+    let result = run_executable_stepped(
+        &executable,
+        pool,
+        binds,
+        util::retire_with_one_image(output_affine),
+    )
+    .await;
+
+    let image_affine = pool.entry(result).unwrap();
+    util::assert_reference(image_affine.into(), "affine.crc.png");
 }
 
 pub async fn run_once_with_output<T>(
@@ -99,8 +113,13 @@ pub async fn run_once_with_output<T>(
     binds: impl IntoIterator<Item = (Register, PoolKey)>,
     output: impl FnOnce(&mut Retire) -> T,
 ) -> T {
+    let executable = compile(&commands, pool);
+    run_executable_with_output(&executable, pool, binds, output).await
+}
+
+fn compile(commands: &CommandBuffer, pool: &Pool) -> Executable {
     let plan = Linker::from_included()
-        .compile(&commands)
+        .compile(commands)
         .expect("Could build command buffer");
 
     let capabilities = Capabilities::from({
@@ -108,11 +127,8 @@ pub async fn run_once_with_output<T>(
         devices.next().expect("the pool to contain a device")
     });
 
-    let executable = plan
-        .lower_to(capabilities)
-        .expect("No extras beyond device required");
-
-    run_executable_with_output(&executable, pool, binds, output).await
+    plan.lower_to(capabilities)
+        .expect("No extras beyond device required")
 }
 
 pub async fn run_executable_with_output<T>(
@@ -166,3 +182,36 @@ pub async fn run_executable_with_output<T>(
     retire.finish();
     result
 }
+
+/// Run an executable with as few steps as possible, the device is polled by the execution.
+pub async fn run_executable_stepped<T>(
+    executable: &Executable,
+    pool: &mut Pool,
+    binds: impl IntoIterator<Item = (Register, PoolKey)>,
+    output: impl FnOnce(&mut Retire) -> T,
+) -> T {
+    let mut environment = executable.from_pool(pool).expect("no device found in pool");
+
+    for (target, key) in binds {
+        environment.bind(target, key).unwrap();
+    }
+
+    let _ = environment.recover_buffers();
+    let mut execution = executable.launch(environment).expect("Launching failed");
+    pool.clear_cache();
+
+    loop {
+        let limits = StepLimits::new().with_steps(usize::MAX);
+        match execution.step_async(limits).await {
+            Ok(StepOutcome::Running) => {}
+            Ok(StepOutcome::Finished) => break,
+            Err(err) => panic!("Stepping failed: {err:?}"),
+        }
+    }
+
+    let mut retire = execution.retire_gracefully(pool);
+    let result = output(&mut retire);
+    let _ = retire.retire_buffers();
+    retire.finish();
+    result
+}
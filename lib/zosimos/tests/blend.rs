@@ -10,9 +10,9 @@
 mod util;
 
 use zosimos::buffer::{self, Descriptor, Whitepoint};
-use zosimos::command::{self, CommandBuffer, Rectangle};
-use zosimos::pool::{Pool, PoolKey};
-use zosimos::program::Program;
+use zosimos::command::{self, CommandBuffer, Linker, Rectangle};
+use zosimos::pool::{Pool, PoolImage, PoolKey};
+use zosimos::program::{Capabilities, Program};
 
 use self::util::{retire_with_one_image, run_once_with_output};
 
@@ -46,7 +46,11 @@ fn integration() {
         (entry.key(), entry.descriptor())
     };
 
-    pool.request_device(&adapter, Program::minimal_device_descriptor())
+    let mut descriptor = Program::minimal_device_descriptor();
+    // Summed-area tables accumulate in single precision, see `run_integral_image`.
+    descriptor.required_features |= adapter.features() & wgpu::Features::FLOAT32_FILTERABLE;
+
+    pool.request_device(&adapter, descriptor)
         .expect("to get a device");
 
     run_blending(&mut pool, pool_foreground.clone(), pool_background.clone());
@@ -65,19 +69,97 @@ fn integration() {
 
     run_fractal_noise(&mut pool);
 
+    run_fractal_noise_seed(&mut pool);
+
     run_transmute(&mut pool, pool_background.clone());
 
     run_palette(&mut pool, pool_background.clone());
 
+    run_palette_address(&mut pool);
+
+    run_palette_interpolation(&mut pool);
+
     run_swap(&mut pool, pool_background.clone());
 
+    run_alpha_plane(&mut pool, pool_foreground.clone(), &foreground);
+
+    run_inject_at(&mut pool);
+
     run_oklab(&mut pool);
 
+    run_oklch(&mut pool);
+
     run_srlab2(&mut pool);
 
+    run_cielab(&mut pool, pool_background.clone(), &background);
+
+    run_cielab_lch(&mut pool);
+
+    run_conversion_pairs(&mut pool, pool_background.clone(), &background);
+
+    run_fused_conversion(&mut pool, pool_background.clone());
+
+    run_cmyk(&mut pool, pool_background.clone(), &background);
+
+    run_cvd(&mut pool);
+
+    run_hdr_transfer(&mut pool);
+
+    run_linear_output(&mut pool);
+
+    run_padded_input(&mut pool);
+
+    run_in_place(&mut pool);
+
+    run_dither(&mut pool);
+
     run_derivative(&mut pool, pool_background.clone());
 
+    run_resize(&mut pool, pool_background.clone(), &background);
+
+    run_tile(&mut pool, pool_background.clone(), &background);
+
     run_solid(&mut pool);
+
+    run_solid_texels(&mut pool);
+
+    run_lens_undistort(&mut pool);
+
+    run_denoise_nlm(&mut pool);
+
+    run_shadow_radius(&mut pool);
+
+    run_cancel(&mut pool);
+
+    run_shared_pipelines(&mut pool);
+
+    run_unstaged_f16(&mut pool);
+
+    run_integral_image(&mut pool);
+
+    run_ab_rotate(&mut pool);
+
+    run_ssim(&mut pool);
+
+    run_tonemap(&mut pool);
+
+    run_luminance(&mut pool);
+
+    run_white_balance(&mut pool);
+
+    run_color_ramp(&mut pool);
+
+    run_spline_ramp(&mut pool);
+
+    run_exposure_fusion(&mut pool);
+
+    run_histogram(&mut pool);
+
+    run_clahe(&mut pool);
+
+    run_reduce(&mut pool);
+
+    run_resize_overshoot(&mut pool);
 }
 
 fn run_blending(
@@ -316,8 +398,14 @@ fn run_fractal_noise(pool: &mut Pool) {
     let descriptor = Descriptor::with_srgb_image(&layout);
 
     let mut commands = CommandBuffer::default();
+    // The golden image is for the fixed, default seed.
+    let noise = command::FractalNoise {
+        seed: 0,
+        ..command::FractalNoise::with_octaves(4)
+    };
+
     let generated = commands
-        .distribution_fractal_noise(descriptor, command::FractalNoise::with_octaves(4))
+        .distribution_fractal_noise(descriptor, noise)
         .unwrap();
 
     let (output, _outformat) = commands.output(generated).expect("Valid for output");
@@ -337,6 +425,46 @@ fn run_fractal_noise(pool: &mut Pool) {
     util::assert_reference_image(layout, "distribution_fractal2d.crc.png");
 }
 
+fn run_fractal_noise_seed(pool: &mut Pool) {
+    let layout = image::DynamicImage::new_rgba8(128, 128);
+    let descriptor = Descriptor::with_srgb_image(&layout);
+
+    let mut generate = |seed: u32| -> Vec<u8> {
+        let noise = command::FractalNoise {
+            seed,
+            ..command::FractalNoise::with_octaves(4)
+        };
+
+        let mut commands = CommandBuffer::default();
+        let generated = commands
+            .distribution_fractal_noise(descriptor.clone(), noise)
+            .unwrap();
+
+        let (output, _outformat) = commands.output(generated).expect("Valid for output");
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Not a byte image").to_vec()
+    };
+
+    let first = generate(1);
+    let again = generate(1);
+    let other = generate(2);
+
+    assert!(first == again, "Same seed must result in the same image");
+
+    // Each channel is decorrelated on its own.
+    for channel in 0..4 {
+        let differs = first
+            .iter()
+            .zip(&other)
+            .skip(channel)
+            .step_by(4)
+            .any(|(a, b)| a != b);
+        assert!(differs, "Channel {channel} did not change with the seed");
+    }
+}
+
 fn run_transmute(pool: &mut Pool, (orig_key, orig_descriptor): (PoolKey, Descriptor)) {
     let mut commands = CommandBuffer::default();
     let (width, height) = orig_descriptor.size();
@@ -403,6 +531,8 @@ fn run_palette(pool: &mut Pool, (orig_key, orig_descriptor): (PoolKey, Descripto
         height: Some(buffer::ColorChannel::G),
         width_base: 0,
         height_base: 0,
+        address: command::PaletteAddress::Clamp,
+        interpolate: false,
     };
 
     let sampled = commands.palette(input, palette, ramp).unwrap();
@@ -420,6 +550,156 @@ fn run_palette(pool: &mut Pool, (orig_key, orig_descriptor): (PoolKey, Descripto
     util::assert_reference(image_sampled.into(), "palette.crc.png");
 }
 
+fn run_palette_address(pool: &mut Pool) {
+    use command::PaletteAddress;
+    const BORDER: [u8; 4] = [255, 0, 255, 255];
+
+    let entries = [
+        [255, 0, 0, 255],
+        [0, 255, 0, 255],
+        [0, 0, 255, 255],
+        [255; 4],
+    ];
+    let palette = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(4, 1, |x, _| {
+        image::Rgba(entries[x as usize])
+    }));
+
+    let pool_palette = {
+        let entry = pool.insert_srgb(&palette);
+        (entry.key(), entry.descriptor())
+    };
+
+    // Linear indices, the last two point beyond the palette. With the base of two entries and
+    // the bias of half an index texel they address the coordinates 0.625 through 1.375.
+    let index_desc =
+        Descriptor::with_texel(buffer::Texel::new_u8(buffer::SampleParts::Luma), 4, 1).unwrap();
+    let mut indices = buffer::ImageBuffer::with_descriptor(&index_desc);
+    indices
+        .as_bytes_mut()
+        .copy_from_slice(&[0x00, 0x40, 0x80, 0xbf]);
+    let pool_indices = (pool.insert(indices, index_desc.clone()).key(), index_desc);
+
+    let mut lookup = |address| {
+        let mut commands = CommandBuffer::default();
+        let palette = commands.input(pool_palette.1.clone()).unwrap();
+        let indices = commands.input(pool_indices.1.clone()).unwrap();
+
+        let config = command::Palette {
+            width: Some(buffer::ColorChannel::Luma),
+            height: None,
+            width_base: 2,
+            height_base: 0,
+            address,
+            interpolate: false,
+        };
+
+        let sampled = commands.palette(palette, config, indices).unwrap();
+        let (output, _outformat) = commands.output(sampled).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(palette, pool_palette.0), (indices, pool_indices.0)],
+            retire_with_one_image(output),
+        );
+
+        let image_sampled = pool.entry(result).unwrap();
+        let bytes = image_sampled.as_bytes().expect("Not a byte image");
+        bytes
+            .chunks_exact(4)
+            .map(|texel| texel.to_vec())
+            .collect::<Vec<_>>()
+    };
+
+    let expected = |texels: [Option<usize>; 4]| {
+        texels.map(|idx| idx.map_or(BORDER, |idx| entries[idx]).to_vec())
+    };
+
+    let clamp = lookup(PaletteAddress::Clamp);
+    assert_eq!(clamp, expected([Some(2), Some(3), Some(3), Some(3)]));
+
+    let repeat = lookup(PaletteAddress::Repeat);
+    assert_eq!(repeat, expected([Some(2), Some(3), Some(0), Some(1)]));
+
+    let mirror = lookup(PaletteAddress::MirrorRepeat);
+    assert_eq!(mirror, expected([Some(2), Some(3), Some(3), Some(2)]));
+
+    let border = lookup(PaletteAddress::Border([1.0, 0.0, 1.0, 1.0]));
+    assert_eq!(border, expected([Some(2), Some(3), None, None]));
+}
+
+fn run_palette_interpolation(pool: &mut Pool) {
+    let palette = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(2, 1, |x, _| {
+        image::Rgba([255 * x as u8, 255 * x as u8, 255 * x as u8, 255])
+    }));
+
+    let pool_palette = {
+        let entry = pool.insert_srgb(&palette);
+        (entry.key(), entry.descriptor())
+    };
+
+    // A ramp across the palette, the index texel bias of 1/32 is less than its steps.
+    let index_desc =
+        Descriptor::with_texel(buffer::Texel::new_u8(buffer::SampleParts::Luma), 16, 1).unwrap();
+    let mut indices = buffer::ImageBuffer::with_descriptor(&index_desc);
+    for (idx, value) in indices.as_bytes_mut().iter_mut().enumerate() {
+        *value = 16 * idx as u8;
+    }
+    let pool_indices = (pool.insert(indices, index_desc.clone()).key(), index_desc);
+
+    let mut lookup = |interpolate| {
+        let mut commands = CommandBuffer::default();
+        let palette = commands.input(pool_palette.1.clone()).unwrap();
+        let indices = commands.input(pool_indices.1.clone()).unwrap();
+
+        let config = command::Palette {
+            width: Some(buffer::ColorChannel::Luma),
+            height: None,
+            width_base: 0,
+            height_base: 0,
+            address: command::PaletteAddress::Clamp,
+            interpolate,
+        };
+
+        let sampled = commands.palette(palette, config, indices).unwrap();
+        let (output, _outformat) = commands.output(sampled).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(palette, pool_palette.0), (indices, pool_indices.0)],
+            retire_with_one_image(output),
+        );
+
+        let image_sampled = pool.entry(result).unwrap();
+        let bytes = image_sampled.as_bytes().expect("Not a byte image");
+        bytes
+            .chunks_exact(4)
+            .map(|texel| texel[0])
+            .collect::<Vec<_>>()
+    };
+
+    let distinct = |values: &[u8]| {
+        assert!(
+            values.windows(2).all(|pair| pair[0] <= pair[1]),
+            "{values:?}"
+        );
+        let mut values = values.to_vec();
+        values.dedup();
+        values.len()
+    };
+
+    // The nearest entry is a hard step from black to white.
+    let stepped = lookup(false);
+    assert_eq!(distinct(&stepped), 2, "{stepped:?}");
+    assert_eq!((stepped[0], stepped[15]), (0, 255));
+
+    // Interpolated, the ramp between the two entry centers is smooth.
+    let smooth = lookup(true);
+    assert!(distinct(&smooth) > 8, "{smooth:?}");
+    assert_eq!((smooth[0], smooth[15]), (0, 255));
+}
+
 fn run_swap(pool: &mut Pool, (orig_key, orig_descriptor): (PoolKey, Descriptor)) {
     use buffer::ColorChannel;
     let mut commands = CommandBuffer::default();
@@ -446,6 +726,89 @@ fn run_swap(pool: &mut Pool, (orig_key, orig_descriptor): (PoolKey, Descriptor))
     util::assert_reference(image_swapped.into(), "swapped.crc.png");
 }
 
+fn run_alpha_plane(
+    pool: &mut Pool,
+    (fg_key, foreground): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    use buffer::ColorChannel;
+    let mut commands = CommandBuffer::default();
+
+    let input = commands.input(foreground.clone()).unwrap();
+    let alpha = commands.extract(input, ColorChannel::Alpha).unwrap();
+
+    // Paint the alpha plane of the foreground into a solid color, and back into itself.
+    let solid = commands
+        .solid_rgba(foreground, [1.0, 0.0, 1.0, 1.0])
+        .unwrap();
+    let masked = commands.inject(solid, ColorChannel::Alpha, alpha).unwrap();
+    let restored = commands.inject(input, ColorChannel::Alpha, alpha).unwrap();
+
+    let (masked, _outformat) = commands.output(masked).expect("Valid for output");
+    let (restored, _outformat) = commands.output(restored).expect("Valid for output");
+
+    let result = run_once_with_output(commands, pool, vec![(input, fg_key)], |retire| {
+        let masked = retire.output(masked).expect("Valid for output").key();
+        let restored = retire.output(restored).expect("Valid for output").key();
+        (masked, restored)
+    });
+
+    let reference = reference.to_rgba8();
+    let (masked, restored) = result;
+
+    let image_masked = pool.entry(masked).unwrap();
+    let bytes = image_masked.as_bytes().expect("Not a byte image");
+    for (texel, original) in bytes.chunks_exact(4).zip(reference.pixels()) {
+        assert_eq!(texel, [255, 0, 255, original[3]]);
+    }
+
+    let image_restored = pool.entry(restored).unwrap();
+    let bytes = image_restored.as_bytes().expect("Not a byte image");
+    assert_eq!(bytes, &reference.as_raw()[..]);
+}
+
+fn run_inject_at(pool: &mut Pool) {
+    use buffer::ColorChannel;
+    let mut commands = CommandBuffer::default();
+
+    let large = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(16, 16));
+    let small = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(4, 4));
+
+    let below = commands.solid_rgba(large, [1.0, 0.0, 1.0, 1.0]).unwrap();
+    let matte = commands.solid_rgba(small, [0.0, 0.0, 0.0, 0.0]).unwrap();
+    let matte = commands.extract(matte, ColorChannel::Alpha).unwrap();
+
+    let placement = Rectangle {
+        x: 4,
+        y: 2,
+        max_x: 12,
+        max_y: 10,
+    };
+
+    let masked = commands
+        .inject_at(
+            below,
+            ColorChannel::Alpha,
+            matte,
+            placement,
+            command::AffineSample::Nearest,
+        )
+        .unwrap();
+
+    let (output, _outformat) = commands.output(masked).expect("Valid for output");
+    let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+    let image_masked = pool.entry(result).unwrap();
+    let bytes = image_masked.as_bytes().expect("Not a byte image");
+
+    for (idx, texel) in bytes.chunks_exact(4).enumerate() {
+        let (x, y) = ((idx % 16) as u32, (idx / 16) as u32);
+        let inside = (4..12).contains(&x) && (2..10).contains(&y);
+        let alpha = if inside { 0 } else { 255 };
+        assert_eq!(texel, [255, 0, 255, alpha], "at {x}, {y}");
+    }
+}
+
 fn run_oklab(pool: &mut Pool) {
     let mut commands = CommandBuffer::default();
 
@@ -507,6 +870,43 @@ fn run_oklab(pool: &mut Pool) {
     util::assert_reference(image_show.into(), "oklab.crc.png");
 }
 
+fn run_oklch(pool: &mut Pool) {
+    let layout = image::DynamicImage::new_rgba8(4, 4);
+    let descriptor = Descriptor::with_srgb_image(&layout);
+
+    let oklch_texel = buffer::Texel {
+        parts: buffer::SampleParts::LchA,
+        ..descriptor.texel.clone()
+    };
+
+    let mut check_hue = |rgba: [f32; 4], expected: [f32; 3]| {
+        let mut commands = CommandBuffer::default();
+        let generated = commands.solid_rgba(descriptor.clone(), rgba).unwrap();
+        let lch = commands
+            .color_convert(generated, buffer::Color::Oklab, oklch_texel.clone())
+            .expect("Valid for conversion");
+
+        let (output, _) = commands.output(lch).expect("Valid for output");
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+        let image = pool.entry(result).unwrap();
+        let bytes = image.as_bytes().expect("Not a byte image");
+
+        for (channel, (&byte, expected)) in bytes.iter().zip(expected).enumerate() {
+            let expected = (expected * 255.0).round();
+            assert!(
+                (f32::from(byte) - expected).abs() <= 1.0,
+                "Channel {channel} of Oklch {rgba:?} is {byte}, expected {expected}"
+            );
+        }
+    };
+
+    // The hue of sRGB red is 29.23°, stored in turns after the positive `a` axis at 0.5.
+    check_hue([1.0, 0.0, 0.0, 1.0], [0.628, 0.258, 29.23 / 360.0 + 0.5]);
+    // Blue is at 264.05°, which wraps around past a full turn.
+    check_hue([0.0, 0.0, 1.0, 1.0], [0.452, 0.313, 264.05 / 360.0 - 0.5]);
+}
+
 fn run_srlab2(pool: &mut Pool) {
     let mut commands = CommandBuffer::default();
 
@@ -574,71 +974,2107 @@ fn run_srlab2(pool: &mut Pool) {
     util::assert_reference(image_show.into(), "srlab2.crc.png");
 }
 
-fn run_derivative(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
-    const METHODS: &[command::DerivativeMethod] = &[
-        command::DerivativeMethod::Scharr3,
-        command::DerivativeMethod::Scharr3To4Bit,
-        command::DerivativeMethod::Scharr3To8Bit,
-        command::DerivativeMethod::Prewitt,
-        command::DerivativeMethod::Sobel,
-    ];
+fn run_cielab(
+    pool: &mut Pool,
+    (bg_key, background): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    let mut commands = CommandBuffer::default();
 
-    for method in METHODS {
-        let mut commands = CommandBuffer::default();
+    let lab_texel = buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits: buffer::SampleBits::Float32x4,
+        parts: buffer::SampleParts::LabA,
+    };
 
-        // Describe the pipeline:
-        // 0: in (background)
-        // 1: derivative(0, derive)
-        // 2: out(2)
-        let background = commands.input(background.clone()).unwrap();
+    // Relative to D50, the sRGB colors are adapted on the way there and back.
+    let input = commands.input(background.clone()).unwrap();
+    let converted = commands
+        .color_convert_cielab(input, Whitepoint::D50, lab_texel)
+        .expect("Valid for conversion");
+    let roundtrip = commands
+        .color_convert_from_cielab(
+            converted,
+            Whitepoint::D50,
+            background.color.clone(),
+            background.texel.clone(),
+        )
+        .expect("Valid for conversion");
 
-        let derived = commands
-            .derivative(
-                background,
-                command::Derivative {
-                    method: method.clone(),
-                    direction: command::Direction::Width,
-                },
-            )
-            .unwrap();
+    let (output, _) = commands.output(roundtrip).expect("Valid for output");
 
-        let (output_derived, _outformat) = commands.output(derived).expect("Valid for output");
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, bg_key)],
+        retire_with_one_image(output),
+    );
 
-        let result = run_once_with_output(
-            commands,
-            pool,
-            vec![(background, bg_key)],
-            retire_with_one_image(output_derived),
-        );
+    let image = PoolImage::from(pool.entry(result).unwrap());
+    let image = image.to_image().expect("Not a byte image").to_rgba8();
 
-        let image_derived = pool.entry(result).unwrap();
-        let reference = format!("derived_{:?}.crc.png", method);
-        util::assert_reference(image_derived.into(), &reference);
-    }
+    let reference = reference.to_rgba8();
+    let worst = image
+        .as_raw()
+        .iter()
+        .zip(reference.as_raw())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max();
+
+    assert!(
+        worst <= Some(1),
+        "Round trip through CIELAB differs by {worst:?}"
+    );
 }
 
-fn run_solid(pool: &mut Pool) {
-    let mut layout = image::DynamicImage::new_rgba8(400, 400);
+fn run_cielab_lch(pool: &mut Pool) {
+    let layout = image::DynamicImage::new_rgba8(4, 4);
     let descriptor = Descriptor::with_srgb_image(&layout);
 
+    let lch_texel = buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits: buffer::SampleBits::Float32x4,
+        parts: buffer::SampleParts::LchA,
+    };
+
     let mut commands = CommandBuffer::default();
-    let generated = commands
-        .solid_rgba(descriptor, [0.5, 0.5, 1.0, 1.0])
+    let red = commands
+        .solid_rgba(descriptor, [1.0, 0.0, 0.0, 1.0])
         .unwrap();
+    let lch = commands
+        .color_convert_cielab(red, Whitepoint::D50, lch_texel)
+        .expect("Valid for conversion");
 
-    let (output, _outformat) = commands.output(generated).expect("Valid for output");
-
+    let (output, _) = commands.output(lch).expect("Valid for output");
     let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
 
-    let image_generated = pool.entry(result).unwrap();
+    let image = pool.entry(result).unwrap();
+    let texels: &[[f32; 4]] = bytemuck::cast_slice(image.as_bytes().expect("Not a byte image"));
+
+    // CSS Color 4 gives sRGB red as `lch(54.29% 106.84 40.85)`, relative to D50. The hue is stored
+    // half a turn off, with positive `a` at 0.5 instead of 0°.
+    let expected = [0.5429, 1.0684, 40.85 / 360.0 + 0.5];
+
+    for texel in texels {
+        for (channel, (value, expected)) in texel.iter().zip(expected).enumerate() {
+            assert!(
+                (value - expected).abs() < 2e-3,
+                "Channel {channel} of LCh red is {value}, expected {expected}"
+            );
+        }
+    }
+}
 
-    match layout {
-        image::DynamicImage::ImageRgba8(ref mut buffer) => {
-            let bytes = image_generated.as_bytes().expect("Not a byte image");
-            bytemuck::cast_slice_mut(&mut *buffer).copy_from_slice(bytes);
+fn run_conversion_pairs(
+    pool: &mut Pool,
+    (bg_key, background): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    // CIELAB, as `None`, has no color and is converted with its own commands relative to D50.
+    let colors = [
+        Some(background.color.clone()),
+        Some(buffer::Color::Oklab),
+        Some(buffer::Color::SrLab2 {
+            whitepoint: Whitepoint::D65,
+        }),
+        None,
+    ];
+
+    let float = |parts| buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits: buffer::SampleBits::Float32x4,
+        parts,
+    };
+
+    let parts_of = |color: &Option<buffer::Color>| match color {
+        Some(buffer::Color::Rgb { .. }) => buffer::SampleParts::RgbA,
+        _ => buffer::SampleParts::LabA,
+    };
+
+    let convert = |commands: &mut CommandBuffer,
+                   src,
+                   source: &Option<buffer::Color>,
+                   target: &Option<buffer::Color>| {
+        let texel = float(parts_of(target));
+        match (source, target) {
+            (None, None) => Ok(src),
+            (None, Some(color)) => {
+                commands.color_convert_from_cielab(src, Whitepoint::D50, color.clone(), texel)
+            }
+            (Some(_), None) => commands.color_convert_cielab(src, Whitepoint::D50, texel),
+            (Some(_), Some(color)) => commands.color_convert(src, color.clone(), texel),
+        }
+    };
+
+    let reference = reference.to_rgba8();
+
+    // Each pair, including the non-RGB ones, round trips from and back to the sRGB image.
+    for source in &colors {
+        for target in &colors {
+            let mut commands = CommandBuffer::default();
+            let input = commands.input(background.clone()).unwrap();
+            let first =
+                convert(&mut commands, input, &colors[0], source).expect("Valid for conversion");
+            let second =
+                convert(&mut commands, first, source, target).expect("Valid for conversion");
+            let roundtrip = match target {
+                None => commands.color_convert_from_cielab(
+                    second,
+                    Whitepoint::D50,
+                    background.color.clone(),
+                    background.texel.clone(),
+                ),
+                Some(_) => commands.color_convert(
+                    second,
+                    background.color.clone(),
+                    background.texel.clone(),
+                ),
+            }
+            .expect("Valid for conversion");
+
+            let (output, _) = commands.output(roundtrip).expect("Valid for output");
+            let result = run_once_with_output(
+                commands,
+                pool,
+                vec![(input, bg_key)],
+                retire_with_one_image(output),
+            );
+
+            let image = PoolImage::from(pool.entry(result).unwrap());
+            let image = image.to_image().expect("Not a byte image").to_rgba8();
+
+            let worst = image
+                .as_raw()
+                .iter()
+                .zip(reference.as_raw())
+                .map(|(a, b)| a.abs_diff(*b))
+                .max();
+
+            assert!(
+                worst <= Some(1),
+                "Round trip through {source:?} and {target:?} differs by {worst:?}"
+            );
         }
-        _ => unreachable!(),
     }
+}
 
-    util::assert_reference_image(layout, "solid.crc.png");
+fn run_fused_conversion(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
+    let region = Rectangle::with_width_height(64, 64);
+    let mut wide = background.color.clone();
+    if let buffer::Color::Rgb { primary, .. } = &mut wide {
+        *primary = buffer::Primaries::Bt2020;
+    }
+
+    // Converting and then cropping fuses into one draw, cropping first does not.
+    let mut run = |convert_first: bool| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(background.clone()).unwrap();
+
+        let result = if convert_first {
+            let converted = commands
+                .color_convert(input, wide.clone(), background.texel.clone())
+                .expect("Valid for conversion");
+            commands.crop(converted, region).expect("Valid to crop")
+        } else {
+            let cropped = commands.crop(input, region).expect("Valid to crop");
+            commands
+                .color_convert(cropped, wide.clone(), background.texel.clone())
+                .expect("Valid for conversion")
+        };
+
+        let (output, _) = commands.output(result).expect("Valid for output");
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, bg_key)],
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Not a byte image").to_vec()
+    };
+
+    let fused = run(true);
+    let separate = run(false);
+    assert!(fused == separate, "Fused conversion differs");
+}
+
+fn run_cmyk(
+    pool: &mut Pool,
+    (bg_key, background): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    let mut commands = CommandBuffer::default();
+
+    let input = commands.input(background.clone()).unwrap();
+    let inks = commands
+        .cmyk_encode(input, command::BlackGeneration::Percentage(50.0))
+        .expect("Valid for separation");
+    let roundtrip = commands
+        .cmyk_decode(inks, background.color.clone())
+        .expect("Valid for decoding");
+
+    let (output, _) = commands.output(roundtrip).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, bg_key)],
+        retire_with_one_image(output),
+    );
+
+    let image = PoolImage::from(pool.entry(result).unwrap());
+    let image = image.to_image().expect("Not a byte image").to_rgb8();
+
+    // The inks are quantized to 8 bits, the naive model is otherwise exact.
+    let reference = reference.to_rgb8();
+    let worst = image
+        .as_raw()
+        .iter()
+        .zip(reference.as_raw())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max();
+
+    assert!(
+        worst <= Some(2),
+        "Round trip through CMYK differs by {worst:?}"
+    );
+}
+
+/// Simulates dichromacy on a known color and compares against the matrices of Machado et al.
+fn run_cvd(pool: &mut Pool) {
+    const COLOR: [f32; 4] = [0.2, 0.5, 0.8, 1.0];
+
+    #[rustfmt::skip]
+    let references = [
+        (command::CvdKind::Protanomaly, [
+            [0.152286, 1.052583, -0.204868],
+            [0.114503, 0.786281, 0.099216],
+            [-0.003882, -0.048116, 1.051998],
+        ]),
+        (command::CvdKind::Tritanomaly, [
+            [1.255528, -0.076749, -0.178779],
+            [-0.078411, 0.930809, 0.147602],
+            [0.004733, 0.691367, 0.303900],
+        ]),
+    ];
+
+    let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, 4, 4);
+    let key = insert_float_texels(pool, &descriptor, &[COLOR; 16]);
+
+    for (kind, matrix) in references {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+        let simulated = commands
+            .simulate_cvd(
+                input,
+                command::Cvd {
+                    kind,
+                    severity: 1.0,
+                },
+            )
+            .expect("Valid to simulate");
+        let (output, _) = commands.output(simulated).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        let expected = matrix.map(|row| row.iter().zip(COLOR).map(|(m, c)| m * c).sum::<f32>());
+        for texel in float_texels(pool, result) {
+            assert!(
+                texel[..3]
+                    .iter()
+                    .zip(expected)
+                    .all(|(c, e)| (c - e).abs() < 1e-3),
+                "{kind:?} simulates {texel:?}, expected {expected:?}"
+            );
+        }
+    }
+}
+
+fn run_hdr_transfer(pool: &mut Pool) {
+    let layout = image::DynamicImage::new_rgba8(4, 4);
+    let srgb = Descriptor::with_srgb_image(&layout);
+
+    let with_transfer = |transfer: buffer::Transfer| {
+        let mut color = buffer::Color::SRGB;
+        if let buffer::Color::Rgb { transfer: t, .. } = &mut color {
+            *t = transfer;
+        }
+        color
+    };
+
+    let linear = Descriptor {
+        color: with_transfer(buffer::Transfer::Linear),
+        ..srgb.clone()
+    };
+
+    let mut check_white = |transfer, white_nits: f32, expected: f32| {
+        let mut commands = CommandBuffer::default();
+        let white = commands.solid_rgba(linear.clone(), [1.0; 4]).unwrap();
+        let encoded = commands
+            .color_convert_with(
+                white,
+                with_transfer(transfer),
+                srgb.texel.clone(),
+                command::ColorConvertOptions {
+                    luminance: command::HdrLuminance { white_nits },
+                    ..Default::default()
+                },
+            )
+            .expect("Valid for conversion");
+
+        let (output, _) = commands.output(encoded).expect("Valid for output");
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+        let image = pool.entry(result).unwrap();
+        let bytes = image.as_bytes().expect("Not a byte image");
+        let expected = expected * 255.0;
+
+        for &byte in &bytes[..3] {
+            assert!(
+                (f32::from(byte) - expected).abs() <= 1.0,
+                "White of {white_nits} cd/m² in {transfer:?} is {byte}, expected {expected}"
+            );
+        }
+    };
+
+    // The published code values of the PQ curve, as fractions of the full range.
+    check_white(buffer::Transfer::Smpte2084, 100.0, 0.5081);
+    check_white(buffer::Transfer::Smpte2084, 203.0, 0.5807);
+    check_white(buffer::Transfer::Smpte2084, 1000.0, 0.7518);
+    check_white(buffer::Transfer::Smpte2084, 10000.0, 1.0);
+    // Reference white of HLG is at 75% of the signal, regardless of absolute luminance.
+    check_white(buffer::Transfer::Bt2100Hlg, 203.0, 0.75);
+}
+
+fn run_linear_output(pool: &mut Pool) {
+    let gray = image::RgbaImage::from_pixel(4, 4, image::Rgba([128, 128, 128, 255]));
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(gray));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let texel = buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits: buffer::SampleBits::Float32x4,
+        parts: buffer::SampleParts::RgbA,
+    };
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let linear = commands
+        .color_convert_linear(input, buffer::Primaries::Bt2020, Whitepoint::D65, texel)
+        .expect("Valid for conversion");
+
+    let (output, _) = commands.output(linear).expect("Valid for output");
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let image = pool.entry(result).unwrap();
+    let bytes = image.as_bytes().expect("Not a byte image");
+    let samples: Vec<f32> = bytes
+        .chunks_exact(4)
+        .map(|sample| f32::from_ne_bytes(sample.try_into().unwrap()))
+        .collect();
+
+    // The sRGB code 128 is 0.2159 in linear light, a neutral gray in any primaries.
+    for &sample in &samples[..3] {
+        assert!(
+            (sample - 0.2159).abs() < 1e-3,
+            "Linear gray is {sample}, not re-encoded"
+        );
+    }
+
+    assert!((samples[3] - 1.0).abs() < 1e-3);
+}
+
+/// Upload a frame whose rows are padded, as handed out by capture APIs.
+fn run_padded_input(pool: &mut Pool) {
+    const WIDTH: u32 = 4;
+    const HEIGHT: u32 = 3;
+    const ROW_STRIDE: u64 = 4 * WIDTH as u64 + 12;
+
+    let texels: Vec<[u8; 4]> = (0..WIDTH * HEIGHT)
+        .map(|idx| [idx as u8 * 16, 0x80, 0xff - idx as u8, 0xff])
+        .collect();
+
+    let mut frame = vec![0xaa; (ROW_STRIDE * u64::from(HEIGHT)) as usize];
+    for (row, texels) in texels.chunks_exact(WIDTH as usize).enumerate() {
+        let line = &mut frame[row * ROW_STRIDE as usize..][..4 * WIDTH as usize];
+        line.copy_from_slice(texels.as_flattened());
+    }
+
+    let blank = image::DynamicImage::new_rgba8(WIDTH, HEIGHT);
+    let mut entry = pool.insert_srgb(&blank);
+    let mut layout = entry.descriptor().layout;
+
+    layout.row_stride = 4 * u64::from(WIDTH) - 1;
+    assert!(entry.set_bytes_with_stride(&frame, layout).is_err());
+    layout.row_stride = u64::MAX / 2;
+    assert!(entry.set_bytes_with_stride(&frame, layout).is_err());
+
+    layout.row_stride = ROW_STRIDE;
+    entry
+        .set_bytes_with_stride(&frame, layout)
+        .expect("Valid padded layout");
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let (output, _) = commands.output(input).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let image = pool.entry(result).unwrap();
+    let pitch = image.layout().as_row_layout().row_stride as usize;
+    let bytes = image.as_bytes().expect("Not a byte image");
+
+    for (row, texels) in texels.chunks_exact(WIDTH as usize).enumerate() {
+        let line = &bytes[row * pitch..][..4 * WIDTH as usize];
+        assert_eq!(line, texels.as_flattened(), "Row {row} differs");
+    }
+}
+
+/// Paint the result of a chain into the texture of its input.
+fn run_in_place(pool: &mut Pool) {
+    const CHAIN: usize = 4;
+
+    let gray = image::RgbaImage::from_pixel(256, 256, image::Rgba([128, 64, 32, 255]));
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(gray.clone()));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+    let region = Rectangle::with_width_height(256, 256);
+
+    let mut run = |in_place: bool| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+
+        let mut result = input;
+        for _ in 0..CHAIN {
+            result = commands.crop(result, region).expect("Valid to crop");
+        }
+
+        let (output, _) = if in_place {
+            commands.output_in_place(input, result)
+        } else {
+            commands.output(result)
+        }
+        .expect("Valid for output");
+
+        let (result, stats) = run_once_with_output(commands, pool, vec![(input, key)], |retire| {
+            let result = retire.output(output).expect("Valid for output").key();
+            (result, retire.retire_buffers())
+        });
+
+        let image = pool.entry(result).unwrap();
+        assert_eq!(image.as_bytes(), Some(gray.as_raw().as_slice()));
+        stats
+    };
+
+    let separate = run(false);
+    let aliased = run(true);
+
+    assert!(aliased.texture_count() < separate.texture_count());
+    assert!(aliased.mem() < separate.mem());
+}
+
+fn run_dither(pool: &mut Pool) {
+    const WIDTH: u32 = 64;
+    const HEIGHT: u32 = 4;
+
+    // A shallow gradient spanning eight codes of an 8-bit sample.
+    let encoded = |x: u32| 0.4 + (x as f32 / WIDTH as f32) * (8.0 / 255.0);
+    let gradient = image::ImageBuffer::from_fn(WIDTH, HEIGHT, |x, _| {
+        let value = (encoded(x) * 65535.0).round() as u16;
+        image::Rgba([value, value, value, u16::MAX])
+    });
+
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba16(gradient));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+    let texel = buffer::Texel::new_u8(buffer::SampleParts::RgbA);
+
+    let mut quantize = |dither: bool| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+        let converted = commands
+            .color_convert_with(
+                input,
+                descriptor.color.clone(),
+                texel.clone(),
+                command::ColorConvertOptions {
+                    dither,
+                    ..Default::default()
+                },
+            )
+            .expect("Valid for conversion");
+
+        let (output, _) = commands.output(converted).expect("Valid for output");
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Not a byte image").to_vec()
+    };
+
+    // The mean error of the red channel over the 4×4 blocks of the dither pattern.
+    let block_error = |bytes: &[u8]| {
+        let mut error = 0.0;
+        for block in 0..WIDTH / 4 {
+            let mut sum = 0.0;
+            let mut expected = 0.0;
+
+            for y in 0..HEIGHT {
+                for x in 4 * block..4 * block + 4 {
+                    sum += f32::from(bytes[((y * WIDTH + x) * 4) as usize]);
+                    expected += encoded(x) * 255.0;
+                }
+            }
+
+            error += (sum - expected).abs() / 16.0;
+        }
+
+        error / (WIDTH / 4) as f32
+    };
+
+    let banded = block_error(&quantize(false));
+    let dithered = block_error(&quantize(true));
+
+    // Truncation alone is biased by half a code, the dither averages out over the pattern.
+    assert!(
+        dithered < 0.25 && dithered < banded,
+        "Dithered error {dithered}, banded error {banded}"
+    );
+}
+
+fn run_derivative(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
+    const METHODS: &[command::DerivativeMethod] = &[
+        command::DerivativeMethod::Scharr3,
+        command::DerivativeMethod::Scharr3To4Bit,
+        command::DerivativeMethod::Scharr3To8Bit,
+        command::DerivativeMethod::Prewitt,
+        command::DerivativeMethod::Sobel,
+    ];
+
+    for method in METHODS {
+        let mut commands = CommandBuffer::default();
+
+        // Describe the pipeline:
+        // 0: in (background)
+        // 1: derivative(0, derive)
+        // 2: out(2)
+        let background = commands.input(background.clone()).unwrap();
+
+        let derived = commands
+            .derivative(
+                background,
+                command::Derivative {
+                    method: method.clone(),
+                    direction: command::Direction::Width,
+                },
+            )
+            .unwrap();
+
+        let (output_derived, _outformat) = commands.output(derived).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(background, bg_key)],
+            retire_with_one_image(output_derived),
+        );
+
+        let image_derived = pool.entry(result).unwrap();
+        let reference = format!("derived_{:?}.crc.png", method);
+        util::assert_reference(image_derived.into(), &reference);
+    }
+}
+
+fn run_resize(
+    pool: &mut Pool,
+    (bg_key, background): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    use image::imageops::FilterType;
+
+    const FILTERS: &[(command::ResizeFilter, FilterType)] = &[
+        (command::ResizeFilter::Bilinear, FilterType::Triangle),
+        (command::ResizeFilter::Bicubic, FilterType::CatmullRom),
+        (command::ResizeFilter::Lanczos3, FilterType::Lanczos3),
+    ];
+
+    let (width, height) = (background.layout.width, background.layout.height);
+    // Both a downscale by a large factor and an upscale.
+    let sizes = [(width / 7, height / 5), (width * 3 / 2, height * 2)];
+
+    for &(filter, reference_filter) in FILTERS {
+        for (new_width, new_height) in sizes {
+            let mut commands = CommandBuffer::default();
+
+            let input = commands.input(background.clone()).unwrap();
+            let resized = commands
+                .resize_with(input, (new_width, new_height), filter)
+                .expect("Valid to resize");
+
+            let (output, _outformat) = commands.output(resized).expect("Valid for output");
+
+            let result = run_once_with_output(
+                commands,
+                pool,
+                vec![(input, bg_key)],
+                retire_with_one_image(output),
+            );
+
+            let image_resized = PoolImage::from(pool.entry(result).unwrap());
+            let image_resized = image_resized.to_image().expect("Not a byte image");
+            let expected = image::imageops::resize(
+                &reference.to_rgba8(),
+                new_width,
+                new_height,
+                reference_filter,
+            );
+
+            // We filter the linear color, the reference filters the encoded sRGB values. Allow for
+            // that difference on average while still catching any misplaced footprint.
+            let resized = image_resized.to_rgba8();
+            let total: u64 = resized
+                .as_raw()
+                .iter()
+                .zip(expected.as_raw())
+                .map(|(&a, &b)| u64::from(a.abs_diff(b)))
+                .sum();
+
+            let mean = total as f64 / resized.as_raw().len() as f64;
+            assert!(
+                mean < 4.0,
+                "{filter:?} to {new_width}x{new_height} differs by {mean}"
+            );
+        }
+    }
+}
+
+fn run_tile(
+    pool: &mut Pool,
+    (bg_key, background): (PoolKey, Descriptor),
+    reference: &image::DynamicImage,
+) {
+    let (width, height) = (background.layout.width, background.layout.height);
+    // Whole repetitions and a partial tile at the right and bottom edge.
+    let (tiled_width, tiled_height) = (2 * width + width / 3, height + height / 2);
+    let reference = reference.to_rgba8();
+
+    for mirrored in [false, true] {
+        let mut commands = CommandBuffer::default();
+
+        let input = commands.input(background.clone()).unwrap();
+        let tiled = if mirrored {
+            commands.tile_mirrored(input, (tiled_width, tiled_height))
+        } else {
+            commands.tile(input, (tiled_width, tiled_height))
+        }
+        .expect("Valid to tile");
+
+        let (output, _outformat) = commands.output(tiled).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, bg_key)],
+            retire_with_one_image(output),
+        );
+
+        let image_tiled = PoolImage::from(pool.entry(result).unwrap());
+        let image_tiled = image_tiled.to_image().expect("Not a byte image").to_rgba8();
+        assert_eq!(image_tiled.dimensions(), (tiled_width, tiled_height));
+
+        let source = |x: u32, size: u32| {
+            let (repetition, offset) = (x / size, x % size);
+            if mirrored && repetition % 2 == 1 {
+                size - 1 - offset
+            } else {
+                offset
+            }
+        };
+
+        for (x, y, pixel) in image_tiled.enumerate_pixels() {
+            let expected = reference.get_pixel(source(x, width), source(y, height));
+            let close = pixel
+                .0
+                .iter()
+                .zip(expected.0)
+                .all(|(&a, b)| a.abs_diff(b) <= 1);
+            assert!(close, "Tiled at {x}, {y}: {pixel:?} vs {expected:?}");
+        }
+    }
+}
+
+fn run_solid(pool: &mut Pool) {
+    let mut layout = image::DynamicImage::new_rgba8(400, 400);
+    let descriptor = Descriptor::with_srgb_image(&layout);
+
+    let mut commands = CommandBuffer::default();
+    let generated = commands
+        .solid_rgba(descriptor, [0.5, 0.5, 1.0, 1.0])
+        .unwrap();
+
+    let (output, _outformat) = commands.output(generated).expect("Valid for output");
+
+    let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+    let image_generated = pool.entry(result).unwrap();
+
+    match layout {
+        image::DynamicImage::ImageRgba8(ref mut buffer) => {
+            let bytes = image_generated.as_bytes().expect("Not a byte image");
+            bytemuck::cast_slice_mut(&mut *buffer).copy_from_slice(bytes);
+        }
+        _ => unreachable!(),
+    }
+
+    util::assert_reference_image(layout, "solid.crc.png");
+}
+
+fn run_solid_texels(pool: &mut Pool) {
+    use buffer::{SampleBits, SampleParts};
+    const COLOR: [f32; 4] = [0.25, 0.5, 0.75, 1.0];
+
+    let mut solid = |bits, parts| {
+        let texel = buffer::Texel {
+            block: buffer::Block::Pixel,
+            bits,
+            parts,
+        };
+
+        let descriptor = Descriptor::with_texel(texel, 8, 8).unwrap();
+
+        let mut commands = CommandBuffer::default();
+        let generated = commands.solid_rgba(descriptor, COLOR).unwrap();
+        let (output, _outformat) = commands.output(generated).expect("Valid for output");
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+        let image_generated = pool.entry(result).unwrap();
+        image_generated
+            .as_bytes()
+            .expect("Not a byte image")
+            .to_vec()
+    };
+
+    let close = |value: f32, expected: f32, tolerance: f32| {
+        assert!(
+            (value - expected).abs() <= tolerance,
+            "{value} is not {expected}"
+        );
+    };
+
+    let luma = solid(SampleBits::UInt8, SampleParts::Luma);
+    assert_eq!(luma.len(), 64);
+    for &texel in &luma {
+        close(f32::from(texel), 0.25 * 255.0, 1.0);
+    }
+
+    // The host buffers need not be aligned for the wider samples.
+    let rgba16: Vec<u16> = solid(SampleBits::UInt16x4, SampleParts::RgbA)
+        .chunks_exact(2)
+        .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]))
+        .collect();
+    assert_eq!(rgba16.len(), 256);
+    for texel in rgba16.chunks_exact(4) {
+        for (&value, expected) in texel.iter().zip(COLOR) {
+            close(f32::from(value), expected * 65535.0, 1.0);
+        }
+    }
+
+    let rgba32f: Vec<f32> = solid(SampleBits::Float32x4, SampleParts::RgbA)
+        .chunks_exact(4)
+        .map(|bytes| f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    assert_eq!(rgba32f.len(), 256);
+    for texel in rgba32f.chunks_exact(4) {
+        assert_eq!(texel, COLOR);
+    }
+}
+
+fn run_lens_undistort(pool: &mut Pool) {
+    const SPACING: f32 = 32.0;
+    let (width, height) = (256u32, 192u32);
+
+    let distortion = command::Distortion {
+        k1: -0.15,
+        k2: 0.02,
+        k3: 0.0,
+        p1: 0.001,
+        p2: -0.002,
+        center: [width as f32 / 2.0, height as f32 / 2.0],
+        focal: [200.0, 200.0],
+        fill: [1.0; 4],
+    };
+
+    let line_distance = |[x, y]: [f32; 2]| {
+        let to_line = |v: f32| (v - (v / SPACING).round() * SPACING).abs();
+        to_line(x).min(to_line(y))
+    };
+
+    // Draw the grid as the lens images it, finding the undistorted point of each texel.
+    let distorted = image::RgbaImage::from_fn(width, height, |x, y| {
+        let target = [x as f32 + 0.5, y as f32 + 0.5];
+        let mut point = target;
+
+        for _ in 0..32 {
+            let [dx, dy] = distortion.distort(point);
+            point = [point[0] + target[0] - dx, point[1] + target[1] - dy];
+        }
+
+        if line_distance(point) < 1.5 {
+            image::Rgba([0, 0, 0, 255])
+        } else {
+            image::Rgba([255, 255, 255, 255])
+        }
+    });
+
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(distorted));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let undistorted = commands
+        .lens_undistort(input, distortion)
+        .expect("Valid to undistort");
+    let (output, _outformat) = commands.output(undistorted).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let image = PoolImage::from(pool.entry(result).unwrap());
+    let image = image.to_image().expect("Not a byte image").to_rgba8();
+
+    // The grid lines are straight again, up to the resampling of their edges.
+    let mut misplaced = 0;
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let distance = line_distance([x as f32 + 0.5, y as f32 + 0.5]);
+        let dark = pixel.0[0] < 128;
+
+        if (distance < 0.5 && !dark) || (distance > 3.0 && dark) {
+            misplaced += 1;
+        }
+    }
+
+    let tolerance = (width * height) / 200;
+    assert!(
+        misplaced <= tolerance,
+        "Grid not restored, {misplaced} texels misplaced"
+    );
+}
+
+fn run_denoise_nlm(pool: &mut Pool) {
+    let (width, height) = (96u32, 96u32);
+
+    // Flat regions with edges between them, and a slow gradient within.
+    let clean = image::RgbaImage::from_fn(width, height, |x, y| {
+        let base: [u8; 3] = match (x < width / 2, y < height / 2) {
+            (true, true) => [200, 60, 40],
+            (false, true) => [40, 160, 220],
+            (true, false) => [90, 200, 90],
+            (false, false) => [230, 230, 210],
+        };
+
+        let shade = (y % (height / 2)) as u8 / 4;
+        image::Rgba([base[0] - shade, base[1] - shade, base[2] - shade, 255])
+    });
+
+    // Deterministic gaussian noise, by Box-Muller over a linear congruential generator.
+    let mut state = 0x853c_49e6_748f_ea9bu64;
+    let mut uniform = move || {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        ((state >> 40) as f32 + 0.5) / (1u64 << 24) as f32
+    };
+
+    const SIGMA: f32 = 12.0;
+    let mut noisy = clean.clone();
+    for pixel in noisy.pixels_mut() {
+        for channel in &mut pixel.0[..3] {
+            let (u, v) = (uniform(), uniform());
+            let gauss = (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos();
+            *channel = (f32::from(*channel) + SIGMA * gauss)
+                .round()
+                .clamp(0.0, 255.0) as u8;
+        }
+    }
+
+    let psnr = |image: &image::RgbaImage| {
+        let (sum, count) = image
+            .pixels()
+            .zip(clean.pixels())
+            .flat_map(|(a, b)| a.0[..3].iter().zip(&b.0[..3]))
+            .map(|(&a, &b)| (f32::from(a) - f32::from(b)).powi(2))
+            .fold((0.0, 0), |(sum, count), e| (sum + e, count + 1));
+        command::psnr(sum / count as f32, 255.0)
+    };
+
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(noisy.clone()));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let nlm = command::Nlm {
+        search_radius: 3,
+        patch_radius: 1,
+        h: 0.08,
+    };
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let denoised = commands.denoise_nlm(input, nlm).expect("Valid to denoise");
+    let (output, _outformat) = commands.output(denoised).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let image = PoolImage::from(pool.entry(result).unwrap());
+    let image = image.to_image().expect("Not a byte image").to_rgba8();
+
+    let (before, after) = (psnr(&noisy), psnr(&image));
+    assert!(
+        after > before + 3.0,
+        "Denoising did not improve the PSNR, {before} dB to {after} dB"
+    );
+}
+
+/// Blurs of different radii specialize the same shader module into separate pipelines.
+fn run_shadow_radius(pool: &mut Pool) {
+    const SIZE: u32 = 48;
+
+    // An opaque square in the center, such that the shadow shows around it.
+    let square = image::RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+        if (20..28).contains(&x) && (20..28).contains(&y) {
+            image::Rgba([255, 255, 255, 255])
+        } else {
+            image::Rgba([0, 0, 0, 0])
+        }
+    });
+
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(square));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let shadow = |blur_sigma: f32| command::Shadow {
+        offset: (0, 0),
+        blur_sigma,
+        color: [0.0; 3],
+        opacity: 1.0,
+        expand: false,
+    };
+
+    // A radius of 3 and 9 texels respectively.
+    let sigmas = [1.0, 3.0];
+
+    let mut run = |sigmas: &[f32]| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+
+        let outputs: Vec<_> = sigmas
+            .iter()
+            .map(|&sigma| {
+                let shadowed = commands
+                    .drop_shadow(input, shadow(sigma))
+                    .expect("Valid to shadow");
+                commands.output(shadowed).expect("Valid for output").0
+            })
+            .collect();
+
+        let results: Vec<_> = run_once_with_output(commands, pool, vec![(input, key)], |retire| {
+            outputs
+                .iter()
+                .map(|&output| retire.output(output).expect("Valid for output").key())
+                .collect()
+        });
+
+        results
+            .into_iter()
+            .map(|result| {
+                let image = pool.entry(result).unwrap();
+                image.as_bytes().expect("Output on the host").to_vec()
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let together = run(&sigmas);
+    let narrow = run(&sigmas[..1]).remove(0);
+    let wide = run(&sigmas[1..]).remove(0);
+
+    assert_eq!(together[0], narrow);
+    assert_eq!(together[1], wide);
+
+    // Five texels right of the square, only the wide blur reaches.
+    let alpha = |bytes: &[u8]| bytes[((24 * SIZE + 32) * 4 + 3) as usize];
+    assert_eq!(alpha(&narrow), 0);
+    assert!(alpha(&wide) > 0);
+}
+
+/// Abandon a long chain after its first step and recover the resources into the pool.
+fn run_cancel(pool: &mut Pool) {
+    const CHAIN: usize = 16;
+
+    let gray = image::RgbaImage::from_pixel(256, 256, image::Rgba([128, 64, 32, 255]));
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(gray.clone()));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+    let region = Rectangle::with_width_height(256, 256);
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+
+    let mut result = input;
+    for _ in 0..CHAIN {
+        result = commands.crop(result, region).expect("Valid to crop");
+    }
+
+    let (output, _) = commands.output(result).expect("Valid for output");
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let executable = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer")
+        .lower_to(capabilities)
+        .expect("No extras beyond device required");
+
+    let mut environment = executable.from_pool(pool).expect("no device found in pool");
+    environment.bind(input, key).unwrap();
+
+    let mut execution = executable.launch(environment).expect("Launching failed");
+    pool.clear_cache();
+
+    let _ = execution.step().expect("Valid first step");
+    assert!(execution.is_running());
+
+    execution.cancel();
+    assert!(!execution.is_running());
+
+    // Stepping is a no-op, as after the end of the program.
+    let _ = execution
+        .step()
+        .expect("Valid to step a cancelled execution");
+    assert!(!execution.is_running());
+
+    // A second cancel is a no-op.
+    execution.cancel();
+
+    let mut retire = execution.retire_gracefully(pool);
+    let returned = retire.input(input).expect("Valid to retire input").key();
+    retire.prune();
+    let stats = retire.retire_buffers();
+    retire.finish();
+
+    assert_eq!(returned, key);
+
+    // The pool is in a good state for running the program to completion.
+    let (result, complete) = run_once_with_output(commands, pool, vec![(input, key)], |retire| {
+        let result = retire.output(output).expect("Valid for output").key();
+        (result, retire.retire_buffers())
+    });
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes(), Some(gray.as_raw().as_slice()));
+
+    // Nothing beyond the resources of a complete run was allocated.
+    assert!(stats.texture_count() <= complete.texture_count());
+    assert!(stats.mem() <= complete.mem());
+}
+
+/// Lower the same commands twice, the second execution shares all pipelines of the first.
+fn run_shared_pipelines(pool: &mut Pool) {
+    let gray = image::RgbaImage::from_pixel(64, 64, image::Rgba([128, 64, 32, 255]));
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(gray.clone()));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let cropped = commands
+        .crop(input, Rectangle::with_width_height(64, 64))
+        .expect("Valid to crop");
+    let (output, _) = commands.output(cropped).expect("Valid for output");
+
+    let plan = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer");
+
+    let run = |pool: &mut Pool| {
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        let executable = plan
+            .lower_to(capabilities)
+            .expect("No extras beyond device required");
+
+        let mut environment = executable.from_pool(pool).expect("no device found in pool");
+        environment.bind(input, key).unwrap();
+        let _ = environment.recover_buffers();
+
+        let mut execution = executable.launch(environment).expect("Launching failed");
+        pool.clear_cache();
+
+        while execution.is_running() {
+            let _wait_point = execution.step().expect("Shouldn't fail but");
+        }
+
+        let usage = execution.resources_used();
+        let compiled = (usage.shaders_compiled(), usage.pipelines_compiled());
+
+        let mut retire = execution.retire_gracefully(pool);
+        let result = retire.output(output).expect("Valid for output").key();
+        let _ = retire.retire_buffers();
+        retire.finish();
+
+        let image = pool.entry(result).unwrap();
+        assert_eq!(image.as_bytes(), Some(gray.as_raw().as_slice()));
+        compiled
+    };
+
+    let _ = run(pool);
+    assert_eq!(
+        run(pool),
+        (0, 0),
+        "No new modules or pipelines for the same shaders"
+    );
+}
+
+/// Half floats are uploaded into their texture directly, other texels are staged.
+fn run_unstaged_f16(pool: &mut Pool) {
+    let texel = |bits| buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits,
+        parts: buffer::SampleParts::RgbA,
+    };
+
+    let mut linear = buffer::Color::SRGB;
+    if let buffer::Color::Rgb { transfer, .. } = &mut linear {
+        *transfer = buffer::Transfer::Linear;
+    }
+
+    let half = Descriptor {
+        color: linear.clone(),
+        ..Descriptor::with_texel(texel(buffer::SampleBits::Float16x4), 16, 16).unwrap()
+    };
+
+    let short = Descriptor {
+        color: linear,
+        ..Descriptor::with_texel(texel(buffer::SampleBits::UInt16x4), 16, 16).unwrap()
+    };
+
+    let run = |pool: &mut Pool, descriptor: Descriptor, texel: [u16; 4]| {
+        let mut image = buffer::ImageBuffer::with_descriptor(&descriptor);
+        for chunk in image.as_bytes_mut().chunks_exact_mut(8) {
+            chunk.copy_from_slice(bytemuck::bytes_of(&texel));
+        }
+
+        let bytes = image.as_bytes().to_vec();
+        let key = pool.insert(image, descriptor.clone()).key();
+
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor).unwrap();
+        let cropped = commands
+            .crop(input, Rectangle::with_width_height(16, 16))
+            .expect("Valid to crop");
+        let (output, _) = commands.output(cropped).expect("Valid for output");
+
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        let executable = Linker::from_included()
+            .compile(&commands)
+            .expect("Could build command buffer")
+            .lower_to(capabilities)
+            .expect("No extras beyond device required");
+
+        let mut environment = executable.from_pool(pool).expect("no device found in pool");
+        environment.bind(input, key).unwrap();
+
+        let mut execution = executable.launch(environment).expect("Launching failed");
+        while execution.is_running() {
+            let _wait_point = execution.step().expect("Shouldn't fail but");
+        }
+
+        let passes = execution.resources_used().render_passes();
+        let mut retire = execution.retire_gracefully(pool);
+        let result = retire.output(output).expect("Valid for output").key();
+        retire.finish();
+
+        let image = pool.entry(result).unwrap();
+        assert_eq!(image.as_bytes(), Some(bytes.as_slice()));
+        passes
+    };
+
+    // The halfs of 0.5, 0.25, 1.0 and 1.0.
+    let direct = run(pool, half, [0x3800, 0x3400, 0x3c00, 0x3c00]);
+    let staged = run(pool, short, [0, 0xffff, 0, 0xffff]);
+
+    // Only the crop itself, without decoding and encoding the texture.
+    assert_eq!(direct, 1);
+    assert_eq!(staged, 3);
+}
+
+/// Compare a summed-area table against sums on the CPU, far beyond the range of half floats.
+fn run_integral_image(pool: &mut Pool) {
+    const WIDTH: u32 = 1024;
+    const HEIGHT: u32 = 1024;
+
+    // A fixed xorshift sequence, the sums of noise are sensitive to the precision of each pass.
+    let mut state = 0x2545_f491_4f6c_dd1d_u64;
+    let mut random = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 56) as u8
+    };
+
+    let descriptor = linear_descriptor(buffer::SampleBits::UInt8x4, WIDTH, HEIGHT);
+    let source = image::RgbaImage::from_fn(WIDTH, HEIGHT, |_, _| {
+        image::Rgba([random(), random(), random(), 255])
+    });
+
+    let mut buffer = buffer::ImageBuffer::with_descriptor(&descriptor);
+    buffer.as_bytes_mut().copy_from_slice(source.as_raw());
+    let key = pool.insert(buffer, descriptor.clone()).key();
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let table = commands
+        .integral_image(input)
+        .expect("Valid for integral image");
+    let (output, _) = commands.output(table).expect("Valid for output");
+
+    let features = {
+        let mut devices = pool.iter_devices();
+        devices
+            .next()
+            .expect("the pool to contain a device")
+            .features()
+    };
+
+    if !features.contains(wgpu::Features::FLOAT32_FILTERABLE) {
+        let capabilities = Capabilities::from(pool.iter_devices().next().unwrap());
+        let lowered = Linker::from_included()
+            .compile(&commands)
+            .expect("Could build command buffer")
+            .lower_to(capabilities);
+        assert!(lowered.is_err(), "Half precision sums must be rejected");
+        panic!("The device lacks FLOAT32_FILTERABLE, the sums of an integral image are untested");
+    }
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let table = PoolImage::from(pool.entry(result).unwrap()).to_image();
+    let Some(image::DynamicImage::ImageRgba32F(table)) = table else {
+        panic!("Not a float image: {table:?}");
+    };
+
+    // The inclusive sums, in double precision.
+    let (width, height) = (WIDTH as usize, HEIGHT as usize);
+    let mut sums = vec![[0.0f64; 4]; width * height];
+    for y in 0..height {
+        for x in 0..width {
+            let texel = source.get_pixel(x as u32, y as u32).0;
+            for c in 0..4 {
+                let left = if x > 0 {
+                    sums[y * width + x - 1][c]
+                } else {
+                    0.0
+                };
+                let up = if y > 0 {
+                    sums[(y - 1) * width + x][c]
+                } else {
+                    0.0
+                };
+                let corner = if x > 0 && y > 0 {
+                    sums[(y - 1) * width + x - 1][c]
+                } else {
+                    0.0
+                };
+
+                let value = f64::from(texel[c]) / 255.0;
+                sums[y * width + x][c] = value + left + up - corner;
+            }
+        }
+    }
+
+    for (x, y, texel) in table.enumerate_pixels() {
+        let reference = sums[y as usize * width + x as usize];
+        for (&sum, &expected) in texel.0.iter().zip(&reference) {
+            assert!(
+                (f64::from(sum) - expected).abs() <= 1e-5 * expected + 1e-4,
+                "Sum at {x},{y} is {sum}, expected {expected}"
+            );
+        }
+    }
+
+    // A box blur from four lookups of the table, compared to the mean of the source.
+    let at = |x: i64, y: i64, c: usize| -> f64 {
+        if x < 0 || y < 0 {
+            0.0
+        } else {
+            f64::from(table.get_pixel(x as u32, y as u32).0[c])
+        }
+    };
+
+    const RADIUS: i64 = 4;
+    let area = ((2 * RADIUS + 1) * (2 * RADIUS + 1)) as f64;
+    for (cx, cy) in [(4, 4), (100, 37), (512, 512), (1019, 1019)] {
+        for c in 0..3 {
+            let (x0, y0, x1, y1) = (cx - RADIUS - 1, cy - RADIUS - 1, cx + RADIUS, cy + RADIUS);
+            let blurred = (at(x1, y1, c) - at(x0, y1, c) - at(x1, y0, c) + at(x0, y0, c)) / area;
+
+            let mut mean = 0.0;
+            for y in cy - RADIUS..=cy + RADIUS {
+                for x in cx - RADIUS..=cx + RADIUS {
+                    mean += f64::from(source.get_pixel(x as u32, y as u32).0[c]) / 255.0;
+                }
+            }
+
+            mean /= area;
+            assert!(
+                (blurred - mean).abs() < 1e-2,
+                "Box blur at {cx},{cy} is {blurred}, expected {mean}"
+            );
+        }
+    }
+}
+
+/// Rotate the hue by half a turn, which negates the chroma in Oklab.
+///
+/// The reference rotation goes through the published linear sRGB to Oklab matrices. A neutral
+/// image relative to D50 must stay neutral, its whitepoint is adapted before entering Oklab.
+fn run_ab_rotate(pool: &mut Pool) {
+    const TEXELS: [[f32; 4]; 4] = [
+        [0.6, 0.4, 0.3, 1.0],
+        [0.2, 0.5, 0.3, 1.0],
+        [0.1, 0.2, 0.7, 1.0],
+        [0.5, 0.5, 0.5, 1.0],
+    ];
+
+    let run = |pool: &mut Pool, descriptor: Descriptor| {
+        let mut image = buffer::ImageBuffer::with_descriptor(&descriptor);
+        image
+            .as_bytes_mut()
+            .copy_from_slice(bytemuck::cast_slice(&TEXELS));
+        let key = pool.insert(image, descriptor.clone()).key();
+
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor).unwrap();
+        let rotated = commands
+            .ab_rotate(input, std::f32::consts::PI)
+            .expect("Valid to rotate");
+        let (output, _) = commands.output(rotated).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        let image = PoolImage::from(pool.entry(result).unwrap()).to_image();
+        let Some(image::DynamicImage::ImageRgba32F(image)) = image else {
+            panic!("Not a float image: {image:?}");
+        };
+
+        image.pixels().map(|texel| texel.0).collect::<Vec<_>>()
+    };
+
+    let close = |actual: [f32; 4], expected: [f32; 4]| {
+        let near = actual
+            .iter()
+            .zip(&expected)
+            .all(|(a, e)| (a - e).abs() < 1e-2);
+        assert!(near, "{actual:?} differs from {expected:?}");
+    };
+
+    let d65 = linear_descriptor(buffer::SampleBits::Float32x4, 4, 1);
+    for (&texel, actual) in TEXELS.iter().zip(run(pool, d65.clone())) {
+        let [l, a, b] = linear_srgb_to_oklab(texel);
+        let [r, g, b] = oklab_to_linear_srgb([l, -a, -b]);
+        close(actual, [r, g, b, texel[3]]);
+    }
+
+    let mut d50 = d65;
+    if let buffer::Color::Rgb { whitepoint, .. } = &mut d50.color {
+        *whitepoint = Whitepoint::D50;
+    }
+
+    let rotated = run(pool, d50);
+    close(rotated[3], TEXELS[3]);
+}
+
+/// The mean structural similarity of fixtures with a closed form index.
+///
+/// Identical images have an index of exactly one. For constant images the variances and the
+/// covariance vanish and the index is the luminance term of Wang et al. alone.
+fn run_ssim(pool: &mut Pool) {
+    const SIZE: u32 = 8;
+
+    let run = |pool: &mut Pool, lhs: &dyn Fn(u32, u32) -> f32, rhs: &dyn Fn(u32, u32) -> f32| {
+        let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, SIZE, SIZE);
+        let mut insert = |value: &dyn Fn(u32, u32) -> f32| {
+            let texels: Vec<[f32; 4]> = (0..SIZE * SIZE)
+                .map(|idx| {
+                    let v = value(idx % SIZE, idx / SIZE);
+                    [v, v, v, 1.0]
+                })
+                .collect();
+
+            let mut image = buffer::ImageBuffer::with_descriptor(&descriptor);
+            image
+                .as_bytes_mut()
+                .copy_from_slice(bytemuck::cast_slice(&texels));
+            pool.insert(image, descriptor.clone()).key()
+        };
+
+        let lhs_key = insert(lhs);
+        let rhs_key = insert(rhs);
+
+        let mut commands = CommandBuffer::default();
+        let lhs = commands.input(descriptor.clone()).unwrap();
+        let rhs = commands.input(descriptor).unwrap();
+        let index = commands
+            .ssim(lhs, rhs, command::Ssim::default())
+            .expect("Valid for ssim");
+        let (output, _) = commands.output_buffer(index).expect("Valid for output");
+
+        let bytes = run_once_with_output(
+            commands,
+            pool,
+            vec![(lhs, lhs_key), (rhs, rhs_key)],
+            |retire| retire.output_buffer(output).expect("Valid for output"),
+        );
+
+        f32::from_le_bytes(bytes[..4].try_into().unwrap())
+    };
+
+    let gradient = |x: u32, _: u32| x as f32 / (SIZE - 1) as f32;
+    let inverse = |x: u32, y: u32| 1.0 - gradient(x, y);
+
+    let same = run(pool, &gradient, &gradient);
+    assert!((same - 1.0).abs() < 1e-3, "{same} is not one");
+
+    // With `k1 = 0.01` the luminance term of 0.25 against 0.75 is `0.3751 / 0.6251`.
+    let constant = run(pool, &|_, _| 0.25, &|_, _| 0.75);
+    assert!((constant - 0.600_064).abs() < 1e-3, "{constant} mismatched");
+
+    // Anti-correlated structure is dissimilar.
+    let opposed = run(pool, &gradient, &inverse);
+    assert!(opposed < 0.0, "{opposed} is not negative");
+}
+
+/// Tone map radiance with both operators and compare against reference values of their curves.
+fn run_tonemap(pool: &mut Pool) {
+    let run = |pool: &mut Pool, tonemap: command::ToneMap, radiance: &[f32]| {
+        let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, radiance.len() as u32, 1);
+
+        let texels: Vec<[f32; 4]> = radiance.iter().map(|&v| [v, v, v, 1.0]).collect();
+        let mut image = buffer::ImageBuffer::with_descriptor(&descriptor);
+        image
+            .as_bytes_mut()
+            .copy_from_slice(bytemuck::cast_slice(&texels));
+        let key = pool.insert(image, descriptor.clone()).key();
+
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor).unwrap();
+        let mapped = commands.tonemap(input, tonemap).expect("Valid to tone map");
+        let (output, _) = commands.output(mapped).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        let image = PoolImage::from(pool.entry(result).unwrap()).to_image();
+        let Some(image::DynamicImage::ImageRgba32F(image)) = image else {
+            panic!("Not a float image: {image:?}");
+        };
+
+        image.pixels().map(|texel| texel.0).collect::<Vec<_>>()
+    };
+
+    let check = |actual: Vec<[f32; 4]>, expected: &[f32]| {
+        for (texel, &expected) in actual.iter().zip(expected) {
+            for &channel in &texel[..3] {
+                assert!(
+                    (channel - expected).abs() < 2e-3,
+                    "{texel:?} differs from {expected}"
+                );
+            }
+
+            assert_eq!(texel[3], 1.0);
+        }
+    };
+
+    // Values of the Narkowicz fit to the ACES reference rendering and output transforms.
+    let aces = run(
+        pool,
+        command::ToneMap::AcesFitted,
+        &[0.0, 0.18, 1.0, 4.0, 16.0],
+    );
+    check(aces, &[0.0, 0.266_899, 0.803_797, 0.973_417, 1.0]);
+
+    // The extended operator maps `white` to one, and `1.0` to `(1 + 1/16) / 2`.
+    let reinhard = run(
+        pool,
+        command::ToneMap::Reinhard { white: 4.0 },
+        &[0.0, 1.0, 4.0, 8.0],
+    );
+    check(reinhard, &[0.0, 0.531_25, 1.0, 1.0]);
+}
+
+/// Extract the luminance of saturated green, whose weight depends on the primaries.
+fn run_luminance(pool: &mut Pool) {
+    let run = |pool: &mut Pool, primaries: buffer::Primaries| {
+        let mut descriptor = linear_descriptor(buffer::SampleBits::Float32x4, 4, 4);
+        if let buffer::Color::Rgb { primary, .. } = &mut descriptor.color {
+            *primary = primaries;
+        }
+
+        let texels = [[0.0f32, 1.0, 0.0, 1.0]; 16];
+        let mut image = buffer::ImageBuffer::with_descriptor(&descriptor);
+        image
+            .as_bytes_mut()
+            .copy_from_slice(bytemuck::cast_slice(&texels));
+        let key = pool.insert(image, descriptor.clone()).key();
+
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor).unwrap();
+        let luma = commands
+            .extract(input, buffer::ColorChannel::Luma)
+            .expect("Valid to extract luminance");
+        let (output, _) = commands.output(luma).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        let bytes = image.as_bytes().expect("Luminance on the host");
+        let luma: Vec<f32> = bytes
+            .chunks_exact(4)
+            .map(|v| f32::from_le_bytes(v.try_into().unwrap()))
+            .collect();
+        assert!(luma.iter().all(|&v| v == luma[0]), "{luma:?}");
+        luma[0]
+    };
+
+    // The `Y` coefficients of green in Rec.709 and in Rec.2020.
+    let bt709 = run(pool, buffer::Primaries::Bt709);
+    let bt2020 = run(pool, buffer::Primaries::Bt2020);
+
+    assert!((bt709 - 0.7152).abs() < 2e-3, "{bt709}");
+    assert!((bt2020 - 0.6780).abs() < 2e-3, "{bt2020}");
+}
+
+/// A uniform color cast over a neutral ramp is removed by the gray-world assumption.
+fn run_white_balance(pool: &mut Pool) {
+    const SIZE: u32 = 8;
+    const CAST: [f32; 3] = [0.9, 0.6, 0.3];
+
+    let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, SIZE, SIZE);
+    let gray = |idx: u32| 0.1 + 0.05 * (idx % SIZE + idx / SIZE) as f32;
+    let texels: Vec<[f32; 4]> = (0..SIZE * SIZE)
+        .map(|idx| {
+            let v = gray(idx);
+            [v * CAST[0], v * CAST[1], v * CAST[2], 1.0]
+        })
+        .collect();
+    let key = insert_float_texels(pool, &descriptor, &texels);
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let balanced = commands
+        .auto_white_balance(input, command::AwbMethod::GrayWorld)
+        .expect("Valid to white balance");
+    let (output, _) = commands.output(balanced).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    // The gains scale each channel to the average of the cast.
+    let average = CAST.iter().sum::<f32>() / 3.0;
+    for (idx, texel) in float_texels(pool, result).into_iter().enumerate() {
+        let expected = gray(idx as u32) * average;
+        assert!(
+            texel[..3].iter().all(|c| (c - expected).abs() < 2e-3),
+            "{texel:?} is not a neutral {expected}"
+        );
+    }
+}
+
+/// Ramps from black to white, sampled at the texel centers.
+///
+/// Interpolated linearly the ramp is the position itself. In Oklab the lightness is interpolated
+/// and a gray of lightness `L` has the linear value `L³`, which must also hold for grays relative
+/// to another whitepoint than the D65 of Oklab.
+fn run_color_ramp(pool: &mut Pool) {
+    const WIDTH: u32 = 8;
+    const STOPS: [(f32, [f32; 4]); 2] = [(0.0, [0.0, 0.0, 0.0, 1.0]), (1.0, [1.0; 4])];
+
+    let run = |pool: &mut Pool, descriptor: Descriptor, space: command::RampSpace| {
+        let mut commands = CommandBuffer::default();
+        let ramp = commands
+            .color_ramp(descriptor, &STOPS, command::Direction::Width, space)
+            .expect("Valid color ramp");
+        let (output, _) = commands.output(ramp).expect("Valid for output");
+
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+        float_texels(pool, result)
+    };
+
+    let check = |texels: Vec<[f32; 4]>, curve: fn(f32) -> f32| {
+        for (x, texel) in texels.iter().enumerate() {
+            let expected = curve((x as f32 + 0.5) / WIDTH as f32);
+            assert!(
+                texel[..3].iter().all(|c| (c - expected).abs() < 2e-3),
+                "{texel:?} at {x} differs from {expected}"
+            );
+        }
+    };
+
+    let d65 = linear_descriptor(buffer::SampleBits::Float32x4, WIDTH, 1);
+    let mut d50 = d65.clone();
+    if let buffer::Color::Rgb { whitepoint, .. } = &mut d50.color {
+        *whitepoint = Whitepoint::D50;
+    }
+
+    check(run(pool, d65.clone(), command::RampSpace::Linear), |t| t);
+    check(run(pool, d65, command::RampSpace::Oklab), |t| t.powi(3));
+    check(run(pool, d50, command::RampSpace::Oklab), |t| t.powi(3));
+}
+
+/// Evaluate Catmull-Rom splines and compare them against their closed form.
+fn run_spline_ramp(pool: &mut Pool) {
+    const WIDTH: u32 = 8;
+
+    let run = |pool: &mut Pool, points: Vec<f32>| {
+        let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, WIDTH, 1);
+        let spline = command::CatmullRom {
+            points: points.into_iter().map(|v| [v, v, v, 1.0]).collect(),
+        };
+
+        let mut commands = CommandBuffer::default();
+        let ramp = commands
+            .spline_ramp(descriptor, spline)
+            .expect("Valid spline ramp");
+        let (output, _) = commands.output(ramp).expect("Valid for output");
+
+        let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+        float_texels(pool, result)
+    };
+
+    let check = |texels: Vec<[f32; 4]>, curve: &dyn Fn(f32) -> f32| {
+        for (x, texel) in texels.iter().enumerate() {
+            let expected = curve((x as f32 + 0.5) / WIDTH as f32);
+            assert!(
+                texel
+                    .iter()
+                    .zip([expected; 3])
+                    .all(|(c, e)| (c - e).abs() < 2e-3),
+                "{texel:?} at {x} differs from {expected}"
+            );
+            assert_eq!(texel[3], 1.0);
+        }
+    };
+
+    // A single segment from 0 to 1 with flat tangents at both ends, `(t + 3t² - 2t³) / 2`.
+    let step = run(pool, vec![0.0, 0.0, 1.0, 1.0]);
+    check(step, &|t| 0.5 * (t + 3.0 * t * t - 2.0 * t * t * t));
+
+    // Uniformly spaced collinear points reproduce the line through them, over all segments.
+    let line = run(pool, vec![-0.25, 0.0, 0.25, 0.5, 0.75, 1.0]);
+    check(line, &|t| 0.75 * t);
+}
+
+/// Fuse an under-, a well- and an over-exposed capture of a colored checkerboard.
+///
+/// The mid-tones of the well exposed capture dominate the weights, the dark capture has little
+/// well-exposedness and the clipped capture has no saturation at all.
+fn run_exposure_fusion(pool: &mut Pool) {
+    const SIZE: u32 = 8;
+    const SCENE: [[f32; 3]; 2] = [[0.30, 0.20, 0.10], [0.15, 0.25, 0.12]];
+
+    let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, SIZE, SIZE);
+    let capture = |exposure: f32| -> Vec<[f32; 4]> {
+        (0..SIZE * SIZE)
+            .map(|idx| {
+                let [r, g, b] = SCENE[((idx % SIZE + idx / SIZE) % 2) as usize];
+                let expose = |v: f32| (v * exposure).min(1.0);
+                [expose(r), expose(g), expose(b), 1.0]
+            })
+            .collect()
+    };
+
+    let well = capture(1.0);
+    let captures = [capture(0.02), well.clone(), capture(20.0)];
+    let keys: Vec<_> = captures
+        .iter()
+        .map(|texels| insert_float_texels(pool, &descriptor, texels))
+        .collect();
+
+    let mut commands = CommandBuffer::default();
+    let inputs: Vec<_> = keys
+        .iter()
+        .map(|_| commands.input(descriptor.clone()).unwrap())
+        .collect();
+    let fused = commands
+        .exposure_fusion(&inputs, command::FusionWeights::default())
+        .expect("Valid to fuse");
+    let (output, _) = commands.output(fused).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        inputs.iter().copied().zip(keys),
+        retire_with_one_image(output),
+    );
+
+    for (texel, expected) in float_texels(pool, result).iter().zip(&well) {
+        assert!(
+            texel
+                .iter()
+                .zip(expected)
+                .all(|(c, e)| (c - e).abs() < 5e-3),
+            "{texel:?} is not the well exposed {expected:?}"
+        );
+    }
+}
+
+/// Count every value of a channel once, and compare the bins with counts on the CPU.
+fn run_histogram(pool: &mut Pool) {
+    const WIDTH: u32 = 256;
+    const HEIGHT: u32 = 4;
+    const BINS: u32 = 16;
+
+    let descriptor = linear_descriptor(buffer::SampleBits::UInt8x4, WIDTH, HEIGHT);
+    let source = image::RgbaImage::from_fn(WIDTH, HEIGHT, |x, y| {
+        image::Rgba([x as u8, (x * y) as u8, 0x80, 0xff])
+    });
+
+    let mut buffer = buffer::ImageBuffer::with_descriptor(&descriptor);
+    buffer.as_bytes_mut().copy_from_slice(source.as_raw());
+    let key = pool.insert(buffer, descriptor.clone()).key();
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let histogram = commands
+        .histogram(
+            input,
+            command::HistogramConfig {
+                bins: BINS,
+                channel: buffer::ColorChannel::R,
+            },
+        )
+        .expect("Valid for histogram");
+    let (output, _) = commands.output_buffer(histogram).expect("Valid for output");
+
+    let bytes = run_once_with_output(commands, pool, vec![(input, key)], |retire| {
+        retire.output_buffer(output).expect("Valid for output")
+    });
+
+    let counts: Vec<u32> = bytes[..4 * BINS as usize]
+        .chunks_exact(4)
+        .map(|v| u32::from_le_bytes(v.try_into().unwrap()))
+        .collect();
+
+    let mut expected = vec![0; BINS as usize];
+    for texel in source.pixels() {
+        let value = f32::from(texel.0[0]) / 255.0;
+        expected[((value * BINS as f32) as usize).min(BINS as usize - 1)] += 1;
+    }
+
+    assert_eq!(counts.iter().sum::<u32>(), WIDTH * HEIGHT);
+    assert_eq!(counts, expected);
+}
+
+/// Equalize a low contrast gray ramp, and check the limit that leaves images unchanged.
+fn run_clahe(pool: &mut Pool) {
+    const SIZE: u32 = 64;
+
+    let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, SIZE, SIZE);
+    let ramp: Vec<[f32; 4]> = (0..SIZE * SIZE)
+        .map(|idx| {
+            let v = 0.4 + 0.1 * (idx % SIZE) as f32 / (SIZE - 1) as f32;
+            [v, v, v, 1.0]
+        })
+        .collect();
+    let key = insert_float_texels(pool, &descriptor, &ramp);
+
+    let mut run = |clip_limit: f32| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+        let equalized = commands
+            .clahe(
+                input,
+                command::Clahe {
+                    tiles: (1, 1),
+                    clip_limit,
+                },
+            )
+            .expect("Valid for clahe");
+        let (output, _) = commands.output(equalized).expect("Valid for output");
+
+        let result = run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        );
+
+        float_texels(pool, result)
+    };
+
+    // Without clipping this is a plain equalization, stretching the ramp over the whole range.
+    let equalized = run(1e3);
+    let row: Vec<f32> = equalized[..SIZE as usize].iter().map(|t| t[1]).collect();
+    assert!(row.windows(2).all(|w| w[0] <= w[1]), "{row:?}");
+    assert!(row[0] < 0.1 && row[SIZE as usize - 1] > 0.9, "{row:?}");
+
+    for texel in &equalized {
+        assert!((texel[0] - texel[1]).abs() < 1e-3 && (texel[2] - texel[1]).abs() < 1e-3);
+    }
+
+    // At the limit of the average count the histogram is flat, and the mapping the identity.
+    for (texel, expected) in run(1.0).iter().zip(&ramp) {
+        assert!(
+            texel
+                .iter()
+                .zip(expected)
+                .all(|(c, e)| (c - e).abs() < 1e-2),
+            "{texel:?} is not {expected:?}"
+        );
+    }
+}
+
+/// Reduce an image of odd size, with isolated extremes, to the statistics of each channel.
+fn run_reduce(pool: &mut Pool) {
+    const WIDTH: u32 = 13;
+    const HEIGHT: u32 = 7;
+
+    let descriptor = linear_descriptor(buffer::SampleBits::Float32x4, WIDTH, HEIGHT);
+    let mut texels: Vec<[f32; 4]> = (0..WIDTH * HEIGHT)
+        .map(|idx| {
+            let v = 0.2 + 0.4 * ((idx * 7) % 11) as f32 / 10.0;
+            [v, 0.6 - v / 2.0, 0.5, 1.0]
+        })
+        .collect();
+
+    // Extremes at the last row and column, which are folded in by the odd borders.
+    texels[(WIDTH * HEIGHT - 1) as usize] = [0.95, 0.05, 0.5, 1.0];
+    texels[(WIDTH - 1) as usize] = [0.05, 0.95, 0.5, 0.25];
+    let key = insert_float_texels(pool, &descriptor, &texels);
+
+    let mut run = |reduction: command::Reduction| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor.clone()).unwrap();
+        let reduced = commands.reduce(input, reduction).expect("Valid for reduce");
+        let (output, _) = commands.output_buffer(reduced).expect("Valid for output");
+
+        let bytes = run_once_with_output(commands, pool, vec![(input, key)], |retire| {
+            retire.output_buffer(output).expect("Valid for output")
+        });
+
+        let mut statistic = [0.0f32; 4];
+        for (c, v) in statistic.iter_mut().zip(bytes[..16].chunks_exact(4)) {
+            *c = f32::from_le_bytes(v.try_into().unwrap());
+        }
+
+        statistic
+    };
+
+    let fold = |init: f32, op: fn(f32, f32) -> f32| {
+        let mut statistic = [init; 4];
+        for texel in &texels {
+            for (s, &v) in statistic.iter_mut().zip(texel) {
+                *s = op(*s, v);
+            }
+        }
+        statistic
+    };
+
+    let count = texels.len() as f32;
+    let mean = fold(0.0, |s, v| s + v).map(|s| s / count);
+
+    let close = |actual: [f32; 4], expected: [f32; 4]| {
+        let near = actual
+            .iter()
+            .zip(&expected)
+            .all(|(a, e)| (a - e).abs() < 2e-3);
+        assert!(near, "{actual:?} differs from {expected:?}");
+    };
+
+    close(run(command::Reduction::Min), [0.05, 0.05, 0.5, 0.25]);
+    close(run(command::Reduction::Max), [0.95, 0.95, 0.5, 1.0]);
+    close(run(command::Reduction::Mean), mean);
+}
+
+/// Upscale a checkerboard with Lanczos, whose overshoot across edges must only clip at the end.
+///
+/// The byte image must be the float image clamped and quantized, which requires that the pass
+/// along the width does not clamp the negative lobes before the pass along the height.
+fn run_resize_overshoot(pool: &mut Pool) {
+    const SIZE: u32 = 64;
+    const RESIZED: (u32, u32) = (96, 96);
+
+    let value = |idx: u32| ((idx % SIZE) / 8 + (idx / SIZE) / 8) % 2;
+
+    let run = |pool: &mut Pool, descriptor: Descriptor, key: PoolKey| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(descriptor).unwrap();
+        let resized = commands
+            .resize_with(input, RESIZED, command::ResizeFilter::Lanczos3)
+            .expect("Valid to resize");
+        let (output, _) = commands.output(resized).expect("Valid for output");
+
+        run_once_with_output(
+            commands,
+            pool,
+            vec![(input, key)],
+            retire_with_one_image(output),
+        )
+    };
+
+    let bytes = linear_descriptor(buffer::SampleBits::UInt8x4, SIZE, SIZE);
+    let mut image = buffer::ImageBuffer::with_descriptor(&bytes);
+    for (idx, texel) in image.as_bytes_mut().chunks_exact_mut(4).enumerate() {
+        let v = 255 * value(idx as u32) as u8;
+        texel.copy_from_slice(&[v, v, v, 255]);
+    }
+    let key = pool.insert(image, bytes.clone()).key();
+    let quantized = run(pool, bytes, key);
+    let quantized = pool.entry(quantized).unwrap().as_bytes().unwrap().to_vec();
+
+    let floats = linear_descriptor(buffer::SampleBits::Float32x4, SIZE, SIZE);
+    let texels: Vec<[f32; 4]> = (0..SIZE * SIZE)
+        .map(|idx| {
+            let v = value(idx) as f32;
+            [v, v, v, 1.0]
+        })
+        .collect();
+    let key = insert_float_texels(pool, &floats, &texels);
+    let result = run(pool, floats, key);
+
+    let expected = float_texels(pool, result)
+        .into_iter()
+        .flatten()
+        .map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+
+    let worst = quantized
+        .iter()
+        .zip(expected)
+        .map(|(&a, b)| a.abs_diff(b))
+        .max();
+    assert!(
+        worst <= Some(1),
+        "Clipped before the last pass by {worst:?}"
+    );
+}
+
+/// Insert an image of `f32` texels, such as described by [`linear_descriptor`].
+fn insert_float_texels(pool: &mut Pool, descriptor: &Descriptor, texels: &[[f32; 4]]) -> PoolKey {
+    let mut image = buffer::ImageBuffer::with_descriptor(descriptor);
+    image
+        .as_bytes_mut()
+        .copy_from_slice(bytemuck::cast_slice(texels));
+    pool.insert(image, descriptor.clone()).key()
+}
+
+/// The texels of a float image in the pool, in row order.
+fn float_texels(pool: &mut Pool, key: PoolKey) -> Vec<[f32; 4]> {
+    let image = PoolImage::from(pool.entry(key).unwrap()).to_image();
+    let Some(image::DynamicImage::ImageRgba32F(image)) = image else {
+        panic!("Not a float image: {image:?}");
+    };
+
+    image.pixels().map(|texel| texel.0).collect()
+}
+
+/// Björn Ottosson's reference conversion of linear sRGB into Oklab.
+fn linear_srgb_to_oklab([r, g, b, _]: [f32; 4]) -> [f32; 3] {
+    let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+    let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+    let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+    let [l, m, s] = [l.cbrt(), m.cbrt(), s.cbrt()];
+
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+/// The inverse of [`linear_srgb_to_oklab`].
+fn oklab_to_linear_srgb([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = l + 0.396_337_78 * a + 0.215_803_76 * b;
+    let m_ = l - 0.105_561_346 * a - 0.063_854_17 * b;
+    let s_ = l - 0.089_484_18 * a - 1.291_485_5 * b;
+    let [l, m, s] = [l_.powi(3), m_.powi(3), s_.powi(3)];
+
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_4 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+}
+
+/// A descriptor of linear sRGB with the texel bits of all four channels.
+fn linear_descriptor(bits: buffer::SampleBits, width: u32, height: u32) -> Descriptor {
+    let texel = buffer::Texel {
+        block: buffer::Block::Pixel,
+        bits,
+        parts: buffer::SampleParts::RgbA,
+    };
+
+    let mut color = buffer::Color::SRGB;
+    if let buffer::Color::Rgb { transfer, .. } = &mut color {
+        *transfer = buffer::Transfer::Linear;
+    }
+
+    Descriptor {
+        color,
+        ..Descriptor::with_texel(texel, width, height).unwrap()
+    }
 }
@@ -2,10 +2,10 @@
 #[path = "util.rs"]
 mod util;
 
-use zosimos::command::{Bilinear, CommandBuffer, Linker, RegisterKnob};
-use zosimos::pool::Pool;
-use zosimos::program::Program;
-use zosimos::{buffer::Descriptor, program::Capabilities};
+use zosimos::buffer::{Descriptor, ImageBuffer, SampleParts};
+use zosimos::command::{Bilinear, CommandBuffer, Linker, Rectangle, RegisterKnob};
+use zosimos::pool::{Pool, PoolImage, PoolKey};
+use zosimos::program::{Capabilities, Program};
 
 use self::util::{retire_with_one_image, run_once_with_output};
 
@@ -33,7 +33,21 @@ fn buffer_interop() {
 
     run_from_buffer_knob(&mut pool);
 
+    run_from_buffer_offset_knob(&mut pool);
+
+    run_buffer_slice(&mut pool);
+
+    run_buffer_concat(&mut pool);
+
+    run_buffer_splat(&mut pool);
+
     run_bilinear(&mut pool);
+
+    run_buffer_offsets(&mut pool);
+
+    run_buffer_output(&mut pool);
+
+    run_half_roundtrip(&mut pool);
 }
 
 fn run_from_buffer(pool: &mut Pool) {
@@ -73,7 +87,8 @@ fn run_from_buffer_knob(pool: &mut Pool) {
     let buffer = commands
         .with_knob()
         .buffer_init(&a)
-        .expect("Valid for knob");
+        .expect("Valid for knob")
+        .register();
 
     let result = commands
         .from_buffer(buffer, descriptor)
@@ -118,6 +133,179 @@ fn run_from_buffer_knob(pool: &mut Pool) {
     util::assert_reference(image.into(), "from_buffer-with-knob.crc.png");
 }
 
+/// Cycle through two frames in the same buffer, by changing the offset knob between launches.
+fn run_from_buffer_offset_knob(pool: &mut Pool) {
+    let mut commands = CommandBuffer::default();
+
+    let descriptor = Descriptor::with_srgb_image(&image::DynamicImage::new_luma8(8, 8));
+
+    // Two frames, as laid out in `run_from_buffer`. Each has a black row at its index.
+    const FRAME: usize = 8 * 256;
+    let mut frames = [0xff; 2 * FRAME];
+    frames[256..][..8].copy_from_slice(&[0x00; 8]);
+    frames[FRAME + 512..][..8].copy_from_slice(&[0x00; 8]);
+    let buffer = commands.buffer_init(&frames);
+
+    // Offsets must be aligned and the image must fit the buffer.
+    assert!(commands
+        .from_buffer_at(buffer, 2, descriptor.clone())
+        .is_err());
+    assert!(commands
+        .from_buffer_at(buffer, FRAME as u64 + 4, descriptor.clone())
+        .is_err());
+
+    let handle = commands
+        .with_knob()
+        .from_buffer_at(buffer, 0, descriptor)
+        .expect("Buffer valid for this image descriptor");
+    assert_eq!(handle.byte_len(), Some(8));
+
+    let (output, _outformat) = commands
+        .output(handle.register())
+        .expect("Valid for output");
+
+    let executable = {
+        let plan = Linker::from_included()
+            .compile(&commands)
+            .expect("Could build command buffer");
+
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        plan.lower_to(capabilities)
+            .expect("No extras beyond device required")
+    };
+
+    {
+        let mut environment = executable.from_pool(pool).expect("no device found in pool");
+
+        // The offsets are rejected before launching, not on the device.
+        for offset in [1u64, FRAME as u64 + 4, 2 * FRAME as u64] {
+            assert!(environment.set_knob(&handle.in_link(0), &offset).is_err());
+        }
+    }
+
+    let knob = executable.query_knob(handle.in_link(0)).unwrap();
+
+    for (frame, offset) in [0, FRAME as u64, 0].into_iter().enumerate() {
+        let data = handle.encode(&offset);
+        let result = util::run_executable_with_output(
+            &executable,
+            pool,
+            vec![],
+            vec![(knob, &data[..])],
+            retire_with_one_image(output),
+        );
+
+        let black_row = if offset == 0 { 1 } else { 2 };
+        let mut expected = [0xff; 8 * 8];
+        expected[8 * black_row..][..8].copy_from_slice(&[0x00; 8]);
+
+        let image = pool.entry(result).unwrap();
+        let bytes = image.as_bytes().expect("Output on the host");
+        assert_eq!(bytes, &expected[..], "Frame {frame} at offset {offset}");
+    }
+}
+
+/// Strip a header from a buffer, before interpreting the rest as an image.
+fn run_buffer_slice(pool: &mut Pool) {
+    let mut commands = CommandBuffer::default();
+
+    let descriptor = Descriptor::with_srgb_image(&image::DynamicImage::new_luma8(8, 8));
+
+    // A header of one row, followed by the frame as laid out in `run_from_buffer`.
+    let mut a = [0xff; 9 * 256];
+    a[..256].copy_from_slice(&[0x00; 256]);
+    a[512..][..8].copy_from_slice(&[0x00; 8]);
+    let buffer = commands.buffer_init(&a);
+
+    let frame = commands
+        .buffer_slice(buffer, 256..9 * 256)
+        .expect("Range valid for this buffer");
+
+    let result = commands
+        .from_buffer(frame, descriptor)
+        .expect("Buffer valid for this image descriptor");
+
+    let (output, _outformat) = commands.output(result).expect("Valid for output");
+
+    let result = run_once_with_output(commands, pool, vec![], retire_with_one_image(output));
+
+    let image = pool.entry(result).unwrap();
+    util::assert_reference(image.into(), "from_buffer.crc.png");
+}
+
+/// Assemble a blob from a header, a frame and a footer, then retrieve the frame again.
+fn run_buffer_concat(pool: &mut Pool) {
+    let mut commands = CommandBuffer::default();
+
+    let descriptor = Descriptor::with_srgb_image(&image::DynamicImage::new_luma8(8, 8));
+
+    // The frame as laid out in `run_from_buffer`.
+    let mut frame = [0xff; 8 * 256];
+    frame[256..][..8].copy_from_slice(&[0x00; 8]);
+
+    let header = commands.buffer_init(&[0x01; 256]);
+    let body = commands.buffer_init(&frame);
+    let footer = commands.buffer_init(&[0x02; 256]);
+
+    let (blob, ranges) = commands
+        .buffer_concat(&[header, body, footer])
+        .expect("Valid to concatenate");
+
+    let part = commands
+        .buffer_slice(blob, ranges[1].clone())
+        .expect("Range valid for this buffer");
+    let result = commands
+        .from_buffer(part, descriptor)
+        .expect("Buffer valid for this image descriptor");
+
+    let (output, _outformat) = commands.output(result).expect("Valid for output");
+    let (output_blob, _) = commands.output_buffer(blob).expect("Valid for output");
+
+    let (result, bytes) = run_once_with_output(commands, pool, vec![], |retire| {
+        let bytes = retire.output_buffer(output_blob).expect("Output of bytes");
+        (retire_with_one_image(output)(retire), bytes)
+    });
+
+    let mut expected = vec![0x01; 256];
+    expected.extend_from_slice(&frame);
+    expected.extend_from_slice(&[0x02; 256]);
+    assert_eq!(bytes, expected);
+
+    let image = pool.entry(result).unwrap();
+    util::assert_reference(image.into(), "from_buffer.crc.png");
+}
+
+/// Fill buffers with a repeated pattern, aligned or not.
+fn run_buffer_splat(pool: &mut Pool) {
+    let mut commands = CommandBuffer::default();
+
+    let block: Vec<u8> = (0..16).collect();
+    let blocks = commands.buffer_splat(&block, 80).expect("Valid to splat");
+    let triples = commands
+        .buffer_splat(&[0xa, 0xb, 0xc], 256)
+        .expect("Valid to splat");
+
+    let (output_blocks, _) = commands.output_buffer(blocks).expect("Valid for output");
+    let (output_triples, _) = commands.output_buffer(triples).expect("Valid for output");
+
+    let (blocks, triples) = run_once_with_output(commands, pool, vec![], |retire| {
+        let blocks = retire
+            .output_buffer(output_blocks)
+            .expect("Output of bytes");
+        let triples = retire
+            .output_buffer(output_triples)
+            .expect("Output of bytes");
+        (blocks, triples)
+    });
+
+    assert_eq!(blocks, block.repeat(80));
+    assert_eq!(triples, [0xa, 0xb, 0xc].repeat(256));
+}
+
 fn run_bilinear(pool: &mut Pool) {
     let mut commands = CommandBuffer::default();
 
@@ -147,3 +335,174 @@ fn run_bilinear(pool: &mut Pool) {
     let image = pool.entry(result).unwrap();
     util::assert_reference(image.into(), "bilinear_from_buffer.crc.png");
 }
+
+/// One buffer holds the parameters of two operations, at different offsets.
+fn run_buffer_offsets(pool: &mut Pool) {
+    let descriptor = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(4, 4));
+
+    let first = [1.0f32, 0.0, 0.0, 1.0];
+    let second = [0.0f32, 0.0, 1.0, 1.0];
+    let params: Vec<u8> = first
+        .iter()
+        .chain(&second)
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+
+    let mut commands = CommandBuffer::default();
+    let buffer = commands.buffer_init(&params);
+
+    let from_first = commands
+        .with_buffer(buffer)
+        .expect("Buffer valid for with_buffer")
+        .solid_rgba(descriptor.clone(), [0.0; 4])
+        .expect("Buffer holds the parameters");
+    let from_second = commands
+        .with_buffer(buffer)
+        .expect("Buffer valid for with_buffer")
+        .with_start(16)
+        .expect("Start within the buffer")
+        .solid_rgba(descriptor.clone(), [0.0; 4])
+        .expect("Buffer holds the parameters");
+
+    // The second color does not fit after the start.
+    assert!(commands
+        .with_buffer(buffer)
+        .unwrap()
+        .with_start(20)
+        .unwrap()
+        .solid_rgba(descriptor.clone(), [0.0; 4])
+        .is_err());
+
+    let (output_first, _) = commands.output(from_first).expect("Valid for output");
+    let (output_second, _) = commands.output(from_second).expect("Valid for output");
+
+    let (key_first, key_second) = run_once_with_output(commands, pool, vec![], |retire| {
+        let first = retire.output(output_first).expect("Valid for output").key();
+        let second = retire
+            .output(output_second)
+            .expect("Valid for output")
+            .key();
+        (first, second)
+    });
+
+    for (key, color) in [(key_first, first), (key_second, second)] {
+        let mut reference = CommandBuffer::default();
+        let solid = reference
+            .solid_rgba(descriptor.clone(), color)
+            .expect("Valid solid color");
+        let (output, _) = reference.output(solid).expect("Valid for output");
+        let expected = run_once_with_output(reference, pool, vec![], retire_with_one_image(output));
+
+        let expected = pool
+            .entry(expected)
+            .unwrap()
+            .as_bytes()
+            .expect("Output on the host")
+            .to_vec();
+        let image = pool.entry(key).unwrap();
+        assert_eq!(image.as_bytes().expect("Output on the host"), &expected[..]);
+    }
+}
+
+fn run_buffer_output(pool: &mut Pool) {
+    let mut commands = CommandBuffer::default();
+
+    let mut under = [0u8; 512];
+    under
+        .iter_mut()
+        .enumerate()
+        .for_each(|(idx, byte)| *byte = idx as u8);
+
+    let base = commands.buffer_init(&under);
+    let over = commands.buffer_init(&[0xff; 16]);
+    let overlaid = commands
+        .buffer_overlay(base, 256, over)
+        .expect("Valid to overlay");
+
+    let (output, _) = commands.output_buffer(overlaid).expect("Valid for output");
+
+    let bytes = run_once_with_output(commands, pool, vec![], |retire| {
+        retire.output_buffer(output).expect("Output of bytes")
+    });
+
+    let mut expected = under;
+    expected[256..][..16].copy_from_slice(&[0xff; 16]);
+    assert_eq!(bytes, expected);
+}
+
+fn run_half_roundtrip(pool: &mut Pool) {
+    // All exactly representable as halfs, including the largest and smallest ones.
+    const SAMPLES: [f32; 8] = [
+        65504.0,
+        1000.25,
+        3.0,
+        1.0,
+        0.0,
+        1.0 / 16384.0,
+        1.0 / 1048576.0,
+        1.0 / 16777216.0,
+    ];
+
+    let radiance = image::Rgba32FImage::from_fn(16, 16, |x, y| {
+        let idx = (x + y) as usize;
+        image::Rgba(core::array::from_fn(|c| SAMPLES[(idx + c) % SAMPLES.len()]))
+    });
+
+    let entry = pool.insert_linear_half(&radiance);
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+    let result = run_half_crop(pool, key, descriptor);
+
+    let image = PoolImage::from(pool.entry(result).unwrap()).to_image();
+    let Some(image::DynamicImage::ImageRgba32F(image)) = image else {
+        panic!("Not a float image: {image:?}");
+    };
+
+    assert_eq!(image.as_raw(), radiance.as_raw());
+
+    // The same data in a channel order that requires staging. The stage shader converts with
+    // `unpackHalf2x16` which need not preserve denormals, so these are raised to normal halfs.
+    let mut descriptor = Descriptor::with_linear_half(16, 16).unwrap();
+    descriptor.texel.parts = SampleParts::BgrA;
+
+    let mut buffer = ImageBuffer::with_descriptor(&descriptor);
+    let target = buffer.as_bytes_mut().chunks_exact_mut(8);
+    for (texel, pixel) in target.zip(radiance.pixels()) {
+        let [r, g, b, a] = pixel.0.map(|sample| half_bits(sample.max(1.0 / 16384.0)));
+        for (half, bits) in texel.chunks_exact_mut(2).zip([b, g, r, a]) {
+            half.copy_from_slice(&bits.to_ne_bytes());
+        }
+    }
+
+    let expected = buffer.as_bytes().to_vec();
+    let entry = pool.insert(buffer, descriptor);
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+    let result = run_half_crop(pool, key, descriptor);
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes(), Some(expected.as_slice()));
+}
+
+fn run_half_crop(pool: &mut Pool, key: PoolKey, descriptor: Descriptor) -> PoolKey {
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let cropped = commands
+        .crop(input, Rectangle::with_width_height(16, 16))
+        .expect("Valid to crop");
+    let (output, _) = commands.output(cropped).expect("Valid for output");
+
+    run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    )
+}
+
+/// The bits of a half, for samples that are exactly representable as normal halfs.
+fn half_bits(sample: f32) -> u16 {
+    let bits = sample.to_bits();
+    let sign = (bits >> 16) & 0x8000;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = (bits >> 13) & 0x3ff;
+    (sign | ((exponent as u32) << 10) | mantissa) as u16
+}
@@ -12,11 +12,32 @@
 //! operations are invoked on the linear equivalent which is always an rgb under a certain
 //! whitepoint.
 use zosimos::{
-    buffer::{Descriptor, ImageBuffer},
-    command::{CommandBuffer, Linker},
-    pool::Pool,
+    buffer::{Block, Color, Descriptor, ImageBuffer, SampleBits, SampleParts, Texel, Transfer},
+    command::{CommandBuffer, Linker, Rectangle},
+    pool::{Pool, PoolImage, PoolKey},
+    program::Program,
 };
 
+#[path = "util.rs"]
+mod util;
+
+use self::util::{retire_with_one_image, run_once_with_output};
+
+const WIDTH: u32 = 8;
+const HEIGHT: u32 = 4;
+
+/// The packed texels with the bit width of each of their parts, from the low bits upwards.
+const PACKED: &[(SampleBits, SampleParts, &[u32])] = &[
+    (SampleBits::UInt1010102, SampleParts::RgbA, &[10, 10, 10, 2]),
+    (SampleBits::UInt1010102, SampleParts::BgrA, &[10, 10, 10, 2]),
+    (SampleBits::UInt2101010, SampleParts::ARgb, &[2, 10, 10, 10]),
+    (SampleBits::UInt2101010, SampleParts::ABgr, &[2, 10, 10, 10]),
+    (SampleBits::UInt565, SampleParts::Rgb, &[5, 6, 5]),
+    (SampleBits::UInt565, SampleParts::Bgr, &[5, 6, 5]),
+    (SampleBits::UInt4x4, SampleParts::RgbA, &[4, 4, 4, 4]),
+    (SampleBits::UInt4x4, SampleParts::ABgr, &[4, 4, 4, 4]),
+];
+
 #[test]
 fn test_descriptor_as_gpu_texture() {
     let mut pool = Pool::new();
@@ -85,3 +106,213 @@ fn check_input_zeros_as(descriptor: Descriptor, pool: &mut Pool, instance: &wgpu
         retire.finish();
     }
 }
+
+#[test]
+fn packed_texels() {
+    const ANY: wgpu::Backends = wgpu::Backends::VULKAN;
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: ANY,
+        ..Default::default()
+    });
+
+    let adapter = Program::request_adapter(&instance).expect("to get an adapter");
+
+    let mut pool = Pool::new();
+    pool.request_device(&adapter, Program::minimal_device_descriptor())
+        .expect("to get a device");
+
+    for &(bits, parts, widths) in PACKED {
+        run_packed_roundtrip(&mut pool, bits, parts, widths);
+        run_packed_decode(&mut pool, bits, parts, widths);
+        run_packed_encode(&mut pool, bits, parts, widths);
+    }
+}
+
+/// Staging in and out again must reproduce every bit.
+fn run_packed_roundtrip(pool: &mut Pool, bits: SampleBits, parts: SampleParts, widths: &[u32]) {
+    let descriptor = linear_descriptor(bits, parts);
+    let packed = packed_image(&descriptor, widths);
+    let key = pool.insert(packed, descriptor.clone()).key();
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor.clone()).unwrap();
+    let cropped = commands
+        .crop(input, Rectangle::with_width_height(WIDTH, HEIGHT))
+        .expect("Valid to crop");
+    let (output, _) = commands.output(cropped).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let expected = packed_image(&descriptor, widths);
+    let image = pool.entry(result).unwrap();
+    assert_eq!(
+        image.as_bytes(),
+        Some(expected.as_bytes()),
+        "{bits:?} {parts:?}"
+    );
+}
+
+/// Each part is unpacked from its own bits and moved to its channel.
+fn run_packed_decode(pool: &mut Pool, bits: SampleBits, parts: SampleParts, widths: &[u32]) {
+    let descriptor = linear_descriptor(bits, parts);
+    let packed = packed_image(&descriptor, widths);
+    let key = pool.insert(packed, descriptor.clone()).key();
+
+    let float = linear_descriptor(SampleBits::Float32x4, SampleParts::RgbA);
+    let result = run_convert(pool, key, descriptor, float);
+
+    let image = PoolImage::from(pool.entry(result).unwrap()).to_image();
+    let Some(image::DynamicImage::ImageRgba32F(image)) = image else {
+        panic!("Not a float image: {image:?}");
+    };
+
+    for (idx, pixel) in image.pixels().enumerate() {
+        let levels = test_levels(idx, widths);
+        let mut expected = [1.0f32; 4];
+
+        for (part, (&level, &width)) in levels.iter().zip(widths).enumerate() {
+            expected[channel_of(parts, part)] = level as f32 / max_level(width) as f32;
+        }
+
+        // Half a step of the widest part, the intermediate texture holds half floats.
+        let tolerance = 0.5 / 1023.0;
+        for (&value, &reference) in pixel.0.iter().zip(&expected) {
+            assert!(
+                (value - reference).abs() < tolerance,
+                "{bits:?} {parts:?} at {idx}: {:?} vs. {expected:?}",
+                pixel.0,
+            );
+        }
+    }
+}
+
+/// Values are rounded to the nearest step when packed, including the 2-bit alpha.
+fn run_packed_encode(pool: &mut Pool, bits: SampleBits, parts: SampleParts, widths: &[u32]) {
+    let float = linear_descriptor(SampleBits::Float32x4, SampleParts::RgbA);
+    let mut buffer = ImageBuffer::with_descriptor(&float);
+
+    let target = buffer.as_bytes_mut().chunks_exact_mut(16);
+    for (idx, texel) in target.enumerate() {
+        let levels = test_levels(idx, widths);
+        let mut values = [1.0f32; 4];
+
+        // Slightly below each level, truncation would give the next lower one.
+        for (part, (&level, &width)) in levels.iter().zip(widths).enumerate() {
+            let value = (level as f32 - 0.2).max(0.0) / max_level(width) as f32;
+            values[channel_of(parts, part)] = value;
+        }
+
+        for (sample, value) in texel.chunks_exact_mut(4).zip(values) {
+            sample.copy_from_slice(&value.to_ne_bytes());
+        }
+    }
+
+    let key = pool.insert(buffer, float.clone()).key();
+
+    let descriptor = linear_descriptor(bits, parts);
+    let expected = packed_image(&descriptor, widths);
+    let result = run_convert(pool, key, float, descriptor);
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(
+        image.as_bytes(),
+        Some(expected.as_bytes()),
+        "{bits:?} {parts:?}"
+    );
+}
+
+fn run_convert(pool: &mut Pool, key: PoolKey, from: Descriptor, into: Descriptor) -> PoolKey {
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(from).unwrap();
+    let converted = commands
+        .color_convert(input, into.color, into.texel)
+        .expect("Valid to convert");
+    let (output, _) = commands.output(converted).expect("Valid for output");
+
+    run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    )
+}
+
+fn linear_descriptor(bits: SampleBits, parts: SampleParts) -> Descriptor {
+    let texel = Texel {
+        block: Block::Pixel,
+        bits,
+        parts,
+    };
+
+    let mut color = Color::SRGB;
+    if let Color::Rgb { transfer, .. } = &mut color {
+        *transfer = Transfer::Linear;
+    }
+
+    Descriptor {
+        color,
+        ..Descriptor::with_texel(texel, WIDTH, HEIGHT).expect("Valid packed texel")
+    }
+}
+
+/// The reference packing, each part in the next higher bits.
+fn packed_image(descriptor: &Descriptor, widths: &[u32]) -> ImageBuffer {
+    let mut buffer = ImageBuffer::with_descriptor(descriptor);
+    let stride = descriptor.texel.bits.bytes() as usize;
+
+    let target = buffer.as_bytes_mut().chunks_exact_mut(stride);
+    for (idx, texel) in target.enumerate() {
+        let levels = test_levels(idx, widths);
+        let (word, _) = levels
+            .iter()
+            .zip(widths)
+            .fold((0u32, 0u32), |(word, shift), (&level, &width)| {
+                (word | (level << shift), shift + width)
+            });
+
+        match stride {
+            2 => texel.copy_from_slice(&(word as u16).to_ne_bytes()),
+            4 => texel.copy_from_slice(&word.to_ne_bytes()),
+            _ => unreachable!("Not a packed texel"),
+        }
+    }
+
+    buffer
+}
+
+/// Levels of each part, differing between the parts, including the extremes of each width.
+fn test_levels(idx: usize, widths: &[u32]) -> [u32; 4] {
+    let mut levels = [0; 4];
+    for (part, (level, &width)) in levels.iter_mut().zip(widths).enumerate() {
+        let max = max_level(width);
+        *level = match idx {
+            0 => 0,
+            1 => max,
+            _ => (idx * (2 * part + 1) * 37 + part) as u32 % (max + 1),
+        };
+    }
+
+    levels
+}
+
+fn max_level(width: u32) -> u32 {
+    (1 << width) - 1
+}
+
+/// The `RgbA` channel of a part, by its position in the texel.
+fn channel_of(parts: SampleParts, part: usize) -> usize {
+    let channels: [usize; 4] = match parts {
+        SampleParts::RgbA | SampleParts::Rgb => [0, 1, 2, 3],
+        SampleParts::BgrA | SampleParts::Bgr => [2, 1, 0, 3],
+        SampleParts::ARgb => [3, 0, 1, 2],
+        SampleParts::ABgr => [3, 2, 1, 0],
+        _ => unreachable!("Not a tested part order"),
+    };
+
+    channels[part]
+}
@@ -266,6 +266,9 @@ fn flat_correction() {
                 initial_amplitude: 0.1,
                 amplitude_damping: 0.2,
                 grid_scale: [10.0, 10.0],
+                lacunarity: 2.0,
+                warp: 0.0,
+                seed: 0,
             },
         )
         .expect("Value as fractal noise");
@@ -290,3 +293,101 @@ fn flat_correction() {
     let image = pool.entry(result).unwrap();
     util::assert_reference(image.into(), "flat_field.crc.png");
 }
+
+#[test]
+fn dynamic_from_buffer() {
+    /// A solid color, read from the uniform data of the shader.
+    struct Solid {
+        source: &'static [u8],
+        descriptor: Descriptor,
+    }
+
+    impl ShaderCommand for Solid {
+        fn source(&self) -> command::ShaderSource {
+            command::ShaderSource::SpirV(self.source.into())
+        }
+
+        fn data(&self, mut data: command::ShaderData<'_>) -> Descriptor {
+            // Only declares the size of the color, which is replaced by the buffer.
+            data.set_data(&[0.0f32; 4]);
+            self.descriptor.clone()
+        }
+    }
+
+    pub const SHADER_ENCODE: &[u8] =
+        include_bytes!(concat!(env!("OUT_DIR"), "/spirv/solid_rgb.frag.v"));
+
+    let _ = env_logger::try_init();
+
+    const ANY: wgpu::Backends = wgpu::Backends::VULKAN;
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: ANY,
+        ..Default::default()
+    });
+
+    let adapter = Program::minimum_adapter(instance.enumerate_adapters(ANY).into_iter())
+        .expect("to get an adapter");
+
+    let mut pool = Pool::new();
+
+    pool.request_device(&adapter, Program::minimal_device_descriptor())
+        .expect("to get a device");
+
+    let descriptor = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(4, 4));
+    let color = [0.25f32, 0.5, 0.75, 1.0];
+
+    // The color is at offset 16 of a larger buffer.
+    let params: Vec<u8> = [[1.0f32; 4], color, [0.0; 4]]
+        .iter()
+        .flatten()
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+
+    let mut commands = CommandBuffer::default();
+    let buffer = commands.buffer_init(&params);
+
+    let solid = Solid {
+        source: SHADER_ENCODE,
+        descriptor: descriptor.clone(),
+    };
+
+    let dynamic = commands
+        .with_buffer(buffer)
+        .expect("Buffer valid for with_buffer")
+        .with_start(16)
+        .expect("Start within the buffer")
+        .construct_dynamic(&solid)
+        .expect("Buffer holds the shader data");
+
+    // The data of the shader does not fit after the start.
+    assert!(commands
+        .with_buffer(buffer)
+        .unwrap()
+        .with_start(48)
+        .unwrap()
+        .construct_dynamic(&solid)
+        .is_err());
+
+    let (output, _) = commands.output(dynamic).expect("Valid for output");
+    let result = run_once_with_output(commands, &mut pool, vec![], retire_with_one_image(output));
+    let result = pool
+        .entry(result)
+        .unwrap()
+        .as_bytes()
+        .expect("Output on the host")
+        .to_vec();
+
+    let mut reference = CommandBuffer::default();
+    let solid = reference
+        .solid_rgba(descriptor.clone(), color)
+        .expect("Valid solid color");
+    let (output, _) = reference.output(solid).expect("Valid for output");
+    let expected =
+        run_once_with_output(reference, &mut pool, vec![], retire_with_one_image(output));
+
+    let expected = pool.entry(expected).unwrap();
+    assert_eq!(
+        expected.as_bytes().expect("Output on the host"),
+        &result[..]
+    );
+}
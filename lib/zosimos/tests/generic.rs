@@ -5,6 +5,7 @@ mod util;
 use zosimos::buffer::{ColorChannel, Descriptor, SampleParts, Texel};
 use zosimos::command::{
     self, Bilinear, CommandBuffer, CommandError, GenericDeclaration, Linker, Palette,
+    PaletteAddress,
 };
 use zosimos::pool::Pool;
 use zosimos::program::{Capabilities, Program};
@@ -33,7 +34,7 @@ fn generic_palette() {
 
     let fixed_palette = (|| {
         let mut commands = CommandBuffer::default();
-        let in_a = commands.generic(GenericDeclaration { bounds: &[] });
+        let in_a = commands.generic(GenericDeclaration::default())?;
 
         let img_input = commands.input_generic(in_a)?;
 
@@ -56,6 +57,8 @@ fn generic_palette() {
                 width: Some(ColorChannel::G),
                 height_base: 0,
                 width_base: 0,
+                address: PaletteAddress::Clamp,
+                interpolate: false,
             },
             img_idx,
         )?;
@@ -125,4 +128,165 @@ fn generic_palette() {
 
     let image = pool.entry(generic).unwrap();
     util::assert_reference(image.into(), "generic.crc.png");
+
+    run_generic_buffer(&mut pool);
+
+    run_repeat(&mut pool);
+}
+
+/// A generic function returns the buffer of its input, instantiated at two sizes.
+fn run_generic_buffer(pool: &mut Pool) {
+    let mut function = CommandBuffer::default();
+    let var = function.generic(GenericDeclaration::default()).unwrap();
+    let input = function.input_generic(var).unwrap();
+    let buffer = function.buffer_from_image(input).unwrap();
+    function.buffer_result(buffer).unwrap();
+
+    let pattern = |width, height| {
+        image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(width, height, |x, y| {
+            image::Rgba([x as u8, y as u8, 0x80, 0xff])
+        }))
+    };
+
+    let small = pattern(4, 4);
+    let large = pattern(8, 3);
+
+    let mut commands = CommandBuffer::default();
+    let callee = commands.function(function.computed_signature()).unwrap();
+
+    let mut instance = |image: &image::DynamicImage| {
+        let entry = pool.insert_srgb(image);
+        let input = commands.input(entry.descriptor()).unwrap();
+        let ty = commands.register_descriptor(input).unwrap();
+
+        let [buffer] = commands
+            .invoke(
+                callee,
+                command::InvocationArguments {
+                    generics: &[ty],
+                    arguments: &[input],
+                },
+            )
+            .unwrap()[..]
+        else {
+            panic!("Mismatched function results count");
+        };
+
+        let (output, _) = commands.output_buffer(buffer).unwrap();
+        ((input, entry.key()), output)
+    };
+
+    let (bind_small, output_small) = instance(&small);
+    let (bind_large, output_large) = instance(&large);
+
+    let executable = {
+        let plan = Linker::from_included()
+            .link(&commands, &[], &[function], &[&[1]])
+            .expect("Could build command buffer");
+
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        plan.lower_to(capabilities)
+            .expect("No extras beyond device required")
+    };
+
+    let (key_small, key_large) = run_executable_with_output(
+        &executable,
+        pool,
+        vec![bind_small, bind_large],
+        [],
+        |retire| {
+            let small = retire.output(output_small).expect("Valid for output").key();
+            let large = retire.output(output_large).expect("Valid for output").key();
+            (small, large)
+        },
+    );
+
+    // Each row of the image is padded to the 256 byte alignment of the device.
+    for (key, image) in [(key_small, &small), (key_large, &large)] {
+        let image = image.to_rgba8();
+        let entry = pool.entry(key).unwrap();
+        let bytes = entry.as_bytes().expect("Output on the host");
+        assert_eq!(bytes.len(), 256 * image.height() as usize);
+
+        for (row, expected) in bytes.chunks(256).zip(image.rows()) {
+            let expected: Vec<u8> = expected.flat_map(|texel| texel.0).collect();
+            assert_eq!(&row[..expected.len()], &expected[..]);
+        }
+    }
+}
+
+/// Apply a rotation of the color channels several times, alternating between two textures.
+fn run_repeat(pool: &mut Pool) {
+    use zosimos::buffer::ColorChannel;
+
+    let pixel = image::Rgba([10, 100, 200, 255]);
+    let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(4, 4, pixel));
+    let entry = pool.insert_srgb(&image);
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let mut function = CommandBuffer::default();
+    let input = function.input(descriptor.clone()).unwrap();
+    let channel_r = function.extract(input, ColorChannel::R).unwrap();
+    let channel_g = function.extract(input, ColorChannel::G).unwrap();
+    let channel_b = function.extract(input, ColorChannel::B).unwrap();
+    let rotated = function.inject(input, ColorChannel::G, channel_r).unwrap();
+    let rotated = function
+        .inject(rotated, ColorChannel::B, channel_g)
+        .unwrap();
+    let rotated = function
+        .inject(rotated, ColorChannel::R, channel_b)
+        .unwrap();
+    function.output(rotated).unwrap();
+
+    let mut commands = CommandBuffer::default();
+    let body = commands.function(function.computed_signature()).unwrap();
+    let input = commands.input(descriptor).unwrap();
+
+    // Rotated four times and five times, which is once and twice.
+    let once = commands.repeat(4, body, input).unwrap();
+    let twice = commands.repeat(5, body, input).unwrap();
+    let (output_once, _) = commands.output(once).unwrap();
+    let (output_twice, _) = commands.output(twice).unwrap();
+
+    let executable = {
+        let plan = Linker::from_included()
+            .link(&commands, &[], &[function], &[&[1]])
+            .expect("Could build command buffer");
+
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        plan.lower_to(capabilities)
+            .expect("No extras beyond device required")
+    };
+
+    let (key_once, key_twice) =
+        run_executable_with_output(&executable, pool, vec![(input, key)], [], |retire| {
+            let once = retire.output(output_once).expect("Valid for output").key();
+            let twice = retire.output(output_twice).expect("Valid for output").key();
+            (once, twice)
+        });
+
+    for (key, expected) in [
+        (key_once, [200, 10, 100, 255]),
+        (key_twice, [100, 200, 10, 255]),
+    ] {
+        let entry = pool.entry(key).unwrap();
+        let bytes = entry.as_bytes().expect("Output on the host");
+
+        for texel in bytes.chunks_exact(4) {
+            for (&actual, expected) in texel.iter().zip(expected) {
+                assert!(
+                    actual.abs_diff(expected) <= 1,
+                    "{texel:?} is not {expected:?}"
+                );
+            }
+        }
+    }
 }
@@ -6,9 +6,10 @@
 mod util;
 
 use zosimos::buffer::Descriptor;
-use zosimos::command::{self, CommandBuffer, Linker};
+use zosimos::command::{self, CommandBuffer, GenericDeclaration, Linker};
 use zosimos::pool::{Pool, PoolKey};
-use zosimos::program::{Capabilities, Program};
+use zosimos::program::{Capabilities, Knob, Program};
+use zosimos::run::Executable;
 
 use self::util::{retire_with_one_image, run_executable_with_output};
 
@@ -44,6 +45,18 @@ fn knob_on_overlay() {
     pool.request_device(&adapter, Program::minimal_device_descriptor())
         .expect("to get a device");
 
+    run_knob_instances(&mut pool);
+
+    run_crop(&mut pool, pool_background.clone());
+
+    run_palette(&mut pool, pool_background.clone());
+
+    run_typed_solid(&mut pool);
+
+    run_knob_while_running(&mut pool);
+
+    run_bypass(&mut pool, pool_background.clone());
+
     run_bilinear(
         &mut pool,
         pool_foreground.clone(),
@@ -123,7 +136,8 @@ fn run_bilinear(
     let result_affine = commands
         .with_knob()
         .bilinear(like, bilinear)
-        .expect("Valid to paint with affine transformation");
+        .expect("Valid to paint with affine transformation")
+        .register();
 
     let (output_affine, _outformat) = commands.output(result_affine).expect("Valid for output");
 
@@ -164,3 +178,570 @@ fn run_bilinear(
         util::assert_reference(image_with_knob.into(), &reference);
     }
 }
+
+/// Move a crop over an image by only changing its knob.
+fn run_crop(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
+    let first = command::Rectangle {
+        x: 0,
+        y: 0,
+        max_x: 128,
+        max_y: 128,
+    };
+
+    let second = command::Rectangle {
+        x: 256,
+        y: 128,
+        max_x: 384,
+        max_y: 256,
+    };
+
+    let crop = |knob: bool, rect: command::Rectangle| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(background.clone()).unwrap();
+
+        let cropped = if knob {
+            commands
+                .with_knob()
+                .crop(input, rect)
+                .map(|knob| knob.register())
+        } else {
+            commands.crop(input, rect)
+        }
+        .expect("Valid to crop");
+
+        let (output, _) = commands.output(cropped).expect("Valid for output");
+        (commands, input, cropped, output)
+    };
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let compile = |commands: &CommandBuffer| {
+        Linker::from_included()
+            .compile(commands)
+            .expect("Could build command buffer")
+            .lower_to(capabilities.clone())
+            .expect("No extras beyond device required")
+    };
+
+    let run = |pool: &mut Pool,
+               executable: &Executable,
+               input: command::Register,
+               output: command::Register,
+               knobs: Vec<(Knob, &[u8])>| {
+        let result = run_executable_with_output(
+            executable,
+            pool,
+            vec![(input, bg_key)],
+            knobs,
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Output on the host").to_vec()
+    };
+
+    let (commands, input, cropped, output) = crop(true, first);
+    let executable = compile(&commands);
+
+    let knob = executable
+        .query_knob(command::RegisterKnob {
+            link_idx: 0,
+            register: cropped,
+        })
+        .unwrap();
+
+    let (width, height) = (512.0, 512.0);
+    let quad = |rect: command::Rectangle| -> Vec<u8> {
+        let (min_u, max_u) = (rect.x as f32 / width, rect.max_x as f32 / width);
+        let (min_v, max_v) = (rect.y as f32 / height, rect.max_y as f32 / height);
+
+        let coords: [[f32; 2]; 8] = [
+            [min_u, min_v],
+            [max_u, min_v],
+            [max_u, max_v],
+            [min_u, max_v],
+            [0.0, 0.0],
+            [1.0, 0.0],
+            [1.0, 1.0],
+            [0.0, 1.0],
+        ];
+
+        coords
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_ne_bytes())
+            .collect()
+    };
+
+    let first_data = quad(first);
+    let second_data = quad(second);
+
+    let knobbed_first = run(
+        pool,
+        &executable,
+        input,
+        output,
+        vec![(knob, &first_data[..])],
+    );
+    let knobbed_second = run(
+        pool,
+        &executable,
+        input,
+        output,
+        vec![(knob, &second_data[..])],
+    );
+    assert_ne!(knobbed_first, knobbed_second);
+
+    for (rect, knobbed) in [(first, knobbed_first), (second, knobbed_second)] {
+        let (commands, input, _, output) = crop(false, rect);
+        let executable = compile(&commands);
+        assert_eq!(run(pool, &executable, input, output, vec![]), knobbed);
+    }
+}
+
+/// Switch commands off and on in one executable, by only changing their knobs.
+fn run_bypass(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
+    let aberration = command::ChromaticAberration {
+        scales: [1.02, 1.0, 0.98],
+        center: [0.5, 0.5],
+    };
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(background.clone()).unwrap();
+    let sharpen = commands
+        .with_knob()
+        .bypassable(input, command::Bypassable::Sharpen(0.5))
+        .expect("Valid to sharpen");
+    let shifted = commands
+        .with_knob()
+        .bypassable(
+            sharpen.register(),
+            command::Bypassable::ChromaticAberration(aberration),
+        )
+        .expect("Valid to shift channels");
+    let (output, _) = commands
+        .output(shifted.register())
+        .expect("Valid for output");
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let executable = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer")
+        .lower_to(capabilities.clone())
+        .expect("No extras beyond device required");
+
+    let sharpen_knob = executable.query_knob(sharpen.in_link(0)).unwrap();
+    let shifted_knob = executable.query_knob(shifted.in_link(0)).unwrap();
+
+    let input_bytes = pool
+        .entry(bg_key)
+        .unwrap()
+        .as_bytes()
+        .expect("Input on the host")
+        .to_vec();
+
+    let mut run = |sharpened: bool, shift: bool| {
+        let sharpen_data = sharpen.encode(sharpened);
+        let shifted_data = shifted.encode(shift);
+
+        let result = run_executable_with_output(
+            &executable,
+            pool,
+            vec![(input, bg_key)],
+            vec![
+                (sharpen_knob, &sharpen_data[..]),
+                (shifted_knob, &shifted_data[..]),
+            ],
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Output on the host").to_vec()
+    };
+
+    let bypassed = run(false, false);
+    let only_sharpened = run(true, false);
+    let enabled = run(true, true);
+
+    assert_eq!(bypassed, input_bytes);
+    assert_ne!(only_sharpened, bypassed);
+    assert_ne!(only_sharpened, enabled);
+
+    // Enabling all commands is the same as recording them without a knob.
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(background).unwrap();
+    let sharpened = commands.sharpen(input, 0.5).unwrap();
+    let shifted = commands
+        .chromatic_aberration(sharpened, aberration)
+        .unwrap();
+    let (output, _) = commands.output(shifted).expect("Valid for output");
+
+    let executable = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer")
+        .lower_to(capabilities)
+        .expect("No extras beyond device required");
+
+    let result = run_executable_with_output(
+        &executable,
+        pool,
+        vec![(input, bg_key)],
+        [],
+        retire_with_one_image(output),
+    );
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes().expect("Output on the host"), &enabled[..]);
+}
+
+/// Shift the base of a palette by only changing its knob.
+fn run_palette(pool: &mut Pool, (bg_key, background): (PoolKey, Descriptor)) {
+    let palette = |knob: bool, (width_base, height_base): (i32, i32)| {
+        let mut commands = CommandBuffer::default();
+        let input = commands.input(background.clone()).unwrap();
+
+        let ramp = commands
+            .bilinear(
+                Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(128, 128)),
+                command::Bilinear {
+                    u_min: [0.0, 0.0, 0.0, 1.0],
+                    v_min: [0.0, 0.0, 0.0, 1.0],
+                    uv_min: [0.0, 0.0, 0.0, 1.0],
+                    u_max: [0.5, 0.0, 0.0, 1.0],
+                    v_max: [0.0, 0.5, 0.0, 1.0],
+                    uv_max: [0.0, 0.0, 0.0, 1.0],
+                },
+            )
+            .unwrap();
+
+        let config = command::Palette {
+            width: Some(zosimos::buffer::ColorChannel::R),
+            height: Some(zosimos::buffer::ColorChannel::G),
+            width_base,
+            height_base,
+            address: command::PaletteAddress::Clamp,
+            interpolate: false,
+        };
+
+        let sampled = if knob {
+            commands
+                .with_knob()
+                .palette(input, config, ramp)
+                .map(|knob| knob.register())
+        } else {
+            commands.palette(input, config, ramp)
+        }
+        .expect("Valid to sample a palette");
+
+        let (output, _) = commands.output(sampled).expect("Valid for output");
+        (commands, input, sampled, output)
+    };
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let mut run = |commands: &CommandBuffer, input, output, knob: Option<(_, &[u8])>| {
+        let plan = Linker::from_included()
+            .compile(commands)
+            .expect("Could build command buffer");
+        let executable = plan
+            .lower_to(capabilities.clone())
+            .expect("No extras beyond device required");
+
+        let knobs = knob.map(|(register, data)| {
+            let knob = executable
+                .query_knob(command::RegisterKnob {
+                    link_idx: 0,
+                    register,
+                })
+                .unwrap();
+            (knob, data)
+        });
+
+        let result = run_executable_with_output(
+            &executable,
+            pool,
+            vec![(input, bg_key)],
+            knobs,
+            retire_with_one_image(output),
+        );
+
+        let image = pool.entry(result).unwrap();
+        image.as_bytes().expect("Output on the host").to_vec()
+    };
+
+    // The channel weights, R along the width and G along the height, then the base and border.
+    let weights = [1.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0];
+    let data: Vec<u8> = weights
+        .iter()
+        .chain(&[64.0, 32.0, 0.0, 0.0])
+        .chain(&[0.0; 4])
+        .flat_map(|v| v.to_ne_bytes())
+        .collect();
+
+    let (commands, input, sampled, output) = palette(true, (0, 0));
+    let knobbed = run(&commands, input, output, Some((sampled, &data[..])));
+
+    let (commands, input, _, output) = palette(false, (64, 32));
+    assert_eq!(run(&commands, input, output, None), knobbed);
+}
+
+/// Set the color of a solid image through its typed handle.
+fn run_typed_solid(pool: &mut Pool) {
+    let like = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(16, 16));
+
+    let solid = |knob: bool, color: [f32; 4]| {
+        let mut commands = CommandBuffer::default();
+
+        let handle = if knob {
+            Some(
+                commands
+                    .with_knob()
+                    .solid_rgba(like.clone(), color)
+                    .unwrap(),
+            )
+        } else {
+            None
+        };
+
+        let image = match handle {
+            Some(handle) => handle.register(),
+            None => commands.solid_rgba(like.clone(), color).unwrap(),
+        };
+
+        let (output, _) = commands.output(image).expect("Valid for output");
+        (commands, handle, output)
+    };
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let compile = |commands: &CommandBuffer| {
+        Linker::from_included()
+            .compile(commands)
+            .expect("Could build command buffer")
+            .lower_to(capabilities.clone())
+            .expect("No extras beyond device required")
+    };
+
+    let (commands, handle, output) = solid(true, [0.0, 0.0, 0.0, 1.0]);
+    let handle = handle.unwrap();
+    assert_eq!(handle.byte_len(), Some(16));
+    assert_eq!(handle.align(), 16);
+
+    let executable = compile(&commands);
+    let color = [0.25, 0.5, 0.75, 1.0];
+    let data = handle.encode(&color);
+    assert_eq!(data.len(), 16);
+
+    let knobbed = {
+        let mut environment = executable.from_pool(pool).expect("no device found in pool");
+
+        // A value of the wrong size must not be written into the program's parameters.
+        assert!(environment
+            .set_knob(&handle.in_link(0), &[0.0f32; 3])
+            .is_err());
+        assert!(environment.set_knob(&handle.in_link(1), &data).is_err());
+        environment.set_knob(&handle.in_link(0), &data).unwrap();
+
+        let mut execution = executable.launch(environment).expect("Launching failed");
+        pool.clear_cache();
+
+        while execution.is_running() {
+            let _wait_point = execution.step().expect("Shouldn't fail but");
+        }
+
+        let mut retire = execution.retire_gracefully(pool);
+        let key = retire.output(output).expect("Valid for output").key();
+        let _ = retire.retire_buffers();
+        retire.finish();
+
+        let image = pool.entry(key).unwrap();
+        image.as_bytes().expect("Output on the host").to_vec()
+    };
+
+    let (commands, _, output) = solid(false, color);
+    let executable = compile(&commands);
+    let result =
+        run_executable_with_output(&executable, pool, vec![], [], retire_with_one_image(output));
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes().expect("Output on the host"), &knobbed[..]);
+}
+
+/// Change a knob of an execution which has been launched.
+fn run_knob_while_running(pool: &mut Pool) {
+    let like = Descriptor::with_srgb_image(&image::DynamicImage::new_rgba8(16, 16));
+
+    let mut commands = CommandBuffer::default();
+    let handle = commands
+        .with_knob()
+        .solid_rgba(like.clone(), [0.0, 0.0, 0.0, 1.0])
+        .unwrap();
+    let (output, _) = commands
+        .output(handle.register())
+        .expect("Valid for output");
+
+    let capabilities = Capabilities::from({
+        let mut devices = pool.iter_devices();
+        devices.next().expect("the pool to contain a device")
+    });
+
+    let executable = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer")
+        .lower_to(capabilities.clone())
+        .expect("No extras beyond device required");
+
+    let knob = executable.query_knob(handle.in_link(0)).unwrap();
+    let color = [0.25, 0.5, 0.75, 1.0];
+    let data = handle.encode(&color);
+
+    let knobbed = {
+        let environment = executable.from_pool(pool).expect("no device found in pool");
+        let mut execution = executable.launch(environment).expect("Launching failed");
+        pool.clear_cache();
+
+        // Nothing has been read yet.
+        assert!(execution.set_knob_bytes(knob, &data[..12]).is_err());
+        // The last value set before the knob is read is used.
+        let red = handle.encode(&[1.0, 0.0, 0.0, 1.0]);
+        execution.set_knob_bytes(knob, &red).unwrap();
+        execution.set_knob_bytes(knob, &data).unwrap();
+
+        while execution.is_running() {
+            let _wait_point = execution.step().expect("Shouldn't fail but");
+        }
+
+        // The knob was read by the execution, it can no longer be changed.
+        assert!(execution.set_knob_bytes(knob, &data).is_err());
+
+        let mut retire = execution.retire_gracefully(pool);
+        let key = retire.output(output).expect("Valid for output").key();
+        let _ = retire.retire_buffers();
+        retire.finish();
+
+        let image = pool.entry(key).unwrap();
+        image.as_bytes().expect("Output on the host").to_vec()
+    };
+
+    let mut commands = CommandBuffer::default();
+    let solid = commands.solid_rgba(like.clone(), color).unwrap();
+    let (output, _) = commands.output(solid).expect("Valid for output");
+
+    let executable = Linker::from_included()
+        .compile(&commands)
+        .expect("Could build command buffer")
+        .lower_to(capabilities)
+        .expect("No extras beyond device required");
+
+    let result =
+        run_executable_with_output(&executable, pool, vec![], [], retire_with_one_image(output));
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes().expect("Output on the host"), &knobbed[..]);
+}
+
+/// A generic function instantiated twice has a separate knob in each instantiation.
+fn run_knob_instances(pool: &mut Pool) {
+    let mut function = CommandBuffer::default();
+    let var = function.generic(GenericDeclaration::default()).unwrap();
+    let image = function.input_generic(var).unwrap();
+    let buffer = function
+        .with_knob()
+        .buffer_init(&[0; 256])
+        .unwrap()
+        .register();
+    function.output(image).unwrap();
+    function.buffer_result(buffer).unwrap();
+
+    let small = pool.insert_srgb(&image::DynamicImage::new_rgba8(4, 4));
+    let small = (small.key(), small.descriptor());
+    let large = pool.insert_srgb(&image::DynamicImage::new_rgba8(8, 8));
+    let large = (large.key(), large.descriptor());
+
+    let mut commands = CommandBuffer::default();
+    let callee = commands.function(function.computed_signature()).unwrap();
+
+    let mut instance = |(key, descriptor): &(PoolKey, Descriptor)| {
+        let input = commands.input(descriptor.clone()).unwrap();
+        let ty = commands.register_descriptor(input).unwrap();
+        let [_, buffer] = commands
+            .invoke(
+                callee,
+                command::InvocationArguments {
+                    generics: &[ty],
+                    arguments: &[input],
+                },
+            )
+            .unwrap()[..]
+        else {
+            panic!("Mismatched function results count");
+        };
+
+        let (output, _) = commands.output_buffer(buffer).unwrap();
+        ((input, *key), output)
+    };
+
+    let (bind_small, output_small) = instance(&small);
+    let (bind_large, output_large) = instance(&large);
+
+    let executable = {
+        let plan = Linker::from_included()
+            .link(&commands, &[], &[function], &[&[1]])
+            .expect("Could build command buffer");
+
+        let capabilities = Capabilities::from({
+            let mut devices = pool.iter_devices();
+            devices.next().expect("the pool to contain a device")
+        });
+
+        plan.lower_to(capabilities)
+            .expect("No extras beyond device required")
+    };
+
+    let register_knob = command::RegisterKnob {
+        link_idx: 1,
+        register: buffer,
+    };
+
+    // The register alone is ambiguous between the instantiations.
+    assert!(executable.query_knob(register_knob).is_none());
+
+    let knob_small = executable
+        .query_knob_instance(register_knob, &[small.1.clone()])
+        .unwrap();
+    let knob_large = executable
+        .query_knob_instance(register_knob, &[large.1.clone()])
+        .unwrap();
+    assert_ne!(knob_small, knob_large);
+
+    let (bytes_small, bytes_large) = run_executable_with_output(
+        &executable,
+        pool,
+        vec![bind_small, bind_large],
+        [
+            (knob_small, &[0x11; 256][..]),
+            (knob_large, &[0x22; 256][..]),
+        ],
+        |retire| {
+            let small = retire.output_buffer(output_small).unwrap();
+            let large = retire.output_buffer(output_large).unwrap();
+            (small, large)
+        },
+    );
+
+    assert_eq!(bytes_small, [0x11; 256]);
+    assert_eq!(bytes_large, [0x22; 256]);
+}
@@ -0,0 +1,97 @@
+//! This test ensures a pipeline cache can be persisted and loaded again.
+use zosimos::command::{CommandBuffer, Rectangle};
+use zosimos::pool::{GpuKey, PipelineCacheStatus, Pool};
+use zosimos::program::Program;
+
+#[path = "util.rs"]
+mod util;
+
+use self::util::{retire_with_one_image, run_once_with_output};
+
+#[test]
+fn persist() {
+    env_logger::init();
+
+    const ANY: wgpu::Backends = wgpu::Backends::VULKAN;
+    let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
+        backends: ANY,
+        ..Default::default()
+    });
+
+    let adapter = Program::minimum_adapter(instance.enumerate_adapters(ANY).into_iter())
+        .expect("to get an adapter");
+
+    let (mut pool, gpu, status) = pool_with_cache(&adapter, None);
+    run_crop(&mut pool);
+
+    let Some(data) = pool.pipeline_cache_data(gpu) else {
+        // Nothing to persist without support of the device or backend.
+        assert_ne!(status, PipelineCacheStatus::Attached);
+        return;
+    };
+
+    assert_eq!(status, PipelineCacheStatus::Attached);
+    drop(pool);
+
+    let (mut pool, gpu, status) = pool_with_cache(&adapter, Some(&data));
+    assert_eq!(status, PipelineCacheStatus::Attached);
+    run_crop(&mut pool);
+    assert!(pool.pipeline_cache_data(gpu).is_some());
+}
+
+fn pool_with_cache(
+    adapter: &wgpu::Adapter,
+    data: Option<&[u8]>,
+) -> (Pool, GpuKey, PipelineCacheStatus) {
+    let mut descriptor = Program::minimal_device_descriptor();
+    descriptor.required_features |= adapter.features() & wgpu::Features::PIPELINE_CACHE;
+
+    let mut pool = Pool::new();
+    let gpu = pool
+        .request_device(adapter, descriptor)
+        .expect("to get a device");
+
+    let device = pool.iter_devices().next().unwrap();
+    let cache = if device.features().contains(wgpu::Features::PIPELINE_CACHE) {
+        // Safety: the data was returned by `get_data` of a cache on the same adapter.
+        Some(unsafe {
+            device.create_pipeline_cache(&wgpu::PipelineCacheDescriptor {
+                label: None,
+                data,
+                fallback: true,
+            })
+        })
+    } else {
+        None
+    };
+
+    let status = match cache {
+        Some(cache) => pool.set_pipeline_cache(gpu, cache).unwrap(),
+        None => PipelineCacheStatus::Unsupported,
+    };
+
+    (pool, gpu, status)
+}
+
+fn run_crop(pool: &mut Pool) {
+    let gray = image::RgbaImage::from_pixel(64, 64, image::Rgba([128, 64, 32, 255]));
+    let entry = pool.insert_srgb(&image::DynamicImage::ImageRgba8(gray.clone()));
+    let (key, descriptor) = (entry.key(), entry.descriptor());
+
+    let mut commands = CommandBuffer::default();
+    let input = commands.input(descriptor).unwrap();
+    let cropped = commands
+        .crop(input, Rectangle::with_width_height(64, 64))
+        .expect("Valid to crop");
+    let (output, _) = commands.output(cropped).expect("Valid for output");
+
+    let result = run_once_with_output(
+        commands,
+        pool,
+        vec![(input, key)],
+        retire_with_one_image(output),
+    );
+
+    let image = pool.entry(result).unwrap();
+    assert_eq!(image.as_bytes(), Some(gray.as_raw().as_slice()));
+}
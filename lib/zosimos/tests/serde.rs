@@ -0,0 +1,201 @@
+//! Check that command buffers survive serialization, and that tampered ones are rejected.
+use image_canvas::color::{Differencing, Luminance};
+use zosimos::buffer::{Color, ColorChannel, Descriptor, Primaries, SampleParts, Texel};
+use zosimos::buffer::{Transfer, Whitepoint};
+use zosimos::command::{
+    self, Bilinear, ChromaticAdaptationMethod, CommandBuffer, CommandError, GenericDeclaration,
+    Palette, PaletteAddress, Rectangle, ShaderCommand,
+};
+
+struct Mandelbrot {
+    descriptor: Descriptor,
+}
+
+impl ShaderCommand for Mandelbrot {
+    fn source(&self) -> command::ShaderSource {
+        const SHADER_ENCODE: &[u8] =
+            include_bytes!(concat!(env!("OUT_DIR"), "/spirv/mandelbrot.frag.v"));
+        command::ShaderSource::SpirV(SHADER_ENCODE.into())
+    }
+
+    fn data(&self, mut data: command::ShaderData<'_>) -> Descriptor {
+        data.set_data(&[3.0f32, 3.0, 0.6, 0.5]);
+        self.descriptor.clone()
+    }
+}
+
+fn palette_function() -> Result<CommandBuffer, CommandError> {
+    let mut commands = CommandBuffer::default();
+    let var = commands.generic(GenericDeclaration::default())?;
+    let input = commands.input_generic(var)?;
+
+    let coords = commands.bilinear(
+        Descriptor::with_texel(Texel::new_u8(SampleParts::RgbA), 64, 64).unwrap(),
+        Bilinear {
+            u_min: [0.0, 0.0, 0.0, 0.0],
+            u_max: [0.0, 0.0, 0.0, 0.0],
+            v_min: [0.0, 0.0, 0.0, 0.0],
+            v_max: [0.0, 0.0, 0.0, 0.0],
+            uv_min: [0.0, 0.0, 0.0, 0.0],
+            uv_max: [1.0, 1.0, 0.0, 0.0],
+        },
+    )?;
+
+    let palette = commands.palette(
+        input,
+        Palette {
+            height: Some(ColorChannel::R),
+            width: Some(ColorChannel::G),
+            height_base: 0,
+            width_base: 0,
+            address: PaletteAddress::Clamp,
+            interpolate: false,
+        },
+        coords,
+    )?;
+
+    commands.output(palette)?;
+    Ok(commands)
+}
+
+/// A buffer with each kind of operation.
+fn every_op() -> Result<CommandBuffer, CommandError> {
+    let function = palette_function()?;
+
+    let mut commands = CommandBuffer::default();
+    let converter = commands.function(function.computed_signature())?;
+
+    let srgb = Descriptor::with_srgb_image(&image::DynamicImage::ImageRgba8(
+        image::RgbaImage::new(64, 64),
+    ));
+
+    let input = commands.input(srgb.clone())?;
+    let input_ty = commands.register_descriptor(input)?;
+
+    let [palette] = commands.invoke(
+        converter,
+        command::InvocationArguments {
+            generics: &[input_ty],
+            arguments: &[input],
+        },
+    )?[..] else {
+        panic!("Mismatched function results count");
+    };
+
+    let solid = commands.solid_rgba(srgb.clone(), [0.5, 0.25, 0.0, 1.0])?;
+    let brot = commands.construct_dynamic(&Mandelbrot {
+        descriptor: srgb.clone(),
+    });
+
+    let adapted = commands.chromatic_adaptation(
+        palette,
+        ChromaticAdaptationMethod::BradfordVonKries,
+        Whitepoint::D50,
+    )?;
+    let cropped = commands.with_knob().crop(
+        adapted,
+        Rectangle {
+            x: 0,
+            y: 0,
+            max_x: 64,
+            max_y: 64,
+        },
+    )?;
+    let cropped = cropped.register();
+    let injected = commands.inject(cropped, ColorChannel::R, solid)?;
+
+    let under = commands.buffer_from_image(brot)?;
+    let over = commands.buffer_init(&[0xff; 16]);
+    let overlaid = commands.buffer_overlay(under, 0, over)?;
+    let from_buffer = commands.from_buffer(overlaid, srgb)?;
+
+    commands.output(injected)?;
+    commands.render(from_buffer)?;
+
+    Ok(commands)
+}
+
+#[test]
+fn round_trip() {
+    let commands = every_op().expect("a valid command buffer");
+    let json = serde_json::to_value(&commands).expect("to serialize");
+
+    let restored: CommandBuffer = serde_json::from_value(json.clone()).expect("to deserialize");
+    let restored_json = serde_json::to_value(&restored).expect("to serialize again");
+    assert_eq!(json, restored_json);
+
+    let function = palette_function().expect("a valid function");
+    let json = serde_json::to_value(&function).expect("to serialize");
+    let restored: CommandBuffer = serde_json::from_value(json.clone()).expect("to deserialize");
+    assert_eq!(json, serde_json::to_value(&restored).unwrap());
+}
+
+#[test]
+fn luma_and_yuv_round_trip() {
+    let luma = Color::Luma {
+        transfer: Transfer::Srgb,
+        whitepoint: Whitepoint::D65,
+        luminance: Luminance::Sdr,
+    };
+
+    let yuv = Color::Yuv {
+        primary: Primaries::Bt709,
+        whitepoint: Whitepoint::D65,
+        transfer: Transfer::Bt709,
+        luminance: Luminance::Sdr,
+        differencing: Differencing::Bt709FullSwing,
+    };
+
+    for (color, parts) in [(luma, SampleParts::Luma), (yuv, SampleParts::Yuv)] {
+        let mut commands = CommandBuffer::default();
+        let descriptor = Descriptor {
+            color: color.clone(),
+            ..Descriptor::with_texel(Texel::new_u8(parts), 8, 8).unwrap()
+        };
+
+        let input = commands.input(descriptor).expect("a valid input");
+        commands.output(input).expect("a valid output");
+
+        let json = serde_json::to_value(&commands)
+            .unwrap_or_else(|err| panic!("to serialize {color:?}: {err}"));
+        let restored: CommandBuffer = serde_json::from_value(json.clone()).expect("to deserialize");
+        assert_eq!(json, serde_json::to_value(&restored).unwrap());
+    }
+}
+
+#[test]
+fn rejects_tampered() {
+    let commands = every_op().expect("a valid command buffer");
+    let json = serde_json::to_value(&commands).expect("to serialize");
+    let len = json["ops"].as_array().unwrap().len();
+
+    // An output of a register that follows it.
+    let mut forward = json.clone();
+    let ops = forward["ops"].as_array_mut().unwrap();
+    let output = ops
+        .iter_mut()
+        .find(|op| op.get("Output").is_some())
+        .expect("an output op");
+    output["Output"]["src"] = serde_json::json!(len - 1);
+    assert!(serde_json::from_value::<CommandBuffer>(forward).is_err());
+
+    // A register that does not exist at all.
+    let mut missing = json.clone();
+    missing["dithered"] = serde_json::json!([len + 10]);
+    assert!(serde_json::from_value::<CommandBuffer>(missing).is_err());
+
+    // A function signature referring to a generic that it does not declare.
+    let mut undeclared = json.clone();
+    undeclared["symbols"][0]["vars"] = serde_json::json!([]);
+    assert!(serde_json::from_value::<CommandBuffer>(undeclared).is_err());
+
+    // Dropping the invocation leaves its result dangling.
+    let mut dangling = json.clone();
+    let ops = dangling["ops"].as_array_mut().unwrap();
+    let invoke = ops
+        .iter()
+        .position(|op| op.get("Invoke").is_some())
+        .expect("an invoke op");
+    ops[invoke] = serde_json::json!({ "Render": { "src": 0 } });
+    assert!(serde_json::from_value::<CommandBuffer>(dangling).is_err());
+}